@@ -0,0 +1,661 @@
+//! A conformance test suite, which feeds the messages of a simulated exchange to the connector's
+//! handlers and validates what they publish.
+//!
+//! The venue's message format is unknown to the suite, so the connector provides a
+//! [`VenueEncoder`] that encodes the simulated exchange's events in the venue's format. The order
+//! requests of [`OrderGateway`] are not exercised since they reach the venue.
+//!
+//! ```ignore
+//! #[test]
+//! fn conformance() {
+//!     let suite = ConformanceSuite::new(MyEncoder, "BTCUSDT");
+//!     suite.run_market_data(|| MyMarketDataHandler::new()).unwrap();
+//!     suite.check_order_lifecycle(&MyGateway::new()).unwrap();
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use hftbacktest::types::{
+    LiveEvent,
+    OrdType,
+    Order,
+    Side,
+    Status,
+    TimeInForce,
+    BUY_EVENT,
+    DEPTH_CLEAR_EVENT,
+    DEPTH_EVENT,
+    DEPTH_SNAPSHOT_EVENT,
+    SELL_EVENT,
+    TRADE_EVENT,
+};
+use thiserror::Error;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::{
+    connector::{GetOrders, PublishEvent},
+    gateway::OrderGateway,
+    marketdata::{FeedError, MarketDataHandler},
+    ordermanager::{ClientOrderManager, OrderManagerError, OrderUpdate},
+    utils::PxQty,
+};
+
+/// The tick size of the simulated exchange's prices.
+const TICK_SIZE: f64 = 0.5;
+
+#[derive(Error, Debug)]
+pub enum ConformanceError {
+    #[error("{check}: {reason}")]
+    Failed { check: &'static str, reason: String },
+}
+
+fn fail<T>(check: &'static str, reason: impl Into<String>) -> Result<T, ConformanceError> {
+    Err(ConformanceError::Failed {
+        check,
+        reason: reason.into(),
+    })
+}
+
+/// Encodes the simulated exchange's events in the venue's message format.
+///
+/// `seq` is the sequence number of the market depth stream, where an update follows the
+/// snapshot or the previous update by one. The venues that identify the updates differently,
+/// such as by the previous update's ID, should derive it from `seq`.
+pub trait VenueEncoder {
+    type MarketData;
+    type OrderMessage;
+
+    fn snapshot(&self, symbol: &str, seq: i64, bids: &[PxQty], asks: &[PxQty]) -> Self::MarketData;
+
+    /// Encodes the changed levels, where the quantity of a deleted level is `0`. The venues that
+    /// send a message per level return the messages in order.
+    fn depth_update(
+        &self,
+        symbol: &str,
+        seq: i64,
+        bids: &[PxQty],
+        asks: &[PxQty],
+    ) -> Vec<Self::MarketData>;
+
+    /// Encodes the trade, where `side` is the taker's side.
+    fn trade(&self, symbol: &str, side: Side, px: f64, qty: f64) -> Self::MarketData;
+
+    fn order_update(
+        &self,
+        symbol: &str,
+        client_order_id: &str,
+        update: &OrderUpdate,
+    ) -> Self::OrderMessage;
+}
+
+/// The market depth maintained by the simulated exchange, and the one rebuilt from the published
+/// events.
+#[derive(Default, Debug, PartialEq)]
+struct Book {
+    bids: BTreeMap<i64, f64>,
+    asks: BTreeMap<i64, f64>,
+}
+
+impl Book {
+    fn apply(&mut self, bids: &[PxQty], asks: &[PxQty]) {
+        for (side, levels) in [(&mut self.bids, bids), (&mut self.asks, asks)] {
+            for &(px, qty) in levels {
+                let tick = (px / TICK_SIZE).round() as i64;
+                if qty > 0.0 {
+                    side.insert(tick, qty);
+                } else {
+                    side.remove(&tick);
+                }
+            }
+        }
+    }
+
+    fn levels(&self) -> (Vec<PxQty>, Vec<PxQty>) {
+        let to_levels = |side: &BTreeMap<i64, f64>| {
+            side.iter()
+                .map(|(&tick, &qty)| (tick as f64 * TICK_SIZE, qty))
+                .collect::<Vec<_>>()
+        };
+        (to_levels(&self.bids), to_levels(&self.asks))
+    }
+}
+
+/// What the handler published, rebuilt from the events.
+#[derive(Default)]
+struct Published {
+    book: Book,
+    clears: usize,
+    depth_events: usize,
+    trades: Vec<(Side, f64, f64)>,
+}
+
+impl Published {
+    fn collect(&mut self, symbol: &str, rx: &mut UnboundedReceiver<PublishEvent>) {
+        while let Ok(ev) = rx.try_recv() {
+            let PublishEvent::LiveEvent(LiveEvent::Feed { symbol: s, event }) = ev else {
+                continue;
+            };
+            if s != symbol {
+                continue;
+            }
+            let kind = event.ev & 0xff;
+            let is_buy = event.ev & BUY_EVENT == BUY_EVENT;
+            let is_sell = event.ev & SELL_EVENT == SELL_EVENT;
+            if kind == DEPTH_CLEAR_EVENT {
+                // Clears the side, or both sides if it's not specified.
+                if is_buy || !is_sell {
+                    self.book.bids.clear();
+                }
+                if is_sell || !is_buy {
+                    self.book.asks.clear();
+                }
+                self.clears += 1;
+            } else if kind == DEPTH_EVENT || kind == DEPTH_SNAPSHOT_EVENT {
+                let level = [(event.px, event.qty)];
+                if is_buy {
+                    self.book.apply(&level, &[]);
+                } else {
+                    self.book.apply(&[], &level);
+                }
+                self.depth_events += 1;
+            } else if kind == TRADE_EVENT {
+                let side = if is_buy {
+                    Side::Buy
+                } else if is_sell {
+                    Side::Sell
+                } else {
+                    Side::None
+                };
+                self.trades.push((side, event.px, event.qty));
+            }
+        }
+    }
+}
+
+/// Drives the handler with the simulated exchange's messages.
+struct Simulation<'a, E: VenueEncoder, H> {
+    encoder: &'a E,
+    symbol: &'a str,
+    handler: H,
+    exchange: Book,
+    seq: i64,
+    published: Published,
+    rx: UnboundedReceiver<PublishEvent>,
+    ev_tx: UnboundedSender<PublishEvent>,
+}
+
+impl<'a, E, H> Simulation<'a, E, H>
+where
+    E: VenueEncoder,
+    H: MarketDataHandler<Message = E::MarketData>,
+{
+    fn new(encoder: &'a E, symbol: &'a str, handler: H) -> Self {
+        let (ev_tx, rx) = unbounded_channel();
+        Self {
+            encoder,
+            symbol,
+            handler,
+            exchange: Default::default(),
+            seq: 0,
+            published: Default::default(),
+            rx,
+            ev_tx,
+        }
+    }
+
+    fn handle(&mut self, msg: E::MarketData) -> Result<(), FeedError> {
+        let result = self.handler.handle(&msg, &self.ev_tx);
+        self.published.collect(self.symbol, &mut self.rx);
+        result
+    }
+
+    /// Sends a snapshot of the exchange's market depth, which replaces the levels.
+    fn snapshot(&mut self, seq: i64, bids: &[PxQty], asks: &[PxQty]) -> Result<(), FeedError> {
+        self.exchange = Default::default();
+        self.exchange.apply(bids, asks);
+        self.seq = seq;
+        let (bids, asks) = self.exchange.levels();
+        let msg = self.encoder.snapshot(self.symbol, seq, &bids, &asks);
+        self.handle(msg)
+    }
+
+    /// Sends the update with the given sequence number, applying it to the exchange's market
+    /// depth.
+    fn update_with_seq(
+        &mut self,
+        seq: i64,
+        bids: &[PxQty],
+        asks: &[PxQty],
+    ) -> Result<(), FeedError> {
+        self.exchange.apply(bids, asks);
+        self.seq = seq;
+        for msg in self.encoder.depth_update(self.symbol, seq, bids, asks) {
+            self.handle(msg)?;
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, bids: &[PxQty], asks: &[PxQty]) -> Result<(), FeedError> {
+        self.update_with_seq(self.seq + 1, bids, asks)
+    }
+
+    fn check_book(&self, check: &'static str) -> Result<(), ConformanceError> {
+        if self.published.book != self.exchange {
+            return fail(
+                check,
+                format!(
+                    "the published market depth {:?} differs from the exchange's {:?}",
+                    self.published.book, self.exchange
+                ),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn ok_or_fail(check: &'static str, result: Result<(), FeedError>) -> Result<(), ConformanceError> {
+    result.or_else(|error| fail(check, format!("unexpected error: {error}")))
+}
+
+/// Validates the connector's handlers against a simulated exchange.
+pub struct ConformanceSuite<E> {
+    encoder: E,
+    symbol: String,
+}
+
+impl<E: VenueEncoder> ConformanceSuite<E> {
+    pub fn new(encoder: E, symbol: &str) -> Self {
+        Self {
+            encoder,
+            symbol: symbol.to_string(),
+        }
+    }
+
+    /// Runs all market data checks, each with a fresh handler.
+    pub fn run_market_data<H, F>(&self, new_handler: F) -> Result<(), ConformanceError>
+    where
+        H: MarketDataHandler<Message = E::MarketData>,
+        F: Fn() -> H,
+    {
+        self.check_snapshot(new_handler())?;
+        self.check_incremental(new_handler())?;
+        self.check_seq_gap(new_handler())?;
+        self.check_reconnection(new_handler())?;
+        self.check_trade(new_handler())?;
+        Ok(())
+    }
+
+    /// The snapshot replaces the market depth, including the levels that are no longer present.
+    pub fn check_snapshot<H>(&self, handler: H) -> Result<(), ConformanceError>
+    where
+        H: MarketDataHandler<Message = E::MarketData>,
+    {
+        const CHECK: &str = "snapshot";
+        let mut sim = Simulation::new(&self.encoder, &self.symbol, handler);
+        ok_or_fail(
+            CHECK,
+            sim.snapshot(100, &[(100.0, 1.0), (99.5, 2.0)], &[(100.5, 1.5)]),
+        )?;
+        if sim.published.clears == 0 {
+            return fail(CHECK, "the snapshot is not preceded by a depth clear event");
+        }
+        sim.check_book(CHECK)?;
+        ok_or_fail(CHECK, sim.snapshot(200, &[(99.0, 3.0)], &[(101.0, 1.0)]))?;
+        sim.check_book(CHECK)
+    }
+
+    /// The updates are applied on top of the snapshot, and a level with zero quantity is deleted.
+    pub fn check_incremental<H>(&self, handler: H) -> Result<(), ConformanceError>
+    where
+        H: MarketDataHandler<Message = E::MarketData>,
+    {
+        const CHECK: &str = "incremental";
+        let mut sim = Simulation::new(&self.encoder, &self.symbol, handler);
+        ok_or_fail(
+            CHECK,
+            sim.snapshot(100, &[(100.0, 1.0), (99.5, 2.0)], &[(100.5, 1.5)]),
+        )?;
+        ok_or_fail(CHECK, sim.update(&[(100.0, 0.0)], &[(101.0, 2.0)]))?;
+        ok_or_fail(CHECK, sim.update(&[(99.5, 4.0)], &[(100.5, 0.0)]))?;
+        sim.check_book(CHECK)
+    }
+
+    /// An update that doesn't follow the previous one is reported as a gap rather than applied,
+    /// and the next snapshot recovers the market depth.
+    pub fn check_seq_gap<H>(&self, handler: H) -> Result<(), ConformanceError>
+    where
+        H: MarketDataHandler<Message = E::MarketData>,
+    {
+        const CHECK: &str = "seq_gap";
+        let mut sim = Simulation::new(&self.encoder, &self.symbol, handler);
+        ok_or_fail(CHECK, sim.snapshot(100, &[(100.0, 1.0)], &[(100.5, 1.0)]))?;
+        ok_or_fail(CHECK, sim.update(&[(99.5, 1.0)], &[]))?;
+        // Skips an update.
+        let seq = sim.seq + 2;
+        match sim.update_with_seq(seq, &[(99.0, 1.0)], &[]) {
+            Err(FeedError::SeqGap { .. }) => {}
+            Err(error) => return fail(CHECK, format!("unexpected error: {error}")),
+            Ok(()) => return fail(CHECK, "the gap is not detected"),
+        }
+        let (bids, asks) = sim.exchange.levels();
+        ok_or_fail(CHECK, sim.snapshot(seq + 10, &bids, &asks))?;
+        sim.check_book(CHECK)
+    }
+
+    /// After the reconnection, the updates are not applied until the next snapshot, which
+    /// replaces the market depth.
+    pub fn check_reconnection<H>(&self, handler: H) -> Result<(), ConformanceError>
+    where
+        H: MarketDataHandler<Message = E::MarketData>,
+    {
+        const CHECK: &str = "reconnection";
+        let mut sim = Simulation::new(&self.encoder, &self.symbol, handler);
+        ok_or_fail(CHECK, sim.snapshot(100, &[(100.0, 1.0)], &[(100.5, 1.0)]))?;
+        ok_or_fail(CHECK, sim.update(&[(99.5, 1.0)], &[]))?;
+
+        sim.handler.reset();
+        let depth_events = sim.published.depth_events;
+        // Either ignoring the update or reporting a gap is conforming.
+        let seq = sim.seq + 50;
+        let _ = sim.update_with_seq(seq, &[(99.0, 5.0)], &[]);
+        if sim.published.depth_events != depth_events {
+            return fail(CHECK, "the update is applied before the snapshot");
+        }
+
+        let clears = sim.published.clears;
+        ok_or_fail(
+            CHECK,
+            sim.snapshot(seq + 1, &[(98.0, 1.0)], &[(102.0, 1.0)]),
+        )?;
+        if sim.published.clears == clears {
+            return fail(CHECK, "the snapshot is not preceded by a depth clear event");
+        }
+        ok_or_fail(CHECK, sim.update(&[(98.5, 1.0)], &[]))?;
+        sim.check_book(CHECK)
+    }
+
+    /// The trade is published with the taker's side, its price and quantity.
+    pub fn check_trade<H>(&self, handler: H) -> Result<(), ConformanceError>
+    where
+        H: MarketDataHandler<Message = E::MarketData>,
+    {
+        const CHECK: &str = "trade";
+        let mut sim = Simulation::new(&self.encoder, &self.symbol, handler);
+        let trades = [(Side::Buy, 100.5, 1.0), (Side::Sell, 100.0, 2.0)];
+        for (side, px, qty) in trades {
+            let msg = self.encoder.trade(&self.symbol, side, px, qty);
+            ok_or_fail(CHECK, sim.handle(msg))?;
+        }
+        if sim.published.trades != trades {
+            return fail(
+                CHECK,
+                format!(
+                    "the published trades {:?} differ from {:?}",
+                    sim.published.trades, trades
+                ),
+            );
+        }
+        Ok(())
+    }
+
+    /// The order updates decoded by the gateway drive the order through its lifecycle, and the
+    /// updates of the orders created by others are ignored.
+    pub fn check_order_lifecycle<G>(&self, gateway: &G) -> Result<(), ConformanceError>
+    where
+        G: OrderGateway<Message = E::OrderMessage>,
+    {
+        const CHECK: &str = "order_lifecycle";
+        let mut order_manager = ClientOrderManager::new("conf", 20);
+        let new_order = |order_id| {
+            Order::new(
+                order_id,
+                200,
+                TICK_SIZE,
+                2.0,
+                Side::Buy,
+                OrdType::Limit,
+                TimeInForce::GTC,
+            )
+        };
+        let update = |status, leaves_qty, exec_qty| OrderUpdate {
+            status,
+            leaves_qty: Some(leaves_qty),
+            exec_qty,
+            exec_price: if exec_qty > 0.0 { 100.0 } else { 0.0 },
+            exch_ts: 1,
+            maker: None,
+            fee: 0.0,
+            trade_id: None,
+        };
+        let apply = |order_manager: &mut ClientOrderManager,
+                     client_order_id: &str,
+                     update: OrderUpdate|
+         -> Result<Option<Order>, ConformanceError> {
+            let msg = self
+                .encoder
+                .order_update(&self.symbol, client_order_id, &update);
+            let updates = gateway.order_updates(&msg);
+            let mut last = None;
+            for (id, update) in updates {
+                match order_manager.update(&id, update) {
+                    Ok(order) => last = Some(order.order),
+                    Err(OrderManagerError::PrefixUnmatched) => {}
+                    Err(error) => return fail(CHECK, format!("unexpected error: {error}")),
+                }
+            }
+            Ok(last)
+        };
+
+        // New -> PartiallyFilled -> Filled.
+        let id = order_manager
+            .new_order(&self.symbol, new_order(1))
+            .or_else(|error| fail(CHECK, error.to_string()))?;
+        let steps = [
+            (Status::New, 2.0, 0.0),
+            (Status::PartiallyFilled, 1.0, 1.0),
+            (Status::Filled, 0.0, 1.0),
+        ];
+        for (status, leaves_qty, exec_qty) in steps {
+            let order = apply(
+                &mut order_manager,
+                &id,
+                update(status, leaves_qty, exec_qty),
+            )?;
+            match order {
+                Some(order)
+                    if order.status == status
+                        && order.leaves_qty == leaves_qty
+                        && order.exec_qty == exec_qty => {}
+                order => {
+                    return fail(
+                        CHECK,
+                        format!("expected {status:?} with leaves {leaves_qty} and exec {exec_qty}, got {order:?}"),
+                    );
+                }
+            }
+        }
+
+        // New -> Canceled, and Rejected.
+        for (order_id, final_status) in [(2, Status::Canceled), (3, Status::Rejected)] {
+            let id = order_manager
+                .new_order(&self.symbol, new_order(order_id))
+                .or_else(|error| fail(CHECK, error.to_string()))?;
+            if final_status == Status::Canceled {
+                apply(&mut order_manager, &id, update(Status::New, 2.0, 0.0))?;
+            }
+            let order = apply(&mut order_manager, &id, update(final_status, 0.0, 0.0))?;
+            if order.map(|order| order.status) != Some(final_status) {
+                return fail(CHECK, format!("the order is not {final_status:?}"));
+            }
+        }
+
+        // Another client's order.
+        apply(
+            &mut order_manager,
+            "other-client-order",
+            update(Status::New, 2.0, 0.0),
+        )?;
+        if !order_manager.orders(None).is_empty() {
+            return fail(CHECK, "the finished orders are still open");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::{ready, Future};
+
+    use super::*;
+    use crate::{
+        feed::{publish_depth, publish_trade},
+        ordermanager::ClientOrderId,
+    };
+
+    enum Msg {
+        Snapshot(i64, Vec<PxQty>, Vec<PxQty>),
+        Update(i64, Vec<PxQty>, Vec<PxQty>),
+        Trade(Side, f64, f64),
+    }
+
+    struct Encoder;
+
+    impl VenueEncoder for Encoder {
+        type MarketData = Msg;
+        type OrderMessage = (String, OrderUpdate);
+
+        fn snapshot(&self, _symbol: &str, seq: i64, bids: &[PxQty], asks: &[PxQty]) -> Msg {
+            Msg::Snapshot(seq, bids.to_vec(), asks.to_vec())
+        }
+
+        fn depth_update(
+            &self,
+            _symbol: &str,
+            seq: i64,
+            bids: &[PxQty],
+            asks: &[PxQty],
+        ) -> Vec<Msg> {
+            vec![Msg::Update(seq, bids.to_vec(), asks.to_vec())]
+        }
+
+        fn trade(&self, _symbol: &str, side: Side, px: f64, qty: f64) -> Msg {
+            Msg::Trade(side, px, qty)
+        }
+
+        fn order_update(
+            &self,
+            _symbol: &str,
+            client_order_id: &str,
+            update: &OrderUpdate,
+        ) -> (String, OrderUpdate) {
+            (client_order_id.to_string(), update.clone())
+        }
+    }
+
+    struct Handler {
+        /// The expected sequence number of the next update, which is `None` until a snapshot.
+        next_seq: Option<i64>,
+        check_seq: bool,
+    }
+
+    impl MarketDataHandler for Handler {
+        type Message = Msg;
+
+        fn handle(
+            &mut self,
+            msg: &Msg,
+            ev_tx: &UnboundedSender<PublishEvent>,
+        ) -> Result<(), FeedError> {
+            match msg {
+                Msg::Snapshot(seq, bids, asks) => {
+                    publish_depth(ev_tx, "TEST", 0, bids, asks, true);
+                    self.next_seq = Some(seq + 1);
+                }
+                Msg::Update(seq, bids, asks) => {
+                    let Some(next_seq) = self.next_seq else {
+                        return Ok(());
+                    };
+                    if self.check_seq && *seq != next_seq {
+                        self.next_seq = None;
+                        return Err(FeedError::SeqGap {
+                            symbol: "TEST".to_string(),
+                            expected: next_seq,
+                            received: *seq,
+                        });
+                    }
+                    publish_depth(ev_tx, "TEST", 0, bids, asks, false);
+                    self.next_seq = Some(seq + 1);
+                }
+                Msg::Trade(side, px, qty) => {
+                    publish_trade(ev_tx, "TEST", *side, 0, *px, *qty);
+                }
+            }
+            Ok(())
+        }
+
+        fn reset(&mut self) {
+            self.next_seq = None;
+        }
+    }
+
+    struct Gateway;
+
+    impl OrderGateway for Gateway {
+        type Error = ();
+        type Message = (String, OrderUpdate);
+
+        fn submit(
+            &self,
+            _symbol: &str,
+            _client_order_id: &str,
+            _order: &Order,
+        ) -> impl Future<Output = Result<(), ()>> + Send {
+            ready(Ok(()))
+        }
+
+        fn cancel(
+            &self,
+            _symbol: &str,
+            _client_order_id: &str,
+        ) -> impl Future<Output = Result<(), ()>> + Send {
+            ready(Ok(()))
+        }
+
+        fn cancel_all(&self, _symbol: &str) -> impl Future<Output = Result<(), ()>> + Send {
+            ready(Ok(()))
+        }
+
+        fn order_updates(&self, msg: &Self::Message) -> Vec<(ClientOrderId, OrderUpdate)> {
+            vec![msg.clone()]
+        }
+    }
+
+    #[test]
+    fn conforming_handlers_pass() {
+        let suite = ConformanceSuite::new(Encoder, "TEST");
+        suite
+            .run_market_data(|| Handler {
+                next_seq: None,
+                check_seq: true,
+            })
+            .unwrap();
+        suite.check_order_lifecycle(&Gateway).unwrap();
+    }
+
+    #[test]
+    fn detect_missing_gap_check() {
+        let suite = ConformanceSuite::new(Encoder, "TEST");
+        let result = suite.check_seq_gap(Handler {
+            next_seq: None,
+            check_seq: false,
+        });
+        assert!(matches!(
+            result,
+            Err(ConformanceError::Failed {
+                check: "seq_gap",
+                ..
+            })
+        ));
+    }
+}
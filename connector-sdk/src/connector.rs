@@ -0,0 +1,139 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use hftbacktest::types::{DepthLevel, ErrorKind, LiveEvent, Order, Value};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::feed::publish_error;
+
+/// A message will be received by the publisher thread and then published to the bots.
+pub enum PublishEvent {
+    BatchStart(u64),
+    BatchEnd(u64),
+    LiveEvent(LiveEvent),
+    RegisterInstrument {
+        id: u64,
+        symbol: String,
+        tick_size: f64,
+    },
+}
+
+/// Provides a build function for the Connector.
+pub trait ConnectorBuilder {
+    type Error: Debug;
+
+    fn build_from(config: &str) -> Result<Self, Self::Error>
+    where
+        Self: Sized;
+}
+
+/// Provides an interface for connecting with an exchange or broker for a live bot.
+pub trait Connector {
+    /// Registers an instrument to be traded through this connector.
+    ///
+    /// * `symbol` - The symbol of the instrument.
+    /// * `tick_size` - The tick size of the instrument, as specified by the bot.
+    /// * `lot_size` - The lot size of the instrument, as specified by the bot.
+    fn register(&mut self, symbol: String, tick_size: f64, lot_size: f64);
+
+    /// Registers an instrument with the granularity of the market depth that the bot needs, so
+    /// that the connector can subscribe to a lighter stream. Other bots may request a different
+    /// level for the same instrument, in which case the finest one should be provided. By
+    /// default, the level is ignored and the instrument is registered by
+    /// [`register`](Connector::register).
+    fn register_with_depth(
+        &mut self,
+        symbol: String,
+        tick_size: f64,
+        lot_size: f64,
+        _depth: DepthLevel,
+    ) {
+        self.register(symbol, tick_size, lot_size);
+    }
+
+    /// Deregisters the instrument when a bot trading it is closed. Other bots may still be trading
+    /// the instrument, so the feeds should be kept unless the connector tracks it. By default,
+    /// nothing is done.
+    fn deregister(&mut self, _symbol: String) {}
+
+    /// Returns an [`OrderManager`].
+    fn order_manager(&self) -> Arc<Mutex<dyn GetOrders + Send + 'static>>;
+
+    /// Runs the connector, establishing the connection and preparing to exchange information such
+    /// as data feed and orders. This method should not block, and any response should be returned
+    /// through the channel using [`PublishEvent`]. The returned error should not be related to the
+    /// exchange; instead, it should indicate a connector internal error.
+    fn run(&mut self, tx: UnboundedSender<PublishEvent>);
+
+    /// Submits a new order. This method should not block, and the response should be returned
+    /// through the channel using [`PublishEvent`]. The returned error should not be related to the
+    /// exchange; instead, it should indicate a connector internal error.
+    fn submit(&self, symbol: String, order: Order, tx: UnboundedSender<PublishEvent>);
+
+    /// Cancels an open order. This method should not block, and the response should be returned
+    /// through the channel using [`PublishEvent`]. The returned error should not be related to the
+    /// exchange; instead, it should indicate a connector internal error.
+    fn cancel(&self, symbol: String, order: Order, tx: UnboundedSender<PublishEvent>);
+
+    /// Resyncs the market depth of the instrument by publishing a fresh market depth snapshot,
+    /// preceded by a depth clear event. This method should not block, and the snapshot should be
+    /// returned through the channel using [`PublishEvent`].
+    fn resync(&self, symbol: String, tx: UnboundedSender<PublishEvent>);
+
+    /// Queries the trading rules of the instrument, such as the tick size and lot size. This
+    /// method should not block, and the response should be returned through the channel as
+    /// [`LiveEvent::InstrumentInfo`]. By default, it responds that the information isn't
+    /// provided.
+    fn query_instrument_info(&self, symbol: String, tx: UnboundedSender<PublishEvent>) {
+        let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::InstrumentInfo {
+            symbol,
+            info: None,
+        }));
+    }
+
+    /// Publishes the open orders of the instrument on the exchange that weren't placed through
+    /// this connector, such as the ones placed manually or left over from a crash, as
+    /// [`LiveEvent::ExternalOrder`]. This is called when the instrument is registered and should
+    /// not block. By default, no external orders are reported.
+    fn report_external_orders(&self, _symbol: String, _tx: UnboundedSender<PublishEvent>) {}
+
+    /// Takes over the external order so that its updates are published as [`LiveEvent::Order`]
+    /// with the order ID of `order`, as for the orders submitted through this connector. This
+    /// method should not block. By default, an error is published since it's not supported.
+    fn adopt_order(
+        &self,
+        _symbol: String,
+        _exch_order_id: String,
+        _order: Order,
+        tx: UnboundedSender<PublishEvent>,
+    ) {
+        publish_error(
+            &tx,
+            ErrorKind::OrderError,
+            Value::String("Adopting an external order is not supported.".to_string()),
+        );
+    }
+
+    /// Cancels the external order, publishing [`LiveEvent::ExternalOrder`] with the final status
+    /// once it's canceled. This method should not block. By default, an error is published since
+    /// it's not supported.
+    fn cancel_external(
+        &self,
+        _symbol: String,
+        _exch_order_id: String,
+        tx: UnboundedSender<PublishEvent>,
+    ) {
+        publish_error(
+            &tx,
+            ErrorKind::OrderError,
+            Value::String("Canceling an external order is not supported.".to_string()),
+        );
+    }
+}
+
+/// Provides `orders` method to get the current working orders.
+pub trait GetOrders {
+    fn orders(&self, symbol: Option<String>) -> Vec<Order>;
+}
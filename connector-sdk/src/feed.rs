@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use hftbacktest::{
+    live::ipc::TO_ALL,
+    prelude::{
+        ErrorKind,
+        Event,
+        LiveError,
+        LiveEvent,
+        Side,
+        Value,
+        LOCAL_ASK_DEPTH_EVENT,
+        LOCAL_BID_DEPTH_EVENT,
+        LOCAL_BUY_TRADE_EVENT,
+        LOCAL_DEPTH_CLEAR_EVENT,
+        LOCAL_SELL_TRADE_EVENT,
+        LOCAL_TRADE_EVENT,
+    },
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{connector::PublishEvent, utils::PxQty};
+
+fn feed(symbol: &str, ev: u64, exch_ts: i64, px: f64, qty: f64) -> PublishEvent {
+    PublishEvent::LiveEvent(LiveEvent::Feed {
+        symbol: symbol.to_string(),
+        event: Event {
+            ev,
+            exch_ts,
+            local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+            order_id: 0,
+            px,
+            qty,
+            ival: 0,
+            fval: 0.0,
+        },
+    })
+}
+
+/// Publishes the market depth levels in a batch. If `clear` is `true`, the current market depth
+/// is cleared within the same batch, so the levels replace it as a snapshot.
+pub fn publish_depth(
+    ev_tx: &UnboundedSender<PublishEvent>,
+    symbol: &str,
+    exch_ts: i64,
+    bids: &[PxQty],
+    asks: &[PxQty],
+    clear: bool,
+) {
+    ev_tx.send(PublishEvent::BatchStart(TO_ALL)).unwrap();
+    if clear {
+        ev_tx
+            .send(feed(symbol, LOCAL_DEPTH_CLEAR_EVENT, exch_ts, 0.0, 0.0))
+            .unwrap();
+    }
+    for &(px, qty) in bids {
+        ev_tx
+            .send(feed(symbol, LOCAL_BID_DEPTH_EVENT, exch_ts, px, qty))
+            .unwrap();
+    }
+    for &(px, qty) in asks {
+        ev_tx
+            .send(feed(symbol, LOCAL_ASK_DEPTH_EVENT, exch_ts, px, qty))
+            .unwrap();
+    }
+    ev_tx.send(PublishEvent::BatchEnd(TO_ALL)).unwrap();
+}
+
+/// Publishes the event that clears the market depth of the symbol.
+pub fn publish_clear(ev_tx: &UnboundedSender<PublishEvent>, symbol: &str) {
+    let now = Utc::now().timestamp_nanos_opt().unwrap();
+    ev_tx
+        .send(feed(symbol, LOCAL_DEPTH_CLEAR_EVENT, now, 0.0, 0.0))
+        .unwrap();
+}
+
+/// Publishes the trade, where `side` is the taker's side, or [`Side::None`] if it's unknown.
+pub fn publish_trade(
+    ev_tx: &UnboundedSender<PublishEvent>,
+    symbol: &str,
+    side: Side,
+    exch_ts: i64,
+    px: f64,
+    qty: f64,
+) {
+    let ev = match side {
+        Side::Buy => LOCAL_BUY_TRADE_EVENT,
+        Side::Sell => LOCAL_SELL_TRADE_EVENT,
+        Side::None | Side::Unsupported => LOCAL_TRADE_EVENT,
+    };
+    ev_tx.send(feed(symbol, ev, exch_ts, px, qty)).unwrap();
+}
+
+/// Publishes the [`ErrorKind::SeqGap`] error with the expected and received sequence numbers.
+pub fn publish_seq_gap(
+    ev_tx: &UnboundedSender<PublishEvent>,
+    symbol: &str,
+    expected: i64,
+    received: i64,
+) {
+    let mut map = HashMap::new();
+    map.insert("symbol".to_string(), Value::String(symbol.to_string()));
+    map.insert("expected".to_string(), Value::Int(expected));
+    map.insert("received".to_string(), Value::Int(received));
+    publish_error(ev_tx, ErrorKind::SeqGap, Value::Map(map));
+}
+
+pub fn publish_error(ev_tx: &UnboundedSender<PublishEvent>, kind: ErrorKind, value: Value) {
+    ev_tx
+        .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+            kind, value,
+        ))))
+        .unwrap();
+}
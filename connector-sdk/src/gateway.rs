@@ -0,0 +1,37 @@
+use std::{fmt::Debug, future::Future};
+
+use hftbacktest::types::Order;
+
+use crate::ordermanager::{ClientOrderId, OrderUpdate};
+
+/// Sends the order requests to the venue and decodes the venue's order updates.
+///
+/// The requests only report whether they're accepted for processing; the order's status is
+/// updated by the order updates, which are applied to
+/// [`ClientOrderManager`](crate::ordermanager::ClientOrderManager).
+pub trait OrderGateway {
+    type Error: Debug;
+    /// The venue's message carrying the order updates.
+    type Message;
+
+    /// Submits the order, where `symbol` is the venue's symbol.
+    fn submit(
+        &self,
+        symbol: &str,
+        client_order_id: &str,
+        order: &Order,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    fn cancel(
+        &self,
+        symbol: &str,
+        client_order_id: &str,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Cancels all open orders of the symbol, which is used to start with a clean state after
+    /// connecting.
+    fn cancel_all(&self, symbol: &str) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Decodes the order updates in the message, keyed by the client order ID.
+    fn order_updates(&self, msg: &Self::Message) -> Vec<(ClientOrderId, OrderUpdate)>;
+}
@@ -0,0 +1,24 @@
+//! The building blocks for the hftbacktest connectors, which lets a connector outside the
+//! connector crate be written against the same interfaces as the bundled ones.
+//!
+//! * [`Connector`] and [`ConnectorBuilder`] are what the connector binary drives.
+//! * [`MarketDataHandler`] decodes the venue's market data into the market depth and trades.
+//! * [`OrderGateway`] sends the order requests to the venue and decodes the order updates.
+//! * [`NormalizeSymbol`] translates between the bots' symbols and the venue's symbols.
+//! * [`ordermanager::ClientOrderManager`] tracks the orders by the client order ID.
+//! * [`feed`] publishes the market data in batches.
+//! * [`conformance`] validates the handlers against a simulated exchange.
+
+pub mod conformance;
+mod connector;
+pub mod feed;
+mod gateway;
+mod marketdata;
+pub mod ordermanager;
+pub mod symbol;
+pub mod utils;
+
+pub use connector::{Connector, ConnectorBuilder, GetOrders, PublishEvent};
+pub use gateway::OrderGateway;
+pub use marketdata::{FeedError, MarketDataHandler};
+pub use symbol::NormalizeSymbol;
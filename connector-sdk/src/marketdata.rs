@@ -0,0 +1,39 @@
+use thiserror::Error;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::connector::PublishEvent;
+
+#[derive(Error, Debug)]
+pub enum FeedError {
+    /// The message doesn't follow the previous one, so the market depth needs a fresh snapshot.
+    #[error("SeqGap: {symbol} expected {expected}, received {received}")]
+    SeqGap {
+        symbol: String,
+        expected: i64,
+        received: i64,
+    },
+    #[error("Decode: {0}")]
+    Decode(String),
+}
+
+/// Decodes the venue's market data messages and publishes the market depth and trades, typically
+/// using [`crate::feed`].
+///
+/// A handler is expected to
+/// * publish a snapshot preceded by a depth clear event, or within the same batch,
+/// * apply the incremental updates only on top of a snapshot,
+/// * report [`FeedError::SeqGap`] instead of applying an update that doesn't follow the previous
+///   one, so that the connector can request a fresh snapshot.
+pub trait MarketDataHandler {
+    type Message;
+
+    fn handle(
+        &mut self,
+        msg: &Self::Message,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError>;
+
+    /// Resets the state, such as the sequence numbers, when the stream reconnects. The updates
+    /// must not be applied until the next snapshot.
+    fn reset(&mut self);
+}
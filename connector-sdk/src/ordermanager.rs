@@ -0,0 +1,227 @@
+use std::sync::{Arc, Mutex};
+
+use hashbrown::HashMap;
+use hftbacktest::types::{Fill, Order, OrderId, Status};
+use thiserror::Error;
+
+use crate::{
+    connector::GetOrders,
+    utils::{generate_rand_string, RefSymbolOrderId, SymbolOrderId},
+};
+
+pub type SharedClientOrderManager = Arc<Mutex<ClientOrderManager>>;
+
+/// The client order ID, which identifies the order created by this connector.
+pub type ClientOrderId = String;
+
+#[derive(Error, Debug)]
+pub enum OrderManagerError {
+    #[error("PrefixUnmatched")]
+    PrefixUnmatched,
+    #[error("OrderNotFound")]
+    OrderNotFound,
+    #[error("OrderAlreadyExist")]
+    OrderAlreadyExist,
+}
+
+#[derive(Clone)]
+pub struct OrderExt {
+    pub symbol: String,
+    pub order: Order,
+}
+
+/// The order update reported by the venue.
+#[derive(Clone, Debug)]
+pub struct OrderUpdate {
+    pub status: Status,
+    /// `None` keeps the current leaves quantity.
+    pub leaves_qty: Option<f64>,
+    /// The quantity filled by this update, which is `0` if it's not a fill.
+    pub exec_qty: f64,
+    pub exec_price: f64,
+    pub exch_ts: i64,
+    /// Whether the fill provided liquidity. `None` if the venue doesn't report it.
+    pub maker: Option<bool>,
+    /// The fee charged for the fill, which is negative for a rebate.
+    pub fee: f64,
+    /// The trade ID of the fill. `None` if the venue doesn't report it.
+    pub trade_id: Option<String>,
+}
+
+impl OrderUpdate {
+    /// Returns the fill reported by this update to the order, if it's a fill.
+    pub fn fill(&self, order: &Order) -> Option<Fill> {
+        (self.exec_qty > 0.0).then(|| Fill {
+            order_id: order.order_id,
+            trade_id: self.trade_id.clone().unwrap_or_default(),
+            side: order.side,
+            price: self.exec_price,
+            qty: self.exec_qty,
+            fee: self.fee,
+            maker: order.maker,
+            exch_ts: self.exch_ts,
+        })
+    }
+}
+
+/// Tracks the orders created by this connector by the client order ID, which consists of the
+/// prefix and a random string.
+pub struct ClientOrderManager {
+    prefix: String,
+    id_len: usize,
+    orders: HashMap<ClientOrderId, OrderExt>,
+    order_id_map: HashMap<SymbolOrderId, ClientOrderId>,
+}
+
+impl ClientOrderManager {
+    /// * `id_len` - The total length of the client order ID, including the prefix.
+    pub fn new(prefix: &str, id_len: usize) -> Self {
+        assert!(
+            prefix.len() < id_len,
+            "order prefix length should be less than {id_len}."
+        );
+        Self {
+            prefix: prefix.to_string(),
+            id_len,
+            orders: Default::default(),
+            order_id_map: Default::default(),
+        }
+    }
+
+    /// Registers the new order and returns its client order ID.
+    pub fn new_order(
+        &mut self,
+        symbol: &str,
+        order: Order,
+    ) -> Result<ClientOrderId, OrderManagerError> {
+        let symbol_order_id = SymbolOrderId::new(symbol.to_string(), order.order_id);
+        if self.order_id_map.contains_key(&symbol_order_id) {
+            return Err(OrderManagerError::OrderAlreadyExist);
+        }
+        let client_order_id = format!(
+            "{}{}",
+            self.prefix,
+            generate_rand_string(self.id_len - self.prefix.len())
+        );
+        self.order_id_map
+            .insert(symbol_order_id, client_order_id.clone());
+        self.orders.insert(
+            client_order_id.clone(),
+            OrderExt {
+                symbol: symbol.to_string(),
+                order,
+            },
+        );
+        Ok(client_order_id)
+    }
+
+    pub fn client_order_id(
+        &self,
+        symbol: &str,
+        order_id: OrderId,
+    ) -> Result<ClientOrderId, OrderManagerError> {
+        self.order_id_map
+            .get(&RefSymbolOrderId::new(symbol, order_id))
+            .cloned()
+            .ok_or(OrderManagerError::OrderNotFound)
+    }
+
+    /// Applies the update to the order and returns it. The order is removed once it's no longer
+    /// active.
+    pub fn update(
+        &mut self,
+        client_order_id: &str,
+        update: OrderUpdate,
+    ) -> Result<OrderExt, OrderManagerError> {
+        if !client_order_id.starts_with(&self.prefix) {
+            return Err(OrderManagerError::PrefixUnmatched);
+        }
+        let order = self
+            .orders
+            .get_mut(client_order_id)
+            .ok_or(OrderManagerError::OrderNotFound)?;
+        order.order.req = Status::None;
+        if update.status != Status::Unsupported {
+            order.order.status = update.status;
+        }
+        if let Some(leaves_qty) = update.leaves_qty {
+            order.order.leaves_qty = leaves_qty;
+        }
+        order.order.exec_qty = update.exec_qty;
+        if update.exec_qty > 0.0 {
+            order.order.exec_price_tick =
+                (update.exec_price / order.order.tick_size).round() as i64;
+            if let Some(maker) = update.maker {
+                order.order.maker = maker;
+            }
+        }
+        order.order.exch_timestamp = update.exch_ts;
+        if !order.order.active() {
+            self.order_id_map
+                .remove(&RefSymbolOrderId::new(&order.symbol, order.order.order_id));
+            Ok(self.orders.remove(client_order_id).unwrap())
+        } else {
+            Ok(order.clone())
+        }
+    }
+
+    pub fn update_submit_fail(
+        &mut self,
+        client_order_id: &str,
+    ) -> Result<OrderExt, OrderManagerError> {
+        let mut order = self
+            .orders
+            .remove(client_order_id)
+            .ok_or(OrderManagerError::OrderNotFound)?;
+        order.order.req = Status::None;
+        order.order.status = Status::Expired;
+        self.order_id_map
+            .remove(&RefSymbolOrderId::new(&order.symbol, order.order.order_id));
+        Ok(order)
+    }
+
+    pub fn update_cancel_fail(
+        &mut self,
+        client_order_id: &str,
+    ) -> Result<OrderExt, OrderManagerError> {
+        let mut order = self
+            .orders
+            .get(client_order_id)
+            .cloned()
+            .ok_or(OrderManagerError::OrderNotFound)?;
+        order.order.req = Status::None;
+        Ok(order)
+    }
+
+    /// Marks all orders of the symbol as canceled and removes them.
+    pub fn cancel_all(&mut self, symbol: &str) -> Vec<Order> {
+        let mut removed = Vec::new();
+        for (client_order_id, order_ext) in &mut self.orders {
+            if order_ext.symbol != symbol {
+                continue;
+            }
+            order_ext.order.req = Status::None;
+            order_ext.order.status = Status::Canceled;
+            self.order_id_map
+                .remove(&RefSymbolOrderId::new(symbol, order_ext.order.order_id));
+            removed.push(client_order_id.clone());
+        }
+        removed
+            .iter()
+            .map(|client_order_id| self.orders.remove(client_order_id).unwrap().order)
+            .collect()
+    }
+}
+
+impl GetOrders for ClientOrderManager {
+    fn orders(&self, symbol: Option<String>) -> Vec<Order> {
+        self.orders
+            .iter()
+            .filter(|(_, order)| {
+                symbol.as_ref().map(|s| order.symbol == *s).unwrap_or(true) && order.order.active()
+            })
+            .map(|(_, order)| &order.order)
+            .cloned()
+            .collect()
+    }
+}
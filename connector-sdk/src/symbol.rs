@@ -0,0 +1,126 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+pub type SharedSymbolNormalizer = Arc<Mutex<SymbolNormalizer>>;
+
+/// Translates between the symbols registered by the bots and the venue's symbols.
+pub trait NormalizeSymbol {
+    /// Returns the venue's symbol for the bots' symbol.
+    fn to_venue(&self, symbol: &str) -> String;
+
+    /// Returns the bots' symbol for the venue's symbol.
+    fn to_local(&self, venue: &str) -> String;
+}
+
+/// How the venue spells the symbols.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum SymbolCase {
+    #[default]
+    Preserve,
+    Upper,
+}
+
+/// Translates between the symbols registered by the bots and the venue's symbols, and keeps the
+/// set of the registered symbols.
+///
+/// An alias takes precedence over the case conversion; for example, KuCoin names bitcoin `XBT`,
+/// so the alias `BTCUSDTM` -> `XBTUSDTM` lets the bots keep using `BTCUSDTM`.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolNormalizer {
+    case: SymbolCase,
+    aliases: HashMap<String, String>,
+    to_local: HashMap<String, String>,
+}
+
+impl SymbolNormalizer {
+    pub fn new(case: SymbolCase) -> Self {
+        Self {
+            case,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the aliases, keyed by the bots' symbol.
+    pub fn aliases(self, aliases: HashMap<String, String>) -> Self {
+        Self { aliases, ..self }
+    }
+
+    /// Returns the venue's symbol for the bots' symbol.
+    pub fn to_venue(&self, symbol: &str) -> String {
+        match self.aliases.get(symbol) {
+            Some(venue) => venue.clone(),
+            None => match self.case {
+                SymbolCase::Preserve => symbol.to_string(),
+                SymbolCase::Upper => symbol.to_uppercase(),
+            },
+        }
+    }
+
+    /// Returns the symbol registered by the bots for the venue's symbol, or the venue's symbol
+    /// as is if it's not registered.
+    pub fn to_local(&self, venue: &str) -> String {
+        self.to_local
+            .get(venue)
+            .cloned()
+            .unwrap_or_else(|| venue.to_string())
+    }
+
+    /// Registers the bots' symbol and returns the venue's symbol if it's newly registered.
+    pub fn register(&mut self, symbol: &str) -> Option<String> {
+        let venue = self.to_venue(symbol);
+        if self.to_local.contains_key(&venue) {
+            return None;
+        }
+        self.to_local.insert(venue.clone(), symbol.to_string());
+        Some(venue)
+    }
+
+    /// Returns the venue's symbols of the registered symbols.
+    pub fn venue_symbols(&self) -> Vec<String> {
+        self.to_local.keys().cloned().collect()
+    }
+
+    pub fn is_registered(&self, venue: &str) -> bool {
+        self.to_local.contains_key(venue)
+    }
+}
+
+impl NormalizeSymbol for SymbolNormalizer {
+    fn to_venue(&self, symbol: &str) -> String {
+        SymbolNormalizer::to_venue(self, symbol)
+    }
+
+    fn to_local(&self, venue: &str) -> String {
+        SymbolNormalizer::to_local(self, venue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn translate_registered_symbols() {
+        let mut aliases = HashMap::new();
+        aliases.insert("BTCUSDTM".to_string(), "XBTUSDTM".to_string());
+        let mut normalizer = SymbolNormalizer::new(SymbolCase::Upper).aliases(aliases);
+
+        assert_eq!(
+            normalizer.register("BTCUSDTM"),
+            Some("XBTUSDTM".to_string())
+        );
+        assert_eq!(
+            normalizer.register("ethusdtm"),
+            Some("ETHUSDTM".to_string())
+        );
+        assert_eq!(normalizer.register("BTCUSDTM"), None);
+
+        assert_eq!(normalizer.to_local("XBTUSDTM"), "BTCUSDTM");
+        assert_eq!(normalizer.to_local("ETHUSDTM"), "ethusdtm");
+        assert_eq!(normalizer.to_local("SOLUSDTM"), "SOLUSDTM");
+    }
+}
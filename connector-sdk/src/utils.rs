@@ -0,0 +1,64 @@
+use hashbrown::Equivalent;
+use hftbacktest::prelude::OrderId;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+
+pub type PxQty = (f64, f64);
+
+#[derive(Eq, Hash, PartialEq, Debug)]
+pub struct SymbolOrderId {
+    pub symbol: String,
+    pub order_id: OrderId,
+}
+
+impl SymbolOrderId {
+    pub fn new(symbol: String, order_id: OrderId) -> Self {
+        Self { symbol, order_id }
+    }
+}
+
+#[derive(Eq, Hash, PartialEq, Debug)]
+pub struct RefSymbolOrderId<'a> {
+    pub symbol: &'a str,
+    pub order_id: OrderId,
+}
+
+impl<'a> RefSymbolOrderId<'a> {
+    pub fn new(symbol: &'a str, order_id: OrderId) -> Self {
+        Self { symbol, order_id }
+    }
+}
+
+impl Equivalent<SymbolOrderId> for RefSymbolOrderId<'_> {
+    fn equivalent(&self, key: &SymbolOrderId) -> bool {
+        key.symbol == self.symbol && key.order_id == self.order_id
+    }
+}
+
+pub fn generate_rand_string(length: usize) -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashMap;
+
+    use crate::utils::{RefSymbolOrderId, SymbolOrderId};
+
+    #[test]
+    fn equivalent_symbol_order_id() {
+        let mut map = HashMap::new();
+        map.insert(
+            SymbolOrderId::new("key1".to_string(), 1),
+            "value1".to_string(),
+        );
+
+        assert_eq!(
+            map.get(&RefSymbolOrderId::new("key1", 1)).unwrap(),
+            "value1"
+        )
+    }
+}
@@ -0,0 +1,64 @@
+use std::{env, fs::read_to_string, thread};
+
+use connector::{replay::Replay, run, ConnectorBuilder, Transport};
+use hftbacktest::{
+    live::{
+        ipc::inprocess::{InProcessServer, InProcessUnifiedChannel},
+        Instrument,
+        LiveBot,
+        LiveBotBuilder,
+    },
+    prelude::{Bot, HashMapMarketDepth, MarketDepth},
+};
+use tokio::runtime::Builder;
+
+const CONNECTOR_NAME: &str = "replay";
+
+/// Runs the replay connector and the bot within a single process over the in-process transport.
+///
+/// Usage: `cargo run --example inprocess -- <replay config path> <symbol> <tick size> <lot size>`
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args: Vec<String> = env::args().collect();
+    let [_, config, symbol, tick_size, lot_size] = args.as_slice() else {
+        eprintln!("Usage: inprocess <replay config path> <symbol> <tick size> <lot size>");
+        return;
+    };
+    let tick_size: f64 = tick_size.parse().unwrap();
+    let lot_size: f64 = lot_size.parse().unwrap();
+
+    // The server must be bound before the bot is built.
+    let server = InProcessServer::bind(CONNECTOR_NAME).unwrap();
+    let connector = Replay::build_from(&read_to_string(config).unwrap()).unwrap();
+    thread::spawn(move || {
+        let rt = Builder::new_multi_thread().enable_all().build().unwrap();
+        let _guard = rt.enter();
+        run(Box::new(connector), Transport::InProcess(server)).unwrap();
+    });
+
+    let mut hbt: LiveBot<InProcessUnifiedChannel, HashMapMarketDepth> = LiveBotBuilder::new()
+        .register(Instrument::new(
+            CONNECTOR_NAME,
+            symbol,
+            tick_size,
+            lot_size,
+            HashMapMarketDepth::new(tick_size, lot_size),
+            0,
+        ))
+        .build()
+        .unwrap();
+
+    for _ in 0..60 {
+        if !hbt.elapse(1_000_000_000).unwrap() {
+            break;
+        }
+        let depth = hbt.depth(0);
+        println!(
+            "best bid: {}, best ask: {}",
+            depth.best_bid(),
+            depth.best_ask()
+        );
+    }
+    hbt.close().unwrap();
+}
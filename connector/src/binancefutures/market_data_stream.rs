@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use chrono::Utc;
-use futures_util::{SinkExt, StreamExt};
+use futures_util::{Sink, SinkExt, StreamExt};
 use hftbacktest::{live::ipc::TO_ALL, prelude::*};
 use tokio::{
     select,
@@ -12,9 +12,10 @@ use tokio::{
 };
 use tokio_tungstenite::{
     connect_async,
+    tungstenite,
     tungstenite::{client::IntoClientRequest, Message},
 };
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 use crate::{
     binancefutures::{
@@ -25,17 +26,35 @@ use crate::{
         },
         rest::BinanceFuturesClient,
         BinanceFuturesError,
+        SharedDepthLevels,
+        SharedSymbolSet,
     },
     connector::PublishEvent,
     utils::{generate_rand_string, parse_depth, parse_px_qty_tup},
 };
 
+/// The synchronization state of the local order book of a symbol, which follows
+/// `<https://binance-docs.github.io/apidocs/futures/en/#how-to-manage-a-local-order-book-correctly>`.
+enum BookState {
+    /// Buffers the depth updates until the REST snapshot arrives.
+    Snapshotting(Vec<stream::Depth>),
+    /// The snapshot with the `lastUpdateId` is applied, and the first depth update to apply must
+    /// satisfy `U <= lastUpdateId <= u`.
+    AwaitingFirst(i64),
+    /// The depth updates are applied in sequence. Holds the last `u`.
+    Synced(i64),
+}
+
 pub struct MarketDataStream {
     client: BinanceFuturesClient,
     ev_tx: UnboundedSender<PublishEvent>,
+    symbols: SharedSymbolSet,
     symbol_rx: Receiver<String>,
-    pending_depth_messages: HashMap<String, Vec<stream::Depth>>,
-    prev_u: HashMap<String, i64>,
+    depth_levels: SharedDepthLevels,
+    depth_rx: Receiver<String>,
+    /// The depth level subscribed for each symbol.
+    subscribed: HashMap<String, DepthLevel>,
+    books: HashMap<String, BookState>,
     rest_tx: UnboundedSender<(String, rest::Depth)>,
     rest_rx: UnboundedReceiver<(String, rest::Depth)>,
 }
@@ -44,143 +63,139 @@ impl MarketDataStream {
     pub fn new(
         client: BinanceFuturesClient,
         ev_tx: UnboundedSender<PublishEvent>,
+        symbols: SharedSymbolSet,
         symbol_rx: Receiver<String>,
+        depth_levels: SharedDepthLevels,
+        depth_rx: Receiver<String>,
     ) -> Self {
         let (rest_tx, rest_rx) = unbounded_channel::<(String, rest::Depth)>();
         Self {
             client,
             ev_tx,
+            symbols,
             symbol_rx,
-            pending_depth_messages: Default::default(),
-            prev_u: Default::default(),
+            depth_levels,
+            depth_rx,
+            subscribed: Default::default(),
+            books: Default::default(),
             rest_tx,
             rest_rx,
         }
     }
 
-    fn process_message(&mut self, stream: EventStream) {
-        match stream {
-            EventStream::DepthUpdate(data) => {
-                let mut prev_u_val = self.prev_u.get_mut(&data.symbol);
-                if prev_u_val.is_none()
-                /* fixme: || data.prev_update_id != **prev_u_val.as_ref().unwrap()*/
-                {
-                    // if !pending_depth_messages.contains_key(&data.symbol) {
-                    let client_ = self.client.clone();
-                    let symbol = data.symbol.clone();
-                    let rest_tx = self.rest_tx.clone();
-                    tokio::spawn(async move {
-                        let resp = client_.get_depth(&symbol).await;
-                        match resp {
-                            Ok(depth) => {
-                                rest_tx.send((symbol, depth)).unwrap();
-                            }
-                            Err(error) => {
-                                error!(
-                                    ?error,
-                                    %symbol,
-                                    "Couldn't get the market depth via REST."
-                                );
-                            }
-                        }
-                    });
-                    // }
-                    // pending_depth_messages
-                    //     .entry(data.symbol.clone())
-                    //     .or_insert(Vec::new())
-                    //     .push(data);
-                    // continue;
+    /// Requests the REST snapshot, buffering the depth updates until it arrives.
+    fn request_snapshot(&mut self, symbol: String, pending: Vec<stream::Depth>) {
+        self.books
+            .insert(symbol.clone(), BookState::Snapshotting(pending));
+        let client = self.client.clone();
+        let rest_tx = self.rest_tx.clone();
+        tokio::spawn(async move {
+            match client.get_depth(&symbol).await {
+                Ok(depth) => {
+                    let _ = rest_tx.send((symbol, depth));
                 }
-                // *prev_u_val.unwrap() = data.last_update_id;
-                // fixme: currently supports natural refresh only.
-                *self
-                    .prev_u
-                    .entry(data.symbol.clone())
-                    .or_insert(data.last_update_id) = data.last_update_id;
-
-                match parse_depth(data.bids, data.asks) {
-                    Ok((bids, asks)) => {
-                        self.ev_tx.send(PublishEvent::BatchStart(TO_ALL)).unwrap();
-
-                        for (px, qty) in bids {
-                            self.ev_tx
-                                .send(PublishEvent::LiveEvent(LiveEvent::Feed {
-                                    symbol: data.symbol.clone(),
-                                    event: Event {
-                                        ev: LOCAL_BID_DEPTH_EVENT,
-                                        exch_ts: data.transaction_time * 1_000_000,
-                                        local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
-                                        order_id: 0,
-                                        px,
-                                        qty,
-                                        ival: 0,
-                                        fval: 0.0,
-                                    },
-                                }))
-                                .unwrap();
-                        }
-
-                        for (px, qty) in asks {
-                            self.ev_tx
-                                .send(PublishEvent::LiveEvent(LiveEvent::Feed {
-                                    symbol: data.symbol.clone(),
-                                    event: Event {
-                                        ev: LOCAL_ASK_DEPTH_EVENT,
-                                        exch_ts: data.transaction_time * 1_000_000,
-                                        local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
-                                        order_id: 0,
-                                        px,
-                                        qty,
-                                        ival: 0,
-                                        fval: 0.0,
-                                    },
-                                }))
-                                .unwrap();
-                        }
+                Err(error) => {
+                    error!(
+                        ?error,
+                        %symbol,
+                        "Couldn't get the market depth via REST."
+                    );
+                }
+            }
+        });
+    }
 
-                        self.ev_tx.send(PublishEvent::BatchEnd(TO_ALL)).unwrap();
-                    }
-                    Err(error) => {
-                        error!(?error, "Couldn't parse DepthUpdate stream.");
-                    }
+    fn process_depth(&mut self, data: stream::Depth) {
+        match self.books.get_mut(&data.symbol) {
+            None => {
+                let symbol = data.symbol.clone();
+                self.request_snapshot(symbol, vec![data]);
+            }
+            Some(BookState::Snapshotting(pending)) => {
+                pending.push(data);
+            }
+            Some(BookState::AwaitingFirst(last_update_id)) => {
+                if data.last_update_id < *last_update_id {
+                    // Already reflected in the snapshot.
+                } else if data.first_update_id <= *last_update_id {
+                    *self.books.get_mut(&data.symbol).unwrap() =
+                        BookState::Synced(data.last_update_id);
+                    self.publish_depth(
+                        data.symbol,
+                        data.transaction_time,
+                        data.bids,
+                        data.asks,
+                        false,
+                    );
+                } else {
+                    // The updates between the snapshot and this update are missing.
+                    warn!(
+                        symbol = data.symbol,
+                        "The depth update doesn't follow the snapshot. Requests a new snapshot."
+                    );
+                    let symbol = data.symbol.clone();
+                    self.request_snapshot(symbol, vec![data]);
                 }
             }
-            EventStream::Trade(data) => match parse_px_qty_tup(data.price, data.qty) {
-                Ok((px, qty)) => {
+            Some(BookState::Synced(prev_u)) => {
+                if data.prev_update_id != *prev_u {
+                    // Applying the update over the missing updates would corrupt the market
+                    // depth, so it's recovered from a fresh snapshot instead.
+                    warn!(
+                        symbol = data.symbol,
+                        expected = *prev_u,
+                        received = data.prev_update_id,
+                        "A gap in the depth updates is detected. Requests a new snapshot."
+                    );
+                    publish_seq_gap(&self.ev_tx, &data.symbol, *prev_u, data.prev_update_id);
+                    let symbol = data.symbol.clone();
+                    self.request_snapshot(symbol, vec![data]);
+                } else {
+                    *prev_u = data.last_update_id;
+                    self.publish_depth(
+                        data.symbol,
+                        data.transaction_time,
+                        data.bids,
+                        data.asks,
+                        false,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Publishes the depth updates. If `clear` is `true`, the current market depth is cleared
+    /// within the same batch before the updates are applied, as the updates are a snapshot of the
+    /// top levels.
+    fn publish_depth(
+        &self,
+        symbol: String,
+        transaction_time: i64,
+        bids: Vec<(String, String)>,
+        asks: Vec<(String, String)>,
+        clear: bool,
+    ) {
+        match parse_depth(bids, asks) {
+            Ok((bids, asks)) => {
+                self.ev_tx.send(PublishEvent::BatchStart(TO_ALL)).unwrap();
+
+                if clear {
                     self.ev_tx
                         .send(PublishEvent::LiveEvent(LiveEvent::Feed {
-                            symbol: data.symbol,
+                            symbol: symbol.clone(),
                             event: Event {
-                                ev: {
-                                    if data.is_the_buyer_the_market_maker {
-                                        LOCAL_SELL_TRADE_EVENT
-                                    } else {
-                                        LOCAL_BUY_TRADE_EVENT
-                                    }
-                                },
-                                exch_ts: data.transaction_time * 1_000_000,
+                                ev: LOCAL_DEPTH_CLEAR_EVENT,
+                                exch_ts: transaction_time * 1_000_000,
                                 local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
                                 order_id: 0,
-                                px,
-                                qty,
+                                px: 0.0,
+                                qty: 0.0,
                                 ival: 0,
                                 fval: 0.0,
                             },
                         }))
                         .unwrap();
                 }
-                Err(e) => {
-                    error!(error = ?e, "Couldn't parse trade stream.");
-                }
-            },
-            _ => unreachable!(),
-        }
-    }
-
-    fn process_snapshot(&self, symbol: String, data: rest::Depth) {
-        match parse_depth(data.bids, data.asks) {
-            Ok((bids, asks)) => {
-                self.ev_tx.send(PublishEvent::BatchStart(TO_ALL)).unwrap();
 
                 for (px, qty) in bids {
                     self.ev_tx
@@ -188,7 +203,7 @@ impl MarketDataStream {
                             symbol: symbol.clone(),
                             event: Event {
                                 ev: LOCAL_BID_DEPTH_EVENT,
-                                exch_ts: data.transaction_time * 1_000_000,
+                                exch_ts: transaction_time * 1_000_000,
                                 local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
                                 order_id: 0,
                                 px,
@@ -206,7 +221,7 @@ impl MarketDataStream {
                             symbol: symbol.clone(),
                             event: Event {
                                 ev: LOCAL_ASK_DEPTH_EVENT,
-                                exch_ts: data.transaction_time * 1_000_000,
+                                exch_ts: transaction_time * 1_000_000,
                                 local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
                                 order_id: 0,
                                 px,
@@ -221,38 +236,169 @@ impl MarketDataStream {
                 self.ev_tx.send(PublishEvent::BatchEnd(TO_ALL)).unwrap();
             }
             Err(error) => {
-                error!(?error, "Couldn't parse Depth response.");
+                error!(?error, "Couldn't parse DepthUpdate stream.");
+            }
+        }
+    }
+
+    fn process_message(&mut self, stream: EventStream) {
+        match stream {
+            EventStream::DepthUpdate(data) => {
+                let level = self.subscribed.get(&data.symbol).copied();
+                if level.and_then(partial_depth_levels).is_some() {
+                    // The partial book depth stream pushes the snapshots of the top levels.
+                    self.publish_depth(
+                        data.symbol,
+                        data.transaction_time,
+                        data.bids,
+                        data.asks,
+                        true,
+                    );
+                } else {
+                    self.process_depth(data);
+                }
+            }
+            EventStream::BookTicker(data) => {
+                self.publish_depth(
+                    data.symbol,
+                    data.transaction_time,
+                    vec![(data.bid_price, data.bid_qty)],
+                    vec![(data.ask_price, data.ask_qty)],
+                    true,
+                );
+            }
+            EventStream::Trade(data) => match parse_px_qty_tup(data.price, data.qty) {
+                Ok((px, qty)) => {
+                    self.ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                            symbol: data.symbol,
+                            event: Event {
+                                ev: {
+                                    if data.is_the_buyer_the_market_maker {
+                                        LOCAL_SELL_TRADE_EVENT
+                                    } else {
+                                        LOCAL_BUY_TRADE_EVENT
+                                    }
+                                },
+                                exch_ts: data.transaction_time * 1_000_000,
+                                local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                                order_id: 0,
+                                px,
+                                qty,
+                                ival: 0,
+                                fval: 0.0,
+                            },
+                        }))
+                        .unwrap();
+                }
+                Err(e) => {
+                    error!(error = ?e, "Couldn't parse trade stream.");
+                }
+            },
+            EventStream::MarkPriceUpdate(data) => {
+                let exch_ts = data.event_time * 1_000_000;
+                let _ = self
+                    .ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::MarkPrice {
+                        symbol: data.symbol.clone(),
+                        price: data.mark_price,
+                        exch_ts,
+                    }));
+                let _ = self
+                    .ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::IndexPrice {
+                        symbol: data.symbol.clone(),
+                        price: data.index_price,
+                        exch_ts,
+                    }));
+                // The delivery contracts, which aren't funded, have no funding time.
+                if data.next_funding_time > 0 {
+                    let _ = self
+                        .ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::FundingRate {
+                            symbol: data.symbol,
+                            funding: FundingRate {
+                                rate: data.funding_rate,
+                                next_funding_time: data.next_funding_time * 1_000_000,
+                                exch_ts,
+                            },
+                        }));
+                }
+            }
+            EventStream::ForceOrder(data) => {
+                let order = data.order;
+                let _ = self.ev_tx.send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                    symbol: order.symbol,
+                    event: Event {
+                        ev: match order.side {
+                            Side::Buy => LOCAL_BUY_LIQUIDATION_EVENT,
+                            _ => LOCAL_SELL_LIQUIDATION_EVENT,
+                        },
+                        exch_ts: order.transaction_time * 1_000_000,
+                        local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                        order_id: 0,
+                        px: order.avg_price,
+                        qty: order.executed_accumulated_qty,
+                        ival: 0,
+                        fval: 0.0,
+                    },
+                }));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn process_snapshot(&mut self, symbol: String, data: rest::Depth) {
+        let pending = match self.books.remove(&symbol) {
+            Some(BookState::Snapshotting(pending)) => pending,
+            Some(state) => {
+                // A snapshot that is no longer awaited.
+                self.books.insert(symbol, state);
+                return;
+            }
+            None => Vec::new(),
+        };
+        // The snapshot replaces the market depth, which may be stale due to the gap.
+        let last_update_id = data.last_update_id;
+        publish_snapshot(&self.ev_tx, symbol.clone(), data, true);
+        self.books
+            .insert(symbol, BookState::AwaitingFirst(last_update_id));
+        // Re-sequences the depth updates buffered while the snapshot was being fetched.
+        for depth in pending {
+            self.process_depth(depth);
+        }
+    }
+
+    /// Subscribes to the trade stream, the mark price stream, the liquidation order stream and the
+    /// depth stream of the requested depth level. If the
+    /// symbol is already subscribed with another level, switches the depth stream.
+    async fn subscribe<S>(&mut self, write: &mut S, symbol: &str) -> Result<(), BinanceFuturesError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        let level = self
+            .depth_levels
+            .lock()
+            .unwrap()
+            .get(symbol)
+            .copied()
+            .unwrap_or_default();
+        let mut params = Vec::new();
+        match self.subscribed.insert(symbol.to_string(), level) {
+            Some(prev) if prev == level => return Ok(()),
+            Some(prev) => {
+                send_request(write, "UNSUBSCRIBE", vec![depth_stream(symbol, prev)]).await?;
+                // The market depth is rebuilt from the new depth stream.
+                self.books.remove(symbol);
+            }
+            None => {
+                params.push(format!("{symbol}@trade"));
+                params.push(format!("{symbol}@markPrice@1s"));
+                params.push(format!("{symbol}@forceOrder"));
             }
         }
-        // fixme: waits for pending messages without blocking.
-        // prev_u.remove(&symbol);
-        // let mut new_prev_u: Option<i64> = None;
-        // while new_prev_u.is_none() {
-        //     if let Some(msg) = pending_depth_messages.get_mut(&symbol) {
-        //         for pending_depth in msg.into_iter() {
-        //             // https://binance-docs.github.io/apidocs/futures/en/#how-to-manage-a-local-order-book-correctly
-        //             // The first processed event should have U <= lastUpdateId AND u >= lastUpdateId
-        //             if (
-        //                 pending_depth.last_update_id < resp.last_update_id
-        //                 || pending_depth.first_update_id > resp.last_update_id
-        //             ) && new_prev_u.is_none() {
-        //                 continue;
-        //             }
-        //             if new_prev_u.is_some() && pending_depth.prev_update_id != *new_prev_u.as_ref().unwrap() {
-        //                 warn!(%symbol, ?pending_depth, "UpdateId does not match.");
-        //             }
-        //
-        //             // Processes a pending depth message
-        //             new_prev_u = Some(pending_depth.last_update_id);
-        //             *prev_u.entry(symbol.clone())
-        //                 .or_insert(pending_depth.last_update_id) = pending_depth.last_update_id;
-        //         }
-        //     }
-        //     if new_prev_u.is_none() {
-        //         // Waits for depth messages.
-        //         todo!()
-        //     }
-        // }
+        params.push(depth_stream(symbol, level));
+        send_request(write, "SUBSCRIBE", params).await
     }
 
     pub async fn connect(&mut self, url: &str) -> Result<(), BinanceFuturesError> {
@@ -260,6 +406,15 @@ impl MarketDataStream {
         let (ws_stream, _) = connect_async(request).await?;
         let (mut write, mut read) = ws_stream.split();
 
+        // Resubscribes to the symbols registered before the reconnection. Their market depth is
+        // cleared since the updates during the disconnection are missing; it's rebuilt from a
+        // fresh snapshot once the depth updates arrive.
+        let symbols: Vec<_> = self.symbols.lock().unwrap().iter().cloned().collect();
+        for symbol in symbols {
+            publish_clear(&self.ev_tx, symbol.clone());
+            self.subscribe(&mut write, &symbol).await?;
+        }
+
         loop {
             select! {
                 Some((symbol, data)) = self.rest_rx.recv() => {
@@ -267,15 +422,7 @@ impl MarketDataStream {
                 }
                 msg = self.symbol_rx.recv() => match msg {
                     Ok(symbol) => {
-                        let id = generate_rand_string(16);
-                        write.send(Message::Text(format!(r#"{{
-                            "method": "SUBSCRIBE",
-                            "params": [
-                                "{symbol}@trade",
-                                "{symbol}@depth@0ms"
-                            ],
-                            "id": "{id}"
-                        }}"#))).await?;
+                        self.subscribe(&mut write, &symbol).await?;
                     }
                     Err(RecvError::Closed) => {
                         return Ok(());
@@ -284,6 +431,19 @@ impl MarketDataStream {
                         error!("{num} subscription requests were missed.");
                     }
                 },
+                msg = self.depth_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        if self.subscribed.contains_key(&symbol) {
+                            self.subscribe(&mut write, &symbol).await?;
+                        }
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} depth level changes were missed.");
+                    }
+                },
                 message = read.next() => match message {
                     Some(Ok(Message::Text(text))) => {
                         match serde_json::from_str::<Stream>(&text) {
@@ -320,3 +480,150 @@ impl MarketDataStream {
         }
     }
 }
+
+/// Returns the number of levels of the partial book depth stream that provides the depth level,
+/// or `None` if the level needs the diff depth stream or the book ticker stream.
+fn partial_depth_levels(level: DepthLevel) -> Option<usize> {
+    match level {
+        DepthLevel::Top(n) => [5, 10, 20].into_iter().find(|&levels| n <= levels),
+        DepthLevel::Bbo | DepthLevel::Full => None,
+    }
+}
+
+/// Returns the name of the stream that provides the market depth of the level.
+fn depth_stream(symbol: &str, level: DepthLevel) -> String {
+    match (level, partial_depth_levels(level)) {
+        (DepthLevel::Bbo, _) => format!("{symbol}@bookTicker"),
+        (_, Some(levels)) => format!("{symbol}@depth{levels}@100ms"),
+        (_, None) => format!("{symbol}@depth@0ms"),
+    }
+}
+
+async fn send_request<S>(
+    write: &mut S,
+    method: &str,
+    params: Vec<String>,
+) -> Result<(), BinanceFuturesError>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    let request = serde_json::json!({
+        "method": method,
+        "params": params,
+        "id": generate_rand_string(16),
+    });
+    write.send(Message::Text(request.to_string())).await?;
+    Ok(())
+}
+
+/// Publishes the [`ErrorKind::SeqGap`] error with the expected and received sequence numbers.
+fn publish_seq_gap(
+    ev_tx: &UnboundedSender<PublishEvent>,
+    symbol: &str,
+    expected: i64,
+    received: i64,
+) {
+    let mut map = HashMap::new();
+    map.insert("symbol".to_string(), Value::String(symbol.to_string()));
+    map.insert("expected".to_string(), Value::Int(expected));
+    map.insert("received".to_string(), Value::Int(received));
+    ev_tx
+        .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+            ErrorKind::SeqGap,
+            Value::Map(map),
+        ))))
+        .unwrap();
+}
+
+/// Publishes the event that clears the market depth of the symbol.
+fn publish_clear(ev_tx: &UnboundedSender<PublishEvent>, symbol: String) {
+    ev_tx
+        .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+            symbol,
+            event: Event {
+                ev: LOCAL_DEPTH_CLEAR_EVENT,
+                exch_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                order_id: 0,
+                px: 0.0,
+                qty: 0.0,
+                ival: 0,
+                fval: 0.0,
+            },
+        }))
+        .unwrap();
+}
+
+/// Publishes the market depth snapshot. If `clear` is `true`, the current market depth is cleared
+/// within the same batch before the snapshot is applied, which resyncs the market depth.
+pub fn publish_snapshot(
+    ev_tx: &UnboundedSender<PublishEvent>,
+    symbol: String,
+    data: rest::Depth,
+    clear: bool,
+) {
+    match parse_depth(data.bids, data.asks) {
+        Ok((bids, asks)) => {
+            ev_tx.send(PublishEvent::BatchStart(TO_ALL)).unwrap();
+
+            if clear {
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                        symbol: symbol.clone(),
+                        event: Event {
+                            ev: LOCAL_DEPTH_CLEAR_EVENT,
+                            exch_ts: data.transaction_time * 1_000_000,
+                            local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                            order_id: 0,
+                            px: 0.0,
+                            qty: 0.0,
+                            ival: 0,
+                            fval: 0.0,
+                        },
+                    }))
+                    .unwrap();
+            }
+
+            for (px, qty) in bids {
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                        symbol: symbol.clone(),
+                        event: Event {
+                            ev: LOCAL_BID_DEPTH_EVENT,
+                            exch_ts: data.transaction_time * 1_000_000,
+                            local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                            order_id: 0,
+                            px,
+                            qty,
+                            ival: 0,
+                            fval: 0.0,
+                        },
+                    }))
+                    .unwrap();
+            }
+
+            for (px, qty) in asks {
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                        symbol: symbol.clone(),
+                        event: Event {
+                            ev: LOCAL_ASK_DEPTH_EVENT,
+                            exch_ts: data.transaction_time * 1_000_000,
+                            local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                            order_id: 0,
+                            px,
+                            qty,
+                            ival: 0,
+                            fval: 0.0,
+                        },
+                    }))
+                    .unwrap();
+            }
+
+            ev_tx.send(PublishEvent::BatchEnd(TO_ALL)).unwrap();
+        }
+        Err(error) => {
+            error!(?error, "Couldn't parse Depth response.");
+        }
+    }
+}
@@ -7,15 +7,19 @@ mod user_data_stream;
 use std::{
     collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use hftbacktest::{
     prelude::get_precision,
-    types::{ErrorKind, LiveError, LiveEvent, Order, Status, Value},
+    types::{DepthLevel, ErrorKind, LiveError, LiveEvent, Order, Status, Value},
 };
 use serde::Deserialize;
 use thiserror::Error;
-use tokio::sync::{broadcast, broadcast::Sender, mpsc::UnboundedSender};
+use tokio::{
+    sync::{broadcast, broadcast::Sender, mpsc::UnboundedSender},
+    time,
+};
 use tokio_tungstenite::tungstenite;
 use tracing::{error, warn};
 
@@ -44,6 +48,8 @@ pub enum BinanceFuturesError {
     ReqError(#[from] reqwest::Error),
     #[error("OrderError: {code} - {msg})")]
     OrderError { code: i64, msg: String },
+    #[error("AuthError: {code} - {msg}")]
+    AuthError { code: i64, msg: String },
     #[error("PrefixUnmatched")]
     PrefixUnmatched,
     #[error("OrderNotFound")]
@@ -54,6 +60,16 @@ pub enum BinanceFuturesError {
     Config(#[from] toml::de::Error),
 }
 
+impl BinanceFuturesError {
+    /// Returns `true` if the error means that the user data stream is no longer authenticated.
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(
+            self,
+            BinanceFuturesError::AuthError { .. } | BinanceFuturesError::ListenKeyExpired
+        )
+    }
+}
+
 impl From<BinanceFuturesError> for Value {
     fn from(value: BinanceFuturesError) -> Value {
         match value {
@@ -73,6 +89,12 @@ impl From<BinanceFuturesError> for Value {
                 map.insert("msg".to_string(), Value::String(msg));
                 map
             }),
+            BinanceFuturesError::AuthError { code, msg } => Value::Map({
+                let mut map = HashMap::new();
+                map.insert("code".to_string(), Value::Int(code));
+                map.insert("msg".to_string(), Value::String(msg));
+                map
+            }),
             BinanceFuturesError::Tunstenite(error) => Value::String(format!("{error}")),
             BinanceFuturesError::ListenKeyExpired => Value::String(value.to_string()),
             BinanceFuturesError::ConnectionInterrupted => Value::String(value.to_string()),
@@ -94,10 +116,17 @@ pub struct Config {
     api_key: String,
     #[serde(default)]
     secret: String,
+    /// The interval in milliseconds at which the open orders' status is polled via REST to fill
+    /// in any order updates missed by the user data stream. `0` disables the polling.
+    #[serde(default)]
+    order_poll_interval_ms: u64,
 }
 
 type SharedSymbolSet = Arc<Mutex<HashSet<String>>>;
 
+/// The finest market depth level requested for each symbol.
+type SharedDepthLevels = Arc<Mutex<HashMap<String, DepthLevel>>>;
+
 /// A connector for Binance USD-m Futures.
 pub struct BinanceFutures {
     config: Config,
@@ -105,13 +134,20 @@ pub struct BinanceFutures {
     order_manager: SharedOrderManager,
     client: BinanceFuturesClient,
     symbol_tx: Sender<String>,
+    depth_levels: SharedDepthLevels,
+    /// Notifies the market data stream of the symbols whose depth level has changed.
+    depth_tx: Sender<String>,
+    tick_sizes: HashMap<String, f64>,
 }
 
 impl BinanceFutures {
     pub fn connect_market_data_stream(&mut self, ev_tx: UnboundedSender<PublishEvent>) {
         let base_url = self.config.stream_url.clone();
         let client = self.client.clone();
+        let symbols = self.symbols.clone();
         let symbol_tx = self.symbol_tx.clone();
+        let depth_levels = self.depth_levels.clone();
+        let depth_tx = self.depth_tx.clone();
 
         tokio::spawn(async move {
             let _ = Retry::new(ExponentialBackoff::default())
@@ -132,7 +168,10 @@ impl BinanceFutures {
                     let mut stream = market_data_stream::MarketDataStream::new(
                         client.clone(),
                         ev_tx.clone(),
+                        symbols.clone(),
                         symbol_tx.subscribe(),
+                        depth_levels.clone(),
+                        depth_tx.subscribe(),
                     );
                     stream.connect(&base_url).await?;
                     Ok(())
@@ -155,9 +194,15 @@ impl BinanceFutures {
                         ?error,
                         "An error occurred in the user data stream connection."
                     );
+                    // The user data stream is reconnected with a new listen key.
+                    let kind = if error.is_auth_failure() {
+                        ErrorKind::AuthFailure
+                    } else {
+                        ErrorKind::ConnectionInterrupted
+                    };
                     ev_tx
                         .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
-                            ErrorKind::ConnectionInterrupted,
+                            kind,
                             error.into(),
                         ))))
                         .unwrap();
@@ -180,6 +225,41 @@ impl BinanceFutures {
                 .await;
         });
     }
+
+    pub fn poll_order_status(&self, ev_tx: UnboundedSender<PublishEvent>) {
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+        let poll_interval = Duration::from_millis(self.config.order_poll_interval_ms);
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(poll_interval);
+            loop {
+                interval.tick().await;
+                let open_orders = order_manager.lock().unwrap().open_orders();
+                for (client_order_id, symbol) in open_orders {
+                    match client.get_order(&client_order_id, &symbol).await {
+                        Ok(resp) => {
+                            if let Some(order) = order_manager
+                                .lock()
+                                .unwrap()
+                                .update_from_poll(&client_order_id, &resp)
+                            {
+                                ev_tx
+                                    .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                                        symbol,
+                                        order,
+                                    }))
+                                    .unwrap();
+                            }
+                        }
+                        Err(error) => {
+                            error!(?error, %client_order_id, "Couldn't poll the order status.");
+                        }
+                    }
+                }
+            }
+        });
+    }
 }
 
 impl ConnectorBuilder for BinanceFutures {
@@ -191,6 +271,7 @@ impl ConnectorBuilder for BinanceFutures {
         let order_manager = Arc::new(Mutex::new(OrderManager::new(&config.order_prefix)));
         let client = BinanceFuturesClient::new(&config.api_url, &config.api_key, &config.secret);
         let (symbol_tx, _) = broadcast::channel(500);
+        let (depth_tx, _) = broadcast::channel(500);
 
         Ok(BinanceFutures {
             config,
@@ -198,21 +279,45 @@ impl ConnectorBuilder for BinanceFutures {
             order_manager,
             client,
             symbol_tx,
+            depth_levels: Default::default(),
+            depth_tx,
+            tick_sizes: Default::default(),
         })
     }
 }
 
 impl Connector for BinanceFutures {
-    fn register(&mut self, symbol: String) {
+    fn register(&mut self, symbol: String, tick_size: f64, lot_size: f64) {
+        self.register_with_depth(symbol, tick_size, lot_size, DepthLevel::Full);
+    }
+
+    fn register_with_depth(
+        &mut self,
+        symbol: String,
+        tick_size: f64,
+        _lot_size: f64,
+        depth: DepthLevel,
+    ) {
         // Binance futures symbols must be lowercase to subscribe to the WebSocket stream.
         if symbol.to_lowercase() != symbol {
             error!("Binance Futures symbol must be lowercase.");
         }
         let symbol = symbol.to_lowercase();
+        self.tick_sizes.insert(symbol.clone(), tick_size);
+        let mut depth_levels = self.depth_levels.lock().unwrap();
         let mut symbols = self.symbols.lock().unwrap();
         if !symbols.contains(&symbol) {
+            depth_levels.insert(symbol.clone(), depth);
             symbols.insert(symbol.clone());
             self.symbol_tx.send(symbol).unwrap();
+        } else if depth_levels
+            .get(&symbol)
+            .is_some_and(|level| *level < depth)
+        {
+            // Another bot needs a finer market depth. The symbol isn't sent through `symbol_tx`,
+            // since it would cancel the open orders.
+            depth_levels.insert(symbol.clone(), depth);
+            let _ = self.depth_tx.send(symbol);
         }
     }
 
@@ -225,6 +330,9 @@ impl Connector for BinanceFutures {
         // Connects to the user stream only if the API key and secret are provided.
         if !self.config.api_key.is_empty() && !self.config.secret.is_empty() {
             self.connect_user_data_stream(ev_tx.clone());
+            if self.config.order_poll_interval_ms > 0 {
+                self.poll_order_status(ev_tx.clone());
+            }
         }
     }
 
@@ -250,6 +358,8 @@ impl Connector for BinanceFutures {
                             order.qty,
                             order.order_type,
                             order.time_in_force,
+                            order.reduce_only,
+                            order.position_side,
                         )
                         .await;
                     match result {
@@ -360,4 +470,127 @@ impl Connector for BinanceFutures {
             }
         });
     }
+
+    fn resync(&self, symbol: String, tx: UnboundedSender<PublishEvent>) {
+        let client = self.client.clone();
+
+        tokio::spawn(async move {
+            match client.get_depth(&symbol).await {
+                Ok(depth) => {
+                    market_data_stream::publish_snapshot(&tx, symbol, depth, true);
+                }
+                Err(error) => {
+                    error!(?error, %symbol, "Couldn't get the market depth via REST.");
+                }
+            }
+        });
+    }
+
+    fn report_external_orders(&self, symbol: String, tx: UnboundedSender<PublishEvent>) {
+        if self.config.api_key.is_empty() || self.config.secret.is_empty() {
+            return;
+        }
+        let Some(tick_size) = self.tick_sizes.get(&symbol).copied() else {
+            return;
+        };
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+
+        tokio::spawn(async move {
+            match client.get_open_orders(&symbol).await {
+                Ok(orders) => {
+                    for resp in orders {
+                        if order_manager
+                            .lock()
+                            .unwrap()
+                            .contains(&resp.client_order_id)
+                        {
+                            continue;
+                        }
+                        let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::ExternalOrder {
+                            symbol: symbol.clone(),
+                            order: resp.to_order(0, tick_size),
+                            exch_order_id: resp.client_order_id,
+                        }));
+                    }
+                }
+                Err(error) => {
+                    error!(?error, %symbol, "Couldn't get the open orders via REST.");
+                }
+            }
+        });
+    }
+
+    fn adopt_order(
+        &self,
+        symbol: String,
+        exch_order_id: String,
+        order: Order,
+        tx: UnboundedSender<PublishEvent>,
+    ) {
+        if !self
+            .order_manager
+            .lock()
+            .unwrap()
+            .adopt(symbol, exch_order_id, order)
+        {
+            tx.send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                ErrorKind::OrderError,
+                BinanceFuturesError::InvalidRequest.into(),
+            ))))
+            .unwrap();
+        }
+    }
+
+    fn cancel_external(
+        &self,
+        symbol: String,
+        exch_order_id: String,
+        tx: UnboundedSender<PublishEvent>,
+    ) {
+        let tick_size = self.tick_sizes.get(&symbol).copied().unwrap_or(1.0);
+        let client = self.client.clone();
+
+        tokio::spawn(async move {
+            match client.cancel_order(&exch_order_id, &symbol).await {
+                Ok(resp) => {
+                    tx.send(PublishEvent::LiveEvent(LiveEvent::ExternalOrder {
+                        symbol,
+                        exch_order_id,
+                        order: resp.to_order(0, tick_size),
+                    }))
+                    .unwrap();
+                }
+                Err(error) => {
+                    tx.send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.into(),
+                    ))))
+                    .unwrap();
+                }
+            }
+        });
+    }
+
+    fn query_instrument_info(&self, symbol: String, tx: UnboundedSender<PublishEvent>) {
+        let client = self.client.clone();
+
+        tokio::spawn(async move {
+            let info = match client.get_exchange_info().await {
+                Ok(exchange_info) => exchange_info
+                    .symbols
+                    .iter()
+                    .find(|info| info.symbol == symbol)
+                    .map(|info| info.instrument_info()),
+                Err(error) => {
+                    error!(?error, %symbol, "Couldn't get the exchange information via REST.");
+                    None
+                }
+            };
+            let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::InstrumentInfo {
+                symbol,
+                info,
+            }));
+        });
+    }
 }
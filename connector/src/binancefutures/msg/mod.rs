@@ -1,4 +1,4 @@
-use hftbacktest::types::{OrdType, Side, Status, TimeInForce};
+use hftbacktest::types::{OrdType, PositionSide, Side, Status, TimeInForce};
 use serde::{
     de::{Error, Unexpected},
     Deserialize,
@@ -22,6 +22,22 @@ where
     }
 }
 
+fn from_str_to_position_side<'de, D>(deserializer: D) -> Result<PositionSide, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    match s {
+        "BOTH" => Ok(PositionSide::Both),
+        "LONG" => Ok(PositionSide::Long),
+        "SHORT" => Ok(PositionSide::Short),
+        s => Err(Error::invalid_value(
+            Unexpected::Other(s),
+            &"BOTH,LONG,SHORT",
+        )),
+    }
+}
+
 fn from_str_to_status<'de, D>(deserializer: D) -> Result<Status, D::Error>
 where
     D: Deserializer<'de>,
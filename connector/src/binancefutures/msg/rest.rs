@@ -1,7 +1,23 @@
-use hftbacktest::types::{OrdType, Side, Status, TimeInForce};
+use hftbacktest::types::{
+    InstrumentInfo,
+    OrdType,
+    Order,
+    OrderId,
+    PositionSide,
+    Side,
+    Status,
+    TimeInForce,
+};
 use serde::Deserialize;
 
-use super::{from_str_to_side, from_str_to_status, from_str_to_tif, from_str_to_type};
+use super::{
+    from_str_to_position_side,
+    from_str_to_side,
+    from_str_to_status,
+    from_str_to_tif,
+    from_str_to_type,
+    stream::ListenKey,
+};
 use crate::utils::{from_str_to_f64, from_str_to_f64_opt, to_lowercase};
 
 #[derive(Deserialize, Debug)]
@@ -15,7 +31,9 @@ pub enum OrderResponseResult {
 pub struct OrderResponse {
     #[serde(rename = "clientOrderId")]
     pub client_order_id: String,
+    /// Query Order responses don't have this field
     #[serde(rename = "cumQty")]
+    #[serde(default)]
     #[serde(deserialize_with = "from_str_to_f64")]
     pub cum_qty: f64,
     /// New Order and Cancel Order responses only field
@@ -48,7 +66,8 @@ pub struct OrderResponse {
     #[serde(deserialize_with = "from_str_to_side")]
     pub side: Side,
     #[serde(rename = "positionSide")]
-    pub position_side: String,
+    #[serde(deserialize_with = "from_str_to_position_side")]
+    pub position_side: PositionSide,
     #[serde(deserialize_with = "from_str_to_status")]
     pub status: Status,
     #[serde(rename = "stopPrice")]
@@ -96,6 +115,34 @@ pub struct OrderResponse {
     pub good_till_date: i64,
 }
 
+impl OrderResponse {
+    /// Converts the response to an [`Order`] with the given order ID.
+    pub fn to_order(&self, order_id: OrderId, tick_size: f64) -> Order {
+        let mut order = Order::new(
+            order_id,
+            (self.price / tick_size).round() as i64,
+            tick_size,
+            self.orig_qty,
+            self.side,
+            self.ty,
+            self.time_in_force,
+        );
+        order.leaves_qty = self.orig_qty - self.executed_qty;
+        order.status = self.status;
+        order.reduce_only = self.reduce_only;
+        order.position_side = self.position_side;
+        order.exch_timestamp = self.update_time * 1_000_000;
+        order
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ListenKeyResult {
+    Ok(ListenKey),
+    Err(ErrorResponse),
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ErrorResponse {
     pub code: i64,
@@ -137,7 +184,8 @@ pub struct PositionInformationV2 {
     #[serde(rename = "unRealizedProfit")]
     pub unrealized_pnl: String,
     #[serde(rename = "positionSide")]
-    pub position_side: String,
+    #[serde(deserialize_with = "from_str_to_position_side")]
+    pub position_side: PositionSide,
     #[serde(rename = "updateTime")]
     pub update_time: i64,
 }
@@ -153,3 +201,67 @@ pub struct Depth {
     pub bids: Vec<(String, String)>,
     pub asks: Vec<(String, String)>,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct ExchangeInfo {
+    pub symbols: Vec<SymbolInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SymbolInfo {
+    #[serde(deserialize_with = "to_lowercase")]
+    pub symbol: String,
+    #[serde(rename = "pricePrecision")]
+    pub price_precision: u32,
+    #[serde(rename = "quantityPrecision")]
+    pub quantity_precision: u32,
+    pub filters: Vec<SymbolFilter>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "filterType")]
+pub enum SymbolFilter {
+    #[serde(rename = "PRICE_FILTER")]
+    Price {
+        #[serde(rename = "tickSize")]
+        #[serde(deserialize_with = "from_str_to_f64")]
+        tick_size: f64,
+    },
+    #[serde(rename = "LOT_SIZE")]
+    LotSize {
+        #[serde(rename = "stepSize")]
+        #[serde(deserialize_with = "from_str_to_f64")]
+        step_size: f64,
+    },
+    #[serde(rename = "MIN_NOTIONAL")]
+    MinNotional {
+        #[serde(deserialize_with = "from_str_to_f64")]
+        notional: f64,
+    },
+    #[serde(other)]
+    Other,
+}
+
+impl SymbolInfo {
+    pub fn instrument_info(&self) -> InstrumentInfo {
+        let mut tick_size = 0.0;
+        let mut lot_size = 0.0;
+        let mut min_notional = 0.0;
+        for filter in &self.filters {
+            match filter {
+                SymbolFilter::Price { tick_size: value } => tick_size = *value,
+                SymbolFilter::LotSize { step_size } => lot_size = *step_size,
+                SymbolFilter::MinNotional { notional } => min_notional = *notional,
+                SymbolFilter::Other => {}
+            }
+        }
+        InstrumentInfo {
+            tick_size,
+            lot_size,
+            min_notional,
+            price_precision: self.price_precision,
+            qty_precision: self.quantity_precision,
+            contract_multiplier: 1.0,
+        }
+    }
+}
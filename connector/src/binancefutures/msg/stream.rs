@@ -1,7 +1,13 @@
-use hftbacktest::types::{OrdType, Side, Status, TimeInForce};
+use hftbacktest::types::{OrdType, PositionSide, Side, Status, TimeInForce};
 use serde::Deserialize;
 
-use super::{from_str_to_side, from_str_to_status, from_str_to_tif, from_str_to_type};
+use super::{
+    from_str_to_position_side,
+    from_str_to_side,
+    from_str_to_status,
+    from_str_to_tif,
+    from_str_to_type,
+};
 use crate::utils::{from_str_to_f64, to_lowercase};
 
 #[derive(Deserialize, Debug)]
@@ -24,6 +30,12 @@ pub enum EventStream {
     DepthUpdate(Depth),
     #[serde(rename = "trade")]
     Trade(Trade),
+    #[serde(rename = "bookTicker")]
+    BookTicker(BookTicker),
+    #[serde(rename = "markPriceUpdate")]
+    MarkPriceUpdate(MarkPriceUpdate),
+    #[serde(rename = "forceOrder")]
+    ForceOrder(ForceOrder),
     #[serde(rename = "ORDER_TRADE_UPDATE")]
     OrderTradeUpdate(OrderTradeUpdate),
     #[serde(rename = "ACCOUNT_UPDATE")]
@@ -56,6 +68,23 @@ pub struct Depth {
     pub asks: Vec<(String, String)>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct BookTicker {
+    #[serde(rename = "T")]
+    pub transaction_time: i64,
+    #[serde(rename = "s")]
+    #[serde(deserialize_with = "to_lowercase")]
+    pub symbol: String,
+    #[serde(rename = "b")]
+    pub bid_price: String,
+    #[serde(rename = "B")]
+    pub bid_qty: String,
+    #[serde(rename = "a")]
+    pub ask_price: String,
+    #[serde(rename = "A")]
+    pub ask_qty: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Trade {
     #[serde(rename = "T")]
@@ -77,6 +106,52 @@ pub struct Trade {
     pub is_the_buyer_the_market_maker: bool,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct MarkPriceUpdate {
+    #[serde(rename = "E")]
+    pub event_time: i64,
+    #[serde(rename = "s")]
+    #[serde(deserialize_with = "to_lowercase")]
+    pub symbol: String,
+    #[serde(rename = "p")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub mark_price: f64,
+    #[serde(rename = "i")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub index_price: f64,
+    #[serde(rename = "r")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub funding_rate: f64,
+    #[serde(rename = "T")]
+    pub next_funding_time: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ForceOrder {
+    #[serde(rename = "E")]
+    pub event_time: i64,
+    #[serde(rename = "o")]
+    pub order: LiquidationOrder,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LiquidationOrder {
+    #[serde(rename = "s")]
+    #[serde(deserialize_with = "to_lowercase")]
+    pub symbol: String,
+    #[serde(rename = "S")]
+    #[serde(deserialize_with = "from_str_to_side")]
+    pub side: Side,
+    #[serde(rename = "ap")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub avg_price: f64,
+    #[serde(rename = "z")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub executed_accumulated_qty: f64,
+    #[serde(rename = "T")]
+    pub transaction_time: i64,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct AccountUpdate {
     #[serde(rename = "E")]
@@ -136,7 +211,8 @@ pub struct Position {
     #[serde(rename = "iw")]
     pub isolated_wallet: Option<String>,
     #[serde(rename = "ps")]
-    pub position_side: String,
+    #[serde(deserialize_with = "from_str_to_position_side")]
+    pub position_side: PositionSide,
 }
 
 #[derive(Deserialize, Debug)]
@@ -195,8 +271,9 @@ pub struct Order {
     pub last_filled_price: f64,
     // #[serde(rename = "N")]
     // pub commission_asset: Option<String>,
-    // #[serde(rename = "n")]
-    // pub commission: Option<String>,
+    #[serde(rename = "n")]
+    #[serde(default)]
+    pub commission: Option<String>,
     #[serde(rename = "T")]
     pub order_trade_time: i64,
     #[serde(rename = "t")]
@@ -205,8 +282,8 @@ pub struct Order {
     // pub bid_notional: String,
     // #[serde(rename = "a")]
     // pub ask_notional: String,
-    // #[serde(rename = "m")]
-    // pub is_maker_side: bool,
+    #[serde(rename = "m")]
+    pub is_maker_side: bool,
     // #[serde(rename = "R")]
     // pub is_reduce_only: bool,
     // #[serde(rename = "wt")]
@@ -60,7 +60,10 @@ impl OrderManager {
         &mut self,
         resp: &OrderTradeUpdate,
     ) -> Result<Option<Order>, BinanceFuturesError> {
-        if !resp.order.client_order_id.starts_with(&self.prefix) {
+        // The adopted external orders don't have the prefix.
+        if !resp.order.client_order_id.starts_with(&self.prefix)
+            && !self.orders.contains_key(&resp.order.client_order_id)
+        {
             return Err(BinanceFuturesError::PrefixUnmatched);
         }
         let order_ext = self
@@ -79,6 +82,9 @@ impl OrderManager {
             order_ext.order.status = resp.order.order_status;
             order_ext.order.exec_qty = resp.order.order_last_filled_qty;
             order_ext.order.order_type = resp.order.order_type;
+            if resp.order.execution_type == "TRADE" {
+                order_ext.order.maker = resp.order.is_maker_side;
+            }
         }
 
         let result = if already_removed {
@@ -238,6 +244,66 @@ impl OrderManager {
         result
     }
 
+    /// Updates the order with the order status polled via REST, which substitutes for the
+    /// WebSocket stream in case its updates are missed. Returns the order only if the polled
+    /// status is newer than the known one.
+    pub fn update_from_poll(
+        &mut self,
+        client_order_id: &ClientOrderId,
+        resp: &OrderResponse,
+    ) -> Option<Order> {
+        let order_ext = self.orders.get_mut(client_order_id)?;
+        if order_ext.removed_by_ws
+            || order_ext.removed_by_rest
+            || resp.update_time * 1_000_000 <= order_ext.order.exch_timestamp
+        {
+            return None;
+        }
+
+        let prev_filled_qty = order_ext.order.qty - order_ext.order.leaves_qty;
+        order_ext.order.qty = resp.orig_qty;
+        order_ext.order.leaves_qty = resp.orig_qty - resp.executed_qty;
+        order_ext.order.side = resp.side;
+        order_ext.order.time_in_force = resp.time_in_force;
+        order_ext.order.exch_timestamp = resp.update_time * 1_000_000;
+        order_ext.order.status = resp.status;
+        // Only reports the quantity filled since the last known update.
+        order_ext.order.exec_qty = (resp.executed_qty - prev_filled_qty).max(0.0);
+        order_ext.order.order_type = resp.ty;
+        order_ext.order.req = Status::None;
+
+        let result = Some(order_ext.order.clone());
+
+        if order_ext.order.status != Status::New
+            && order_ext.order.status != Status::PartiallyFilled
+        {
+            // The deletion is considered confirmed by both channels, since the missed update
+            // might never be delivered. The order is kept until the gc in case it arrives later.
+            order_ext.removed_by_ws = true;
+            order_ext.removed_by_rest = true;
+            self.order_id_map.remove(&RefSymbolOrderId::new(
+                &order_ext.symbol,
+                order_ext.order.order_id,
+            ));
+        }
+
+        result
+    }
+
+    /// Returns the client order IDs and the symbols of the open orders to poll the status of.
+    pub fn open_orders(&self) -> Vec<(ClientOrderId, String)> {
+        self.orders
+            .iter()
+            .filter(|(_, order_ext)| {
+                !order_ext.removed_by_ws
+                    && !order_ext.removed_by_rest
+                    && order_ext.order.req == Status::None
+                    && order_ext.order.active()
+            })
+            .map(|(client_order_id, order_ext)| (client_order_id.clone(), order_ext.symbol.clone()))
+            .collect()
+    }
+
     pub fn prepare_client_order_id(&mut self, symbol: String, order: Order) -> Option<String> {
         let symbol_order_id = SymbolOrderId::new(symbol.clone(), order.order_id);
         if self.order_id_map.contains_key(&symbol_order_id) {
@@ -263,6 +329,34 @@ impl OrderManager {
         Some(client_order_id)
     }
 
+    /// Returns whether the order with the client order ID is managed by this order manager.
+    pub fn contains(&self, client_order_id: &str) -> bool {
+        self.orders.contains_key(client_order_id)
+    }
+
+    /// Takes over the external order with the client order ID so that its updates are handled as
+    /// the bot's order. Returns `false` if either ID is already in use.
+    pub fn adopt(&mut self, symbol: String, client_order_id: String, order: Order) -> bool {
+        let symbol_order_id = SymbolOrderId::new(symbol.clone(), order.order_id);
+        if self.order_id_map.contains_key(&symbol_order_id)
+            || self.orders.contains_key(&client_order_id)
+        {
+            return false;
+        }
+        self.order_id_map
+            .insert(symbol_order_id, client_order_id.clone());
+        self.orders.insert(
+            client_order_id,
+            OrderExt {
+                symbol,
+                order,
+                removed_by_ws: false,
+                removed_by_rest: false,
+            },
+        );
+        true
+    }
+
     pub fn get_client_order_id(&self, symbol: &str, order_id: OrderId) -> Option<String> {
         self.order_id_map
             .get(&RefSymbolOrderId::new(symbol, order_id))
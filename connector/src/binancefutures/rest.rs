@@ -1,14 +1,11 @@
 use chrono::Utc;
-use hftbacktest::types::{OrdType, Side, TimeInForce};
+use hftbacktest::types::{OrdType, PositionSide, Side, TimeInForce};
 use serde::Deserialize;
 
 use super::msg::{rest, rest::PositionInformationV2};
 use crate::{
     binancefutures::{
-        msg::{
-            rest::{OrderResponse, OrderResponseResult},
-            stream::ListenKey,
-        },
+        msg::rest::{ErrorResponse, ListenKeyResult, OrderResponse, OrderResponseResult},
         BinanceFuturesError,
     },
     utils::sign_hmac_sha256,
@@ -147,14 +144,27 @@ impl BinanceFuturesClient {
         Ok(resp)
     }
 
-    pub async fn start_user_data_stream(&self) -> Result<String, reqwest::Error> {
-        let resp: Result<ListenKey, _> = self.post("/fapi/v1/listenKey", String::new()).await;
-        resp.map(|v| v.listen_key)
+    pub async fn start_user_data_stream(&self) -> Result<String, BinanceFuturesError> {
+        let resp: ListenKeyResult = self.post("/fapi/v1/listenKey", String::new()).await?;
+        match resp {
+            ListenKeyResult::Ok(resp) => Ok(resp.listen_key),
+            ListenKeyResult::Err(resp) => Err(BinanceFuturesError::AuthError {
+                code: resp.code,
+                msg: resp.msg,
+            }),
+        }
     }
 
-    pub async fn keepalive_user_data_stream(&self) -> Result<(), reqwest::Error> {
-        let _: serde_json::Value = self.put("/fapi/v1/listenKey", String::new()).await?;
-        Ok(())
+    pub async fn keepalive_user_data_stream(&self) -> Result<(), BinanceFuturesError> {
+        let resp: serde_json::Value = self.put("/fapi/v1/listenKey", String::new()).await?;
+        // The listen key that has expired or is invalid can no longer be extended.
+        match ErrorResponse::deserialize(&resp) {
+            Ok(resp) => Err(BinanceFuturesError::AuthError {
+                code: resp.code,
+                msg: resp.msg,
+            }),
+            Err(_) => Ok(()),
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -168,6 +178,8 @@ impl BinanceFuturesClient {
         qty: f64,
         order_type: OrdType,
         time_in_force: TimeInForce,
+        reduce_only: bool,
+        position_side: PositionSide,
     ) -> Result<OrderResponse, BinanceFuturesError> {
         let mut body = String::with_capacity(200);
         body.push_str("newClientOrderId=");
@@ -184,6 +196,14 @@ impl BinanceFuturesClient {
         body.push_str(order_type.as_ref());
         body.push_str("&timeInForce=");
         body.push_str(time_in_force.as_ref());
+        // In hedge mode, the position side determines whether the order reduces the position, and
+        // `reduceOnly` cannot be sent.
+        if position_side != PositionSide::Both {
+            body.push_str("&positionSide=");
+            body.push_str(position_side.as_ref());
+        } else if reduce_only {
+            body.push_str("&reduceOnly=true");
+        }
 
         let resp: OrderResponseResult = self.post("/fapi/v1/order", body).await?;
         match resp {
@@ -291,6 +311,37 @@ impl BinanceFuturesClient {
         }
     }
 
+    pub async fn get_order(
+        &self,
+        client_order_id: &str,
+        symbol: &str,
+    ) -> Result<OrderResponse, BinanceFuturesError> {
+        let mut query = String::with_capacity(100);
+        query.push_str("symbol=");
+        query.push_str(symbol);
+        query.push_str("&origClientOrderId=");
+        query.push_str(client_order_id);
+
+        let resp: OrderResponseResult = self.get("/fapi/v1/order", query).await?;
+        match resp {
+            OrderResponseResult::Ok(resp) => Ok(resp),
+            OrderResponseResult::Err(resp) => Err(BinanceFuturesError::OrderError {
+                code: resp.code,
+                msg: resp.msg,
+            }),
+        }
+    }
+
+    pub async fn get_open_orders(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<OrderResponse>, reqwest::Error> {
+        let resp: Vec<OrderResponse> = self
+            .get("/fapi/v1/openOrders", format!("symbol={}", symbol))
+            .await?;
+        Ok(resp)
+    }
+
     pub async fn cancel_orders(
         &self,
         symbol: &str,
@@ -346,4 +397,11 @@ impl BinanceFuturesClient {
             .await?;
         Ok(resp)
     }
+
+    pub async fn get_exchange_info(&self) -> Result<rest::ExchangeInfo, reqwest::Error> {
+        let resp: rest::ExchangeInfo = self
+            .get_noauth("/fapi/v1/exchangeInfo", String::new())
+            .await?;
+        Ok(resp)
+    }
 }
@@ -6,7 +6,7 @@ use tokio::{
     select,
     sync::{
         broadcast::{error::RecvError, Receiver},
-        mpsc::UnboundedSender,
+        mpsc::{unbounded_channel, UnboundedSender},
     },
     time,
 };
@@ -27,6 +27,12 @@ use crate::{
     connector::PublishEvent,
 };
 
+/// The interval at which the listen key is extended. It expires 60 minutes after the last
+/// extension.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(60 * 30);
+/// The interval at which the failed extension of the listen key is retried.
+const KEEPALIVE_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct UserDataStream {
     symbols: SharedSymbolSet,
     client: BinanceFuturesClient,
@@ -53,12 +59,18 @@ impl UserDataStream {
     }
 
     pub async fn get_listen_key(&self) -> Result<String, BinanceFuturesError> {
-        Ok(self.client.start_user_data_stream().await?)
+        self.client.start_user_data_stream().await
     }
 
     fn process_message(&self, stream: EventStream) -> Result<(), BinanceFuturesError> {
         match stream {
-            EventStream::DepthUpdate(_) | EventStream::Trade(_) => unreachable!(),
+            EventStream::DepthUpdate(_)
+            | EventStream::Trade(_)
+            | EventStream::BookTicker(_)
+            | EventStream::MarkPriceUpdate(_)
+            | EventStream::ForceOrder(_) => {
+                unreachable!()
+            }
             EventStream::ListenKeyExpired(_) => {
                 return Err(BinanceFuturesError::ListenKeyExpired);
             }
@@ -67,6 +79,7 @@ impl UserDataStream {
                     self.ev_tx
                         .send(PublishEvent::LiveEvent(LiveEvent::Position {
                             symbol: position.symbol,
+                            side: position.position_side,
                             qty: position.position_amount,
                             exch_ts: data.transaction_time * 1_000_000,
                         }))
@@ -74,8 +87,57 @@ impl UserDataStream {
                 }
             }
             EventStream::OrderTradeUpdate(data) => {
+                // The orders placed by the exchange to liquidate or auto-deleverage the position
+                // are reported as liquidations instead of the order updates.
+                if is_forced_close(&data.order.client_order_id) {
+                    if data.order.order_last_filled_qty > 0.0 {
+                        let liquidation = Liquidation {
+                            side: data.order.side,
+                            price: data.order.last_filled_price,
+                            qty: data.order.order_last_filled_qty,
+                            fee: data
+                                .order
+                                .commission
+                                .as_deref()
+                                .and_then(|commission| commission.parse().ok())
+                                .unwrap_or(0.0),
+                            exch_ts: data.order.order_trade_time * 1_000_000,
+                        };
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Liquidation {
+                                symbol: data.order.symbol,
+                                liquidation,
+                            }))
+                            .unwrap();
+                    }
+                    return Ok(());
+                }
                 match self.order_manager.lock().unwrap().update_from_ws(&data) {
                     Ok(Some(order)) => {
+                        // Reports the execution ahead of the order update.
+                        if data.order.execution_type == "TRADE" {
+                            let fill = Fill {
+                                order_id: order.order_id,
+                                trade_id: data.order.trade_id.to_string(),
+                                side: data.order.side,
+                                price: data.order.last_filled_price,
+                                qty: data.order.order_last_filled_qty,
+                                fee: data
+                                    .order
+                                    .commission
+                                    .as_deref()
+                                    .and_then(|commission| commission.parse().ok())
+                                    .unwrap_or(0.0),
+                                maker: data.order.is_maker_side,
+                                exch_ts: data.order.order_trade_time * 1_000_000,
+                            };
+                            self.ev_tx
+                                .send(PublishEvent::LiveEvent(LiveEvent::Fill {
+                                    symbol: data.order.symbol.clone(),
+                                    fill,
+                                }))
+                                .unwrap();
+                        }
                         self.ev_tx
                             .send(PublishEvent::LiveEvent(LiveEvent::Order {
                                 symbol: data.order.symbol,
@@ -106,7 +168,8 @@ impl UserDataStream {
         let request = url.into_client_request()?;
         let (ws_stream, _) = connect_async(request).await?;
         let (mut write, mut read) = ws_stream.split();
-        let mut interval = time::interval(Duration::from_secs(60 * 30));
+        let mut interval = time::interval(KEEPALIVE_INTERVAL);
+        let (keepalive_tx, mut keepalive_rx) = unbounded_channel();
 
         let symbols: HashSet<_> = self.symbols.lock().unwrap().iter().cloned().collect();
         let client = self.client.clone();
@@ -145,12 +208,25 @@ impl UserDataStream {
                         .unwrap()
                         .gc();
                     let client_ = self.client.clone();
+                    let keepalive_tx = keepalive_tx.clone();
                     tokio::spawn(async move {
-                        if let Err(error) = client_.keepalive_user_data_stream().await {
-                            error!(?error, "Failed keepalive user data stream.");
-                        }
+                        let _ = keepalive_tx.send(client_.keepalive_user_data_stream().await);
                     });
                 }
+                Some(result) = keepalive_rx.recv() => match result {
+                    Ok(()) => {}
+                    Err(error) if error.is_auth_failure() => {
+                        // Reconnects with a new listen key rather than waiting for the stream
+                        // to go silent.
+                        return Err(error);
+                    }
+                    Err(error) => {
+                        // The listen key is valid for 60 minutes, so it can be retried before it
+                        // expires.
+                        error!(?error, "Failed keepalive user data stream. Retries shortly.");
+                        interval.reset_after(KEEPALIVE_RETRY_INTERVAL);
+                    }
+                },
                 msg = self.symbol_rx.recv() => {
                     match msg {
                         Ok(symbol) => {
@@ -246,6 +322,7 @@ pub async fn get_position_information(
         ev_tx
             .send(PublishEvent::LiveEvent(LiveEvent::Position {
                 symbol: position.symbol,
+                side: position.position_side,
                 qty: position.position_amount,
                 exch_ts: position.update_time * 1_000_000,
             }))
@@ -255,6 +332,7 @@ pub async fn get_position_information(
         ev_tx
             .send(PublishEvent::LiveEvent(LiveEvent::Position {
                 symbol,
+                side: PositionSide::Both,
                 qty: 0.0,
                 exch_ts: 0,
             }))
@@ -262,3 +340,9 @@ pub async fn get_position_information(
     }
     Ok(())
 }
+
+/// Returns `true` if the order is placed by the exchange to liquidate the position, with the
+/// client order ID `autoclose-XXX`, or to auto-deleverage it, with `adl_autoclose`.
+fn is_forced_close(client_order_id: &str) -> bool {
+    client_order_id.starts_with("autoclose-") || client_order_id.starts_with("adl_autoclose")
+}
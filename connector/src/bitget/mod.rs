@@ -0,0 +1,379 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use hftbacktest::{
+    prelude::get_precision,
+    types::{ErrorKind, LiveEvent, OrdType, Order, PositionSide, Side, TimeInForce, Value},
+};
+use serde::Deserialize;
+use serde_json::json;
+use thiserror::Error;
+use tokio::sync::{broadcast, broadcast::Sender, mpsc::UnboundedSender};
+use tracing::error;
+
+use crate::{
+    bitget::{private_stream::PrivateStream, public_stream::PublicStream, rest::BitgetClient},
+    connector::{Connector, ConnectorBuilder, GetOrders, PublishEvent},
+    toolkit::{
+        feed::publish_error,
+        ordermanager::{ClientOrderManager, OrderManagerError, SharedClientOrderManager},
+        symbol::{SharedSymbolNormalizer, SymbolCase, SymbolNormalizer},
+        ws::WsError,
+    },
+    utils::{ExponentialBackoff, Retry},
+};
+
+#[allow(dead_code)]
+mod msg;
+mod private_stream;
+mod public_stream;
+mod rest;
+
+#[derive(Error, Debug)]
+pub enum BitgetError {
+    #[error("AuthError: {code} - {msg}")]
+    AuthError { code: i64, msg: String },
+    #[error("OrderError: {code} - {msg}")]
+    OrderError { code: i64, msg: String },
+    #[error("OrderManager: {0}")]
+    OrderManager(#[from] OrderManagerError),
+    #[error("InvalidArg: {0}")]
+    InvalidArg(&'static str),
+    #[error("Serde: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("Reqwest: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Ws: {0}")]
+    Ws(#[from] WsError),
+    #[error("Config: {0:?}")]
+    Config(#[from] toml::de::Error),
+}
+
+impl BitgetError {
+    /// Classifies the error code of the REST API.
+    pub fn from_code(code: String, msg: String) -> Self {
+        let code = code.parse::<i64>().unwrap_or(-1);
+        match code {
+            // Invalid API key, signature, timestamp or passphrase.
+            40001..=40012 | 40037 => BitgetError::AuthError { code, msg },
+            _ => BitgetError::OrderError { code, msg },
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            BitgetError::AuthError { code, msg } | BitgetError::OrderError { code, msg } => {
+                Value::Map({
+                    let mut map = HashMap::new();
+                    map.insert("code".to_string(), Value::Int(*code));
+                    map.insert("msg".to_string(), Value::String(msg.clone()));
+                    map
+                })
+            }
+            BitgetError::OrderManager(_) => Value::String(self.to_string()),
+            BitgetError::InvalidArg(_) => Value::String(self.to_string()),
+            BitgetError::Serde(_) => Value::String(self.to_string()),
+            BitgetError::Reqwest(_) => Value::String(self.to_string()),
+            BitgetError::Ws(_) => Value::String(self.to_string()),
+            BitgetError::Config(_) => Value::String(self.to_string()),
+        }
+    }
+}
+
+fn default_product_type() -> String {
+    "USDT-FUTURES".to_string()
+}
+
+fn default_margin_coin() -> String {
+    "USDT".to_string()
+}
+
+fn default_margin_mode() -> String {
+    "crossed".to_string()
+}
+
+#[derive(Deserialize)]
+pub struct Config {
+    public_url: String,
+    private_url: String,
+    rest_url: String,
+    api_key: String,
+    secret: String,
+    passphrase: String,
+    /// `USDT-FUTURES`, `USDC-FUTURES` or `COIN-FUTURES`.
+    #[serde(default = "default_product_type")]
+    product_type: String,
+    #[serde(default = "default_margin_coin")]
+    margin_coin: String,
+    /// `crossed` or `isolated`.
+    #[serde(default = "default_margin_mode")]
+    margin_mode: String,
+    #[serde(default)]
+    order_prefix: String,
+    /// The venue's symbols keyed by the bots' symbols, for the symbols that differ other than by
+    /// case.
+    #[serde(default)]
+    symbol_aliases: HashMap<String, String>,
+}
+
+/// A connector for Bitget futures in the one-way position mode.
+pub struct Bitget {
+    config: Config,
+    order_manager: SharedClientOrderManager,
+    symbols: SharedSymbolNormalizer,
+    client: BitgetClient,
+    symbol_tx: Sender<String>,
+    resync_tx: Sender<String>,
+}
+
+impl Bitget {
+    fn connect_public_stream(&self, ev_tx: UnboundedSender<PublishEvent>) {
+        let public_url = self.config.public_url.clone();
+        let product_type = self.config.product_type.clone();
+        let symbols = self.symbols.clone();
+        let symbol_tx = self.symbol_tx.clone();
+        let resync_tx = self.resync_tx.clone();
+
+        tokio::spawn(async move {
+            let _ = Retry::new(ExponentialBackoff::default())
+                .error_handler(|error: BitgetError| {
+                    error!(?error, "An error occurred in the public stream connection.");
+                    publish_error(&ev_tx, ErrorKind::ConnectionInterrupted, error.to_value());
+                    Ok(())
+                })
+                .retry(|| async {
+                    let mut stream = PublicStream::new(
+                        ev_tx.clone(),
+                        symbols.clone(),
+                        symbol_tx.subscribe(),
+                        resync_tx.subscribe(),
+                        product_type.clone(),
+                    );
+                    stream.connect(&public_url).await?;
+                    Ok(())
+                })
+                .await;
+        });
+    }
+
+    fn connect_private_stream(&self, ev_tx: UnboundedSender<PublishEvent>) {
+        let private_url = self.config.private_url.clone();
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+        let symbols = self.symbols.clone();
+        let symbol_tx = self.symbol_tx.clone();
+
+        tokio::spawn(async move {
+            let _ = Retry::new(ExponentialBackoff::default())
+                .error_handler(|error: BitgetError| {
+                    error!(
+                        ?error,
+                        "An error occurred in the private stream connection."
+                    );
+                    let kind = if matches!(error, BitgetError::AuthError { .. }) {
+                        ErrorKind::AuthFailure
+                    } else {
+                        ErrorKind::ConnectionInterrupted
+                    };
+                    publish_error(&ev_tx, kind, error.to_value());
+                    Ok(())
+                })
+                .retry(|| async {
+                    let mut stream = PrivateStream::new(
+                        client.clone(),
+                        ev_tx.clone(),
+                        order_manager.clone(),
+                        symbols.clone(),
+                        symbol_tx.subscribe(),
+                    );
+                    stream.connect(&private_url).await?;
+                    Ok(())
+                })
+                .await;
+        });
+    }
+
+    fn order_params(&self, symbol: &str, order: &Order) -> Result<serde_json::Value, BitgetError> {
+        let mut params = json!({
+            "symbol": symbol,
+            "marginMode": self.config.margin_mode,
+            "size": order.qty.to_string(),
+        });
+        params["side"] = match order.side {
+            Side::Buy => json!("buy"),
+            Side::Sell => json!("sell"),
+            Side::None | Side::Unsupported => return Err(BitgetError::InvalidArg("side")),
+        };
+        match order.order_type {
+            OrdType::Limit => {
+                params["orderType"] = json!("limit");
+                params["price"] = json!(format!(
+                    "{:.prec$}",
+                    order.price_tick as f64 * order.tick_size,
+                    prec = get_precision(order.tick_size)
+                ));
+            }
+            OrdType::Market => {
+                params["orderType"] = json!("market");
+            }
+            OrdType::Unsupported => return Err(BitgetError::InvalidArg("order_type")),
+        }
+        params["force"] = match order.time_in_force {
+            TimeInForce::GTC => json!("gtc"),
+            TimeInForce::GTX => json!("post_only"),
+            TimeInForce::FOK => json!("fok"),
+            TimeInForce::IOC => json!("ioc"),
+            TimeInForce::Unsupported => return Err(BitgetError::InvalidArg("time_in_force")),
+        };
+        if order.reduce_only {
+            params["reduceOnly"] = json!("YES");
+        }
+        // Only one-way mode is supported.
+        if order.position_side != PositionSide::Both {
+            return Err(BitgetError::InvalidArg("position_side"));
+        }
+        Ok(params)
+    }
+}
+
+impl ConnectorBuilder for Bitget {
+    type Error = BitgetError;
+
+    fn build_from(config: &str) -> Result<Self, Self::Error> {
+        let config: Config = toml::from_str(config)?;
+        if config.order_prefix.len() > 8 {
+            panic!("order prefix length should be not greater than 8.");
+        }
+        let (symbol_tx, _) = broadcast::channel(500);
+        let (resync_tx, _) = broadcast::channel(500);
+        let order_manager = Arc::new(Mutex::new(ClientOrderManager::new(
+            &config.order_prefix,
+            32,
+        )));
+        let symbols = Arc::new(Mutex::new(
+            SymbolNormalizer::new(SymbolCase::Upper).aliases(config.symbol_aliases.clone()),
+        ));
+        let client = BitgetClient::new(
+            &config.rest_url,
+            &config.api_key,
+            &config.secret,
+            &config.passphrase,
+            &config.product_type,
+            &config.margin_coin,
+        );
+        Ok(Bitget {
+            config,
+            order_manager,
+            symbols,
+            client,
+            symbol_tx,
+            resync_tx,
+        })
+    }
+}
+
+impl Connector for Bitget {
+    fn register(&mut self, symbol: String, _tick_size: f64, _lot_size: f64) {
+        if let Some(venue) = self.symbols.lock().unwrap().register(&symbol) {
+            self.symbol_tx.send(venue).unwrap();
+        }
+    }
+
+    fn order_manager(&self) -> Arc<Mutex<dyn GetOrders + Send + 'static>> {
+        self.order_manager.clone()
+    }
+
+    fn run(&mut self, ev_tx: UnboundedSender<PublishEvent>) {
+        self.connect_public_stream(ev_tx.clone());
+        self.connect_private_stream(ev_tx);
+    }
+
+    fn submit(&self, symbol: String, order: Order, ev_tx: UnboundedSender<PublishEvent>) {
+        let venue = self.symbols.lock().unwrap().to_venue(&symbol);
+        let result = self.order_params(&venue, &order).and_then(|mut params| {
+            let client_oid = self
+                .order_manager
+                .lock()
+                .unwrap()
+                .new_order(&symbol, order)?;
+            params["clientOid"] = json!(client_oid);
+            Ok((client_oid, params))
+        });
+        let (client_oid, params) = match result {
+            Ok(result) => result,
+            Err(error) => {
+                publish_error(&ev_tx, ErrorKind::OrderError, error.to_value());
+                return;
+            }
+        };
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+
+        tokio::spawn(async move {
+            // The order's status is updated by the orders channel once it's accepted.
+            if let Err(error) = client.place_order(params).await {
+                if let Ok(order) = order_manager
+                    .lock()
+                    .unwrap()
+                    .update_submit_fail(&client_oid)
+                {
+                    ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                            symbol: order.symbol,
+                            order: order.order,
+                        }))
+                        .unwrap();
+                }
+                publish_error(&ev_tx, ErrorKind::OrderError, error.to_value());
+            }
+        });
+    }
+
+    fn cancel(&self, symbol: String, order: Order, ev_tx: UnboundedSender<PublishEvent>) {
+        let venue = self.symbols.lock().unwrap().to_venue(&symbol);
+        let client_oid = match self
+            .order_manager
+            .lock()
+            .unwrap()
+            .client_order_id(&symbol, order.order_id)
+        {
+            Ok(client_oid) => client_oid,
+            Err(error) => {
+                publish_error(
+                    &ev_tx,
+                    ErrorKind::OrderError,
+                    BitgetError::from(error).to_value(),
+                );
+                return;
+            }
+        };
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+
+        tokio::spawn(async move {
+            if let Err(error) = client.cancel_order(&venue, &client_oid).await {
+                if let Ok(order) = order_manager
+                    .lock()
+                    .unwrap()
+                    .update_cancel_fail(&client_oid)
+                {
+                    ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                            symbol: order.symbol,
+                            order: order.order,
+                        }))
+                        .unwrap();
+                }
+                publish_error(&ev_tx, ErrorKind::OrderError, error.to_value());
+            }
+        });
+    }
+
+    fn resync(&self, symbol: String, _ev_tx: UnboundedSender<PublishEvent>) {
+        // The public stream resubscribes to the books channel to receive a fresh snapshot.
+        let venue = self.symbols.lock().unwrap().to_venue(&symbol);
+        self.resync_tx.send(venue).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+
+use hftbacktest::types::{Side, Status};
+use serde::{
+    de,
+    de::{Error, Unexpected, Visitor},
+    Deserialize,
+    Deserializer,
+};
+
+use crate::utils::{from_str_to_f64, from_str_to_f64_opt, from_str_to_i64, PxQty};
+
+struct SideVisitor;
+
+impl<'de> Visitor<'de> for SideVisitor {
+    type Value = Side;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string containing \"buy\" or \"sell\"")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match s {
+            "buy" => Ok(Side::Buy),
+            "sell" => Ok(Side::Sell),
+            s => Err(Error::invalid_value(Unexpected::Other(s), &"buy or sell")),
+        }
+    }
+}
+
+fn from_str_to_side<'de, D>(deserializer: D) -> Result<Side, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(SideVisitor)
+}
+
+struct StatusVisitor;
+
+impl<'de> Visitor<'de> for StatusVisitor {
+    type Value = Status;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string containing an order status")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match s {
+            "live" | "new" => Ok(Status::New),
+            "partially_filled" => Ok(Status::PartiallyFilled),
+            "filled" => Ok(Status::Filled),
+            "canceled" | "cancelled" => Ok(Status::Canceled),
+            _ => Ok(Status::Unsupported),
+        }
+    }
+}
+
+fn from_str_to_status<'de, D>(deserializer: D) -> Result<Status, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(StatusVisitor)
+}
+
+fn from_str_to_levels<'de, D>(deserializer: D) -> Result<Vec<PxQty>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let levels: Vec<(String, String)> = Deserialize::deserialize(deserializer)?;
+    levels
+        .into_iter()
+        .map(|(px, qty)| {
+            Ok((
+                px.parse::<f64>().map_err(Error::custom)?,
+                qty.parse::<f64>().map_err(Error::custom)?,
+            ))
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum StreamMsg {
+    Event(EventMsg),
+    Push(PushMsg),
+}
+
+/// The response to the `login` and `subscribe` operations, and the error.
+#[derive(Deserialize, Debug)]
+pub struct EventMsg {
+    pub event: String,
+    #[serde(default)]
+    pub code: Option<serde_json::Value>,
+    #[serde(default)]
+    pub msg: Option<String>,
+    #[serde(default)]
+    pub arg: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PushMsg {
+    #[serde(default)]
+    pub action: Option<String>,
+    pub arg: Arg,
+    pub data: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Arg {
+    #[serde(rename = "instType")]
+    pub inst_type: String,
+    pub channel: String,
+    #[serde(rename = "instId")]
+    pub inst_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Book {
+    #[serde(deserialize_with = "from_str_to_levels")]
+    pub asks: Vec<PxQty>,
+    #[serde(deserialize_with = "from_str_to_levels")]
+    pub bids: Vec<PxQty>,
+    #[serde(deserialize_with = "from_str_to_i64")]
+    pub ts: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Trade {
+    #[serde(deserialize_with = "from_str_to_i64")]
+    pub ts: i64,
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub price: f64,
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub size: f64,
+    #[serde(deserialize_with = "from_str_to_side")]
+    pub side: Side,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OrderData {
+    #[serde(rename = "instId")]
+    pub inst_id: String,
+    #[serde(rename = "clientOid")]
+    #[serde(default)]
+    pub client_oid: String,
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub size: f64,
+    #[serde(rename = "accBaseVolume")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub acc_base_volume: f64,
+    /// The quantity filled by this update.
+    #[serde(rename = "baseVolume")]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub base_volume: Option<f64>,
+    #[serde(rename = "fillPrice")]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub fill_price: Option<f64>,
+    #[serde(rename = "tradeId")]
+    #[serde(default)]
+    pub trade_id: Option<String>,
+    /// `T` for taker or `M` for maker.
+    #[serde(rename = "tradeScope")]
+    #[serde(default)]
+    pub trade_scope: Option<String>,
+    /// The fee of the fill, which is negative when it's charged.
+    #[serde(rename = "fillFee")]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub fill_fee: Option<f64>,
+    #[serde(deserialize_with = "from_str_to_status")]
+    pub status: Status,
+    #[serde(rename = "uTime")]
+    #[serde(deserialize_with = "from_str_to_i64")]
+    pub u_time: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Position {
+    #[serde(rename = "instId")]
+    pub inst_id: String,
+    #[serde(rename = "holdSide")]
+    pub hold_side: String,
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub total: f64,
+    #[serde(rename = "uTime")]
+    #[serde(deserialize_with = "from_str_to_i64")]
+    pub u_time: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RestResponse<T> {
+    pub code: String,
+    pub msg: String,
+    #[serde(default = "Option::default")]
+    pub data: Option<T>,
+}
@@ -0,0 +1,249 @@
+use std::{collections::HashMap, time::Duration};
+
+use hftbacktest::prelude::{LiveEvent, PositionSide, Status};
+use serde_json::json;
+use tokio::{
+    select,
+    sync::{
+        broadcast::{error::RecvError, Receiver},
+        mpsc::UnboundedSender,
+    },
+};
+use tracing::{debug, error};
+
+use crate::{
+    bitget::{
+        msg::{OrderData, Position, PushMsg, StreamMsg},
+        rest::BitgetClient,
+        BitgetError,
+    },
+    connector::PublishEvent,
+    toolkit::{
+        ordermanager::{OrderManagerError, OrderUpdate, SharedClientOrderManager},
+        symbol::SharedSymbolNormalizer,
+        ws::WsSession,
+    },
+};
+
+pub struct PrivateStream {
+    client: BitgetClient,
+    ev_tx: UnboundedSender<PublishEvent>,
+    order_manager: SharedClientOrderManager,
+    symbols: SharedSymbolNormalizer,
+    symbol_rx: Receiver<String>,
+}
+
+impl PrivateStream {
+    pub fn new(
+        client: BitgetClient,
+        ev_tx: UnboundedSender<PublishEvent>,
+        order_manager: SharedClientOrderManager,
+        symbols: SharedSymbolNormalizer,
+        symbol_rx: Receiver<String>,
+    ) -> Self {
+        Self {
+            client,
+            ev_tx,
+            order_manager,
+            symbols,
+            symbol_rx,
+        }
+    }
+
+    fn handle_order(&self, data: OrderData) {
+        let update = OrderUpdate {
+            status: data.status,
+            leaves_qty: Some(data.size - data.acc_base_volume),
+            exec_qty: if matches!(data.status, Status::PartiallyFilled | Status::Filled) {
+                data.base_volume.unwrap_or(0.0)
+            } else {
+                0.0
+            },
+            exec_price: data.fill_price.unwrap_or(0.0),
+            exch_ts: data.u_time * 1_000_000,
+            maker: data.trade_scope.as_deref().map(|scope| scope == "M"),
+            fee: -data.fill_fee.unwrap_or(0.0),
+            trade_id: data.trade_id.clone(),
+        };
+        let result = self
+            .order_manager
+            .lock()
+            .unwrap()
+            .update(&data.client_oid, update.clone());
+        match result {
+            Ok(order) => {
+                // Reports the execution ahead of the order update.
+                if let Some(fill) = update.fill(&order.order) {
+                    self.ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Fill {
+                            symbol: order.symbol.clone(),
+                            fill,
+                        }))
+                        .unwrap();
+                }
+                self.ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                        symbol: order.symbol,
+                        order: order.order,
+                    }))
+                    .unwrap();
+            }
+            Err(OrderManagerError::PrefixUnmatched) => {
+                // This order is not created by this connector.
+            }
+            Err(error) => {
+                error!(?error, ?data, "Couldn't update the order.");
+            }
+        }
+    }
+
+    fn handle_positions(&self, data: Vec<Position>, exch_ts: i64) {
+        // The positions channel pushes all open positions, so the registered symbols that are
+        // not included have no position.
+        let symbols = self.symbols.lock().unwrap();
+        let mut positions: HashMap<String, (f64, i64)> = symbols
+            .venue_symbols()
+            .into_iter()
+            .map(|symbol| (symbol, (0.0, exch_ts)))
+            .collect();
+        for position in data {
+            let qty = if position.hold_side == "short" {
+                -position.total
+            } else {
+                position.total
+            };
+            let entry = positions.entry(position.inst_id).or_insert((0.0, 0));
+            entry.0 += qty;
+            entry.1 = position.u_time * 1_000_000;
+        }
+        for (symbol, (qty, exch_ts)) in positions {
+            if !symbols.is_registered(&symbol) {
+                continue;
+            }
+            self.ev_tx
+                .send(PublishEvent::LiveEvent(LiveEvent::Position {
+                    symbol: symbols.to_local(&symbol),
+                    side: PositionSide::Both,
+                    qty,
+                    exch_ts,
+                }))
+                .unwrap();
+        }
+    }
+
+    fn handle_push(&self, msg: PushMsg) -> Result<(), BitgetError> {
+        match msg.arg.channel.as_str() {
+            "orders" => {
+                let data: Vec<OrderData> = serde_json::from_value(msg.data)?;
+                for order in data {
+                    self.handle_order(order);
+                }
+            }
+            "positions" => {
+                let data: Vec<Position> = serde_json::from_value(msg.data)?;
+                let exch_ts = data.iter().map(|p| p.u_time).max().unwrap_or(0) * 1_000_000;
+                self.handle_positions(data, exch_ts);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn cancel_all(&self, symbol: String) {
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+        let ev_tx = self.ev_tx.clone();
+        let local = self.symbols.lock().unwrap().to_local(&symbol);
+        tokio::spawn(async move {
+            match client.cancel_all_orders(&symbol).await {
+                Ok(()) => {
+                    let orders = order_manager.lock().unwrap().cancel_all(&local);
+                    for order in orders {
+                        ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                                symbol: local.clone(),
+                                order,
+                            }))
+                            .unwrap();
+                    }
+                }
+                Err(error) => {
+                    error!(?error, %symbol, "Couldn't cancel all orders.");
+                }
+            }
+        });
+    }
+
+    pub async fn connect(&mut self, url: &str) -> Result<(), BitgetError> {
+        let mut ws = WsSession::connect(url)
+            .await?
+            .keepalive(Duration::from_secs(30), "ping".to_string());
+        ws.send(json!({"op": "login", "args": [self.client.login_arg()]}).to_string())
+            .await?;
+
+        loop {
+            select! {
+                msg = self.symbol_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        self.cancel_all(symbol);
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} subscription requests were missed.");
+                    }
+                },
+                text = ws.recv() => {
+                    let text = text?;
+                    if text == "pong" {
+                        continue;
+                    }
+                    match serde_json::from_str::<StreamMsg>(&text) {
+                        Ok(StreamMsg::Event(event)) if event.event == "login" => {
+                            // Cancels all orders before subscribing in order to start with the
+                            // clean state.
+                            let symbols = self.symbols.lock().unwrap().venue_symbols();
+                            for symbol in symbols {
+                                self.cancel_all(symbol);
+                            }
+                            let args: Vec<_> = ["orders", "positions"]
+                                .iter()
+                                .map(|channel| json!({
+                                    "instType": self.client.product_type(),
+                                    "channel": channel,
+                                    "instId": "default",
+                                }))
+                                .collect();
+                            ws.send(json!({"op": "subscribe", "args": args}).to_string()).await?;
+                        }
+                        Ok(StreamMsg::Event(event)) if event.event == "error" => {
+                            // Error codes 30000-30099 relate to the login.
+                            let code = event
+                                .code
+                                .as_ref()
+                                .and_then(|code| code.as_i64())
+                                .unwrap_or(0);
+                            let msg = event.msg.unwrap_or_default();
+                            if (30000..30100).contains(&code) {
+                                return Err(BitgetError::AuthError { code, msg });
+                            }
+                            error!(%code, %msg, "The request is rejected.");
+                        }
+                        Ok(StreamMsg::Event(event)) => {
+                            debug!(?event, "Event");
+                        }
+                        Ok(StreamMsg::Push(msg)) => {
+                            if let Err(error) = self.handle_push(msg) {
+                                error!(?error, %text, "Couldn't handle PushMsg.");
+                            }
+                        }
+                        Err(error) => {
+                            error!(?error, %text, "Couldn't parse StreamMsg.");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::{collections::HashSet, time::Duration};
+
+use connector_sdk::{FeedError, MarketDataHandler};
+use serde_json::json;
+use tokio::{
+    select,
+    sync::{
+        broadcast::{error::RecvError, Receiver},
+        mpsc::UnboundedSender,
+    },
+};
+use tracing::{debug, error};
+
+use crate::{
+    bitget::{
+        msg::{Book, PushMsg, StreamMsg, Trade},
+        BitgetError,
+    },
+    connector::PublishEvent,
+    toolkit::{
+        decode_error,
+        feed::{publish_depth, publish_trade},
+        symbol::SharedSymbolNormalizer,
+        ws::WsSession,
+    },
+};
+
+/// Decodes the books and trade channels. The updates of the books channel aren't numbered
+/// contiguously, so a gap can't be detected; the updates are applied only on top of a snapshot.
+pub struct MarketData {
+    symbols: SharedSymbolNormalizer,
+    synced: HashSet<String>,
+}
+
+impl MarketData {
+    pub fn new(symbols: SharedSymbolNormalizer) -> Self {
+        Self {
+            symbols,
+            synced: Default::default(),
+        }
+    }
+
+    fn handle_push(
+        &mut self,
+        msg: PushMsg,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        let symbol = self.symbols.lock().unwrap().to_local(&msg.arg.inst_id);
+        let snapshot = msg.action.as_deref() == Some("snapshot");
+        match msg.arg.channel.as_str() {
+            "books" => {
+                // The snapshot is sent upon every subscription, including the ones after a
+                // reconnection or a resync, and replaces the current market depth.
+                if snapshot {
+                    self.synced.insert(symbol.clone());
+                } else if !self.synced.contains(&symbol) {
+                    return Ok(());
+                }
+                let data: Vec<Book> = serde_json::from_value(msg.data).map_err(decode_error)?;
+                for book in data {
+                    publish_depth(
+                        ev_tx,
+                        &symbol,
+                        book.ts * 1_000_000,
+                        &book.bids,
+                        &book.asks,
+                        snapshot,
+                    );
+                }
+            }
+            "trade" => {
+                // The snapshot contains the recent trades before the subscription.
+                if snapshot {
+                    return Ok(());
+                }
+                let data: Vec<Trade> = serde_json::from_value(msg.data).map_err(decode_error)?;
+                for trade in data {
+                    publish_trade(
+                        ev_tx,
+                        &symbol,
+                        trade.side,
+                        trade.ts * 1_000_000,
+                        trade.price,
+                        trade.size,
+                    );
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl MarketDataHandler for MarketData {
+    type Message = String;
+
+    fn handle(
+        &mut self,
+        text: &String,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        if text == "pong" {
+            return Ok(());
+        }
+        match serde_json::from_str::<StreamMsg>(text).map_err(decode_error)? {
+            StreamMsg::Event(event) => {
+                if event.event == "error" {
+                    error!(?event, "The request is rejected.");
+                } else {
+                    debug!(?event, "Event");
+                }
+            }
+            StreamMsg::Push(msg) => self.handle_push(msg, ev_tx)?,
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.synced.clear();
+    }
+}
+
+pub struct PublicStream {
+    ev_tx: UnboundedSender<PublishEvent>,
+    symbols: SharedSymbolNormalizer,
+    symbol_rx: Receiver<String>,
+    resync_rx: Receiver<String>,
+    inst_type: String,
+    market_data: MarketData,
+}
+
+impl PublicStream {
+    pub fn new(
+        ev_tx: UnboundedSender<PublishEvent>,
+        symbols: SharedSymbolNormalizer,
+        symbol_rx: Receiver<String>,
+        resync_rx: Receiver<String>,
+        inst_type: String,
+    ) -> Self {
+        Self {
+            ev_tx,
+            market_data: MarketData::new(symbols.clone()),
+            symbols,
+            symbol_rx,
+            resync_rx,
+            inst_type,
+        }
+    }
+
+    fn args(&self, symbol: &str, channels: &[&str]) -> Vec<serde_json::Value> {
+        channels
+            .iter()
+            .map(|channel| {
+                json!({
+                    "instType": self.inst_type,
+                    "channel": channel,
+                    "instId": symbol,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn connect(&mut self, url: &str) -> Result<(), BitgetError> {
+        let mut ws = WsSession::connect(url)
+            .await?
+            .keepalive(Duration::from_secs(30), "ping".to_string());
+
+        // Resubscribes to the symbols registered before the reconnection. The snapshot sent upon
+        // the subscription replaces the market depth that misses the updates during the
+        // disconnection.
+        let symbols = self.symbols.lock().unwrap().venue_symbols();
+        for symbol in symbols {
+            let args = self.args(&symbol, &["books", "trade"]);
+            ws.send(json!({"op": "subscribe", "args": args}).to_string())
+                .await?;
+        }
+
+        loop {
+            select! {
+                msg = self.symbol_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        let args = self.args(&symbol, &["books", "trade"]);
+                        ws.send(json!({"op": "subscribe", "args": args}).to_string()).await?;
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} subscription requests were missed.");
+                    }
+                },
+                msg = self.resync_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        // Resubscribes to the books channel, which sends a fresh snapshot.
+                        let args = self.args(&symbol, &["books"]);
+                        for op in ["unsubscribe", "subscribe"] {
+                            ws.send(json!({"op": op, "args": args}).to_string()).await?;
+                        }
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} resync requests were missed.");
+                    }
+                },
+                text = ws.recv() => {
+                    let text = text?;
+                    if let Err(error) = self.market_data.handle(&text, &self.ev_tx) {
+                        error!(?error, %text, "Couldn't handle StreamMsg.");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use connector_sdk::{
+        conformance::{ConformanceSuite, VenueEncoder},
+        ordermanager::OrderUpdate,
+    };
+    use hftbacktest::prelude::Side;
+    use serde_json::json;
+
+    use super::MarketData;
+    use crate::utils::PxQty;
+
+    struct Encoder;
+
+    impl Encoder {
+        fn push(symbol: &str, channel: &str, action: &str, data: serde_json::Value) -> String {
+            json!({
+                "action": action,
+                "arg": {
+                    "instType": "USDT-FUTURES",
+                    "channel": channel,
+                    "instId": symbol,
+                },
+                "data": data,
+                "ts": 1,
+            })
+            .to_string()
+        }
+
+        fn book(symbol: &str, action: &str, seq: i64, bids: &[PxQty], asks: &[PxQty]) -> String {
+            let levels = |levels: &[PxQty]| {
+                levels
+                    .iter()
+                    .map(|&(px, qty)| json!([px.to_string(), qty.to_string()]))
+                    .collect::<Vec<_>>()
+            };
+            Self::push(
+                symbol,
+                "books",
+                action,
+                json!([{
+                    "asks": levels(asks),
+                    "bids": levels(bids),
+                    "checksum": 0,
+                    "seq": seq,
+                    "ts": "1",
+                }]),
+            )
+        }
+    }
+
+    impl VenueEncoder for Encoder {
+        type MarketData = String;
+        type OrderMessage = ();
+
+        fn snapshot(&self, symbol: &str, seq: i64, bids: &[PxQty], asks: &[PxQty]) -> String {
+            Self::book(symbol, "snapshot", seq, bids, asks)
+        }
+
+        fn depth_update(
+            &self,
+            symbol: &str,
+            seq: i64,
+            bids: &[PxQty],
+            asks: &[PxQty],
+        ) -> Vec<String> {
+            vec![Self::book(symbol, "update", seq, bids, asks)]
+        }
+
+        fn trade(&self, symbol: &str, side: Side, px: f64, qty: f64) -> String {
+            let side = if side == Side::Sell { "sell" } else { "buy" };
+            Self::push(
+                symbol,
+                "trade",
+                "update",
+                json!([{
+                    "ts": "1",
+                    "price": px.to_string(),
+                    "size": qty.to_string(),
+                    "side": side,
+                    "tradeId": "1",
+                }]),
+            )
+        }
+
+        fn order_update(&self, _symbol: &str, _client_order_id: &str, _update: &OrderUpdate) {}
+    }
+
+    #[test]
+    fn conformance() {
+        let suite = ConformanceSuite::new(Encoder, "BTCUSDT");
+        let new_handler = || MarketData::new(Default::default());
+        suite.check_snapshot(new_handler()).unwrap();
+        suite.check_incremental(new_handler()).unwrap();
+        // The updates aren't numbered contiguously, so `check_seq_gap` doesn't apply.
+        suite.check_reconnection(new_handler()).unwrap();
+        suite.check_trade(new_handler()).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    bitget::{msg::RestResponse, BitgetError},
+    utils::sign_hmac_sha256_base64,
+};
+
+#[derive(Clone)]
+pub struct BitgetClient {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    secret: String,
+    passphrase: String,
+    product_type: String,
+    margin_coin: String,
+}
+
+impl BitgetClient {
+    pub fn new(
+        url: &str,
+        api_key: &str,
+        secret: &str,
+        passphrase: &str,
+        product_type: &str,
+        margin_coin: &str,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            api_key: api_key.to_string(),
+            secret: secret.to_string(),
+            passphrase: passphrase.to_string(),
+            product_type: product_type.to_string(),
+            margin_coin: margin_coin.to_string(),
+        }
+    }
+
+    async fn post<T: for<'a> Deserialize<'a>>(
+        &self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<Option<T>, BitgetError> {
+        let body = body.to_string();
+        let timestamp = Utc::now().timestamp_millis().to_string();
+        let signature =
+            sign_hmac_sha256_base64(&self.secret, &format!("{timestamp}POST{path}{body}"));
+        let resp: RestResponse<T> = self
+            .client
+            .post(format!("{}{}", self.url, path))
+            .header("Content-Type", "application/json")
+            .header("locale", "en-US")
+            .header("ACCESS-KEY", &self.api_key)
+            .header("ACCESS-SIGN", signature)
+            .header("ACCESS-TIMESTAMP", timestamp)
+            .header("ACCESS-PASSPHRASE", &self.passphrase)
+            .body(body)
+            .send()
+            .await?
+            .json()
+            .await?;
+        if resp.code != "00000" {
+            return Err(BitgetError::from_code(resp.code, resp.msg));
+        }
+        Ok(resp.data)
+    }
+
+    /// Submits the order, whose parameters are completed with the product type and the margin
+    /// coin.
+    pub async fn place_order(&self, mut params: serde_json::Value) -> Result<(), BitgetError> {
+        params["productType"] = json!(self.product_type);
+        params["marginCoin"] = json!(self.margin_coin);
+        let _: Option<serde_json::Value> =
+            self.post("/api/v2/mix/order/place-order", params).await?;
+        Ok(())
+    }
+
+    pub async fn cancel_order(&self, symbol: &str, client_oid: &str) -> Result<(), BitgetError> {
+        let _: Option<serde_json::Value> = self
+            .post(
+                "/api/v2/mix/order/cancel-order",
+                json!({
+                    "symbol": symbol,
+                    "productType": self.product_type,
+                    "marginCoin": self.margin_coin,
+                    "clientOid": client_oid,
+                }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels all open orders of the symbol.
+    pub async fn cancel_all_orders(&self, symbol: &str) -> Result<(), BitgetError> {
+        let _: Option<serde_json::Value> = self
+            .post(
+                "/api/v2/mix/order/batch-cancel-orders",
+                json!({
+                    "symbol": symbol,
+                    "productType": self.product_type,
+                    "marginCoin": self.margin_coin,
+                }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the `login` operation's argument of the private WebSocket stream.
+    pub fn login_arg(&self) -> serde_json::Value {
+        let timestamp = Utc::now().timestamp().to_string();
+        let sign = sign_hmac_sha256_base64(&self.secret, &format!("{timestamp}GET/user/verify"));
+        json!({
+            "apiKey": self.api_key,
+            "passphrase": self.passphrase,
+            "timestamp": timestamp,
+            "sign": sign,
+        })
+    }
+
+    pub fn product_type(&self) -> &str {
+        &self.product_type
+    }
+}
@@ -2,12 +2,16 @@ use std::{
     collections::{HashMap, HashSet},
     num::{ParseFloatError, ParseIntError},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use hftbacktest::types::{ErrorKind, LiveError, LiveEvent, Order, Value};
+use hftbacktest::types::{DepthLevel, ErrorKind, LiveError, LiveEvent, Order, Value};
 use serde::Deserialize;
 use thiserror::Error;
-use tokio::sync::{broadcast, broadcast::Sender, mpsc::UnboundedSender};
+use tokio::{
+    sync::{broadcast, broadcast::Sender, mpsc::UnboundedSender},
+    time,
+};
 use tracing::error;
 
 use crate::{
@@ -111,10 +115,17 @@ pub struct Config {
     secret: String,
     category: String,
     order_prefix: String,
+    /// The interval in milliseconds at which the open orders' status is polled via REST to fill
+    /// in any order updates missed by the private stream. `0` disables the polling.
+    #[serde(default)]
+    order_poll_interval_ms: u64,
 }
 
 type SharedSymbolSet = Arc<Mutex<HashSet<String>>>;
 
+/// The finest market depth level requested for each symbol.
+type SharedDepthLevels = Arc<Mutex<HashMap<String, DepthLevel>>>;
+
 pub struct Bybit {
     config: Config,
     order_tx: Sender<OrderOp>,
@@ -122,13 +133,21 @@ pub struct Bybit {
     symbols: SharedSymbolSet,
     client: BybitClient,
     symbol_tx: Sender<String>,
+    resync_tx: Sender<String>,
+    depth_levels: SharedDepthLevels,
+    /// Notifies the public stream of the symbols whose depth level has changed.
+    depth_tx: Sender<String>,
 }
 
 impl Bybit {
     fn connect_public_stream(&self, ev_tx: UnboundedSender<PublishEvent>) {
         // Connects to the public stream for the market data.
         let public_url = self.config.public_url.clone();
+        let symbols = self.symbols.clone();
         let symbol_tx = self.symbol_tx.clone();
+        let resync_tx = self.resync_tx.clone();
+        let depth_levels = self.depth_levels.clone();
+        let depth_tx = self.depth_tx.clone();
 
         tokio::spawn(async move {
             let _ = Retry::new(ExponentialBackoff::default())
@@ -143,7 +162,14 @@ impl Bybit {
                     Ok(())
                 })
                 .retry(|| async {
-                    let mut stream = PublicStream::new(ev_tx.clone(), symbol_tx.subscribe());
+                    let mut stream = PublicStream::new(
+                        ev_tx.clone(),
+                        symbols.clone(),
+                        symbol_tx.subscribe(),
+                        resync_tx.subscribe(),
+                        depth_levels.clone(),
+                        depth_tx.subscribe(),
+                    );
                     if let Err(error) = stream.connect(&public_url).await {
                         error!(?error, "A connection error occurred.");
                         ev_tx
@@ -183,9 +209,15 @@ impl Bybit {
                         ?error,
                         "An error occurred in the private stream connection."
                     );
+                    // The stream is authenticated again when it reconnects.
+                    let kind = if matches!(error, BybitError::AuthError { .. }) {
+                        ErrorKind::AuthFailure
+                    } else {
+                        ErrorKind::ConnectionInterrupted
+                    };
                     ev_tx
                         .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
-                            ErrorKind::ConnectionInterrupted,
+                            kind,
                             error.to_value(),
                         ))))
                         .unwrap();
@@ -231,9 +263,15 @@ impl Bybit {
             let _ = Retry::new(ExponentialBackoff::default())
                 .error_handler(|error: BybitError| {
                     error!(?error, "An error occurred in the trade stream connection.");
+                    // The stream is authenticated again when it reconnects.
+                    let kind = if matches!(error, BybitError::AuthError { .. }) {
+                        ErrorKind::AuthFailure
+                    } else {
+                        ErrorKind::ConnectionInterrupted
+                    };
                     ev_tx
                         .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
-                            ErrorKind::ConnectionInterrupted,
+                            kind,
                             error.to_value(),
                         ))))
                         .unwrap();
@@ -253,6 +291,41 @@ impl Bybit {
                 .await;
         });
     }
+
+    fn poll_order_status(&self, ev_tx: UnboundedSender<PublishEvent>) {
+        let category = self.config.category.clone();
+        let order_manager = self.order_manager.clone();
+        let client = self.client.clone();
+        let poll_interval = Duration::from_millis(self.config.order_poll_interval_ms);
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(poll_interval);
+            loop {
+                interval.tick().await;
+                let open_orders = order_manager.lock().unwrap().open_orders();
+                for (order_link_id, symbol) in open_orders {
+                    match client.get_order(&category, &symbol, &order_link_id).await {
+                        Ok(Some(data)) => {
+                            if let Some(order) =
+                                order_manager.lock().unwrap().update_from_poll(&data)
+                            {
+                                ev_tx
+                                    .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                                        symbol: order.symbol,
+                                        order: order.order,
+                                    }))
+                                    .unwrap();
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(error) => {
+                            error!(?error, %order_link_id, "Couldn't poll the order status.");
+                        }
+                    }
+                }
+            }
+        });
+    }
 }
 
 impl ConnectorBuilder for Bybit {
@@ -268,6 +341,8 @@ impl ConnectorBuilder for Bybit {
         }
         let (order_tx, _) = broadcast::channel(500);
         let (symbol_tx, _) = broadcast::channel(500);
+        let (resync_tx, _) = broadcast::channel(500);
+        let (depth_tx, _) = broadcast::channel(500);
         let order_manager = Arc::new(Mutex::new(OrderManager::new(&config.order_prefix)));
         let client = BybitClient::new(&config.rest_url, &config.api_key, &config.secret);
         Ok(Bybit {
@@ -277,16 +352,38 @@ impl ConnectorBuilder for Bybit {
             client,
             symbols: Default::default(),
             symbol_tx,
+            resync_tx,
+            depth_levels: Default::default(),
+            depth_tx,
         })
     }
 }
 
 impl Connector for Bybit {
-    fn register(&mut self, symbol: String) {
+    fn register(&mut self, symbol: String, tick_size: f64, lot_size: f64) {
+        self.register_with_depth(symbol, tick_size, lot_size, DepthLevel::Full);
+    }
+
+    fn register_with_depth(
+        &mut self,
+        symbol: String,
+        _tick_size: f64,
+        _lot_size: f64,
+        depth: DepthLevel,
+    ) {
+        let mut depth_levels = self.depth_levels.lock().unwrap();
         let mut symbols = self.symbols.lock().unwrap();
         if !symbols.contains(&symbol) {
+            depth_levels.insert(symbol.clone(), depth);
             symbols.insert(symbol.clone());
             self.symbol_tx.send(symbol).unwrap();
+        } else if depth_levels
+            .get(&symbol)
+            .is_some_and(|level| *level < depth)
+        {
+            // Another bot needs a finer market depth.
+            depth_levels.insert(symbol.clone(), depth);
+            let _ = self.depth_tx.send(symbol);
         }
     }
 
@@ -297,6 +394,9 @@ impl Connector for Bybit {
     fn run(&mut self, ev_tx: UnboundedSender<PublishEvent>) {
         self.connect_public_stream(ev_tx.clone());
         self.connect_private_stream(ev_tx.clone());
+        if self.config.order_poll_interval_ms > 0 {
+            self.poll_order_status(ev_tx.clone());
+        }
         self.connect_trade_stream(ev_tx);
     }
 
@@ -350,4 +450,28 @@ impl Connector for Bybit {
             }
         }
     }
+
+    fn resync(&self, symbol: String, _ev_tx: UnboundedSender<PublishEvent>) {
+        // The public stream resubscribes to the order book to receive a fresh snapshot.
+        self.resync_tx.send(symbol).unwrap();
+    }
+
+    fn query_instrument_info(&self, symbol: String, tx: UnboundedSender<PublishEvent>) {
+        let category = self.config.category.clone();
+        let client = self.client.clone();
+
+        tokio::spawn(async move {
+            let info = match client.get_instrument(&category, &symbol).await {
+                Ok(instrument) => instrument.map(|instrument| instrument.instrument_info()),
+                Err(error) => {
+                    error!(?error, %symbol, "Couldn't get the instrument information via REST.");
+                    None
+                }
+            };
+            let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::InstrumentInfo {
+                symbol,
+                info,
+            }));
+        });
+    }
 }
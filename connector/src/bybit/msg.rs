@@ -1,6 +1,9 @@
 use std::{collections::HashMap, fmt, fmt::Debug};
 
-use hftbacktest::types::{OrdType, Side, Status, TimeInForce};
+use hftbacktest::{
+    prelude::get_precision,
+    types::{InstrumentInfo, OrdType, PositionSide, Side, Status, TimeInForce},
+};
 use serde::{
     de,
     de::{Error, Unexpected, Visitor},
@@ -173,6 +176,8 @@ pub struct PublicStream {
     pub ts: i64,
     pub data: serde_json::Value,
     pub cts: Option<i64>,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -226,6 +231,33 @@ pub struct Trade {
     pub iv: Option<f64>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct Ticker {
+    pub symbol: String,
+    #[serde(rename = "openInterest")]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub open_interest: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Liquidation {
+    #[serde(rename = "T")]
+    pub ts: i64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    /// The side of the liquidated position.
+    #[serde(rename = "S")]
+    #[serde(deserialize_with = "from_str_to_side")]
+    pub side: Side,
+    #[serde(rename = "v")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub size: f64,
+    #[serde(rename = "p")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub price: f64,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
 pub enum PrivateStreamMsg {
@@ -337,6 +369,19 @@ pub struct Position {
     pub is_reduce_only: bool,
 }
 
+impl Position {
+    /// Returns the position side from the position index, which is `1` for the long and `2` for the
+    /// short position in hedge mode.
+    pub fn position_side(&self) -> PositionSide {
+        match self.position_idx {
+            0 => PositionSide::Both,
+            1 => PositionSide::Long,
+            2 => PositionSide::Short,
+            _ => PositionSide::Unsupported,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Execution {
     pub category: String,
@@ -572,8 +617,42 @@ pub struct Order {
     #[serde(rename = "timeInForce")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub time_in_force: Option<String>,
+    #[serde(rename = "reduceOnly")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduce_only: Option<bool>,
+    #[serde(rename = "positionIdx")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_idx: Option<i64>,
+    #[serde(rename = "orderLinkId")]
+    pub order_link_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RestOrder {
+    pub symbol: String,
+    #[serde(rename = "orderId")]
+    pub order_id: String,
     #[serde(rename = "orderLinkId")]
     pub order_link_id: String,
+    #[serde(deserialize_with = "from_str_to_side")]
+    pub side: Side,
+    #[serde(rename = "orderStatus")]
+    #[serde(deserialize_with = "from_str_to_status")]
+    pub order_status: Status,
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub qty: f64,
+    #[serde(rename = "leavesQty")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub leaves_qty: f64,
+    #[serde(rename = "cumExecQty")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub cum_exec_qty: f64,
+    #[serde(rename = "avgPrice")]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub avg_price: Option<f64>,
+    #[serde(rename = "updatedTime")]
+    #[serde(deserialize_with = "from_str_to_i64")]
+    pub updated_time: i64,
 }
 
 #[derive(Deserialize, Debug)]
@@ -599,3 +678,66 @@ pub struct RestResponse {
     pub ret_ext_info: serde_json::Value,
     pub time: i64,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct Instrument {
+    pub symbol: String,
+    #[serde(rename = "priceScale", default)]
+    pub price_scale: Option<String>,
+    #[serde(rename = "priceFilter")]
+    pub price_filter: PriceFilter,
+    #[serde(rename = "lotSizeFilter")]
+    pub lot_size_filter: LotSizeFilter,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PriceFilter {
+    #[serde(rename = "tickSize")]
+    #[serde(deserialize_with = "from_str_to_f64")]
+    pub tick_size: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LotSizeFilter {
+    /// The quantity step of the derivatives.
+    #[serde(rename = "qtyStep", default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub qty_step: Option<f64>,
+    /// The quantity step of the spot.
+    #[serde(rename = "basePrecision", default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub base_precision: Option<f64>,
+    #[serde(rename = "minNotionalValue", default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub min_notional_value: Option<f64>,
+    #[serde(rename = "minOrderAmt", default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub min_order_amt: Option<f64>,
+}
+
+impl Instrument {
+    pub fn instrument_info(&self) -> InstrumentInfo {
+        let tick_size = self.price_filter.tick_size;
+        let lot_size = self
+            .lot_size_filter
+            .qty_step
+            .or(self.lot_size_filter.base_precision)
+            .unwrap_or(0.0);
+        InstrumentInfo {
+            tick_size,
+            lot_size,
+            min_notional: self
+                .lot_size_filter
+                .min_notional_value
+                .or(self.lot_size_filter.min_order_amt)
+                .unwrap_or(0.0),
+            price_precision: self
+                .price_scale
+                .as_ref()
+                .and_then(|scale| scale.parse().ok())
+                .unwrap_or(get_precision(tick_size) as u32),
+            qty_precision: get_precision(lot_size) as u32,
+            contract_multiplier: 1.0,
+        }
+    }
+}
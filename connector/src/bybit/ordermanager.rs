@@ -3,12 +3,12 @@ use std::sync::{Arc, Mutex};
 use hashbrown::HashMap;
 use hftbacktest::{
     prelude::get_precision,
-    types::{OrdType, Order, OrderId, Side, Status, TimeInForce},
+    types::{OrdType, Order, OrderId, PositionSide, Side, Status, TimeInForce},
 };
 
 use crate::{
     bybit::{
-        msg::{Execution, FastExecution, Order as BybitOrder, PrivateOrder},
+        msg::{Execution, FastExecution, Order as BybitOrder, PrivateOrder, RestOrder},
         BybitError,
     },
     connector::GetOrders,
@@ -61,6 +61,44 @@ impl OrderManager {
         }
     }
 
+    /// Updates the order with the order status polled via REST, which fills in any updates missed
+    /// by the private stream. Returns the order only if the polled status is newer than the known
+    /// one.
+    pub fn update_from_poll(&mut self, data: &RestOrder) -> Option<OrderExt> {
+        let order = self.orders.get_mut(&data.order_link_id)?;
+        if data.updated_time * 1_000_000 <= order.order.exch_timestamp {
+            return None;
+        }
+        let prev_filled_qty = order.order.qty - order.order.leaves_qty;
+        order.order.req = Status::None;
+        order.order.status = data.order_status;
+        order.order.leaves_qty = data.leaves_qty;
+        order.order.exch_timestamp = data.updated_time * 1_000_000;
+        // Only reports the quantity filled since the last known update.
+        order.order.exec_qty = (data.cum_exec_qty - prev_filled_qty).max(0.0);
+        if let Some(avg_price) = data.avg_price {
+            if order.order.exec_qty > 0.0 {
+                order.order.exec_price_tick = (avg_price / order.order.tick_size).round() as i64;
+            }
+        }
+        if !order.order.active() {
+            self.order_id_map
+                .remove(&RefSymbolOrderId::new(&order.symbol, order.order.order_id));
+            self.orders.remove(&data.order_link_id)
+        } else {
+            Some(order.clone())
+        }
+    }
+
+    /// Returns the order link IDs and the symbols of the open orders to poll the status of.
+    pub fn open_orders(&self) -> Vec<(OrderLinkId, String)> {
+        self.orders
+            .iter()
+            .filter(|(_, order)| order.order.req == Status::None && order.order.active())
+            .map(|(order_link_id, order)| (order_link_id.clone(), order.symbol.clone()))
+            .collect()
+    }
+
     pub fn update_execution(&mut self, data: &Execution) -> Result<OrderExt, BybitError> {
         if !data.order_link_id.starts_with(&self.prefix) {
             return Err(BybitError::PrefixUnmatched);
@@ -73,6 +111,7 @@ impl OrderManager {
             (data.exec_price / order_info.order.price_tick as f64).round() as i64;
         order_info.order.exec_qty = data.exec_qty;
         order_info.order.exch_timestamp = data.exec_time * 1_000_000;
+        order_info.order.maker = data.is_maker;
         Ok(order_info.clone())
     }
 
@@ -134,6 +173,15 @@ impl OrderManager {
                     }
                 }
             }),
+            reduce_only: order.reduce_only.then_some(true),
+            position_idx: match order.position_side {
+                PositionSide::Both => None,
+                PositionSide::Long => Some(1),
+                PositionSide::Short => Some(2),
+                PositionSide::Unsupported => {
+                    return Err(BybitError::InvalidArg("position_side"));
+                }
+            },
             order_link_id: order_link_id.clone(),
         };
 
@@ -176,6 +224,8 @@ impl OrderManager {
             price: None,
             category: category.to_string(),
             time_in_force: None,
+            reduce_only: None,
+            position_idx: None,
             order_link_id: order_link_id.clone(),
         };
         Ok(order)
@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use chrono::Utc;
 use futures_util::{stream::SplitSink, SinkExt, StreamExt};
-use hftbacktest::prelude::LiveEvent;
+use hftbacktest::prelude::{Fill, Liquidation, LiveEvent, Side};
 use tokio::{
     net::TcpStream,
     select,
@@ -169,6 +169,7 @@ impl PrivateStream {
                     };
                     self.ev_tx
                         .send(PublishEvent::LiveEvent(LiveEvent::Position {
+                            side: position.position_side(),
                             symbol: position.symbol,
                             qty,
                             exch_ts: position.updated_time * 1_000_000,
@@ -180,11 +181,50 @@ impl PrivateStream {
                 debug!(?data, "Execution");
                 let mut order_manager = self.order_manager.lock().unwrap();
                 for execution in &data.data {
+                    // The executions of the exchange's liquidation and auto-deleveraging are
+                    // reported as liquidations instead of the fills.
+                    if execution.exec_type == "BustTrade" || execution.exec_type == "AdlTrade" {
+                        let side = match execution.side.as_str() {
+                            "Buy" => Side::Buy,
+                            "Sell" => Side::Sell,
+                            _ => Side::Unsupported,
+                        };
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Liquidation {
+                                symbol: execution.symbol.clone(),
+                                liquidation: Liquidation {
+                                    side,
+                                    price: execution.exec_price,
+                                    qty: execution.exec_qty,
+                                    fee: execution.exec_fee.parse().unwrap_or(0.0),
+                                    exch_ts: execution.exec_time * 1_000_000,
+                                },
+                            }))
+                            .unwrap();
+                        continue;
+                    }
                     match order_manager.update_execution(execution) {
                         Ok(OrderExt {
                             symbol: asset,
                             order,
                         }) => {
+                            // Reports the execution ahead of the order update.
+                            let fill = Fill {
+                                order_id: order.order_id,
+                                trade_id: execution.exec_id.clone(),
+                                side: order.side,
+                                price: execution.exec_price,
+                                qty: execution.exec_qty,
+                                fee: execution.exec_fee.parse().unwrap_or(0.0),
+                                maker: execution.is_maker,
+                                exch_ts: execution.exec_time * 1_000_000,
+                            };
+                            self.ev_tx
+                                .send(PublishEvent::LiveEvent(LiveEvent::Fill {
+                                    symbol: asset.clone(),
+                                    fill,
+                                }))
+                                .unwrap();
                             self.ev_tx
                                 .send(PublishEvent::LiveEvent(LiveEvent::Order {
                                     symbol: asset,
@@ -382,6 +422,7 @@ pub async fn get_position(
         ev_tx
             .send(PublishEvent::LiveEvent(LiveEvent::Position {
                 symbol: symbol.to_string(),
+                side: position.position_side(),
                 qty,
                 exch_ts: position.updated_time,
             }))
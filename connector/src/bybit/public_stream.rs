@@ -1,17 +1,31 @@
-use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use chrono::Utc;
-use futures_util::{SinkExt, StreamExt};
-use hftbacktest::prelude::{
-    Event,
-    LiveEvent,
-    Side,
-    LOCAL_ASK_DEPTH_BBO_EVENT,
-    LOCAL_ASK_DEPTH_EVENT,
-    LOCAL_BID_DEPTH_BBO_EVENT,
-    LOCAL_BID_DEPTH_EVENT,
-    LOCAL_BUY_TRADE_EVENT,
-    LOCAL_SELL_TRADE_EVENT,
+use futures_util::{Sink, SinkExt, StreamExt};
+use hftbacktest::{
+    live::ipc::TO_ALL,
+    prelude::{
+        DepthLevel,
+        ErrorKind,
+        Event,
+        LiveError,
+        LiveEvent,
+        Side,
+        Value,
+        LOCAL_ASK_DEPTH_BBO_EVENT,
+        LOCAL_ASK_DEPTH_EVENT,
+        LOCAL_BID_DEPTH_BBO_EVENT,
+        LOCAL_BID_DEPTH_EVENT,
+        LOCAL_BUY_LIQUIDATION_EVENT,
+        LOCAL_BUY_TRADE_EVENT,
+        LOCAL_DEPTH_CLEAR_EVENT,
+        LOCAL_OPEN_INTEREST_EVENT,
+        LOCAL_SELL_LIQUIDATION_EVENT,
+        LOCAL_SELL_TRADE_EVENT,
+    },
 };
 use tokio::{
     select,
@@ -23,15 +37,18 @@ use tokio::{
 };
 use tokio_tungstenite::{
     connect_async,
+    tungstenite,
     tungstenite::{client::IntoClientRequest, Message},
 };
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 use crate::{
     bybit::{
         msg,
         msg::{Op, OrderBook, PublicStreamMsg},
         BybitError,
+        SharedDepthLevels,
+        SharedSymbolSet,
     },
     connector::PublishEvent,
     utils::parse_depth,
@@ -39,25 +56,84 @@ use crate::{
 
 pub struct PublicStream {
     ev_tx: UnboundedSender<PublishEvent>,
+    symbols: SharedSymbolSet,
     symbol_rx: Receiver<String>,
+    resync_rx: Receiver<String>,
+    resyncing: HashSet<String>,
+    /// The last update ID of each order book topic, by which the gaps in the updates are detected.
+    update_ids: HashMap<String, i64>,
+    /// The symbols whose order book needs a fresh snapshot due to a gap.
+    gapped: Vec<String>,
+    depth_levels: SharedDepthLevels,
+    depth_rx: Receiver<String>,
+    /// The depth level subscribed for each symbol.
+    subscribed: HashMap<String, DepthLevel>,
 }
 
 impl PublicStream {
-    pub fn new(ev_tx: UnboundedSender<PublishEvent>, symbol_rx: Receiver<String>) -> Self {
-        Self { ev_tx, symbol_rx }
+    pub fn new(
+        ev_tx: UnboundedSender<PublishEvent>,
+        symbols: SharedSymbolSet,
+        symbol_rx: Receiver<String>,
+        resync_rx: Receiver<String>,
+        depth_levels: SharedDepthLevels,
+        depth_rx: Receiver<String>,
+    ) -> Self {
+        Self {
+            ev_tx,
+            symbols,
+            symbol_rx,
+            resync_rx,
+            resyncing: Default::default(),
+            update_ids: Default::default(),
+            gapped: Default::default(),
+            depth_levels,
+            depth_rx,
+            subscribed: Default::default(),
+        }
     }
 
-    async fn handle_public_stream(&self, text: &str) -> Result<(), BybitError> {
+    /// Returns the deepest order book topic subscribed for the symbol, whose snapshot replaces the
+    /// market depth.
+    fn deepest_topic(&self, symbol: &str) -> String {
+        let level = self.subscribed.get(symbol).copied().unwrap_or_default();
+        depth_topics(symbol, level).pop().unwrap()
+    }
+
+    async fn handle_public_stream(&mut self, text: &str) -> Result<(), BybitError> {
         let stream = serde_json::from_str::<PublicStreamMsg>(text)?;
         match stream {
             PublicStreamMsg::Op(resp) => {
                 debug!(?resp, "Op");
             }
             PublicStreamMsg::Topic(stream) => {
-                if stream.topic.starts_with("orderbook.1") {
+                if stream.topic.starts_with("orderbook.1.") {
                     let data: OrderBook = serde_json::from_value(stream.data)?;
                     let (bids, asks) = parse_depth(data.bids, data.asks)?;
 
+                    // Only the best bid and ask are subscribed, so the market depth is replaced by
+                    // them.
+                    let bbo_only = self.subscribed.get(&data.symbol) == Some(&DepthLevel::Bbo);
+                    if bbo_only {
+                        self.resyncing.remove(&data.symbol);
+                        self.ev_tx.send(PublishEvent::BatchStart(TO_ALL)).unwrap();
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                                symbol: data.symbol.clone(),
+                                event: Event {
+                                    ev: LOCAL_DEPTH_CLEAR_EVENT,
+                                    exch_ts: stream.cts.unwrap() * 1_000_000,
+                                    local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                                    order_id: 0,
+                                    px: 0.0,
+                                    qty: 0.0,
+                                    ival: 0,
+                                    fval: 0.0,
+                                },
+                            }))
+                            .unwrap();
+                    }
+
                     for (px, qty) in bids {
                         self.ev_tx
                             .send(PublishEvent::LiveEvent(LiveEvent::Feed {
@@ -93,10 +169,67 @@ impl PublicStream {
                             }))
                             .unwrap();
                     }
+
+                    if bbo_only {
+                        self.ev_tx.send(PublishEvent::BatchEnd(TO_ALL)).unwrap();
+                    }
                 } else if stream.topic.starts_with("orderbook") {
                     let data: OrderBook = serde_json::from_value(stream.data)?;
+                    if stream.ty.as_deref() == Some("snapshot") {
+                        self.update_ids.insert(stream.topic.clone(), data.update_id);
+                    } else if let Some(prev_update_id) = self.update_ids.get_mut(&stream.topic) {
+                        if data.update_id != *prev_update_id + 1 {
+                            // Applying the update over the missing updates would corrupt the
+                            // market depth, so it's recovered from a fresh snapshot instead.
+                            warn!(
+                                topic = stream.topic,
+                                expected = *prev_update_id + 1,
+                                received = data.update_id,
+                                "A gap in the order book updates is detected. Requests a new snapshot."
+                            );
+                            let mut map = HashMap::new();
+                            map.insert("symbol".to_string(), Value::String(data.symbol.clone()));
+                            map.insert("expected".to_string(), Value::Int(*prev_update_id + 1));
+                            map.insert("received".to_string(), Value::Int(data.update_id));
+                            self.ev_tx
+                                .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                                    ErrorKind::SeqGap,
+                                    Value::Map(map),
+                                ))))
+                                .unwrap();
+                            if !self.resyncing.contains(&data.symbol) {
+                                self.gapped.push(data.symbol.clone());
+                            }
+                            *prev_update_id = data.update_id;
+                            return Ok(());
+                        }
+                        *prev_update_id = data.update_id;
+                    }
                     let (bids, asks) = parse_depth(data.bids, data.asks)?;
 
+                    // The fresh snapshot from the resubscription replaces the current market
+                    // depth.
+                    if stream.topic == self.deepest_topic(&data.symbol)
+                        && stream.ty.as_deref() == Some("snapshot")
+                        && self.resyncing.remove(&data.symbol)
+                    {
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                                symbol: data.symbol.clone(),
+                                event: Event {
+                                    ev: LOCAL_DEPTH_CLEAR_EVENT,
+                                    exch_ts: stream.cts.unwrap() * 1_000_000,
+                                    local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                                    order_id: 0,
+                                    px: 0.0,
+                                    qty: 0.0,
+                                    ival: 0,
+                                    fval: 0.0,
+                                },
+                            }))
+                            .unwrap();
+                    }
+
                     for (px, qty) in bids {
                         self.ev_tx
                             .send(PublishEvent::LiveEvent(LiveEvent::Feed {
@@ -157,12 +290,117 @@ impl PublicStream {
                             }))
                             .unwrap();
                     }
+                } else if stream.topic.starts_with("tickers") {
+                    let data: msg::Ticker = serde_json::from_value(stream.data)?;
+                    // The delta only contains the changed fields, and the spot tickers don't have
+                    // the open interest.
+                    if let Some(open_interest) = data.open_interest {
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                                symbol: data.symbol,
+                                event: Event {
+                                    ev: LOCAL_OPEN_INTEREST_EVENT,
+                                    exch_ts: stream.ts * 1_000_000,
+                                    local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                                    order_id: 0,
+                                    px: 0.0,
+                                    qty: open_interest,
+                                    ival: 0,
+                                    fval: 0.0,
+                                },
+                            }))
+                            .unwrap();
+                    }
+                } else if stream.topic.starts_with("allLiquidation") {
+                    let data: Vec<msg::Liquidation> = serde_json::from_value(stream.data)?;
+                    for item in data {
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                                symbol: item.symbol,
+                                event: Event {
+                                    // The liquidation order is on the opposite side of the
+                                    // liquidated position.
+                                    ev: {
+                                        if item.side == Side::Buy {
+                                            LOCAL_SELL_LIQUIDATION_EVENT
+                                        } else {
+                                            LOCAL_BUY_LIQUIDATION_EVENT
+                                        }
+                                    },
+                                    exch_ts: item.ts * 1_000_000,
+                                    local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                                    order_id: 0,
+                                    px: item.price,
+                                    qty: item.size,
+                                    ival: 0,
+                                    fval: 0.0,
+                                },
+                            }))
+                            .unwrap();
+                    }
                 }
             }
         }
         Ok(())
     }
 
+    /// Subscribes to the trades, the tickers, the liquidations and the order book topics of the
+    /// requested depth level. If the
+    /// symbol is already subscribed with another level, switches the order book topics, and the
+    /// market depth is replaced by the snapshot of the new deepest topic.
+    async fn subscribe<S>(&mut self, write: &mut S, symbol: &str) -> Result<(), BybitError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        let level = self
+            .depth_levels
+            .lock()
+            .unwrap()
+            .get(symbol)
+            .copied()
+            .unwrap_or_default();
+        let topics = depth_topics(symbol, level);
+        let args = match self.subscribed.insert(symbol.to_string(), level) {
+            Some(prev) if prev == level => return Ok(()),
+            Some(prev) => {
+                let prev_topics = depth_topics(symbol, prev);
+                let unsubscribed: Vec<_> = prev_topics
+                    .iter()
+                    .filter(|topic| !topics.contains(topic))
+                    .cloned()
+                    .collect();
+                for topic in &unsubscribed {
+                    self.update_ids.remove(topic);
+                }
+                if !unsubscribed.is_empty() {
+                    send_op(write, "unsubscribe", unsubscribed).await?;
+                }
+                self.resyncing.insert(symbol.to_string());
+                topics
+                    .into_iter()
+                    .filter(|topic| !prev_topics.contains(topic))
+                    .collect()
+            }
+            None => {
+                // Subscribed separately, since the liquidation topic doesn't exist for the spot
+                // and a failed subscription shouldn't affect the market data topics.
+                send_op(
+                    write,
+                    "subscribe",
+                    vec![
+                        format!("tickers.{symbol}"),
+                        format!("allLiquidation.{symbol}"),
+                    ],
+                )
+                .await?;
+                let mut args = topics;
+                args.push(format!("publicTrade.{symbol}"));
+                args
+            }
+        };
+        send_op(write, "subscribe", args).await
+    }
+
     pub async fn connect(&mut self, url: &str) -> Result<(), BybitError> {
         let mut request = url.into_client_request()?;
         let _ = request.headers_mut();
@@ -171,6 +409,30 @@ impl PublicStream {
         let (mut write, mut read) = ws_stream.split();
         let mut interval = time::interval(Duration::from_secs(15));
 
+        // Resubscribes to the symbols registered before the reconnection. Their market depth is
+        // cleared since the updates during the disconnection are missing, and is replaced by the
+        // fresh snapshot sent upon the subscription.
+        let symbols: Vec<_> = self.symbols.lock().unwrap().iter().cloned().collect();
+        for symbol in symbols {
+            self.ev_tx
+                .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                    symbol: symbol.clone(),
+                    event: Event {
+                        ev: LOCAL_DEPTH_CLEAR_EVENT,
+                        exch_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                        local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                        order_id: 0,
+                        px: 0.0,
+                        qty: 0.0,
+                        ival: 0,
+                        fval: 0.0,
+                    },
+                }))
+                .unwrap();
+            self.subscribe(&mut write, &symbol).await?;
+            self.resyncing.insert(symbol);
+        }
+
         loop {
             select! {
                 _ = interval.tick() => {
@@ -184,23 +446,7 @@ impl PublicStream {
                 }
                 msg = self.symbol_rx.recv() => match msg {
                     Ok(symbol) => {
-                        // Subscribes to the orderbook.1, orderbook.50 and orderbook.500 topics to
-                        // obtain a wider range of depth and the most frequent updates.
-                        // The different updates are handled by data fusion.
-                        // Please see: `<https://bybit-exchange.github.io/docs/v5/websocket/public/orderbook>`
-                        let args = vec![
-                            format!("orderbook.1.{symbol}"),
-                            format!("orderbook.50.{symbol}"),
-                            format!("orderbook.500.{symbol}"),
-                            format!("publicTrade.{symbol}")
-                        ];
-                        let op = Op {
-                            req_id: "subscribe".to_string(),
-                            op: "subscribe".to_string(),
-                            args,
-                        };
-                        let s = serde_json::to_string(&op).unwrap();
-                        write.send(Message::Text(s)).await?;
+                        self.subscribe(&mut write, &symbol).await?;
                     }
                     Err(RecvError::Closed) => {
                         return Ok(());
@@ -209,12 +455,41 @@ impl PublicStream {
                         error!("{num} subscription requests were missed.");
                     }
                 },
+                msg = self.depth_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        if self.subscribed.contains_key(&symbol) {
+                            self.subscribe(&mut write, &symbol).await?;
+                        }
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} depth level changes were missed.");
+                    }
+                },
+                msg = self.resync_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        resubscribe(&mut write, &self.deepest_topic(&symbol)).await?;
+                        self.resyncing.insert(symbol);
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} resync requests were missed.");
+                    }
+                },
                 message = read.next() => {
                     match message {
                         Some(Ok(Message::Text(text))) => {
                             if let Err(error) = self.handle_public_stream(&text).await {
                                 error!(?error, %text, "Couldn't handle PublicStreamMsg.");
                             }
+                            for symbol in std::mem::take(&mut self.gapped) {
+                                resubscribe(&mut write, &self.deepest_topic(&symbol)).await?;
+                                self.resyncing.insert(symbol);
+                            }
                         }
                         Some(Ok(Message::Ping(_))) => {
                             write.send(Message::Pong(Vec::new())).await?;
@@ -241,3 +516,47 @@ impl PublicStream {
         }
     }
 }
+
+async fn resubscribe<S>(write: &mut S, topic: &str) -> Result<(), BybitError>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    // Resubscribes to the deepest order book topic, which sends a fresh snapshot.
+    for op in ["unsubscribe", "subscribe"] {
+        send_op(write, op, vec![topic.to_string()]).await?;
+    }
+    Ok(())
+}
+
+/// Returns the order book topics that provide the market depth of the level, the deepest last.
+///
+/// The orderbook.1 topic is always subscribed, since it has the most frequent updates. For the full
+/// market depth, the orderbook.1, orderbook.50 and orderbook.500 topics are subscribed to obtain a
+/// wider range of depth, and the different updates are handled by data fusion.
+/// Please see: `<https://bybit-exchange.github.io/docs/v5/websocket/public/orderbook>`
+fn depth_topics(symbol: &str, level: DepthLevel) -> Vec<String> {
+    let depths: &[usize] = match level {
+        DepthLevel::Bbo => &[1],
+        DepthLevel::Top(n) if n <= 50 => &[1, 50],
+        DepthLevel::Top(n) if n <= 200 => &[1, 200],
+        DepthLevel::Top(_) | DepthLevel::Full => &[1, 50, 500],
+    };
+    depths
+        .iter()
+        .map(|depth| format!("orderbook.{depth}.{symbol}"))
+        .collect()
+}
+
+async fn send_op<S>(write: &mut S, op: &str, args: Vec<String>) -> Result<(), BybitError>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    let op = Op {
+        req_id: op.to_string(),
+        op: op.to_string(),
+        args,
+    };
+    let s = serde_json::to_string(&op).unwrap();
+    write.send(Message::Text(s)).await?;
+    Ok(())
+}
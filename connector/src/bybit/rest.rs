@@ -3,7 +3,7 @@ use serde::Deserialize;
 
 use crate::{
     bybit::{
-        msg::{Position, RestResponse},
+        msg::{Instrument, Position, RestOrder, RestResponse},
         BybitError,
     },
     utils::sign_hmac_sha256,
@@ -114,4 +114,47 @@ impl BybitClient {
             Ok(position)
         }
     }
+
+    pub async fn get_order(
+        &self,
+        category: &str,
+        symbol: &str,
+        order_link_id: &str,
+    ) -> Result<Option<RestOrder>, BybitError> {
+        let resp: RestResponse = self
+            .get(
+                "/v5/order/realtime",
+                &format!("category={category}&symbol={symbol}&orderLinkId={order_link_id}"),
+                &self.api_key,
+                &self.secret,
+            )
+            .await?;
+        if resp.ret_code != 0 {
+            Err(BybitError::OpError(resp.ret_msg))
+        } else {
+            let orders: Vec<RestOrder> = serde_json::from_value(resp.result.list.unwrap())?;
+            Ok(orders.into_iter().next())
+        }
+    }
+
+    pub async fn get_instrument(
+        &self,
+        category: &str,
+        symbol: &str,
+    ) -> Result<Option<Instrument>, BybitError> {
+        let resp: RestResponse = self
+            .get(
+                "/v5/market/instruments-info",
+                &format!("category={category}&symbol={symbol}"),
+                &self.api_key,
+                &self.secret,
+            )
+            .await?;
+        if resp.ret_code != 0 {
+            Err(BybitError::OpError(resp.ret_msg))
+        } else {
+            let instruments: Vec<Instrument> = serde_json::from_value(resp.result.list.unwrap())?;
+            Ok(instruments.into_iter().next())
+        }
+    }
 }
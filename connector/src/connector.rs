@@ -1,58 +1 @@
-use std::{
-    fmt::Debug,
-    sync::{Arc, Mutex},
-};
-
-use hftbacktest::types::{LiveEvent, Order};
-use tokio::sync::mpsc::UnboundedSender;
-
-/// A message will be received by the publisher thread and then published to the bots.
-pub enum PublishEvent {
-    BatchStart(u64),
-    BatchEnd(u64),
-    LiveEvent(LiveEvent),
-    RegisterInstrument {
-        id: u64,
-        symbol: String,
-        tick_size: f64,
-    },
-}
-
-/// Provides a build function for the Connector.
-pub trait ConnectorBuilder {
-    type Error: Debug;
-
-    fn build_from(config: &str) -> Result<Self, Self::Error>
-    where
-        Self: Sized;
-}
-
-/// Provides an interface for connecting with an exchange or broker for a live bot.
-pub trait Connector {
-    /// Registers an instrument to be traded through this connector.
-    fn register(&mut self, symbol: String);
-
-    /// Returns an [`OrderManager`].
-    fn order_manager(&self) -> Arc<Mutex<dyn GetOrders + Send + 'static>>;
-
-    /// Runs the connector, establishing the connection and preparing to exchange information such
-    /// as data feed and orders. This method should not block, and any response should be returned
-    /// through the channel using [`PublishEvent`]. The returned error should not be related to the
-    /// exchange; instead, it should indicate a connector internal error.
-    fn run(&mut self, tx: UnboundedSender<PublishEvent>);
-
-    /// Submits a new order. This method should not block, and the response should be returned
-    /// through the channel using [`PublishEvent`]. The returned error should not be related to the
-    /// exchange; instead, it should indicate a connector internal error.
-    fn submit(&self, symbol: String, order: Order, tx: UnboundedSender<PublishEvent>);
-
-    /// Cancels an open order. This method should not block, and the response should be returned
-    /// through the channel using [`PublishEvent`]. The returned error should not be related to the
-    /// exchange; instead, it should indicate a connector internal error.
-    fn cancel(&self, symbol: String, order: Order, tx: UnboundedSender<PublishEvent>);
-}
-
-/// Provides `orders` method to get the current working orders.
-pub trait GetOrders {
-    fn orders(&self, symbol: Option<String>) -> Vec<Order>;
-}
+pub use connector_sdk::{Connector, ConnectorBuilder, GetOrders, PublishEvent};
@@ -0,0 +1,246 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use hftbacktest::types::{ErrorKind, LiveError, LiveEvent, Order, Value};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::{broadcast, broadcast::Sender, mpsc::UnboundedSender};
+use tokio_tungstenite::tungstenite;
+use tracing::error;
+
+use crate::{
+    connector::{Connector, ConnectorBuilder, GetOrders, PublishEvent},
+    deribit::{
+        ordermanager::{OrderManager, OrderOp, SharedOrderManager},
+        stream::DeribitStream,
+    },
+    utils::{ExponentialBackoff, Retry},
+};
+
+#[allow(dead_code)]
+mod msg;
+mod ordermanager;
+mod stream;
+
+#[derive(Error, Debug)]
+pub enum DeribitError {
+    #[error("AuthError: {code} - {msg}")]
+    AuthError { code: i64, msg: String },
+    #[error("RpcError: {code} - {msg}")]
+    RpcError { code: i64, msg: String },
+    #[error("PrefixUnmatched")]
+    PrefixUnmatched,
+    #[error("OrderNotFound")]
+    OrderNotFound,
+    #[error("InvalidArg: {0}")]
+    InvalidArg(&'static str),
+    #[error("OrderAlreadyExist")]
+    OrderAlreadyExist,
+    #[error("Serde: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("Tungstenite: {0}")]
+    Tungstenite(Box<tungstenite::Error>),
+    #[error("ConnectionAbort: {0}")]
+    ConnectionAbort(String),
+    #[error("ConnectionInterrupted")]
+    ConnectionInterrupted,
+    #[error("Config: {0:?}")]
+    Config(#[from] toml::de::Error),
+}
+
+// The tungstenite error is over 100 bytes, so it is boxed to keep `DeribitError` small.
+impl From<tungstenite::Error> for DeribitError {
+    fn from(error: tungstenite::Error) -> Self {
+        DeribitError::Tungstenite(Box::new(error))
+    }
+}
+
+impl DeribitError {
+    pub fn to_value(&self) -> Value {
+        match self {
+            DeribitError::AuthError { code, msg } | DeribitError::RpcError { code, msg } => {
+                Value::Map({
+                    let mut map = HashMap::new();
+                    map.insert("code".to_string(), Value::Int(*code));
+                    map.insert("msg".to_string(), Value::String(msg.clone()));
+                    map
+                })
+            }
+            DeribitError::PrefixUnmatched => Value::String(self.to_string()),
+            DeribitError::OrderNotFound => Value::String(self.to_string()),
+            DeribitError::InvalidArg(_) => Value::String(self.to_string()),
+            DeribitError::OrderAlreadyExist => Value::String(self.to_string()),
+            DeribitError::Serde(_) => Value::String(self.to_string()),
+            DeribitError::Tungstenite(_) => Value::String(self.to_string()),
+            DeribitError::ConnectionAbort(_) => Value::String(self.to_string()),
+            DeribitError::ConnectionInterrupted => Value::String(self.to_string()),
+            DeribitError::Config(_) => Value::String(self.to_string()),
+        }
+    }
+}
+
+fn default_currencies() -> Vec<String> {
+    vec!["any".to_string()]
+}
+
+#[derive(Deserialize)]
+pub struct Config {
+    ws_url: String,
+    client_id: String,
+    client_secret: String,
+    #[serde(default)]
+    order_prefix: String,
+    /// The currencies of which the portfolio margin updates are received. `any` receives the
+    /// updates of all currencies.
+    #[serde(default = "default_currencies")]
+    currencies: Vec<String>,
+}
+
+type SharedSymbolSet = Arc<Mutex<HashSet<String>>>;
+
+/// A connector for Deribit, which covers options as well as futures and perpetuals.
+///
+/// Symbols are Deribit instrument names, such as `BTC-PERPETUAL` or `BTC-27DEC24-100000-C`.
+pub struct Deribit {
+    config: Config,
+    order_tx: Sender<OrderOp>,
+    order_manager: SharedOrderManager,
+    symbols: SharedSymbolSet,
+    symbol_tx: Sender<String>,
+    resync_tx: Sender<String>,
+}
+
+impl Deribit {
+    fn connect_stream(&self, ev_tx: UnboundedSender<PublishEvent>) {
+        let ws_url = self.config.ws_url.clone();
+        let client_id = self.config.client_id.clone();
+        let client_secret = self.config.client_secret.clone();
+        let currencies = self.config.currencies.clone();
+        let order_manager = self.order_manager.clone();
+        let symbols = self.symbols.clone();
+        let symbol_tx = self.symbol_tx.clone();
+        let resync_tx = self.resync_tx.clone();
+        let order_tx = self.order_tx.clone();
+
+        tokio::spawn(async move {
+            let _ = Retry::new(ExponentialBackoff::default())
+                .error_handler(|error: DeribitError| {
+                    error!(?error, "An error occurred in the stream connection.");
+                    // The stream is authenticated again when it reconnects.
+                    let kind = if matches!(error, DeribitError::AuthError { .. }) {
+                        ErrorKind::AuthFailure
+                    } else {
+                        ErrorKind::ConnectionInterrupted
+                    };
+                    ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                            kind,
+                            error.to_value(),
+                        ))))
+                        .unwrap();
+                    Ok(())
+                })
+                .retry(|| async {
+                    let mut stream = DeribitStream::new(
+                        client_id.clone(),
+                        client_secret.clone(),
+                        currencies.clone(),
+                        ev_tx.clone(),
+                        order_manager.clone(),
+                        symbols.clone(),
+                        symbol_tx.subscribe(),
+                        resync_tx.subscribe(),
+                        order_tx.subscribe(),
+                    );
+                    stream.connect(&ws_url).await?;
+                    Ok(())
+                })
+                .await;
+        });
+    }
+}
+
+impl ConnectorBuilder for Deribit {
+    type Error = DeribitError;
+
+    fn build_from(config: &str) -> Result<Self, Self::Error> {
+        let config: Config = toml::from_str(config)?;
+        if config.order_prefix.len() > 16 {
+            panic!("order prefix length should be not greater than 16.");
+        }
+        let (order_tx, _) = broadcast::channel(500);
+        let (symbol_tx, _) = broadcast::channel(500);
+        let (resync_tx, _) = broadcast::channel(500);
+        let order_manager = Arc::new(Mutex::new(OrderManager::new(&config.order_prefix)));
+        Ok(Deribit {
+            config,
+            order_tx,
+            order_manager,
+            symbols: Default::default(),
+            symbol_tx,
+            resync_tx,
+        })
+    }
+}
+
+impl Connector for Deribit {
+    fn register(&mut self, symbol: String, _tick_size: f64, _lot_size: f64) {
+        let mut symbols = self.symbols.lock().unwrap();
+        if !symbols.contains(&symbol) {
+            symbols.insert(symbol.clone());
+            self.symbol_tx.send(symbol).unwrap();
+        }
+    }
+
+    fn order_manager(&self) -> Arc<Mutex<dyn GetOrders + Send + 'static>> {
+        self.order_manager.clone()
+    }
+
+    fn run(&mut self, ev_tx: UnboundedSender<PublishEvent>) {
+        self.connect_stream(ev_tx);
+    }
+
+    fn submit(&self, symbol: String, order: Order, ev_tx: UnboundedSender<PublishEvent>) {
+        match self.order_manager.lock().unwrap().new_order(&symbol, order) {
+            Ok(op) => {
+                self.order_tx.send(op).unwrap();
+            }
+            Err(error) => {
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.to_value(),
+                    ))))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn cancel(&self, symbol: String, order: Order, ev_tx: UnboundedSender<PublishEvent>) {
+        match self
+            .order_manager
+            .lock()
+            .unwrap()
+            .cancel_order(&symbol, order.order_id)
+        {
+            Ok(op) => {
+                self.order_tx.send(op).unwrap();
+            }
+            Err(error) => {
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.to_value(),
+                    ))))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn resync(&self, symbol: String, _ev_tx: UnboundedSender<PublishEvent>) {
+        // The stream resubscribes to the order book to receive a fresh snapshot.
+        self.resync_tx.send(symbol).unwrap();
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt;
+
+use hftbacktest::types::{Side, Status};
+use serde::{
+    de,
+    de::{Error, Unexpected, Visitor},
+    Deserialize,
+    Deserializer,
+    Serialize,
+};
+
+struct SideVisitor;
+
+impl<'de> Visitor<'de> for SideVisitor {
+    type Value = Side;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string containing \"buy\" or \"sell\"")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match s {
+            "buy" => Ok(Side::Buy),
+            "sell" => Ok(Side::Sell),
+            s => Err(Error::invalid_value(Unexpected::Other(s), &"buy or sell")),
+        }
+    }
+}
+
+fn from_str_to_side<'de, D>(deserializer: D) -> Result<Side, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(SideVisitor)
+}
+
+struct StatusVisitor;
+
+impl<'de> Visitor<'de> for StatusVisitor {
+    type Value = Status;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string containing an order state")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match s {
+            "open" | "untriggered" | "triggered" => Ok(Status::New),
+            "filled" => Ok(Status::Filled),
+            "cancelled" => Ok(Status::Canceled),
+            "rejected" => Ok(Status::Rejected),
+            _ => Ok(Status::Unsupported),
+        }
+    }
+}
+
+fn from_str_to_status<'de, D>(deserializer: D) -> Result<Status, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(StatusVisitor)
+}
+
+struct PriceVisitor;
+
+impl<'de> Visitor<'de> for PriceVisitor {
+    type Value = Option<f64>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number or \"market_price\"")
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(v as f64))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Some(v as f64))
+    }
+
+    fn visit_str<E>(self, _s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        // A market order has the price of `market_price`.
+        Ok(None)
+    }
+}
+
+fn to_price_opt<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(PriceVisitor)
+}
+
+#[derive(Serialize, Debug)]
+pub struct Request {
+    pub jsonrpc: &'static str,
+    pub id: u64,
+    pub method: String,
+    pub params: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum StreamMsg {
+    Response(Response),
+    Notification(Notification),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub id: u64,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<RpcError>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Notification {
+    pub method: String,
+    pub params: NotificationParams,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NotificationParams {
+    #[serde(default)]
+    pub channel: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub ty: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Book {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub timestamp: i64,
+    pub instrument_name: String,
+    pub change_id: i64,
+    #[serde(default)]
+    pub prev_change_id: Option<i64>,
+    /// `[action, price, amount]`, where the action is one of `new`, `change` and `delete`.
+    pub bids: Vec<(String, f64, f64)>,
+    pub asks: Vec<(String, f64, f64)>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Trade {
+    pub instrument_name: String,
+    pub price: f64,
+    pub amount: f64,
+    #[serde(deserialize_with = "from_str_to_side")]
+    pub direction: Side,
+    pub timestamp: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UserChanges {
+    pub instrument_name: String,
+    #[serde(default)]
+    pub trades: Vec<UserTrade>,
+    #[serde(default)]
+    pub orders: Vec<UserOrder>,
+    #[serde(default)]
+    pub positions: Vec<Position>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UserTrade {
+    pub instrument_name: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub trade_id: String,
+    pub price: f64,
+    pub amount: f64,
+    #[serde(default)]
+    pub fee: f64,
+    /// `M` for maker or `T` for taker.
+    #[serde(default)]
+    pub liquidity: String,
+    pub timestamp: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UserOrder {
+    pub instrument_name: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(deserialize_with = "from_str_to_status")]
+    pub order_state: Status,
+    #[serde(deserialize_with = "to_price_opt")]
+    pub price: Option<f64>,
+    pub amount: f64,
+    pub filled_amount: f64,
+    pub last_update_timestamp: i64,
+}
+
+/// The response of `private/buy` and `private/sell`.
+#[derive(Deserialize, Debug)]
+pub struct OrderResult {
+    pub order: UserOrder,
+    #[serde(default)]
+    pub trades: Vec<UserTrade>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Position {
+    pub instrument_name: String,
+    pub size: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Portfolio {
+    pub currency: String,
+    pub equity: f64,
+    pub balance: f64,
+    pub margin_balance: f64,
+    pub initial_margin: f64,
+    pub maintenance_margin: f64,
+    pub available_funds: f64,
+}
@@ -0,0 +1,239 @@
+use std::sync::{Arc, Mutex};
+
+use hashbrown::HashMap;
+use hftbacktest::{
+    prelude::get_precision,
+    types::{OrdType, Order, OrderId, PositionSide, Side, Status, TimeInForce},
+};
+use serde_json::json;
+
+use crate::{
+    connector::GetOrders,
+    deribit::{
+        msg::{UserOrder, UserTrade},
+        DeribitError,
+    },
+    utils::{generate_rand_string, RefSymbolOrderId, SymbolOrderId},
+};
+
+pub type SharedOrderManager = Arc<Mutex<OrderManager>>;
+
+/// The user-defined label of the order, which identifies the order created by this connector.
+pub type Label = String;
+
+#[derive(Clone)]
+pub struct OrderExt {
+    pub symbol: String,
+    pub order: Order,
+}
+
+/// A request to the exchange regarding an order.
+#[derive(Clone, Debug)]
+pub struct OrderOp {
+    pub method: &'static str,
+    pub label: Label,
+    pub params: serde_json::Value,
+}
+
+pub struct OrderManager {
+    prefix: String,
+    orders: HashMap<Label, OrderExt>,
+    order_id_map: HashMap<SymbolOrderId, Label>,
+}
+
+impl OrderManager {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            orders: Default::default(),
+            order_id_map: Default::default(),
+        }
+    }
+
+    pub fn update_order(&mut self, data: &UserOrder) -> Result<OrderExt, DeribitError> {
+        if !data.label.starts_with(&self.prefix) {
+            return Err(DeribitError::PrefixUnmatched);
+        }
+        let order = self
+            .orders
+            .get_mut(&data.label)
+            .ok_or(DeribitError::OrderNotFound)?;
+        order.order.req = Status::None;
+        order.order.status = if data.order_state == Status::New && data.filled_amount > 0.0 {
+            Status::PartiallyFilled
+        } else {
+            data.order_state
+        };
+        order.order.leaves_qty = data.amount - data.filled_amount;
+        order.order.exec_qty = 0.0;
+        order.order.exch_timestamp = data.last_update_timestamp * 1_000_000;
+        if !order.order.active() {
+            self.order_id_map
+                .remove(&RefSymbolOrderId::new(&order.symbol, order.order.order_id));
+            Ok(self.orders.remove(&data.label).unwrap())
+        } else {
+            Ok(order.clone())
+        }
+    }
+
+    pub fn update_trade(&mut self, data: &UserTrade) -> Result<OrderExt, DeribitError> {
+        if !data.label.starts_with(&self.prefix) {
+            return Err(DeribitError::PrefixUnmatched);
+        }
+        let order = self
+            .orders
+            .get_mut(&data.label)
+            .ok_or(DeribitError::OrderNotFound)?;
+        order.order.exec_price_tick = (data.price / order.order.tick_size).round() as i64;
+        order.order.exec_qty = data.amount;
+        order.order.exch_timestamp = data.timestamp * 1_000_000;
+        order.order.maker = data.liquidity == "M";
+        Ok(order.clone())
+    }
+
+    pub fn new_order(&mut self, symbol: &str, order: Order) -> Result<OrderOp, DeribitError> {
+        let label = format!("{}{}", self.prefix, generate_rand_string(16));
+        let method = match order.side {
+            Side::Buy => "private/buy",
+            Side::Sell => "private/sell",
+            Side::None | Side::Unsupported => return Err(DeribitError::InvalidArg("side")),
+        };
+        let mut params = json!({
+            "instrument_name": symbol,
+            "amount": order.qty,
+            "label": label,
+        });
+        match order.order_type {
+            OrdType::Limit => {
+                params["type"] = json!("limit");
+                // Rounds off the floating-point error of the multiplication.
+                let price = format!(
+                    "{:.prec$}",
+                    order.price_tick as f64 * order.tick_size,
+                    prec = get_precision(order.tick_size)
+                );
+                params["price"] = json!(price.parse::<f64>().unwrap());
+            }
+            OrdType::Market => {
+                params["type"] = json!("market");
+            }
+            OrdType::Unsupported => return Err(DeribitError::InvalidArg("order_type")),
+        }
+        match order.time_in_force {
+            TimeInForce::GTC => {
+                params["time_in_force"] = json!("good_til_cancelled");
+            }
+            TimeInForce::GTX => {
+                params["time_in_force"] = json!("good_til_cancelled");
+                params["post_only"] = json!(true);
+                params["reject_post_only"] = json!(true);
+            }
+            TimeInForce::FOK => {
+                params["time_in_force"] = json!("fill_or_kill");
+            }
+            TimeInForce::IOC => {
+                params["time_in_force"] = json!("immediate_or_cancel");
+            }
+            TimeInForce::Unsupported => return Err(DeribitError::InvalidArg("time_in_force")),
+        }
+        if order.reduce_only {
+            params["reduce_only"] = json!(true);
+        }
+        // Deribit doesn't have hedge mode.
+        if order.position_side != PositionSide::Both {
+            return Err(DeribitError::InvalidArg("position_side"));
+        }
+
+        let symbol_order_id = SymbolOrderId::new(symbol.to_string(), order.order_id);
+        if self.order_id_map.contains_key(&symbol_order_id) {
+            return Err(DeribitError::OrderAlreadyExist);
+        }
+
+        self.order_id_map.insert(symbol_order_id, label.clone());
+        self.orders.insert(
+            label.clone(),
+            OrderExt {
+                symbol: symbol.to_string(),
+                order,
+            },
+        );
+        Ok(OrderOp {
+            method,
+            label,
+            params,
+        })
+    }
+
+    pub fn cancel_order(
+        &mut self,
+        symbol: &str,
+        order_id: OrderId,
+    ) -> Result<OrderOp, DeribitError> {
+        let label = self
+            .order_id_map
+            .get(&RefSymbolOrderId::new(symbol, order_id))
+            .ok_or(DeribitError::OrderNotFound)?;
+        Ok(OrderOp {
+            method: "private/cancel_by_label",
+            label: label.clone(),
+            params: json!({
+                "label": label,
+            }),
+        })
+    }
+
+    pub fn update_submit_fail(&mut self, label: &str) -> Result<OrderExt, DeribitError> {
+        let mut order = self
+            .orders
+            .remove(label)
+            .ok_or(DeribitError::OrderNotFound)?;
+        order.order.req = Status::None;
+        order.order.status = Status::Expired;
+        self.order_id_map
+            .remove(&RefSymbolOrderId::new(&order.symbol, order.order.order_id));
+        Ok(order)
+    }
+
+    pub fn update_cancel_fail(&mut self, label: &str) -> Result<OrderExt, DeribitError> {
+        let mut order = self
+            .orders
+            .get(label)
+            .cloned()
+            .ok_or(DeribitError::OrderNotFound)?;
+        order.order.req = Status::None;
+        Ok(order)
+    }
+
+    pub fn cancel_all(&mut self, symbol: &str) -> Vec<Order> {
+        let mut removed_labels = Vec::new();
+        for (label, order_ext) in &mut self.orders {
+            if order_ext.symbol != symbol {
+                continue;
+            }
+
+            order_ext.order.status = Status::Canceled;
+
+            self.order_id_map
+                .remove(&RefSymbolOrderId::new(symbol, order_ext.order.order_id));
+            removed_labels.push(label.clone());
+        }
+
+        removed_labels
+            .iter()
+            .map(|label| self.orders.remove(label).unwrap().order)
+            .collect()
+    }
+}
+
+impl GetOrders for OrderManager {
+    fn orders(&self, symbol: Option<String>) -> Vec<Order> {
+        self.orders
+            .iter()
+            .filter(|(_, order)| {
+                symbol.as_ref().map(|s| order.symbol == *s).unwrap_or(true) && order.order.active()
+            })
+            .map(|(_, order)| &order.order)
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,800 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono::Utc;
+use connector_sdk::{FeedError, MarketDataHandler};
+use futures_util::{Sink, SinkExt, StreamExt};
+use hftbacktest::prelude::{
+    ErrorKind,
+    Event,
+    Fill,
+    LiveError,
+    LiveEvent,
+    MarginState,
+    PositionSide,
+    LOCAL_DEPTH_CLEAR_EVENT,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    select,
+    sync::{
+        broadcast::{error::RecvError, Receiver},
+        mpsc::UnboundedSender,
+    },
+    time,
+};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite,
+    tungstenite::{client::IntoClientRequest, Message},
+};
+use tracing::{debug, error, warn};
+
+use crate::{
+    connector::PublishEvent,
+    deribit::{
+        msg::{
+            Book,
+            Notification,
+            OrderResult,
+            Portfolio,
+            Request,
+            Response,
+            StreamMsg,
+            Trade,
+            UserChanges,
+            UserOrder,
+            UserTrade,
+        },
+        ordermanager::{OrderOp, SharedOrderManager},
+        DeribitError,
+        SharedSymbolSet,
+    },
+    toolkit::{
+        decode_error,
+        feed::{publish_depth, publish_seq_gap, publish_trade},
+    },
+};
+
+/// The interval in seconds at which the exchange sends a heartbeat that must be answered.
+const HEARTBEAT_INTERVAL: u64 = 30;
+
+/// A request awaiting its response.
+enum Pending {
+    Auth,
+    Subscribe,
+    Submit { label: String },
+    Cancel { label: String },
+    CancelAll { symbol: String },
+}
+
+/// Decodes the order book and trade notifications. The order book updates are chained by the
+/// change ID, where an update follows the previous one if its `prev_change_id` matches.
+#[derive(Default)]
+pub struct MarketData {
+    change_ids: HashMap<String, i64>,
+}
+
+impl MarketData {
+    fn handle_book(
+        &mut self,
+        data: Book,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        if data.ty == "snapshot" {
+            self.change_ids
+                .insert(data.instrument_name.clone(), data.change_id);
+        } else {
+            let Some(expected) = self.change_ids.get(&data.instrument_name).copied() else {
+                // Awaits the snapshot.
+                return Ok(());
+            };
+            if data.prev_change_id != Some(expected) {
+                self.change_ids.remove(&data.instrument_name);
+                return Err(FeedError::SeqGap {
+                    symbol: data.instrument_name,
+                    expected,
+                    received: data.prev_change_id.unwrap_or(0),
+                });
+            }
+            self.change_ids
+                .insert(data.instrument_name.clone(), data.change_id);
+        }
+
+        let to_levels = |levels: &[(String, f64, f64)]| {
+            levels
+                .iter()
+                .map(|(action, px, qty)| (*px, if action == "delete" { 0.0 } else { *qty }))
+                .collect::<Vec<_>>()
+        };
+        publish_depth(
+            ev_tx,
+            &data.instrument_name,
+            data.timestamp * 1_000_000,
+            &to_levels(&data.bids),
+            &to_levels(&data.asks),
+            data.ty == "snapshot",
+        );
+        Ok(())
+    }
+}
+
+impl MarketDataHandler for MarketData {
+    type Message = Notification;
+
+    fn handle(
+        &mut self,
+        msg: &Notification,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        if msg.params.channel.starts_with("book.") {
+            let data = Book::deserialize(&msg.params.data).map_err(decode_error)?;
+            self.handle_book(data, ev_tx)?;
+        } else if msg.params.channel.starts_with("trades.") {
+            let data = Vec::<Trade>::deserialize(&msg.params.data).map_err(decode_error)?;
+            for trade in data {
+                publish_trade(
+                    ev_tx,
+                    &trade.instrument_name,
+                    trade.direction,
+                    trade.timestamp * 1_000_000,
+                    trade.price,
+                    trade.amount,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.change_ids.clear();
+    }
+}
+
+/// Handles the market data, the user's orders, positions and portfolio margin, and the order
+/// requests through a single JSON-RPC connection.
+pub struct DeribitStream {
+    client_id: String,
+    client_secret: String,
+    currencies: Vec<String>,
+    ev_tx: UnboundedSender<PublishEvent>,
+    order_manager: SharedOrderManager,
+    symbols: SharedSymbolSet,
+    symbol_rx: Receiver<String>,
+    resync_rx: Receiver<String>,
+    order_rx: Receiver<OrderOp>,
+    next_id: u64,
+    pending: HashMap<u64, Pending>,
+    market_data: MarketData,
+    refresh_token: Option<String>,
+    refresh_in: Option<Duration>,
+    authenticated: bool,
+}
+
+impl DeribitStream {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client_id: String,
+        client_secret: String,
+        currencies: Vec<String>,
+        ev_tx: UnboundedSender<PublishEvent>,
+        order_manager: SharedOrderManager,
+        symbols: SharedSymbolSet,
+        symbol_rx: Receiver<String>,
+        resync_rx: Receiver<String>,
+        order_rx: Receiver<OrderOp>,
+    ) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            currencies,
+            ev_tx,
+            order_manager,
+            symbols,
+            symbol_rx,
+            resync_rx,
+            order_rx,
+            next_id: 0,
+            pending: Default::default(),
+            market_data: Default::default(),
+            refresh_token: None,
+            refresh_in: None,
+            authenticated: false,
+        }
+    }
+
+    async fn request<S>(
+        &mut self,
+        write: &mut S,
+        method: &str,
+        params: serde_json::Value,
+        pending: Option<Pending>,
+    ) -> Result<(), DeribitError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        self.next_id += 1;
+        let req = Request {
+            jsonrpc: "2.0",
+            id: self.next_id,
+            method: method.to_string(),
+            params,
+        };
+        if let Some(pending) = pending {
+            self.pending.insert(self.next_id, pending);
+        }
+        let s = serde_json::to_string(&req)?;
+        write.send(Message::Text(s)).await?;
+        Ok(())
+    }
+
+    async fn subscribe<S>(&mut self, write: &mut S, symbol: &str) -> Result<(), DeribitError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        // Cancels all orders in order to start with the clean state.
+        self.request(
+            write,
+            "private/cancel_all_by_instrument",
+            json!({ "instrument_name": symbol }),
+            Some(Pending::CancelAll {
+                symbol: symbol.to_string(),
+            }),
+        )
+        .await?;
+        self.request(
+            write,
+            "private/subscribe",
+            json!({
+                "channels": [
+                    format!("book.{symbol}.raw"),
+                    format!("trades.{symbol}.raw"),
+                    format!("user.changes.{symbol}.raw"),
+                ]
+            }),
+            Some(Pending::Subscribe),
+        )
+        .await
+    }
+
+    /// Resubscribes to the order book channel, which sends a fresh snapshot.
+    async fn resubscribe_book<S>(&mut self, write: &mut S, symbol: &str) -> Result<(), DeribitError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        self.market_data.change_ids.remove(symbol);
+        let channels = json!({ "channels": [format!("book.{symbol}.raw")] });
+        self.request(write, "private/unsubscribe", channels.clone(), None)
+            .await?;
+        self.request(
+            write,
+            "private/subscribe",
+            channels,
+            Some(Pending::Subscribe),
+        )
+        .await
+    }
+
+    async fn on_authenticated<S>(&mut self, write: &mut S) -> Result<(), DeribitError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        if self.authenticated {
+            // The session is refreshed.
+            return Ok(());
+        }
+        self.authenticated = true;
+
+        let channels: Vec<_> = self
+            .currencies
+            .iter()
+            .map(|currency| format!("user.portfolio.{currency}"))
+            .collect();
+        if !channels.is_empty() {
+            self.request(
+                write,
+                "private/subscribe",
+                json!({ "channels": channels }),
+                Some(Pending::Subscribe),
+            )
+            .await?;
+        }
+
+        // Subscribes to the symbols registered so far, including those registered before the
+        // reconnection. Their market depth is cleared since the updates during the disconnection
+        // are missing, and is replaced by the fresh snapshot sent upon the subscription.
+        let symbols: Vec<_> = self.symbols.lock().unwrap().iter().cloned().collect();
+        for symbol in symbols {
+            self.ev_tx
+                .send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                    symbol: symbol.clone(),
+                    event: Event {
+                        ev: LOCAL_DEPTH_CLEAR_EVENT,
+                        exch_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                        local_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                        order_id: 0,
+                        px: 0.0,
+                        qty: 0.0,
+                        ival: 0,
+                        fval: 0.0,
+                    },
+                }))
+                .unwrap();
+            self.subscribe(write, &symbol).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_response<S>(
+        &mut self,
+        write: &mut S,
+        resp: Response,
+    ) -> Result<(), DeribitError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        let Some(pending) = self.pending.remove(&resp.id) else {
+            debug!(?resp, "Response");
+            return Ok(());
+        };
+        match (pending, resp.error) {
+            (Pending::Auth, Some(error)) => {
+                return Err(DeribitError::AuthError {
+                    code: error.code,
+                    msg: error.message,
+                });
+            }
+            (Pending::Auth, None) => {
+                let result = resp.result.unwrap_or_default();
+                self.refresh_token = result["refresh_token"].as_str().map(|s| s.to_string());
+                // Refreshes the session well before the access token expires.
+                self.refresh_in = result["expires_in"]
+                    .as_u64()
+                    .map(|expires_in| Duration::from_secs(expires_in / 2));
+                self.on_authenticated(write).await?;
+            }
+            (Pending::Submit { label }, Some(error)) => {
+                let error = DeribitError::RpcError {
+                    code: error.code,
+                    msg: error.message,
+                };
+                match self
+                    .order_manager
+                    .lock()
+                    .unwrap()
+                    .update_submit_fail(&label)
+                {
+                    Ok(order) => {
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                                symbol: order.symbol,
+                                order: order.order,
+                            }))
+                            .unwrap();
+                    }
+                    Err(error) => {
+                        error!(?error, %label, "Couldn't update the submit failure.");
+                    }
+                }
+                self.ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.to_value(),
+                    ))))
+                    .unwrap();
+            }
+            (Pending::Submit { .. }, None) => {
+                let result: OrderResult = serde_json::from_value(resp.result.unwrap_or_default())?;
+                self.handle_user_trades(&result.trades);
+                self.handle_user_orders(&[result.order]);
+            }
+            (Pending::Cancel { label }, Some(error)) => {
+                let error = DeribitError::RpcError {
+                    code: error.code,
+                    msg: error.message,
+                };
+                match self
+                    .order_manager
+                    .lock()
+                    .unwrap()
+                    .update_cancel_fail(&label)
+                {
+                    Ok(order) => {
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                                symbol: order.symbol,
+                                order: order.order,
+                            }))
+                            .unwrap();
+                    }
+                    Err(error) => {
+                        error!(?error, %label, "Couldn't update the cancel failure.");
+                    }
+                }
+                self.ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.to_value(),
+                    ))))
+                    .unwrap();
+            }
+            (Pending::Cancel { .. }, None) => {
+                // The order update follows through the user changes channel.
+            }
+            (Pending::CancelAll { symbol }, None) => {
+                let orders = self.order_manager.lock().unwrap().cancel_all(&symbol);
+                for order in orders {
+                    self.ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                            symbol: symbol.clone(),
+                            order,
+                        }))
+                        .unwrap();
+                }
+            }
+            (Pending::CancelAll { symbol }, Some(error)) => {
+                error!(?error, %symbol, "Couldn't cancel all orders.");
+            }
+            (Pending::Subscribe, Some(error)) => {
+                error!(?error, "Couldn't subscribe to the channels.");
+            }
+            (Pending::Subscribe, None) => {
+                debug!(result = ?resp.result, "Subscribed.");
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_user_trades(&self, trades: &[UserTrade]) {
+        for trade in trades {
+            match self.order_manager.lock().unwrap().update_trade(trade) {
+                Ok(order) => {
+                    // Reports the execution ahead of the order update.
+                    self.ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Fill {
+                            symbol: order.symbol.clone(),
+                            fill: Fill::from_order(&order.order, trade.trade_id.clone(), trade.fee),
+                        }))
+                        .unwrap();
+                    self.ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                            symbol: order.symbol,
+                            order: order.order,
+                        }))
+                        .unwrap();
+                }
+                Err(DeribitError::PrefixUnmatched) => {
+                    // This order is not created by this connector.
+                }
+                Err(error) => {
+                    error!(?error, ?trade, "Couldn't update the order from the trade.");
+                }
+            }
+        }
+    }
+
+    fn handle_user_orders(&self, orders: &[UserOrder]) {
+        for data in orders {
+            match self.order_manager.lock().unwrap().update_order(data) {
+                Ok(order) => {
+                    self.ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                            symbol: order.symbol,
+                            order: order.order,
+                        }))
+                        .unwrap();
+                }
+                Err(DeribitError::PrefixUnmatched) => {
+                    // This order is not created by this connector.
+                }
+                Err(DeribitError::OrderNotFound) => {
+                    // This order is already deleted.
+                }
+                Err(error) => {
+                    error!(?error, ?data, "Couldn't update the order.");
+                }
+            }
+        }
+    }
+
+    fn handle_user_changes(&self, data: UserChanges) {
+        // The fills are applied before the order status, which may close the order.
+        self.handle_user_trades(&data.trades);
+        self.handle_user_orders(&data.orders);
+        for position in data.positions {
+            self.ev_tx
+                .send(PublishEvent::LiveEvent(LiveEvent::Position {
+                    symbol: position.instrument_name,
+                    side: PositionSide::Both,
+                    qty: position.size,
+                    exch_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                }))
+                .unwrap();
+        }
+        debug!(symbol = data.instrument_name, "UserChanges");
+    }
+
+    fn handle_portfolio(&self, data: Portfolio) {
+        self.ev_tx
+            .send(PublishEvent::LiveEvent(LiveEvent::Margin(MarginState {
+                currency: data.currency,
+                equity: data.equity,
+                balance: data.balance,
+                margin_balance: data.margin_balance,
+                initial_margin: data.initial_margin,
+                maintenance_margin: data.maintenance_margin,
+                available_funds: data.available_funds,
+                exch_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+            })))
+            .unwrap();
+    }
+
+    async fn handle_stream<S>(&mut self, write: &mut S, text: &str) -> Result<(), DeribitError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        match serde_json::from_str::<StreamMsg>(text)? {
+            StreamMsg::Response(resp) => {
+                self.handle_response(write, resp).await?;
+            }
+            StreamMsg::Notification(msg) => {
+                if msg.method == "heartbeat" {
+                    if msg.params.ty.as_deref() == Some("test_request") {
+                        self.request(write, "public/test", json!({}), None).await?;
+                    }
+                } else if msg.params.channel.starts_with("book.")
+                    || msg.params.channel.starts_with("trades.")
+                {
+                    match self.market_data.handle(&msg, &self.ev_tx) {
+                        Ok(()) => {}
+                        Err(FeedError::SeqGap {
+                            symbol,
+                            expected,
+                            received,
+                        }) => {
+                            // Applying the update over the missing updates would corrupt the
+                            // market depth, so it's recovered from a fresh snapshot instead.
+                            warn!(
+                                %symbol,
+                                expected,
+                                received,
+                                "A gap in the order book updates is detected. Requests a new snapshot."
+                            );
+                            publish_seq_gap(&self.ev_tx, &symbol, expected, received);
+                            self.resubscribe_book(write, &symbol).await?;
+                        }
+                        Err(error) => {
+                            error!(?error, "Couldn't handle the market data.");
+                        }
+                    }
+                } else if msg.params.channel.starts_with("user.changes.") {
+                    self.handle_user_changes(serde_json::from_value(msg.params.data)?);
+                } else if msg.params.channel.starts_with("user.portfolio.") {
+                    self.handle_portfolio(serde_json::from_value(msg.params.data)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn connect(&mut self, url: &str) -> Result<(), DeribitError> {
+        let request = url.into_client_request()?;
+        let (ws_stream, _) = connect_async(request).await?;
+        let (mut write, mut read) = ws_stream.split();
+
+        let params = json!({
+            "grant_type": "client_credentials",
+            "client_id": self.client_id,
+            "client_secret": self.client_secret,
+        });
+        self.request(&mut write, "public/auth", params, Some(Pending::Auth))
+            .await?;
+        self.request(
+            &mut write,
+            "public/set_heartbeat",
+            json!({ "interval": HEARTBEAT_INTERVAL }),
+            None,
+        )
+        .await?;
+
+        let refresh = time::sleep(Duration::MAX);
+        tokio::pin!(refresh);
+
+        loop {
+            if let Some(refresh_in) = self.refresh_in.take() {
+                refresh.as_mut().reset(time::Instant::now() + refresh_in);
+            }
+            select! {
+                _ = &mut refresh => {
+                    // Refreshes the session before the access token expires.
+                    if let Some(refresh_token) = self.refresh_token.take() {
+                        let params = json!({
+                            "grant_type": "refresh_token",
+                            "refresh_token": refresh_token,
+                        });
+                        self.request(&mut write, "public/auth", params, Some(Pending::Auth))
+                            .await?;
+                    }
+                    refresh.as_mut().reset(time::Instant::now() + Duration::from_secs(86400));
+                }
+                msg = self.symbol_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        // The symbols registered before the authentication are subscribed to
+                        // upon the authentication.
+                        if self.authenticated {
+                            self.subscribe(&mut write, &symbol).await?;
+                        }
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} subscription requests were missed.");
+                    }
+                },
+                msg = self.resync_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        self.resubscribe_book(&mut write, &symbol).await?;
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} resync requests were missed.");
+                    }
+                },
+                msg = self.order_rx.recv() => match msg {
+                    Ok(op) => {
+                        let pending = if op.method == "private/cancel_by_label" {
+                            Pending::Cancel { label: op.label }
+                        } else {
+                            Pending::Submit { label: op.label }
+                        };
+                        self.request(&mut write, op.method, op.params, Some(pending)).await?;
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} order requests were missed.");
+                    }
+                },
+                message = read.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        match self.handle_stream(&mut write, &text).await {
+                            Ok(()) => {}
+                            Err(error @ DeribitError::AuthError { .. }) => {
+                                return Err(error);
+                            }
+                            Err(error) => {
+                                error!(?error, %text, "Couldn't handle the stream message.");
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Ping(data))) => {
+                        write.send(Message::Pong(data)).await?;
+                    }
+                    Some(Ok(Message::Close(close_frame))) => {
+                        return Err(DeribitError::ConnectionAbort(
+                            close_frame.map(|f| f.to_string()).unwrap_or(String::new())
+                        ));
+                    }
+                    Some(Ok(Message::Binary(_)))
+                    | Some(Ok(Message::Frame(_)))
+                    | Some(Ok(Message::Pong(_))) => {}
+                    Some(Err(error)) => {
+                        return Err(DeribitError::from(error));
+                    }
+                    None => {
+                        return Err(DeribitError::ConnectionInterrupted);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use connector_sdk::{
+        conformance::{ConformanceSuite, VenueEncoder},
+        ordermanager::OrderUpdate,
+    };
+    use hftbacktest::prelude::Side;
+    use serde_json::json;
+
+    use super::MarketData;
+    use crate::{deribit::msg::Notification, utils::PxQty};
+
+    struct Encoder;
+
+    impl Encoder {
+        fn notification(channel: String, data: serde_json::Value) -> Notification {
+            serde_json::from_value(json!({
+                "jsonrpc": "2.0",
+                "method": "subscription",
+                "params": {
+                    "channel": channel,
+                    "data": data,
+                },
+            }))
+            .unwrap()
+        }
+
+        fn book(
+            symbol: &str,
+            ty: &str,
+            change_id: i64,
+            prev_change_id: Option<i64>,
+            bids: &[PxQty],
+            asks: &[PxQty],
+        ) -> Notification {
+            let levels = |levels: &[PxQty]| {
+                levels
+                    .iter()
+                    .map(|&(px, qty)| {
+                        let action = match (ty, qty) {
+                            ("snapshot", _) => "new",
+                            (_, 0.0) => "delete",
+                            _ => "change",
+                        };
+                        json!([action, px, qty])
+                    })
+                    .collect::<Vec<_>>()
+            };
+            Self::notification(
+                format!("book.{symbol}.raw"),
+                json!({
+                    "type": ty,
+                    "timestamp": 1,
+                    "instrument_name": symbol,
+                    "change_id": change_id,
+                    "prev_change_id": prev_change_id,
+                    "bids": levels(bids),
+                    "asks": levels(asks),
+                }),
+            )
+        }
+    }
+
+    impl VenueEncoder for Encoder {
+        type MarketData = Notification;
+        type OrderMessage = ();
+
+        fn snapshot(&self, symbol: &str, seq: i64, bids: &[PxQty], asks: &[PxQty]) -> Notification {
+            Self::book(symbol, "snapshot", seq, None, bids, asks)
+        }
+
+        fn depth_update(
+            &self,
+            symbol: &str,
+            seq: i64,
+            bids: &[PxQty],
+            asks: &[PxQty],
+        ) -> Vec<Notification> {
+            vec![Self::book(symbol, "change", seq, Some(seq - 1), bids, asks)]
+        }
+
+        fn trade(&self, symbol: &str, side: Side, px: f64, qty: f64) -> Notification {
+            let direction = if side == Side::Sell { "sell" } else { "buy" };
+            Self::notification(
+                format!("trades.{symbol}.raw"),
+                json!([{
+                    "instrument_name": symbol,
+                    "price": px,
+                    "amount": qty,
+                    "direction": direction,
+                    "timestamp": 1,
+                }]),
+            )
+        }
+
+        fn order_update(&self, _symbol: &str, _client_order_id: &str, _update: &OrderUpdate) {}
+    }
+
+    #[test]
+    fn conformance() {
+        let suite = ConformanceSuite::new(Encoder, "BTC-PERPETUAL");
+        suite.run_market_data(MarketData::default).unwrap();
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use connector_sdk::{FeedError, MarketDataHandler};
+use hftbacktest::prelude::Side;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{error, warn};
+
+use crate::{
+    connector::PublishEvent,
+    fix::{
+        message::{msg_type, parse_timestamp, tag, FixMessage},
+        profile::VenueProfile,
+        session::{Application, GapAction},
+    },
+    toolkit::{
+        feed::{publish_clear, publish_depth, publish_seq_gap, publish_trade},
+        symbol::SharedSymbolNormalizer,
+    },
+    utils::PxQty,
+};
+
+/// Builds the `MarketDataRequest` of the symbol's bids, offers and trades. The subscription
+/// sends the snapshot followed by the incremental updates, while the snapshot request only sends
+/// the snapshot.
+pub fn market_data_request(
+    symbol: &str,
+    subscribe: bool,
+    market_depth: u32,
+    fields: &[(u32, String)],
+) -> FixMessage {
+    let (req_id, subscription_request_type) = if subscribe {
+        (format!("md-{symbol}"), "1")
+    } else {
+        (
+            format!("snapshot-{symbol}-{}", Utc::now().timestamp_millis()),
+            "0",
+        )
+    };
+    let msg = FixMessage::new(msg_type::MARKET_DATA_REQUEST)
+        .field(tag::MD_REQ_ID, req_id)
+        .field(tag::SUBSCRIPTION_REQUEST_TYPE, subscription_request_type)
+        .field(tag::MARKET_DEPTH, market_depth)
+        // Incremental refresh.
+        .field(tag::MD_UPDATE_TYPE, 1)
+        .field(tag::NO_MD_ENTRY_TYPES, 3)
+        .field(tag::MD_ENTRY_TYPE, 0)
+        .field(tag::MD_ENTRY_TYPE, 1)
+        .field(tag::MD_ENTRY_TYPE, 2)
+        .field(tag::NO_RELATED_SYM, 1)
+        .field(tag::SYMBOL, symbol);
+    VenueProfile::append(msg, fields)
+}
+
+/// Decodes the market data snapshots and incremental refreshes. The gaps are detected by the
+/// session through `MsgSeqNum`, so the incremental refreshes are applied only on top of a
+/// snapshot, and [`MarketDataHandler::reset`] is called upon the logon and a gap.
+pub struct MarketData {
+    symbols: SharedSymbolNormalizer,
+    synced: HashSet<String>,
+}
+
+impl MarketData {
+    pub fn new(symbols: SharedSymbolNormalizer) -> Self {
+        Self {
+            symbols,
+            synced: Default::default(),
+        }
+    }
+
+    fn on_snapshot(
+        &mut self,
+        msg: &FixMessage,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        let Some(symbol) = msg.get(tag::SYMBOL) else {
+            return Err(FeedError::Decode("Symbol is missing.".to_string()));
+        };
+        let exch_ts = msg
+            .get(tag::SENDING_TIME)
+            .and_then(parse_timestamp)
+            .unwrap_or_else(|| Utc::now().timestamp_nanos_opt().unwrap());
+        let mut bids = Vec::new();
+        let mut asks = Vec::new();
+        for entry in msg.groups(tag::MD_ENTRY_TYPE) {
+            let Some((entry_type, px, qty)) = parse_entry(entry) else {
+                continue;
+            };
+            match entry_type {
+                "0" => bids.push((px, qty)),
+                "1" => asks.push((px, qty)),
+                _ => {}
+            }
+        }
+        let local = self.symbols.lock().unwrap().to_local(symbol);
+        // The snapshot replaces the current market depth.
+        publish_depth(ev_tx, &local, exch_ts, &bids, &asks, true);
+        self.synced.insert(symbol.to_string());
+        Ok(())
+    }
+
+    fn on_incremental_refresh(&self, msg: &FixMessage, ev_tx: &UnboundedSender<PublishEvent>) {
+        let exch_ts = msg
+            .get(tag::SENDING_TIME)
+            .and_then(parse_timestamp)
+            .unwrap_or_else(|| Utc::now().timestamp_nanos_opt().unwrap());
+        let mut depth: HashMap<String, (Vec<PxQty>, Vec<PxQty>)> = HashMap::new();
+        let top_symbol = msg.get(tag::SYMBOL);
+        for entry in msg.groups(tag::MD_UPDATE_ACTION) {
+            let Some(symbol) = entry
+                .iter()
+                .find(|(tag, _)| *tag == tag::SYMBOL)
+                .map(|(_, v)| v.as_str())
+                .or(top_symbol)
+            else {
+                error!(?msg, "Symbol is missing.");
+                continue;
+            };
+            let action = entry[0].1.as_str();
+            let Some((entry_type, px, qty)) = parse_entry(entry) else {
+                continue;
+            };
+            // The deleted level has no quantity.
+            let qty = if action == "2" { 0.0 } else { qty };
+            match entry_type {
+                // The market depth awaits the snapshot.
+                "0" | "1" if !self.synced.contains(symbol) => {}
+                "0" => depth
+                    .entry(symbol.to_string())
+                    .or_default()
+                    .0
+                    .push((px, qty)),
+                "1" => depth
+                    .entry(symbol.to_string())
+                    .or_default()
+                    .1
+                    .push((px, qty)),
+                "2" => {
+                    let side = match entry.iter().find(|(tag, _)| *tag == tag::SIDE) {
+                        Some((_, side)) if side == "1" => Side::Buy,
+                        Some((_, side)) if side == "2" => Side::Sell,
+                        _ => Side::None,
+                    };
+                    let local = self.symbols.lock().unwrap().to_local(symbol);
+                    publish_trade(ev_tx, &local, side, exch_ts, px, qty);
+                }
+                _ => {}
+            }
+        }
+        for (symbol, (bids, asks)) in depth {
+            let local = self.symbols.lock().unwrap().to_local(&symbol);
+            publish_depth(ev_tx, &local, exch_ts, &bids, &asks, false);
+        }
+    }
+}
+
+impl MarketDataHandler for MarketData {
+    type Message = FixMessage;
+
+    fn handle(
+        &mut self,
+        msg: &FixMessage,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        match msg.msg_type() {
+            msg_type::MARKET_DATA_SNAPSHOT => self.on_snapshot(msg, ev_tx)?,
+            msg_type::MARKET_DATA_INCREMENTAL_REFRESH => self.on_incremental_refresh(msg, ev_tx),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.synced.clear();
+    }
+}
+
+/// The market data session's application, which maintains the subscriptions and publishes the
+/// market data.
+pub struct MarketDataApp {
+    ev_tx: UnboundedSender<PublishEvent>,
+    symbols: SharedSymbolNormalizer,
+    market_data: MarketData,
+    market_depth: u32,
+    md_request_fields: Vec<(u32, String)>,
+    logon_fields: Vec<(u32, String)>,
+}
+
+impl MarketDataApp {
+    pub fn new(
+        ev_tx: UnboundedSender<PublishEvent>,
+        symbols: SharedSymbolNormalizer,
+        market_depth: u32,
+        md_request_fields: Vec<(u32, String)>,
+        logon_fields: Vec<(u32, String)>,
+    ) -> Self {
+        Self {
+            ev_tx,
+            market_data: MarketData::new(symbols.clone()),
+            symbols,
+            market_depth,
+            md_request_fields,
+            logon_fields,
+        }
+    }
+}
+
+/// Parses `MDEntryType(269)`, `MDEntryPx(270)` and `MDEntrySize(271)` of the entry.
+fn parse_entry(entry: &[(u32, String)]) -> Option<(&str, f64, f64)> {
+    let mut entry_type = None;
+    let mut px = None;
+    let mut qty = 0.0;
+    for (tag, value) in entry {
+        match *tag {
+            tag::MD_ENTRY_TYPE => entry_type = Some(value.as_str()),
+            tag::MD_ENTRY_PX => px = value.parse::<f64>().ok(),
+            tag::MD_ENTRY_SIZE => qty = value.parse::<f64>().unwrap_or(0.0),
+            _ => {}
+        }
+    }
+    Some((entry_type?, px?, qty))
+}
+
+impl Application for MarketDataApp {
+    fn on_logon(&mut self) -> Vec<FixMessage> {
+        // Subscribes to the symbols registered before the reconnection. Their market depth is
+        // cleared since the updates during the disconnection are missing, and is replaced by the
+        // snapshot sent upon the subscription.
+        self.market_data.reset();
+        let symbols = self.symbols.lock().unwrap().venue_symbols();
+        symbols
+            .iter()
+            .map(|symbol| {
+                let local = self.symbols.lock().unwrap().to_local(symbol);
+                publish_clear(&self.ev_tx, &local);
+                market_data_request(symbol, true, self.market_depth, &self.md_request_fields)
+            })
+            .collect()
+    }
+
+    fn on_message(&mut self, msg: &FixMessage) -> Vec<FixMessage> {
+        match msg.msg_type() {
+            msg_type::MARKET_DATA_SNAPSHOT | msg_type::MARKET_DATA_INCREMENTAL_REFRESH => {
+                if let Err(error) = self.market_data.handle(msg, &self.ev_tx) {
+                    error!(?error, ?msg, "Couldn't handle the market data.");
+                }
+            }
+            msg_type::MARKET_DATA_REQUEST_REJECT | msg_type::BUSINESS_MESSAGE_REJECT => {
+                error!(?msg, "The market data request is rejected.");
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn on_gap(&mut self, begin: u64, end: u64) -> GapAction {
+        // The resent updates would be stale, so the market depth is recovered from fresh
+        // snapshots instead.
+        warn!(
+            begin,
+            end, "Requests fresh snapshots due to the missing market data."
+        );
+        self.market_data.reset();
+        let symbols = self.symbols.lock().unwrap().venue_symbols();
+        GapAction::Skip(
+            symbols
+                .iter()
+                .map(|symbol| {
+                    let local = self.symbols.lock().unwrap().to_local(symbol);
+                    publish_seq_gap(&self.ev_tx, &local, begin as i64, end as i64 + 1);
+                    market_data_request(symbol, false, self.market_depth, &self.md_request_fields)
+                })
+                .collect(),
+        )
+    }
+
+    fn logon_fields(&self) -> Vec<(u32, String)> {
+        self.logon_fields.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use connector_sdk::{
+        conformance::{ConformanceSuite, VenueEncoder},
+        ordermanager::OrderUpdate,
+    };
+    use hftbacktest::prelude::Side;
+
+    use super::MarketData;
+    use crate::{
+        fix::message::{msg_type, tag, FixMessage},
+        utils::PxQty,
+    };
+
+    struct Encoder;
+
+    impl VenueEncoder for Encoder {
+        type MarketData = FixMessage;
+        type OrderMessage = ();
+
+        fn snapshot(&self, symbol: &str, _seq: i64, bids: &[PxQty], asks: &[PxQty]) -> FixMessage {
+            let mut msg = FixMessage::new(msg_type::MARKET_DATA_SNAPSHOT)
+                .field(tag::SYMBOL, symbol)
+                .field(tag::NO_MD_ENTRIES, bids.len() + asks.len());
+            for (entry_type, levels) in [(0, bids), (1, asks)] {
+                for &(px, qty) in levels {
+                    msg = msg
+                        .field(tag::MD_ENTRY_TYPE, entry_type)
+                        .field(tag::MD_ENTRY_PX, px)
+                        .field(tag::MD_ENTRY_SIZE, qty);
+                }
+            }
+            msg
+        }
+
+        fn depth_update(
+            &self,
+            symbol: &str,
+            _seq: i64,
+            bids: &[PxQty],
+            asks: &[PxQty],
+        ) -> Vec<FixMessage> {
+            let mut msg = FixMessage::new(msg_type::MARKET_DATA_INCREMENTAL_REFRESH)
+                .field(tag::NO_MD_ENTRIES, bids.len() + asks.len());
+            for (entry_type, levels) in [(0, bids), (1, asks)] {
+                for &(px, qty) in levels {
+                    let action = if qty == 0.0 { 2 } else { 1 };
+                    msg = msg
+                        .field(tag::MD_UPDATE_ACTION, action)
+                        .field(tag::MD_ENTRY_TYPE, entry_type)
+                        .field(tag::SYMBOL, symbol)
+                        .field(tag::MD_ENTRY_PX, px)
+                        .field(tag::MD_ENTRY_SIZE, qty);
+                }
+            }
+            vec![msg]
+        }
+
+        fn trade(&self, symbol: &str, side: Side, px: f64, qty: f64) -> FixMessage {
+            FixMessage::new(msg_type::MARKET_DATA_INCREMENTAL_REFRESH)
+                .field(tag::NO_MD_ENTRIES, 1)
+                .field(tag::MD_UPDATE_ACTION, 0)
+                .field(tag::MD_ENTRY_TYPE, 2)
+                .field(tag::SYMBOL, symbol)
+                .field(tag::MD_ENTRY_PX, px)
+                .field(tag::MD_ENTRY_SIZE, qty)
+                .field(tag::SIDE, if side == Side::Sell { 2 } else { 1 })
+        }
+
+        fn order_update(&self, _symbol: &str, _client_order_id: &str, _update: &OrderUpdate) {}
+    }
+
+    #[test]
+    fn conformance() {
+        let suite = ConformanceSuite::new(Encoder, "BTC-USD");
+        let new_handler = || MarketData::new(Default::default());
+        suite.check_snapshot(new_handler()).unwrap();
+        suite.check_incremental(new_handler()).unwrap();
+        // The gaps are detected by the session, so `check_seq_gap` doesn't apply.
+        suite.check_reconnection(new_handler()).unwrap();
+        suite.check_trade(new_handler()).unwrap();
+    }
+}
@@ -0,0 +1,325 @@
+use std::{fmt::Write, str::FromStr};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::fix::FixError;
+
+/// The field delimiter.
+pub const SOH: u8 = 0x01;
+
+#[allow(dead_code)]
+pub mod tag {
+    pub const ACCOUNT: u32 = 1;
+    pub const BEGIN_SEQ_NO: u32 = 7;
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECK_SUM: u32 = 10;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const COMMISSION: u32 = 12;
+    pub const END_SEQ_NO: u32 = 16;
+    pub const EXEC_ID: u32 = 17;
+    pub const EXEC_INST: u32 = 18;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const NEW_SEQ_NO: u32 = 36;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const ORIG_CL_ORD_ID: u32 = 41;
+    pub const POSS_DUP_FLAG: u32 = 43;
+    pub const PRICE: u32 = 44;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const TEXT: u32 = 58;
+    pub const TIME_IN_FORCE: u32 = 59;
+    pub const TRANSACT_TIME: u32 = 60;
+    pub const ENCRYPT_METHOD: u32 = 98;
+    pub const HEART_BT_INT: u32 = 108;
+    pub const TEST_REQ_ID: u32 = 112;
+    pub const ORIG_SENDING_TIME: u32 = 122;
+    pub const GAP_FILL_FLAG: u32 = 123;
+    pub const RESET_SEQ_NUM_FLAG: u32 = 141;
+    pub const NO_RELATED_SYM: u32 = 146;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+    pub const MD_REQ_ID: u32 = 262;
+    pub const SUBSCRIPTION_REQUEST_TYPE: u32 = 263;
+    pub const MARKET_DEPTH: u32 = 264;
+    pub const MD_UPDATE_TYPE: u32 = 265;
+    pub const NO_MD_ENTRY_TYPES: u32 = 267;
+    pub const NO_MD_ENTRIES: u32 = 268;
+    pub const MD_ENTRY_TYPE: u32 = 269;
+    pub const MD_ENTRY_PX: u32 = 270;
+    pub const MD_ENTRY_SIZE: u32 = 271;
+    pub const MD_UPDATE_ACTION: u32 = 279;
+    pub const USERNAME: u32 = 553;
+    pub const PASSWORD: u32 = 554;
+    pub const LAST_LIQUIDITY_IND: u32 = 851;
+}
+
+pub mod msg_type {
+    pub const HEARTBEAT: &str = "0";
+    pub const TEST_REQUEST: &str = "1";
+    pub const RESEND_REQUEST: &str = "2";
+    pub const REJECT: &str = "3";
+    pub const SEQUENCE_RESET: &str = "4";
+    pub const LOGOUT: &str = "5";
+    pub const EXECUTION_REPORT: &str = "8";
+    pub const ORDER_CANCEL_REJECT: &str = "9";
+    pub const LOGON: &str = "A";
+    pub const NEW_ORDER_SINGLE: &str = "D";
+    pub const ORDER_CANCEL_REQUEST: &str = "F";
+    pub const MARKET_DATA_REQUEST: &str = "V";
+    pub const MARKET_DATA_SNAPSHOT: &str = "W";
+    pub const MARKET_DATA_INCREMENTAL_REFRESH: &str = "X";
+    pub const MARKET_DATA_REQUEST_REJECT: &str = "Y";
+    pub const BUSINESS_MESSAGE_REJECT: &str = "j";
+
+    /// Returns `true` if the message type is a session-level message.
+    pub fn is_admin(msg_type: &str) -> bool {
+        matches!(
+            msg_type,
+            HEARTBEAT | TEST_REQUEST | RESEND_REQUEST | REJECT | SEQUENCE_RESET | LOGOUT | LOGON
+        )
+    }
+}
+
+/// The standard header fields that are set by the session when the message is sent.
+pub struct Header<'a> {
+    pub begin_string: &'a str,
+    pub sender_comp_id: &'a str,
+    pub target_comp_id: &'a str,
+    pub msg_seq_num: u64,
+    pub sending_time: DateTime<Utc>,
+    /// Set when the message is sent again in response to a resend request.
+    pub poss_dup: bool,
+}
+
+/// A FIX message consisting of the message type and the other fields in order, excluding
+/// `BeginString`, `BodyLength` and `CheckSum`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixMessage {
+    msg_type: String,
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> Self {
+        Self {
+            msg_type: msg_type.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Appends the field.
+    pub fn field(mut self, tag: u32, value: impl ToString) -> Self {
+        self.fields.push((tag, value.to_string()));
+        self
+    }
+
+    pub fn msg_type(&self) -> &str {
+        &self.msg_type
+    }
+
+    /// Returns the value of the first occurrence of the tag.
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_parsed<T: FromStr>(&self, tag: u32) -> Option<T> {
+        self.get(tag).and_then(|v| v.parse().ok())
+    }
+
+    pub fn msg_seq_num(&self) -> Option<u64> {
+        self.get_parsed(tag::MSG_SEQ_NUM)
+    }
+
+    pub fn poss_dup(&self) -> bool {
+        self.get(tag::POSS_DUP_FLAG) == Some("Y")
+    }
+
+    /// Splits the fields into the repeating group entries, each of which starts with the
+    /// delimiter tag, which is the first tag of the group.
+    pub fn groups(&self, delimiter: u32) -> Vec<&[(u32, String)]> {
+        let mut groups = Vec::new();
+        let mut start = None;
+        for (i, (tag, _)) in self.fields.iter().enumerate() {
+            if *tag == delimiter {
+                if let Some(start) = start {
+                    groups.push(&self.fields[start..i]);
+                }
+                start = Some(i);
+            }
+        }
+        if let Some(start) = start {
+            groups.push(&self.fields[start..]);
+        }
+        groups
+    }
+
+    pub fn encode(&self, header: &Header) -> Vec<u8> {
+        let mut body = String::with_capacity(256);
+        write!(body, "35={}\x01", self.msg_type).unwrap();
+        write!(body, "49={}\x01", header.sender_comp_id).unwrap();
+        write!(body, "56={}\x01", header.target_comp_id).unwrap();
+        write!(body, "34={}\x01", header.msg_seq_num).unwrap();
+        if header.poss_dup {
+            body.push_str("43=Y\x01");
+        }
+        write!(body, "52={}\x01", format_timestamp(header.sending_time)).unwrap();
+        for (tag, value) in &self.fields {
+            write!(body, "{tag}={value}\x01").unwrap();
+        }
+
+        let mut msg = format!("8={}\x019={}\x01{body}", header.begin_string, body.len());
+        let checksum = checksum(msg.as_bytes());
+        write!(msg, "10={checksum:03}\x01").unwrap();
+        msg.into_bytes()
+    }
+
+    /// Decodes the first complete message in the buffer and returns it with the number of bytes
+    /// consumed, or `None` if the buffer doesn't contain a complete message yet.
+    pub fn decode(buf: &[u8]) -> Result<Option<(Self, usize)>, FixError> {
+        // 8=FIX.4.4<SOH>9=123<SOH>
+        let Some(begin_end) = buf.iter().position(|&b| b == SOH) else {
+            return Ok(None);
+        };
+        if !buf.starts_with(b"8=") {
+            return Err(FixError::Decode("BeginString is expected".to_string()));
+        }
+        let Some(len_end) = buf[begin_end + 1..]
+            .iter()
+            .position(|&b| b == SOH)
+            .map(|i| begin_end + 1 + i)
+        else {
+            return Ok(None);
+        };
+        let body_length: usize = std::str::from_utf8(&buf[begin_end + 1..len_end])
+            .ok()
+            .and_then(|s| s.strip_prefix("9="))
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| FixError::Decode("invalid BodyLength".to_string()))?;
+        let body_end = len_end + 1 + body_length;
+        // 10=XXX<SOH>
+        let msg_end = body_end + 7;
+        if buf.len() < msg_end {
+            return Ok(None);
+        }
+        let expected = checksum(&buf[..body_end]);
+        let received: u32 = std::str::from_utf8(&buf[body_end..msg_end - 1])
+            .ok()
+            .and_then(|s| s.strip_prefix("10="))
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| FixError::Decode("invalid CheckSum".to_string()))?;
+        if expected != received {
+            return Err(FixError::Decode(format!(
+                "CheckSum mismatch: expected {expected}, received {received}"
+            )));
+        }
+
+        let body = std::str::from_utf8(&buf[len_end + 1..body_end])
+            .map_err(|error| FixError::Decode(error.to_string()))?;
+        let mut msg_type = None;
+        let mut fields = Vec::new();
+        for field in body.split('\x01').filter(|f| !f.is_empty()) {
+            let (tag, value) = field
+                .split_once('=')
+                .ok_or_else(|| FixError::Decode(format!("invalid field: {field}")))?;
+            let tag: u32 = tag
+                .parse()
+                .map_err(|_| FixError::Decode(format!("invalid tag: {tag}")))?;
+            if tag == tag::MSG_TYPE {
+                msg_type = Some(value.to_string());
+            } else {
+                fields.push((tag, value.to_string()));
+            }
+        }
+        let msg_type =
+            msg_type.ok_or_else(|| FixError::Decode("MsgType is missing".to_string()))?;
+        Ok(Some((FixMessage { msg_type, fields }, msg_end)))
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().map(|&b| b as u32).sum::<u32>() % 256
+}
+
+/// Formats the timestamp in the `UTCTimestamp` format with milliseconds.
+pub fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y%m%d-%H:%M:%S%.3f").to_string()
+}
+
+/// Parses the `UTCTimestamp` into nanoseconds.
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    NaiveDateTime::parse_from_str(s, "%Y%m%d-%H:%M:%S%.f")
+        .ok()
+        .and_then(|dt| dt.and_utc().timestamp_nanos_opt())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        let msg = FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+            .field(tag::CL_ORD_ID, "abc")
+            .field(tag::SYMBOL, "BTC-USD")
+            .field(tag::SIDE, 1)
+            .field(tag::ORDER_QTY, 0.5);
+        let header = Header {
+            begin_string: "FIX.4.4",
+            sender_comp_id: "CLIENT",
+            target_comp_id: "VENUE",
+            msg_seq_num: 7,
+            sending_time: Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap(),
+            poss_dup: false,
+        };
+        let mut buf = msg.encode(&header);
+        let len = buf.len();
+        // A partial message follows.
+        buf.extend_from_slice(b"8=FIX.4.4\x019=5");
+
+        let (decoded, consumed) = FixMessage::decode(&buf).unwrap().unwrap();
+        assert_eq!(consumed, len);
+        assert_eq!(decoded.msg_type(), msg_type::NEW_ORDER_SINGLE);
+        assert_eq!(decoded.msg_seq_num(), Some(7));
+        assert_eq!(
+            decoded.get(tag::SENDING_TIME),
+            Some("20240102-03:04:05.000")
+        );
+        assert_eq!(decoded.get(tag::SYMBOL), Some("BTC-USD"));
+        assert_eq!(decoded.get_parsed::<f64>(tag::ORDER_QTY), Some(0.5));
+
+        assert!(FixMessage::decode(&buf[consumed..]).unwrap().is_none());
+    }
+
+    #[test]
+    fn reject_checksum_mismatch() {
+        let msg = b"8=FIX.4.4\x019=5\x0135=0\x0110=000\x01";
+        assert!(FixMessage::decode(msg).is_err());
+    }
+
+    #[test]
+    fn split_groups() {
+        let msg = FixMessage::new(msg_type::MARKET_DATA_INCREMENTAL_REFRESH)
+            .field(tag::NO_MD_ENTRIES, 2)
+            .field(tag::MD_UPDATE_ACTION, 0)
+            .field(tag::MD_ENTRY_TYPE, 0)
+            .field(tag::MD_UPDATE_ACTION, 2)
+            .field(tag::MD_ENTRY_TYPE, 1);
+        let groups = msg.groups(tag::MD_UPDATE_ACTION);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1][1], (tag::MD_ENTRY_TYPE, "1".to_string()));
+    }
+}
@@ -0,0 +1,352 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use hftbacktest::{
+    prelude::get_precision,
+    types::{ErrorKind, OrdType, Order, PositionSide, Side, TimeInForce, Value},
+};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tracing::error;
+
+use crate::{
+    connector::{Connector, ConnectorBuilder, GetOrders, PublishEvent},
+    fix::{
+        market_data::{market_data_request, MarketDataApp},
+        message::{format_timestamp, msg_type, tag, FixMessage},
+        order_entry::OrderEntryApp,
+        profile::VenueProfile,
+        session::{Application, Session, SessionConfig},
+    },
+    toolkit::{
+        feed::publish_error,
+        ordermanager::{ClientOrderManager, OrderManagerError, SharedClientOrderManager},
+        symbol::{SharedSymbolNormalizer, SymbolCase, SymbolNormalizer},
+    },
+    utils::{generate_rand_string, ExponentialBackoff, Retry},
+};
+
+mod market_data;
+mod message;
+mod order_entry;
+mod profile;
+mod session;
+
+/// The length of `ClOrdID(11)`, which some venues limit to 20 characters.
+const CL_ORD_ID_LEN: usize = 20;
+
+#[derive(Error, Debug)]
+pub enum FixError {
+    #[error("Io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Decode: {0}")]
+    Decode(String),
+    #[error("InvalidTag: {0}")]
+    InvalidTag(String),
+    #[error("ConnectionInterrupted")]
+    ConnectionInterrupted,
+    #[error("LogonTimeout")]
+    LogonTimeout,
+    #[error("HeartbeatTimeout")]
+    HeartbeatTimeout,
+    #[error("SeqTooLow: expected {expected}, received {received}")]
+    SeqTooLow { expected: u64, received: u64 },
+    #[error("AuthError: {0}")]
+    AuthError(String),
+    #[error("Logout: {0}")]
+    Logout(String),
+    #[error("OrderManager: {0}")]
+    OrderManager(#[from] OrderManagerError),
+    #[error("InvalidArg: {0}")]
+    InvalidArg(&'static str),
+    #[error("Config: {0:?}")]
+    Config(#[from] toml::de::Error),
+}
+
+impl FixError {
+    pub fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    profile: VenueProfile,
+    market_data: SessionConfig,
+    order_entry: SessionConfig,
+    #[serde(default)]
+    order_prefix: String,
+}
+
+/// A generic FIX 4.4 connector, which runs a market data session and an order entry session.
+/// The venue-specific conventions are set by [`VenueProfile`] in the configuration.
+///
+/// Symbols are the venue's `Symbol(55)` as is. Positions are not reported.
+pub struct Fix {
+    config: Config,
+    order_manager: SharedClientOrderManager,
+    symbols: SharedSymbolNormalizer,
+    order_fields: Vec<(u32, String)>,
+    md_request_fields: Vec<(u32, String)>,
+    logon_fields: Vec<(u32, String)>,
+    md_tx: UnboundedSender<FixMessage>,
+    md_rx: Option<UnboundedReceiver<FixMessage>>,
+    order_tx: UnboundedSender<FixMessage>,
+    order_rx: Option<UnboundedReceiver<FixMessage>>,
+}
+
+/// Runs the session, reconnecting with the exponential backoff whenever it's disconnected.
+fn spawn_session<A: Application + Send + 'static>(
+    config: SessionConfig,
+    begin_string: String,
+    app: A,
+    out_rx: UnboundedReceiver<FixMessage>,
+    ev_tx: UnboundedSender<PublishEvent>,
+) {
+    // The session state and the queued messages are carried over to the next connection.
+    let state = Arc::new(tokio::sync::Mutex::new((
+        Session::new(config, begin_string),
+        app,
+        out_rx,
+    )));
+    tokio::spawn(async move {
+        let _ = Retry::new(ExponentialBackoff::default())
+            .error_handler(|error: FixError| {
+                error!(?error, "An error occurred in the FIX session.");
+                let kind = if matches!(error, FixError::AuthError(_)) {
+                    ErrorKind::AuthFailure
+                } else {
+                    ErrorKind::ConnectionInterrupted
+                };
+                publish_error(&ev_tx, kind, error.to_value());
+                Ok(())
+            })
+            .retry(|| {
+                let state = state.clone();
+                async move {
+                    let mut state = state.lock().await;
+                    let (session, app, out_rx) = &mut *state;
+                    session.run(app, out_rx).await
+                }
+            })
+            .await;
+    });
+}
+
+impl Fix {
+    fn new_order_single(&self, symbol: &str, order: &Order) -> Result<FixMessage, FixError> {
+        let side = match order.side {
+            Side::Buy => "1",
+            Side::Sell => "2",
+            Side::None | Side::Unsupported => return Err(FixError::InvalidArg("side")),
+        };
+        let mut msg = FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+            .field(tag::SYMBOL, symbol)
+            .field(tag::SIDE, side)
+            .field(tag::TRANSACT_TIME, format_timestamp(Utc::now()))
+            .field(tag::ORDER_QTY, order.qty);
+        match order.order_type {
+            OrdType::Limit => {
+                msg = msg.field(tag::ORD_TYPE, "2").field(
+                    tag::PRICE,
+                    format!(
+                        "{:.prec$}",
+                        order.price_tick as f64 * order.tick_size,
+                        prec = get_precision(order.tick_size)
+                    ),
+                );
+            }
+            OrdType::Market => {
+                msg = msg.field(tag::ORD_TYPE, "1");
+            }
+            OrdType::Unsupported => return Err(FixError::InvalidArg("order_type")),
+        }
+        let mut exec_insts = Vec::new();
+        match order.time_in_force {
+            TimeInForce::GTC => {
+                msg = msg.field(tag::TIME_IN_FORCE, "1");
+            }
+            TimeInForce::GTX => {
+                let exec_inst = self
+                    .config
+                    .profile
+                    .post_only_exec_inst
+                    .as_ref()
+                    .ok_or(FixError::InvalidArg("time_in_force"))?;
+                msg = msg.field(tag::TIME_IN_FORCE, "1");
+                exec_insts.push(exec_inst.as_str());
+            }
+            TimeInForce::IOC => {
+                msg = msg.field(tag::TIME_IN_FORCE, "3");
+            }
+            TimeInForce::FOK => {
+                msg = msg.field(tag::TIME_IN_FORCE, "4");
+            }
+            TimeInForce::Unsupported => return Err(FixError::InvalidArg("time_in_force")),
+        }
+        if order.reduce_only {
+            // Do not increase
+            exec_insts.push("E");
+        }
+        if order.position_side != PositionSide::Both {
+            return Err(FixError::InvalidArg("position_side"));
+        }
+        if !exec_insts.is_empty() {
+            // ExecInst is a multiple value string, delimited by a space.
+            msg = msg.field(tag::EXEC_INST, exec_insts.join(" "));
+        }
+        if let Some(account) = &self.config.profile.account {
+            msg = msg.field(tag::ACCOUNT, account);
+        }
+        Ok(VenueProfile::append(msg, &self.order_fields))
+    }
+}
+
+impl ConnectorBuilder for Fix {
+    type Error = FixError;
+
+    fn build_from(config: &str) -> Result<Self, Self::Error> {
+        let config: Config = toml::from_str(config)?;
+        let order_fields = VenueProfile::parse_fields(&config.profile.order_fields)?;
+        let md_request_fields = VenueProfile::parse_fields(&config.profile.md_request_fields)?;
+        let logon_fields = VenueProfile::parse_fields(&config.profile.logon_fields)?;
+        let order_manager = Arc::new(Mutex::new(ClientOrderManager::new(
+            &config.order_prefix,
+            CL_ORD_ID_LEN,
+        )));
+        let (md_tx, md_rx) = unbounded_channel();
+        let (order_tx, order_rx) = unbounded_channel();
+        Ok(Fix {
+            config,
+            order_manager,
+            symbols: Arc::new(Mutex::new(SymbolNormalizer::new(SymbolCase::Preserve))),
+            order_fields,
+            md_request_fields,
+            logon_fields,
+            md_tx,
+            md_rx: Some(md_rx),
+            order_tx,
+            order_rx: Some(order_rx),
+        })
+    }
+}
+
+impl Connector for Fix {
+    fn register(&mut self, symbol: String, _tick_size: f64, _lot_size: f64) {
+        if let Some(venue) = self.symbols.lock().unwrap().register(&symbol) {
+            self.md_tx
+                .send(market_data_request(
+                    &venue,
+                    true,
+                    self.config.profile.market_depth,
+                    &self.md_request_fields,
+                ))
+                .unwrap();
+        }
+    }
+
+    fn order_manager(&self) -> Arc<Mutex<dyn GetOrders + Send + 'static>> {
+        self.order_manager.clone()
+    }
+
+    fn run(&mut self, ev_tx: UnboundedSender<PublishEvent>) {
+        let begin_string = self.config.profile.begin_string.clone();
+        spawn_session(
+            self.config.market_data.clone(),
+            begin_string.clone(),
+            MarketDataApp::new(
+                ev_tx.clone(),
+                self.symbols.clone(),
+                self.config.profile.market_depth,
+                self.md_request_fields.clone(),
+                self.logon_fields.clone(),
+            ),
+            self.md_rx.take().unwrap(),
+            ev_tx.clone(),
+        );
+        spawn_session(
+            self.config.order_entry.clone(),
+            begin_string,
+            OrderEntryApp::new(
+                ev_tx.clone(),
+                self.order_manager.clone(),
+                self.logon_fields.clone(),
+            ),
+            self.order_rx.take().unwrap(),
+            ev_tx,
+        );
+    }
+
+    fn submit(&self, symbol: String, order: Order, ev_tx: UnboundedSender<PublishEvent>) {
+        let venue = self.symbols.lock().unwrap().to_venue(&symbol);
+        let result = self.new_order_single(&venue, &order).and_then(|msg| {
+            let client_order_id = self
+                .order_manager
+                .lock()
+                .unwrap()
+                .new_order(&symbol, order)?;
+            Ok(msg.field(tag::CL_ORD_ID, client_order_id))
+        });
+        match result {
+            // The order's status is updated by the execution report once it's accepted.
+            Ok(msg) => self.order_tx.send(msg).unwrap(),
+            Err(error) => publish_error(&ev_tx, ErrorKind::OrderError, error.to_value()),
+        }
+    }
+
+    fn cancel(&self, symbol: String, order: Order, ev_tx: UnboundedSender<PublishEvent>) {
+        let venue = self.symbols.lock().unwrap().to_venue(&symbol);
+        let orig_client_order_id = match self
+            .order_manager
+            .lock()
+            .unwrap()
+            .client_order_id(&symbol, order.order_id)
+        {
+            Ok(client_order_id) => client_order_id,
+            Err(error) => {
+                publish_error(
+                    &ev_tx,
+                    ErrorKind::OrderError,
+                    FixError::from(error).to_value(),
+                );
+                return;
+            }
+        };
+        let side = match order.side {
+            Side::Buy => "1",
+            _ => "2",
+        };
+        // The cancel request needs its own unique `ClOrdID(11)`, while the execution reports refer
+        // to the order by `OrigClOrdID(41)`.
+        let prefix = &self.config.order_prefix;
+        let client_order_id = format!(
+            "{prefix}{}",
+            generate_rand_string(CL_ORD_ID_LEN - prefix.len())
+        );
+        let msg = FixMessage::new(msg_type::ORDER_CANCEL_REQUEST)
+            .field(tag::ORIG_CL_ORD_ID, orig_client_order_id)
+            .field(tag::CL_ORD_ID, client_order_id)
+            .field(tag::SYMBOL, venue)
+            .field(tag::SIDE, side)
+            .field(tag::ORDER_QTY, order.qty)
+            .field(tag::TRANSACT_TIME, format_timestamp(Utc::now()));
+        self.order_tx
+            .send(VenueProfile::append(msg, &self.order_fields))
+            .unwrap();
+    }
+
+    fn resync(&self, symbol: String, _ev_tx: UnboundedSender<PublishEvent>) {
+        // Requests a fresh snapshot, which replaces the market depth.
+        let venue = self.symbols.lock().unwrap().to_venue(&symbol);
+        self.md_tx
+            .send(market_data_request(
+                &venue,
+                false,
+                self.config.profile.market_depth,
+                &self.md_request_fields,
+            ))
+            .unwrap();
+    }
+}
@@ -0,0 +1,207 @@
+use chrono::Utc;
+use hftbacktest::prelude::{ErrorKind, LiveEvent, Order, Status, Value};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::error;
+
+use crate::{
+    connector::PublishEvent,
+    fix::{
+        message::{msg_type, parse_timestamp, tag, FixMessage},
+        session::{Application, GapAction},
+    },
+    toolkit::{
+        feed::publish_error,
+        ordermanager::{OrderManagerError, OrderUpdate, SharedClientOrderManager},
+    },
+};
+
+/// The order entry session's application, which tracks the orders by `ClOrdID(11)` and publishes
+/// the order updates from the execution reports.
+pub struct OrderEntryApp {
+    ev_tx: UnboundedSender<PublishEvent>,
+    order_manager: SharedClientOrderManager,
+    logon_fields: Vec<(u32, String)>,
+}
+
+impl OrderEntryApp {
+    pub fn new(
+        ev_tx: UnboundedSender<PublishEvent>,
+        order_manager: SharedClientOrderManager,
+        logon_fields: Vec<(u32, String)>,
+    ) -> Self {
+        Self {
+            ev_tx,
+            order_manager,
+            logon_fields,
+        }
+    }
+
+    fn publish_order(&self, symbol: String, order: Order) {
+        self.ev_tx
+            .send(PublishEvent::LiveEvent(LiveEvent::Order { symbol, order }))
+            .unwrap();
+    }
+
+    fn on_execution_report(&self, msg: &FixMessage) {
+        let status = match msg.get(tag::ORD_STATUS) {
+            Some("0") => Status::New,
+            Some("1") => Status::PartiallyFilled,
+            Some("2") => Status::Filled,
+            Some("4") => Status::Canceled,
+            Some("8") => Status::Rejected,
+            Some("C") => Status::Expired,
+            _ => Status::Unsupported,
+        };
+        // `ExecType(150)` of `F` is a trade.
+        let (exec_qty, exec_price) = if msg.get(tag::EXEC_TYPE) == Some("F") {
+            (
+                msg.get_parsed(tag::LAST_QTY).unwrap_or(0.0),
+                msg.get_parsed(tag::LAST_PX).unwrap_or(0.0),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        let update = OrderUpdate {
+            status,
+            leaves_qty: msg.get_parsed(tag::LEAVES_QTY),
+            exec_qty,
+            exec_price,
+            exch_ts: msg
+                .get(tag::TRANSACT_TIME)
+                .and_then(parse_timestamp)
+                .unwrap_or_else(|| Utc::now().timestamp_nanos_opt().unwrap()),
+            // `LastLiquidityInd(851)` of `1` is added liquidity, and `2` is removed liquidity.
+            maker: match msg.get(tag::LAST_LIQUIDITY_IND) {
+                Some("1") => Some(true),
+                Some("2") => Some(false),
+                _ => None,
+            },
+            fee: msg.get_parsed(tag::COMMISSION).unwrap_or(0.0),
+            trade_id: msg.get(tag::EXEC_ID).map(|exec_id| exec_id.to_string()),
+        };
+        if status == Status::Rejected {
+            error!(text = msg.get(tag::TEXT), ?msg, "The order is rejected.");
+        }
+
+        // The cancel request has its own `ClOrdID(11)` and refers to the order by
+        // `OrigClOrdID(41)`.
+        let mut order_manager = self.order_manager.lock().unwrap();
+        let result = match msg.get(tag::CL_ORD_ID) {
+            Some(client_order_id) => match order_manager.update(client_order_id, update.clone()) {
+                Err(OrderManagerError::PrefixUnmatched | OrderManagerError::OrderNotFound) => msg
+                    .get(tag::ORIG_CL_ORD_ID)
+                    .map(|orig| order_manager.update(orig, update.clone()))
+                    .unwrap_or(Err(OrderManagerError::OrderNotFound)),
+                result => result,
+            },
+            None => Err(OrderManagerError::OrderNotFound),
+        };
+        drop(order_manager);
+        match result {
+            Ok(order) => {
+                // Reports the execution ahead of the order update.
+                if let Some(fill) = update.fill(&order.order) {
+                    self.ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Fill {
+                            symbol: order.symbol.clone(),
+                            fill,
+                        }))
+                        .unwrap();
+                }
+                self.publish_order(order.symbol, order.order)
+            }
+            Err(OrderManagerError::PrefixUnmatched) => {
+                // This order is not created by this connector.
+            }
+            Err(error) => {
+                error!(
+                    ?error,
+                    ?msg,
+                    "Couldn't update the order from ExecutionReport."
+                );
+            }
+        }
+    }
+
+    fn on_cancel_reject(&self, msg: &FixMessage) {
+        let Some(orig) = msg.get(tag::ORIG_CL_ORD_ID) else {
+            return;
+        };
+        let result = self.order_manager.lock().unwrap().update_cancel_fail(orig);
+        match result {
+            Ok(order) => {
+                self.publish_order(order.symbol, order.order);
+                publish_error(
+                    &self.ev_tx,
+                    ErrorKind::OrderError,
+                    Value::String(
+                        msg.get(tag::TEXT)
+                            .unwrap_or("OrderCancelReject")
+                            .to_string(),
+                    ),
+                );
+            }
+            Err(OrderManagerError::PrefixUnmatched) => {}
+            Err(error) => {
+                error!(
+                    ?error,
+                    ?msg,
+                    "Couldn't update the order from OrderCancelReject."
+                );
+            }
+        }
+    }
+}
+
+impl Application for OrderEntryApp {
+    fn on_logon(&mut self) -> Vec<FixMessage> {
+        Vec::new()
+    }
+
+    fn on_message(&mut self, msg: &FixMessage) -> Vec<FixMessage> {
+        match msg.msg_type() {
+            msg_type::EXECUTION_REPORT => self.on_execution_report(msg),
+            msg_type::ORDER_CANCEL_REJECT => self.on_cancel_reject(msg),
+            msg_type::BUSINESS_MESSAGE_REJECT => {
+                error!(?msg, "The message is rejected by the counterparty.");
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn on_gap(&mut self, _begin: u64, _end: u64) -> GapAction {
+        // The missing execution reports are required to keep the order states.
+        GapAction::Resend
+    }
+
+    fn on_unsent(&mut self, msg: &FixMessage) {
+        let result = match msg.msg_type() {
+            msg_type::NEW_ORDER_SINGLE => msg
+                .get(tag::CL_ORD_ID)
+                .map(|id| self.order_manager.lock().unwrap().update_submit_fail(id)),
+            msg_type::ORDER_CANCEL_REQUEST => msg
+                .get(tag::ORIG_CL_ORD_ID)
+                .map(|id| self.order_manager.lock().unwrap().update_cancel_fail(id)),
+            _ => None,
+        };
+        match result {
+            Some(Ok(order)) => {
+                self.publish_order(order.symbol, order.order);
+                publish_error(
+                    &self.ev_tx,
+                    ErrorKind::OrderError,
+                    Value::String("The order request was not sent while disconnected.".to_string()),
+                );
+            }
+            Some(Err(error)) => {
+                error!(?error, ?msg, "Couldn't update the unsent order request.");
+            }
+            None => {}
+        }
+    }
+
+    fn logon_fields(&self) -> Vec<(u32, String)> {
+        self.logon_fields.clone()
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::fix::{message::FixMessage, FixError};
+
+fn default_begin_string() -> String {
+    "FIX.4.4".to_string()
+}
+
+fn default_post_only_exec_inst() -> Option<String> {
+    // Participate don't initiate.
+    Some("6".to_string())
+}
+
+/// The venue-specific conventions on top of the FIX 4.4 standard, so that a new venue can be
+/// supported by the configuration.
+#[derive(Deserialize, Clone)]
+pub struct VenueProfile {
+    #[serde(default = "default_begin_string")]
+    pub begin_string: String,
+    /// `Account(1)` of the orders.
+    #[serde(default)]
+    pub account: Option<String>,
+    /// The extra fields of the `Logon` message, keyed by the tag.
+    #[serde(default)]
+    pub logon_fields: BTreeMap<String, String>,
+    /// The extra fields of the `NewOrderSingle` and `OrderCancelRequest` messages, such as
+    /// `ExDestination(100)`, keyed by the tag.
+    #[serde(default)]
+    pub order_fields: BTreeMap<String, String>,
+    /// The extra fields of the `MarketDataRequest` message, keyed by the tag.
+    #[serde(default)]
+    pub md_request_fields: BTreeMap<String, String>,
+    /// `MarketDepth(264)` of the market data subscription, where `0` is the full book.
+    #[serde(default)]
+    pub market_depth: u32,
+    /// `ExecInst(18)` for the post-only orders. The post-only orders are rejected if it's not
+    /// set.
+    #[serde(default = "default_post_only_exec_inst")]
+    pub post_only_exec_inst: Option<String>,
+}
+
+impl Default for VenueProfile {
+    fn default() -> Self {
+        Self {
+            begin_string: default_begin_string(),
+            account: None,
+            logon_fields: Default::default(),
+            order_fields: Default::default(),
+            md_request_fields: Default::default(),
+            market_depth: 0,
+            post_only_exec_inst: default_post_only_exec_inst(),
+        }
+    }
+}
+
+impl VenueProfile {
+    /// Parses the fields keyed by the tag in string.
+    pub fn parse_fields(fields: &BTreeMap<String, String>) -> Result<Vec<(u32, String)>, FixError> {
+        fields
+            .iter()
+            .map(|(tag, value)| {
+                tag.parse::<u32>()
+                    .map(|tag| (tag, value.clone()))
+                    .map_err(|_| FixError::InvalidTag(tag.clone()))
+            })
+            .collect()
+    }
+
+    /// Appends the fields to the message.
+    pub fn append(msg: FixMessage, fields: &[(u32, String)]) -> FixMessage {
+        fields
+            .iter()
+            .fold(msg, |msg, (tag, value)| msg.field(*tag, value))
+    }
+}
@@ -0,0 +1,436 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{tcp::OwnedWriteHalf, TcpStream},
+    select,
+    sync::mpsc::UnboundedReceiver,
+    time,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::fix::{
+    message::{format_timestamp, msg_type, tag, FixMessage, Header},
+    FixError,
+};
+
+fn default_heartbeat_interval() -> u64 {
+    30
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The configuration of a FIX session.
+#[derive(Deserialize, Clone)]
+pub struct SessionConfig {
+    pub host: String,
+    pub port: u16,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// In seconds.
+    #[serde(default = "default_heartbeat_interval")]
+    pub heartbeat_interval: u64,
+    /// Resets the sequence numbers on every logon. Otherwise, the sequence numbers continue from
+    /// the ones persisted in `seq_store`.
+    #[serde(default = "default_true")]
+    pub reset_on_logon: bool,
+    /// The file where the next outgoing and incoming sequence numbers are persisted.
+    #[serde(default)]
+    pub seq_store: Option<PathBuf>,
+}
+
+/// What the session does when incoming messages are missing.
+pub enum GapAction {
+    /// Requests the counterparty to resend the missing messages.
+    Resend,
+    /// Skips the missing messages and sends the messages, such as the requests for fresh
+    /// snapshots, instead.
+    Skip(Vec<FixMessage>),
+}
+
+/// The application-level handler of a FIX session.
+pub trait Application {
+    /// Returns the messages to send once the logon is completed.
+    fn on_logon(&mut self) -> Vec<FixMessage>;
+
+    /// Handles the application message and returns the messages to send in response.
+    fn on_message(&mut self, msg: &FixMessage) -> Vec<FixMessage>;
+
+    /// Decides how to recover the missing incoming messages in `[begin, end]`.
+    fn on_gap(&mut self, begin: u64, end: u64) -> GapAction;
+
+    /// Handles the message that was queued while the session was disconnected, which is
+    /// discarded since it would be stale by the time the session is logged on.
+    fn on_unsent(&mut self, _msg: &FixMessage) {}
+
+    /// Returns the extra fields of the `Logon` message required by the venue.
+    fn logon_fields(&self) -> Vec<(u32, String)> {
+        Vec::new()
+    }
+}
+
+/// The sequence number ranges of the incoming messages that are requested to be resent.
+#[derive(Default)]
+struct Gaps(Vec<(u64, u64)>);
+
+impl Gaps {
+    fn add(&mut self, begin: u64, end: u64) {
+        self.0.push((begin, end));
+    }
+
+    /// Removes the sequence number from the gaps, returning `true` if it was missing.
+    fn fill(&mut self, seq: u64) -> bool {
+        self.fill_range(seq, seq + 1)
+    }
+
+    /// Removes the sequence numbers in `[begin, end)` from the gaps, returning `true` if any of
+    /// them was missing.
+    fn fill_range(&mut self, begin: u64, end: u64) -> bool {
+        let mut filled = false;
+        let mut remaining = Vec::with_capacity(self.0.len());
+        for &(b, e) in &self.0 {
+            if e < begin || b >= end {
+                remaining.push((b, e));
+                continue;
+            }
+            filled = true;
+            if b < begin {
+                remaining.push((b, begin - 1));
+            }
+            if e >= end {
+                remaining.push((end, e));
+            }
+        }
+        self.0 = remaining;
+        filled
+    }
+}
+
+/// A FIX session over TCP, which manages the logon, the heartbeats, and the sequence numbers
+/// including the resend requests in both directions.
+///
+/// TLS is not handled; a venue that requires it can be reached through a TLS tunnel such as
+/// stunnel.
+pub struct Session {
+    config: SessionConfig,
+    begin_string: String,
+    next_out: u64,
+    next_in: u64,
+    gaps: Gaps,
+}
+
+impl Session {
+    pub fn new(config: SessionConfig, begin_string: String) -> Self {
+        Self {
+            config,
+            begin_string,
+            next_out: 1,
+            next_in: 1,
+            gaps: Default::default(),
+        }
+    }
+
+    fn load_seq(&mut self) {
+        if self.config.reset_on_logon {
+            self.next_out = 1;
+            self.next_in = 1;
+            return;
+        }
+        let Some(path) = &self.config.seq_store else {
+            return;
+        };
+        match fs::read_to_string(path) {
+            Ok(s) => {
+                let mut it = s.split_whitespace().map(|n| n.parse::<u64>());
+                if let (Some(Ok(next_out)), Some(Ok(next_in))) = (it.next(), it.next()) {
+                    self.next_out = next_out;
+                    self.next_in = next_in;
+                }
+            }
+            Err(error) => {
+                warn!(?error, ?path, "Couldn't load the sequence numbers.");
+            }
+        }
+    }
+
+    fn store_seq(&self) {
+        if let Some(path) = &self.config.seq_store {
+            if let Err(error) = fs::write(path, format!("{} {}", self.next_out, self.next_in)) {
+                error!(?error, ?path, "Couldn't store the sequence numbers.");
+            }
+        }
+    }
+
+    async fn send(
+        &mut self,
+        writer: &mut OwnedWriteHalf,
+        msg: &FixMessage,
+    ) -> Result<(), FixError> {
+        let header = Header {
+            begin_string: &self.begin_string,
+            sender_comp_id: &self.config.sender_comp_id,
+            target_comp_id: &self.config.target_comp_id,
+            msg_seq_num: self.next_out,
+            sending_time: Utc::now(),
+            poss_dup: false,
+        };
+        writer.write_all(&msg.encode(&header)).await?;
+        self.next_out += 1;
+        self.store_seq();
+        Ok(())
+    }
+
+    /// Answers the resend request with a gap fill, since the stale application messages such as
+    /// orders must not be sent again.
+    async fn send_gap_fill(
+        &mut self,
+        writer: &mut OwnedWriteHalf,
+        begin: u64,
+    ) -> Result<(), FixError> {
+        let now = Utc::now();
+        let msg = FixMessage::new(msg_type::SEQUENCE_RESET)
+            .field(tag::ORIG_SENDING_TIME, format_timestamp(now))
+            .field(tag::GAP_FILL_FLAG, "Y")
+            .field(tag::NEW_SEQ_NO, self.next_out);
+        let header = Header {
+            begin_string: &self.begin_string,
+            sender_comp_id: &self.config.sender_comp_id,
+            target_comp_id: &self.config.target_comp_id,
+            msg_seq_num: begin,
+            sending_time: now,
+            poss_dup: true,
+        };
+        writer.write_all(&msg.encode(&header)).await?;
+        Ok(())
+    }
+
+    /// Connects and logs on, then runs the session until it's disconnected. The messages received
+    /// from `out_rx` are sent once the logon is completed.
+    pub async fn run<A: Application>(
+        &mut self,
+        app: &mut A,
+        out_rx: &mut UnboundedReceiver<FixMessage>,
+    ) -> Result<(), FixError> {
+        while let Ok(msg) = out_rx.try_recv() {
+            app.on_unsent(&msg);
+        }
+        let stream = TcpStream::connect((self.config.host.as_str(), self.config.port)).await?;
+        stream.set_nodelay(true)?;
+        let (mut reader, mut writer) = stream.into_split();
+
+        self.load_seq();
+        self.gaps = Default::default();
+        let heartbeat_interval = Duration::from_secs(self.config.heartbeat_interval);
+        let mut logon = FixMessage::new(msg_type::LOGON)
+            .field(tag::ENCRYPT_METHOD, 0)
+            .field(tag::HEART_BT_INT, self.config.heartbeat_interval);
+        if self.config.reset_on_logon {
+            logon = logon.field(tag::RESET_SEQ_NUM_FLAG, "Y");
+        }
+        if let Some(username) = &self.config.username {
+            logon = logon.field(tag::USERNAME, username);
+        }
+        if let Some(password) = &self.config.password {
+            logon = logon.field(tag::PASSWORD, password);
+        }
+        for (tag, value) in app.logon_fields() {
+            logon = logon.field(tag, value);
+        }
+        self.send(&mut writer, &logon).await?;
+
+        let mut logged_on = false;
+        let connected_at = Instant::now();
+        let mut last_sent = Instant::now();
+        let mut last_recv = Instant::now();
+        let mut test_req_sent: Option<Instant> = None;
+        let mut interval = time::interval(Duration::from_secs(1));
+        let mut buf = Vec::with_capacity(65536);
+        let mut chunk = vec![0u8; 65536];
+
+        loop {
+            select! {
+                n = reader.read(&mut chunk) => {
+                    let n = n?;
+                    if n == 0 {
+                        return Err(FixError::ConnectionInterrupted);
+                    }
+                    last_recv = Instant::now();
+                    test_req_sent = None;
+                    buf.extend_from_slice(&chunk[..n]);
+                    while let Some((msg, len)) = FixMessage::decode(&buf)? {
+                        buf.drain(..len);
+                        let out = self.handle(app, msg, &mut logged_on, &mut writer).await?;
+                        for msg in out {
+                            self.send(&mut writer, &msg).await?;
+                            last_sent = Instant::now();
+                        }
+                    }
+                }
+                Some(msg) = out_rx.recv(), if logged_on => {
+                    self.send(&mut writer, &msg).await?;
+                    last_sent = Instant::now();
+                }
+                _ = interval.tick() => {
+                    if !logged_on {
+                        if connected_at.elapsed() >= heartbeat_interval {
+                            return Err(FixError::LogonTimeout);
+                        }
+                        continue;
+                    }
+                    if last_sent.elapsed() >= heartbeat_interval {
+                        self.send(&mut writer, &FixMessage::new(msg_type::HEARTBEAT)).await?;
+                        last_sent = Instant::now();
+                    }
+                    match test_req_sent {
+                        Some(sent) if sent.elapsed() >= heartbeat_interval => {
+                            return Err(FixError::HeartbeatTimeout);
+                        }
+                        None if last_recv.elapsed() >= heartbeat_interval + heartbeat_interval / 5 => {
+                            let msg = FixMessage::new(msg_type::TEST_REQUEST)
+                                .field(tag::TEST_REQ_ID, format_timestamp(Utc::now()));
+                            self.send(&mut writer, &msg).await?;
+                            last_sent = Instant::now();
+                            test_req_sent = Some(Instant::now());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handles the incoming message and returns the messages to send in response.
+    async fn handle<A: Application>(
+        &mut self,
+        app: &mut A,
+        msg: FixMessage,
+        logged_on: &mut bool,
+        writer: &mut OwnedWriteHalf,
+    ) -> Result<Vec<FixMessage>, FixError> {
+        let seq = msg
+            .msg_seq_num()
+            .ok_or_else(|| FixError::Decode("MsgSeqNum is missing".to_string()))?;
+        let mut out = Vec::new();
+
+        if msg.msg_type() == msg_type::SEQUENCE_RESET {
+            let new_seq_no: u64 = msg
+                .get_parsed(tag::NEW_SEQ_NO)
+                .ok_or_else(|| FixError::Decode("NewSeqNo is missing".to_string()))?;
+            if msg.get(tag::GAP_FILL_FLAG) == Some("Y") {
+                // Fills the gap of the application messages that are not resent.
+                self.gaps.fill_range(seq, new_seq_no);
+                if seq == self.next_in {
+                    self.next_in = new_seq_no;
+                }
+            } else {
+                self.next_in = new_seq_no;
+                self.gaps = Default::default();
+            }
+            self.store_seq();
+            return Ok(out);
+        }
+
+        if seq < self.next_in {
+            if msg.poss_dup() && self.gaps.fill(seq) {
+                // A resent message that was missing.
+                if !msg_type::is_admin(msg.msg_type()) {
+                    out.extend(app.on_message(&msg));
+                }
+                return Ok(out);
+            } else if msg.poss_dup() {
+                return Ok(out);
+            }
+            return Err(FixError::SeqTooLow {
+                expected: self.next_in,
+                received: seq,
+            });
+        }
+        if seq > self.next_in && msg.msg_type() != msg_type::LOGOUT {
+            warn!(
+                expected = self.next_in,
+                received = seq,
+                "A gap in the incoming sequence numbers is detected."
+            );
+            match app.on_gap(self.next_in, seq - 1) {
+                GapAction::Resend => {
+                    self.gaps.add(self.next_in, seq - 1);
+                    out.push(
+                        FixMessage::new(msg_type::RESEND_REQUEST)
+                            .field(tag::BEGIN_SEQ_NO, self.next_in)
+                            .field(tag::END_SEQ_NO, seq - 1),
+                    );
+                }
+                GapAction::Skip(msgs) => out.extend(msgs),
+            }
+        }
+        self.next_in = seq + 1;
+        self.store_seq();
+
+        match msg.msg_type() {
+            msg_type::LOGON => {
+                info!(
+                    sender_comp_id = self.config.sender_comp_id,
+                    "The FIX session is logged on."
+                );
+                *logged_on = true;
+                out.extend(app.on_logon());
+            }
+            msg_type::HEARTBEAT => {}
+            msg_type::TEST_REQUEST => {
+                let mut heartbeat = FixMessage::new(msg_type::HEARTBEAT);
+                if let Some(test_req_id) = msg.get(tag::TEST_REQ_ID) {
+                    heartbeat = heartbeat.field(tag::TEST_REQ_ID, test_req_id);
+                }
+                out.push(heartbeat);
+            }
+            msg_type::RESEND_REQUEST => {
+                let begin: u64 = msg.get_parsed(tag::BEGIN_SEQ_NO).unwrap_or(1);
+                self.send_gap_fill(writer, begin).await?;
+            }
+            msg_type::REJECT => {
+                error!(?msg, "The message is rejected by the counterparty.");
+            }
+            msg_type::LOGOUT => {
+                let text = msg.get(tag::TEXT).unwrap_or_default().to_string();
+                if !*logged_on {
+                    return Err(FixError::AuthError(text));
+                }
+                return Err(FixError::Logout(text));
+            }
+            _ => {
+                debug!(msg_type = msg.msg_type(), "Application message");
+                out.extend(app.on_message(&msg));
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_gaps() {
+        let mut gaps = Gaps::default();
+        gaps.add(5, 10);
+        assert!(gaps.fill(7));
+        assert!(!gaps.fill(7));
+        assert_eq!(gaps.0, vec![(5, 6), (8, 10)]);
+        assert!(gaps.fill_range(5, 9));
+        assert_eq!(gaps.0, vec![(9, 10)]);
+        assert!(!gaps.fill_range(11, 20));
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use hftbacktest::types::{ErrorKind, LiveError, LiveEvent, Order, Value};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::{broadcast, broadcast::Sender, mpsc::UnboundedSender};
+use tracing::error;
+
+use crate::{
+    connector::{Connector, ConnectorBuilder, GetOrders, PublishEvent},
+    kraken::{
+        ordermanager::{OrderManager, SharedOrderManager},
+        private_stream::PrivateStream,
+        public_stream::PublicStream,
+        rest::KrakenClient,
+    },
+    utils::{ExponentialBackoff, Retry},
+};
+
+#[allow(dead_code)]
+mod msg;
+mod ordermanager;
+mod private_stream;
+mod public_stream;
+mod rest;
+
+#[derive(Error, Debug)]
+pub enum KrakenError {
+    #[error("AuthError: {0}")]
+    AuthError(String),
+    #[error("ApiError: {0:?}")]
+    ApiError(Vec<String>),
+    #[error("PrefixUnmatched")]
+    PrefixUnmatched,
+    #[error("OrderNotFound")]
+    OrderNotFound,
+    #[error("InvalidArg: {0}")]
+    InvalidArg(&'static str),
+    #[error("OrderAlreadyExist")]
+    OrderAlreadyExist,
+    #[error("Serde: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("Reqwest: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Tungstenite: {0}")]
+    Tungstenite(Box<tokio_tungstenite::tungstenite::Error>),
+    #[error("ConnectionAbort: {0}")]
+    ConnectionAbort(String),
+    #[error("ConnectionInterrupted")]
+    ConnectionInterrupted,
+    #[error("Config: {0:?}")]
+    Config(#[from] toml::de::Error),
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for KrakenError {
+    fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+        KrakenError::Tungstenite(Box::new(error))
+    }
+}
+
+impl KrakenError {
+    /// Classifies the errors returned by the REST API, such as `EAPI:Invalid key`.
+    pub fn from_errors(errors: Vec<String>) -> Self {
+        match errors.iter().find(|error| {
+            error.starts_with("EAPI:Invalid key")
+                || error.starts_with("EAPI:Invalid signature")
+                || error.starts_with("EGeneral:Permission denied")
+        }) {
+            Some(error) => KrakenError::AuthError(error.clone()),
+            None => KrakenError::ApiError(errors),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            KrakenError::AuthError(msg) => Value::Map({
+                let mut map = HashMap::new();
+                map.insert("msg".to_string(), Value::String(msg.clone()));
+                map
+            }),
+            KrakenError::ApiError(errors) => Value::List(
+                errors
+                    .iter()
+                    .map(|error| Value::String(error.clone()))
+                    .collect(),
+            ),
+            KrakenError::PrefixUnmatched => Value::String(self.to_string()),
+            KrakenError::OrderNotFound => Value::String(self.to_string()),
+            KrakenError::InvalidArg(_) => Value::String(self.to_string()),
+            KrakenError::OrderAlreadyExist => Value::String(self.to_string()),
+            KrakenError::Serde(_) => Value::String(self.to_string()),
+            KrakenError::Reqwest(_) => Value::String(self.to_string()),
+            KrakenError::Tungstenite(_) => Value::String(self.to_string()),
+            KrakenError::ConnectionAbort(_) => Value::String(self.to_string()),
+            KrakenError::ConnectionInterrupted => Value::String(self.to_string()),
+            KrakenError::Config(_) => Value::String(self.to_string()),
+        }
+    }
+}
+
+fn default_book_depth() -> u32 {
+    100
+}
+
+#[derive(Deserialize)]
+pub struct Config {
+    public_url: String,
+    private_url: String,
+    rest_url: String,
+    api_key: String,
+    secret: String,
+    #[serde(default)]
+    order_prefix: String,
+    /// The depth of the book channel subscription, which is one of 10, 25, 100, 500 and 1000.
+    #[serde(default = "default_book_depth")]
+    book_depth: u32,
+}
+
+type SharedSymbolSet = Arc<Mutex<HashSet<String>>>;
+
+/// A connector for Kraken spot, which uses the WebSocket v2 API for the market data and the
+/// order updates, and the REST API for the order entry.
+///
+/// Symbols are the WebSocket v2 pair names, such as `BTC/USD`. Kraken Futures uses a separate API
+/// with its own endpoints, symbols and authentication, and is not supported by this connector.
+pub struct Kraken {
+    config: Config,
+    order_manager: SharedOrderManager,
+    symbols: SharedSymbolSet,
+    client: KrakenClient,
+    symbol_tx: Sender<String>,
+    resync_tx: Sender<String>,
+}
+
+impl Kraken {
+    fn connect_public_stream(&self, ev_tx: UnboundedSender<PublishEvent>) {
+        let public_url = self.config.public_url.clone();
+        let book_depth = self.config.book_depth;
+        let symbols = self.symbols.clone();
+        let symbol_tx = self.symbol_tx.clone();
+        let resync_tx = self.resync_tx.clone();
+
+        tokio::spawn(async move {
+            let _ = Retry::new(ExponentialBackoff::default())
+                .error_handler(|error: KrakenError| {
+                    error!(?error, "An error occurred in the public stream connection.");
+                    ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                            ErrorKind::ConnectionInterrupted,
+                            error.to_value(),
+                        ))))
+                        .unwrap();
+                    Ok(())
+                })
+                .retry(|| async {
+                    let mut stream = PublicStream::new(
+                        ev_tx.clone(),
+                        symbols.clone(),
+                        symbol_tx.subscribe(),
+                        resync_tx.subscribe(),
+                        book_depth,
+                    );
+                    stream.connect(&public_url).await?;
+                    Ok(())
+                })
+                .await;
+        });
+    }
+
+    fn connect_private_stream(&self, ev_tx: UnboundedSender<PublishEvent>) {
+        let private_url = self.config.private_url.clone();
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+        let symbols = self.symbols.clone();
+
+        tokio::spawn(async move {
+            let _ = Retry::new(ExponentialBackoff::default())
+                .error_handler(|error: KrakenError| {
+                    error!(
+                        ?error,
+                        "An error occurred in the private stream connection."
+                    );
+                    let kind = if matches!(error, KrakenError::AuthError(_)) {
+                        ErrorKind::AuthFailure
+                    } else {
+                        ErrorKind::ConnectionInterrupted
+                    };
+                    ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                            kind,
+                            error.to_value(),
+                        ))))
+                        .unwrap();
+                    Ok(())
+                })
+                .retry(|| async {
+                    let mut stream = PrivateStream::new(
+                        client.clone(),
+                        ev_tx.clone(),
+                        order_manager.clone(),
+                        symbols.clone(),
+                    );
+                    stream.connect(&private_url).await?;
+                    Ok(())
+                })
+                .await;
+        });
+    }
+}
+
+impl ConnectorBuilder for Kraken {
+    type Error = KrakenError;
+
+    fn build_from(config: &str) -> Result<Self, Self::Error> {
+        let config: Config = toml::from_str(config)?;
+        // The client order ID is a UUID or a free text of up to 18 characters.
+        if config.order_prefix.len() > 6 {
+            panic!("order prefix length should be not greater than 6.");
+        }
+        if [&config.public_url, &config.private_url, &config.rest_url]
+            .iter()
+            .any(|url| url.contains("futures.kraken.com"))
+        {
+            return Err(KrakenError::InvalidArg(
+                "Kraken Futures is not supported; only the Kraken Spot endpoints are.",
+            ));
+        }
+        let (symbol_tx, _) = broadcast::channel(500);
+        let (resync_tx, _) = broadcast::channel(500);
+        let order_manager = Arc::new(Mutex::new(OrderManager::new(&config.order_prefix)));
+        let client = KrakenClient::new(&config.rest_url, &config.api_key, &config.secret);
+        Ok(Kraken {
+            config,
+            order_manager,
+            symbols: Default::default(),
+            client,
+            symbol_tx,
+            resync_tx,
+        })
+    }
+}
+
+impl Connector for Kraken {
+    fn register(&mut self, symbol: String, _tick_size: f64, _lot_size: f64) {
+        let mut symbols = self.symbols.lock().unwrap();
+        if !symbols.contains(&symbol) {
+            symbols.insert(symbol.clone());
+            self.symbol_tx.send(symbol).unwrap();
+        }
+    }
+
+    fn order_manager(&self) -> Arc<Mutex<dyn GetOrders + Send + 'static>> {
+        self.order_manager.clone()
+    }
+
+    fn run(&mut self, ev_tx: UnboundedSender<PublishEvent>) {
+        self.connect_public_stream(ev_tx.clone());
+        self.connect_private_stream(ev_tx);
+    }
+
+    fn submit(&self, symbol: String, order: Order, ev_tx: UnboundedSender<PublishEvent>) {
+        let (cl_ord_id, params) = match self.order_manager.lock().unwrap().new_order(&symbol, order)
+        {
+            Ok(result) => result,
+            Err(error) => {
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.to_value(),
+                    ))))
+                    .unwrap();
+                return;
+            }
+        };
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+
+        tokio::spawn(async move {
+            // The order's status is updated by the executions channel once it's accepted.
+            if let Err(error) = client.add_order(&params).await {
+                if let Ok(order) = order_manager.lock().unwrap().update_submit_fail(&cl_ord_id) {
+                    ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                            symbol: order.symbol,
+                            order: order.order,
+                        }))
+                        .unwrap();
+                }
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.to_value(),
+                    ))))
+                    .unwrap();
+            }
+        });
+    }
+
+    fn cancel(&self, symbol: String, order: Order, ev_tx: UnboundedSender<PublishEvent>) {
+        let cl_ord_id = match self
+            .order_manager
+            .lock()
+            .unwrap()
+            .get_client_order_id(&symbol, order.order_id)
+        {
+            Ok(cl_ord_id) => cl_ord_id,
+            Err(error) => {
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.to_value(),
+                    ))))
+                    .unwrap();
+                return;
+            }
+        };
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+
+        tokio::spawn(async move {
+            if let Err(error) = client.cancel_order(&cl_ord_id).await {
+                if let Ok(order) = order_manager.lock().unwrap().update_cancel_fail(&cl_ord_id) {
+                    ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                            symbol: order.symbol,
+                            order: order.order,
+                        }))
+                        .unwrap();
+                }
+                ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+                        ErrorKind::OrderError,
+                        error.to_value(),
+                    ))))
+                    .unwrap();
+            }
+        });
+    }
+
+    fn resync(&self, symbol: String, _ev_tx: UnboundedSender<PublishEvent>) {
+        // The public stream resubscribes to the book channel to receive a fresh snapshot.
+        self.resync_tx.send(symbol).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+
+use hftbacktest::types::{Side, Status};
+use serde::{
+    de,
+    de::{Error, Unexpected, Visitor},
+    Deserialize,
+    Deserializer,
+};
+
+struct SideVisitor;
+
+impl<'de> Visitor<'de> for SideVisitor {
+    type Value = Side;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string containing \"buy\" or \"sell\"")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match s {
+            "buy" => Ok(Side::Buy),
+            "sell" => Ok(Side::Sell),
+            s => Err(Error::invalid_value(Unexpected::Other(s), &"buy or sell")),
+        }
+    }
+}
+
+fn from_str_to_side<'de, D>(deserializer: D) -> Result<Side, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(SideVisitor)
+}
+
+struct StatusVisitor;
+
+impl<'de> Visitor<'de> for StatusVisitor {
+    type Value = Status;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string containing an order status")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match s {
+            "pending_new" | "new" => Ok(Status::New),
+            "partially_filled" => Ok(Status::PartiallyFilled),
+            "filled" => Ok(Status::Filled),
+            "canceled" => Ok(Status::Canceled),
+            "expired" => Ok(Status::Expired),
+            _ => Ok(Status::Unsupported),
+        }
+    }
+}
+
+fn from_str_to_status<'de, D>(deserializer: D) -> Result<Status, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(StatusVisitor)
+}
+
+/// Converts the RFC 3339 timestamp into nanoseconds.
+fn from_rfc3339_to_nanos<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    chrono::DateTime::parse_from_rfc3339(&s)
+        .map_err(Error::custom)?
+        .timestamp_nanos_opt()
+        .ok_or_else(|| Error::custom("timestamp out of range"))
+}
+
+fn from_rfc3339_to_nanos_opt<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    from_rfc3339_to_nanos(deserializer).map(Some)
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum StreamMsg {
+    Channel(ChannelMsg),
+    Method(MethodResponse),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChannelMsg {
+    pub channel: String,
+    #[serde(rename = "type")]
+    #[serde(default)]
+    pub ty: Option<String>,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MethodResponse {
+    pub method: String,
+    #[serde(default)]
+    pub success: Option<bool>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PxQty {
+    pub price: f64,
+    pub qty: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Book {
+    pub symbol: String,
+    #[serde(default)]
+    pub bids: Vec<PxQty>,
+    #[serde(default)]
+    pub asks: Vec<PxQty>,
+    #[serde(default)]
+    #[serde(deserialize_with = "from_rfc3339_to_nanos_opt")]
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Trade {
+    pub symbol: String,
+    #[serde(deserialize_with = "from_str_to_side")]
+    pub side: Side,
+    pub price: f64,
+    pub qty: f64,
+    #[serde(deserialize_with = "from_rfc3339_to_nanos")]
+    pub timestamp: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Execution {
+    pub exec_type: String,
+    #[serde(default)]
+    pub cl_ord_id: String,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(deserialize_with = "from_str_to_status")]
+    pub order_status: Status,
+    #[serde(default)]
+    pub order_qty: Option<f64>,
+    #[serde(default)]
+    pub cum_qty: Option<f64>,
+    #[serde(default)]
+    pub last_qty: Option<f64>,
+    #[serde(default)]
+    pub last_price: Option<f64>,
+    #[serde(default)]
+    pub exec_id: Option<String>,
+    /// `m` for maker or `t` for taker.
+    #[serde(default)]
+    pub liquidity_ind: Option<String>,
+    #[serde(default)]
+    pub fee_usd_equiv: Option<f64>,
+    #[serde(deserialize_with = "from_rfc3339_to_nanos")]
+    pub timestamp: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Balance {
+    pub asset: String,
+    pub balance: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RestResponse<T> {
+    pub error: Vec<String>,
+    pub result: Option<T>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebSocketsToken {
+    pub token: String,
+}
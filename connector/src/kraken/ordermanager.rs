@@ -0,0 +1,205 @@
+use std::sync::{Arc, Mutex};
+
+use hashbrown::HashMap;
+use hftbacktest::{
+    prelude::get_precision,
+    types::{OrdType, Order, OrderId, PositionSide, Side, Status, TimeInForce},
+};
+
+use crate::{
+    connector::GetOrders,
+    kraken::{msg::Execution, KrakenError},
+    utils::{generate_rand_string, RefSymbolOrderId, SymbolOrderId},
+};
+
+pub type SharedOrderManager = Arc<Mutex<OrderManager>>;
+
+/// The client order ID, which identifies the order created by this connector.
+pub type ClientOrderId = String;
+
+/// The form parameters of the `AddOrder` request.
+pub type OrderParams = Vec<(&'static str, String)>;
+
+#[derive(Clone)]
+pub struct OrderExt {
+    pub symbol: String,
+    pub order: Order,
+}
+
+pub struct OrderManager {
+    prefix: String,
+    orders: HashMap<ClientOrderId, OrderExt>,
+    order_id_map: HashMap<SymbolOrderId, ClientOrderId>,
+}
+
+impl OrderManager {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            orders: Default::default(),
+            order_id_map: Default::default(),
+        }
+    }
+
+    pub fn update_execution(&mut self, data: &Execution) -> Result<OrderExt, KrakenError> {
+        if !data.cl_ord_id.starts_with(&self.prefix) {
+            return Err(KrakenError::PrefixUnmatched);
+        }
+        let order = self
+            .orders
+            .get_mut(&data.cl_ord_id)
+            .ok_or(KrakenError::OrderNotFound)?;
+        order.order.req = Status::None;
+        if data.order_status != Status::Unsupported {
+            order.order.status = data.order_status;
+        }
+        if let (Some(order_qty), Some(cum_qty)) = (data.order_qty, data.cum_qty) {
+            order.order.leaves_qty = order_qty - cum_qty;
+        }
+        if data.exec_type == "trade" {
+            order.order.exec_qty = data.last_qty.unwrap_or(0.0);
+            if let Some(last_price) = data.last_price {
+                order.order.exec_price_tick = (last_price / order.order.tick_size).round() as i64;
+            }
+            if let Some(liquidity_ind) = &data.liquidity_ind {
+                order.order.maker = liquidity_ind == "m";
+            }
+        } else {
+            order.order.exec_qty = 0.0;
+        }
+        order.order.exch_timestamp = data.timestamp;
+        if !order.order.active() {
+            self.order_id_map
+                .remove(&RefSymbolOrderId::new(&order.symbol, order.order.order_id));
+            Ok(self.orders.remove(&data.cl_ord_id).unwrap())
+        } else {
+            Ok(order.clone())
+        }
+    }
+
+    /// Registers the order and returns the client order ID with the `AddOrder` parameters.
+    pub fn new_order(
+        &mut self,
+        symbol: &str,
+        order: Order,
+    ) -> Result<(ClientOrderId, OrderParams), KrakenError> {
+        let cl_ord_id = format!("{}{}", self.prefix, generate_rand_string(12));
+        let mut params = vec![
+            ("pair", symbol.to_string()),
+            ("volume", order.qty.to_string()),
+            ("cl_ord_id", cl_ord_id.clone()),
+        ];
+        match order.side {
+            Side::Buy => params.push(("type", "buy".to_string())),
+            Side::Sell => params.push(("type", "sell".to_string())),
+            Side::None | Side::Unsupported => return Err(KrakenError::InvalidArg("side")),
+        }
+        match order.order_type {
+            OrdType::Limit => {
+                params.push(("ordertype", "limit".to_string()));
+                params.push((
+                    "price",
+                    format!(
+                        "{:.prec$}",
+                        order.price_tick as f64 * order.tick_size,
+                        prec = get_precision(order.tick_size)
+                    ),
+                ));
+            }
+            OrdType::Market => params.push(("ordertype", "market".to_string())),
+            OrdType::Unsupported => return Err(KrakenError::InvalidArg("order_type")),
+        }
+        match order.time_in_force {
+            TimeInForce::GTC => params.push(("timeinforce", "GTC".to_string())),
+            TimeInForce::GTX => {
+                params.push(("timeinforce", "GTC".to_string()));
+                params.push(("oflags", "post".to_string()));
+            }
+            TimeInForce::IOC => params.push(("timeinforce", "IOC".to_string())),
+            TimeInForce::FOK | TimeInForce::Unsupported => {
+                return Err(KrakenError::InvalidArg("time_in_force"))
+            }
+        }
+        if order.reduce_only {
+            params.push(("reduce_only", "true".to_string()));
+        }
+        // Kraken Futures doesn't have hedge mode.
+        if order.position_side != PositionSide::Both {
+            return Err(KrakenError::InvalidArg("position_side"));
+        }
+
+        let symbol_order_id = SymbolOrderId::new(symbol.to_string(), order.order_id);
+        if self.order_id_map.contains_key(&symbol_order_id) {
+            return Err(KrakenError::OrderAlreadyExist);
+        }
+
+        self.order_id_map.insert(symbol_order_id, cl_ord_id.clone());
+        self.orders.insert(
+            cl_ord_id.clone(),
+            OrderExt {
+                symbol: symbol.to_string(),
+                order,
+            },
+        );
+        Ok((cl_ord_id, params))
+    }
+
+    pub fn get_client_order_id(
+        &self,
+        symbol: &str,
+        order_id: OrderId,
+    ) -> Result<ClientOrderId, KrakenError> {
+        self.order_id_map
+            .get(&RefSymbolOrderId::new(symbol, order_id))
+            .cloned()
+            .ok_or(KrakenError::OrderNotFound)
+    }
+
+    pub fn update_submit_fail(&mut self, cl_ord_id: &str) -> Result<OrderExt, KrakenError> {
+        let mut order = self
+            .orders
+            .remove(cl_ord_id)
+            .ok_or(KrakenError::OrderNotFound)?;
+        order.order.req = Status::None;
+        order.order.status = Status::Expired;
+        self.order_id_map
+            .remove(&RefSymbolOrderId::new(&order.symbol, order.order.order_id));
+        Ok(order)
+    }
+
+    pub fn update_cancel_fail(&mut self, cl_ord_id: &str) -> Result<OrderExt, KrakenError> {
+        let mut order = self
+            .orders
+            .get(cl_ord_id)
+            .cloned()
+            .ok_or(KrakenError::OrderNotFound)?;
+        order.order.req = Status::None;
+        Ok(order)
+    }
+
+    /// Cancels all orders of all symbols, since Kraken's `CancelAll` is not per symbol.
+    pub fn cancel_all(&mut self) -> Vec<OrderExt> {
+        self.order_id_map.clear();
+        self.orders
+            .drain()
+            .map(|(_, mut order_ext)| {
+                order_ext.order.req = Status::None;
+                order_ext.order.status = Status::Canceled;
+                order_ext
+            })
+            .collect()
+    }
+}
+
+impl GetOrders for OrderManager {
+    fn orders(&self, symbol: Option<String>) -> Vec<Order> {
+        self.orders
+            .iter()
+            .filter(|(_, order)| {
+                symbol.as_ref().map(|s| order.symbol == *s).unwrap_or(true) && order.order.active()
+            })
+            .map(|(_, order)| &order.order)
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use hftbacktest::prelude::{Fill, LiveEvent, PositionSide};
+use serde_json::json;
+use tokio::{select, sync::mpsc::UnboundedSender, time};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{client::IntoClientRequest, Message},
+};
+use tracing::{debug, error};
+
+use crate::{
+    connector::PublishEvent,
+    kraken::{
+        msg::{Balance, Execution, StreamMsg},
+        ordermanager::SharedOrderManager,
+        rest::KrakenClient,
+        KrakenError,
+        SharedSymbolSet,
+    },
+};
+
+pub struct PrivateStream {
+    client: KrakenClient,
+    ev_tx: UnboundedSender<PublishEvent>,
+    order_manager: SharedOrderManager,
+    symbols: SharedSymbolSet,
+}
+
+impl PrivateStream {
+    pub fn new(
+        client: KrakenClient,
+        ev_tx: UnboundedSender<PublishEvent>,
+        order_manager: SharedOrderManager,
+        symbols: SharedSymbolSet,
+    ) -> Self {
+        Self {
+            client,
+            ev_tx,
+            order_manager,
+            symbols,
+        }
+    }
+
+    fn handle_private_stream(&self, text: &str) -> Result<(), KrakenError> {
+        match serde_json::from_str::<StreamMsg>(text)? {
+            StreamMsg::Method(resp) => {
+                if resp.success == Some(false) {
+                    let msg = resp.error.unwrap_or_default();
+                    if msg.contains("Invalid token") || msg.contains("EAPI:") {
+                        return Err(KrakenError::AuthError(msg));
+                    }
+                    error!(%msg, method = resp.method, "The request is rejected.");
+                } else {
+                    debug!(?resp, "Method");
+                }
+            }
+            StreamMsg::Channel(msg) => match msg.channel.as_str() {
+                "executions" => {
+                    let data: Vec<Execution> = serde_json::from_value(msg.data)?;
+                    for execution in data {
+                        let result = self
+                            .order_manager
+                            .lock()
+                            .unwrap()
+                            .update_execution(&execution);
+                        match result {
+                            Ok(order) => {
+                                // Reports the execution ahead of the order update.
+                                if execution.exec_type == "trade" {
+                                    let fill = Fill::from_order(
+                                        &order.order,
+                                        execution.exec_id.clone().unwrap_or_default(),
+                                        execution.fee_usd_equiv.unwrap_or(0.0),
+                                    );
+                                    self.ev_tx
+                                        .send(PublishEvent::LiveEvent(LiveEvent::Fill {
+                                            symbol: order.symbol.clone(),
+                                            fill,
+                                        }))
+                                        .unwrap();
+                                }
+                                self.ev_tx
+                                    .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                                        symbol: order.symbol,
+                                        order: order.order,
+                                    }))
+                                    .unwrap();
+                            }
+                            Err(KrakenError::PrefixUnmatched) => {
+                                // This order is not created by this connector.
+                            }
+                            Err(error) => {
+                                error!(?error, ?execution, "Couldn't update the order.");
+                            }
+                        }
+                    }
+                }
+                "balances" => {
+                    let data: Vec<Balance> = serde_json::from_value(msg.data)?;
+                    let symbols: Vec<_> = self.symbols.lock().unwrap().iter().cloned().collect();
+                    for balance in data {
+                        // Spot has no positions, so the balance of the base asset is published as
+                        // the position of each registered symbol quoted in it.
+                        for symbol in symbols
+                            .iter()
+                            .filter(|symbol| symbol.split('/').next() == Some(&balance.asset))
+                        {
+                            self.ev_tx
+                                .send(PublishEvent::LiveEvent(LiveEvent::Position {
+                                    symbol: symbol.clone(),
+                                    side: PositionSide::Both,
+                                    qty: balance.balance,
+                                    exch_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                                }))
+                                .unwrap();
+                        }
+                    }
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    pub async fn connect(&mut self, url: &str) -> Result<(), KrakenError> {
+        // The token must be used within 15 minutes of its creation, so a new one is obtained for
+        // every connection.
+        let token = self.client.get_ws_token().await?;
+
+        // Cancels all orders before connecting to the stream in order to start with the clean
+        // state.
+        self.client.cancel_all_orders().await?;
+        let orders = self.order_manager.lock().unwrap().cancel_all();
+        for order in orders {
+            self.ev_tx
+                .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                    symbol: order.symbol,
+                    order: order.order,
+                }))
+                .unwrap();
+        }
+
+        let request = url.into_client_request()?;
+        let (ws_stream, _) = connect_async(request).await?;
+        let (mut write, mut read) = ws_stream.split();
+        let mut interval = time::interval(Duration::from_secs(30));
+
+        for req in [
+            json!({
+                "method": "subscribe",
+                "params": {
+                    "channel": "executions",
+                    "token": token,
+                    "snap_orders": true,
+                    "snap_trades": false,
+                },
+            }),
+            json!({
+                "method": "subscribe",
+                "params": {
+                    "channel": "balances",
+                    "token": token,
+                },
+            }),
+        ] {
+            write.send(Message::Text(req.to_string())).await?;
+        }
+
+        loop {
+            select! {
+                _ = interval.tick() => {
+                    let s = json!({"method": "ping"}).to_string();
+                    write.send(Message::Text(s)).await?;
+                }
+                message = read.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        match self.handle_private_stream(&text) {
+                            Err(error @ KrakenError::AuthError(_)) => return Err(error),
+                            Err(error) => {
+                                error!(?error, %text, "Couldn't handle StreamMsg.");
+                            }
+                            Ok(()) => {}
+                        }
+                    }
+                    Some(Ok(Message::Ping(data))) => {
+                        write.send(Message::Pong(data)).await?;
+                    }
+                    Some(Ok(Message::Close(close_frame))) => {
+                        return Err(KrakenError::ConnectionAbort(
+                            close_frame.map(|f| f.to_string()).unwrap_or(String::new())
+                        ));
+                    }
+                    Some(Ok(Message::Binary(_)))
+                    | Some(Ok(Message::Frame(_)))
+                    | Some(Ok(Message::Pong(_))) => {}
+                    Some(Err(error)) => {
+                        return Err(KrakenError::from(error));
+                    }
+                    None => {
+                        return Err(KrakenError::ConnectionInterrupted);
+                    }
+                }
+            }
+        }
+    }
+}
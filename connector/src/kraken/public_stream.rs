@@ -0,0 +1,341 @@
+use std::{collections::HashSet, time::Duration};
+
+use chrono::Utc;
+use connector_sdk::{FeedError, MarketDataHandler};
+use futures_util::{Sink, SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    select,
+    sync::{
+        broadcast::{error::RecvError, Receiver},
+        mpsc::UnboundedSender,
+    },
+    time,
+};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite,
+    tungstenite::{client::IntoClientRequest, Message},
+};
+use tracing::{debug, error};
+
+use crate::{
+    connector::PublishEvent,
+    kraken::{
+        msg::{Book, PxQty, StreamMsg, Trade},
+        KrakenError,
+        SharedSymbolSet,
+    },
+    toolkit::{
+        decode_error,
+        feed::{publish_depth, publish_trade},
+    },
+};
+
+/// Decodes the book and trade channels. The book channel carries a checksum rather than sequence
+/// numbers, so a gap can't be detected; the updates are applied only on top of a snapshot.
+#[derive(Default)]
+pub struct MarketData {
+    synced: HashSet<String>,
+}
+
+impl MarketDataHandler for MarketData {
+    type Message = String;
+
+    fn handle(
+        &mut self,
+        text: &String,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        match serde_json::from_str::<StreamMsg>(text).map_err(decode_error)? {
+            StreamMsg::Method(resp) => {
+                if resp.success == Some(false) {
+                    error!(?resp, "The request is rejected.");
+                } else {
+                    debug!(?resp, "Method");
+                }
+            }
+            StreamMsg::Channel(msg) => match msg.channel.as_str() {
+                "book" => {
+                    let data = Vec::<Book>::deserialize(&msg.data).map_err(decode_error)?;
+                    // The snapshot is sent upon every subscription, including the ones after a
+                    // reconnection or a resync, and replaces the current market depth.
+                    let snapshot = msg.ty.as_deref() == Some("snapshot");
+                    for book in data {
+                        if snapshot {
+                            self.synced.insert(book.symbol.clone());
+                        } else if !self.synced.contains(&book.symbol) {
+                            continue;
+                        }
+                        let exch_ts = book
+                            .timestamp
+                            .unwrap_or_else(|| Utc::now().timestamp_nanos_opt().unwrap());
+                        let to_levels = |levels: &[PxQty]| {
+                            levels
+                                .iter()
+                                .map(|level| (level.price, level.qty))
+                                .collect::<Vec<_>>()
+                        };
+                        publish_depth(
+                            ev_tx,
+                            &book.symbol,
+                            exch_ts,
+                            &to_levels(&book.bids),
+                            &to_levels(&book.asks),
+                            snapshot,
+                        );
+                    }
+                }
+                "trade" => {
+                    let data = Vec::<Trade>::deserialize(&msg.data).map_err(decode_error)?;
+                    for trade in data {
+                        publish_trade(
+                            ev_tx,
+                            &trade.symbol,
+                            trade.side,
+                            trade.timestamp,
+                            trade.price,
+                            trade.qty,
+                        );
+                    }
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.synced.clear();
+    }
+}
+
+pub struct PublicStream {
+    ev_tx: UnboundedSender<PublishEvent>,
+    symbols: SharedSymbolSet,
+    symbol_rx: Receiver<String>,
+    resync_rx: Receiver<String>,
+    book_depth: u32,
+    market_data: MarketData,
+}
+
+impl PublicStream {
+    pub fn new(
+        ev_tx: UnboundedSender<PublishEvent>,
+        symbols: SharedSymbolSet,
+        symbol_rx: Receiver<String>,
+        resync_rx: Receiver<String>,
+        book_depth: u32,
+    ) -> Self {
+        Self {
+            ev_tx,
+            symbols,
+            symbol_rx,
+            resync_rx,
+            book_depth,
+            market_data: Default::default(),
+        }
+    }
+
+    pub async fn connect(&mut self, url: &str) -> Result<(), KrakenError> {
+        let request = url.into_client_request()?;
+        let (ws_stream, _) = connect_async(request).await?;
+        let (mut write, mut read) = ws_stream.split();
+        let mut interval = time::interval(Duration::from_secs(30));
+
+        // Resubscribes to the symbols registered before the reconnection. The snapshot sent upon
+        // the subscription replaces the market depth that misses the updates during the
+        // disconnection.
+        let symbols: Vec<_> = self.symbols.lock().unwrap().iter().cloned().collect();
+        for symbol in symbols {
+            subscribe(&mut write, &symbol, self.book_depth).await?;
+        }
+
+        loop {
+            select! {
+                _ = interval.tick() => {
+                    let s = json!({"method": "ping"}).to_string();
+                    write.send(Message::Text(s)).await?;
+                }
+                msg = self.symbol_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        subscribe(&mut write, &symbol, self.book_depth).await?;
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} subscription requests were missed.");
+                    }
+                },
+                msg = self.resync_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        resubscribe(&mut write, &symbol, self.book_depth).await?;
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} resync requests were missed.");
+                    }
+                },
+                message = read.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Err(error) = self.market_data.handle(&text, &self.ev_tx) {
+                            error!(?error, %text, "Couldn't handle StreamMsg.");
+                        }
+                    }
+                    Some(Ok(Message::Ping(data))) => {
+                        write.send(Message::Pong(data)).await?;
+                    }
+                    Some(Ok(Message::Close(close_frame))) => {
+                        return Err(KrakenError::ConnectionAbort(
+                            close_frame.map(|f| f.to_string()).unwrap_or(String::new())
+                        ));
+                    }
+                    Some(Ok(Message::Binary(_)))
+                    | Some(Ok(Message::Frame(_)))
+                    | Some(Ok(Message::Pong(_))) => {}
+                    Some(Err(error)) => {
+                        return Err(KrakenError::from(error));
+                    }
+                    None => {
+                        return Err(KrakenError::ConnectionInterrupted);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn subscribe<S>(write: &mut S, symbol: &str, book_depth: u32) -> Result<(), KrakenError>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    let book = json!({
+        "method": "subscribe",
+        "params": {
+            "channel": "book",
+            "symbol": [symbol],
+            "depth": book_depth,
+        },
+    });
+    let trade = json!({
+        "method": "subscribe",
+        "params": {
+            "channel": "trade",
+            "symbol": [symbol],
+            "snapshot": false,
+        },
+    });
+    write.send(Message::Text(book.to_string())).await?;
+    write.send(Message::Text(trade.to_string())).await?;
+    Ok(())
+}
+
+async fn resubscribe<S>(write: &mut S, symbol: &str, book_depth: u32) -> Result<(), KrakenError>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    // Resubscribes to the book channel, which sends a fresh snapshot.
+    for method in ["unsubscribe", "subscribe"] {
+        let req = json!({
+            "method": method,
+            "params": {
+                "channel": "book",
+                "symbol": [symbol],
+                "depth": book_depth,
+            },
+        });
+        write.send(Message::Text(req.to_string())).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use connector_sdk::{
+        conformance::{ConformanceSuite, VenueEncoder},
+        ordermanager::OrderUpdate,
+    };
+    use hftbacktest::prelude::Side;
+    use serde_json::json;
+
+    use super::MarketData;
+    use crate::utils::PxQty;
+
+    struct Encoder;
+
+    impl Encoder {
+        fn book(symbol: &str, ty: &str, bids: &[PxQty], asks: &[PxQty]) -> String {
+            let levels = |levels: &[PxQty]| {
+                levels
+                    .iter()
+                    .map(|&(px, qty)| json!({"price": px, "qty": qty}))
+                    .collect::<Vec<_>>()
+            };
+            json!({
+                "channel": "book",
+                "type": ty,
+                "data": [{
+                    "symbol": symbol,
+                    "bids": levels(bids),
+                    "asks": levels(asks),
+                    "checksum": 0,
+                    "timestamp": "2024-01-01T00:00:00.000000Z",
+                }],
+            })
+            .to_string()
+        }
+    }
+
+    impl VenueEncoder for Encoder {
+        type MarketData = String;
+        type OrderMessage = ();
+
+        fn snapshot(&self, symbol: &str, _seq: i64, bids: &[PxQty], asks: &[PxQty]) -> String {
+            Self::book(symbol, "snapshot", bids, asks)
+        }
+
+        fn depth_update(
+            &self,
+            symbol: &str,
+            _seq: i64,
+            bids: &[PxQty],
+            asks: &[PxQty],
+        ) -> Vec<String> {
+            vec![Self::book(symbol, "update", bids, asks)]
+        }
+
+        fn trade(&self, symbol: &str, side: Side, px: f64, qty: f64) -> String {
+            let side = if side == Side::Sell { "sell" } else { "buy" };
+            json!({
+                "channel": "trade",
+                "type": "update",
+                "data": [{
+                    "symbol": symbol,
+                    "side": side,
+                    "price": px,
+                    "qty": qty,
+                    "ord_type": "limit",
+                    "trade_id": 1,
+                    "timestamp": "2024-01-01T00:00:00.000000Z",
+                }],
+            })
+            .to_string()
+        }
+
+        fn order_update(&self, _symbol: &str, _client_order_id: &str, _update: &OrderUpdate) {}
+    }
+
+    #[test]
+    fn conformance() {
+        let suite = ConformanceSuite::new(Encoder, "BTC/USD");
+        suite.check_snapshot(MarketData::default()).unwrap();
+        suite.check_incremental(MarketData::default()).unwrap();
+        // The book channel has no sequence numbers, so `check_seq_gap` doesn't apply.
+        suite.check_reconnection(MarketData::default()).unwrap();
+        suite.check_trade(MarketData::default()).unwrap();
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::kraken::{
+    msg::{RestResponse, WebSocketsToken},
+    KrakenError,
+};
+
+#[derive(Clone)]
+pub struct KrakenClient {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    secret: String,
+    nonce: Arc<AtomicU64>,
+}
+
+impl KrakenClient {
+    pub fn new(url: &str, api_key: &str, secret: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            api_key: api_key.to_string(),
+            secret: secret.to_string(),
+            nonce: Default::default(),
+        }
+    }
+
+    /// Returns a nonce, which must increase with every request made with the API key.
+    fn nonce(&self) -> u64 {
+        let now = Utc::now().timestamp_micros() as u64;
+        let prev = self
+            .nonce
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |prev| {
+                Some(now.max(prev + 1))
+            })
+            .unwrap();
+        now.max(prev + 1)
+    }
+
+    fn sign(&self, path: &str, nonce: u64, body: &str) -> Result<String, KrakenError> {
+        let secret = STANDARD
+            .decode(&self.secret)
+            .map_err(|_| KrakenError::InvalidArg("secret"))?;
+        let mut sha256 = Sha256::new();
+        sha256.update(nonce.to_string().as_bytes());
+        sha256.update(body.as_bytes());
+        let mut mac = Hmac::<Sha512>::new_from_slice(&secret).unwrap();
+        mac.update(path.as_bytes());
+        mac.update(&sha256.finalize());
+        Ok(STANDARD.encode(mac.finalize().into_bytes()))
+    }
+
+    async fn post<T: for<'a> Deserialize<'a>>(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> Result<T, KrakenError> {
+        let nonce = self.nonce();
+        let mut body = format!("nonce={nonce}");
+        for (key, value) in params {
+            body.push('&');
+            body.push_str(key);
+            body.push('=');
+            body.push_str(&urlencode(value));
+        }
+        let signature = self.sign(path, nonce, &body)?;
+        let resp: RestResponse<T> = self
+            .client
+            .post(format!("{}{}", self.url, path))
+            .header("Accept", "application/json")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("API-Key", &self.api_key)
+            .header("API-Sign", signature)
+            .body(body)
+            .send()
+            .await?
+            .json()
+            .await?;
+        match resp.result {
+            Some(result) if resp.error.is_empty() => Ok(result),
+            _ => Err(KrakenError::from_errors(resp.error)),
+        }
+    }
+
+    /// Returns the token to subscribe to the private WebSocket channels.
+    pub async fn get_ws_token(&self) -> Result<String, KrakenError> {
+        let resp: WebSocketsToken = self.post("/0/private/GetWebSocketsToken", &[]).await?;
+        Ok(resp.token)
+    }
+
+    pub async fn add_order(&self, params: &[(&str, String)]) -> Result<(), KrakenError> {
+        let _: serde_json::Value = self.post("/0/private/AddOrder", params).await?;
+        Ok(())
+    }
+
+    pub async fn cancel_order(&self, cl_ord_id: &str) -> Result<(), KrakenError> {
+        let _: serde_json::Value = self
+            .post(
+                "/0/private/CancelOrder",
+                &[("cl_ord_id", cl_ord_id.to_string())],
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn cancel_all_orders(&self) -> Result<(), KrakenError> {
+        let _: serde_json::Value = self.post("/0/private/CancelAll", &[]).await?;
+        Ok(())
+    }
+}
+
+fn urlencode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+
+use hftbacktest::types::Side;
+use serde::{
+    de,
+    de::{Error, Unexpected, Visitor},
+    Deserialize,
+    Deserializer,
+};
+
+use crate::utils::{from_str_to_f64_opt, PxQty};
+
+struct SideVisitor;
+
+impl<'de> Visitor<'de> for SideVisitor {
+    type Value = Side;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string containing \"buy\" or \"sell\"")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match s {
+            "buy" => Ok(Side::Buy),
+            "sell" => Ok(Side::Sell),
+            s => Err(Error::invalid_value(Unexpected::Other(s), &"buy or sell")),
+        }
+    }
+}
+
+fn from_str_to_side<'de, D>(deserializer: D) -> Result<Side, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(SideVisitor)
+}
+
+struct NumVisitor;
+
+impl<'de> Visitor<'de> for NumVisitor {
+    type Value = f64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number or a string containing a number")
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(v)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(v as f64)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(v as f64)
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        s.parse::<f64>().map_err(Error::custom)
+    }
+}
+
+/// KuCoin sends some numbers as strings in one message and as numbers in another.
+fn from_num_or_str_to_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(NumVisitor)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RestResponse<T> {
+    pub code: String,
+    #[serde(default)]
+    pub msg: Option<String>,
+    #[serde(default = "Option::default")]
+    pub data: Option<T>,
+}
+
+/// The response of the `bullet-public` and `bullet-private` endpoints, which gives the token to
+/// connect to the WebSocket stream.
+#[derive(Deserialize, Debug)]
+pub struct Bullet {
+    pub token: String,
+    #[serde(rename = "instanceServers")]
+    pub instance_servers: Vec<InstanceServer>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct InstanceServer {
+    pub endpoint: String,
+    /// In milliseconds.
+    #[serde(rename = "pingInterval")]
+    pub ping_interval: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Level2Snapshot {
+    pub sequence: i64,
+    pub bids: Vec<PxQty>,
+    pub asks: Vec<PxQty>,
+    /// In nanoseconds.
+    pub ts: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StreamMsg {
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub code: Option<serde_json::Value>,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Level2 {
+    pub sequence: i64,
+    /// `price,side,size`, where the size of `0` deletes the level.
+    pub change: String,
+    /// In milliseconds.
+    pub timestamp: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Execution {
+    #[serde(deserialize_with = "from_str_to_side")]
+    pub side: Side,
+    #[serde(deserialize_with = "from_num_or_str_to_f64")]
+    pub size: f64,
+    #[serde(deserialize_with = "from_num_or_str_to_f64")]
+    pub price: f64,
+    /// In nanoseconds.
+    pub ts: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TradeOrder {
+    pub symbol: String,
+    /// `open`, `match`, `filled`, `canceled` or `update`.
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(rename = "clientOid")]
+    #[serde(default)]
+    pub client_oid: Option<String>,
+    #[serde(rename = "remainSize")]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub remain_size: Option<f64>,
+    #[serde(rename = "matchSize")]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub match_size: Option<f64>,
+    #[serde(rename = "matchPrice")]
+    #[serde(default)]
+    #[serde(deserialize_with = "from_str_to_f64_opt")]
+    pub match_price: Option<f64>,
+    #[serde(rename = "tradeId")]
+    #[serde(default)]
+    pub trade_id: Option<String>,
+    /// `maker` or `taker`, only in the `match` message.
+    #[serde(default)]
+    pub liquidity: Option<String>,
+    /// In nanoseconds.
+    pub ts: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Position {
+    #[serde(rename = "currentQty")]
+    #[serde(default)]
+    pub current_qty: Option<f64>,
+    #[serde(rename = "currentTimestamp")]
+    #[serde(default)]
+    pub current_timestamp: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PositionItem {
+    pub symbol: String,
+    #[serde(rename = "currentQty")]
+    pub current_qty: f64,
+    #[serde(rename = "currentTimestamp")]
+    pub current_timestamp: i64,
+}
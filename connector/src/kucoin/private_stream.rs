@@ -0,0 +1,290 @@
+use std::{collections::HashSet, time::Duration};
+
+use chrono::Utc;
+use hftbacktest::prelude::{LiveEvent, PositionSide, Status};
+use serde_json::json;
+use tokio::{
+    select,
+    sync::{
+        broadcast::{error::RecvError, Receiver},
+        mpsc::UnboundedSender,
+    },
+};
+use tracing::{debug, error};
+
+use crate::{
+    connector::PublishEvent,
+    kucoin::{
+        msg::{Position, StreamMsg, TradeOrder},
+        rest::KucoinClient,
+        KucoinError,
+    },
+    toolkit::{
+        ordermanager::{OrderManagerError, OrderUpdate, SharedClientOrderManager},
+        symbol::SharedSymbolNormalizer,
+        ws::WsSession,
+    },
+    utils::generate_rand_string,
+};
+
+pub struct PrivateStream {
+    client: KucoinClient,
+    ev_tx: UnboundedSender<PublishEvent>,
+    order_manager: SharedClientOrderManager,
+    symbols: SharedSymbolNormalizer,
+    symbol_rx: Receiver<String>,
+}
+
+impl PrivateStream {
+    pub fn new(
+        client: KucoinClient,
+        ev_tx: UnboundedSender<PublishEvent>,
+        order_manager: SharedClientOrderManager,
+        symbols: SharedSymbolNormalizer,
+        symbol_rx: Receiver<String>,
+    ) -> Self {
+        Self {
+            client,
+            ev_tx,
+            order_manager,
+            symbols,
+            symbol_rx,
+        }
+    }
+
+    fn handle_trade_order(&self, data: TradeOrder) {
+        let Some(client_oid) = &data.client_oid else {
+            // This order is not created by this connector.
+            return;
+        };
+        let status = match data.ty.as_str() {
+            "open" => Status::New,
+            "match" if data.remain_size == Some(0.0) => Status::Filled,
+            "match" => Status::PartiallyFilled,
+            "filled" => Status::Filled,
+            "canceled" => Status::Canceled,
+            _ => Status::Unsupported,
+        };
+        // The fill is reported only by the `match` message, which precedes the `filled` message.
+        let (exec_qty, exec_price) = if data.ty == "match" {
+            (
+                data.match_size.unwrap_or(0.0),
+                data.match_price.unwrap_or(0.0),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        let update = OrderUpdate {
+            status,
+            leaves_qty: data.remain_size,
+            exec_qty,
+            exec_price,
+            exch_ts: data.ts,
+            maker: data
+                .liquidity
+                .as_deref()
+                .map(|liquidity| liquidity == "maker"),
+            // The fee isn't reported with the fill.
+            fee: 0.0,
+            trade_id: data.trade_id.clone(),
+        };
+        let result = self
+            .order_manager
+            .lock()
+            .unwrap()
+            .update(client_oid, update.clone());
+        match result {
+            Ok(order) => {
+                // Reports the execution ahead of the order update.
+                if let Some(fill) = update.fill(&order.order) {
+                    self.ev_tx
+                        .send(PublishEvent::LiveEvent(LiveEvent::Fill {
+                            symbol: order.symbol.clone(),
+                            fill,
+                        }))
+                        .unwrap();
+                }
+                self.ev_tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                        symbol: order.symbol,
+                        order: order.order,
+                    }))
+                    .unwrap();
+            }
+            Err(OrderManagerError::PrefixUnmatched) => {
+                // This order is not created by this connector.
+            }
+            Err(error) => {
+                error!(?error, ?data, "Couldn't update the order.");
+            }
+        }
+    }
+
+    fn handle_text(&self, text: &str) -> Result<(), KucoinError> {
+        let msg: StreamMsg = serde_json::from_str(text)?;
+        match msg.ty.as_str() {
+            "message" => {
+                let topic = msg.topic.unwrap_or_default();
+                if topic == "/contractMarket/tradeOrders" {
+                    let data: TradeOrder = serde_json::from_value(msg.data)?;
+                    self.handle_trade_order(data);
+                } else if let Some(symbol) = topic.strip_prefix("/contract/position:") {
+                    let data: Position = serde_json::from_value(msg.data)?;
+                    // The settlement and the funding messages don't carry the quantity.
+                    if let Some(qty) = data.current_qty {
+                        let local = self.symbols.lock().unwrap().to_local(symbol);
+                        self.ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Position {
+                                symbol: local,
+                                side: PositionSide::Both,
+                                qty,
+                                exch_ts: data.current_timestamp.unwrap_or(0) * 1_000_000,
+                            }))
+                            .unwrap();
+                    }
+                }
+            }
+            "error" => {
+                error!(?msg, "The request is rejected.");
+            }
+            _ => {
+                debug!(?msg, "StreamMsg");
+            }
+        }
+        Ok(())
+    }
+
+    fn cancel_all(&self, symbol: String) {
+        let client = self.client.clone();
+        let order_manager = self.order_manager.clone();
+        let ev_tx = self.ev_tx.clone();
+        let local = self.symbols.lock().unwrap().to_local(&symbol);
+        tokio::spawn(async move {
+            match client.cancel_all_orders(&symbol).await {
+                Ok(()) => {
+                    let orders = order_manager.lock().unwrap().cancel_all(&local);
+                    for order in orders {
+                        ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Order {
+                                symbol: local.clone(),
+                                order,
+                            }))
+                            .unwrap();
+                    }
+                }
+                Err(error) => {
+                    error!(?error, %symbol, "Couldn't cancel all orders.");
+                }
+            }
+        });
+    }
+
+    /// Fetches the positions, which are published as `0` for the registered symbols without a
+    /// position.
+    fn get_positions(&self) {
+        let client = self.client.clone();
+        let symbols = self.symbols.clone();
+        let ev_tx = self.ev_tx.clone();
+        tokio::spawn(async move {
+            match client.get_positions().await {
+                Ok(positions) => {
+                    let symbols = symbols.lock().unwrap();
+                    let mut remaining: HashSet<_> = symbols.venue_symbols().into_iter().collect();
+                    for position in positions {
+                        if !remaining.remove(&position.symbol) {
+                            continue;
+                        }
+                        ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Position {
+                                symbol: symbols.to_local(&position.symbol),
+                                side: PositionSide::Both,
+                                qty: position.current_qty,
+                                exch_ts: position.current_timestamp * 1_000_000,
+                            }))
+                            .unwrap();
+                    }
+                    for symbol in remaining {
+                        ev_tx
+                            .send(PublishEvent::LiveEvent(LiveEvent::Position {
+                                symbol: symbols.to_local(&symbol),
+                                side: PositionSide::Both,
+                                qty: 0.0,
+                                exch_ts: Utc::now().timestamp_nanos_opt().unwrap(),
+                            }))
+                            .unwrap();
+                    }
+                }
+                Err(error) => {
+                    error!(?error, "Couldn't get the positions.");
+                }
+            }
+        });
+    }
+
+    pub async fn connect(&mut self) -> Result<(), KucoinError> {
+        // A new token is required for every connection.
+        let bullet = self.client.bullet_private().await?;
+        let server = bullet
+            .instance_servers
+            .first()
+            .ok_or(KucoinError::InvalidResponse)?;
+        let url = format!(
+            "{}?token={}&connectId={}",
+            server.endpoint,
+            bullet.token,
+            generate_rand_string(16)
+        );
+        let mut ws = WsSession::connect(&url).await?.keepalive(
+            Duration::from_millis(server.ping_interval),
+            json!({"id": "ping", "type": "ping"}).to_string(),
+        );
+
+        subscribe(&mut ws, "/contractMarket/tradeOrders".to_string()).await?;
+        // Cancels all orders in order to start with the clean state.
+        let symbols = self.symbols.lock().unwrap().venue_symbols();
+        for symbol in symbols {
+            subscribe(&mut ws, format!("/contract/position:{symbol}")).await?;
+            self.cancel_all(symbol);
+        }
+        self.get_positions();
+
+        loop {
+            select! {
+                msg = self.symbol_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        subscribe(&mut ws, format!("/contract/position:{symbol}")).await?;
+                        self.cancel_all(symbol);
+                        self.get_positions();
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} subscription requests were missed.");
+                    }
+                },
+                text = ws.recv() => {
+                    let text = text?;
+                    if let Err(error) = self.handle_text(&text) {
+                        error!(?error, %text, "Couldn't handle StreamMsg.");
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn subscribe(ws: &mut WsSession, topic: String) -> Result<(), KucoinError> {
+    ws.send(
+        json!({
+            "id": generate_rand_string(16),
+            "type": "subscribe",
+            "topic": topic,
+            "privateChannel": true,
+            "response": true,
+        })
+        .to_string(),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,503 @@
+use std::{collections::HashMap, mem, time::Duration};
+
+use connector_sdk::{FeedError, MarketDataHandler};
+use serde_json::json;
+use tokio::{
+    select,
+    sync::{
+        broadcast::{error::RecvError, Receiver},
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    },
+};
+use tracing::{debug, error, warn};
+
+use crate::{
+    connector::PublishEvent,
+    kucoin::{
+        msg::{Execution, Level2, Level2Snapshot, StreamMsg},
+        rest::KucoinClient,
+        KucoinError,
+    },
+    toolkit::{
+        decode_error,
+        feed::{publish_clear, publish_depth, publish_seq_gap, publish_trade},
+        symbol::SharedSymbolNormalizer,
+        ws::WsSession,
+    },
+    utils::generate_rand_string,
+};
+
+/// The synchronization state of the local order book of a symbol, which follows
+/// `<https://www.kucoin.com/docs/websocket/futures-trading/public-channels/level2-market-data>`.
+enum BookState {
+    /// Buffers the level 2 updates until the REST snapshot arrives.
+    Snapshotting(Vec<Level2>),
+    /// The updates are applied in sequence. Holds the last sequence.
+    Synced(i64),
+}
+
+/// A message of the public stream, or the REST snapshot of a symbol requested by
+/// [`MarketData`].
+pub enum PublicMsg {
+    Text(String),
+    Snapshot(String, Level2Snapshot),
+}
+
+/// Synchronizes the local order books from the level 2 updates and the REST snapshots, and decodes
+/// the trades. Since it doesn't perform I/O, the snapshots it awaits are requested by the stream
+/// through [`MarketData::take_snapshot_requests`].
+pub struct MarketData {
+    symbols: SharedSymbolNormalizer,
+    books: HashMap<String, BookState>,
+    snapshot_requests: Vec<String>,
+}
+
+impl MarketData {
+    pub fn new(symbols: SharedSymbolNormalizer) -> Self {
+        Self {
+            symbols,
+            books: Default::default(),
+            snapshot_requests: Vec::new(),
+        }
+    }
+
+    /// Awaits a fresh snapshot of the symbol, such as upon the resync request.
+    pub fn resync(&mut self, symbol: String) {
+        self.await_snapshot(symbol, Vec::new());
+    }
+
+    /// Takes the symbols whose REST snapshot needs to be requested.
+    pub fn take_snapshot_requests(&mut self) -> Vec<String> {
+        mem::take(&mut self.snapshot_requests)
+    }
+
+    /// Awaits the REST snapshot, buffering the level 2 updates until it arrives.
+    fn await_snapshot(&mut self, symbol: String, pending: Vec<Level2>) {
+        self.books
+            .insert(symbol.clone(), BookState::Snapshotting(pending));
+        self.snapshot_requests.push(symbol);
+    }
+
+    fn process_level2(
+        &mut self,
+        symbol: String,
+        data: Level2,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        match self.books.get_mut(&symbol) {
+            None => {
+                self.await_snapshot(symbol, vec![data]);
+            }
+            Some(BookState::Snapshotting(pending)) => {
+                pending.push(data);
+            }
+            Some(BookState::Synced(sequence)) => {
+                if data.sequence <= *sequence {
+                    // Already reflected in the snapshot.
+                } else if data.sequence == *sequence + 1 {
+                    *sequence = data.sequence;
+                    self.publish_level2(&symbol, &data, ev_tx)?;
+                } else {
+                    let expected = *sequence + 1;
+                    let received = data.sequence;
+                    self.await_snapshot(symbol.clone(), vec![data]);
+                    return Err(FeedError::SeqGap {
+                        symbol: self.symbols.lock().unwrap().to_local(&symbol),
+                        expected,
+                        received,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn publish_level2(
+        &self,
+        symbol: &str,
+        data: &Level2,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        let invalid = || FeedError::Decode(format!("invalid level 2 change: {}", data.change));
+        let mut parts = data.change.split(',');
+        let (Some(px), Some(side), Some(qty)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let (Ok(px), Ok(qty)) = (px.parse::<f64>(), qty.parse::<f64>()) else {
+            return Err(invalid());
+        };
+        let local = self.symbols.lock().unwrap().to_local(symbol);
+        let level = [(px, qty)];
+        let (bids, asks): (&[_], &[_]) = if side == "buy" {
+            (&level, &[])
+        } else {
+            (&[], &level)
+        };
+        publish_depth(ev_tx, &local, data.timestamp * 1_000_000, bids, asks, false);
+        Ok(())
+    }
+
+    fn process_snapshot(
+        &mut self,
+        symbol: &str,
+        data: &Level2Snapshot,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        let pending = match self.books.remove(symbol) {
+            Some(BookState::Snapshotting(pending)) => pending,
+            Some(BookState::Synced(sequence)) if data.sequence <= sequence => {
+                // A snapshot older than the market depth, which is no longer awaited.
+                self.books
+                    .insert(symbol.to_string(), BookState::Synced(sequence));
+                return Ok(());
+            }
+            _ => Vec::new(),
+        };
+        // The snapshot replaces the market depth, which may be stale due to the gap.
+        let local = self.symbols.lock().unwrap().to_local(symbol);
+        publish_depth(ev_tx, &local, data.ts, &data.bids, &data.asks, true);
+        self.books
+            .insert(symbol.to_string(), BookState::Synced(data.sequence));
+        // Re-sequences the updates buffered while the snapshot was being fetched. Once a gap is
+        // found, the rest are buffered for the next snapshot.
+        let mut result = Ok(());
+        for level2 in pending {
+            result = result.and(self.process_level2(symbol.to_string(), level2, ev_tx));
+        }
+        result
+    }
+
+    fn process_text(
+        &mut self,
+        text: &str,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        let msg: StreamMsg = serde_json::from_str(text).map_err(decode_error)?;
+        match msg.ty.as_str() {
+            "message" => {
+                let topic = msg.topic.unwrap_or_default();
+                if let Some(symbol) = topic.strip_prefix("/contractMarket/level2:") {
+                    let data: Level2 = serde_json::from_value(msg.data).map_err(decode_error)?;
+                    self.process_level2(symbol.to_string(), data, ev_tx)?;
+                } else if let Some(symbol) = topic.strip_prefix("/contractMarket/execution:") {
+                    let data: Execution = serde_json::from_value(msg.data).map_err(decode_error)?;
+                    let local = self.symbols.lock().unwrap().to_local(symbol);
+                    publish_trade(ev_tx, &local, data.side, data.ts, data.price, data.size);
+                }
+            }
+            "error" => {
+                error!(?msg, "The request is rejected.");
+            }
+            _ => {
+                debug!(?msg, "StreamMsg");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MarketDataHandler for MarketData {
+    type Message = PublicMsg;
+
+    fn handle(
+        &mut self,
+        msg: &PublicMsg,
+        ev_tx: &UnboundedSender<PublishEvent>,
+    ) -> Result<(), FeedError> {
+        match msg {
+            PublicMsg::Text(text) => self.process_text(text, ev_tx),
+            PublicMsg::Snapshot(symbol, data) => self.process_snapshot(symbol, data, ev_tx),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.books.clear();
+        self.snapshot_requests.clear();
+    }
+}
+
+pub struct PublicStream {
+    client: KucoinClient,
+    ev_tx: UnboundedSender<PublishEvent>,
+    symbols: SharedSymbolNormalizer,
+    symbol_rx: Receiver<String>,
+    resync_rx: Receiver<String>,
+    market_data: MarketData,
+    rest_tx: UnboundedSender<(String, Level2Snapshot)>,
+    rest_rx: UnboundedReceiver<(String, Level2Snapshot)>,
+}
+
+impl PublicStream {
+    pub fn new(
+        client: KucoinClient,
+        ev_tx: UnboundedSender<PublishEvent>,
+        symbols: SharedSymbolNormalizer,
+        symbol_rx: Receiver<String>,
+        resync_rx: Receiver<String>,
+    ) -> Self {
+        let (rest_tx, rest_rx) = unbounded_channel();
+        Self {
+            client,
+            ev_tx,
+            market_data: MarketData::new(symbols.clone()),
+            symbols,
+            symbol_rx,
+            resync_rx,
+            rest_tx,
+            rest_rx,
+        }
+    }
+
+    /// Requests the REST snapshots awaited by the market data.
+    fn request_snapshots(&mut self) {
+        for symbol in self.market_data.take_snapshot_requests() {
+            let client = self.client.clone();
+            let rest_tx = self.rest_tx.clone();
+            tokio::spawn(async move {
+                match client.get_level2_snapshot(&symbol).await {
+                    Ok(snapshot) => {
+                        let _ = rest_tx.send((symbol, snapshot));
+                    }
+                    Err(error) => {
+                        error!(?error, %symbol, "Couldn't get the market depth via REST.");
+                    }
+                }
+            });
+        }
+    }
+
+    fn handle(&mut self, msg: PublicMsg) {
+        match self.market_data.handle(&msg, &self.ev_tx) {
+            Ok(()) => {}
+            Err(FeedError::SeqGap {
+                symbol,
+                expected,
+                received,
+            }) => {
+                // Applying the update over the missing updates would corrupt the market depth,
+                // so it's recovered from a fresh snapshot instead.
+                warn!(
+                    %symbol,
+                    expected,
+                    received,
+                    "A gap in the level 2 updates is detected. Requests a new snapshot."
+                );
+                publish_seq_gap(&self.ev_tx, &symbol, expected, received);
+            }
+            Err(error) => {
+                error!(?error, "Couldn't handle the market data.");
+            }
+        }
+        self.request_snapshots();
+    }
+
+    pub async fn connect(&mut self) -> Result<(), KucoinError> {
+        // A new token is required for every connection.
+        let bullet = self.client.bullet_public().await?;
+        let server = bullet
+            .instance_servers
+            .first()
+            .ok_or(KucoinError::InvalidResponse)?;
+        let url = format!(
+            "{}?token={}&connectId={}",
+            server.endpoint,
+            bullet.token,
+            generate_rand_string(16)
+        );
+        let mut ws = WsSession::connect(&url).await?.keepalive(
+            Duration::from_millis(server.ping_interval),
+            json!({"id": "ping", "type": "ping"}).to_string(),
+        );
+
+        // Resubscribes to the symbols registered before the reconnection. Their market depth is
+        // cleared since the updates during the disconnection are missing; it's rebuilt from a
+        // fresh snapshot once the updates arrive.
+        let symbols = self.symbols.lock().unwrap().venue_symbols();
+        for symbol in symbols {
+            let local = self.symbols.lock().unwrap().to_local(&symbol);
+            publish_clear(&self.ev_tx, &local);
+            subscribe(&mut ws, &symbol).await?;
+        }
+
+        loop {
+            select! {
+                Some((symbol, data)) = self.rest_rx.recv() => {
+                    self.handle(PublicMsg::Snapshot(symbol, data));
+                }
+                msg = self.symbol_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        subscribe(&mut ws, &symbol).await?;
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} subscription requests were missed.");
+                    }
+                },
+                msg = self.resync_rx.recv() => match msg {
+                    Ok(symbol) => {
+                        self.market_data.resync(symbol);
+                        self.request_snapshots();
+                    }
+                    Err(RecvError::Closed) => {
+                        return Ok(());
+                    }
+                    Err(RecvError::Lagged(num)) => {
+                        error!("{num} resync requests were missed.");
+                    }
+                },
+                text = ws.recv() => {
+                    self.handle(PublicMsg::Text(text?));
+                }
+            }
+        }
+    }
+}
+
+async fn subscribe(ws: &mut WsSession, symbol: &str) -> Result<(), KucoinError> {
+    for topic in [
+        format!("/contractMarket/level2:{symbol}"),
+        format!("/contractMarket/execution:{symbol}"),
+    ] {
+        ws.send(
+            json!({
+                "id": generate_rand_string(16),
+                "type": "subscribe",
+                "topic": topic,
+                "privateChannel": false,
+                "response": true,
+            })
+            .to_string(),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use connector_sdk::{
+        conformance::{ConformanceSuite, VenueEncoder},
+        ordermanager::OrderUpdate,
+        MarketDataHandler,
+    };
+    use hftbacktest::prelude::Side;
+    use serde_json::json;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::{BookState, MarketData, PublicMsg};
+    use crate::utils::PxQty;
+
+    /// Since a level 2 update changes a single level, the encoder numbers each level of an update
+    /// in turn, keeping the gaps between the updates.
+    #[derive(Default)]
+    struct Encoder {
+        /// The last update's sequence number of the suite and the last level's sequence number.
+        last: Cell<(i64, i64)>,
+    }
+
+    impl VenueEncoder for Encoder {
+        type MarketData = PublicMsg;
+        type OrderMessage = ();
+
+        fn snapshot(&self, symbol: &str, seq: i64, bids: &[PxQty], asks: &[PxQty]) -> PublicMsg {
+            let sequence = seq * 1000;
+            self.last.set((seq, sequence));
+            let snapshot = serde_json::from_value(json!({
+                "sequence": sequence,
+                "bids": bids,
+                "asks": asks,
+                "ts": 1,
+            }))
+            .unwrap();
+            PublicMsg::Snapshot(symbol.to_string(), snapshot)
+        }
+
+        fn depth_update(
+            &self,
+            symbol: &str,
+            seq: i64,
+            bids: &[PxQty],
+            asks: &[PxQty],
+        ) -> Vec<PublicMsg> {
+            let (last_seq, last_sequence) = self.last.get();
+            let mut sequence = last_sequence + (seq - last_seq) - 1;
+            let levels = bids
+                .iter()
+                .map(|level| ("buy", level))
+                .chain(asks.iter().map(|level| ("sell", level)));
+            let msgs = levels
+                .map(|(side, &(px, qty))| {
+                    sequence += 1;
+                    PublicMsg::Text(
+                        json!({
+                            "type": "message",
+                            "topic": format!("/contractMarket/level2:{symbol}"),
+                            "subject": "level2",
+                            "data": {
+                                "sequence": sequence,
+                                "change": format!("{px},{side},{qty}"),
+                                "timestamp": 1,
+                            },
+                        })
+                        .to_string(),
+                    )
+                })
+                .collect();
+            self.last.set((seq, sequence));
+            msgs
+        }
+
+        fn trade(&self, symbol: &str, side: Side, px: f64, qty: f64) -> PublicMsg {
+            let side = if side == Side::Sell { "sell" } else { "buy" };
+            PublicMsg::Text(
+                json!({
+                    "type": "message",
+                    "topic": format!("/contractMarket/execution:{symbol}"),
+                    "subject": "match",
+                    "data": {
+                        "symbol": symbol,
+                        "side": side,
+                        "size": qty,
+                        "price": px.to_string(),
+                        "ts": 1,
+                    },
+                })
+                .to_string(),
+            )
+        }
+
+        fn order_update(&self, _symbol: &str, _client_order_id: &str, _update: &OrderUpdate) {}
+    }
+
+    #[test]
+    fn conformance() {
+        let suite = ConformanceSuite::new(Encoder::default(), "XBTUSDTM");
+        suite
+            .run_market_data(|| MarketData::new(Default::default()))
+            .unwrap();
+    }
+
+    #[test]
+    fn buffer_updates_until_snapshot() {
+        let mut market_data = MarketData::new(Default::default());
+        let encoder = Encoder::default();
+        let (ev_tx, _ev_rx) = unbounded_channel();
+
+        let snapshot = encoder.snapshot("XBTUSDTM", 1, &[(100.0, 1.0)], &[]);
+        for msg in encoder.depth_update("XBTUSDTM", 2, &[(99.5, 1.0)], &[]) {
+            market_data.handle(&msg, &ev_tx).unwrap();
+        }
+        assert_eq!(market_data.take_snapshot_requests(), vec!["XBTUSDTM"]);
+        assert!(market_data.take_snapshot_requests().is_empty());
+
+        market_data.handle(&snapshot, &ev_tx).unwrap();
+        assert!(matches!(
+            market_data.books.get("XBTUSDTM"),
+            Some(BookState::Synced(1001))
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::Utc;
+use reqwest::Method;
+use serde::Deserialize;
+
+use crate::{
+    kucoin::{
+        msg::{Bullet, Level2Snapshot, PositionItem, RestResponse},
+        KucoinError,
+    },
+    utils::sign_hmac_sha256_base64,
+};
+
+#[derive(Clone)]
+pub struct KucoinClient {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    secret: String,
+    passphrase: String,
+}
+
+impl KucoinClient {
+    pub fn new(url: &str, api_key: &str, secret: &str, passphrase: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            api_key: api_key.to_string(),
+            secret: secret.to_string(),
+            // The API key version 2 requires the passphrase to be signed with the secret.
+            passphrase: sign_hmac_sha256_base64(secret, passphrase),
+        }
+    }
+
+    async fn request<T: for<'a> Deserialize<'a>>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<serde_json::Value>,
+        signed: bool,
+    ) -> Result<Option<T>, KucoinError> {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let mut builder = self
+            .client
+            .request(method.clone(), format!("{}{}", self.url, endpoint))
+            .header("Content-Type", "application/json");
+        if signed {
+            let timestamp = Utc::now().timestamp_millis().to_string();
+            let signature = sign_hmac_sha256_base64(
+                &self.secret,
+                &format!("{timestamp}{}{endpoint}{body}", method.as_str()),
+            );
+            builder = builder
+                .header("KC-API-KEY", &self.api_key)
+                .header("KC-API-SIGN", signature)
+                .header("KC-API-TIMESTAMP", timestamp)
+                .header("KC-API-PASSPHRASE", &self.passphrase)
+                .header("KC-API-KEY-VERSION", "2");
+        }
+        let resp: RestResponse<T> = builder.body(body).send().await?.json().await?;
+        if resp.code != "200000" {
+            return Err(KucoinError::from_code(
+                resp.code,
+                resp.msg.unwrap_or_default(),
+            ));
+        }
+        Ok(resp.data)
+    }
+
+    /// Returns the token and the endpoint to connect to the public WebSocket stream.
+    pub async fn bullet_public(&self) -> Result<Bullet, KucoinError> {
+        self.request(Method::POST, "/api/v1/bullet-public", None, false)
+            .await?
+            .ok_or(KucoinError::InvalidResponse)
+    }
+
+    /// Returns the token and the endpoint to connect to the private WebSocket stream.
+    pub async fn bullet_private(&self) -> Result<Bullet, KucoinError> {
+        self.request(Method::POST, "/api/v1/bullet-private", None, true)
+            .await?
+            .ok_or(KucoinError::InvalidResponse)
+    }
+
+    pub async fn get_level2_snapshot(&self, symbol: &str) -> Result<Level2Snapshot, KucoinError> {
+        self.request(
+            Method::GET,
+            &format!("/api/v1/level2/snapshot?symbol={symbol}"),
+            None,
+            false,
+        )
+        .await?
+        .ok_or(KucoinError::InvalidResponse)
+    }
+
+    pub async fn get_positions(&self) -> Result<Vec<PositionItem>, KucoinError> {
+        Ok(self
+            .request(Method::GET, "/api/v1/positions", None, true)
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn place_order(&self, params: serde_json::Value) -> Result<(), KucoinError> {
+        let _: Option<serde_json::Value> = self
+            .request(Method::POST, "/api/v1/orders", Some(params), true)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn cancel_order(&self, symbol: &str, client_oid: &str) -> Result<(), KucoinError> {
+        let _: Option<serde_json::Value> = self
+            .request(
+                Method::DELETE,
+                &format!("/api/v1/orders/client-order/{client_oid}?symbol={symbol}"),
+                None,
+                true,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels all open orders of the symbol.
+    pub async fn cancel_all_orders(&self, symbol: &str) -> Result<(), KucoinError> {
+        let _: Option<serde_json::Value> = self
+            .request(
+                Method::DELETE,
+                &format!("/api/v1/orders?symbol={symbol}"),
+                None,
+                true,
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,455 @@
+//! The connectors that relay the market data and the orders between the live bots and the
+//! exchanges. Besides running as the `connector` binary, a connector can be embedded in the bot
+//! process by [`run`] with [`Transport::InProcess`].
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use chrono::Utc;
+pub use connector::{Connector, ConnectorBuilder, GetOrders, PublishEvent};
+use hftbacktest::{
+    live::ipc::{
+        iceoryx::{ChannelError, IceoryxBuilder},
+        inprocess::InProcessServer,
+        socket::SocketServer,
+        PubSubReceiver,
+        PubSubSender,
+        TO_ALL,
+    },
+    prelude::*,
+};
+use iceoryx2::{
+    node::NodeBuilder,
+    prelude::{ipc, NodeEvent},
+};
+use tokio::{
+    runtime::Builder,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
+use tracing::error;
+
+use crate::{
+    fuse::FusedHashMapMarketDepth,
+    subscription::{Recipients, Subscriptions},
+};
+
+#[cfg(feature = "binancefutures")]
+pub mod binancefutures;
+#[cfg(feature = "bitget")]
+pub mod bitget;
+#[cfg(feature = "bybit")]
+pub mod bybit;
+#[cfg(feature = "deribit")]
+pub mod deribit;
+#[cfg(feature = "fix")]
+pub mod fix;
+#[cfg(feature = "kraken")]
+pub mod kraken;
+#[cfg(feature = "kucoin")]
+pub mod kucoin;
+pub mod paper;
+pub mod replay;
+
+mod connector;
+mod fuse;
+mod subscription;
+mod toolkit;
+mod utils;
+
+/// The interval at which the heartbeat is published to the bots.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Position {
+    qty: f64,
+    exch_ts: i64,
+}
+
+/// Handles the request from the bot.
+fn handle_request(
+    id: u64,
+    ev: LiveRequest,
+    tx: &UnboundedSender<PublishEvent>,
+    connector: &mut Box<dyn Connector>,
+    subscriptions: &Mutex<Subscriptions>,
+) {
+    match ev {
+        LiveRequest::Order {
+            symbol: asset,
+            order,
+        } => match order.req {
+            Status::New => {
+                // Routes the responses to the order only to this bot.
+                subscriptions
+                    .lock()
+                    .unwrap()
+                    .own(id, asset.clone(), order.order_id);
+                // Requests to the Connector submit the new order.
+                connector.submit(asset, order, tx.clone());
+            }
+            Status::Canceled => {
+                // Requests to the Connector cancel the order.
+                connector.cancel(asset, order, tx.clone());
+            }
+            status => {
+                error!(?status, "An invalid request was received from the bot.");
+            }
+        },
+        LiveRequest::RegisterInstrument {
+            symbol,
+            tick_size,
+            lot_size,
+            depth,
+        } => {
+            // Publishes the live events of the instrument to this bot from now on.
+            subscriptions.lock().unwrap().subscribe(id, symbol.clone());
+            // Makes prepare the publisher thread to also add the instrument.
+            tx.send(PublishEvent::RegisterInstrument {
+                id,
+                symbol: symbol.clone(),
+                tick_size,
+            })
+            .unwrap();
+            // Requests to the Connector subscribe to the necessary feeds for the instrument.
+            connector.register_with_depth(symbol.clone(), tick_size, lot_size, depth);
+            connector.report_external_orders(symbol, tx.clone());
+        }
+        LiveRequest::DeregisterInstrument { symbol } => {
+            // Keeps trading the instrument while other bots still trade it.
+            if subscriptions.lock().unwrap().unsubscribe(id, &symbol) {
+                connector.deregister(symbol);
+            }
+        }
+        LiveRequest::Resync { symbol } => {
+            // Requests to the Connector resend the market depth snapshot.
+            connector.resync(symbol, tx.clone());
+        }
+        LiveRequest::QueryInstrumentInfo { symbol } => {
+            connector.query_instrument_info(symbol, tx.clone());
+        }
+        LiveRequest::AdoptOrder {
+            symbol,
+            exch_order_id,
+            order,
+        } => {
+            subscriptions
+                .lock()
+                .unwrap()
+                .own(id, symbol.clone(), order.order_id);
+            connector.adopt_order(symbol, exch_order_id, order, tx.clone());
+        }
+        LiveRequest::CancelExternal {
+            symbol,
+            exch_order_id,
+        } => {
+            connector.cancel_external(symbol, exch_order_id, tx.clone());
+        }
+    }
+}
+
+/// Handles the requests received from the bots until there are no more.
+fn receive_requests(
+    bot_rx: &dyn PubSubReceiver<LiveRequest>,
+    tx: &UnboundedSender<PublishEvent>,
+    connector: &mut Box<dyn Connector>,
+    subscriptions: &Mutex<Subscriptions>,
+) -> Result<(), ChannelError> {
+    loop {
+        let (id, ev) = match bot_rx.receive() {
+            Ok(Some(received)) => received,
+            Ok(None) => return Ok(()),
+            // A malformed request is skipped rather than stopping the connector.
+            Err(ChannelError::Decode(error)) => {
+                error!(?error, "A malformed request from the bots is skipped.");
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        handle_request(id, ev, tx, connector, subscriptions);
+    }
+}
+
+fn run_receive_task(
+    transport: &Transport,
+    tx: UnboundedSender<PublishEvent>,
+    connector: &mut Box<dyn Connector>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+) -> Result<(), ChannelError> {
+    let cycle_time = Duration::from_nanos(1000);
+    let bot_rx: Box<dyn PubSubReceiver<LiveRequest>> = match transport {
+        Transport::Iceoryx(name) => Box::new(IceoryxBuilder::new(name).bot(false).receiver()?),
+        Transport::Socket(server) => Box::new(server.receiver::<LiveRequest>()),
+        Transport::InProcess(server) => {
+            // Bypasses iceoryx entirely, so the connector runs until the process exits.
+            let bot_rx = server.receiver();
+            loop {
+                receive_requests(&bot_rx, &tx, connector, &subscriptions)?;
+                thread::sleep(cycle_time);
+            }
+        }
+    };
+    let node = NodeBuilder::new()
+        .create::<ipc::Service>()
+        .map_err(|error| ChannelError::BuildError(error.to_string()))?;
+    while let NodeEvent::Tick = node.wait(cycle_time) {
+        receive_requests(bot_rx.as_ref(), &tx, connector, &subscriptions)?;
+    }
+    Ok(())
+}
+
+async fn run_publish_task(
+    name: &str,
+    sender: Option<Box<dyn PubSubSender<LiveEvent> + Send>>,
+    order_manager: Arc<Mutex<dyn GetOrders + Send>>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    mut rx: UnboundedReceiver<PublishEvent>,
+) -> Result<(), ChannelError> {
+    let mut depth = HashMap::new();
+    let mut position: HashMap<(String, PositionSide), Position> = HashMap::new();
+    let bot_tx: Box<dyn PubSubSender<LiveEvent>> = match sender {
+        Some(sender) => sender,
+        None => Box::new(IceoryxBuilder::new(name).bot(false).sender()?),
+    };
+
+    while let Some(msg) = rx.recv().await {
+        match msg {
+            PublishEvent::RegisterInstrument {
+                id,
+                symbol,
+                tick_size,
+            } => {
+                // Sends the current state (orders, position, and market depth) to the bot that
+                // requested to add this instrument in batch mode.
+                bot_tx.send(id, &LiveEvent::BatchStart)?;
+
+                for order in order_manager.lock().unwrap().orders(Some(symbol.clone())) {
+                    bot_tx.send(
+                        id,
+                        &LiveEvent::Order {
+                            symbol: symbol.clone(),
+                            order,
+                        },
+                    )?;
+                }
+
+                for side in [PositionSide::Both, PositionSide::Long, PositionSide::Short] {
+                    if let Some(position) = position.get(&(symbol.clone(), side)) {
+                        bot_tx.send(
+                            id,
+                            &LiveEvent::Position {
+                                symbol: symbol.clone(),
+                                side,
+                                qty: position.qty,
+                                exch_ts: position.exch_ts,
+                            },
+                        )?;
+                    }
+                }
+
+                match depth.entry(symbol) {
+                    Entry::Occupied(mut entry) => {
+                        let depth_: &mut FusedHashMapMarketDepth = entry.get_mut();
+                        let snapshot = depth_.snapshot();
+                        for event in snapshot {
+                            bot_tx.send(
+                                id,
+                                &LiveEvent::Feed {
+                                    symbol: entry.key().clone(),
+                                    event,
+                                },
+                            )?;
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(FusedHashMapMarketDepth::new(tick_size));
+                    }
+                }
+
+                bot_tx.send(id, &LiveEvent::BatchEnd)?;
+            }
+            PublishEvent::LiveEvent(ev) => {
+                // The live event will only be published if the result is true.
+                if handle_ev(&ev, &mut depth, &mut position) {
+                    // Publishes only to the bots that registered the instrument, or that own the
+                    // order.
+                    match subscriptions.lock().unwrap().route(&ev) {
+                        Recipients::All => bot_tx.send(TO_ALL, &ev)?,
+                        Recipients::Bot(id) => bot_tx.send(id, &ev)?,
+                        Recipients::Bots(ids) => {
+                            for &id in ids {
+                                bot_tx.send(id, &ev)?;
+                            }
+                        }
+                        Recipients::Nobody => {}
+                    }
+                }
+            }
+            PublishEvent::BatchStart(id) => {
+                bot_tx.send(id, &LiveEvent::BatchStart)?;
+            }
+            PublishEvent::BatchEnd(id) => {
+                bot_tx.send(id, &LiveEvent::BatchEnd)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Maintains the market depth for all added instruments, allowing another bot to request the same
+/// instrument and publishing the market depth snapshot, and fuses the market depth from different
+/// streams, such as L1 or L2 with varying depths and update frequencies, to provide the most
+/// granular and frequent updates.
+///
+/// Returns true when the received live event needs to be published; otherwise, it does not.
+/// For example, publication is unnecessary if the received market depth data is outdated by more
+/// recent data from a different stream due to fusion.
+fn handle_ev(
+    ev: &LiveEvent,
+    depth: &mut HashMap<String, FusedHashMapMarketDepth>,
+    position: &mut HashMap<(String, PositionSide), Position>,
+) -> bool {
+    match ev {
+        LiveEvent::Feed { symbol, event } => {
+            if event.is(BUY_EVENT | DEPTH_EVENT) {
+                let depth_ = {
+                    match depth.get_mut(symbol) {
+                        Some(d) => d,
+                        None => return false,
+                    }
+                };
+                return depth_.update_bid_depth(event.px, event.qty, event.exch_ts);
+            } else if event.is(SELL_EVENT | DEPTH_EVENT) {
+                let depth_ = {
+                    match depth.get_mut(symbol) {
+                        Some(d) => d,
+                        None => return false,
+                    }
+                };
+                return depth_.update_ask_depth(event.px, event.qty, event.exch_ts);
+            } else if event.is(BUY_EVENT | DEPTH_BBO_EVENT) {
+                let depth_ = {
+                    match depth.get_mut(symbol) {
+                        Some(d) => d,
+                        None => return false,
+                    }
+                };
+                return depth_.update_best_bid(event.px, event.qty, event.exch_ts);
+            } else if event.is(SELL_EVENT | DEPTH_BBO_EVENT) {
+                let depth_ = {
+                    match depth.get_mut(symbol) {
+                        Some(d) => d,
+                        None => return false,
+                    }
+                };
+                return depth_.update_best_ask(event.px, event.qty, event.exch_ts);
+            } else if event.is(DEPTH_CLEAR_EVENT) {
+                let depth_ = {
+                    match depth.get_mut(symbol) {
+                        Some(d) => d,
+                        None => return false,
+                    }
+                };
+                depth_.clear_depth(Side::None, 0.0);
+            }
+        }
+        LiveEvent::Position {
+            symbol,
+            side,
+            qty,
+            exch_ts,
+        } => {
+            let key = (symbol.clone(), *side);
+            if let Some(position) = position.get_mut(&key) {
+                return if *exch_ts >= position.exch_ts {
+                    position.qty = *qty;
+                    true
+                } else {
+                    false
+                };
+            } else {
+                position.insert(
+                    key,
+                    Position {
+                        qty: *qty,
+                        exch_ts: *exch_ts,
+                    },
+                );
+                return true;
+            }
+        }
+        _ => {}
+    }
+    true
+}
+
+/// The transport through which the connector exchanges messages with the bots.
+pub enum Transport {
+    /// The shared memory IPC, through which the bots connect by the connector name.
+    Iceoryx(String),
+    /// The TCP or Unix domain socket, through which the bots connect by the endpoint.
+    Socket(SocketServer),
+    /// The in-process transport, through which the bots in the same process connect by the name
+    /// to which the server is bound, using
+    /// [`InProcessUnifiedChannel`](hftbacktest::live::ipc::inprocess::InProcessUnifiedChannel).
+    InProcess(InProcessServer),
+}
+
+/// Runs the connector, relaying the requests from the bots to the connector and publishing the
+/// live events from the connector to the bots through the transport, along with the heartbeat.
+///
+/// This blocks the current thread, and must be called within a Tokio runtime, on which the
+/// connector spawns its tasks. With the shared memory IPC or the socket, it returns when the
+/// process is requested to terminate. With the in-process transport, it runs until the process
+/// exits.
+pub fn run(mut connector: Box<dyn Connector>, transport: Transport) -> Result<(), ChannelError> {
+    let (pub_tx, pub_rx) = unbounded_channel();
+    connector.run(pub_tx.clone());
+
+    // Publishes the heartbeat so that the bots can detect a dead or unresponsive connector.
+    let heartbeat_tx = pub_tx.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let timestamp = Utc::now().timestamp_nanos_opt().unwrap();
+            if heartbeat_tx
+                .send(PublishEvent::LiveEvent(LiveEvent::Heartbeat { timestamp }))
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let (name, sender): (String, Option<Box<dyn PubSubSender<LiveEvent> + Send>>) = match &transport
+    {
+        Transport::Iceoryx(name) => (name.clone(), None),
+        Transport::Socket(server) => (String::new(), Some(Box::new(server.sender::<LiveEvent>()))),
+        Transport::InProcess(server) => (String::new(), Some(Box::new(server.sender()))),
+    };
+    let order_manager = connector.order_manager();
+    let subscriptions = Arc::new(Mutex::new(Subscriptions::default()));
+    let subscriptions_ = subscriptions.clone();
+    let handle = thread::spawn(move || {
+        let rt = Builder::new_current_thread().enable_all().build().unwrap();
+
+        rt.block_on(async move {
+            run_publish_task(&name, sender, order_manager, subscriptions_, pub_rx)
+                .await
+                .map_err(|error: ChannelError| {
+                    error!(
+                        ?error,
+                        "An error occurred while sending a live event to the bots."
+                    );
+                })
+                .unwrap();
+        });
+    });
+
+    run_receive_task(&transport, pub_tx, &mut connector, subscriptions)?;
+    let _ = handle.join();
+    Ok(())
+}
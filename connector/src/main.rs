@@ -1,274 +1,23 @@
-use std::{
-    collections::{hash_map::Entry, HashMap},
-    fs::read_to_string,
-    panic,
-    process::exit,
-    sync::{Arc, Mutex},
-    thread,
-    time::Duration,
-};
+use std::{fs::read_to_string, panic, process::exit};
 
 use clap::Parser;
-use hftbacktest::{
-    live::ipc::{
-        iceoryx::{ChannelError, IceoryxBuilder},
-        TO_ALL,
-    },
-    prelude::*,
-};
-use iceoryx2::{
-    node::NodeBuilder,
-    prelude::{ipc, NodeEvent},
-};
-use tokio::{
-    runtime::Builder,
-    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
-};
-use tracing::error;
-
-use crate::{
+use connector::{
     binancefutures::BinanceFutures,
+    bitget::Bitget,
     bybit::Bybit,
-    connector::{Connector, ConnectorBuilder, GetOrders, PublishEvent},
-    fuse::FusedHashMapMarketDepth,
+    deribit::Deribit,
+    fix::Fix,
+    kraken::Kraken,
+    kucoin::Kucoin,
+    paper::PaperConnector,
+    replay::Replay,
+    run,
+    Connector,
+    ConnectorBuilder,
+    Transport,
 };
-
-#[cfg(feature = "binancefutures")]
-pub mod binancefutures;
-#[cfg(feature = "bybit")]
-pub mod bybit;
-
-mod connector;
-mod fuse;
-mod utils;
-
-struct Position {
-    qty: f64,
-    exch_ts: i64,
-}
-
-fn run_receive_task(
-    name: &str,
-    tx: UnboundedSender<PublishEvent>,
-    connector: &mut Box<dyn Connector>,
-) -> Result<(), ChannelError> {
-    let node = NodeBuilder::new()
-        .create::<ipc::Service>()
-        .map_err(|error| ChannelError::BuildError(error.to_string()))?;
-    let bot_rx = IceoryxBuilder::new(name).bot(false).receiver()?;
-    loop {
-        let cycle_time = Duration::from_nanos(1000);
-        match node.wait(cycle_time) {
-            NodeEvent::Tick => {
-                while let Some((id, ev)) = bot_rx.receive()? {
-                    match ev {
-                        LiveRequest::Order {
-                            symbol: asset,
-                            order,
-                        } => match order.req {
-                            Status::New => {
-                                // Requests to the Connector submit the new order.
-                                connector.submit(asset, order, tx.clone());
-                            }
-                            Status::Canceled => {
-                                // Requests to the Connector cancel the order.
-                                connector.cancel(asset, order, tx.clone());
-                            }
-                            status => {
-                                error!(?status, "An invalid request was received from the bot.");
-                            }
-                        },
-                        LiveRequest::RegisterInstrument {
-                            symbol,
-                            tick_size,
-                            lot_size: _,
-                        } => {
-                            // Makes prepare the publisher thread to also add the instrument.
-                            tx.send(PublishEvent::RegisterInstrument {
-                                id,
-                                symbol: symbol.clone(),
-                                tick_size,
-                            })
-                            .unwrap();
-                            // Requests to the Connector subscribe to the necessary feeds for the
-                            // instrument.
-                            connector.register(symbol);
-                        }
-                    }
-                }
-            }
-            NodeEvent::TerminationRequest | NodeEvent::InterruptSignal => {
-                break;
-            }
-        }
-    }
-    Ok(())
-}
-
-async fn run_publish_task(
-    name: &str,
-    order_manager: Arc<Mutex<dyn GetOrders>>,
-    mut rx: UnboundedReceiver<PublishEvent>,
-) -> Result<(), ChannelError> {
-    let mut depth = HashMap::new();
-    let mut position: HashMap<String, Position> = HashMap::new();
-    let bot_tx = IceoryxBuilder::new(name).bot(false).sender()?;
-
-    while let Some(msg) = rx.recv().await {
-        match msg {
-            PublishEvent::RegisterInstrument {
-                id,
-                symbol,
-                tick_size,
-            } => {
-                // Sends the current state (orders, position, and market depth) to the bot that
-                // requested to add this instrument in batch mode.
-                bot_tx.send(id, &LiveEvent::BatchStart)?;
-
-                for order in order_manager.lock().unwrap().orders(Some(symbol.clone())) {
-                    bot_tx.send(
-                        id,
-                        &LiveEvent::Order {
-                            symbol: symbol.clone(),
-                            order,
-                        },
-                    )?;
-                }
-
-                if let Some(position) = position.get(&symbol) {
-                    bot_tx.send(
-                        id,
-                        &LiveEvent::Position {
-                            symbol: symbol.clone(),
-                            qty: position.qty,
-                            exch_ts: position.exch_ts,
-                        },
-                    )?;
-                }
-
-                match depth.entry(symbol) {
-                    Entry::Occupied(mut entry) => {
-                        let depth_: &mut FusedHashMapMarketDepth = entry.get_mut();
-                        let snapshot = depth_.snapshot();
-                        for event in snapshot {
-                            bot_tx.send(
-                                id,
-                                &LiveEvent::Feed {
-                                    symbol: entry.key().clone(),
-                                    event,
-                                },
-                            )?;
-                        }
-                    }
-                    Entry::Vacant(entry) => {
-                        entry.insert(FusedHashMapMarketDepth::new(tick_size));
-                    }
-                }
-
-                bot_tx.send(id, &LiveEvent::BatchEnd)?;
-            }
-            PublishEvent::LiveEvent(ev) => {
-                // The live event will only be published if the result is true.
-                if handle_ev(&ev, &mut depth, &mut position) {
-                    bot_tx.send(TO_ALL, &ev)?;
-                }
-            }
-            PublishEvent::BatchStart(id) => {
-                bot_tx.send(id, &LiveEvent::BatchStart)?;
-            }
-            PublishEvent::BatchEnd(id) => {
-                bot_tx.send(id, &LiveEvent::BatchEnd)?;
-            }
-        }
-    }
-    Ok(())
-}
-
-/// Maintains the market depth for all added instruments, allowing another bot to request the same
-/// instrument and publishing the market depth snapshot, and fuses the market depth from different
-/// streams, such as L1 or L2 with varying depths and update frequencies, to provide the most
-/// granular and frequent updates.
-///
-/// Returns true when the received live event needs to be published; otherwise, it does not.
-/// For example, publication is unnecessary if the received market depth data is outdated by more
-/// recent data from a different stream due to fusion.
-fn handle_ev(
-    ev: &LiveEvent,
-    depth: &mut HashMap<String, FusedHashMapMarketDepth>,
-    position: &mut HashMap<String, Position>,
-) -> bool {
-    match ev {
-        LiveEvent::Feed { symbol, event } => {
-            if event.is(BUY_EVENT | DEPTH_EVENT) {
-                let depth_ = {
-                    match depth.get_mut(symbol) {
-                        Some(d) => d,
-                        None => return false,
-                    }
-                };
-                return depth_.update_bid_depth(event.px, event.qty, event.exch_ts);
-            } else if event.is(SELL_EVENT | DEPTH_EVENT) {
-                let depth_ = {
-                    match depth.get_mut(symbol) {
-                        Some(d) => d,
-                        None => return false,
-                    }
-                };
-                return depth_.update_ask_depth(event.px, event.qty, event.exch_ts);
-            } else if event.is(BUY_EVENT | DEPTH_BBO_EVENT) {
-                let depth_ = {
-                    match depth.get_mut(symbol) {
-                        Some(d) => d,
-                        None => return false,
-                    }
-                };
-                return depth_.update_best_bid(event.px, event.qty, event.exch_ts);
-            } else if event.is(SELL_EVENT | DEPTH_BBO_EVENT) {
-                let depth_ = {
-                    match depth.get_mut(symbol) {
-                        Some(d) => d,
-                        None => return false,
-                    }
-                };
-                return depth_.update_best_ask(event.px, event.qty, event.exch_ts);
-            } else if event.is(DEPTH_CLEAR_EVENT) {
-                let depth_ = {
-                    match depth.get_mut(symbol) {
-                        Some(d) => d,
-                        None => return false,
-                    }
-                };
-                depth_.clear_depth(Side::None, 0.0);
-            }
-        }
-        LiveEvent::Position {
-            symbol,
-            qty,
-            exch_ts,
-        } => {
-            if position.contains_key(symbol) {
-                let position = position.get_mut(symbol).unwrap();
-                return if *exch_ts >= position.exch_ts {
-                    position.qty = *qty;
-                    true
-                } else {
-                    false
-                };
-            } else {
-                position.insert(
-                    symbol.clone(),
-                    Position {
-                        qty: *qty,
-                        exch_ts: *exch_ts,
-                    },
-                );
-                return true;
-            }
-        }
-        _ => {}
-    }
-    true
-}
+use hftbacktest::{backtest::models::RiskAdverseQueueModel, live::ipc::socket::SocketServer};
+use tracing::error;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -278,11 +27,31 @@ struct Args {
 
     /// Connector
     /// * binancefutures: Binance USD-m Futures
+    /// * bitget: Bitget Futures
     /// * bybit: Bybit Linear Futures
+    /// * deribit: Deribit Options and Futures
+    /// * fix: Generic FIX 4.4
+    /// * kraken: Kraken Spot
+    /// * kucoin: KuCoin Futures
+    /// * replay: Replays the recorded market data
     connector: String,
 
     /// Connector's configuration file path.
     config: String,
+
+    /// Runs in paper-trading mode, which consumes the live market data from the connector but
+    /// matches the orders locally against the live market depth instead of sending them to the
+    /// exchange.
+    #[arg(long)]
+    paper: bool,
+
+    /// Listens for the bots on a TCP or Unix domain socket endpoint, such as
+    /// `tcp://127.0.0.1:9000` or `unix:///tmp/connector.sock`, instead of the shared memory IPC.
+    /// The bots then use the endpoint as the connector name. Listening on an address other than
+    /// loopback requires the shared secret in `HFTBACKTEST_SOCKET_SECRET`, set for the bots as
+    /// well, and since the transport isn't encrypted, a tunnel across untrusted networks.
+    #[arg(long)]
+    listen: Option<String>,
 }
 
 #[tokio::main]
@@ -298,8 +67,6 @@ async fn main() {
 
     tracing_subscriber::fmt::init();
 
-    let (pub_tx, pub_rx) = unbounded_channel();
-
     let config = read_to_string(&args.config)
         .map_err(|error| {
             error!(
@@ -312,21 +79,67 @@ async fn main() {
 
     let mut connector: Box<dyn Connector> = match args.connector.as_str() {
         "binancefutures" => {
-            let mut connector = BinanceFutures::build_from(&config)
+            let connector = BinanceFutures::build_from(&config)
                 .map_err(|error| {
                     error!(?error, "Couldn't build the BinanceFutures connector.");
                 })
                 .unwrap();
-            connector.run(pub_tx.clone());
+            Box::new(connector)
+        }
+        "bitget" => {
+            let connector = Bitget::build_from(&config)
+                .map_err(|error| {
+                    error!(?error, "Couldn't build the Bitget connector.");
+                })
+                .unwrap();
             Box::new(connector)
         }
         "bybit" => {
-            let mut connector = Bybit::build_from(&config)
+            let connector = Bybit::build_from(&config)
                 .map_err(|error| {
                     error!(?error, "Couldn't build the Bybit connector.");
                 })
                 .unwrap();
-            connector.run(pub_tx.clone());
+            Box::new(connector)
+        }
+        "deribit" => {
+            let connector = Deribit::build_from(&config)
+                .map_err(|error| {
+                    error!(?error, "Couldn't build the Deribit connector.");
+                })
+                .unwrap();
+            Box::new(connector)
+        }
+        "fix" => {
+            let connector = Fix::build_from(&config)
+                .map_err(|error| {
+                    error!(?error, "Couldn't build the FIX connector.");
+                })
+                .unwrap();
+            Box::new(connector)
+        }
+        "kraken" => {
+            let connector = Kraken::build_from(&config)
+                .map_err(|error| {
+                    error!(?error, "Couldn't build the Kraken connector.");
+                })
+                .unwrap();
+            Box::new(connector)
+        }
+        "kucoin" => {
+            let connector = Kucoin::build_from(&config)
+                .map_err(|error| {
+                    error!(?error, "Couldn't build the KuCoin connector.");
+                })
+                .unwrap();
+            Box::new(connector)
+        }
+        "replay" => {
+            let connector = Replay::build_from(&config)
+                .map_err(|error| {
+                    error!(?error, "Couldn't build the Replay connector.");
+                })
+                .unwrap();
             Box::new(connector)
         }
         connector => {
@@ -334,27 +147,22 @@ async fn main() {
             exit(1);
         }
     };
-
-    let name = args.name.clone();
-    let order_manager = connector.order_manager();
-    let handle = thread::spawn(move || {
-        let rt = Builder::new_current_thread().enable_all().build().unwrap();
-
-        rt.block_on(async move {
-            run_publish_task(&name, order_manager, pub_rx)
-                .await
-                .map_err(|error: ChannelError| {
-                    error!(
-                        ?error,
-                        "An error occurred while sending a live event to the bots."
-                    );
+    if args.paper {
+        connector = Box::new(PaperConnector::new(connector, || {
+            Box::new(RiskAdverseQueueModel::new())
+        }));
+    }
+    let transport = match &args.listen {
+        Some(endpoint) => Transport::Socket(
+            SocketServer::bind(endpoint)
+                .map_err(|error| {
+                    error!(?error, %endpoint, "Couldn't listen on the endpoint.");
                 })
-                .unwrap();
-        });
-    });
-
-    let name = args.name;
-    run_receive_task(&name, pub_tx, &mut connector)
+                .unwrap(),
+        ),
+        None => Transport::Iceoryx(args.name.clone()),
+    };
+    run(connector, transport)
         .map_err(|error| {
             error!(
                 ?error,
@@ -362,5 +170,4 @@ async fn main() {
             );
         })
         .unwrap();
-    let _ = handle.join();
 }
@@ -0,0 +1,394 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Utc;
+use hftbacktest::{
+    backtest::{
+        assettype::LinearAsset,
+        data::Reader,
+        models::{CommonFees, ConstantLatency, QueueModel, TradingValueFeeModel},
+        order::OrderBus,
+        proc::{NoPartialFillExchange, PostOnlyBehavior},
+        state::State,
+        BacktestError,
+    },
+    prelude::*,
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tracing::error;
+
+use crate::connector::{Connector, GetOrders, PublishEvent};
+
+type BoxedQueueModel = Box<dyn QueueModel<HashMapMarketDepth> + Send>;
+
+type PaperExchange = NoPartialFillExchange<
+    LinearAsset,
+    ConstantLatency,
+    BoxedQueueModel,
+    HashMapMarketDepth,
+    TradingValueFeeModel<CommonFees>,
+>;
+
+/// Publishes the fill of the order ahead of the order update, so that the fill is available once
+/// the order update is received. The paper orders are filled entirely at once, so the order ID
+/// identifies the fill, and no fee is charged.
+fn publish_fill(symbol: &str, order: &Order, tx: &UnboundedSender<PublishEvent>) {
+    let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::Fill {
+        symbol: symbol.to_string(),
+        fill: Fill::from_order(order, order.order_id.to_string(), 0.0),
+    }));
+}
+
+/// Publishes the order responses, followed by the position if any of them is filled.
+fn publish_responses(
+    symbol: &str,
+    responses: Vec<Order>,
+    position: f64,
+    exch_ts: i64,
+    tx: &UnboundedSender<PublishEvent>,
+) {
+    let mut filled = false;
+    for order in responses {
+        if order.status == Status::Filled {
+            publish_fill(symbol, &order, tx);
+            filled = true;
+        }
+        let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::Order {
+            symbol: symbol.to_string(),
+            order,
+        }));
+    }
+    if filled {
+        let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::Position {
+            symbol: symbol.to_string(),
+            side: PositionSide::Both,
+            qty: position,
+            exch_ts,
+        }));
+    }
+}
+
+/// Maintains the market depth of an instrument from the live market data and matches the paper
+/// orders against it by [`NoPartialFillExchange`], the same exchange model as the backtest, which
+/// is driven by the live market data instead of the feed data.
+struct PaperBook {
+    exchange: PaperExchange,
+    responses: OrderBus,
+}
+
+// SAFETY: The order bus is shared only between the book and its exchange, and the book is only
+// accessed under the mutex of the connector, so the shared state is never accessed concurrently.
+unsafe impl Send for PaperBook {}
+
+impl PaperBook {
+    fn new(tick_size: f64, lot_size: f64, queue_model: BoxedQueueModel) -> Self {
+        let responses = OrderBus::new();
+        let exchange = NoPartialFillExchange::new(
+            Reader::builder().build().unwrap(),
+            HashMapMarketDepth::new(tick_size, lot_size),
+            State::new(
+                LinearAsset::new(1.0),
+                TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)),
+            ),
+            ConstantLatency::new(0, 0),
+            queue_model,
+            responses.clone(),
+            OrderBus::new(),
+            PostOnlyBehavior::Reject,
+        );
+        Self {
+            exchange,
+            responses,
+        }
+    }
+
+    fn position(&self) -> f64 {
+        self.exchange.state_values().position
+    }
+
+    fn drain_responses(&mut self) -> Vec<Order> {
+        let mut responses = Vec::new();
+        while let Some((order, _)) = self.responses.pop_front() {
+            responses.push(order);
+        }
+        responses
+    }
+
+    /// Takes the responses of the exchange, or rejects the order if the exchange has failed to
+    /// process it.
+    fn take_responses(
+        &mut self,
+        result: Result<(), BacktestError>,
+        mut order: Order,
+        timestamp: i64,
+    ) -> Vec<Order> {
+        if let Err(error) = result {
+            error!(
+                ?error,
+                order_id = order.order_id,
+                "Couldn't process the paper order."
+            );
+            order.req = Status::Rejected;
+            order.exch_timestamp = timestamp;
+            return vec![order];
+        }
+        self.drain_responses()
+    }
+
+    fn submit(&mut self, mut order: Order, timestamp: i64) -> Vec<Order> {
+        let best_tick = match order.side {
+            Side::Buy => self.exchange.depth().best_ask_tick(),
+            _ => self.exchange.depth().best_bid_tick(),
+        };
+        // Only one-way mode is supported.
+        if order.position_side != PositionSide::Both {
+            order.req = Status::Rejected;
+            order.exch_timestamp = timestamp;
+            return vec![order];
+        }
+        // A market order cannot be filled without the opposite side of the market depth.
+        if order.order_type == OrdType::Market
+            && (best_tick == INVALID_MIN || best_tick == INVALID_MAX)
+        {
+            order.req = Status::None;
+            order.status = Status::Expired;
+            order.exch_timestamp = timestamp;
+            return vec![order];
+        }
+        order.req = Status::New;
+        let result = self.exchange.process_order(order.clone(), timestamp);
+        self.take_responses(result, order, timestamp)
+    }
+
+    fn cancel(&mut self, mut order: Order, timestamp: i64) -> Vec<Order> {
+        order.req = Status::Canceled;
+        let result = self.exchange.process_order(order.clone(), timestamp);
+        self.take_responses(result, order, timestamp)
+    }
+
+    /// Applies the feed event and returns the responses of the orders filled by it.
+    fn on_feed(&mut self, event: &Event) -> Vec<Order> {
+        // The live feed events are local events, which the exchange processes as its own.
+        let mut event = event.clone();
+        event.ev |= EXCH_EVENT;
+        if let Err(error) = self.exchange.process_event(&event) {
+            error!(?error, "Couldn't match the paper orders.");
+        }
+        self.drain_responses()
+    }
+}
+
+struct PaperOrders {
+    books: Arc<Mutex<HashMap<String, PaperBook>>>,
+}
+
+impl GetOrders for PaperOrders {
+    fn orders(&self, symbol: Option<String>) -> Vec<Order> {
+        let books = self.books.lock().unwrap();
+        books
+            .iter()
+            .filter(|(book_symbol, _)| symbol.as_ref().is_none_or(|s| s == *book_symbol))
+            .flat_map(|(_, book)| book.exchange.orders())
+            .collect()
+    }
+}
+
+/// A paper-trading connector that consumes the live market data from another connector but
+/// matches the orders locally against the live market depth, using a queue model of the backtest,
+/// instead of sending them to the exchange. This allows strategies to be validated in the
+/// production market conditions without any exchange risk.
+///
+/// The order responses and the position from the exchange account of the underlying connector are
+/// discarded, and those of the paper orders are published instead.
+pub struct PaperConnector {
+    inner: Box<dyn Connector>,
+    queue_model: Box<dyn Fn() -> BoxedQueueModel>,
+    books: Arc<Mutex<HashMap<String, PaperBook>>>,
+    order_manager: Arc<Mutex<PaperOrders>>,
+}
+
+impl PaperConnector {
+    /// Constructs a `PaperConnector`.
+    ///
+    /// * `inner` - The connector from which the live market data is consumed.
+    /// * `queue_model` - The function that creates the queue model for each instrument.
+    pub fn new<F>(inner: Box<dyn Connector>, queue_model: F) -> Self
+    where
+        F: Fn() -> BoxedQueueModel + 'static,
+    {
+        let books = Arc::new(Mutex::new(HashMap::new()));
+        Self {
+            inner,
+            queue_model: Box::new(queue_model),
+            books: books.clone(),
+            order_manager: Arc::new(Mutex::new(PaperOrders { books })),
+        }
+    }
+}
+
+impl Connector for PaperConnector {
+    fn register(&mut self, symbol: String, tick_size: f64, lot_size: f64) {
+        self.books
+            .lock()
+            .unwrap()
+            .entry(symbol.clone())
+            .or_insert_with(|| PaperBook::new(tick_size, lot_size, (self.queue_model)()));
+        self.inner.register(symbol, tick_size, lot_size);
+    }
+
+    fn deregister(&mut self, symbol: String) {
+        self.inner.deregister(symbol);
+    }
+
+    fn order_manager(&self) -> Arc<Mutex<dyn GetOrders + Send + 'static>> {
+        self.order_manager.clone()
+    }
+
+    fn run(&mut self, tx: UnboundedSender<PublishEvent>) {
+        let (inner_tx, mut inner_rx) = unbounded_channel();
+        self.inner.run(inner_tx);
+        let books = self.books.clone();
+        tokio::spawn(async move {
+            while let Some(ev) = inner_rx.recv().await {
+                match ev {
+                    PublishEvent::LiveEvent(LiveEvent::Feed { symbol, event }) => {
+                        let (responses, position) = match books.lock().unwrap().get_mut(&symbol) {
+                            Some(book) => (book.on_feed(&event), book.position()),
+                            None => (Vec::new(), 0.0),
+                        };
+                        let exch_ts = event.exch_ts;
+                        let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::Feed {
+                            symbol: symbol.clone(),
+                            event,
+                        }));
+                        publish_responses(&symbol, responses, position, exch_ts, &tx);
+                    }
+                    // Discards the state of the exchange account.
+                    PublishEvent::LiveEvent(LiveEvent::Order { .. })
+                    | PublishEvent::LiveEvent(LiveEvent::Position { .. })
+                    | PublishEvent::LiveEvent(LiveEvent::Liquidation { .. })
+                    | PublishEvent::LiveEvent(LiveEvent::Fill { .. }) => {}
+                    ev => {
+                        let _ = tx.send(ev);
+                    }
+                }
+            }
+        });
+    }
+
+    fn submit(&self, symbol: String, order: Order, tx: UnboundedSender<PublishEvent>) {
+        let timestamp = Utc::now().timestamp_nanos_opt().unwrap();
+        let (responses, position) = match self.books.lock().unwrap().get_mut(&symbol) {
+            Some(book) => (book.submit(order, timestamp), book.position()),
+            None => {
+                let mut order = order;
+                order.req = Status::Rejected;
+                (vec![order], 0.0)
+            }
+        };
+        publish_responses(&symbol, responses, position, timestamp, &tx);
+    }
+
+    fn cancel(&self, symbol: String, order: Order, tx: UnboundedSender<PublishEvent>) {
+        let timestamp = Utc::now().timestamp_nanos_opt().unwrap();
+        let (responses, position) = match self.books.lock().unwrap().get_mut(&symbol) {
+            Some(book) => (book.cancel(order, timestamp), book.position()),
+            None => {
+                let mut order = order;
+                order.req = Status::Rejected;
+                (vec![order], 0.0)
+            }
+        };
+        publish_responses(&symbol, responses, position, timestamp, &tx);
+    }
+
+    fn resync(&self, symbol: String, tx: UnboundedSender<PublishEvent>) {
+        self.inner.resync(symbol, tx);
+    }
+
+    fn query_instrument_info(&self, symbol: String, tx: UnboundedSender<PublishEvent>) {
+        self.inner.query_instrument_info(symbol, tx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hftbacktest::{backtest::models::RiskAdverseQueueModel, prelude::*};
+
+    use super::PaperBook;
+
+    fn feed(ev: u64, px: f64, qty: f64, exch_ts: i64) -> Event {
+        Event {
+            ev: LOCAL_EVENT | ev,
+            exch_ts,
+            local_ts: exch_ts + 5,
+            px,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        }
+    }
+
+    fn order(order_id: u64, price_tick: i64, side: Side, time_in_force: TimeInForce) -> Order {
+        let mut order = Order::new(
+            order_id,
+            price_tick,
+            0.1,
+            1.0,
+            side,
+            OrdType::Limit,
+            time_in_force,
+        );
+        order.req = Status::New;
+        order
+    }
+
+    #[test]
+    fn test_paper_book() {
+        let mut book = PaperBook::new(0.1, 1.0, Box::new(RiskAdverseQueueModel::new()));
+        book.on_feed(&feed(BUY_EVENT | DEPTH_EVENT, 100.0, 5.0, 100));
+        book.on_feed(&feed(SELL_EVENT | DEPTH_EVENT, 100.1, 5.0, 100));
+
+        // A post-only order that would cross is rejected, and a crossing order takes the best.
+        let resp = book.submit(order(1, 1001, Side::Buy, TimeInForce::GTX), 200);
+        assert_eq!(resp[0].status, Status::Expired);
+        let resp = book.submit(order(2, 1002, Side::Buy, TimeInForce::GTC), 200);
+        assert_eq!(resp[0].status, Status::Filled);
+        assert_eq!(resp[0].exec_price_tick, 1001);
+        assert!(!resp[0].maker);
+        assert_eq!(book.position(), 1.0);
+
+        // A resting order is filled once the queue ahead is consumed by trades.
+        let resp = book.submit(order(3, 1000, Side::Buy, TimeInForce::GTC), 300);
+        assert_eq!(resp[0].status, Status::New);
+        assert_eq!(book.exchange.orders().len(), 1);
+        assert!(book
+            .on_feed(&feed(SELL_EVENT | TRADE_EVENT, 100.0, 3.0, 400))
+            .is_empty());
+        let filled = book.on_feed(&feed(SELL_EVENT | TRADE_EVENT, 100.0, 3.0, 500));
+        assert_eq!(filled.len(), 1);
+        assert_eq!(filled[0].order_id, 3);
+        assert_eq!(filled[0].status, Status::Filled);
+        assert!(filled[0].maker);
+        assert_eq!(book.position(), 2.0);
+
+        // A resting order is filled when the opposite best crosses it.
+        book.submit(order(4, 1003, Side::Sell, TimeInForce::GTC), 600);
+        let filled = book.on_feed(&feed(BUY_EVENT | DEPTH_EVENT, 100.3, 1.0, 700));
+        assert_eq!(filled.len(), 1);
+        assert_eq!(book.position(), 1.0);
+
+        // A resting order is canceled, and an unknown or a duplicate order is rejected.
+        book.submit(order(5, 990, Side::Buy, TimeInForce::GTC), 800);
+        let resp = book.submit(order(5, 990, Side::Buy, TimeInForce::GTC), 800);
+        assert_eq!(resp[0].req, Status::Rejected);
+        let resp = book.cancel(order(5, 990, Side::Buy, TimeInForce::GTC), 900);
+        assert_eq!(resp[0].status, Status::Canceled);
+        assert!(book.exchange.orders().is_empty());
+        let resp = book.cancel(order(6, 1000, Side::Buy, TimeInForce::GTC), 900);
+        assert_eq!(resp[0].req, Status::Rejected);
+    }
+}
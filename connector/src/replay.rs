@@ -0,0 +1,347 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use hftbacktest::{
+    backtest::data::{read_npy_file, read_npz_file, Data},
+    types::{ErrorKind, Event, LiveError, LiveEvent, Order, Status, Value, LOCAL_EVENT},
+};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{error, info};
+
+use crate::connector::{Connector, ConnectorBuilder, GetOrders, PublishEvent};
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("Config: {0:?}")]
+    Config(#[from] toml::de::Error),
+    #[error("InvalidSpeed")]
+    InvalidSpeed,
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+fn default_start_delay_ms() -> u64 {
+    1000
+}
+
+fn default_rebase_timestamps() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub struct Config {
+    /// The replay speed relative to the recorded time, such as `1.0` for real-time and `10.0` for
+    /// ten times faster. `0` replays the events as fast as possible.
+    #[serde(default = "default_speed")]
+    speed: f64,
+    /// The delay in milliseconds from the first instrument registration to the start of the
+    /// replay, which allows the bot to register all of its instruments before the replay starts.
+    /// The events of the instruments not registered by then are skipped.
+    #[serde(default = "default_start_delay_ms")]
+    start_delay_ms: u64,
+    /// If enabled, the timestamps of the events are rebased to the current time at which they are
+    /// replayed, keeping the recorded feed latency. Otherwise, the recorded timestamps are sent
+    /// as-is.
+    #[serde(default = "default_rebase_timestamps")]
+    rebase_timestamps: bool,
+    /// The recorded `npz` or `npy` event files of each symbol, in chronological order.
+    data: HashMap<String, Vec<String>>,
+}
+
+/// Reads the recorded event files of a symbol in order.
+struct SymbolFeed {
+    symbol: String,
+    files: VecDeque<String>,
+    data: Option<Data<Event>>,
+    pos: usize,
+}
+
+impl SymbolFeed {
+    /// Returns the next local event, loading the next file if the current one is exhausted.
+    fn peek(&mut self) -> Option<&Event> {
+        loop {
+            if let Some(data) = &self.data {
+                while self.pos < data.len() && !data[self.pos].is(LOCAL_EVENT) {
+                    self.pos += 1;
+                }
+                if self.pos < data.len() {
+                    break;
+                }
+            }
+            let filepath = self.files.pop_front()?;
+            let result = if filepath.ends_with(".npy") {
+                read_npy_file::<Event>(&filepath)
+            } else {
+                read_npz_file::<Event>(&filepath, "data")
+            };
+            match result {
+                Ok(data) => {
+                    info!(%filepath, symbol = self.symbol, "Replaying the file.");
+                    self.data = Some(data);
+                    self.pos = 0;
+                }
+                Err(error) => {
+                    error!(?error, %filepath, "Couldn't read the file.");
+                    self.data = None;
+                }
+            }
+        }
+        self.data.as_ref().map(|data| &data[self.pos])
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+    }
+}
+
+/// Merges the recorded events of the symbols in the order of the local timestamp.
+struct ReplayFeed {
+    feeds: Vec<SymbolFeed>,
+}
+
+impl ReplayFeed {
+    fn new(data: &HashMap<String, Vec<String>>, symbols: &HashSet<String>) -> Self {
+        let feeds = data
+            .iter()
+            .filter(|(symbol, _)| symbols.contains(*symbol))
+            .map(|(symbol, files)| SymbolFeed {
+                symbol: symbol.clone(),
+                files: files.iter().cloned().collect(),
+                data: None,
+                pos: 0,
+            })
+            .collect();
+        Self { feeds }
+    }
+}
+
+impl Iterator for ReplayFeed {
+    type Item = (String, Event);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut next: Option<(usize, i64)> = None;
+        for (i, feed) in self.feeds.iter_mut().enumerate() {
+            if let Some(ev) = feed.peek() {
+                if next.is_none_or(|(_, local_ts)| ev.local_ts < local_ts) {
+                    next = Some((i, ev.local_ts));
+                }
+            }
+        }
+        let (i, _) = next?;
+        let feed = &mut self.feeds[i];
+        let ev = feed.peek().unwrap().clone();
+        feed.advance();
+        Some((feed.symbol.clone(), ev))
+    }
+}
+
+struct NoOrders;
+
+impl GetOrders for NoOrders {
+    fn orders(&self, _symbol: Option<String>) -> Vec<Order> {
+        Vec::new()
+    }
+}
+
+/// A connector that replays the recorded market data over the same IPC channel as the other
+/// connectors, at a configurable speed, so that the full live stack, including the IPC, `LiveBot`
+/// and the strategy, can be tested without an exchange.
+///
+/// It doesn't execute orders by itself; every order request is rejected. To also test the order
+/// handling, run it in paper-trading mode.
+pub struct Replay {
+    config: Arc<Config>,
+    symbols: Arc<Mutex<HashSet<String>>>,
+    started: bool,
+    tx: Option<UnboundedSender<PublishEvent>>,
+    order_manager: Arc<Mutex<NoOrders>>,
+}
+
+impl Replay {
+    fn start(&mut self) {
+        let Some(tx) = self.tx.clone() else {
+            return;
+        };
+        self.started = true;
+        let config = self.config.clone();
+        let symbols = self.symbols.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(config.start_delay_ms));
+            let symbols = symbols.lock().unwrap().clone();
+            let mut first_ts = None;
+            let start = Instant::now();
+            for (symbol, mut event) in ReplayFeed::new(&config.data, &symbols) {
+                let first_ts = *first_ts.get_or_insert(event.local_ts);
+                if config.speed > 0.0 {
+                    let elapsed = ((event.local_ts - first_ts) as f64 / config.speed) as u64;
+                    let target = start + Duration::from_nanos(elapsed);
+                    let now = Instant::now();
+                    if target > now {
+                        thread::sleep(target - now);
+                    }
+                }
+                if config.rebase_timestamps {
+                    let latency = event.local_ts - event.exch_ts;
+                    event.local_ts = Utc::now().timestamp_nanos_opt().unwrap();
+                    event.exch_ts = event.local_ts - latency;
+                }
+                if tx
+                    .send(PublishEvent::LiveEvent(LiveEvent::Feed { symbol, event }))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            info!("The replay is complete.");
+        });
+    }
+
+    fn reject(order: &mut Order, tx: &UnboundedSender<PublishEvent>) {
+        order.req = Status::None;
+        order.exch_timestamp = Utc::now().timestamp_nanos_opt().unwrap();
+        let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::Error(LiveError::with(
+            ErrorKind::OrderError,
+            Value::String("The replay connector doesn't execute orders.".to_string()),
+        ))));
+    }
+}
+
+impl ConnectorBuilder for Replay {
+    type Error = ReplayError;
+
+    fn build_from(config: &str) -> Result<Self, Self::Error> {
+        let config: Config = toml::from_str(config)?;
+        if !config.speed.is_finite() || config.speed < 0.0 {
+            return Err(ReplayError::InvalidSpeed);
+        }
+        Ok(Replay {
+            config: Arc::new(config),
+            symbols: Default::default(),
+            started: false,
+            tx: None,
+            order_manager: Arc::new(Mutex::new(NoOrders)),
+        })
+    }
+}
+
+impl Connector for Replay {
+    fn register(&mut self, symbol: String, _tick_size: f64, _lot_size: f64) {
+        if !self.config.data.contains_key(&symbol) {
+            error!(%symbol, "No recorded data is configured for the symbol.");
+        }
+        self.symbols.lock().unwrap().insert(symbol);
+        if !self.started {
+            self.start();
+        }
+    }
+
+    fn order_manager(&self) -> Arc<Mutex<dyn GetOrders + Send + 'static>> {
+        self.order_manager.clone()
+    }
+
+    fn run(&mut self, tx: UnboundedSender<PublishEvent>) {
+        self.tx = Some(tx);
+    }
+
+    fn submit(&self, symbol: String, mut order: Order, tx: UnboundedSender<PublishEvent>) {
+        Self::reject(&mut order, &tx);
+        order.status = Status::Expired;
+        let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::Order { symbol, order }));
+    }
+
+    fn cancel(&self, symbol: String, mut order: Order, tx: UnboundedSender<PublishEvent>) {
+        Self::reject(&mut order, &tx);
+        let _ = tx.send(PublishEvent::LiveEvent(LiveEvent::Order { symbol, order }));
+    }
+
+    fn resync(&self, _symbol: String, _tx: UnboundedSender<PublishEvent>) {
+        // The replayed market depth cannot be rebuilt from a snapshot.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        fs::{remove_file, File},
+    };
+
+    use hftbacktest::{
+        backtest::data::write_npy,
+        types::{Event, EXCH_EVENT, LOCAL_EVENT, TRADE_EVENT},
+    };
+
+    use super::ReplayFeed;
+
+    fn event(ev: u64, local_ts: i64) -> Event {
+        Event {
+            ev: ev | TRADE_EVENT,
+            exch_ts: local_ts - 5,
+            local_ts,
+            px: 100.0,
+            qty: 1.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_replay_feed() {
+        let dir = std::env::temp_dir();
+        let pid = std::process::id();
+        let files: Vec<_> = (0..3)
+            .map(|i| {
+                dir.join(format!("hftbacktest_replay_{pid}_{i}.npy"))
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        let contents = [
+            vec![
+                event(LOCAL_EVENT, 100),
+                event(EXCH_EVENT, 150),
+                event(LOCAL_EVENT, 300),
+            ],
+            vec![event(LOCAL_EVENT | EXCH_EVENT, 500)],
+            vec![event(LOCAL_EVENT, 200), event(LOCAL_EVENT, 400)],
+        ];
+        for (file, data) in files.iter().zip(contents.iter()) {
+            write_npy(&mut File::create(file).unwrap(), data).unwrap();
+        }
+
+        let data = HashMap::from([
+            ("a".to_string(), vec![files[0].clone(), files[1].clone()]),
+            ("b".to_string(), vec![files[2].clone()]),
+            ("c".to_string(), vec![files[2].clone()]),
+        ]);
+        let symbols = HashSet::from(["a".to_string(), "b".to_string()]);
+        let replayed: Vec<_> = ReplayFeed::new(&data, &symbols)
+            .map(|(symbol, ev)| (symbol, ev.local_ts))
+            .collect();
+        for file in &files {
+            remove_file(file).unwrap();
+        }
+
+        assert_eq!(
+            replayed,
+            [
+                ("a".to_string(), 100),
+                ("b".to_string(), 200),
+                ("a".to_string(), 300),
+                ("b".to_string(), 400),
+                ("a".to_string(), 500),
+            ]
+        );
+    }
+}
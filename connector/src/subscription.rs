@@ -0,0 +1,235 @@
+use std::collections::{HashMap, HashSet};
+
+use hftbacktest::types::{LiveEvent, OrderId};
+
+/// The bots to which a live event is published.
+#[derive(Debug, PartialEq)]
+pub enum Recipients<'a> {
+    /// All connected bots.
+    All,
+    /// The bot that owns the order.
+    Bot(u64),
+    /// The bots that registered the instrument.
+    Bots(&'a HashSet<u64>),
+    /// No bot, as no bot registered the instrument.
+    Nobody,
+}
+
+/// Tracks which bots registered which instruments and which bot submitted which order, so that
+/// the connector serving multiple bots publishes each bot only the live events of the instruments
+/// it registered and the responses to its own orders, instead of the full stream.
+#[derive(Default)]
+pub struct Subscriptions {
+    symbols: HashMap<String, HashSet<u64>>,
+    /// The number of the registered instruments of each bot.
+    bots: HashMap<u64, usize>,
+    orders: HashMap<(String, OrderId), u64>,
+}
+
+impl Subscriptions {
+    /// Registers the instrument for the bot.
+    pub fn subscribe(&mut self, bot: u64, symbol: String) {
+        if self.symbols.entry(symbol).or_default().insert(bot) {
+            *self.bots.entry(bot).or_default() += 1;
+        }
+    }
+
+    /// Deregisters the instrument for the bot. Returns `true` if no bot trades the instrument
+    /// anymore, in which case the connector can stop trading the instrument.
+    pub fn unsubscribe(&mut self, bot: u64, symbol: &str) -> bool {
+        let Some(subscribers) = self.symbols.get_mut(symbol) else {
+            return false;
+        };
+        if !subscribers.remove(&bot) {
+            return false;
+        }
+        if let Some(count) = self.bots.get_mut(&bot) {
+            *count -= 1;
+            if *count == 0 {
+                self.bots.remove(&bot);
+            }
+        }
+        self.orders
+            .retain(|(symbol_, _), owner| !(symbol_ == symbol && *owner == bot));
+        if subscribers.is_empty() {
+            self.symbols.remove(symbol);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Records that the bot submitted the order, so that the order responses are published only to
+    /// the bot.
+    pub fn own(&mut self, bot: u64, symbol: String, order_id: OrderId) {
+        self.orders.insert((symbol, order_id), bot);
+    }
+
+    /// Returns the bots to which the live event is published. The ownership of the order is
+    /// released once the order reaches a final status.
+    pub fn route(&mut self, ev: &LiveEvent) -> Recipients<'_> {
+        let symbol = match ev {
+            LiveEvent::Order { symbol, order } => {
+                let key = (symbol.clone(), order.order_id);
+                let owner = if order.status.is_final() {
+                    self.orders.remove(&key)
+                } else {
+                    self.orders.get(&key).copied()
+                };
+                if let Some(owner) = owner {
+                    return Recipients::Bot(owner);
+                }
+                symbol
+            }
+            LiveEvent::Fill { symbol, fill } => {
+                if let Some(&owner) = self.orders.get(&(symbol.clone(), fill.order_id)) {
+                    return Recipients::Bot(owner);
+                }
+                symbol
+            }
+            LiveEvent::Feed { symbol, .. }
+            | LiveEvent::Position { symbol, .. }
+            | LiveEvent::Liquidation { symbol, .. }
+            | LiveEvent::MarkPrice { symbol, .. }
+            | LiveEvent::IndexPrice { symbol, .. }
+            | LiveEvent::FundingRate { symbol, .. }
+            | LiveEvent::InstrumentInfo { symbol, .. }
+            | LiveEvent::ExternalOrder { symbol, .. } => symbol,
+            LiveEvent::BatchStart
+            | LiveEvent::BatchEnd
+            | LiveEvent::Error(_)
+            | LiveEvent::Heartbeat { .. }
+            | LiveEvent::Margin(_) => return Recipients::All,
+        };
+        match self.symbols.get(symbol) {
+            // Publishes once to all bots if every bot registered the instrument.
+            Some(subscribers) if subscribers.len() == self.bots.len() => Recipients::All,
+            Some(subscribers) => Recipients::Bots(subscribers),
+            None => Recipients::Nobody,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use hftbacktest::types::{Event, Fill, LiveEvent, OrdType, Order, Side, Status, TimeInForce};
+
+    use crate::subscription::{Recipients, Subscriptions};
+
+    fn feed(symbol: &str) -> LiveEvent {
+        LiveEvent::Feed {
+            symbol: symbol.to_string(),
+            event: Event {
+                ev: 0,
+                exch_ts: 0,
+                local_ts: 0,
+                px: 0.0,
+                qty: 0.0,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            },
+        }
+    }
+
+    fn order(symbol: &str, order_id: u64, status: Status) -> LiveEvent {
+        let mut order = Order::new(
+            order_id,
+            100,
+            0.1,
+            1.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        order.status = status;
+        LiveEvent::Order {
+            symbol: symbol.to_string(),
+            order,
+        }
+    }
+
+    #[test]
+    fn test_join_and_leave() {
+        let mut subs = Subscriptions::default();
+        subs.subscribe(1, "BTCUSDT".to_string());
+        assert_eq!(subs.route(&feed("BTCUSDT")), Recipients::All);
+        assert_eq!(subs.route(&feed("ETHUSDT")), Recipients::Nobody);
+
+        // Another bot joins with a different instrument while the first one is running.
+        subs.subscribe(2, "ETHUSDT".to_string());
+        assert_eq!(
+            subs.route(&feed("BTCUSDT")),
+            Recipients::Bots(&HashSet::from([1]))
+        );
+        assert_eq!(
+            subs.route(&feed("ETHUSDT")),
+            Recipients::Bots(&HashSet::from([2]))
+        );
+
+        subs.subscribe(2, "BTCUSDT".to_string());
+        assert_eq!(subs.route(&feed("BTCUSDT")), Recipients::All);
+
+        // The instrument is still traded by the second bot after the first one leaves.
+        assert!(!subs.unsubscribe(1, "BTCUSDT"));
+        assert_eq!(subs.route(&feed("BTCUSDT")), Recipients::All);
+        assert_eq!(subs.route(&feed("ETHUSDT")), Recipients::All);
+
+        assert!(!subs.unsubscribe(1, "BTCUSDT"));
+        assert!(subs.unsubscribe(2, "BTCUSDT"));
+        assert_eq!(subs.route(&feed("BTCUSDT")), Recipients::Nobody);
+        assert!(subs.unsubscribe(2, "ETHUSDT"));
+        assert_eq!(subs.route(&LiveEvent::BatchEnd), Recipients::All);
+    }
+
+    #[test]
+    fn test_order_routing() {
+        let mut subs = Subscriptions::default();
+        subs.subscribe(1, "BTCUSDT".to_string());
+        subs.subscribe(2, "BTCUSDT".to_string());
+        subs.own(1, "BTCUSDT".to_string(), 10);
+        subs.own(2, "BTCUSDT".to_string(), 20);
+
+        assert_eq!(
+            subs.route(&order("BTCUSDT", 10, Status::New)),
+            Recipients::Bot(1)
+        );
+        assert_eq!(
+            subs.route(&order("BTCUSDT", 20, Status::PartiallyFilled)),
+            Recipients::Bot(2)
+        );
+        let fill = |order_id| LiveEvent::Fill {
+            symbol: "BTCUSDT".to_string(),
+            fill: Fill {
+                order_id,
+                trade_id: "1".to_string(),
+                side: Side::Buy,
+                price: 10.0,
+                qty: 1.0,
+                fee: 0.0,
+                maker: true,
+                exch_ts: 0,
+            },
+        };
+        assert_eq!(subs.route(&fill(10)), Recipients::Bot(1));
+        assert_eq!(
+            subs.route(&order("BTCUSDT", 10, Status::Filled)),
+            Recipients::Bot(1)
+        );
+        // The ownership is released with the final status, and the unknown orders go to the
+        // bots that registered the instrument.
+        assert_eq!(
+            subs.route(&order("BTCUSDT", 10, Status::Filled)),
+            Recipients::All
+        );
+
+        // The orders of the bot that left are no longer routed to it.
+        subs.unsubscribe(2, "BTCUSDT");
+        assert_eq!(
+            subs.route(&order("BTCUSDT", 20, Status::Canceled)),
+            Recipients::All
+        );
+    }
+}
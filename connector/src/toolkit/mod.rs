@@ -0,0 +1,16 @@
+//! Building blocks shared by the connectors so that a new venue only needs to implement its
+//! message parsing and request signing.
+//!
+//! * [`ws::WsSession`] keeps a WebSocket connection alive and reports its loss as an error.
+//! * [`ordermanager`], [`symbol`] and [`feed`] are provided by [`connector_sdk`], so that the
+//!   connectors outside this crate can use them as well.
+
+pub use connector_sdk::{feed, ordermanager, symbol};
+use connector_sdk::FeedError;
+
+pub mod ws;
+
+/// Reports the failure to decode a market data message as [`FeedError::Decode`].
+pub fn decode_error(error: serde_json::Error) -> FeedError {
+    FeedError::Decode(error.to_string())
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use futures_util::{
+    stream::{SplitSink, SplitStream},
+    SinkExt,
+    StreamExt,
+};
+use thiserror::Error;
+use tokio::{net::TcpStream, select, time, time::Interval};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite,
+    tungstenite::{client::IntoClientRequest, Message},
+    MaybeTlsStream,
+    WebSocketStream,
+};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Error, Debug)]
+pub enum WsError {
+    #[error("Tungstenite: {0}")]
+    Tungstenite(Box<tungstenite::Error>),
+    #[error("ConnectionAbort: {0}")]
+    ConnectionAbort(String),
+    #[error("ConnectionInterrupted")]
+    ConnectionInterrupted,
+}
+
+// The tungstenite error is boxed to keep `WsError`, and the connector errors wrapping it, small.
+impl From<tungstenite::Error> for WsError {
+    fn from(error: tungstenite::Error) -> Self {
+        WsError::Tungstenite(Box::new(error))
+    }
+}
+
+/// A WebSocket connection that answers the pings, sends the venue's application-level keepalive
+/// message, and reports the loss of the connection as an error.
+pub struct WsSession {
+    write: SplitSink<WsStream, Message>,
+    read: SplitStream<WsStream>,
+    keepalive: Option<(Interval, String)>,
+}
+
+impl WsSession {
+    pub async fn connect(url: &str) -> Result<Self, WsError> {
+        let request = url.into_client_request()?;
+        let (ws_stream, _) = connect_async(request).await?;
+        let (write, read) = ws_stream.split();
+        Ok(Self {
+            write,
+            read,
+            keepalive: None,
+        })
+    }
+
+    /// Sends the text message at the interval to keep the connection alive, for the venues that
+    /// require an application-level ping such as `ping` or `{"op":"ping"}`.
+    pub fn keepalive(self, interval: Duration, msg: String) -> Self {
+        let mut interval = time::interval(interval);
+        // The first tick completes immediately, but the connection has just been established.
+        interval.reset();
+        Self {
+            keepalive: Some((interval, msg)),
+            ..self
+        }
+    }
+
+    pub async fn send(&mut self, text: String) -> Result<(), WsError> {
+        self.write.send(Message::Text(text)).await?;
+        Ok(())
+    }
+
+    /// Waits for the next text message. This can be used in `select!`; if it's cancelled, at
+    /// most a pong or a keepalive message that is being sent is lost.
+    pub async fn recv(&mut self) -> Result<String, WsError> {
+        loop {
+            let message = match &mut self.keepalive {
+                Some((interval, msg)) => {
+                    select! {
+                        _ = interval.tick() => {
+                            self.write.send(Message::Text(msg.clone())).await?;
+                            continue;
+                        }
+                        message = self.read.next() => message,
+                    }
+                }
+                None => self.read.next().await,
+            };
+            match message {
+                Some(Ok(Message::Text(text))) => return Ok(text),
+                Some(Ok(Message::Ping(data))) => {
+                    self.write.send(Message::Pong(data)).await?;
+                }
+                Some(Ok(Message::Close(close_frame))) => {
+                    return Err(WsError::ConnectionAbort(
+                        close_frame.map(|f| f.to_string()).unwrap_or(String::new()),
+                    ));
+                }
+                Some(Ok(Message::Binary(_)))
+                | Some(Ok(Message::Frame(_)))
+                | Some(Ok(Message::Pong(_))) => {}
+                Some(Err(error)) => return Err(WsError::from(error)),
+                None => return Err(WsError::ConnectionInterrupted),
+            }
+        }
+    }
+}
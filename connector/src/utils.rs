@@ -6,10 +6,9 @@ use std::{
     time::{Duration, Instant},
 };
 
-use hashbrown::Equivalent;
-use hftbacktest::prelude::OrderId;
+use base64::{engine::general_purpose::STANDARD, Engine};
+pub use connector_sdk::utils::{generate_rand_string, PxQty, RefSymbolOrderId, SymbolOrderId};
 use hmac::{Hmac, KeyInit, Mac};
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use serde::{
     de,
     de::{Error, Visitor},
@@ -138,7 +137,12 @@ pub fn sign_hmac_sha256(secret: &str, s: &str) -> String {
     tmp
 }
 
-pub type PxQty = (f64, f64);
+/// Returns the base64-encoded HMAC-SHA256 signature, as used by Bitget and KuCoin.
+pub fn sign_hmac_sha256_base64(secret: &str, s: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(s.as_bytes());
+    STANDARD.encode(mac.finalize().into_bytes())
+}
 
 pub fn parse_depth(
     bids: Vec<(String, String)>,
@@ -265,62 +269,3 @@ where
         }
     }
 }
-
-#[derive(Eq, Hash, PartialEq, Debug)]
-pub struct SymbolOrderId {
-    pub symbol: String,
-    pub order_id: OrderId,
-}
-
-impl SymbolOrderId {
-    pub fn new(symbol: String, order_id: OrderId) -> Self {
-        Self { symbol, order_id }
-    }
-}
-
-#[derive(Eq, Hash, PartialEq, Debug)]
-pub struct RefSymbolOrderId<'a> {
-    pub symbol: &'a str,
-    pub order_id: OrderId,
-}
-
-impl<'a> RefSymbolOrderId<'a> {
-    pub fn new(symbol: &'a str, order_id: OrderId) -> Self {
-        Self { symbol, order_id }
-    }
-}
-
-impl Equivalent<SymbolOrderId> for RefSymbolOrderId<'_> {
-    fn equivalent(&self, key: &SymbolOrderId) -> bool {
-        key.symbol == self.symbol && key.order_id == self.order_id
-    }
-}
-
-pub fn generate_rand_string(length: usize) -> String {
-    thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(length)
-        .map(char::from)
-        .collect()
-}
-
-#[cfg(test)]
-mod tests {
-    use hashbrown::HashMap;
-
-    use crate::utils::{RefSymbolOrderId, SymbolOrderId};
-
-    #[test]
-    fn equivalent_symbol_order_id() {
-        let mut map = HashMap::new();
-        map.insert(
-            SymbolOrderId::new("key1".to_string(), 1),
-            "value1".to_string(),
-        );
-
-        assert_eq!(
-            map.get(&RefSymbolOrderId::new("key1", 1)).unwrap(),
-            "value1"
-        )
-    }
-}
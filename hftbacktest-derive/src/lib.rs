@@ -232,6 +232,15 @@ pub fn build_asset(input: TokenStream) -> TokenStream {
                             (Ident::new("Local", Span::call_site()), em_ident.clone())
                         };
 
+                        // The depth updates are not tracked by the L3 market depth.
+                        let local_depth_updates = if l3 {
+                            quote! {}
+                        } else {
+                            quote! {
+                                .last_depth_updates_capacity(#asset.last_depth_updates_cap)
+                            }
+                        };
+
                         let depth_construct = match marketdepth.to_string().as_str() {
                             "HashMapMarketDepth" => {
                                 quote! {
@@ -245,7 +254,8 @@ pub fn build_asset(input: TokenStream) -> TokenStream {
                                         #asset.lot_size,
                                         #asset.roi_lb,
                                         #asset.roi_ub
-                                    );
+                                    )
+                                    .auto_recenter(#asset.roi_auto_recenter);
                                 }
                             }
                             _ => panic!(),
@@ -290,6 +300,13 @@ pub fn build_asset(input: TokenStream) -> TokenStream {
                                 Some(DataSource::Data(data)) => {
                                     market_depth.apply_snapshot(data);
                                 }
+                                Some(DataSource::Shared(view)) => {
+                                    // The shared view is read-only, so the snapshot is applied
+                                    // from a copy.
+                                    let events: Vec<_> =
+                                        (0..view.len()).map(|i| view[i].clone()).collect();
+                                    market_depth.apply_snapshot(&Data::from_data(&events));
+                                }
                                 None => {}
                             }
 
@@ -301,7 +318,7 @@ pub fn build_asset(input: TokenStream) -> TokenStream {
                                 #asset.last_trades_cap,
                                 ob_local_to_exch.clone(),
                                 ob_exch_to_local.clone(),
-                            ));
+                            )#local_depth_updates);
 
                             let mut market_depth = #depth_construct;
                             match #asset.initial_snapshot.as_ref() {
@@ -312,6 +329,13 @@ pub fn build_asset(input: TokenStream) -> TokenStream {
                                 Some(DataSource::Data(data)) => {
                                     market_depth.apply_snapshot(data);
                                 }
+                                Some(DataSource::Shared(view)) => {
+                                    // The shared view is read-only, so the snapshot is applied
+                                    // from a copy.
+                                    let events: Vec<_> =
+                                        (0..view.len()).map(|i| view[i].clone()).collect();
+                                    market_depth.apply_snapshot(&Data::from_data(&events));
+                                }
                                 None => {}
                             }
 
@@ -325,11 +349,13 @@ pub fn build_asset(input: TokenStream) -> TokenStream {
                                 queue_model,
                                 ob_exch_to_local,
                                 ob_local_to_exch,
+                                #asset.post_only,
                             ));
 
                             Asset {
                                 local,
-                                exch
+                                exch,
+                                manifest: Default::default(),
                             }
                         },
                     });
@@ -0,0 +1,110 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use hftbacktest::{
+    backtest::{
+        assettype::LinearAsset,
+        data::{synthetic::SyntheticGenerator, Data, DataSource},
+        models::{
+            CommonFees,
+            ConstantLatency,
+            PowerProbQueueFunc3,
+            ProbQueueModel,
+            TradingValueFeeModel,
+        },
+        Backtest,
+        L2AssetBuilder,
+    },
+    depth::{HashMapMarketDepth, MarketDepth},
+    prelude::{Bot, OrdType, TimeInForce},
+    types::Event,
+};
+
+/// The reference dataset: an hour of the synthetic feed of about 1,500 events per second.
+fn reference_data() -> Data<Event> {
+    let events = SyntheticGenerator::new(0.1, 0.001)
+        .seed(1)
+        .intensity(1_000.0, 500.0, 1_000.0)
+        .generate(3_600_000_000_000);
+    Data::from_data(&events)
+}
+
+fn backtest(data: &Data<Event>) -> Backtest<HashMapMarketDepth> {
+    Backtest::builder()
+        .add_asset(
+            L2AssetBuilder::new()
+                .data(vec![DataSource::Data(data.clone())])
+                .latency_model(ConstantLatency::new(1_000_000, 1_000_000))
+                .asset_type(LinearAsset::new(1.0))
+                .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                .queue_model(ProbQueueModel::new(PowerProbQueueFunc3::new(3.0)))
+                .depth(|| HashMapMarketDepth::new(0.1, 0.001))
+                .build()
+                .unwrap(),
+        )
+        .build()
+        .unwrap()
+}
+
+/// Keeps a grid of `levels` orders on each side from the best price, so that the exchange
+/// matches the trades and the depth changes against the resting orders.
+fn run_grid(hbt: &mut Backtest<HashMapMarketDepth>, levels: i64) {
+    let mut order_id = 0;
+    while hbt.elapse(100_000_000).unwrap() {
+        hbt.clear_inactive_orders(Some(0));
+        let depth = hbt.depth(0);
+        let (best_bid_tick, best_ask_tick) = (depth.best_bid_tick(), depth.best_ask_tick());
+        if levels == 0 || best_ask_tick <= best_bid_tick {
+            continue;
+        }
+        let ids: Vec<_> = hbt.orders(0).keys().copied().collect();
+        for id in ids {
+            // The orders with a pending request can't be canceled yet.
+            let _ = hbt.cancel(0, id, false);
+        }
+        for i in 0..levels {
+            order_id += 2;
+            let bid = (best_bid_tick - i) as f64 * 0.1;
+            let ask = (best_ask_tick + i) as f64 * 0.1;
+            hbt.submit_buy_order(
+                0,
+                order_id,
+                bid,
+                0.001,
+                TimeInForce::GTC,
+                OrdType::Limit,
+                false,
+            )
+            .unwrap();
+            hbt.submit_sell_order(
+                0,
+                order_id + 1,
+                ask,
+                0.001,
+                TimeInForce::GTC,
+                OrdType::Limit,
+                false,
+            )
+            .unwrap();
+        }
+    }
+}
+
+fn end_to_end(c: &mut Criterion) {
+    let data = reference_data();
+    let mut group = c.benchmark_group("end_to_end");
+    // Each event is processed by both the local and the exchange.
+    group.throughput(Throughput::Elements(2 * data.len() as u64));
+    group.sample_size(10);
+    for levels in [0, 1, 20] {
+        group.bench_function(format!("grid/{levels}"), |b| {
+            b.iter_batched_ref(
+                || backtest(&data),
+                |hbt| run_grid(hbt, levels),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, end_to_end);
+criterion_main!(benches);
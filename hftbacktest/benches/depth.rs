@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use hftbacktest::{
+    backtest::data::synthetic::SyntheticGenerator,
+    depth::{BTreeMarketDepth, HashMapMarketDepth, L2MarketDepth, ROIVectorMarketDepth},
+    types::{Event, BUY_EVENT, DEPTH_EVENT},
+};
+
+/// Returns the depth events of the reference synthetic feed.
+fn depth_events() -> Vec<Event> {
+    SyntheticGenerator::new(0.1, 0.001)
+        .seed(1)
+        .intensity(1_000.0, 500.0, 1_000.0)
+        .generate(60_000_000_000)
+        .into_iter()
+        .filter(|ev| ev.is(DEPTH_EVENT))
+        .collect()
+}
+
+fn apply<MD: L2MarketDepth>(depth: &mut MD, events: &[Event]) {
+    for ev in events {
+        if ev.is(BUY_EVENT) {
+            depth.update_bid_depth(ev.px, ev.qty, ev.local_ts);
+        } else {
+            depth.update_ask_depth(ev.px, ev.qty, ev.local_ts);
+        }
+    }
+}
+
+fn depth_updates(c: &mut Criterion) {
+    let events = depth_events();
+    let mut group = c.benchmark_group("depth_updates");
+    group.throughput(Throughput::Elements(events.len() as u64));
+    group.bench_function("hashmap", |b| {
+        b.iter_batched_ref(
+            || HashMapMarketDepth::new(0.1, 0.001),
+            |depth| apply(depth, &events),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("btree", |b| {
+        b.iter_batched_ref(
+            || BTreeMarketDepth::new(0.1, 0.001),
+            |depth| apply(depth, &events),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("roivector", |b| {
+        b.iter_batched_ref(
+            || ROIVectorMarketDepth::new(0.1, 0.001, 0.0, 1000.0).auto_recenter(true),
+            |depth| apply(depth, &events),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, depth_updates);
+criterion_main!(benches);
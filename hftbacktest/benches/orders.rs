@@ -0,0 +1,135 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use hftbacktest::{
+    backtest::{
+        assettype::LinearAsset,
+        data::{Data, DataSource},
+        models::{
+            CommonFees,
+            ConstantLatency,
+            PowerProbQueueFunc3,
+            ProbQueueModel,
+            QueueState,
+            TradingValueFeeModel,
+        },
+        Backtest,
+        L2AssetBuilder,
+    },
+    depth::HashMapMarketDepth,
+    prelude::{Bot, OrdType, Order, Side, TimeInForce},
+    types::{Event, BUY_EVENT, DEPTH_EVENT, EXCH_EVENT, LOCAL_EVENT, SELL_EVENT, TRADE_EVENT},
+};
+
+/// Builds the feed where the quantities at the five levels on each side change every 100ns, with
+/// a trade at the best bid every tenth update.
+fn feed(len: usize) -> DataSource<Event> {
+    let events: Vec<_> = (0..len)
+        .map(|i| {
+            let ts = 100 * i as i64;
+            let level = (i % 10) as f64 * 0.1;
+            let (ev, px, qty) = match i % 10 {
+                0 => (TRADE_EVENT | SELL_EVENT, 100.0, 1.0),
+                j if j % 2 == 0 => (DEPTH_EVENT | BUY_EVENT, 100.0 - level, 5.0 + j as f64),
+                j => (DEPTH_EVENT | SELL_EVENT, 100.1 + level, 5.0 + j as f64),
+            };
+            Event {
+                ev: EXCH_EVENT | LOCAL_EVENT | ev,
+                exch_ts: ts,
+                local_ts: ts + 5,
+                px,
+                qty,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            }
+        })
+        .collect();
+    DataSource::Data(Data::from_data(&events))
+}
+
+fn order_clone(c: &mut Criterion) {
+    // Every order response carries a copy of the order, including its queue state.
+    let mut order = Order::new(
+        1,
+        1000,
+        0.1,
+        1.0,
+        Side::Buy,
+        OrdType::Limit,
+        TimeInForce::GTC,
+    );
+    let mut group = c.benchmark_group("order_clone");
+    order.q = QueueState::custom(5.0f64);
+    group.bench_function("boxed", |b| b.iter(|| black_box(&order).clone()));
+    order.q = QueueState::Qty(5.0);
+    group.bench_function("inline", |b| b.iter(|| black_box(&order).clone()));
+    group.finish();
+}
+
+fn order_heavy(c: &mut Criterion) {
+    // Requotes a bid and an ask at every step, which replaces the orders at the exchange.
+    const STEPS: usize = 1_000;
+    let data = feed(STEPS * 10 + 10);
+    let mut group = c.benchmark_group("order_heavy");
+    group.throughput(criterion::Throughput::Elements(2 * STEPS as u64));
+    group.sample_size(20);
+    group.bench_function("requote", |b| {
+        b.iter_batched(
+            || {
+                Backtest::builder()
+                    .add_asset(
+                        L2AssetBuilder::new()
+                            .data(vec![data.clone()])
+                            .latency_model(ConstantLatency::new(10, 10))
+                            .asset_type(LinearAsset::new(1.0))
+                            .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                            .queue_model(ProbQueueModel::new(PowerProbQueueFunc3::new(3.0)))
+                            .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                            .build()
+                            .unwrap(),
+                    )
+                    .build()
+                    .unwrap()
+            },
+            |mut hbt| {
+                let mut order_id = 0;
+                for _ in 0..STEPS {
+                    hbt.elapse(1_000).unwrap();
+                    hbt.clear_inactive_orders(Some(0));
+                    let ids: Vec<_> = hbt.orders(0).keys().copied().collect();
+                    for id in ids {
+                        let _ = hbt.cancel(0, id, false);
+                    }
+                    for (side, price) in [(Side::Buy, 99.5), (Side::Sell, 100.6)] {
+                        order_id += 1;
+                        let _ = match side {
+                            Side::Buy => hbt.submit_buy_order(
+                                0,
+                                order_id,
+                                price,
+                                1.0,
+                                TimeInForce::GTC,
+                                OrdType::Limit,
+                                false,
+                            ),
+                            _ => hbt.submit_sell_order(
+                                0,
+                                order_id,
+                                price,
+                                1.0,
+                                TimeInForce::GTC,
+                                OrdType::Limit,
+                                false,
+                            ),
+                        };
+                    }
+                }
+                hbt
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, order_clone, order_heavy);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use hftbacktest::depth::{L2MarketDepth, MarketDepth, ROIVectorMarketDepth};
+
+/// Builds a book of a 0.01 tick size with the range of interest spanning 20,000 ticks, where every
+/// third tick within 500 ticks of the mid-price has a price level.
+fn book() -> ROIVectorMarketDepth {
+    let mut depth = ROIVectorMarketDepth::new(0.01, 0.001, 900.0, 1100.0);
+    for i in (0..500).step_by(3) {
+        depth.update_bid_depth(1000.0 - i as f64 * 0.01, 1.0 + i as f64, 0);
+        depth.update_ask_depth(1000.01 + i as f64 * 0.01, 1.0 + i as f64, 0);
+    }
+    depth
+}
+
+fn qty_in_range(c: &mut Criterion) {
+    let depth = book();
+    let best_bid_tick = depth.best_bid_tick();
+    let mut group = c.benchmark_group("qty_in_range");
+    for ticks in [10, 100, 1000] {
+        group.bench_function(format!("scalar/{ticks}"), |b| {
+            let lb = (best_bid_tick - ticks - depth.roi_lb) as usize;
+            let ub = (best_bid_tick - depth.roi_lb) as usize;
+            b.iter(|| black_box(&depth.bid_depth()[lb..=ub]).iter().sum::<f64>())
+        });
+        group.bench_function(format!("simd/{ticks}"), |b| {
+            b.iter(|| black_box(&depth).bid_qty_in_range(best_bid_tick - ticks, best_bid_tick))
+        });
+    }
+    group.finish();
+}
+
+fn levels(c: &mut Criterion) {
+    let depth = book();
+    let mut group = c.benchmark_group("levels");
+    for n in [5, 20, 100] {
+        group.bench_function(format!("scalar/{n}"), |b| {
+            let end = (depth.best_bid_tick() - depth.roi_lb) as usize;
+            b.iter(|| {
+                black_box(&depth).bid_depth()[..=end]
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|(_, &qty)| qty > 0.0)
+                    .take(n)
+                    .map(|(t, &qty)| (t as i64 + depth.roi_lb, qty))
+                    .collect::<Vec<_>>()
+            })
+        });
+        group.bench_function(format!("simd/{n}"), |b| {
+            b.iter(|| black_box(&depth).bid_levels(n))
+        });
+    }
+    group.finish();
+}
+
+fn best_level_removal(c: &mut Criterion) {
+    // Removing the best bid scans down for the next level, which is 100 ticks away.
+    let mut depth = ROIVectorMarketDepth::new(0.01, 0.001, 900.0, 1100.0);
+    depth.update_bid_depth(999.0, 1.0, 0);
+    c.bench_function("best_level_removal", |b| {
+        b.iter(|| {
+            depth.update_bid_depth(1000.0, 1.0, 0);
+            depth.update_bid_depth(black_box(1000.0), 0.0, 0);
+        })
+    });
+}
+
+criterion_group!(benches, qty_in_range, levels, best_level_removal);
+criterion_main!(benches);
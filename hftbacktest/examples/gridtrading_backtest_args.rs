@@ -57,6 +57,9 @@ struct Args {
     maker_fee: f64,
     #[arg(long, default_value_t = 0.0007)]
     taker_fee: f64,
+    /// Prints the event throughput of the backtest when it finishes.
+    #[arg(long)]
+    report_throughput: bool,
 }
 
 fn prepare_backtest(
@@ -135,5 +138,8 @@ fn main() {
     )
     .unwrap();
     hbt.close().unwrap();
+    if args.report_throughput {
+        println!("{}", hbt.throughput());
+    }
     recorder.to_csv(args.name, args.output_path).unwrap();
 }
@@ -46,6 +46,18 @@ fn prepare_live() -> LiveBot<IceoryxUnifiedChannel, HashMapMarketDepth> {
                         _ => {}
                     }
                 }
+                ErrorKind::ConnectorUnresponsive => {
+                    error!(connector = ?error.value(), "ConnectorUnresponsive");
+                }
+                ErrorKind::StaleFeed => {
+                    error!(instrument = ?error.value(), "StaleFeed");
+                }
+                ErrorKind::SeqGap => {
+                    error!(gap = ?error.value(), "SeqGap");
+                }
+                ErrorKind::AuthFailure => {
+                    error!(error = ?error.value(), "AuthFailure");
+                }
                 ErrorKind::Custom(errno) => {
                     if errno == 1000 {
                         // Aborts the connection.
@@ -0,0 +1,82 @@
+use hftbacktest::prelude::*;
+
+use super::{quote_bid_ask, Strategy};
+
+/// Quotes around the reservation price of the Avellaneda-Stoikov model, which shifts away from
+/// the mid price in proportion to the inventory risk.
+///
+/// The volatility is estimated as the exponentially weighted moving average of the squared mid
+/// price changes over each interval. All prices in the model are expressed in ticks.
+///
+/// * reservation price = mid - q * gamma * sigma^2 * tau
+/// * half spread = gamma * sigma^2 * tau / 2 + ln(1 + gamma / k) / gamma
+pub struct AvellanedaStoikov {
+    /// The risk aversion.
+    pub gamma: f64,
+    /// The decay of the order arrival intensity with the distance from the mid price in ticks.
+    pub k: f64,
+    /// The time horizon as the number of intervals.
+    pub tau: f64,
+    /// The smoothing factor of the variance estimate.
+    pub decay: f64,
+    pub order_qty: f64,
+    pub max_position: f64,
+    prev_mid_tick: f64,
+    variance: f64,
+}
+
+impl AvellanedaStoikov {
+    /// Constructs `AvellanedaStoikov`.
+    pub fn new(
+        gamma: f64,
+        k: f64,
+        tau: f64,
+        decay: f64,
+        order_qty: f64,
+        max_position: f64,
+    ) -> Self {
+        Self {
+            gamma,
+            k,
+            tau,
+            decay,
+            order_qty,
+            max_position,
+            prev_mid_tick: f64::NAN,
+            variance: 0.0,
+        }
+    }
+}
+
+impl<MD> Strategy<MD> for AvellanedaStoikov
+where
+    MD: MarketDepth,
+{
+    fn update<I>(&mut self, hbt: &mut I, asset_no: usize) -> Result<(), I::Error>
+    where
+        I: Bot<MD>,
+    {
+        let depth = hbt.depth(asset_no);
+        let tick_size = depth.tick_size();
+        let mid_tick = (depth.best_bid_tick() + depth.best_ask_tick()) as f64 / 2.0;
+        if self.prev_mid_tick.is_finite() {
+            let change = mid_tick - self.prev_mid_tick;
+            self.variance = self.decay * change * change + (1.0 - self.decay) * self.variance;
+        }
+        self.prev_mid_tick = mid_tick;
+
+        let q = hbt.position(asset_no) / self.order_qty;
+        let risk = self.gamma * self.variance * self.tau;
+        let reservation_tick = mid_tick - q * risk;
+        let half_spread_tick = risk / 2.0 + (1.0 + self.gamma / self.k).ln() / self.gamma;
+
+        quote_bid_ask(
+            hbt,
+            asset_no,
+            (reservation_tick - half_spread_tick) * tick_size,
+            (reservation_tick + half_spread_tick) * tick_size,
+            self.order_qty,
+            self.max_position,
+        )
+    }
+}
@@ -0,0 +1,71 @@
+use hftbacktest::prelude::*;
+
+use super::{quote, Strategy};
+
+/// Posts a grid of orders on both sides around the mid price, skewed by the position.
+pub struct GridTrading {
+    /// The half spread relative to the mid price.
+    pub relative_half_spread: f64,
+    /// The grid interval relative to the mid price.
+    pub relative_grid_interval: f64,
+    /// The number of orders on each side.
+    pub grid_num: usize,
+    /// The minimum grid interval. This should be a multiple of the tick size.
+    pub min_grid_step: f64,
+    /// The relative half spread shift per order quantity of position.
+    pub skew: f64,
+    pub order_qty: f64,
+    pub max_position: f64,
+}
+
+impl<MD> Strategy<MD> for GridTrading
+where
+    MD: MarketDepth,
+{
+    fn update<I>(&mut self, hbt: &mut I, asset_no: usize) -> Result<(), I::Error>
+    where
+        I: Bot<MD>,
+    {
+        let depth = hbt.depth(asset_no);
+        let position = hbt.position(asset_no);
+        let tick_size = depth.tick_size();
+        let min_grid_step = ((self.min_grid_step / tick_size).round() * tick_size).max(tick_size);
+
+        let mid_price = (depth.best_bid() + depth.best_ask()) / 2.0;
+        let normalized_position = position / self.order_qty;
+
+        let relative_bid_depth = self.relative_half_spread + self.skew * normalized_position;
+        let relative_ask_depth = self.relative_half_spread - self.skew * normalized_position;
+
+        let bid_price = (mid_price * (1.0 - relative_bid_depth)).min(depth.best_bid());
+        let ask_price = (mid_price * (1.0 + relative_ask_depth)).max(depth.best_ask());
+
+        // Rounding the grid interval to min_grid_step keeps the orders on the grid stable.
+        let grid_interval = ((mid_price * self.relative_grid_interval / min_grid_step).round()
+            * min_grid_step)
+            .max(min_grid_step);
+
+        let bid_price = (bid_price / grid_interval).floor() * grid_interval;
+        let ask_price = (ask_price / grid_interval).ceil() * grid_interval;
+
+        hbt.clear_inactive_orders(Some(asset_no));
+
+        let bid_prices: Vec<f64> = if position < self.max_position {
+            (0..self.grid_num)
+                .map(|i| bid_price - i as f64 * grid_interval)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        quote(hbt, asset_no, Side::Buy, &bid_prices, self.order_qty)?;
+
+        let ask_prices: Vec<f64> = if position > -self.max_position {
+            (0..self.grid_num)
+                .map(|i| ask_price + i as f64 * grid_interval)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        quote(hbt, asset_no, Side::Sell, &ask_prices, self.order_qty)
+    }
+}
@@ -0,0 +1,104 @@
+use hftbacktest::prelude::*;
+
+use super::{quote_bid_ask, Strategy};
+
+/// Quotes around a fair price shifted by the order flow imbalance (OFI) at the best prices, with
+/// an inventory skew.
+///
+/// The OFI is sampled over each interval, as defined by Cont, Kukanov, and Stoikov, and normalized
+/// by the average quantity at the best prices, both smoothed by an exponentially weighted moving
+/// average.
+pub struct ImbalanceMarketMaking {
+    /// The fair price shift in ticks per unit of the normalized OFI.
+    pub ofi_coeff: f64,
+    /// The half spread in ticks.
+    pub half_spread: f64,
+    /// The quote shift in ticks per order quantity of position.
+    pub skew: f64,
+    /// The smoothing factor of the OFI and the average quantity.
+    pub decay: f64,
+    pub order_qty: f64,
+    pub max_position: f64,
+    prev: Option<(i64, f64, i64, f64)>,
+    ofi: f64,
+    avg_qty: f64,
+}
+
+impl ImbalanceMarketMaking {
+    /// Constructs `ImbalanceMarketMaking`.
+    pub fn new(
+        ofi_coeff: f64,
+        half_spread: f64,
+        skew: f64,
+        decay: f64,
+        order_qty: f64,
+        max_position: f64,
+    ) -> Self {
+        Self {
+            ofi_coeff,
+            half_spread,
+            skew,
+            decay,
+            order_qty,
+            max_position,
+            prev: None,
+            ofi: 0.0,
+            avg_qty: 0.0,
+        }
+    }
+}
+
+impl<MD> Strategy<MD> for ImbalanceMarketMaking
+where
+    MD: MarketDepth,
+{
+    fn update<I>(&mut self, hbt: &mut I, asset_no: usize) -> Result<(), I::Error>
+    where
+        I: Bot<MD>,
+    {
+        let depth = hbt.depth(asset_no);
+        let tick_size = depth.tick_size();
+        let bid_tick = depth.best_bid_tick();
+        let ask_tick = depth.best_ask_tick();
+        let bid_qty = depth.bid_qty_at_tick(bid_tick);
+        let ask_qty = depth.ask_qty_at_tick(ask_tick);
+
+        if let Some((prev_bid_tick, prev_bid_qty, prev_ask_tick, prev_ask_qty)) = self.prev {
+            let mut e = 0.0;
+            if bid_tick >= prev_bid_tick {
+                e += bid_qty;
+            }
+            if bid_tick <= prev_bid_tick {
+                e -= prev_bid_qty;
+            }
+            if ask_tick <= prev_ask_tick {
+                e -= ask_qty;
+            }
+            if ask_tick >= prev_ask_tick {
+                e += prev_ask_qty;
+            }
+            self.ofi = self.decay * e + (1.0 - self.decay) * self.ofi;
+            self.avg_qty =
+                self.decay * (bid_qty + ask_qty) / 2.0 + (1.0 - self.decay) * self.avg_qty;
+        }
+        self.prev = Some((bid_tick, bid_qty, ask_tick, ask_qty));
+
+        let normalized_ofi = if self.avg_qty > 0.0 {
+            self.ofi / self.avg_qty
+        } else {
+            0.0
+        };
+        let q = hbt.position(asset_no) / self.order_qty;
+        let mid_tick = (bid_tick + ask_tick) as f64 / 2.0;
+        let fair_tick = mid_tick + self.ofi_coeff * normalized_ofi - self.skew * q;
+
+        quote_bid_ask(
+            hbt,
+            asset_no,
+            (fair_tick - self.half_spread) * tick_size,
+            (fair_tick + self.half_spread) * tick_size,
+            self.order_qty,
+            self.max_position,
+        )
+    }
+}
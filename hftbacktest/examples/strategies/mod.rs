@@ -0,0 +1,170 @@
+//! Reference strategies built on the [`Bot`] trait.
+//!
+//! Each strategy only depends on [`Bot`], [`MarketDepth`], and [`Recorder`], so the same code runs
+//! against both [`Backtest`](hftbacktest::backtest::Backtest) and
+//! [`LiveBot`](hftbacktest::live::LiveBot). See `strategies_backtest.rs` and `strategies_live.rs`,
+//! and `tests/strategies.rs`, which runs each strategy through a short backtest.
+#![allow(dead_code)]
+
+use std::{collections::HashMap, fmt::Debug};
+
+use hftbacktest::prelude::*;
+
+pub use self::{
+    avellaneda_stoikov::AvellanedaStoikov,
+    grid::GridTrading,
+    imbalance::ImbalanceMarketMaking,
+};
+
+mod avellaneda_stoikov;
+mod grid;
+mod imbalance;
+
+/// Provides a strategy that updates its quotes at every fixed interval.
+pub trait Strategy<MD>
+where
+    MD: MarketDepth,
+{
+    /// Updates the quotes of the asset. This is called at every interval once the market depth
+    /// is complete.
+    fn update<I>(&mut self, hbt: &mut I, asset_no: usize) -> Result<(), I::Error>
+    where
+        I: Bot<MD>;
+}
+
+/// Runs the strategy on the asset until the end of the data in backtesting or until the bot is
+/// closed in live trading.
+///
+/// * `interval` - The interval at which the quotes are updated in nanoseconds.
+/// * `record_every` - The number of intervals between each record.
+pub fn run<MD, I, R, S>(
+    hbt: &mut I,
+    recorder: &mut R,
+    strategy: &mut S,
+    asset_no: usize,
+    interval: i64,
+    record_every: usize,
+) -> Result<(), I::Error>
+where
+    MD: MarketDepth,
+    I: Bot<MD>,
+    R: Recorder,
+    <R as Recorder>::Error: Debug,
+    S: Strategy<MD>,
+{
+    let mut int = 0;
+    while hbt.elapse(interval)? {
+        int += 1;
+        if int % record_every == 0 {
+            recorder.record(hbt).unwrap();
+        }
+
+        let depth = hbt.depth(asset_no);
+        if depth.best_bid_tick() == INVALID_MIN || depth.best_ask_tick() == INVALID_MAX {
+            // Market depth is incomplete.
+            continue;
+        }
+
+        strategy.update(hbt, asset_no)?;
+    }
+    Ok(())
+}
+
+/// Keeps exactly the given prices quoted on one side. The orders that are not at the given prices
+/// are cancelled, and the missing prices are posted as post-only limit orders. The order price in
+/// ticks is used as the order ID, so the quotes must not cross each other.
+pub fn quote<MD, I>(
+    hbt: &mut I,
+    asset_no: usize,
+    side: Side,
+    prices: &[f64],
+    order_qty: f64,
+) -> Result<(), I::Error>
+where
+    MD: MarketDepth,
+    I: Bot<MD>,
+{
+    let tick_size = hbt.depth(asset_no).tick_size();
+    let new_orders: HashMap<OrderId, f64> = prices
+        .iter()
+        .filter(|price| price.is_finite())
+        .map(|&price| ((price / tick_size).round() as OrderId, price))
+        .collect();
+
+    let orders = hbt.orders(asset_no);
+    let cancel_order_ids: Vec<OrderId> = orders
+        .values()
+        .filter(|order| {
+            order.side == side && order.cancellable() && !new_orders.contains_key(&order.order_id)
+        })
+        .map(|order| order.order_id)
+        .collect();
+    let submit_orders: Vec<(OrderId, f64)> = new_orders
+        .into_iter()
+        .filter(|(order_id, _)| !orders.contains_key(order_id))
+        .collect();
+
+    for order_id in cancel_order_ids {
+        hbt.cancel(asset_no, order_id, false)?;
+    }
+    for (order_id, price) in submit_orders {
+        match side {
+            Side::Buy => hbt.submit_buy_order(
+                asset_no,
+                order_id,
+                price,
+                order_qty,
+                TimeInForce::GTX,
+                OrdType::Limit,
+                false,
+            )?,
+            _ => hbt.submit_sell_order(
+                asset_no,
+                order_id,
+                price,
+                order_qty,
+                TimeInForce::GTX,
+                OrdType::Limit,
+                false,
+            )?,
+        };
+    }
+    Ok(())
+}
+
+/// Quotes a single bid and ask, rounding the prices to the tick size without crossing the best
+/// prices. Each side stops quoting once the position reaches `max_position`.
+pub fn quote_bid_ask<MD, I>(
+    hbt: &mut I,
+    asset_no: usize,
+    bid_price: f64,
+    ask_price: f64,
+    order_qty: f64,
+    max_position: f64,
+) -> Result<(), I::Error>
+where
+    MD: MarketDepth,
+    I: Bot<MD>,
+{
+    let depth = hbt.depth(asset_no);
+    let tick_size = depth.tick_size();
+    let bid_price = ((bid_price / tick_size).floor() * tick_size).min(depth.best_bid());
+    let ask_price = ((ask_price / tick_size).ceil() * tick_size).max(depth.best_ask());
+    let position = hbt.position(asset_no);
+
+    hbt.clear_inactive_orders(Some(asset_no));
+
+    let bid_prices: &[f64] = if position < max_position {
+        &[bid_price]
+    } else {
+        &[]
+    };
+    quote(hbt, asset_no, Side::Buy, bid_prices, order_qty)?;
+
+    let ask_prices: &[f64] = if position > -max_position {
+        &[ask_price]
+    } else {
+        &[]
+    };
+    quote(hbt, asset_no, Side::Sell, ask_prices, order_qty)
+}
@@ -0,0 +1,118 @@
+use clap::{Parser, ValueEnum};
+use hftbacktest::{
+    backtest::{
+        assettype::LinearAsset,
+        data::{read_npz_file, DataSource},
+        models::{
+            CommonFees,
+            IntpOrderLatency,
+            PowerProbQueueFunc3,
+            ProbQueueModel,
+            TradingValueFeeModel,
+        },
+        recorder::BacktestRecorder,
+        Backtest,
+        ExchangeKind,
+        L2AssetBuilder,
+    },
+    prelude::{ApplySnapshot, Bot, HashMapMarketDepth},
+};
+use strategies::{run, AvellanedaStoikov, GridTrading, ImbalanceMarketMaking};
+
+mod strategies;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum StrategyKind {
+    Grid,
+    AvellanedaStoikov,
+    Imbalance,
+}
+
+#[derive(Parser, Debug)]
+#[command(about = None, long_about = None)]
+struct Args {
+    #[arg(long, value_enum)]
+    strategy: StrategyKind,
+    #[arg(long, default_value = ".")]
+    output_path: String,
+}
+
+fn prepare_backtest() -> Backtest<HashMapMarketDepth> {
+    let latency_data = (20240501..20240532)
+        .map(|date| DataSource::File(format!("latency_{date}.npz")))
+        .collect();
+
+    let latency_model = IntpOrderLatency::new(latency_data, 0);
+    let asset_type = LinearAsset::new(1.0);
+    let queue_model = ProbQueueModel::new(PowerProbQueueFunc3::new(3.0));
+
+    let data = (20240501..20240532)
+        .map(|date| DataSource::File(format!("1000SHIBUSDT_{date}.npz")))
+        .collect();
+
+    Backtest::builder()
+        .add_asset(
+            L2AssetBuilder::new()
+                .data(data)
+                .latency_model(latency_model)
+                .asset_type(asset_type)
+                .fee_model(TradingValueFeeModel::new(CommonFees::new(-0.00005, 0.0007)))
+                .exchange(ExchangeKind::NoPartialFillExchange)
+                .queue_model(queue_model)
+                .depth(|| {
+                    let mut depth = HashMapMarketDepth::new(0.000001, 1.0);
+                    depth.apply_snapshot(
+                        &read_npz_file("1000SHIBUSDT_20240501_SOD.npz", "data").unwrap(),
+                    );
+                    depth
+                })
+                .build()
+                .unwrap(),
+        )
+        .build()
+        .unwrap()
+}
+
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args = Args::parse();
+
+    let mut hbt = prepare_backtest();
+    let mut recorder = BacktestRecorder::new(&hbt);
+
+    let order_qty = 1.0;
+    let max_position = 10.0 * order_qty;
+    // Updates the quotes every 100ms and records every 1s.
+    match args.strategy {
+        StrategyKind::Grid => {
+            let mut strategy = GridTrading {
+                relative_half_spread: 0.0005,
+                relative_grid_interval: 0.0005,
+                grid_num: 10,
+                min_grid_step: 0.000001,
+                skew: 0.00005,
+                order_qty,
+                max_position,
+            };
+            run(&mut hbt, &mut recorder, &mut strategy, 0, 100_000_000, 10).unwrap();
+        }
+        StrategyKind::AvellanedaStoikov => {
+            let mut strategy =
+                AvellanedaStoikov::new(0.05, 1.5, 100.0, 0.01, order_qty, max_position);
+            run(&mut hbt, &mut recorder, &mut strategy, 0, 100_000_000, 10).unwrap();
+        }
+        StrategyKind::Imbalance => {
+            let mut strategy =
+                ImbalanceMarketMaking::new(2.0, 3.0, 0.5, 0.1, order_qty, max_position);
+            run(&mut hbt, &mut recorder, &mut strategy, 0, 100_000_000, 10).unwrap();
+        }
+    }
+    hbt.close().unwrap();
+    recorder
+        .to_csv(
+            format!("{:?}", args.strategy).to_lowercase(),
+            &args.output_path,
+        )
+        .unwrap();
+}
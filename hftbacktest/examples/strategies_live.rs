@@ -0,0 +1,80 @@
+use clap::{Parser, ValueEnum};
+use hftbacktest::{
+    live::{
+        ipc::iceoryx::IceoryxUnifiedChannel,
+        Instrument,
+        LiveBot,
+        LiveBotBuilder,
+        LoggingRecorder,
+    },
+    prelude::{Bot, HashMapMarketDepth},
+};
+use strategies::{run, AvellanedaStoikov, GridTrading, ImbalanceMarketMaking};
+
+mod strategies;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum StrategyKind {
+    Grid,
+    AvellanedaStoikov,
+    Imbalance,
+}
+
+#[derive(Parser, Debug)]
+#[command(about = None, long_about = None)]
+struct Args {
+    #[arg(long, value_enum)]
+    strategy: StrategyKind,
+}
+
+fn prepare_live() -> LiveBot<IceoryxUnifiedChannel, HashMapMarketDepth> {
+    LiveBotBuilder::new()
+        .register(Instrument::new(
+            "binancefutures",
+            "1000SHIBUSDT",
+            0.000001,
+            1.0,
+            HashMapMarketDepth::new(0.000001, 1.0),
+            0,
+        ))
+        .build()
+        .unwrap()
+}
+
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args = Args::parse();
+
+    let mut hbt = prepare_live();
+    let mut recorder = LoggingRecorder::new();
+
+    let order_qty = 1.0;
+    let max_position = 10.0 * order_qty;
+    // Updates the quotes every 100ms and records every 1s.
+    match args.strategy {
+        StrategyKind::Grid => {
+            let mut strategy = GridTrading {
+                relative_half_spread: 0.0005,
+                relative_grid_interval: 0.0005,
+                grid_num: 10,
+                min_grid_step: 0.000001,
+                skew: 0.00005,
+                order_qty,
+                max_position,
+            };
+            run(&mut hbt, &mut recorder, &mut strategy, 0, 100_000_000, 10).unwrap();
+        }
+        StrategyKind::AvellanedaStoikov => {
+            let mut strategy =
+                AvellanedaStoikov::new(0.05, 1.5, 100.0, 0.01, order_qty, max_position);
+            run(&mut hbt, &mut recorder, &mut strategy, 0, 100_000_000, 10).unwrap();
+        }
+        StrategyKind::Imbalance => {
+            let mut strategy =
+                ImbalanceMarketMaking::new(2.0, 3.0, 0.5, 0.1, order_qty, max_position);
+            run(&mut hbt, &mut recorder, &mut strategy, 0, 100_000_000, 10).unwrap();
+        }
+    }
+    hbt.close().unwrap();
+}
@@ -0,0 +1,42 @@
+use clap::Parser;
+use hftbacktest::{
+    backtest::data::{
+        read_npy_file,
+        read_npz_file,
+        validate::{repair, validate},
+        write_npy_file,
+    },
+    types::Event,
+};
+
+#[derive(Parser, Debug)]
+#[command(about = "Validates the feed data and optionally writes the cleaned data.")]
+struct Args {
+    /// The `.npy` or `.npz` file to validate.
+    #[arg(long)]
+    input: String,
+    #[arg(long)]
+    tick_size: f64,
+    /// The `.npy` file to which the cleaned data is written.
+    #[arg(long)]
+    output: Option<String>,
+}
+
+fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    let data = if args.input.ends_with(".npz") {
+        read_npz_file::<Event>(&args.input, "data")?
+    } else {
+        read_npy_file::<Event>(&args.input)?
+    };
+
+    match args.output {
+        Some(output) => {
+            let (cleaned, report) = repair(&data, args.tick_size);
+            print!("{report}");
+            write_npy_file(&output, &cleaned)?;
+        }
+        None => print!("{}", validate(&data, args.tick_size)),
+    }
+    Ok(())
+}
@@ -0,0 +1,263 @@
+use super::has_bbo;
+use crate::{
+    depth::MarketDepth,
+    types::{Bot, OrdType, OrderId, Side, TimeInForce},
+};
+
+/// An execution algorithm that works a parent order by slicing it into child orders through any
+/// [`Bot`], so the execution quality can be benchmarked in backtesting with the same latency and
+/// queue models as the strategy, and the same code runs in live trading.
+pub trait ExecutionAlgo {
+    /// Tracks the child order fills and submits or cancels the child orders as scheduled. This
+    /// should be called on every iteration of the event loop, before the inactive orders are
+    /// cleared.
+    fn update<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>;
+
+    /// Stops the algorithm and cancels the working child order.
+    fn cancel<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>;
+
+    /// Returns `true` if the parent order is completely filled, or the algorithm has been
+    /// stopped and no child order is working.
+    fn is_done(&self) -> bool;
+
+    /// Returns the [`ExecutionReport`] of the parent order so far.
+    fn report(&self) -> ExecutionReport;
+}
+
+/// The execution quality of a parent order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExecutionReport {
+    /// The quantity of the parent order.
+    pub qty: f64,
+    /// The filled quantity.
+    pub filled_qty: f64,
+    /// The average fill price, which is `NaN` if nothing is filled.
+    pub avg_price: f64,
+    /// The mid price when the algorithm started, which is `NaN` if the market depth was empty.
+    pub arrival_price: f64,
+    /// The implementation shortfall of the average fill price against the arrival price, in basis
+    /// points, where a positive value is a cost.
+    pub slippage_bps: f64,
+}
+
+/// Determines the price of the child orders.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChildPricing {
+    /// The child order joins the best price on its side and waits in the queue.
+    #[default]
+    Passive,
+    /// The child order takes the best price on the opposite side as an IOC order.
+    Aggressive,
+}
+
+#[derive(Clone, Debug)]
+struct ChildOrder {
+    order_id: OrderId,
+    price: f64,
+    exec_qty: f64,
+}
+
+/// The state of a parent order and its working child order, shared by the execution algorithms.
+#[derive(Clone, Debug)]
+pub(crate) struct ParentOrder {
+    pub asset_no: usize,
+    pub side: Side,
+    pub qty: f64,
+    pub limit_price: Option<f64>,
+    pub time_in_force: TimeInForce,
+    pub stopped: bool,
+    lot_size: f64,
+    filled_qty: f64,
+    filled_value: f64,
+    arrival_price: Option<f64>,
+    child: Option<ChildOrder>,
+}
+
+impl ParentOrder {
+    pub fn new(asset_no: usize, side: Side, qty: f64) -> Self {
+        Self {
+            asset_no,
+            side,
+            qty,
+            limit_price: None,
+            time_in_force: TimeInForce::GTC,
+            stopped: false,
+            lot_size: 0.0,
+            filled_qty: 0.0,
+            filled_value: 0.0,
+            arrival_price: None,
+            child: None,
+        }
+    }
+
+    /// Tracks the fills of the child order and returns `true` if it's still working.
+    pub fn track<MD, I>(&mut self, bot: &I) -> bool
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let depth = bot.depth(self.asset_no);
+        self.lot_size = depth.lot_size();
+        if self.arrival_price.is_none() && has_bbo(depth) {
+            self.arrival_price = Some((depth.best_bid() + depth.best_ask()) / 2.0);
+        }
+        let Some(child) = self.child.as_mut() else {
+            return false;
+        };
+        let Some(order) = bot.orders(self.asset_no).get(&child.order_id) else {
+            // The order has been cleared, so its last known fill is final.
+            self.child = None;
+            return false;
+        };
+        let exec_qty = (order.qty - order.leaves_qty).max(0.0);
+        if exec_qty > child.exec_qty {
+            self.filled_qty += exec_qty - child.exec_qty;
+            self.filled_value += (exec_qty - child.exec_qty) * order.exec_price();
+            child.exec_qty = exec_qty;
+        }
+        if !order.active() && !order.pending() {
+            self.child = None;
+            return false;
+        }
+        true
+    }
+
+    pub fn filled_qty(&self) -> f64 {
+        self.filled_qty
+    }
+
+    /// Returns the quantity left to fill, which is zero once less than the lot size is left.
+    pub fn remaining(&self) -> f64 {
+        let remaining = self.qty - self.filled_qty;
+        if remaining < self.lot_size / 2.0 {
+            0.0
+        } else {
+            remaining
+        }
+    }
+
+    /// Returns the price at which a new child order would be placed, or `None` if the market
+    /// depth is empty.
+    pub fn child_price<MD: MarketDepth>(&self, depth: &MD, pricing: ChildPricing) -> Option<f64> {
+        if !has_bbo(depth) {
+            return None;
+        }
+        let price = match (self.side, pricing) {
+            (Side::Buy, ChildPricing::Passive) | (Side::Sell, ChildPricing::Aggressive) => {
+                depth.best_bid()
+            }
+            _ => depth.best_ask(),
+        };
+        Some(match (self.side, self.limit_price) {
+            (Side::Buy, Some(limit_price)) => price.min(limit_price),
+            (Side::Sell, Some(limit_price)) => price.max(limit_price),
+            _ => price,
+        })
+    }
+
+    /// Returns the price of the working child order.
+    pub fn working_price(&self) -> Option<f64> {
+        self.child.as_ref().map(|child| child.price)
+    }
+
+    /// Submits a child order for the quantity, rounded down to the lot size and capped at the
+    /// remaining quantity.
+    pub fn submit_child<MD, I>(
+        &mut self,
+        bot: &mut I,
+        qty: f64,
+        pricing: ChildPricing,
+    ) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let depth = bot.depth(self.asset_no);
+        let lot_size = depth.lot_size();
+        let Some(price) = self.child_price(depth, pricing) else {
+            return Ok(());
+        };
+        let qty = ((qty.min(self.remaining()) / lot_size + 1e-9).floor()) * lot_size;
+        if qty <= 0.0 {
+            return Ok(());
+        }
+        let time_in_force = match pricing {
+            ChildPricing::Passive => self.time_in_force,
+            ChildPricing::Aggressive => TimeInForce::IOC,
+        };
+        let order_id = bot.next_order_id();
+        match self.side {
+            Side::Buy => bot.submit_buy_order(
+                self.asset_no,
+                order_id,
+                price,
+                qty,
+                time_in_force,
+                OrdType::Limit,
+                false,
+            )?,
+            _ => bot.submit_sell_order(
+                self.asset_no,
+                order_id,
+                price,
+                qty,
+                time_in_force,
+                OrdType::Limit,
+                false,
+            )?,
+        };
+        self.child = Some(ChildOrder {
+            order_id,
+            price,
+            exec_qty: 0.0,
+        });
+        Ok(())
+    }
+
+    /// Cancels the working child order if it can be canceled.
+    pub fn cancel_child<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let Some(child) = &self.child else {
+            return Ok(());
+        };
+        let cancellable = bot
+            .orders(self.asset_no)
+            .get(&child.order_id)
+            .is_some_and(|order| order.cancellable());
+        if cancellable {
+            bot.cancel(self.asset_no, child.order_id, false)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.child.is_none() && (self.stopped || self.remaining() == 0.0)
+    }
+
+    pub fn report(&self) -> ExecutionReport {
+        let avg_price = if self.filled_qty > 0.0 {
+            self.filled_value / self.filled_qty
+        } else {
+            f64::NAN
+        };
+        let arrival_price = self.arrival_price.unwrap_or(f64::NAN);
+        ExecutionReport {
+            qty: self.qty,
+            filled_qty: self.filled_qty,
+            avg_price,
+            arrival_price,
+            slippage_bps: AsRef::<f64>::as_ref(&self.side) * (avg_price - arrival_price)
+                / arrival_price
+                * 10_000.0,
+        }
+    }
+}
@@ -0,0 +1,17 @@
+mod execution;
+mod pov;
+mod quoter;
+mod spread;
+mod twap;
+
+pub use execution::{ChildPricing, ExecutionAlgo, ExecutionReport};
+pub use pov::Pov;
+pub use quoter::Quoter;
+pub use spread::{ImbalancePolicy, Spread};
+pub use twap::Twap;
+
+use crate::depth::{MarketDepth, INVALID_MAX, INVALID_MIN};
+
+fn has_bbo<MD: MarketDepth>(depth: &MD) -> bool {
+    depth.best_bid_tick() != INVALID_MIN && depth.best_ask_tick() != INVALID_MAX
+}
@@ -0,0 +1,175 @@
+use super::execution::{ChildPricing, ExecutionAlgo, ExecutionReport, ParentOrder};
+use crate::{
+    depth::MarketDepth,
+    types::{Bot, Event, Side, TimeInForce},
+};
+
+/// Percentage-of-volume execution, which keeps the filled quantity at a fixed fraction of the
+/// market trading volume since the start.
+///
+/// The market volume is counted from [`Bot::last_trades`], so the market trades must be
+/// collected, and the buffer must not be cleared more than once between updates. The passive
+/// child order is repriced when the best price moves away from it.
+#[derive(Clone, Debug)]
+pub struct Pov {
+    parent: ParentOrder,
+    participation_rate: f64,
+    pricing: ChildPricing,
+    market_volume: f64,
+    cursor: TradeCursor,
+}
+
+impl Pov {
+    /// Constructs a `Pov`. The market volume is counted from the first
+    /// [`update`](ExecutionAlgo::update).
+    ///
+    /// * `asset_no` - Asset number to trade.
+    /// * `side` - The side of the parent order.
+    /// * `qty` - The quantity of the parent order.
+    /// * `participation_rate` - The target fraction of the market trading volume, such as `0.1`
+    ///   for 10%.
+    pub fn new(asset_no: usize, side: Side, qty: f64, participation_rate: f64) -> Self {
+        Self {
+            parent: ParentOrder::new(asset_no, side, qty),
+            participation_rate,
+            pricing: ChildPricing::Passive,
+            market_volume: 0.0,
+            cursor: TradeCursor::default(),
+        }
+    }
+
+    /// Sets the limit price beyond which no child order is placed.
+    pub fn limit_price(mut self, limit_price: f64) -> Self {
+        self.parent.limit_price = Some(limit_price);
+        self
+    }
+
+    /// Sets the [`ChildPricing`] of the child orders. The default value is
+    /// [`ChildPricing::Passive`].
+    pub fn pricing(self, pricing: ChildPricing) -> Self {
+        Self { pricing, ..self }
+    }
+
+    /// Sets the [`TimeInForce`] of the passive child orders. The default value is
+    /// [`TimeInForce::GTC`].
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.parent.time_in_force = time_in_force;
+        self
+    }
+
+    /// Returns the market trading volume counted so far.
+    pub fn market_volume(&self) -> f64 {
+        self.market_volume
+    }
+
+    fn target(&self) -> f64 {
+        (self.market_volume * self.participation_rate).min(self.parent.qty)
+    }
+}
+
+impl ExecutionAlgo for Pov {
+    fn update<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let working = self.parent.track(bot);
+        self.market_volume += self.cursor.advance(bot.last_trades(self.parent.asset_no));
+        if self.parent.stopped || self.parent.remaining() == 0.0 {
+            return Ok(());
+        }
+        if working {
+            let price = self
+                .parent
+                .child_price(bot.depth(self.parent.asset_no), self.pricing);
+            if price.is_some() && price != self.parent.working_price() {
+                self.parent.cancel_child(bot)?;
+            }
+            return Ok(());
+        }
+        let qty = self.target() - self.parent.filled_qty();
+        if qty >= bot.depth(self.parent.asset_no).lot_size() {
+            self.parent.submit_child(bot, qty, self.pricing)?;
+        }
+        Ok(())
+    }
+
+    fn cancel<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        self.parent.stopped = true;
+        self.parent.cancel_child(bot)
+    }
+
+    fn is_done(&self) -> bool {
+        self.parent.is_done()
+    }
+
+    fn report(&self) -> ExecutionReport {
+        self.parent.report()
+    }
+}
+
+/// Remembers how far the market trade buffer has been counted.
+#[derive(Clone, Debug, Default)]
+struct TradeCursor {
+    len: usize,
+    first: Option<(i64, f64, f64)>,
+}
+
+impl TradeCursor {
+    /// Returns the quantity of the trades appended since the last call.
+    fn advance(&mut self, trades: &[Event]) -> f64 {
+        let first = trades.first().map(|ev| (ev.exch_ts, ev.px, ev.qty));
+        if first != self.first || trades.len() < self.len {
+            // The buffer has been cleared, so every trade in it is new.
+            self.len = 0;
+            self.first = first;
+        }
+        let qty = trades[self.len..].iter().map(|ev| ev.qty).sum();
+        self.len = trades.len();
+        qty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pov, TradeCursor};
+    use crate::types::{Event, Side};
+
+    fn trade(exch_ts: i64, qty: f64) -> Event {
+        Event {
+            ev: 0,
+            exch_ts,
+            local_ts: exch_ts,
+            px: 100.0,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_pov_trade_cursor() {
+        let mut cursor = TradeCursor::default();
+        let mut trades = vec![trade(1, 1.0), trade(2, 2.0)];
+        assert_eq!(cursor.advance(&trades), 3.0);
+        assert_eq!(cursor.advance(&trades), 0.0);
+        trades.push(trade(3, 4.0));
+        assert_eq!(cursor.advance(&trades), 4.0);
+
+        // Cleared and refilled with more trades than before.
+        let trades = vec![trade(4, 1.0), trade(5, 1.0), trade(6, 1.0), trade(7, 1.0)];
+        assert_eq!(cursor.advance(&trades), 4.0);
+        assert_eq!(cursor.advance(&[]), 0.0);
+
+        let mut pov = Pov::new(0, Side::Buy, 10.0, 0.1);
+        pov.market_volume = 50.0;
+        assert_eq!(pov.target(), 5.0);
+        pov.market_volume = 500.0;
+        assert_eq!(pov.target(), 10.0);
+    }
+}
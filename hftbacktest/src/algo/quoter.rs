@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use super::has_bbo;
+use crate::{
+    depth::MarketDepth,
+    types::{Bot, OrdType, OrderId, Side, TimeInForce},
+};
+
+/// Maintains a two-sided ladder of quotes around a reference price, skewed by the inventory.
+///
+/// On every [`update`](Quoter::update), the desired ladder is computed and diffed against the
+/// working orders placed by the quoter, so that only the quotes that moved are canceled and only
+/// the missing quotes are submitted. The quotes are identified by their price in ticks, and a
+/// quote that is partially filled stays in place until it moves.
+///
+/// The reservation price is the reference price shifted by `-skew * position / order_qty`, so
+/// the quotes lean towards reducing the inventory. Level `i` of the bid side is placed at
+/// `reservation - half_spread - i * level_spacing`, rounded down to the tick size and capped at
+/// the best bid, and the ask side mirrors it. The side that would increase the position beyond
+/// the maximum position is not quoted.
+#[derive(Clone, Debug)]
+pub struct Quoter {
+    asset_no: usize,
+    order_qty: f64,
+    levels: usize,
+    half_spread: f64,
+    level_spacing: f64,
+    skew: f64,
+    max_position: f64,
+    time_in_force: TimeInForce,
+    working: HashMap<OrderId, (Side, i64)>,
+}
+
+impl Quoter {
+    /// Constructs a `Quoter`.
+    ///
+    /// * `asset_no` - Asset number to quote.
+    /// * `order_qty` - The quantity of each quote.
+    /// * `levels` - The number of quotes on each side.
+    pub fn new(asset_no: usize, order_qty: f64, levels: usize) -> Self {
+        Self {
+            asset_no,
+            order_qty,
+            levels,
+            half_spread: 0.0,
+            level_spacing: 0.0,
+            skew: 0.0,
+            max_position: f64::INFINITY,
+            time_in_force: TimeInForce::GTX,
+            working: HashMap::new(),
+        }
+    }
+
+    /// Sets the distance of the first level from the reservation price, in price. The default
+    /// value is `0`, which joins the best bid and ask.
+    pub fn half_spread(self, half_spread: f64) -> Self {
+        Self {
+            half_spread,
+            ..self
+        }
+    }
+
+    /// Sets the distance between the levels, in price. It is at least one tick. The default value
+    /// is `0`, which places the levels one tick apart.
+    pub fn level_spacing(self, level_spacing: f64) -> Self {
+        Self {
+            level_spacing,
+            ..self
+        }
+    }
+
+    /// Sets how far the reservation price is shifted per order quantity of the position, in
+    /// price. The default value is `0`.
+    pub fn skew(self, skew: f64) -> Self {
+        Self { skew, ..self }
+    }
+
+    /// Sets the maximum absolute position. The default value is unlimited.
+    pub fn max_position(self, max_position: f64) -> Self {
+        Self {
+            max_position,
+            ..self
+        }
+    }
+
+    /// Sets the [`TimeInForce`] of the quotes. The default value is [`TimeInForce::GTX`].
+    pub fn time_in_force(self, time_in_force: TimeInForce) -> Self {
+        Self {
+            time_in_force,
+            ..self
+        }
+    }
+
+    /// Returns the order IDs of the working quotes placed by this quoter.
+    pub fn order_ids(&self) -> impl Iterator<Item = &OrderId> {
+        self.working.keys()
+    }
+
+    /// Updates the ladder around the reference price, such as the mid price or a fair value
+    /// estimate. Nothing is quoted while the market depth is empty or the reference price is not
+    /// finite.
+    pub fn update<MD, I>(&mut self, bot: &mut I, reference_price: f64) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        self.track(bot);
+        let depth = bot.depth(self.asset_no);
+        let desired = if has_bbo(depth) && reference_price.is_finite() {
+            self.ladder(
+                reference_price,
+                bot.position(self.asset_no),
+                depth.tick_size(),
+                depth.best_bid_tick(),
+                depth.best_ask_tick(),
+            )
+        } else {
+            Vec::new()
+        };
+        let tick_size = depth.tick_size();
+
+        let working: Vec<_> = self
+            .working
+            .iter()
+            .map(|(&order_id, &(side, price_tick))| {
+                let cancellable = bot
+                    .orders(self.asset_no)
+                    .get(&order_id)
+                    .is_some_and(|order| order.cancellable());
+                (order_id, side, price_tick, cancellable)
+            })
+            .collect();
+        let (cancels, submits) = diff(&desired, working);
+        for order_id in cancels {
+            bot.cancel(self.asset_no, order_id, false)?;
+        }
+        for (side, price_tick) in submits {
+            let order_id = bot.next_order_id();
+            let price = price_tick as f64 * tick_size;
+            match side {
+                Side::Buy => bot.submit_buy_order(
+                    self.asset_no,
+                    order_id,
+                    price,
+                    self.order_qty,
+                    self.time_in_force,
+                    OrdType::Limit,
+                    false,
+                )?,
+                _ => bot.submit_sell_order(
+                    self.asset_no,
+                    order_id,
+                    price,
+                    self.order_qty,
+                    self.time_in_force,
+                    OrdType::Limit,
+                    false,
+                )?,
+            };
+            self.working.insert(order_id, (side, price_tick));
+        }
+        Ok(())
+    }
+
+    /// Cancels all working quotes placed by this quoter.
+    pub fn cancel_all<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        self.track(bot);
+        for order_id in self.working.keys() {
+            let cancellable = bot
+                .orders(self.asset_no)
+                .get(order_id)
+                .is_some_and(|order| order.cancellable());
+            if cancellable {
+                bot.cancel(self.asset_no, *order_id, false)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forgets the quotes that are no longer working.
+    fn track<MD, I>(&mut self, bot: &I)
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let orders = bot.orders(self.asset_no);
+        self.working.retain(|order_id, _| {
+            orders
+                .get(order_id)
+                .is_some_and(|order| order.active() || order.pending())
+        });
+    }
+
+    /// Returns the desired quotes as the side and the price in ticks.
+    fn ladder(
+        &self,
+        reference_price: f64,
+        position: f64,
+        tick_size: f64,
+        best_bid_tick: i64,
+        best_ask_tick: i64,
+    ) -> Vec<(Side, i64)> {
+        let reservation = reference_price - self.skew * position / self.order_qty;
+        let spacing = ((self.level_spacing / tick_size).round() as i64).max(1);
+        let mut desired = Vec::with_capacity(self.levels * 2);
+        if position < self.max_position {
+            let bid_tick =
+                (((reservation - self.half_spread) / tick_size).floor() as i64).min(best_bid_tick);
+            desired.extend((0..self.levels as i64).map(|i| (Side::Buy, bid_tick - i * spacing)));
+        }
+        if position > -self.max_position {
+            let ask_tick =
+                (((reservation + self.half_spread) / tick_size).ceil() as i64).max(best_ask_tick);
+            desired.extend((0..self.levels as i64).map(|i| (Side::Sell, ask_tick + i * spacing)));
+        }
+        desired
+    }
+}
+
+/// Diffs the desired quotes against the working orders, given as the order ID, side, price in
+/// ticks, and whether it can be canceled, and returns the orders to cancel and the quotes to
+/// submit. A working order covers its price even while its cancellation is pending, so that a
+/// quote isn't doubled when it moves back.
+fn diff(
+    desired: &[(Side, i64)],
+    mut working: Vec<(OrderId, Side, i64, bool)>,
+) -> (Vec<OrderId>, Vec<(Side, i64)>) {
+    working.sort_by_key(|&(order_id, ..)| order_id);
+    let mut covered = Vec::with_capacity(working.len());
+    let mut cancels = Vec::new();
+    for (order_id, side, price_tick, cancellable) in working {
+        let quote = (side, price_tick);
+        if desired.contains(&quote) && !covered.contains(&quote) {
+            covered.push(quote);
+        } else if cancellable {
+            cancels.push(order_id);
+        }
+    }
+    let submits = desired
+        .iter()
+        .filter(|quote| !covered.contains(quote))
+        .copied()
+        .collect();
+    (cancels, submits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, Quoter};
+    use crate::types::Side;
+
+    #[test]
+    fn test_quoter_ladder_and_diff() {
+        let quoter = Quoter::new(0, 1.0, 2)
+            .half_spread(0.5)
+            .level_spacing(1.0)
+            .skew(0.5)
+            .max_position(2.0);
+        // Long twice the order quantity, so the reservation price is 99.0 and the bid side isn't quoted.
+        assert_eq!(
+            quoter.ladder(100.0, 2.0, 0.5, 199, 201),
+            vec![(Side::Sell, 201), (Side::Sell, 203)]
+        );
+        // The ask side is capped at the best ask.
+        assert_eq!(
+            quoter.ladder(100.0, -1.0, 0.5, 199, 203),
+            vec![
+                (Side::Buy, 199),
+                (Side::Buy, 197),
+                (Side::Sell, 203),
+                (Side::Sell, 205)
+            ]
+        );
+
+        let desired = [(Side::Buy, 199), (Side::Buy, 197), (Side::Sell, 203)];
+        let working = vec![
+            (1, Side::Buy, 199, true),
+            (2, Side::Buy, 198, true),
+            (3, Side::Buy, 197, false),
+            (4, Side::Buy, 199, true),
+            (5, Side::Sell, 204, false),
+        ];
+        let (cancels, submits) = diff(&desired, working);
+        assert_eq!(cancels, vec![2, 4]);
+        assert_eq!(submits, vec![(Side::Sell, 203)]);
+    }
+}
@@ -0,0 +1,382 @@
+use super::has_bbo;
+use crate::{
+    depth::MarketDepth,
+    types::{Bot, OrdType, OrderId, Side, TimeInForce},
+};
+
+/// Determines how [`Spread`] handles the imbalance between the filled quantities of its legs,
+/// which leaves an unhedged exposure on one leg.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImbalancePolicy {
+    /// The imbalance is only tracked and left to the strategy.
+    #[default]
+    None,
+    /// The lagging leg is completed by a taker order.
+    Complete,
+    /// The excess of the leading leg is unwound by a taker order.
+    Unwind,
+}
+
+#[derive(Clone, Debug)]
+struct LegOrder {
+    leg: usize,
+    order_id: OrderId,
+    side: Side,
+    exec_qty: f64,
+}
+
+/// Trades a ratio spread across two instruments, such as a pair or a basis, through any [`Bot`],
+/// so the same code works in backtesting and live trading.
+///
+/// One unit of the spread is long one unit of leg A and short `ratio` units of leg B, so its
+/// price is `price_a - ratio * price_b`. Buying the spread buys leg A and sells leg B, and selling
+/// does the opposite.
+///
+/// [`update`](Spread::update) should be called on every iteration of the event loop, before the
+/// inactive orders are cleared, to track the leg fills and handle the fill imbalance according to
+/// the [`ImbalancePolicy`].
+#[derive(Clone, Debug)]
+pub struct Spread {
+    assets: [usize; 2],
+    ratio: f64,
+    time_in_force: TimeInForce,
+    order_type: OrdType,
+    policy: ImbalancePolicy,
+    tolerance: f64,
+    timeout: i64,
+    orders: Vec<LegOrder>,
+    filled: [f64; 2],
+    imbalanced_since: Option<i64>,
+}
+
+impl Spread {
+    /// Constructs a `Spread`.
+    ///
+    /// * `asset_a` - Asset number of leg A.
+    /// * `asset_b` - Asset number of leg B.
+    /// * `ratio` - The quantity of leg B per unit of leg A.
+    pub fn new(asset_a: usize, asset_b: usize, ratio: f64) -> Self {
+        Self {
+            assets: [asset_a, asset_b],
+            ratio,
+            time_in_force: TimeInForce::GTC,
+            order_type: OrdType::Limit,
+            policy: ImbalancePolicy::None,
+            tolerance: 0.0,
+            timeout: 0,
+            orders: Vec::new(),
+            filled: [0.0; 2],
+            imbalanced_since: None,
+        }
+    }
+
+    /// Sets the [`TimeInForce`] of the leg orders. The default value is [`TimeInForce::GTC`].
+    pub fn time_in_force(self, time_in_force: TimeInForce) -> Self {
+        Self {
+            time_in_force,
+            ..self
+        }
+    }
+
+    /// Sets the [`OrdType`] of the leg orders. The default value is [`OrdType::Limit`].
+    pub fn order_type(self, order_type: OrdType) -> Self {
+        Self { order_type, ..self }
+    }
+
+    /// Sets how the fill imbalance is handled. The default value is [`ImbalancePolicy::None`].
+    ///
+    /// * `policy` - The [`ImbalancePolicy`].
+    /// * `tolerance` - The imbalance, in units of the spread, that is tolerated without any action.
+    /// * `timeout` - How long the imbalance may last while the leg orders are still working. Once
+    ///   it elapses, the working orders are canceled and the imbalance is handled. The unit should
+    ///   be the same as the timestamp unit.
+    pub fn imbalance_policy(self, policy: ImbalancePolicy, tolerance: f64, timeout: i64) -> Self {
+        Self {
+            policy,
+            tolerance,
+            timeout,
+            ..self
+        }
+    }
+
+    /// Returns the mid price of the spread, or `None` if either market depth is empty.
+    pub fn mid<MD, I>(&self, bot: &I) -> Option<f64>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let (bid, ask) = self.bid_ask(bot)?;
+        Some((bid + ask) / 2.0)
+    }
+
+    /// Returns the prices at which the spread can be sold and bought by taking the best prices of
+    /// the legs, or `None` if either market depth is empty.
+    pub fn bid_ask<MD, I>(&self, bot: &I) -> Option<(f64, f64)>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let depth_a = bot.depth(self.assets[0]);
+        let depth_b = bot.depth(self.assets[1]);
+        if !has_bbo(depth_a) || !has_bbo(depth_b) {
+            return None;
+        }
+        Some((
+            depth_a.best_bid() - self.ratio * depth_b.best_ask(),
+            depth_a.best_ask() - self.ratio * depth_b.best_bid(),
+        ))
+    }
+
+    /// Submits the orders of both legs.
+    ///
+    /// * `side` - [`Side::Buy`] buys the spread, and [`Side::Sell`] sells it.
+    /// * `qty` - The quantity in units of the spread, which is the quantity of leg A. The quantity
+    ///   of leg B is rounded to its lot size.
+    /// * `price_a` - The order price of leg A.
+    /// * `price_b` - The order price of leg B.
+    pub fn submit<MD, I>(
+        &mut self,
+        bot: &mut I,
+        side: Side,
+        qty: f64,
+        price_a: f64,
+        price_b: f64,
+    ) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let qty_b = round_lot(qty * self.ratio, bot.depth(self.assets[1]).lot_size());
+        self.submit_leg(bot, 0, side, price_a, qty, self.time_in_force)?;
+        self.submit_leg(bot, 1, opposite(side), price_b, qty_b, self.time_in_force)
+    }
+
+    /// Cancels the working orders of both legs.
+    pub fn cancel<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        for leg_order in &self.orders {
+            let asset_no = self.assets[leg_order.leg];
+            let cancellable = bot
+                .orders(asset_no)
+                .get(&leg_order.order_id)
+                .is_some_and(|order| order.cancellable());
+            if cancellable {
+                bot.cancel(asset_no, leg_order.order_id, false)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Tracks the leg fills and handles the fill imbalance according to the
+    /// [`ImbalancePolicy`].
+    pub fn update<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let mut working = false;
+        let assets = self.assets;
+        let filled = &mut self.filled;
+        self.orders.retain_mut(|leg_order| {
+            let Some(order) = bot.orders(assets[leg_order.leg]).get(&leg_order.order_id) else {
+                // The order has been cleared, so its last known fill is final.
+                return false;
+            };
+            let exec_qty = (order.qty - order.leaves_qty).max(0.0);
+            filled[leg_order.leg] +=
+                (exec_qty - leg_order.exec_qty) * AsRef::<f64>::as_ref(&leg_order.side);
+            leg_order.exec_qty = exec_qty;
+            let done = !order.active() && !order.pending();
+            working |= !done;
+            !done
+        });
+
+        if self.imbalance().abs() <= self.tolerance {
+            self.imbalanced_since = None;
+            return Ok(());
+        }
+        let now = bot.current_timestamp();
+        let since = *self.imbalanced_since.get_or_insert(now);
+        if self.policy == ImbalancePolicy::None {
+            return Ok(());
+        }
+        if working {
+            if now - since >= self.timeout {
+                self.cancel(bot)?;
+            }
+            return Ok(());
+        }
+        if let Some((leg, side, qty)) = self.correction() {
+            let depth = bot.depth(self.assets[leg]);
+            if !has_bbo(depth) {
+                return Ok(());
+            }
+            let qty = round_lot(qty, depth.lot_size());
+            if qty <= 0.0 {
+                return Ok(());
+            }
+            let price = match side {
+                Side::Buy => depth.best_ask(),
+                _ => depth.best_bid(),
+            };
+            self.submit_leg(bot, leg, side, price, qty, TimeInForce::IOC)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the filled quantities of leg A and leg B, where the sell quantity is negative.
+    pub fn filled(&self) -> (f64, f64) {
+        (self.filled[0], self.filled[1])
+    }
+
+    /// Returns the position in units of the spread that both legs have filled.
+    pub fn position(&self) -> f64 {
+        let (units_a, units_b) = self.units();
+        if units_a * units_b <= 0.0 {
+            0.0
+        } else {
+            units_a.signum() * units_a.abs().min(units_b.abs())
+        }
+    }
+
+    /// Returns the fill imbalance in units of the spread, which is positive if leg A has filled
+    /// more on the buy side than leg B has on the sell side, leaving a net long exposure.
+    pub fn imbalance(&self) -> f64 {
+        let (units_a, units_b) = self.units();
+        units_a - units_b
+    }
+
+    /// Returns the net value of the leg quantities that are not hedged by the other leg, valued at
+    /// the mid prices, or `None` if either market depth is empty.
+    pub fn leg_risk<MD, I>(&self, bot: &I) -> Option<f64>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let depth_a = bot.depth(self.assets[0]);
+        let depth_b = bot.depth(self.assets[1]);
+        if !has_bbo(depth_a) || !has_bbo(depth_b) {
+            return None;
+        }
+        let (units_a, units_b) = self.units();
+        let matched = self.position();
+        let mid_a = (depth_a.best_bid() + depth_a.best_ask()) / 2.0;
+        let mid_b = (depth_b.best_bid() + depth_b.best_ask()) / 2.0;
+        Some((units_a - matched) * mid_a - self.ratio * (units_b - matched) * mid_b)
+    }
+
+    /// Returns `true` if no leg order is working.
+    pub fn is_done(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// Returns the filled quantities of the legs in units of the spread.
+    fn units(&self) -> (f64, f64) {
+        (self.filled[0], -self.filled[1] / self.ratio)
+    }
+
+    /// Returns the leg, side, and quantity of the order that resolves the imbalance according to
+    /// the policy.
+    fn correction(&self) -> Option<(usize, Side, f64)> {
+        let imbalance = self.imbalance();
+        match self.policy {
+            ImbalancePolicy::None => None,
+            // Leg B follows leg A.
+            ImbalancePolicy::Complete if imbalance > 0.0 => {
+                Some((1, Side::Sell, imbalance * self.ratio))
+            }
+            ImbalancePolicy::Complete => Some((1, Side::Buy, -imbalance * self.ratio)),
+            // Leg A follows leg B.
+            ImbalancePolicy::Unwind if imbalance > 0.0 => Some((0, Side::Sell, imbalance)),
+            ImbalancePolicy::Unwind => Some((0, Side::Buy, -imbalance)),
+        }
+    }
+
+    fn submit_leg<MD, I>(
+        &mut self,
+        bot: &mut I,
+        leg: usize,
+        side: Side,
+        price: f64,
+        qty: f64,
+        time_in_force: TimeInForce,
+    ) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let order_id = bot.next_order_id();
+        let asset_no = self.assets[leg];
+        match side {
+            Side::Buy => bot.submit_buy_order(
+                asset_no,
+                order_id,
+                price,
+                qty,
+                time_in_force,
+                self.order_type,
+                false,
+            )?,
+            _ => bot.submit_sell_order(
+                asset_no,
+                order_id,
+                price,
+                qty,
+                time_in_force,
+                self.order_type,
+                false,
+            )?,
+        };
+        self.orders.push(LegOrder {
+            leg,
+            order_id,
+            side,
+            exec_qty: 0.0,
+        });
+        Ok(())
+    }
+}
+
+fn opposite(side: Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        _ => Side::Buy,
+    }
+}
+
+fn round_lot(qty: f64, lot_size: f64) -> f64 {
+    (qty / lot_size).round() * lot_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImbalancePolicy, Spread};
+    use crate::types::Side;
+
+    #[test]
+    fn test_spread_imbalance() {
+        let mut spread = Spread::new(0, 1, 2.0);
+        // Bought 3 of leg A and sold 4 of leg B, so 2 units are hedged and 1 unit of leg A isn't.
+        spread.filled = [3.0, -4.0];
+        assert_eq!(spread.position(), 2.0);
+        assert_eq!(spread.imbalance(), 1.0);
+        assert_eq!(spread.correction(), None);
+
+        let spread = spread.imbalance_policy(ImbalancePolicy::Complete, 0.0, 0);
+        assert_eq!(spread.correction(), Some((1, Side::Sell, 2.0)));
+        let mut spread = spread.imbalance_policy(ImbalancePolicy::Unwind, 0.0, 0);
+        assert_eq!(spread.correction(), Some((0, Side::Sell, 1.0)));
+
+        // Sold 1 of leg A and bought 6 of leg B.
+        spread.filled = [-1.0, 6.0];
+        assert_eq!(spread.position(), -1.0);
+        assert_eq!(spread.imbalance(), 2.0);
+        spread.filled = [-3.0, 2.0];
+        assert_eq!(spread.position(), -1.0);
+        assert_eq!(spread.imbalance(), -2.0);
+        assert_eq!(spread.correction(), Some((0, Side::Buy, 2.0)));
+    }
+}
@@ -0,0 +1,157 @@
+use super::execution::{ChildPricing, ExecutionAlgo, ExecutionReport, ParentOrder};
+use crate::{
+    depth::MarketDepth,
+    types::{Bot, Side, TimeInForce},
+};
+
+/// Time-weighted average price execution, which fills the parent order evenly over the duration.
+///
+/// The duration is divided into `num_slices` intervals, and by the end of each interval, the
+/// cumulative target is the proportional share of the parent order. At the start of each
+/// interval, the unfilled child order is canceled and a new child order is placed for the
+/// shortfall against the target. Once the duration has elapsed, the remaining quantity is taken
+/// aggressively unless disabled.
+#[derive(Clone, Debug)]
+pub struct Twap {
+    parent: ParentOrder,
+    duration: i64,
+    num_slices: u32,
+    pricing: ChildPricing,
+    complete_at_end: bool,
+    start: Option<i64>,
+    slice: Option<u32>,
+}
+
+impl Twap {
+    /// Constructs a `Twap`. The execution starts at the first [`update`](ExecutionAlgo::update).
+    ///
+    /// * `asset_no` - Asset number to trade.
+    /// * `side` - The side of the parent order.
+    /// * `qty` - The quantity of the parent order.
+    /// * `duration` - The duration over which the parent order is executed, in the same unit as
+    ///   the timestamps.
+    /// * `num_slices` - The number of intervals into which the duration is divided.
+    pub fn new(asset_no: usize, side: Side, qty: f64, duration: i64, num_slices: u32) -> Self {
+        Self {
+            parent: ParentOrder::new(asset_no, side, qty),
+            duration,
+            num_slices: num_slices.max(1),
+            pricing: ChildPricing::Passive,
+            complete_at_end: true,
+            start: None,
+            slice: None,
+        }
+    }
+
+    /// Sets the limit price beyond which no child order is placed.
+    pub fn limit_price(mut self, limit_price: f64) -> Self {
+        self.parent.limit_price = Some(limit_price);
+        self
+    }
+
+    /// Sets the [`ChildPricing`] of the scheduled child orders. The default value is
+    /// [`ChildPricing::Passive`].
+    pub fn pricing(self, pricing: ChildPricing) -> Self {
+        Self { pricing, ..self }
+    }
+
+    /// Sets the [`TimeInForce`] of the passive child orders. The default value is
+    /// [`TimeInForce::GTC`].
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.parent.time_in_force = time_in_force;
+        self
+    }
+
+    /// Sets whether the remaining quantity is taken aggressively once the duration has elapsed.
+    /// The default value is `true`.
+    pub fn complete_at_end(self, complete_at_end: bool) -> Self {
+        Self {
+            complete_at_end,
+            ..self
+        }
+    }
+
+    /// Returns the slice in progress and the cumulative target quantity at the timestamp.
+    fn target(&self, start: i64, timestamp: i64) -> (u32, f64) {
+        let elapsed = (timestamp - start).max(0) as i128;
+        let slice = (elapsed * self.num_slices as i128 / self.duration.max(1) as i128)
+            .min(self.num_slices as i128) as u32;
+        let target =
+            self.parent.qty * (slice + 1).min(self.num_slices) as f64 / self.num_slices as f64;
+        (slice, target)
+    }
+}
+
+impl ExecutionAlgo for Twap {
+    fn update<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let working = self.parent.track(bot);
+        if self.parent.stopped || self.parent.remaining() == 0.0 {
+            return Ok(());
+        }
+        let now = bot.current_timestamp();
+        let start = *self.start.get_or_insert(now);
+        let (slice, target) = self.target(start, now);
+        let ended = slice == self.num_slices;
+        if self.slice != Some(slice) {
+            if working {
+                // Reslices the unfilled quantity.
+                return self.parent.cancel_child(bot);
+            }
+            self.slice = Some(slice);
+            let pricing = if ended && self.complete_at_end {
+                ChildPricing::Aggressive
+            } else {
+                self.pricing
+            };
+            let qty = target - self.parent.filled_qty();
+            return self.parent.submit_child(bot, qty, pricing);
+        }
+        if ended && self.complete_at_end && !working {
+            // Takes what the previous aggressive child order left.
+            return self.parent.submit_child(
+                bot,
+                self.parent.remaining(),
+                ChildPricing::Aggressive,
+            );
+        }
+        Ok(())
+    }
+
+    fn cancel<MD, I>(&mut self, bot: &mut I) -> Result<(), I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        self.parent.stopped = true;
+        self.parent.cancel_child(bot)
+    }
+
+    fn is_done(&self) -> bool {
+        self.parent.is_done()
+    }
+
+    fn report(&self) -> ExecutionReport {
+        self.parent.report()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Twap;
+    use crate::types::Side;
+
+    #[test]
+    fn test_twap_target() {
+        let twap = Twap::new(0, Side::Buy, 10.0, 1_000, 4);
+        assert_eq!(twap.target(100, 100), (0, 2.5));
+        assert_eq!(twap.target(100, 349), (0, 2.5));
+        assert_eq!(twap.target(100, 350), (1, 5.0));
+        assert_eq!(twap.target(100, 1_099), (3, 10.0));
+        assert_eq!(twap.target(100, 1_100), (4, 10.0));
+        assert_eq!(twap.target(100, 5_000), (4, 10.0));
+    }
+}
@@ -5,6 +5,13 @@ pub trait AssetType {
 
     /// Calculates the equity.
     fn equity(&self, price: f64, balance: f64, position: f64, fee: f64) -> f64;
+
+    /// Calculates the value of the position in the settlement currency.
+    fn position_value(&self, price: f64, position: f64) -> f64;
+
+    /// Changes the contract size, such as on a re-denomination of the instrument. By default, the
+    /// asset type has no contract size and ignores it.
+    fn set_contract_size(&mut self, _contract_size: f64) {}
 }
 
 /// The common type of asset where the contract's notional value is linear to the quote currency.
@@ -28,9 +35,20 @@ impl AssetType for LinearAsset {
     fn equity(&self, price: f64, balance: f64, position: f64, fee: f64) -> f64 {
         balance + self.contract_size * position * price - fee
     }
+
+    fn position_value(&self, price: f64, position: f64) -> f64 {
+        self.contract_size * position * price
+    }
+
+    fn set_contract_size(&mut self, contract_size: f64) {
+        self.contract_size = contract_size;
+    }
 }
 
-/// The contract’s notional value is denominated in the quote currency.
+/// The contract’s notional value is denominated in the quote currency, while the contract is
+/// margined and settled in the base currency, such as coin-margined BTCUSD perpetuals. The amount,
+/// fees, and equity are calculated in the base currency, so the PnL is non-linear to the price:
+/// `contract_size * position * (1 / entry_price - 1 / price)`.
 #[derive(Clone)]
 pub struct InverseAsset {
     contract_size: f64,
@@ -51,4 +69,78 @@ impl AssetType for InverseAsset {
     fn equity(&self, price: f64, balance: f64, position: f64, fee: f64) -> f64 {
         -balance - self.contract_size * position / price - fee
     }
+
+    fn position_value(&self, price: f64, position: f64) -> f64 {
+        self.contract_size * position / price
+    }
+
+    fn set_contract_size(&mut self, contract_size: f64) {
+        self.contract_size = contract_size;
+    }
+}
+
+/// The contract is settled in a currency other than the quote currency, such as ETHUSD
+/// perpetuals settled in BTC. The PnL is linear to the price, but converted into the settlement
+/// currency at the fixed `multiplier`. The amount, fees, and equity are calculated in the
+/// settlement currency.
+#[derive(Clone)]
+pub struct QuantoAsset {
+    contract_size: f64,
+    multiplier: f64,
+}
+
+impl QuantoAsset {
+    /// Constructs an instance of `QuantoAsset`.
+    ///
+    /// * `contract_size` - The contract size in the base currency.
+    /// * `multiplier` - The value of one unit of the quote currency in the settlement currency.
+    pub fn new(contract_size: f64, multiplier: f64) -> Self {
+        Self {
+            contract_size,
+            multiplier,
+        }
+    }
+}
+
+impl AssetType for QuantoAsset {
+    fn amount(&self, exec_price: f64, qty: f64) -> f64 {
+        self.multiplier * self.contract_size * exec_price * qty
+    }
+
+    fn equity(&self, price: f64, balance: f64, position: f64, fee: f64) -> f64 {
+        balance + self.multiplier * self.contract_size * position * price - fee
+    }
+
+    fn position_value(&self, price: f64, position: f64) -> f64 {
+        self.multiplier * self.contract_size * position * price
+    }
+
+    fn set_contract_size(&mut self, contract_size: f64) {
+        self.contract_size = contract_size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetType, InverseAsset, QuantoAsset};
+
+    #[test]
+    fn test_inverse_asset_pnl() {
+        let asset = InverseAsset::new(100.0);
+        // Buys 10 contracts at 50,000 and marks to 40,000.
+        let balance = -asset.amount(50_000.0, 10.0);
+        let equity = asset.equity(40_000.0, balance, 10.0, 0.0);
+        let expected = 100.0 * 10.0 * (1.0 / 50_000.0 - 1.0 / 40_000.0);
+        assert!((equity - expected).abs() < 1e-12);
+        assert!((asset.position_value(40_000.0, 10.0) - 0.025).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_quanto_asset_pnl() {
+        let asset = QuantoAsset::new(1.0, 0.000001);
+        // Buys 10 contracts at 2,000 and marks to 2,100.
+        let balance = -asset.amount(2_000.0, 10.0);
+        let equity = asset.equity(2_100.0, balance, 10.0, 0.0);
+        assert!((equity - 0.001).abs() < 1e-12);
+    }
 }
@@ -0,0 +1,132 @@
+/// The trading sessions and halts of an exchange with trading hours, such as CME or Eurex, as
+/// opposed to the 24/7 crypto exchanges.
+///
+/// The exchange rejects new orders outside the sessions and during the halts, while the cancels
+/// are still accepted. The orders resting at the close are kept. The recorded state values can be
+/// summarized per session by
+/// [`BacktestRecorder::session_summary`](crate::backtest::recorder::BacktestRecorder::session_summary).
+///
+/// ```
+/// use hftbacktest::backtest::calendar::SessionCalendar;
+///
+/// const HOUR: i64 = 3_600_000_000_000;
+///
+/// // Five sessions from 08:00 to 22:00 every day, starting from midnight of the first day, with a
+/// // halt from 12:00 to 12:05 on the first day.
+/// let calendar = SessionCalendar::new()
+///     .recurring(8 * HOUR, 14 * HOUR, 24 * HOUR, 5)
+///     .halt(12 * HOUR, 12 * HOUR + HOUR / 12);
+/// assert!(calendar.is_open(9 * HOUR));
+/// assert!(!calendar.is_open(12 * HOUR));
+/// assert!(!calendar.is_open(23 * HOUR));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SessionCalendar {
+    sessions: Vec<(i64, i64)>,
+    halts: Vec<(i64, i64)>,
+}
+
+impl SessionCalendar {
+    /// Constructs `SessionCalendar` without any sessions.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a session from `open` until `close`.
+    pub fn session(mut self, open: i64, close: i64) -> Self {
+        assert!(open < close);
+        self.sessions.push((open, close));
+        self.sessions.sort();
+        self
+    }
+
+    /// Adds `count` sessions of the `duration`, repeating every `interval` from `open`.
+    pub fn recurring(mut self, open: i64, duration: i64, interval: i64, count: usize) -> Self {
+        assert!(duration > 0 && interval >= duration);
+        for i in 0..count as i64 {
+            let open = open + i * interval;
+            self.sessions.push((open, open + duration));
+        }
+        self.sessions.sort();
+        self
+    }
+
+    /// Adds a trading halt from `start` until `end`.
+    pub fn halt(mut self, start: i64, end: i64) -> Self {
+        assert!(start < end);
+        self.halts.push((start, end));
+        self.halts.sort();
+        self
+    }
+
+    /// Returns the sessions in chronological order.
+    pub fn sessions(&self) -> &[(i64, i64)] {
+        &self.sessions
+    }
+
+    /// Returns the session the timestamp falls in, regardless of the halts.
+    pub fn session_at(&self, timestamp: i64) -> Option<(i64, i64)> {
+        find(&self.sessions, timestamp)
+    }
+
+    /// Returns `true` if the timestamp is within a session and not within a halt.
+    pub fn is_open(&self, timestamp: i64) -> bool {
+        self.session_at(timestamp).is_some() && find(&self.halts, timestamp).is_none()
+    }
+}
+
+/// The trading results of a session, computed from the recorded state values by
+/// [`BacktestRecorder::session_summary`](crate::backtest::recorder::BacktestRecorder::session_summary).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionSummary {
+    pub open: i64,
+    pub close: i64,
+    /// The change in equity from the last record at or before the open, or from the first record
+    /// in the session if there's none, until the last record in the session.
+    pub pnl: f64,
+    /// The fees paid during the session.
+    pub fee: f64,
+    pub num_trades: i64,
+    pub trading_volume: f64,
+    pub trading_value: f64,
+    /// The position at the last record in the session.
+    pub closing_position: f64,
+    /// The largest absolute position recorded during the session.
+    pub max_position: f64,
+}
+
+/// Finds the window containing the timestamp among the sorted windows.
+fn find(windows: &[(i64, i64)], timestamp: i64) -> Option<(i64, i64)> {
+    let i = windows.partition_point(|&(start, _)| start <= timestamp);
+    windows[..i]
+        .iter()
+        .rev()
+        .find(|&&(_, end)| timestamp < end)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SessionCalendar;
+
+    #[test]
+    fn test_sessions_and_halts() {
+        let calendar = SessionCalendar::new()
+            .recurring(100, 50, 100, 3)
+            .session(10, 20)
+            .halt(120, 130);
+
+        assert_eq!(
+            calendar.sessions(),
+            &[(10, 20), (100, 150), (200, 250), (300, 350)]
+        );
+        assert_eq!(calendar.session_at(15), Some((10, 20)));
+        assert_eq!(calendar.session_at(20), None);
+        assert_eq!(calendar.session_at(125), Some((100, 150)));
+        assert!(calendar.is_open(100));
+        assert!(!calendar.is_open(125));
+        assert!(calendar.is_open(130));
+        assert!(!calendar.is_open(150));
+        assert!(!calendar.is_open(360));
+    }
+}
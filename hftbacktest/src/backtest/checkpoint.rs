@@ -0,0 +1,147 @@
+use std::io::{Error as IoError, ErrorKind};
+
+use bincode::{config, Decode, Encode};
+
+use crate::{
+    backtest::{
+        data::{Data, Reader},
+        BacktestError,
+    },
+    depth::ApplySnapshot,
+    types::{Event, Order},
+};
+
+const MAGIC: &[u8; 8] = b"HBTCKPT1";
+
+/// Encodes the state of a backtest into a checkpoint. Each component writes its state in order,
+/// and reads it back in the same order through [`CheckpointReader`].
+pub struct CheckpointWriter {
+    buf: Vec<u8>,
+}
+
+impl CheckpointWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            buf: MAGIC.to_vec(),
+        }
+    }
+
+    /// Writes the value into the checkpoint.
+    pub fn write<T: Encode>(&mut self, value: &T) -> Result<(), BacktestError> {
+        bincode::encode_into_std_write(value, &mut self.buf, config::standard())
+            .map_err(|err| checkpoint_error(err.to_string()))?;
+        Ok(())
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Decodes the state of a backtest from a checkpoint written by [`CheckpointWriter`].
+pub struct CheckpointReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> CheckpointReader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Result<Self, BacktestError> {
+        match buf.strip_prefix(MAGIC) {
+            Some(buf) => Ok(Self { buf }),
+            None => Err(checkpoint_error("not a checkpoint")),
+        }
+    }
+
+    /// Reads the next value from the checkpoint.
+    pub fn read<T: Decode<()>>(&mut self) -> Result<T, BacktestError> {
+        let (value, len) = bincode::decode_from_slice(self.buf, config::standard())
+            .map_err(|err| checkpoint_error(err.to_string()))?;
+        self.buf = &self.buf[len..];
+        Ok(value)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+/// Returns the error for a checkpoint that cannot be written or restored.
+pub fn checkpoint_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+    error: E,
+) -> BacktestError {
+    BacktestError::DataError(IoError::new(ErrorKind::InvalidData, error))
+}
+
+/// Writes the position in the feed data and the market depth into the checkpoint.
+pub(crate) fn save_feed<MD>(
+    writer: &mut CheckpointWriter,
+    reader: &Reader<Event>,
+    row_num: usize,
+    depth: &MD,
+) -> Result<(), BacktestError>
+where
+    MD: ApplySnapshot,
+{
+    writer.write(&(reader.data_num(), row_num))?;
+    writer.write(&depth.snapshot())
+}
+
+/// Restores the market depth from the checkpoint and reloads the feed data at the saved position,
+/// returning the data and the row number to resume from.
+pub(crate) fn load_feed<MD>(
+    checkpoint: &mut CheckpointReader,
+    reader: &mut Reader<Event>,
+    depth: &mut MD,
+) -> Result<(Data<Event>, usize), BacktestError>
+where
+    MD: ApplySnapshot,
+{
+    let (data_num, row_num): (usize, usize) = checkpoint.read()?;
+    let snapshot: Vec<Event> = checkpoint.read()?;
+    depth.apply_snapshot(&Data::from_data(&snapshot));
+    if data_num == 0 {
+        return Ok((Data::empty(), row_num));
+    }
+    reader.seek(data_num - 1);
+    let data = reader.next_data()?;
+    if row_num >= data.len() {
+        return Err(checkpoint_error("the data doesn't match the checkpoint"));
+    }
+    Ok((data, row_num))
+}
+
+/// Writes the orders into the checkpoint, each followed by the state written by `save_q`, such as
+/// the queue position.
+pub(crate) fn save_orders<'a, I, F>(
+    writer: &mut CheckpointWriter,
+    orders: I,
+    mut save_q: F,
+) -> Result<(), BacktestError>
+where
+    I: ExactSizeIterator<Item = &'a Order>,
+    F: FnMut(&Order, &mut CheckpointWriter) -> Result<(), BacktestError>,
+{
+    writer.write(&orders.len())?;
+    for order in orders {
+        writer.write(order)?;
+        save_q(order, writer)?;
+    }
+    Ok(())
+}
+
+/// Restores the orders written by [`save_orders`].
+pub(crate) fn load_orders<F>(
+    checkpoint: &mut CheckpointReader,
+    mut load_q: F,
+) -> Result<Vec<Order>, BacktestError>
+where
+    F: FnMut(&mut Order, &mut CheckpointReader) -> Result<(), BacktestError>,
+{
+    let len: usize = checkpoint.read()?;
+    let mut orders = Vec::with_capacity(len);
+    for _ in 0..len {
+        let mut order: Order = checkpoint.read()?;
+        load_q(&mut order, checkpoint)?;
+        orders.push(order);
+    }
+    Ok(orders)
+}
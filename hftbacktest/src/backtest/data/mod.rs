@@ -1,17 +1,34 @@
 mod npy;
 mod reader;
+mod schema;
+pub mod synthetic;
+pub mod validate;
 
 use std::{
     marker::PhantomData,
-    mem::size_of,
+    mem::{size_of, size_of_val},
     ops::{Index, IndexMut},
     ptr::null_mut,
     rc::Rc,
     slice::SliceIndex,
+    sync::Arc,
 };
 
-pub use npy::{read_npy_file, read_npz_file, write_npy, Field, NpyDTyped, NpyHeader};
-pub use reader::{Cache, DataPreprocess, DataSource, FeedLatencyAdjustment, Reader, ReaderBuilder};
+#[cfg(feature = "fs")]
+pub use npy::{read_npy_file, read_npz_file, write_npy_file};
+pub use npy::{read_npy_stream, write_npy, write_npy_bytes, Field, NpyDTyped, NpyHeader};
+pub use reader::{
+    Cache,
+    DataPreprocess,
+    DataSource,
+    FeedLatencyAdjustment,
+    Reader,
+    ReaderBuilder,
+    TimeRange,
+};
+#[cfg(feature = "fs")]
+pub use schema::read_event_file;
+pub use schema::{read_event_stream, SchemaVersion};
 
 use crate::utils::{AlignedArray, CACHE_LINE_SIZE};
 
@@ -72,6 +89,21 @@ where
         }
     }
 
+    /// Constructs `Data` by copying the given elements, which is useful for building a dataset in
+    /// memory, such as for testing.
+    pub fn from_data(data: &[D]) -> Self {
+        if data.is_empty() {
+            return Self::empty();
+        }
+        let size = size_of_val(data);
+        let ptr = DataPtr::new(size);
+        // DataPtr is aligned to the cache line, which satisfies the alignment of D.
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr() as *const u8, ptr.at(0) as *mut u8, size);
+            Self::from_data_ptr(ptr, 0)
+        }
+    }
+
     /// Returns a reference to an element, without doing bounds checking.
     ///
     /// # Safety
@@ -121,10 +153,116 @@ where
     }
 }
 
+/// A read-only buffer of [`Data`] that can be shared between threads, so that backtests running
+/// in parallel can read the same data without loading it per thread.
+pub struct SharedData<D> {
+    buf: Arc<SharedBuffer>,
+    offset: usize,
+    _d_marker: PhantomData<D>,
+}
+
+/// The buffer of [`SharedData`], which is freed when the last [`SharedData`] or
+/// [`SharedDataView`] referencing it is dropped.
+#[derive(Debug)]
+pub struct SharedBuffer(DataPtr);
+
+// The buffer is never mutated through the shared references, since it's only exposed through
+// `SharedDataView`, which is read-only.
+unsafe impl Send for SharedBuffer {}
+unsafe impl Sync for SharedBuffer {}
+
+impl<D> SharedData<D>
+where
+    D: POD + Clone,
+{
+    /// Constructs `SharedData` by taking over the buffer of the `data`, or by copying it if the
+    /// buffer is also referenced by other `Data`.
+    pub fn new(data: Data<D>) -> Self {
+        let Data { ptr, offset, .. } = data;
+        let (ptr, offset) = match Rc::try_unwrap(ptr) {
+            Ok(ptr) => (ptr, offset),
+            Err(ptr) => {
+                let size = ptr.len() - offset;
+                if size == 0 {
+                    return Self::new(Data::empty());
+                }
+                let copied = DataPtr::new(size);
+                unsafe {
+                    std::ptr::copy_nonoverlapping(ptr.at(offset), copied.at(0) as *mut u8, size);
+                }
+                (copied, 0)
+            }
+        };
+        Self {
+            buf: Arc::new(SharedBuffer(ptr)),
+            offset,
+            _d_marker: PhantomData,
+        }
+    }
+
+    /// Returns a read-only view of the shared buffer, which keeps the buffer alive.
+    pub fn view(&self) -> SharedDataView<D> {
+        let ptr = DataPtr {
+            ptr: self.buf.0.ptr,
+            managed: false,
+            _owner: Some(self.buf.clone()),
+        };
+        SharedDataView {
+            data: unsafe { Data::from_data_ptr(ptr, self.offset) },
+        }
+    }
+}
+
+/// A read-only view of [`SharedData`]. Unlike [`Data`], it can't be modified, and can be given to
+/// a backtest only as [`DataSource::Shared`], which doesn't allow a [`DataPreprocess`].
+#[derive(Clone, Debug)]
+pub struct SharedDataView<D>
+where
+    D: POD + Clone,
+{
+    data: Data<D>,
+}
+
+impl<D> SharedDataView<D>
+where
+    D: POD + Clone,
+{
+    /// Returns the length of the array.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if the view is empty.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the `Data` read by the backtest, which must not be exposed mutably.
+    pub(crate) fn into_data(self) -> Data<D> {
+        self.data
+    }
+}
+
+impl<D> Index<usize> for SharedDataView<D>
+where
+    D: POD + Clone,
+{
+    type Output = D;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.data[index]
+    }
+}
+
 #[derive(Debug)]
 pub struct DataPtr {
     ptr: *mut [u8],
     managed: bool,
+    /// Keeps the shared buffer alive while it's read through a [`SharedDataView`].
+    _owner: Option<Arc<SharedBuffer>>,
 }
 
 impl DataPtr {
@@ -133,6 +271,7 @@ impl DataPtr {
         Self {
             ptr: arr.into_raw(),
             managed: true,
+            _owner: None,
         }
     }
 
@@ -147,6 +286,7 @@ impl DataPtr {
         Self {
             ptr,
             managed: false,
+            _owner: None,
         }
     }
 
@@ -168,6 +308,7 @@ impl Default for DataPtr {
         Self {
             ptr: null_mut::<[u8; 0]>() as *mut [u8],
             managed: false,
+            _owner: None,
         }
     }
 }
@@ -1,6 +1,8 @@
+#[cfg(feature = "fs")]
+use std::{fs::File, io::BufWriter};
 use std::{
-    fs::File,
     io::{Error, ErrorKind, Read, Write},
+    mem::size_of,
 };
 
 use crate::{
@@ -67,6 +69,14 @@ impl NpyHeader {
         for (key, value) in dict {
             match key.as_str() {
                 "descr" => {
+                    // A plain array has a single unnamed field.
+                    if let Ok(ty) = value.get_string() {
+                        descr.push(Field {
+                            name: String::new(),
+                            ty: ty.to_string(),
+                        });
+                        continue;
+                    }
                     let list = value.get_list()?;
                     for item in list {
                         let tuple = item.get_list()?;
@@ -173,7 +183,11 @@ pub fn read_npy<R: Read, D: NpyDTyped + Clone>(
 
     let mut read_size = 0;
     while read_size < size {
-        read_size += reader.read(&mut buf[read_size..])?;
+        let n = reader.read(&mut buf[read_size..])?;
+        if n == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "truncated data"));
+        }
+        read_size += n;
     }
 
     if buf[0..6].to_vec() != b"\x93NUMPY" {
@@ -226,9 +240,95 @@ pub fn read_npy<R: Read, D: NpyDTyped + Clone>(
     Ok(data)
 }
 
+/// Reads the header of a structured array `numpy` stream, returning the bytes read and the total
+/// size of the file computed from the header.
+fn read_npy_header<R: Read, D: NpyDTyped>(reader: &mut R) -> std::io::Result<(Vec<u8>, usize)> {
+    let mut prefix = vec![0u8; 10];
+    reader.read_exact(&mut prefix)?;
+    let header_len = u16::from_le_bytes(prefix[8..10].try_into().unwrap()) as usize;
+    prefix.resize(10 + header_len, 0);
+    reader.read_exact(&mut prefix[10..])?;
+    let header = std::str::from_utf8(&prefix[10..])
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let header = NpyHeader::from_header(header)?;
+    let len = header.shape.first().copied().unwrap_or(0);
+    let size = prefix.len() + len * size_of::<D>();
+    Ok((prefix, size))
+}
+
+/// Reads a structured array `numpy` stream whose size isn't known in advance, such as a
+/// decompressing stream. The buffer is sized from the header, and the data is read directly into
+/// it.
+pub fn read_npy_stream<R: Read, D: NpyDTyped + Clone>(reader: &mut R) -> std::io::Result<Data<D>> {
+    let (prefix, size) = read_npy_header::<_, D>(reader)?;
+    read_npy(&mut prefix.as_slice().chain(reader), size)
+}
+
+#[cfg(feature = "fs")]
+/// Returns the codec of the compressed `numpy` file by its extension, `.zst` for zstd and `.lz4`
+/// for the LZ4 frame format.
+pub(crate) fn compression_codec(filepath: &str) -> Option<&'static str> {
+    if filepath.ends_with(".zst") {
+        Some("zstd")
+    } else if filepath.ends_with(".lz4") {
+        Some("lz4")
+    } else {
+        None
+    }
+}
+
+/// Opens the decompressing stream of the compressed `numpy` file.
+#[cfg(all(feature = "fs", feature = "compression"))]
+pub(crate) fn open_compressed(filepath: &str, codec: &str) -> std::io::Result<Box<dyn Read>> {
+    let file = File::open(filepath)?;
+    if codec == "zstd" {
+        Ok(Box::new(zstd::stream::read::Decoder::new(file)?))
+    } else {
+        Ok(Box::new(lz4_flex::frame::FrameDecoder::new(
+            std::io::BufReader::new(file),
+        )))
+    }
+}
+
+#[cfg(all(feature = "fs", not(feature = "compression")))]
+pub(crate) fn open_compressed(_filepath: &str, codec: &str) -> std::io::Result<Box<dyn Read>> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        format!("reading {codec} compressed data requires the `compression` feature"),
+    ))
+}
+
+#[cfg(feature = "fs")]
+/// Returns `true` if the file is a `numpy` file, either uncompressed or compressed.
+pub(crate) fn is_npy_file(filepath: &str) -> bool {
+    [".npy", ".npy.zst", ".npy.lz4"]
+        .iter()
+        .any(|ext| filepath.ends_with(ext))
+}
+
+#[cfg(feature = "fs")]
+/// Returns the size of the `numpy` file once it's loaded into memory, which is read from the
+/// header if the file is compressed.
+pub(crate) fn npy_file_size<D: NpyDTyped>(filepath: &str) -> std::io::Result<usize> {
+    match compression_codec(filepath) {
+        Some(codec) => {
+            let (_, size) = read_npy_header::<_, D>(&mut open_compressed(filepath, codec)?)?;
+            Ok(size)
+        }
+        None => Ok(File::open(filepath)?.metadata()?.len() as usize),
+    }
+}
+
+#[cfg(feature = "fs")]
 /// Reads a structured array `numpy` file. Currently, it doesn't check if the data structure is the
 /// same as what the file contains. Users should be cautious about this.
+///
+/// If the file name ends with `.zst` or `.lz4`, the file is decompressed as a zstd or LZ4 frame
+/// stream while it's read, which requires the `compression` feature.
 pub fn read_npy_file<D: NpyDTyped + Clone>(filepath: &str) -> std::io::Result<Data<D>> {
+    if let Some(codec) = compression_codec(filepath) {
+        return read_npy_stream(&mut open_compressed(filepath, codec)?);
+    }
     let mut file = File::open(filepath)?;
 
     file.sync_all()?;
@@ -237,8 +337,12 @@ pub fn read_npy_file<D: NpyDTyped + Clone>(filepath: &str) -> std::io::Result<Da
     read_npy(&mut file, size)
 }
 
+#[cfg(feature = "fs")]
 /// Reads a structured array `numpy` zip archived file. Currently, it doesn't check if the data
 /// structure is the same as what the file contains. Users should be cautious about this.
+///
+/// The archived file can be stored, deflated, or compressed by zstd, which requires the
+/// `compression` feature.
 pub fn read_npz_file<D: NpyDTyped + Clone>(filepath: &str, name: &str) -> std::io::Result<Data<D>> {
     let mut archive = zip::ZipArchive::new(File::open(filepath)?)?;
 
@@ -249,11 +353,53 @@ pub fn read_npz_file<D: NpyDTyped + Clone>(filepath: &str, name: &str) -> std::i
 }
 
 pub fn write_npy<W: Write, T: NpyDTyped>(write: &mut W, data: &[T]) -> std::io::Result<()> {
-    let descr = T::descr();
+    write_npy_bytes(write, T::descr(), data.len(), vec_as_bytes(data))
+}
+
+#[cfg(feature = "fs")]
+/// Writes a structured array `numpy` file. If the file name ends with `.zst` or `.lz4`, the file
+/// is compressed by zstd at the default level or in the LZ4 frame format, which requires the
+/// `compression` feature.
+pub fn write_npy_file<T: NpyDTyped>(filepath: &str, data: &[T]) -> std::io::Result<()> {
+    let file = File::create(filepath)?;
+    match compression_codec(filepath) {
+        #[cfg(feature = "compression")]
+        Some("zstd") => {
+            let mut encoder = zstd::stream::write::Encoder::new(file, 0)?;
+            write_npy(&mut encoder, data)?;
+            encoder.finish()?.sync_all()
+        }
+        #[cfg(feature = "compression")]
+        Some(_) => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(BufWriter::new(file));
+            write_npy(&mut encoder, data)?;
+            encoder.finish()?.flush()
+        }
+        #[cfg(not(feature = "compression"))]
+        Some(codec) => Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("writing {codec} compressed data requires the `compression` feature"),
+        )),
+        None => {
+            let mut writer = BufWriter::new(file);
+            write_npy(&mut writer, data)?;
+            writer.flush()
+        }
+    }
+}
+
+/// Writes a structured array whose fields are only known at runtime. `bytes` must hold `len`
+/// packed rows laid out as described by `descr`.
+pub fn write_npy_bytes<W: Write>(
+    write: &mut W,
+    descr: DType,
+    len: usize,
+    bytes: &[u8],
+) -> std::io::Result<()> {
     let header = NpyHeader {
         descr,
         fortran_order: false,
-        shape: vec![data.len()],
+        shape: vec![len],
     };
 
     write.write_all(b"\x93NUMPY\x01\x00")?;
@@ -261,7 +407,7 @@ pub fn write_npy<W: Write, T: NpyDTyped>(write: &mut W, data: &[T]) -> std::io::
     let len = header_str.len() as u16;
     write.write_all(&len.to_le_bytes())?;
     write.write_all(header_str.as_bytes())?;
-    write.write_all(vec_as_bytes(data))?;
+    write.write_all(bytes)?;
     Ok(())
 }
 
@@ -270,3 +416,36 @@ fn vec_as_bytes<T>(vec: &[T]) -> &[u8] {
     let ptr = vec.as_ptr() as *const u8;
     unsafe { std::slice::from_raw_parts(ptr, len) }
 }
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::{read_npy_file, write_npy_file};
+    use crate::types::{Event, BUY_EVENT, DEPTH_EVENT, EXCH_EVENT, LOCAL_EVENT};
+
+    #[test]
+    fn test_compressed_npy() {
+        let events: Vec<_> = (0..100)
+            .map(|i| Event {
+                ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | BUY_EVENT,
+                exch_ts: i,
+                local_ts: i + 1,
+                px: 100.0 + i as f64,
+                qty: 1.0,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            })
+            .collect();
+        for ext in ["npy", "npy.zst", "npy.lz4"] {
+            let path = std::env::temp_dir()
+                .join(format!("hftbacktest_npy_{}.{ext}", std::process::id()))
+                .to_string_lossy()
+                .to_string();
+            write_npy_file(&path, &events).unwrap();
+            let data = read_npy_file::<Event>(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(data.len(), events.len());
+            assert_eq!(data[99].px, 199.0);
+        }
+    }
+}
@@ -1,22 +1,29 @@
+#[cfg(feature = "fs")]
+use std::fs::File;
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::HashMap,
     io::{Error as IoError, ErrorKind},
+    mem::size_of,
     rc::Rc,
     sync::{
+        atomic::{AtomicUsize, Ordering},
         mpsc::{channel, Receiver, Sender},
         Arc,
     },
-    thread,
 };
 
-use uuid::Uuid;
-
+#[cfg(feature = "fs")]
+use crate::backtest::data::{
+    npy::{is_npy_file, npy_file_size, read_npy_file, read_npz_file},
+    schema::read_event_file,
+};
 use crate::{
     backtest::{
         data::{
-            npy::{read_npy_file, read_npz_file, NpyDTyped},
+            npy::NpyDTyped,
             Data,
+            SharedDataView,
             POD,
         },
         BacktestError,
@@ -34,9 +41,14 @@ where
     ///
     /// It will be loaded when needed and released
     /// when no [Processor](`crate::backtest::proc::Processor`) is reading the data.
+    #[cfg(feature = "fs")]
     File(String),
     /// Data is loaded and set by the user.
     Data(Data<D>),
+    /// Data is shared with other backtests running in parallel, such as by
+    /// [`BacktestGrid`](crate::backtest::grid::BacktestGrid). Since it's read-only, it can't be
+    /// preprocessed, so the latency offset and the time range can't be set on the asset.
+    Shared(SharedDataView<D>),
 }
 
 #[derive(Debug)]
@@ -87,9 +99,14 @@ where
 /// Provides a data cache that allows both the local processor and exchange processor to access the
 /// same or different data based on their timestamps without the need for reloading.
 #[derive(Clone, Debug)]
-pub struct Cache<D>(Rc<RefCell<HashMap<String, CachedData<D>>>>)
+pub struct Cache<D>
 where
-    D: POD + Clone;
+    D: POD + Clone,
+{
+    entries: Rc<RefCell<HashMap<String, CachedData<D>>>>,
+    usage: Rc<Cell<usize>>,
+    peak_usage: Rc<Cell<usize>>,
+}
 
 impl<D> Cache<D>
 where
@@ -97,24 +114,45 @@ where
 {
     /// Constructs an instance of `Cache`.
     pub fn new() -> Self {
-        Self(Default::default())
+        Self {
+            entries: Default::default(),
+            usage: Default::default(),
+            peak_usage: Default::default(),
+        }
+    }
+
+    fn add_usage(&self, data: &Data<D>) {
+        let usage = self.usage.get() + data.len() * size_of::<D>();
+        self.usage.set(usage);
+        if usage > self.peak_usage.get() {
+            self.peak_usage.set(usage);
+        }
+    }
+
+    fn sub_usage(&self, key: &str) {
+        if let Some(cached_data) = self.entries.borrow().get(key) {
+            self.usage
+                .set(self.usage.get() - cached_data.data.len() * size_of::<D>());
+        }
     }
 
-    /// Inserts a key-value pair into the `Cache`.
+    /// Inserts a key-value pair into the `Cache`, replacing the existing data for the key.
     pub fn insert(&mut self, key: String, data: Data<D>) {
-        self.0.borrow_mut().insert(key, CachedData::new(data));
+        self.sub_usage(&key);
+        self.add_usage(&data);
+        self.entries.borrow_mut().insert(key, CachedData::new(data));
     }
 
     /// Prepares cached data by inserting a key-value pair with empty data into the `Cache`.
     /// This placeholder will be replaced when the actual data is ready.
     pub fn prepare(&mut self, key: String) {
-        self.0.borrow_mut().insert(key, CachedData::empty());
+        self.entries.borrow_mut().insert(key, CachedData::empty());
     }
 
     /// Removes the [`Data`] if all retrieved [`Data`] are released.
     pub fn remove(&mut self, data: Data<D>) {
         let mut remove = None;
-        for (key, cached_data) in self.0.borrow_mut().iter_mut() {
+        for (key, cached_data) in self.entries.borrow_mut().iter_mut() {
             if data.data_eq(&cached_data.data) {
                 if cached_data.turn_in() {
                     remove = Some(key.clone());
@@ -123,25 +161,28 @@ where
             }
         }
         if let Some(key) = remove {
-            self.0.borrow_mut().remove(&key).unwrap();
+            self.sub_usage(&key);
+            self.entries.borrow_mut().remove(&key);
         }
     }
 
     /// Returns `true` if the `Cache` contains the [`Data`] for the specified key.
     pub fn contains(&self, key: &str) -> bool {
-        self.0.borrow().contains_key(key)
+        self.entries.borrow().contains_key(key)
     }
 
     /// Returns the [`Data`] corresponding to the key.
     pub fn get(&mut self, key: &str) -> Data<D> {
-        let mut borrowed = self.0.borrow_mut();
+        let mut borrowed = self.entries.borrow_mut();
         let cached_data = borrowed.get_mut(key).unwrap();
         cached_data.checkout()
     }
 
     /// Sets the [`Data`] for the specified key and marks it as ready.
     pub fn set(&mut self, key: &str, data: Data<D>) {
-        let mut borrowed = self.0.borrow_mut();
+        self.sub_usage(key);
+        self.add_usage(&data);
+        let mut borrowed = self.entries.borrow_mut();
         let cached_data = borrowed.get_mut(key).unwrap();
         cached_data.set(data);
         cached_data.ready = true;
@@ -149,7 +190,17 @@ where
 
     /// Returns `true` if the [`Data`] for the specified key is ready.
     pub fn is_ready(&self, key: &str) -> bool {
-        self.0.borrow().get(key).unwrap().ready
+        self.entries.borrow().get(key).unwrap().ready
+    }
+
+    /// Returns the number of bytes currently held by the `Cache`.
+    pub fn memory_usage(&self) -> usize {
+        self.usage.get()
+    }
+
+    /// Returns the peak number of bytes held by the `Cache` at the same time.
+    pub fn peak_memory_usage(&self) -> usize {
+        self.peak_usage.get()
     }
 }
 
@@ -187,6 +238,8 @@ where
     result: Result<DataSend<D>, IoError>,
 }
 
+// The data is loaded in the background only from files.
+#[cfg(feature = "fs")]
 impl<D> LoadDataResult<D>
 where
     D: NpyDTyped + Clone,
@@ -206,6 +259,10 @@ where
     }
 }
 
+/// Sequence for the keys of the [`Data`] set by the user, which must be unique within the
+/// [`Cache`].
+static DATA_KEY_SEQ: AtomicUsize = AtomicUsize::new(0);
+
 /// A builder for constructing [`Reader`].
 pub struct ReaderBuilder<D>
 where
@@ -214,7 +271,9 @@ where
     data_key_list: Vec<String>,
     cache: Cache<D>,
     temporary_data: HashMap<String, Data<D>>,
+    shared_data: HashMap<String, Data<D>>,
     parallel_load: bool,
+    memory_budget: Option<usize>,
     preprocessor: Option<Arc<Box<dyn DataPreprocess<D> + Sync + Send + 'static>>>,
 }
 
@@ -227,7 +286,9 @@ where
             data_key_list: Default::default(),
             cache: Default::default(),
             temporary_data: Default::default(),
+            shared_data: Default::default(),
             parallel_load: false,
+            memory_budget: None,
             preprocessor: None,
         }
     }
@@ -255,6 +316,23 @@ where
         }
     }
 
+    /// Sets the memory budget in bytes for the data held by [`Reader`]. When preloading the next
+    /// data would exceed the budget, [`Reader`] loads the data only when it is needed, one at a
+    /// time. The size of the next data is estimated from its file.
+    ///
+    /// The budget only disables the preloading by [`parallel_load`](Self::parallel_load). Each data
+    /// is loaded into memory as a whole, as reading a file in chunks isn't supported, so the usage
+    /// can still exceed the budget. A dataset larger than the budget should be split into smaller
+    /// files, such as daily files.
+    ///
+    /// The default value is `None`, indicating no budget.
+    pub fn memory_budget(self, memory_budget: Option<usize>) -> Self {
+        Self {
+            memory_budget,
+            ..self
+        }
+    }
+
     /// Sets a [`DataPreprocess`].
     pub fn preprocessor<Preprocessor>(self, preprocessor: Preprocessor) -> Self
     where
@@ -271,27 +349,41 @@ where
     pub fn data(self, data: Vec<DataSource<D>>) -> Self {
         let mut data_key_list = self.data_key_list;
         let mut temporary_data = self.temporary_data;
+        let mut shared_data = self.shared_data;
         for item in data {
             match item {
+                #[cfg(feature = "fs")]
                 DataSource::File(filepath) => {
                     data_key_list.push(filepath);
                 }
                 DataSource::Data(data) => {
-                    let key = Uuid::new_v4().to_string();
+                    let key = format!("data:{}", DATA_KEY_SEQ.fetch_add(1, Ordering::Relaxed));
                     data_key_list.push(key.clone());
                     temporary_data.insert(key, data);
                 }
+                DataSource::Shared(data) => {
+                    let key = format!("data:{}", DATA_KEY_SEQ.fetch_add(1, Ordering::Relaxed));
+                    data_key_list.push(key.clone());
+                    shared_data.insert(key, data.into_data());
+                }
             }
         }
         Self {
             data_key_list,
             temporary_data,
+            shared_data,
             ..self
         }
     }
 
     /// Builds a [`Reader`].
     pub fn build(self) -> Result<Reader<D>, IoError> {
+        if !self.shared_data.is_empty() && self.preprocessor.is_some() {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                "the shared data cannot be preprocessed",
+            ));
+        }
         let mut cache = self.cache.clone();
         for (key, mut data) in self.temporary_data {
             if let Some(p) = &self.preprocessor {
@@ -299,6 +391,9 @@ where
             }
             cache.insert(key, data)
         }
+        for (key, data) in self.shared_data {
+            cache.insert(key, data)
+        }
 
         let (tx, rx) = channel();
         Ok(Reader {
@@ -308,6 +403,7 @@ where
             tx,
             rx: Rc::new(rx),
             parallel_load: self.parallel_load,
+            memory_budget: self.memory_budget,
             preprocessor: self.preprocessor.clone(),
         })
     }
@@ -322,9 +418,11 @@ where
     data_key_list: Vec<String>,
     cache: Cache<D>,
     data_num: usize,
+    #[cfg_attr(not(feature = "fs"), allow(dead_code))]
     tx: Sender<LoadDataResult<D>>,
     rx: Rc<Receiver<LoadDataResult<D>>>,
     parallel_load: bool,
+    memory_budget: Option<usize>,
     preprocessor: Option<Arc<Box<dyn DataPreprocess<D> + Sync + Send + 'static>>>,
 }
 
@@ -352,7 +450,9 @@ where
             if self.parallel_load {
                 let next_key = self.data_key_list.get(self.data_num + 1).cloned();
                 if let Some(next_key) = next_key {
-                    self.load_data(&next_key)?;
+                    if self.fits_memory_budget(&next_key) {
+                        self.load_data(&next_key)?;
+                    }
                 }
             }
 
@@ -374,22 +474,68 @@ where
 
             let data = self.cache.get(&key);
             self.data_num += 1;
+            // The data left empty by the preprocessor, such as outside the time range, is
+            // skipped.
+            if data.is_empty() && self.preprocessor.is_some() {
+                self.release(data);
+                return self.next_data();
+            }
             Ok(data)
         } else {
             Err(BacktestError::EndOfData)
         }
     }
 
+    /// Returns the number of [`Data`] retrieved so far.
+    pub fn data_num(&self) -> usize {
+        self.data_num
+    }
+
+    /// Moves to the specified position in the sequence of data, so that [`next_data`](Self::next_data)
+    /// retrieves the data at that position. This is used to resume from a checkpoint without
+    /// loading the preceding data.
+    pub fn seek(&mut self, data_num: usize) {
+        self.data_num = data_num;
+    }
+
+    /// Returns the number of bytes of the data currently held.
+    pub fn memory_usage(&self) -> usize {
+        self.cache.memory_usage()
+    }
+
+    /// Returns the peak number of bytes of the data held at the same time.
+    pub fn peak_memory_usage(&self) -> usize {
+        self.cache.peak_memory_usage()
+    }
+
+    /// Returns `true` if the data for the specified key can be preloaded without exceeding the
+    /// memory budget.
+    fn fits_memory_budget(&self, key: &str) -> bool {
+        match self.memory_budget {
+            Some(memory_budget) if !self.cache.contains(key) => {
+                self.cache.memory_usage() + estimate_data_size::<D>(key) <= memory_budget
+            }
+            _ => true,
+        }
+    }
+
     fn load_data(&mut self, key: &str) -> Result<(), BacktestError> {
         if !self.cache.contains(key) {
             self.cache.prepare(key.to_string());
 
-            if key.ends_with(".npy") {
+            #[cfg(not(feature = "fs"))]
+            return Err(BacktestError::DataError(IoError::new(
+                ErrorKind::Unsupported,
+                "loading data files requires the `fs` feature",
+            )));
+
+            #[cfg(feature = "fs")]
+            if is_npy_file(key) {
                 let tx = self.tx.clone();
                 let filepath = key.to_string();
                 let preprocessor = self.preprocessor.clone();
 
-                let _ = thread::spawn(move || {
+                spawn_loader(move || {
                     let load_data = |filepath: &str| {
                         let mut data = read_npy_file::<D>(filepath)?;
                         if let Some(preprocessor) = &preprocessor {
@@ -413,7 +559,7 @@ where
                 let filepath = key.to_string();
                 let preprocessor = self.preprocessor.clone();
 
-                let _ = thread::spawn(move || {
+                spawn_loader(move || {
                     let load_data = |filepath: &str| {
                         let mut data = read_npz_file::<D>(filepath, "data")?;
                         if let Some(preprocessor) = &preprocessor {
@@ -443,6 +589,40 @@ where
     }
 }
 
+/// Runs the data loading task in a separate thread. Since threads are unavailable on `wasm32`, the
+/// task runs in place there, and the data is loaded sequentially.
+#[cfg(feature = "fs")]
+fn spawn_loader<F>(task: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = std::thread::spawn(task);
+    #[cfg(target_arch = "wasm32")]
+    task();
+}
+
+/// Estimates the number of bytes that the data file occupies once it is loaded into memory.
+/// Returns `0` if the size cannot be determined.
+#[cfg(feature = "fs")]
+fn estimate_data_size<D: NpyDTyped>(filepath: &str) -> usize {
+    if filepath.ends_with(".npz") {
+        File::open(filepath)
+            .ok()
+            .and_then(|file| zip::ZipArchive::new(file).ok())
+            .and_then(|mut archive| archive.by_name("data.npy").ok().map(|f| f.size() as usize))
+            .unwrap_or(0)
+    } else {
+        npy_file_size::<D>(filepath).unwrap_or(0)
+    }
+}
+
+#[cfg(not(feature = "fs"))]
+#[allow(clippy::extra_unused_type_parameters)]
+fn estimate_data_size<D: NpyDTyped>(_filepath: &str) -> usize {
+    0
+}
+
 /// `DataPreprocess` offers a function to preprocess data before it is fed into the backtesting.
 /// This feature is primarily introduced to adjust timestamps, making it particularly useful when
 /// backtesting the market from a location different from where your order latency was originally
@@ -460,6 +640,33 @@ where
     fn preprocess(&self, data: &mut Data<D>) -> Result<(), IoError>;
 }
 
+/// Applies the two preprocessors in order.
+impl<D, A, B> DataPreprocess<D> for (A, B)
+where
+    D: POD + Clone,
+    A: DataPreprocess<D>,
+    B: DataPreprocess<D>,
+{
+    fn preprocess(&self, data: &mut Data<D>) -> Result<(), IoError> {
+        self.0.preprocess(data)?;
+        self.1.preprocess(data)
+    }
+}
+
+/// Applies the preprocessor if it's set.
+impl<D, P> DataPreprocess<D> for Option<P>
+where
+    D: POD + Clone,
+    P: DataPreprocess<D>,
+{
+    fn preprocess(&self, data: &mut Data<D>) -> Result<(), IoError> {
+        match self {
+            Some(preprocessor) => preprocessor.preprocess(data),
+            None => Ok(()),
+        }
+    }
+}
+
 /// Pre-processes the feed data to adjust for latency. `local_ts` is offset by the specified latency
 /// offset.
 #[derive(Clone)]
@@ -489,3 +696,198 @@ impl DataPreprocess<Event> for FeedLatencyAdjustment {
         Ok(())
     }
 }
+
+/// Selects the feed events whose local timestamp is within `[start_ts, end_ts)`, so that a
+/// backtest can run on a part of the dataset without slicing the files in advance. The events
+/// are located by binary search, so the data must be sorted by the local timestamp.
+///
+/// As a [`DataPreprocess`], it slices each file as it's loaded, and [`Reader`] skips the files
+/// left empty. Since the market depth snapshot at the start of the range is not part of the
+/// slice, the initial snapshot should be provided separately.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeRange {
+    start_ts: i64,
+    end_ts: i64,
+}
+
+impl TimeRange {
+    /// Constructs a `TimeRange` from `start_ts` inclusive until `end_ts` exclusive.
+    pub fn new(start_ts: i64, end_ts: i64) -> Self {
+        assert!(start_ts <= end_ts);
+        Self { start_ts, end_ts }
+    }
+
+    /// Returns the events within the range. The data is shared rather than copied if it lies
+    /// entirely within the range.
+    pub fn slice(&self, data: &Data<Event>) -> Data<Event> {
+        let start = lower_bound(data, self.start_ts);
+        let end = lower_bound(data, self.end_ts);
+        if start == 0 && end == data.len() {
+            return data.clone();
+        }
+        let events: Vec<_> = (start..end).map(|i| data[i].clone()).collect();
+        Data::from_data(&events)
+    }
+
+    /// Loads the events within the range from the files, which must be arranged in the
+    /// chronological order, and merges them into one [`Data`]. Loading stops at the first file
+    /// starting at or after the end of the range. The files of any
+    /// [`SchemaVersion`](super::SchemaVersion) can be loaded.
+    #[cfg(feature = "fs")]
+    pub fn load(&self, filepaths: &[&str]) -> Result<Data<Event>, IoError> {
+        let mut events = Vec::new();
+        for filepath in filepaths {
+            let data = read_event_file(filepath)?;
+            if !data.is_empty() && data[0].local_ts >= self.end_ts {
+                break;
+            }
+            let data = self.slice(&data);
+            events.extend((0..data.len()).map(|i| data[i].clone()));
+        }
+        Ok(Data::from_data(&events))
+    }
+}
+
+impl DataPreprocess<Event> for TimeRange {
+    fn preprocess(&self, data: &mut Data<Event>) -> Result<(), IoError> {
+        *data = self.slice(data);
+        Ok(())
+    }
+}
+
+/// Returns the index of the first event whose local timestamp is not less than `timestamp`.
+fn lower_bound(data: &Data<Event>, timestamp: i64) -> usize {
+    let (mut lo, mut hi) = (0, data.len());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if data[mid].local_ts < timestamp {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use super::{Cache, Reader, TimeRange};
+    #[cfg(feature = "fs")]
+    use crate::backtest::data::write_npy_file;
+    use crate::{
+        backtest::data::{Data, DataSource},
+        types::{Event, LOCAL_BID_DEPTH_EVENT},
+    };
+
+    fn events(local_ts: &[i64]) -> Data<Event> {
+        let events: Vec<_> = local_ts
+            .iter()
+            .map(|&local_ts| Event {
+                ev: LOCAL_BID_DEPTH_EVENT,
+                exch_ts: local_ts - 1,
+                local_ts,
+                px: 100.0,
+                qty: 1.0,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            })
+            .collect();
+        Data::from_data(&events)
+    }
+
+    #[test]
+    fn test_cache_usage() {
+        let mut cache = Cache::new();
+        let size = size_of::<Event>();
+        cache.insert("a".to_string(), events(&[1, 2]));
+        assert_eq!(cache.memory_usage(), 2 * size);
+
+        // Re-inserting the key replaces the data.
+        cache.insert("a".to_string(), events(&[1, 2, 3]));
+        assert_eq!(cache.memory_usage(), 3 * size);
+        assert_eq!(cache.peak_memory_usage(), 3 * size);
+
+        cache.prepare("b".to_string());
+        cache.set("b", events(&[1]));
+        assert_eq!(cache.memory_usage(), 4 * size);
+
+        let data = cache.get("a");
+        cache.remove(data);
+        assert_eq!(cache.memory_usage(), size);
+        assert_eq!(cache.peak_memory_usage(), 4 * size);
+    }
+
+    #[test]
+    fn test_time_range() {
+        let range = TimeRange::new(20, 40);
+        let sliced = range.slice(&events(&[10, 20, 20, 30, 40, 50]));
+        assert_eq!(sliced.len(), 3);
+        assert_eq!((sliced[0].local_ts, sliced[2].local_ts), (20, 30));
+
+        // The data outside the range is skipped.
+        let mut reader = Reader::builder()
+            .parallel_load(false)
+            .preprocessor(range)
+            .data(vec![
+                DataSource::Data(events(&[1, 2])),
+                DataSource::Data(events(&[25, 45])),
+                DataSource::Data(events(&[50])),
+            ])
+            .build()
+            .unwrap();
+        let data = reader.next_data().unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].local_ts, 25);
+        assert!(reader.next_data().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_memory_budget() {
+        let dir = std::env::temp_dir();
+        let files: Vec<_> = (0..3)
+            .map(|i| {
+                let data = events(&[1, 2, 3, 4]);
+                let events: Vec<_> = (0..data.len()).map(|i| data[i].clone()).collect();
+                let path = dir.join(format!(
+                    "hftbacktest_test_memory_budget_{}_{i}.npy",
+                    std::process::id()
+                ));
+                write_npy_file(path.to_str().unwrap(), &events).unwrap();
+                path.to_str().unwrap().to_string()
+            })
+            .collect();
+        let size = 4 * size_of::<Event>();
+        let reader = |memory_budget| {
+            Reader::<Event>::builder()
+                .parallel_load(true)
+                .memory_budget(memory_budget)
+                .data(files.iter().cloned().map(DataSource::File).collect())
+                .build()
+                .unwrap()
+        };
+
+        let mut unbounded = reader(None);
+        let data = unbounded.next_data().unwrap();
+        assert!(unbounded.cache.contains(&files[1]));
+        unbounded.release(data);
+
+        // The budget only disables the preloading, so the current data is loaded even though it
+        // doesn't fit the budget.
+        let mut bounded = reader(Some(size / 2));
+        for file in &files[1..] {
+            let data = bounded.next_data().unwrap();
+            assert_eq!(data.len(), 4);
+            assert!(!bounded.cache.contains(file));
+            bounded.release(data);
+        }
+        assert_eq!(bounded.peak_memory_usage(), size);
+
+        for file in files {
+            let _ = std::fs::remove_file(file);
+        }
+    }
+}
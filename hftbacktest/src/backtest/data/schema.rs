@@ -0,0 +1,210 @@
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::{
+    io::{Error, ErrorKind, Read},
+    mem::size_of,
+};
+
+#[cfg(feature = "fs")]
+use crate::backtest::data::npy::{compression_codec, open_compressed};
+use crate::{
+    backtest::data::{
+        npy::{read_npy, Field, NpyDTyped, NpyHeader},
+        Data,
+    },
+    types::{Event, BUY_EVENT, EXCH_EVENT, LOCAL_EVENT, SELL_EVENT},
+};
+
+/// The version of the feed data schema.
+///
+/// The version of a data file is identified by the `numpy` dtype and shape in its header rather
+/// than by an additional header field, so that the files remain readable by `numpy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SchemaVersion {
+    /// The 2-dimensional `float64` array of hftbacktest 1.x, whose columns are the event,
+    /// the exchange timestamp, the local timestamp, the side, the price, and the quantity. An
+    /// invalid timestamp is `-1`.
+    V1,
+    /// The structured array of [`Event`].
+    V2,
+}
+
+impl SchemaVersion {
+    /// The version written by this version of hftbacktest.
+    pub const CURRENT: SchemaVersion = SchemaVersion::V2;
+
+    /// Identifies the schema version from the `numpy` header, or returns `None` if it's unknown.
+    pub fn from_header(header: &NpyHeader) -> Option<Self> {
+        let types = |descr: &[Field]| {
+            descr
+                .iter()
+                .map(|field| field.ty.clone())
+                .collect::<Vec<_>>()
+        };
+        match header.shape.as_slice() {
+            [_] if types(&header.descr) == types(&Event::descr()) => Some(SchemaVersion::V2),
+            [_, 6] if types(&header.descr) == ["<f8"] => Some(SchemaVersion::V1),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a feed data file of any known [`SchemaVersion`], converting the events into the current
+/// schema. Like [`read_npy_file`](super::read_npy_file), `.npy` files can be compressed, and the
+/// array named `data` is read from `.npz` files.
+///
+/// The files of an older version can be upgraded by writing the returned events with
+/// [`write_npy_file`](super::write_npy_file).
+#[cfg(feature = "fs")]
+pub fn read_event_file(filepath: &str) -> std::io::Result<Data<Event>> {
+    if filepath.ends_with(".npz") {
+        let mut archive = zip::ZipArchive::new(File::open(filepath)?)?;
+        let mut file = archive.by_name("data.npy")?;
+        read_event_stream(&mut file)
+    } else if let Some(codec) = compression_codec(filepath) {
+        read_event_stream(&mut open_compressed(filepath, codec)?)
+    } else {
+        read_event_stream(&mut File::open(filepath)?)
+    }
+}
+
+/// Reads a feed data `numpy` stream of any known [`SchemaVersion`], converting the events into the
+/// current schema.
+pub fn read_event_stream<R: Read>(reader: &mut R) -> std::io::Result<Data<Event>> {
+    let mut prefix = vec![0u8; 10];
+    reader.read_exact(&mut prefix)?;
+    if &prefix[0..6] != b"\x93NUMPY" {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "must start with \\x93NUMPY",
+        ));
+    }
+    let header_len = u16::from_le_bytes(prefix[8..10].try_into().unwrap()) as usize;
+    prefix.resize(10 + header_len, 0);
+    reader.read_exact(&mut prefix[10..])?;
+    let header = std::str::from_utf8(&prefix[10..])
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let header = NpyHeader::from_header(header)?;
+
+    match SchemaVersion::from_header(&header) {
+        Some(SchemaVersion::V2) => {
+            let size = prefix.len() + header.shape[0] * size_of::<Event>();
+            read_npy(&mut prefix.as_slice().chain(reader), size)
+        }
+        Some(SchemaVersion::V1) => {
+            let mut bytes = vec![0u8; header.shape[0] * 6 * size_of::<f64>()];
+            reader.read_exact(&mut bytes)?;
+            let events: Vec<_> = bytes
+                .chunks_exact(6 * size_of::<f64>())
+                .map(|row| {
+                    let mut cols = row
+                        .chunks_exact(size_of::<f64>())
+                        .map(|col| f64::from_le_bytes(col.try_into().unwrap()));
+                    let mut col = || cols.next().unwrap();
+                    upgrade_v1([col(), col(), col(), col(), col(), col()])
+                })
+                .collect();
+            Ok(Data::from_data(&events))
+        }
+        None => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unknown schema: {}", header.descr()),
+        )),
+    }
+}
+
+/// Converts a row of [`SchemaVersion::V1`] into [`Event`].
+fn upgrade_v1([ev, exch_ts, local_ts, side, px, qty]: [f64; 6]) -> Event {
+    let mut ev = ev as u64;
+    let (exch_ts, local_ts) = (exch_ts as i64, local_ts as i64);
+    // The later 1.x versions already carry the exchange and local event flags.
+    if ev & (EXCH_EVENT | LOCAL_EVENT) == 0 {
+        if exch_ts != -1 {
+            ev |= EXCH_EVENT;
+        }
+        if local_ts != -1 {
+            ev |= LOCAL_EVENT;
+        }
+    }
+    if side > 0.0 {
+        ev |= BUY_EVENT;
+    } else if side < 0.0 {
+        ev |= SELL_EVENT;
+    }
+    Event {
+        ev,
+        // An invalid timestamp is replaced with the valid one, as it's ignored anyway.
+        exch_ts: if exch_ts == -1 { local_ts } else { exch_ts },
+        local_ts: if local_ts == -1 { exch_ts } else { local_ts },
+        px,
+        qty,
+        order_id: 0,
+        ival: 0,
+        fval: 0.0,
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use std::io::Write;
+
+    use super::read_event_file;
+    use crate::{
+        backtest::data::write_npy_file,
+        types::{
+            Event,
+            EXCH_BUY_TRADE_EVENT,
+            EXCH_EVENT,
+            LOCAL_BID_DEPTH_EVENT,
+            LOCAL_SELL_TRADE_EVENT,
+        },
+    };
+
+    #[test]
+    fn test_read_v1_event_file() {
+        let dir = std::env::temp_dir();
+        let rows: [[f64; 6]; 2] = [
+            [1.0, 10.0, 12.0, 1.0, 100.0, 2.0],
+            [2.0, 20.0, -1.0, 1.0, 101.0, 1.0],
+        ];
+        let mut header = "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 6), }".to_string();
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        let v1_path = dir.join("hftbacktest_test_v1.npy");
+        let mut file = std::fs::File::create(&v1_path).unwrap();
+        file.write_all(b"\x93NUMPY\x01\x00").unwrap();
+        file.write_all(&(header.len() as u16).to_le_bytes())
+            .unwrap();
+        file.write_all(header.as_bytes()).unwrap();
+        for value in rows.iter().flatten() {
+            file.write_all(&value.to_le_bytes()).unwrap();
+        }
+        drop(file);
+
+        let data = read_event_file(v1_path.to_str().unwrap()).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].ev, LOCAL_BID_DEPTH_EVENT | EXCH_EVENT);
+        assert_eq!((data[0].exch_ts, data[0].local_ts), (10, 12));
+        assert_eq!(data[1].ev, EXCH_BUY_TRADE_EVENT);
+        assert_eq!((data[1].exch_ts, data[1].local_ts), (20, 20));
+        assert_eq!(data[1].px, 101.0);
+
+        // The current version is read as is.
+        let event = Event {
+            ev: LOCAL_SELL_TRADE_EVENT | EXCH_EVENT,
+            exch_ts: 1,
+            local_ts: 2,
+            px: 100.0,
+            qty: 1.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        };
+        let v2_path = dir.join("hftbacktest_test_v2.npy");
+        write_npy_file(v2_path.to_str().unwrap(), std::slice::from_ref(&event)).unwrap();
+        let data = read_event_file(v2_path.to_str().unwrap()).unwrap();
+        assert_eq!(data[0], event);
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    types::{
+        Event,
+        BUY_EVENT,
+        DEPTH_CLEAR_EVENT,
+        DEPTH_EVENT,
+        DEPTH_SNAPSHOT_EVENT,
+        EXCH_EVENT,
+        LOCAL_EVENT,
+        SELL_EVENT,
+        TRADE_EVENT,
+    },
+    utils::SplitMix64,
+};
+
+const SECOND: f64 = 1_000_000_000.0;
+
+/// Generates a synthetic L2 feed, so that strategies can be tested and the engine can be
+/// benchmarked without real exchange data.
+///
+/// The market events arrive as a self-exciting Hawkes process, whose intensity jumps by the
+/// `excitation` on every event and decays back to the base intensity at the `decay` rate, which
+/// reproduces the clustering of real market activity. The mid price follows a geometric random
+/// walk with the given volatility, and the spread follows a mean-reverting process. Each event
+/// is either a trade at the best price, which consumes the quantity there, or a quantity change
+/// of a random level, and the book is shifted with the mid price and the spread.
+///
+/// The timestamps are in nanoseconds, and the generated events have both the exchange and local
+/// event flags. The same seed always produces the same events.
+///
+/// ```
+/// use hftbacktest::backtest::data::{synthetic::SyntheticGenerator, write_npy_file};
+///
+/// let events = SyntheticGenerator::new(0.1, 0.001)
+///     .seed(7)
+///     .initial_price(1000.0)
+///     .intensity(100.0, 50.0, 80.0)
+///     .generate(60_000_000_000);
+/// # let path = std::env::temp_dir().join("synthetic_doc.npy");
+/// write_npy_file(path.to_str().unwrap(), &events).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct SyntheticGenerator {
+    tick_size: f64,
+    lot_size: f64,
+    seed: u64,
+    start_timestamp: i64,
+    initial_price: f64,
+    volatility: f64,
+    base_intensity: f64,
+    excitation: f64,
+    decay: f64,
+    mean_spread: f64,
+    spread_reversion: f64,
+    spread_volatility: f64,
+    depth_levels: usize,
+    mean_level_qty: f64,
+    mean_trade_qty: f64,
+    trade_ratio: f64,
+    feed_latency: i64,
+}
+
+impl SyntheticGenerator {
+    /// Constructs `SyntheticGenerator` with the tick size and the lot size.
+    pub fn new(tick_size: f64, lot_size: f64) -> Self {
+        Self {
+            tick_size,
+            lot_size,
+            seed: 0,
+            start_timestamp: 0,
+            initial_price: 1000.0 * tick_size,
+            volatility: 0.0005,
+            base_intensity: 100.0,
+            excitation: 50.0,
+            decay: 100.0,
+            mean_spread: 1.5,
+            spread_reversion: 1.0,
+            spread_volatility: 0.5,
+            depth_levels: 20,
+            mean_level_qty: 10.0 * lot_size,
+            mean_trade_qty: 2.0 * lot_size,
+            trade_ratio: 0.2,
+            feed_latency: 1_000_000,
+        }
+    }
+
+    /// Sets the seed of the random number generator. The default is `0`.
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// Sets the timestamp of the initial snapshot. The default is `0`.
+    pub fn start_timestamp(self, start_timestamp: i64) -> Self {
+        Self {
+            start_timestamp,
+            ..self
+        }
+    }
+
+    /// Sets the initial mid price. The default is `1000` ticks.
+    pub fn initial_price(self, initial_price: f64) -> Self {
+        Self {
+            initial_price,
+            ..self
+        }
+    }
+
+    /// Sets the volatility of the mid price's log return over a second. The default is `0.0005`.
+    pub fn volatility(self, volatility: f64) -> Self {
+        Self { volatility, ..self }
+    }
+
+    /// Sets the Hawkes process of the event arrivals, with the base intensity in events per
+    /// second, the jump of the intensity on every event, and the decay rate of the excitation per
+    /// second. The `excitation` must be less than the `decay` for the process to be stationary.
+    /// The default is `(100, 50, 100)`.
+    pub fn intensity(self, base_intensity: f64, excitation: f64, decay: f64) -> Self {
+        assert!(base_intensity > 0.0 && excitation >= 0.0 && excitation < decay);
+        Self {
+            base_intensity,
+            excitation,
+            decay,
+            ..self
+        }
+    }
+
+    /// Sets the spread dynamics, with the mean spread in ticks, the mean reversion rate per
+    /// second, and the volatility of the spread in ticks over a second. The default is
+    /// `(1.5, 1, 0.5)`.
+    pub fn spread(self, mean_spread: f64, reversion: f64, volatility: f64) -> Self {
+        assert!(mean_spread >= 1.0);
+        Self {
+            mean_spread,
+            spread_reversion: reversion,
+            spread_volatility: volatility,
+            ..self
+        }
+    }
+
+    /// Sets the number of price levels on each side. The default is `20`.
+    pub fn depth_levels(self, depth_levels: usize) -> Self {
+        assert!(depth_levels > 0);
+        Self {
+            depth_levels,
+            ..self
+        }
+    }
+
+    /// Sets the mean quantity of a price level and the mean quantity of a trade. The default is
+    /// `10` lots and `2` lots.
+    pub fn qty(self, mean_level_qty: f64, mean_trade_qty: f64) -> Self {
+        Self {
+            mean_level_qty,
+            mean_trade_qty,
+            ..self
+        }
+    }
+
+    /// Sets the fraction of the events that are trades. The default is `0.2`.
+    pub fn trade_ratio(self, trade_ratio: f64) -> Self {
+        Self {
+            trade_ratio,
+            ..self
+        }
+    }
+
+    /// Sets the feed latency added to the exchange timestamp to obtain the local timestamp. The
+    /// default is 1ms.
+    pub fn feed_latency(self, feed_latency: i64) -> Self {
+        Self {
+            feed_latency,
+            ..self
+        }
+    }
+
+    /// Generates the events for the `duration` in nanoseconds, starting with a snapshot of the
+    /// market depth.
+    pub fn generate(&self, duration: i64) -> Vec<Event> {
+        let mut state = State {
+            gen: self,
+            rng: SplitMix64::new(self.seed),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            events: Vec::new(),
+            timestamp: self.start_timestamp,
+        };
+        let mut mid = self.initial_price;
+        let mut spread = self.mean_spread;
+        let (best_bid, best_ask) = self.bbo(mid, spread);
+
+        // The initial snapshot.
+        for (side, clear_px) in [
+            (BUY_EVENT, (best_bid - self.depth_levels as i64) as f64),
+            (SELL_EVENT, (best_ask + self.depth_levels as i64) as f64),
+        ] {
+            state.push(DEPTH_CLEAR_EVENT | side, clear_px * self.tick_size, 0.0);
+        }
+        state.shift_book(best_bid, best_ask, DEPTH_SNAPSHOT_EVENT);
+
+        // Simulates the Hawkes process by Ogata's thinning. Since the intensity only decays
+        // between events, the intensity right after the last event bounds it until the next.
+        let end = self.start_timestamp + duration;
+        let mut excited = 0.0;
+        let mut elapsed = 0.0;
+        loop {
+            let upper = self.base_intensity + excited;
+            let wait = -state.rng.next_f64().max(f64::MIN_POSITIVE).ln() / upper;
+            elapsed += wait;
+            excited *= (-self.decay * wait).exp();
+            if state.rng.next_f64() * upper > self.base_intensity + excited {
+                continue;
+            }
+            let timestamp = self.start_timestamp + (elapsed * SECOND) as i64;
+            if timestamp >= end {
+                break;
+            }
+            excited += self.excitation;
+
+            let dt = (timestamp - state.timestamp) as f64 / SECOND;
+            state.timestamp = timestamp;
+            mid *= (self.volatility * dt.sqrt() * state.normal()).exp();
+            spread += self.spread_reversion * (self.mean_spread - spread) * dt
+                + self.spread_volatility * dt.sqrt() * state.normal();
+            spread = spread.max(1.0);
+            let (best_bid, best_ask) = self.bbo(mid, spread);
+            state.shift_book(best_bid, best_ask, DEPTH_EVENT);
+
+            if state.rng.next_f64() < self.trade_ratio {
+                state.trade();
+            } else {
+                state.update_level();
+            }
+        }
+        state.events
+    }
+
+    /// Returns the best bid and ask ticks for the mid price and the spread in ticks.
+    fn bbo(&self, mid: f64, spread: f64) -> (i64, i64) {
+        let spread = spread.round() as i64;
+        let best_bid = (mid / self.tick_size - spread as f64 / 2.0).round() as i64;
+        (best_bid, best_bid + spread)
+    }
+}
+
+struct State<'a> {
+    gen: &'a SyntheticGenerator,
+    rng: SplitMix64,
+    bids: BTreeMap<i64, f64>,
+    asks: BTreeMap<i64, f64>,
+    events: Vec<Event>,
+    timestamp: i64,
+}
+
+impl State<'_> {
+    fn push(&mut self, ev: u64, px: f64, qty: f64) {
+        self.events.push(Event {
+            ev: ev | EXCH_EVENT | LOCAL_EVENT,
+            exch_ts: self.timestamp,
+            local_ts: self.timestamp + self.gen.feed_latency,
+            px,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+    }
+
+    /// Returns a standard normal random value by the Box-Muller transform.
+    fn normal(&mut self) -> f64 {
+        let u1 = self.rng.next_f64().max(f64::MIN_POSITIVE);
+        let u2 = self.rng.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// Returns a random quantity with the mean, rounded up to the lot size.
+    fn random_qty(&mut self, mean: f64) -> f64 {
+        let qty = -mean * self.rng.next_f64().max(f64::MIN_POSITIVE).ln();
+        (qty / self.gen.lot_size).ceil().max(1.0) * self.gen.lot_size
+    }
+
+    /// Moves the book to the best bid and ask, removing the levels that are no longer within the
+    /// depth and filling the new ones.
+    fn shift_book(&mut self, best_bid: i64, best_ask: i64, kind: u64) {
+        let levels = self.gen.depth_levels as i64;
+        for (side, range) in [
+            (BUY_EVENT, (best_bid - levels + 1)..=best_bid),
+            (SELL_EVENT, best_ask..=(best_ask + levels - 1)),
+        ] {
+            let book = if side == BUY_EVENT {
+                &self.bids
+            } else {
+                &self.asks
+            };
+            let removed: Vec<_> = book
+                .keys()
+                .filter(|tick| !range.contains(tick))
+                .copied()
+                .collect();
+            let added: Vec<_> = range.filter(|tick| !book.contains_key(tick)).collect();
+            for tick in removed {
+                self.book(side).remove(&tick);
+                self.push(DEPTH_EVENT | side, tick as f64 * self.gen.tick_size, 0.0);
+            }
+            for tick in added {
+                let qty = self.random_qty(self.gen.mean_level_qty);
+                self.book(side).insert(tick, qty);
+                self.push(kind | side, tick as f64 * self.gen.tick_size, qty);
+            }
+        }
+    }
+
+    fn book(&mut self, side: u64) -> &mut BTreeMap<i64, f64> {
+        if side == BUY_EVENT {
+            &mut self.bids
+        } else {
+            &mut self.asks
+        }
+    }
+
+    /// Trades at the best price of a random side, refilling the level if it's depleted.
+    fn trade(&mut self) {
+        let (initiator, side) = if self.rng.next_f64() < 0.5 {
+            (BUY_EVENT, SELL_EVENT)
+        } else {
+            (SELL_EVENT, BUY_EVENT)
+        };
+        let (&tick, &level_qty) = if side == BUY_EVENT {
+            self.bids.last_key_value().unwrap()
+        } else {
+            self.asks.first_key_value().unwrap()
+        };
+        let qty = self.random_qty(self.gen.mean_trade_qty).min(level_qty);
+        let px = tick as f64 * self.gen.tick_size;
+        self.push(TRADE_EVENT | initiator, px, qty);
+
+        let mut remaining = level_qty - qty;
+        if remaining < self.gen.lot_size / 2.0 {
+            remaining = self.random_qty(self.gen.mean_level_qty);
+        }
+        self.book(side).insert(tick, remaining);
+        self.push(DEPTH_EVENT | side, px, remaining);
+    }
+
+    /// Changes the quantity of a random level.
+    fn update_level(&mut self) {
+        let side = if self.rng.next_f64() < 0.5 {
+            BUY_EVENT
+        } else {
+            SELL_EVENT
+        };
+        let index = (self.rng.next_f64() * self.gen.depth_levels as f64) as usize;
+        let tick = if side == BUY_EVENT {
+            *self.bids.keys().rev().nth(index).unwrap()
+        } else {
+            *self.asks.keys().nth(index).unwrap()
+        };
+        let qty = self.random_qty(self.gen.mean_level_qty);
+        self.book(side).insert(tick, qty);
+        self.push(DEPTH_EVENT | side, tick as f64 * self.gen.tick_size, qty);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyntheticGenerator;
+    use crate::{
+        backtest::data::{validate::validate, Data},
+        types::{EXCH_EVENT, EXCH_TRADE_EVENT},
+    };
+
+    #[test]
+    fn test_synthetic_generator() {
+        let generator = SyntheticGenerator::new(0.1, 0.01)
+            .seed(1)
+            .initial_price(100.0)
+            .depth_levels(5);
+        let events = generator.generate(10_000_000_000);
+        assert_eq!(events, generator.generate(10_000_000_000));
+        assert_ne!(events, generator.clone().seed(2).generate(10_000_000_000));
+
+        // 10 initial levels with 2 clear events, and at least the base intensity afterwards.
+        assert!(events.len() > 12 + 1000);
+        let trades = events.iter().filter(|ev| ev.is(EXCH_TRADE_EVENT)).count();
+        assert!(trades > 100);
+        assert!(events.iter().all(|ev| ev.ev & EXCH_EVENT != 0));
+
+        let report = validate(&Data::from_data(&events), 0.1);
+        assert!(report.is_valid(), "{report}");
+    }
+}
@@ -0,0 +1,379 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use crate::{
+    backtest::data::Data,
+    types::{
+        Event,
+        BUY_EVENT,
+        DEPTH_BBO_EVENT,
+        DEPTH_CLEAR_EVENT,
+        DEPTH_EVENT,
+        DEPTH_SNAPSHOT_EVENT,
+        EXCH_EVENT,
+        LOCAL_EVENT,
+        SELL_EVENT,
+    },
+};
+
+/// An anomaly found in the feed data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Issue {
+    /// The local timestamp of a local event is earlier than that of the preceding local event.
+    NonMonotonicLocalTimestamp,
+    /// The exchange timestamp of an exchange event is earlier than that of the preceding exchange
+    /// event.
+    NonMonotonicExchTimestamp,
+    /// The exchange timestamp is later than the local timestamp, meaning the feed latency is
+    /// negative.
+    NegativeLatency,
+    /// The event is identical to the preceding event.
+    Duplicate,
+    /// The best bid is at or above the best ask once all local events at the same local timestamp
+    /// are applied.
+    CrossedBook,
+    /// A depth update is received before any snapshot or clear event. If the initial snapshot is
+    /// provided separately, this can be ignored.
+    MissingSnapshot,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Issue::NonMonotonicLocalTimestamp => "non-monotonic local timestamp",
+            Issue::NonMonotonicExchTimestamp => "non-monotonic exchange timestamp",
+            Issue::NegativeLatency => "exchange timestamp later than local timestamp",
+            Issue::Duplicate => "duplicate event",
+            Issue::CrossedBook => "crossed book",
+            Issue::MissingSnapshot => "depth update before snapshot",
+        })
+    }
+}
+
+/// A fix applied by [`repair`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fix {
+    /// The duplicate event is dropped.
+    DroppedDuplicate,
+    /// The local timestamp is raised to the exchange timestamp.
+    ClampedLocalTimestamp,
+    /// The local timestamp is raised to that of the preceding local event.
+    RaisedLocalTimestamp,
+}
+
+impl Display for Fix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Fix::DroppedDuplicate => "dropped duplicate event",
+            Fix::ClampedLocalTimestamp => "clamped local timestamp to exchange timestamp",
+            Fix::RaisedLocalTimestamp => "raised local timestamp to preceding local event",
+        })
+    }
+}
+
+/// The number of occurrences of an issue or a fix, and the index of the first event affected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Occurrences {
+    pub count: usize,
+    pub first_index: usize,
+}
+
+/// The result of [`validate`] or [`repair`].
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    /// The number of events inspected.
+    pub num_events: usize,
+    /// The issues found in the input data.
+    pub issues: BTreeMap<Issue, Occurrences>,
+    /// The fixes applied to the output data. The indices refer to the input data.
+    pub fixes: BTreeMap<Fix, Occurrences>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if no issues are found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the number of occurrences of the issue.
+    pub fn count(&self, issue: Issue) -> usize {
+        self.issues.get(&issue).map(|o| o.count).unwrap_or(0)
+    }
+
+    fn add_issue(&mut self, issue: Issue, index: usize) {
+        add(&mut self.issues, issue, index);
+    }
+
+    fn add_fix(&mut self, fix: Fix, index: usize) {
+        add(&mut self.fixes, fix, index);
+    }
+}
+
+fn add<K: Ord>(map: &mut BTreeMap<K, Occurrences>, key: K, index: usize) {
+    map.entry(key)
+        .and_modify(|o| o.count += 1)
+        .or_insert(Occurrences {
+            count: 1,
+            first_index: index,
+        });
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "events: {}", self.num_events)?;
+        if self.issues.is_empty() {
+            writeln!(f, "no issues found")?;
+        }
+        for (issue, o) in self.issues.iter() {
+            writeln!(
+                f,
+                "issue: {issue}: {} (first at {})",
+                o.count, o.first_index
+            )?;
+        }
+        for (fix, o) in self.fixes.iter() {
+            writeln!(f, "fix: {fix}: {} (first at {})", o.count, o.first_index)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tracks the local market depth to detect crossed books and missing snapshots.
+struct Book {
+    tick_size: f64,
+    bids: BTreeMap<i64, f64>,
+    asks: BTreeMap<i64, f64>,
+    initialized: bool,
+}
+
+impl Book {
+    fn new(tick_size: f64) -> Self {
+        Self {
+            tick_size,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            initialized: false,
+        }
+    }
+
+    /// Applies the local depth event and returns `false` if it's a depth update received before
+    /// any snapshot or clear event.
+    fn apply(&mut self, ev: &Event) -> bool {
+        if ev.is(LOCAL_EVENT | DEPTH_CLEAR_EVENT) {
+            let both = ev.ev & (BUY_EVENT | SELL_EVENT) == 0;
+            if both || ev.ev & BUY_EVENT != 0 {
+                self.bids.clear();
+            }
+            if both || ev.ev & SELL_EVENT != 0 {
+                self.asks.clear();
+            }
+            self.initialized = true;
+            true
+        } else if ev.is(LOCAL_EVENT | DEPTH_SNAPSHOT_EVENT)
+            || ev.is(LOCAL_EVENT | DEPTH_EVENT)
+            || ev.is(LOCAL_EVENT | DEPTH_BBO_EVENT)
+        {
+            let side = if ev.ev & BUY_EVENT != 0 {
+                &mut self.bids
+            } else {
+                &mut self.asks
+            };
+            let price_tick = (ev.px / self.tick_size).round() as i64;
+            if ev.qty > 0.0 {
+                side.insert(price_tick, ev.qty);
+            } else {
+                side.remove(&price_tick);
+            }
+            if ev.is(LOCAL_EVENT | DEPTH_SNAPSHOT_EVENT) {
+                self.initialized = true;
+            }
+            self.initialized
+        } else {
+            true
+        }
+    }
+
+    fn is_crossed(&self) -> bool {
+        match (self.bids.keys().next_back(), self.asks.keys().next()) {
+            (Some(best_bid), Some(best_ask)) => best_bid >= best_ask,
+            _ => false,
+        }
+    }
+}
+
+/// Inspects the events one by one in their original form.
+struct Validator {
+    report: ValidationReport,
+    book: Book,
+    prev: Option<Event>,
+    prev_local_ts: Option<i64>,
+    prev_exch_ts: Option<i64>,
+}
+
+impl Validator {
+    fn new(tick_size: f64) -> Self {
+        Self {
+            report: Default::default(),
+            book: Book::new(tick_size),
+            prev: None,
+            prev_local_ts: None,
+            prev_exch_ts: None,
+        }
+    }
+
+    fn inspect(&mut self, index: usize, ev: &Event) {
+        self.report.num_events += 1;
+        if self.prev.as_ref() == Some(ev) {
+            self.report.add_issue(Issue::Duplicate, index);
+        }
+        if ev.exch_ts > ev.local_ts {
+            self.report.add_issue(Issue::NegativeLatency, index);
+        }
+        if ev.ev & EXCH_EVENT != 0 {
+            if self.prev_exch_ts.is_some_and(|ts| ev.exch_ts < ts) {
+                self.report
+                    .add_issue(Issue::NonMonotonicExchTimestamp, index);
+            }
+            self.prev_exch_ts = Some(ev.exch_ts);
+        }
+        if ev.ev & LOCAL_EVENT != 0 {
+            if let Some(prev_local_ts) = self.prev_local_ts {
+                if ev.local_ts < prev_local_ts {
+                    self.report
+                        .add_issue(Issue::NonMonotonicLocalTimestamp, index);
+                }
+                // The book is checked only once all events at the same timestamp are applied,
+                // since a snapshot or a batch of updates can cross the book in the middle.
+                if ev.local_ts != prev_local_ts && self.book.is_crossed() {
+                    self.report.add_issue(Issue::CrossedBook, index - 1);
+                }
+            }
+            self.prev_local_ts = Some(ev.local_ts);
+            if !self.book.apply(ev) {
+                self.report.add_issue(Issue::MissingSnapshot, index);
+            }
+        }
+        self.prev = Some(ev.clone());
+    }
+
+    fn finish(mut self) -> ValidationReport {
+        if self.book.is_crossed() {
+            self.report
+                .add_issue(Issue::CrossedBook, self.report.num_events - 1);
+        }
+        self.report
+    }
+}
+
+/// Checks the feed data for non-monotonic timestamps, negative feed latencies, duplicate events,
+/// crossed books, and depth updates received before any snapshot.
+pub fn validate(data: &Data<Event>, tick_size: f64) -> ValidationReport {
+    let mut validator = Validator::new(tick_size);
+    for index in 0..data.len() {
+        validator.inspect(index, &data[index]);
+    }
+    validator.finish()
+}
+
+/// Validates the feed data as [`validate`] does and returns the cleaned events along with the
+/// report of the issues found and the fixes applied. Duplicate events are dropped, and the local
+/// timestamps are raised so that they're not earlier than the exchange timestamps and don't go
+/// backwards. The other issues can't be fixed reliably and are only reported.
+pub fn repair(data: &Data<Event>, tick_size: f64) -> (Vec<Event>, ValidationReport) {
+    let mut validator = Validator::new(tick_size);
+    let mut fixes = ValidationReport::default();
+    let mut cleaned = Vec::with_capacity(data.len());
+    let mut prev_local_ts: Option<i64> = None;
+    for index in 0..data.len() {
+        let ev = &data[index];
+        let is_duplicate = validator.prev.as_ref() == Some(ev);
+        validator.inspect(index, ev);
+        if is_duplicate {
+            fixes.add_fix(Fix::DroppedDuplicate, index);
+            continue;
+        }
+
+        let mut ev = ev.clone();
+        if ev.exch_ts > ev.local_ts {
+            ev.local_ts = ev.exch_ts;
+            fixes.add_fix(Fix::ClampedLocalTimestamp, index);
+        }
+        if ev.ev & LOCAL_EVENT != 0 {
+            if let Some(prev_local_ts) = prev_local_ts {
+                if ev.local_ts < prev_local_ts {
+                    ev.local_ts = prev_local_ts;
+                    fixes.add_fix(Fix::RaisedLocalTimestamp, index);
+                }
+            }
+            prev_local_ts = Some(ev.local_ts);
+        }
+        cleaned.push(ev);
+    }
+    let mut report = validator.finish();
+    report.fixes = fixes.fixes;
+    (cleaned, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{repair, validate, Fix, Issue};
+    use crate::{
+        backtest::data::Data,
+        types::{
+            Event,
+            EXCH_EVENT,
+            LOCAL_ASK_DEPTH_EVENT,
+            LOCAL_BID_DEPTH_EVENT,
+            LOCAL_BID_DEPTH_SNAPSHOT_EVENT,
+            LOCAL_EVENT,
+        },
+    };
+
+    fn event(ev: u64, exch_ts: i64, local_ts: i64, px: f64, qty: f64) -> Event {
+        Event {
+            ev: ev | EXCH_EVENT | LOCAL_EVENT,
+            exch_ts,
+            local_ts,
+            px,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_validate_and_repair() {
+        let data = Data::from_data(&[
+            event(LOCAL_BID_DEPTH_EVENT, 1, 2, 100.0, 1.0),
+            event(LOCAL_BID_DEPTH_SNAPSHOT_EVENT, 3, 4, 100.0, 1.0),
+            event(LOCAL_ASK_DEPTH_EVENT, 5, 6, 101.0, 1.0),
+            event(LOCAL_ASK_DEPTH_EVENT, 5, 6, 101.0, 1.0),
+            event(LOCAL_ASK_DEPTH_EVENT, 7, 5, 100.0, 1.0),
+            event(LOCAL_ASK_DEPTH_EVENT, 8, 9, 100.0, 0.0),
+        ]);
+
+        let report = validate(&data, 0.1);
+        assert_eq!(report.num_events, 6);
+        assert_eq!(report.count(Issue::MissingSnapshot), 1);
+        assert_eq!(report.count(Issue::Duplicate), 1);
+        assert_eq!(report.count(Issue::NegativeLatency), 1);
+        assert_eq!(report.count(Issue::NonMonotonicLocalTimestamp), 1);
+        assert_eq!(report.count(Issue::NonMonotonicExchTimestamp), 0);
+        assert_eq!(report.issues[&Issue::CrossedBook].first_index, 4);
+        assert!(report.fixes.is_empty());
+
+        let (cleaned, report) = repair(&data, 0.1);
+        assert_eq!(cleaned.len(), 5);
+        assert_eq!(cleaned[3].local_ts, 7);
+        assert_eq!(report.fixes[&Fix::DroppedDuplicate].first_index, 3);
+        assert_eq!(report.fixes[&Fix::ClampedLocalTimestamp].count, 1);
+        assert!(!report.fixes.contains_key(&Fix::RaisedLocalTimestamp));
+        assert_eq!(
+            validate(&Data::from_data(&cleaned), 0.1).count(Issue::Duplicate),
+            0
+        );
+    }
+}
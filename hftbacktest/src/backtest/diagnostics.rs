@@ -0,0 +1,299 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    fs::File,
+    io::{BufWriter, Error, Write},
+    path::Path,
+    rc::Rc,
+};
+
+use crate::types::{Order, OrderId, Side, Status};
+
+/// The lifetime of a resting order at the exchange, recorded by [`FillDiagnostics`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderDiagnostics {
+    pub order_id: OrderId,
+    pub side: Side,
+    /// The order price. If the order was modified, it's the last price.
+    pub price: f64,
+    pub qty: f64,
+    /// The exchange timestamp at which the order started resting.
+    pub entry_timestamp: i64,
+    /// The exchange timestamp at which the order left the book, or at which it was last observed
+    /// if it's still resting.
+    pub exit_timestamp: i64,
+    /// The estimated quantity ahead of the order when it started resting, if the queue model
+    /// provides it.
+    pub initial_queue_position: Option<f64>,
+    /// The estimated quantity ahead of the order when it was last observed.
+    pub final_queue_position: Option<f64>,
+    /// The total time the order spent at or inside the best price of its side.
+    pub time_at_touch: i64,
+    /// [`Status::Filled`], [`Status::Canceled`] or [`Status::Expired`], or [`Status::New`] if
+    /// the order is still resting.
+    pub outcome: Status,
+    /// The executed quantity.
+    pub exec_qty: f64,
+}
+
+/// A change in the estimated queue position of a resting order, recorded by [`FillDiagnostics`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QueueSample {
+    pub timestamp: i64,
+    pub order_id: OrderId,
+    /// The estimated quantity ahead of the order, if the queue model provides it.
+    pub queue_position: Option<f64>,
+    /// Whether the order is at or inside the best price of its side.
+    pub at_touch: bool,
+}
+
+/// Records, for every order resting at the exchange, its estimated queue position over time, the
+/// time spent at the touch, and its eventual outcome, so that the queue model's realism can be
+/// validated against live fills.
+///
+/// A queue sample is recorded whenever the estimated queue position or whether the order is at
+/// the touch changes. The orders filled immediately on entry never rest, so they're not recorded.
+/// Since every resting order is inspected after each exchange event, this slows down the
+/// backtest, and it's meant to be enabled for diagnosis only.
+///
+/// After the backtest, [`FillDiagnostics::orders`] gives the lifetime and outcome of each order and
+/// [`FillDiagnostics::samples`] the queue position trace, both of which
+/// [`FillDiagnostics::to_csv`] writes for the comparison with the live fills.
+///
+/// ```ignore
+/// let diagnostics = FillDiagnostics::new();
+/// let mut hbt = Backtest::builder()
+///     .add_asset(
+///         L2AssetBuilder::new()
+///             // ...
+///             .fill_diagnostics(diagnostics.clone())
+///             .build()?,
+///     )
+///     .build()?;
+/// strategy(&mut hbt)?;
+/// diagnostics.to_csv("btcusdt_", ".")?;
+/// ```
+#[derive(Clone, Default)]
+pub struct FillDiagnostics(Rc<RefCell<FillDiagnosticsInner>>);
+
+#[derive(Default)]
+struct FillDiagnosticsInner {
+    resting: HashMap<OrderId, Tracked>,
+    closed: Vec<OrderDiagnostics>,
+    samples: Vec<QueueSample>,
+}
+
+struct Tracked {
+    record: OrderDiagnostics,
+    at_touch: bool,
+}
+
+impl Tracked {
+    /// Accumulates the time at the touch until the timestamp.
+    fn elapse(&mut self, timestamp: i64) {
+        if self.at_touch {
+            self.record.time_at_touch += timestamp - self.record.exit_timestamp;
+        }
+        self.record.exit_timestamp = timestamp;
+    }
+}
+
+impl FillDiagnostics {
+    /// Constructs `FillDiagnostics` without any records.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the records of the orders that have rested, in the order they left the book,
+    /// followed by the orders still resting.
+    pub fn orders(&self) -> Vec<OrderDiagnostics> {
+        let inner = self.0.borrow();
+        let mut resting: Vec<_> = inner
+            .resting
+            .values()
+            .map(|tracked| tracked.record.clone())
+            .collect();
+        resting.sort_by_key(|record| (record.entry_timestamp, record.order_id));
+        inner.closed.iter().cloned().chain(resting).collect()
+    }
+
+    /// Returns the queue samples in chronological order.
+    pub fn samples(&self) -> Vec<QueueSample> {
+        self.0.borrow().samples.clone()
+    }
+
+    /// Saves the records into the directory at the specified path, as `{prefix}orders.csv` for
+    /// the order lifetimes and `{prefix}queue.csv` for the queue samples.
+    pub fn to_csv<Prefix, P>(&self, prefix: Prefix, path: P) -> Result<(), Error>
+    where
+        Prefix: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let prefix = prefix.as_ref();
+        let mut file = BufWriter::new(File::create(
+            path.as_ref().join(format!("{prefix}orders.csv")),
+        )?);
+        writeln!(
+            file,
+            "order_id,side,price,qty,entry_timestamp,exit_timestamp,initial_queue_position,\
+            final_queue_position,time_at_touch,outcome,exec_qty"
+        )?;
+        for record in self.orders() {
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                record.order_id,
+                if record.side == Side::Buy {
+                    "buy"
+                } else {
+                    "sell"
+                },
+                record.price,
+                record.qty,
+                record.entry_timestamp,
+                record.exit_timestamp,
+                optional(record.initial_queue_position),
+                optional(record.final_queue_position),
+                record.time_at_touch,
+                match record.outcome {
+                    Status::Filled => "filled",
+                    Status::Canceled => "canceled",
+                    Status::Expired => "expired",
+                    _ => "resting",
+                },
+                record.exec_qty,
+            )?;
+        }
+        file.flush()?;
+
+        let mut file = BufWriter::new(File::create(
+            path.as_ref().join(format!("{prefix}queue.csv")),
+        )?);
+        writeln!(file, "timestamp,order_id,queue_position,at_touch")?;
+        for sample in self.0.borrow().samples.iter() {
+            writeln!(
+                file,
+                "{},{},{},{}",
+                sample.timestamp,
+                sample.order_id,
+                optional(sample.queue_position),
+                sample.at_touch as u8,
+            )?;
+        }
+        file.flush()
+    }
+
+    /// Observes the resting order at the exchange.
+    pub(crate) fn observe(
+        &self,
+        order: &Order,
+        queue_position: Option<f64>,
+        at_touch: bool,
+        timestamp: i64,
+    ) {
+        let mut inner = self.0.borrow_mut();
+        let inner = &mut *inner;
+        let changed = match inner.resting.entry(order.order_id) {
+            Entry::Vacant(entry) => {
+                entry.insert(Tracked {
+                    record: OrderDiagnostics {
+                        order_id: order.order_id,
+                        side: order.side,
+                        price: order.price_tick as f64 * order.tick_size,
+                        qty: order.qty,
+                        entry_timestamp: timestamp,
+                        exit_timestamp: timestamp,
+                        initial_queue_position: queue_position,
+                        final_queue_position: queue_position,
+                        time_at_touch: 0,
+                        outcome: Status::New,
+                        exec_qty: 0.0,
+                    },
+                    at_touch,
+                });
+                true
+            }
+            Entry::Occupied(mut entry) => {
+                let tracked = entry.get_mut();
+                tracked.elapse(timestamp);
+                let changed = tracked.at_touch != at_touch
+                    || tracked.record.final_queue_position != queue_position;
+                tracked.at_touch = at_touch;
+                tracked.record.final_queue_position = queue_position;
+                tracked.record.price = order.price_tick as f64 * order.tick_size;
+                tracked.record.qty = order.qty;
+                tracked.record.exec_qty = order.qty - order.leaves_qty;
+                changed
+            }
+        };
+        if changed {
+            inner.samples.push(QueueSample {
+                timestamp,
+                order_id: order.order_id,
+                queue_position,
+                at_touch,
+            });
+        }
+    }
+
+    /// Records the outcome of the order leaving the book, if it has rested.
+    pub(crate) fn close(&self, order: &Order, timestamp: i64) {
+        let mut inner = self.0.borrow_mut();
+        if let Some(mut tracked) = inner.resting.remove(&order.order_id) {
+            tracked.elapse(timestamp);
+            tracked.record.outcome = order.status;
+            tracked.record.exec_qty = order.qty - order.leaves_qty;
+            inner.closed.push(tracked.record);
+        }
+    }
+}
+
+fn optional(value: Option<f64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FillDiagnostics;
+    use crate::types::{OrdType, Order, Side, Status, TimeInForce};
+
+    #[test]
+    fn test_fill_diagnostics() {
+        let diagnostics = FillDiagnostics::new();
+        let mut order = Order::new(
+            1,
+            1000,
+            0.1,
+            2.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        diagnostics.observe(&order, Some(5.0), false, 100);
+        // Unchanged, so no sample is recorded.
+        diagnostics.observe(&order, Some(5.0), false, 110);
+        diagnostics.observe(&order, Some(5.0), true, 120);
+        diagnostics.observe(&order, Some(2.0), true, 150);
+        order.status = Status::Filled;
+        order.leaves_qty = 0.0;
+        diagnostics.close(&order, 170);
+        // An order that has never rested is ignored.
+        diagnostics.close(&order, 180);
+
+        let orders = diagnostics.orders();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].entry_timestamp, 100);
+        assert_eq!(orders[0].exit_timestamp, 170);
+        assert_eq!(orders[0].initial_queue_position, Some(5.0));
+        assert_eq!(orders[0].final_queue_position, Some(2.0));
+        assert_eq!(orders[0].time_at_touch, 50);
+        assert_eq!(orders[0].outcome, Status::Filled);
+        assert_eq!(orders[0].exec_qty, 2.0);
+        let samples: Vec<_> = diagnostics
+            .samples()
+            .iter()
+            .map(|sample| sample.timestamp)
+            .collect();
+        assert_eq!(samples, vec![100, 120, 150]);
+    }
+}
@@ -0,0 +1,548 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::Path,
+};
+
+use serde_json::Value as JsonValue;
+
+use crate::{
+    backtest::diagnostics::FillDiagnostics,
+    depth::MarketDepth,
+    types::{Bot, OrdType, OrderId, Side, Status, TimeInForce},
+};
+
+/// An order request recorded in the live session journal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalRequest {
+    pub asset_no: usize,
+    /// The local timestamp at which the request was sent.
+    pub timestamp: i64,
+    pub order_id: OrderId,
+    /// [`Status::New`] for a new order, or [`Status::Canceled`] for a cancel.
+    pub req: Status,
+    pub side: Side,
+    pub price: f64,
+    pub qty: f64,
+    pub time_in_force: TimeInForce,
+    pub order_type: OrdType,
+}
+
+/// A fill, either recorded in the live session journal or executed in the replay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillRecord {
+    pub asset_no: usize,
+    /// The exchange timestamp of the fill.
+    pub timestamp: i64,
+    pub order_id: OrderId,
+    pub side: Side,
+    pub price: f64,
+    pub qty: f64,
+}
+
+/// The order requests and fills of a live session, read from the journal written by
+/// [`LiveBotBuilder::journal`](crate::live::LiveBotBuilder::journal).
+#[derive(Clone, Debug, Default)]
+pub struct LiveSession {
+    pub requests: Vec<JournalRequest>,
+    pub fills: Vec<FillRecord>,
+}
+
+impl LiveSession {
+    /// Reads the live session from the journal file. The order responses and errors are skipped,
+    /// as are the requests other than new orders and cancels.
+    pub fn from_journal<P: AsRef<Path>>(path: P) -> Result<Self, IoError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    fn parse(journal: &str) -> Result<Self, IoError> {
+        let mut session = Self::default();
+        for line in journal.lines().filter(|line| !line.trim().is_empty()) {
+            let record: JsonValue = serde_json::from_str(line)
+                .map_err(|error| IoError::new(ErrorKind::InvalidData, error))?;
+            match record["type"].as_str() {
+                Some("order_request") => {
+                    let order = &record["order"];
+                    let req = match order["req"].as_str() {
+                        Some("New") => Status::New,
+                        Some("Canceled") => Status::Canceled,
+                        _ => continue,
+                    };
+                    // The local timestamp is set on the order when it's submitted.
+                    let timestamp = match order["local_ts"].as_i64() {
+                        Some(local_ts) if local_ts > 0 => local_ts,
+                        _ => int(&record, "ts")?,
+                    };
+                    session.requests.push(JournalRequest {
+                        asset_no: int(&record, "asset_no")? as usize,
+                        timestamp,
+                        order_id: int(order, "order_id")? as OrderId,
+                        req,
+                        side: side(order)?,
+                        price: float(order, "price")?,
+                        qty: float(order, "qty")?,
+                        time_in_force: match order["time_in_force"].as_str() {
+                            Some("GTC") => TimeInForce::GTC,
+                            Some("GTX") => TimeInForce::GTX,
+                            Some("FOK") => TimeInForce::FOK,
+                            Some("IOC") => TimeInForce::IOC,
+                            _ => TimeInForce::Unsupported,
+                        },
+                        order_type: match order["order_type"].as_str() {
+                            Some("Limit") => OrdType::Limit,
+                            Some("Market") => OrdType::Market,
+                            _ => OrdType::Unsupported,
+                        },
+                    });
+                }
+                Some("fill") => {
+                    session.fills.push(FillRecord {
+                        asset_no: int(&record, "asset_no")? as usize,
+                        timestamp: int(&record, "exch_ts")?,
+                        order_id: int(&record, "order_id")? as OrderId,
+                        side: side(&record)?,
+                        price: float(&record, "exec_price")?,
+                        qty: float(&record, "exec_qty")?,
+                    });
+                }
+                _ => {}
+            }
+        }
+        session.requests.sort_by_key(|request| request.timestamp);
+        Ok(session)
+    }
+}
+
+fn invalid(field: &str) -> IoError {
+    IoError::new(
+        ErrorKind::InvalidData,
+        format!("invalid journal field `{field}`"),
+    )
+}
+
+fn int(value: &JsonValue, field: &str) -> Result<i64, IoError> {
+    value[field].as_i64().ok_or_else(|| invalid(field))
+}
+
+fn float(value: &JsonValue, field: &str) -> Result<f64, IoError> {
+    value[field].as_f64().ok_or_else(|| invalid(field))
+}
+
+fn side(value: &JsonValue) -> Result<Side, IoError> {
+    match value["side"].as_str() {
+        Some("Buy") => Ok(Side::Buy),
+        Some("Sell") => Ok(Side::Sell),
+        _ => Err(invalid("side")),
+    }
+}
+
+/// Replays the order requests of a live session in the backtester and compares the resulting
+/// fills, queue positions, and PnL against the live session, to calibrate the queue and latency
+/// models.
+///
+/// The requests are submitted to the backtest at the local timestamps at which they were sent
+/// live, with the same order IDs, so the backtest should be built on the market data recorded
+/// during the session. The replayed fills are collected from the orders' state between the
+/// requests, so the partial fills of an order between two requests are merged into one.
+///
+/// If [`FillDiagnostics`] is attached to an asset, the divergence of each order includes the
+/// backtest's estimated queue position at the time the order was filled live.
+///
+/// ```ignore
+/// let session = LiveSession::from_journal("journal_20240809.jsonl")?;
+/// let diagnostics = FillDiagnostics::new();
+/// let mut hbt = Backtest::builder()
+///     .add_asset(
+///         L2AssetBuilder::new()
+///             .data(vec![DataSource::File("btcusdt_20240809.npz".to_string())])
+///             .fill_diagnostics(diagnostics.clone())
+///             // ...
+///             .build()?,
+///     )
+///     .build()?;
+/// let report = DivergenceAnalyzer::new(session)
+///     .fill_diagnostics(0, diagnostics)
+///     .run(&mut hbt)?;
+/// println!("{report}");
+/// ```
+pub struct DivergenceAnalyzer {
+    session: LiveSession,
+    diagnostics: Vec<(usize, FillDiagnostics)>,
+}
+
+impl DivergenceAnalyzer {
+    /// Constructs a `DivergenceAnalyzer` for the live session.
+    pub fn new(session: LiveSession) -> Self {
+        Self {
+            session,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Attaches the [`FillDiagnostics`] set on the asset's builder, to report the estimated
+    /// queue positions.
+    pub fn fill_diagnostics(mut self, asset_no: usize, diagnostics: FillDiagnostics) -> Self {
+        self.diagnostics.push((asset_no, diagnostics));
+        self
+    }
+
+    /// Replays the live session's requests in the backtest until the last recorded event, and
+    /// returns the divergence report.
+    pub fn run<MD, I>(&self, bot: &mut I) -> Result<DivergenceReport, I::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let end = self
+            .session
+            .requests
+            .iter()
+            .map(|request| request.timestamp)
+            .chain(self.session.fills.iter().map(|fill| fill.timestamp))
+            .max()
+            .unwrap_or(0);
+        let mut collector = FillCollector::default();
+        for request in &self.session.requests {
+            if request.asset_no >= bot.num_assets() {
+                continue;
+            }
+            let duration = request.timestamp - bot.current_timestamp();
+            if duration > 0 && !bot.elapse(duration)? {
+                break;
+            }
+            collector.collect(bot);
+            match request.req {
+                Status::New => {
+                    if request.side == Side::Buy {
+                        bot.submit_buy_order(
+                            request.asset_no,
+                            request.order_id,
+                            request.price,
+                            request.qty,
+                            request.time_in_force,
+                            request.order_type,
+                            false,
+                        )?;
+                    } else {
+                        bot.submit_sell_order(
+                            request.asset_no,
+                            request.order_id,
+                            request.price,
+                            request.qty,
+                            request.time_in_force,
+                            request.order_type,
+                            false,
+                        )?;
+                    }
+                }
+                _ => {
+                    bot.cancel(request.asset_no, request.order_id, false)?;
+                }
+            }
+        }
+        let duration = end - bot.current_timestamp();
+        if duration > 0 {
+            bot.elapse(duration)?;
+        }
+        collector.collect(bot);
+
+        let marks: Vec<_> = (0..bot.num_assets())
+            .map(|asset_no| bot.depth(asset_no).mid())
+            .collect();
+        Ok(self.compare(&collector.fills, &marks))
+    }
+
+    fn compare(&self, backtest_fills: &[FillRecord], marks: &[f64]) -> DivergenceReport {
+        let mut orders = Vec::new();
+        let mut index = HashMap::new();
+        let mut num_requested = 0;
+        for request in &self.session.requests {
+            if request.req == Status::New {
+                num_requested += 1;
+                slot(&mut orders, &mut index, request.asset_no, request.order_id);
+            }
+        }
+        for fill in &self.session.fills {
+            let i = slot(&mut orders, &mut index, fill.asset_no, fill.order_id);
+            orders[i].live.add(fill);
+        }
+        for fill in backtest_fills {
+            let i = slot(&mut orders, &mut index, fill.asset_no, fill.order_id);
+            orders[i].backtest.add(fill);
+        }
+
+        for order in orders.iter_mut() {
+            let (Some(live_timestamp), Some(diagnostics)) = (
+                order.live.first_timestamp,
+                self.diagnostics
+                    .iter()
+                    .find(|(asset_no, _)| *asset_no == order.asset_no)
+                    .map(|(_, diagnostics)| diagnostics),
+            ) else {
+                continue;
+            };
+            order.queue_position_at_live_fill = diagnostics
+                .samples()
+                .iter()
+                .rev()
+                .find(|sample| {
+                    sample.order_id == order.order_id && sample.timestamp <= live_timestamp
+                })
+                .and_then(|sample| sample.queue_position);
+        }
+
+        let pnl = |fills: &[FillRecord]| {
+            let mut cash = vec![0.0; marks.len()];
+            let mut position = vec![0.0; marks.len()];
+            for fill in fills.iter().filter(|fill| fill.asset_no < marks.len()) {
+                let sign = if fill.side == Side::Buy { 1.0 } else { -1.0 };
+                cash[fill.asset_no] -= sign * fill.qty * fill.price;
+                position[fill.asset_no] += sign * fill.qty;
+            }
+            (0..marks.len())
+                .filter(|&asset_no| position[asset_no] != 0.0 || cash[asset_no] != 0.0)
+                .map(|asset_no| cash[asset_no] + position[asset_no] * marks[asset_no])
+                .sum()
+        };
+        DivergenceReport {
+            num_requested,
+            live_pnl: pnl(&self.session.fills),
+            backtest_pnl: pnl(backtest_fills),
+            orders,
+        }
+    }
+}
+
+/// Returns the index of the order's divergence, adding it if it's not seen yet.
+fn slot(
+    orders: &mut Vec<OrderDivergence>,
+    index: &mut HashMap<(usize, OrderId), usize>,
+    asset_no: usize,
+    order_id: OrderId,
+) -> usize {
+    *index.entry((asset_no, order_id)).or_insert_with(|| {
+        orders.push(OrderDivergence::new(asset_no, order_id));
+        orders.len() - 1
+    })
+}
+
+/// Collects the fills from the changes in the orders' executed quantity.
+#[derive(Default)]
+struct FillCollector {
+    filled: HashMap<(usize, OrderId), f64>,
+    fills: Vec<FillRecord>,
+}
+
+impl FillCollector {
+    fn collect<MD, I>(&mut self, bot: &I)
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        for asset_no in 0..bot.num_assets() {
+            let mut orders: Vec<_> = bot.orders(asset_no).values().collect();
+            orders.sort_by_key(|order| (order.exch_timestamp, order.order_id));
+            for order in orders {
+                let filled_qty = order.qty - order.leaves_qty;
+                let prev = self.filled.entry((asset_no, order.order_id)).or_default();
+                if filled_qty > *prev
+                    && matches!(order.status, Status::Filled | Status::PartiallyFilled)
+                {
+                    self.fills.push(FillRecord {
+                        asset_no,
+                        timestamp: order.exch_timestamp,
+                        order_id: order.order_id,
+                        side: order.side,
+                        price: order.exec_price(),
+                        qty: filled_qty - *prev,
+                    });
+                    *prev = filled_qty;
+                }
+            }
+        }
+    }
+}
+
+/// The fills of an order on one side of the comparison.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FillSummary {
+    pub filled_qty: f64,
+    /// The average fill price, which is `NaN` if the order isn't filled.
+    pub avg_price: f64,
+    pub first_timestamp: Option<i64>,
+}
+
+impl FillSummary {
+    fn add(&mut self, fill: &FillRecord) {
+        let value = if self.filled_qty > 0.0 {
+            self.avg_price * self.filled_qty
+        } else {
+            0.0
+        };
+        self.filled_qty += fill.qty;
+        self.avg_price = (value + fill.price * fill.qty) / self.filled_qty;
+        self.first_timestamp = Some(
+            self.first_timestamp
+                .map_or(fill.timestamp, |ts| ts.min(fill.timestamp)),
+        );
+    }
+}
+
+/// The divergence of an order between the live session and the replay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderDivergence {
+    pub asset_no: usize,
+    pub order_id: OrderId,
+    pub live: FillSummary,
+    pub backtest: FillSummary,
+    /// The backtest's estimated quantity ahead of the order when it was first filled live, if
+    /// [`FillDiagnostics`] is attached.
+    pub queue_position_at_live_fill: Option<f64>,
+}
+
+impl OrderDivergence {
+    fn new(asset_no: usize, order_id: OrderId) -> Self {
+        Self {
+            asset_no,
+            order_id,
+            live: FillSummary {
+                avg_price: f64::NAN,
+                ..Default::default()
+            },
+            backtest: FillSummary {
+                avg_price: f64::NAN,
+                ..Default::default()
+            },
+            queue_position_at_live_fill: None,
+        }
+    }
+
+    /// Returns the time by which the backtest filled the order later than the live session, if
+    /// both filled it. A negative value means that the backtest filled it earlier.
+    pub fn fill_delay(&self) -> Option<i64> {
+        Some(self.backtest.first_timestamp? - self.live.first_timestamp?)
+    }
+
+    /// Returns `true` if the filled quantities differ.
+    pub fn is_divergent(&self) -> bool {
+        (self.live.filled_qty - self.backtest.filled_qty).abs() > 1e-9
+    }
+}
+
+/// The report of [`DivergenceAnalyzer`]. The PnL is the sum of the cash flows of the fills and
+/// the resulting positions marked at the mid price at the end of the replay, excluding fees.
+#[derive(Clone, Debug)]
+pub struct DivergenceReport {
+    /// The number of new orders requested.
+    pub num_requested: usize,
+    /// The orders that were requested or filled, in the order first seen.
+    pub orders: Vec<OrderDivergence>,
+    pub live_pnl: f64,
+    pub backtest_pnl: f64,
+}
+
+impl DivergenceReport {
+    /// Returns the orders filled live but not in the backtest.
+    pub fn live_only(&self) -> impl Iterator<Item = &OrderDivergence> {
+        self.orders
+            .iter()
+            .filter(|order| order.live.filled_qty > 0.0 && order.backtest.filled_qty == 0.0)
+    }
+
+    /// Returns the orders filled in the backtest but not live.
+    pub fn backtest_only(&self) -> impl Iterator<Item = &OrderDivergence> {
+        self.orders
+            .iter()
+            .filter(|order| order.backtest.filled_qty > 0.0 && order.live.filled_qty == 0.0)
+    }
+
+    /// Returns the mean of the fill delays of the orders filled on both sides.
+    pub fn mean_fill_delay(&self) -> Option<f64> {
+        let delays: Vec<_> = self
+            .orders
+            .iter()
+            .filter_map(|order| order.fill_delay())
+            .collect();
+        if delays.is_empty() {
+            None
+        } else {
+            Some(delays.iter().sum::<i64>() as f64 / delays.len() as f64)
+        }
+    }
+}
+
+impl Display for DivergenceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let filled = |qty: fn(&OrderDivergence) -> f64| {
+            self.orders.iter().filter(|order| qty(order) > 0.0).count()
+        };
+        writeln!(f, "requested orders: {}", self.num_requested)?;
+        writeln!(
+            f,
+            "filled orders: live {}, backtest {}, live only {}, backtest only {}",
+            filled(|order| order.live.filled_qty),
+            filled(|order| order.backtest.filled_qty),
+            self.live_only().count(),
+            self.backtest_only().count()
+        )?;
+        writeln!(
+            f,
+            "divergent orders: {}",
+            self.orders
+                .iter()
+                .filter(|order| order.is_divergent())
+                .count()
+        )?;
+        if let Some(delay) = self.mean_fill_delay() {
+            writeln!(f, "mean fill delay: {delay:.0}")?;
+        }
+        write!(
+            f,
+            "pnl: live {:.6}, backtest {:.6}, difference {:+.6}",
+            self.live_pnl,
+            self.backtest_pnl,
+            self.backtest_pnl - self.live_pnl
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DivergenceAnalyzer, FillRecord, LiveSession};
+    use crate::types::{Side, Status};
+
+    #[test]
+    fn test_divergence() {
+        let journal = r#"
+{"type":"order_request","ts":90,"asset_no":0,"symbol":"BTCUSDT","order":{"order_id":1,"side":"Buy","price":100.0,"qty":1.0,"req":"New","time_in_force":"GTX","order_type":"Limit","local_ts":100}}
+{"type":"order_request","ts":150,"asset_no":0,"symbol":"BTCUSDT","order":{"order_id":2,"side":"Sell","price":102.0,"qty":1.0,"req":"New","time_in_force":"GTC","order_type":"Limit","local_ts":0}}
+{"type":"order_response","ts":160,"asset_no":0,"symbol":"BTCUSDT","order":{"order_id":1}}
+{"type":"fill","ts":210,"asset_no":0,"symbol":"BTCUSDT","order_id":1,"side":"Buy","exec_price":100.0,"exec_qty":1.0,"leaves_qty":0.0,"maker":true,"exch_ts":200}
+{"type":"error","ts":220,"kind":"OrderError","value":"None"}
+"#;
+        let session = LiveSession::parse(journal).unwrap();
+        assert_eq!(session.requests.len(), 2);
+        assert_eq!(session.requests[0].timestamp, 100);
+        assert_eq!(session.requests[1].timestamp, 150);
+        assert_eq!(session.requests[1].req, Status::New);
+        assert_eq!(session.fills.len(), 1);
+
+        let backtest_fills = [FillRecord {
+            asset_no: 0,
+            timestamp: 230,
+            order_id: 2,
+            side: Side::Sell,
+            price: 102.0,
+            qty: 1.0,
+        }];
+        let report = DivergenceAnalyzer::new(session).compare(&backtest_fills, &[101.0]);
+        assert_eq!(report.num_requested, 2);
+        assert_eq!(report.live_only().next().unwrap().order_id, 1);
+        assert_eq!(report.backtest_only().next().unwrap().order_id, 2);
+        assert_eq!(report.mean_fill_delay(), None);
+        assert_eq!(report.live_pnl, 1.0);
+        assert_eq!(report.backtest_pnl, 1.0);
+    }
+}
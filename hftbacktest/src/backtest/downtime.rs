@@ -0,0 +1,139 @@
+use std::io::Error as IoError;
+
+use crate::{
+    backtest::data::{Data, DataPreprocess},
+    types::{Event, LOCAL_EVENT},
+};
+
+/// The exchange's downtime windows, such as scheduled maintenance, during which the exchange
+/// rejects all order requests and stops publishing the feed.
+///
+/// The feed events whose local timestamp falls within a window are held back and delivered at the
+/// end of the window, so the local sees the stale market depth during the downtime and catches up
+/// once the exchange is back. The orders resting at the exchange are kept.
+///
+/// Keep a clone of the downtime to look up the upcoming window in the strategy, so that it can
+/// flatten the position in advance.
+///
+/// ```
+/// use hftbacktest::backtest::downtime::Downtime;
+///
+/// const HOUR: i64 = 3_600_000_000_000;
+///
+/// // An hour of maintenance every week, starting from the given timestamp, for 4 weeks.
+/// let downtime = Downtime::new().recurring(1_700_000_000_000_000_000, HOUR, 7 * 24 * HOUR, 4);
+/// assert!(downtime.is_down(1_700_000_000_000_000_000 + HOUR / 2));
+/// assert_eq!(
+///     downtime.next_window(1_700_000_000_000_000_000 + HOUR),
+///     Some((
+///         1_700_000_000_000_000_000 + 7 * 24 * HOUR,
+///         1_700_000_000_000_000_000 + 7 * 24 * HOUR + HOUR
+///     ))
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Downtime {
+    windows: Vec<(i64, i64)>,
+}
+
+impl Downtime {
+    /// Constructs `Downtime` without any windows.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a downtime window from `start` until `end`.
+    pub fn window(mut self, start: i64, end: i64) -> Self {
+        assert!(start < end);
+        self.windows.push((start, end));
+        self.windows.sort();
+        self
+    }
+
+    /// Adds `count` downtime windows of the `duration`, repeating every `interval` from `start`.
+    pub fn recurring(mut self, start: i64, duration: i64, interval: i64, count: usize) -> Self {
+        assert!(duration > 0 && interval >= duration);
+        for i in 0..count as i64 {
+            let start = start + i * interval;
+            self.windows.push((start, start + duration));
+        }
+        self.windows.sort();
+        self
+    }
+
+    /// Returns the downtime windows in chronological order.
+    pub fn windows(&self) -> &[(i64, i64)] {
+        &self.windows
+    }
+
+    /// Returns `true` if the exchange is down at the timestamp.
+    pub fn is_down(&self, timestamp: i64) -> bool {
+        self.window_at(timestamp).is_some()
+    }
+
+    /// Returns the window the exchange is down in at the timestamp, or the next one if it's up.
+    pub fn next_window(&self, timestamp: i64) -> Option<(i64, i64)> {
+        self.windows
+            .iter()
+            .find(|&&(_, end)| timestamp < end)
+            .copied()
+    }
+
+    fn window_at(&self, timestamp: i64) -> Option<(i64, i64)> {
+        self.next_window(timestamp)
+            .filter(|&(start, _)| start <= timestamp)
+    }
+}
+
+impl DataPreprocess<Event> for Downtime {
+    fn preprocess(&self, data: &mut Data<Event>) -> Result<(), IoError> {
+        for i in 0..data.len() {
+            let ev = &mut data[i];
+            if !ev.is(LOCAL_EVENT) {
+                continue;
+            }
+            if let Some((_, end)) = self.window_at(ev.local_ts) {
+                ev.local_ts = end;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Downtime;
+    use crate::{
+        backtest::data::{Data, DataPreprocess},
+        types::{Event, EXCH_EVENT, LOCAL_EVENT},
+    };
+
+    #[test]
+    fn test_downtime() {
+        let downtime = Downtime::new().recurring(100, 50, 200, 2).window(20, 30);
+        assert_eq!(downtime.windows(), &[(20, 30), (100, 150), (300, 350)]);
+        assert!(!downtime.is_down(19));
+        assert!(downtime.is_down(20));
+        assert!(!downtime.is_down(150));
+        assert_eq!(downtime.next_window(150), Some((300, 350)));
+        assert_eq!(downtime.next_window(350), None);
+
+        let events: Vec<_> = [90, 120, 140, 160]
+            .iter()
+            .map(|&ts| Event {
+                ev: EXCH_EVENT | LOCAL_EVENT,
+                exch_ts: ts,
+                local_ts: ts + 5,
+                px: 0.0,
+                qty: 0.0,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            })
+            .collect();
+        let mut data = Data::from_data(&events);
+        downtime.preprocess(&mut data).unwrap();
+        let local_ts: Vec<_> = (0..data.len()).map(|i| data[i].local_ts).collect();
+        assert_eq!(local_ts, vec![95, 150, 150, 165]);
+    }
+}
@@ -1,6 +1,12 @@
 use std::mem;
 
-use crate::utils::{AlignedArray, CACHE_LINE_SIZE};
+use crate::{
+    backtest::{
+        checkpoint::{checkpoint_error, CheckpointReader, CheckpointWriter},
+        BacktestError,
+    },
+    utils::{AlignedArray, CACHE_LINE_SIZE},
+};
 
 #[derive(Clone, Copy)]
 #[repr(C, align(32))]
@@ -109,4 +115,22 @@ impl EventSet {
     pub fn invalidate_exch_data(&mut self, asset_no: usize) {
         self.invalidate(4 * asset_no + 2);
     }
+
+    /// Writes the event timestamps into the checkpoint.
+    pub fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        writer.write(&(self.timestamp[..].to_vec(), self.invalid))
+    }
+
+    /// Restores the event timestamps from the checkpoint.
+    pub fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        let (timestamp, invalid): (Vec<i64>, usize) = reader.read()?;
+        if timestamp.len() != self.num_assets * 4 {
+            return Err(checkpoint_error("the number of assets doesn't match"));
+        }
+        for (i, ts) in timestamp.into_iter().enumerate() {
+            self.timestamp[i] = ts;
+        }
+        self.invalid = invalid;
+        Ok(())
+    }
 }
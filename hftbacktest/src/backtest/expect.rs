@@ -0,0 +1,388 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
+
+use thiserror::Error;
+
+use crate::{
+    depth::{MarketDepth, INVALID_MAX, INVALID_MIN},
+    types::{Bot, Order, OrderId, Recorder, Side, StateValues, Status},
+};
+
+/// The view of an asset's state at a check point, on which the [`Expectation`]s are evaluated.
+pub struct Context<'a> {
+    pub timestamp: i64,
+    pub asset_no: usize,
+    pub best_bid_tick: i64,
+    pub best_ask_tick: i64,
+    pub tick_size: f64,
+    pub state_values: &'a StateValues,
+    pub orders: &'a HashMap<OrderId, Order>,
+}
+
+impl Context<'_> {
+    /// Returns whether the market depth has both the best bid and the best ask.
+    pub fn has_bbo(&self) -> bool {
+        self.best_bid_tick != INVALID_MIN && self.best_ask_tick != INVALID_MAX
+    }
+}
+
+/// Provides a condition that must hold throughout a backtest run.
+pub trait Expectation {
+    /// Returns the name of the expectation, which is shown in the failure diagnostics.
+    fn name(&self) -> &str;
+
+    /// Checks the condition, returning the description of the violation if it doesn't hold.
+    fn check(&mut self, ctx: &Context) -> Option<String>;
+}
+
+/// A violation of an [`Expectation`].
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub name: String,
+    pub timestamp: i64,
+    pub asset_no: usize,
+    pub description: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] asset {} at {}: {}",
+            self.name, self.asset_no, self.timestamp, self.description
+        )
+    }
+}
+
+/// Error returned when the [`Expectation`]s are violated, listing the violations.
+#[derive(Error, Debug)]
+#[error("{} expectation violation(s)\n{}", num_violations, list(violations))]
+pub struct ExpectationError {
+    /// The total number of violations, including the ones not kept in `violations`.
+    pub num_violations: usize,
+    pub violations: Vec<Violation>,
+}
+
+fn list(violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(|violation| format!("  {violation}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Expects that the own buy orders are always priced below the own sell orders.
+pub struct NoCrossedQuotes;
+
+impl Expectation for NoCrossedQuotes {
+    fn name(&self) -> &str {
+        "no_crossed_quotes"
+    }
+
+    fn check(&mut self, ctx: &Context) -> Option<String> {
+        let active = || ctx.orders.values().filter(|order| order.active());
+        let highest_bid = active()
+            .filter(|order| order.side == Side::Buy)
+            .max_by_key(|order| order.price_tick)?;
+        let lowest_ask = active()
+            .filter(|order| order.side == Side::Sell)
+            .min_by_key(|order| order.price_tick)?;
+        (highest_bid.price_tick >= lowest_ask.price_tick).then(|| {
+            format!(
+                "buy order {} at {} crosses sell order {} at {}",
+                highest_bid.order_id,
+                highest_bid.price(),
+                lowest_ask.order_id,
+                lowest_ask.price()
+            )
+        })
+    }
+}
+
+/// Expects that the absolute position never exceeds the given maximum.
+pub struct MaxPosition(pub f64);
+
+impl Expectation for MaxPosition {
+    fn name(&self) -> &str {
+        "max_position"
+    }
+
+    fn check(&mut self, ctx: &Context) -> Option<String> {
+        let position = ctx.state_values.position;
+        (position.abs() > self.0)
+            .then(|| format!("position {position} exceeds the maximum {}", self.0))
+    }
+}
+
+/// Expects that every order request is responded to within the given time since it was sent. The
+/// unit should be the same as the data's timestamp unit.
+pub struct AckWithin(pub i64);
+
+impl Expectation for AckWithin {
+    fn name(&self) -> &str {
+        "ack_within"
+    }
+
+    fn check(&mut self, ctx: &Context) -> Option<String> {
+        let order = ctx
+            .orders
+            .values()
+            .filter(|order| order.req != Status::None)
+            .min_by_key(|order| order.local_timestamp)?;
+        let elapsed = ctx.timestamp - order.local_timestamp;
+        (elapsed > self.0).then(|| {
+            format!(
+                "order {} has been waiting for the {:?} response for {elapsed}, longer than {}",
+                order.order_id, order.req, self.0
+            )
+        })
+    }
+}
+
+/// Expects the custom condition given as a closure.
+pub struct Condition<F> {
+    name: String,
+    condition: F,
+}
+
+impl<F> Condition<F>
+where
+    F: FnMut(&Context) -> Option<String>,
+{
+    /// Constructs `Condition`.
+    pub fn new(name: &str, condition: F) -> Self {
+        Self {
+            name: name.to_string(),
+            condition,
+        }
+    }
+}
+
+impl<F> Expectation for Condition<F>
+where
+    F: FnMut(&Context) -> Option<String>,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check(&mut self, ctx: &Context) -> Option<String> {
+        (self.condition)(ctx)
+    }
+}
+
+/// Checks the [`Expectation`]s over a backtest run, so that a strategy regression test can assert
+/// that they hold throughout the run.
+///
+/// As this implements [`Recorder`], it can be passed where the strategy records its state, or
+/// [`check`](Self::check) can be called directly at the desired points. Then,
+/// [`verify`](Self::verify) returns the diagnostics of the violations.
+///
+/// ```ignore
+/// let mut expect = Expectations::new()
+///     .expect(NoCrossedQuotes)
+///     .expect(MaxPosition(10.0))
+///     .expect(AckWithin(100_000_000));
+///
+/// strategy(&mut hbt, &mut expect)?;
+/// expect.verify().unwrap();
+/// ```
+pub struct Expectations {
+    expectations: Vec<Box<dyn Expectation>>,
+    violations: Vec<Violation>,
+    num_violations: usize,
+    max_violations: usize,
+    fail_fast: bool,
+}
+
+impl Expectations {
+    /// Constructs an instance of `Expectations`.
+    pub fn new() -> Self {
+        Self {
+            expectations: Vec::new(),
+            violations: Vec::new(),
+            num_violations: 0,
+            max_violations: 20,
+            fail_fast: false,
+        }
+    }
+
+    /// Adds an expectation.
+    pub fn expect<E: Expectation + 'static>(mut self, expectation: E) -> Self {
+        self.expectations.push(Box::new(expectation));
+        self
+    }
+
+    /// Sets the maximum number of violations kept for the diagnostics. The default value is `20`.
+    pub fn max_violations(self, max_violations: usize) -> Self {
+        Self {
+            max_violations,
+            ..self
+        }
+    }
+
+    /// Sets whether [`check`](Self::check) returns an error on the first violation, which stops
+    /// the run at the point of the violation. The default value is `false`.
+    pub fn fail_fast(self, fail_fast: bool) -> Self {
+        Self { fail_fast, ..self }
+    }
+
+    /// Checks the expectations on all assets at the current timestamp.
+    pub fn check<MD, I>(&mut self, hbt: &I) -> Result<(), ExpectationError>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let timestamp = hbt.current_timestamp();
+        for asset_no in 0..hbt.num_assets() {
+            let depth = hbt.depth(asset_no);
+            let ctx = Context {
+                timestamp,
+                asset_no,
+                best_bid_tick: depth.best_bid_tick(),
+                best_ask_tick: depth.best_ask_tick(),
+                tick_size: depth.tick_size(),
+                state_values: hbt.state_values(asset_no),
+                orders: hbt.orders(asset_no),
+            };
+            for expectation in self.expectations.iter_mut() {
+                if let Some(description) = expectation.check(&ctx) {
+                    self.num_violations += 1;
+                    if self.violations.len() < self.max_violations {
+                        self.violations.push(Violation {
+                            name: expectation.name().to_string(),
+                            timestamp,
+                            asset_no,
+                            description,
+                        });
+                    }
+                }
+            }
+        }
+        if self.fail_fast && self.num_violations > 0 {
+            return self.verify();
+        }
+        Ok(())
+    }
+
+    /// Returns the violations that have occurred so far.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Returns an error with the diagnostics if any expectation has been violated.
+    pub fn verify(&self) -> Result<(), ExpectationError> {
+        if self.num_violations > 0 {
+            Err(ExpectationError {
+                num_violations: self.num_violations,
+                violations: self.violations.clone(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for Expectations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder for Expectations {
+    type Error = ExpectationError;
+
+    fn record<MD, I>(&mut self, hbt: &mut I) -> Result<(), Self::Error>
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        self.check(hbt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{AckWithin, Context, Expectation, MaxPosition, NoCrossedQuotes};
+    use crate::types::{OrdType, Order, OrderId, Side, StateValues, Status, TimeInForce};
+
+    fn ctx<'a>(
+        timestamp: i64,
+        state_values: &'a StateValues,
+        orders: &'a HashMap<OrderId, Order>,
+    ) -> Context<'a> {
+        Context {
+            timestamp,
+            asset_no: 0,
+            best_bid_tick: 1000,
+            best_ask_tick: 1001,
+            tick_size: 0.1,
+            state_values,
+            orders,
+        }
+    }
+
+    #[test]
+    fn test_expectations() {
+        let mut orders = HashMap::new();
+        let mut bid = Order::new(
+            1,
+            1000,
+            0.1,
+            1.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTX,
+        );
+        bid.status = Status::New;
+        orders.insert(1, bid);
+        let mut ask = Order::new(
+            2,
+            1001,
+            0.1,
+            1.0,
+            Side::Sell,
+            OrdType::Limit,
+            TimeInForce::GTX,
+        );
+        ask.status = Status::New;
+        ask.req = Status::New;
+        ask.local_timestamp = 50;
+        orders.insert(2, ask);
+
+        let state_values = StateValues {
+            position: -2.0,
+            balance: 0.0,
+            fee: 0.0,
+            num_trades: 0,
+            trading_volume: 0.0,
+            trading_value: 0.0,
+            ..Default::default()
+        };
+
+        assert!(NoCrossedQuotes
+            .check(&ctx(100, &state_values, &orders))
+            .is_none());
+        assert!(MaxPosition(2.0)
+            .check(&ctx(100, &state_values, &orders))
+            .is_none());
+        assert!(MaxPosition(1.0)
+            .check(&ctx(100, &state_values, &orders))
+            .is_some());
+        assert!(AckWithin(50)
+            .check(&ctx(100, &state_values, &orders))
+            .is_none());
+        assert!(AckWithin(50)
+            .check(&ctx(101, &state_values, &orders))
+            .is_some());
+
+        orders.get_mut(&2).unwrap().price_tick = 1000;
+        assert!(NoCrossedQuotes
+            .check(&ctx(101, &state_values, &orders))
+            .is_some());
+    }
+}
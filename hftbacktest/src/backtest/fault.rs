@@ -0,0 +1,201 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use crate::{
+    types::{Order, OrderId, Status},
+    utils::SplitMix64,
+};
+
+pub(crate) enum FaultDecision {
+    Accept,
+    Reject,
+    Delay(i64),
+}
+
+/// Injects faults into the exchange's handling of the order requests, so that the strategy's
+/// error-handling paths can be exercised in backtests. The faults are drawn from the
+/// pseudo-random sequence generated from the seed, so a run is reproducible.
+///
+/// * New orders can be rejected at random. The scheduled outages, during which every order is
+///   rejected, are simulated by [`Downtime`](crate::backtest::downtime::Downtime).
+/// * Cancel requests can be delayed at the exchange. Since the requests are processed in order,
+///   the requests sent after a delayed cancel are delayed behind it.
+/// * The acknowledgements of new orders and cancels can be dropped, so the local never receives
+///   the response and the request remains in process. Responses carrying fills are never dropped,
+///   so the local position stays consistent with the exchange.
+///
+/// Since the faults are drawn at random, [`FaultInjector::num_rejected`],
+/// [`FaultInjector::num_delayed`] and [`FaultInjector::num_dropped`] tell how many were actually
+/// injected, which confirms that a short run exercised each error-handling path.
+///
+/// ```
+/// use hftbacktest::backtest::fault::FaultInjector;
+///
+/// // Rejects 1% of new orders, delays 5% of cancels by 100ms, and drops 0.1% of
+/// // acknowledgements.
+/// let faults = FaultInjector::new(42)
+///     .reject_probability(0.01)
+///     .cancel_delay(0.05, 100_000_000)
+///     .drop_ack_probability(0.001);
+/// ```
+#[derive(Clone)]
+pub struct FaultInjector(Rc<RefCell<FaultInjectorInner>>);
+
+struct FaultInjectorInner {
+    rng: SplitMix64,
+    reject_probability: f64,
+    cancel_delay_probability: f64,
+    cancel_delay: i64,
+    drop_ack_probability: f64,
+    delayed: HashSet<OrderId>,
+    num_rejected: u64,
+    num_delayed: u64,
+    num_dropped: u64,
+}
+
+impl FaultInjector {
+    /// Constructs `FaultInjector` with no faults, seeding its pseudo-random sequence with the
+    /// `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self(Rc::new(RefCell::new(FaultInjectorInner {
+            rng: SplitMix64::new(seed),
+            reject_probability: 0.0,
+            cancel_delay_probability: 0.0,
+            cancel_delay: 0,
+            drop_ack_probability: 0.0,
+            delayed: Default::default(),
+            num_rejected: 0,
+            num_delayed: 0,
+            num_dropped: 0,
+        })))
+    }
+
+    /// Sets the probability that a new order is rejected.
+    pub fn reject_probability(self, probability: f64) -> Self {
+        self.0.borrow_mut().reject_probability = probability;
+        self
+    }
+
+    /// Sets the probability that a cancel request is delayed at the exchange by `delay`.
+    pub fn cancel_delay(self, probability: f64, delay: i64) -> Self {
+        {
+            let mut inner = self.0.borrow_mut();
+            inner.cancel_delay_probability = probability;
+            inner.cancel_delay = delay;
+        }
+        self
+    }
+
+    /// Sets the probability that the acknowledgement of a new order or a cancel is dropped.
+    pub fn drop_ack_probability(self, probability: f64) -> Self {
+        self.0.borrow_mut().drop_ack_probability = probability;
+        self
+    }
+
+    /// Returns the number of new orders rejected by the injector.
+    pub fn num_rejected(&self) -> u64 {
+        self.0.borrow().num_rejected
+    }
+
+    /// Returns the number of cancel requests delayed by the injector.
+    pub fn num_delayed(&self) -> u64 {
+        self.0.borrow().num_delayed
+    }
+
+    /// Returns the number of acknowledgements dropped by the injector.
+    pub fn num_dropped(&self) -> u64 {
+        self.0.borrow().num_dropped
+    }
+
+    /// Decides the fault of the order request received by the exchange.
+    pub(crate) fn on_request(&self, order: &Order) -> FaultDecision {
+        let mut inner = self.0.borrow_mut();
+        match order.req {
+            Status::New => {
+                let probability = inner.reject_probability;
+                if probability > 0.0 && inner.rng.next_f64() < probability {
+                    inner.num_rejected += 1;
+                    return FaultDecision::Reject;
+                }
+            }
+            Status::Canceled => {
+                // The delayed request is processed when it arrives again.
+                if inner.delayed.remove(&order.order_id) {
+                    return FaultDecision::Accept;
+                }
+                let probability = inner.cancel_delay_probability;
+                if probability > 0.0 && inner.rng.next_f64() < probability {
+                    inner.delayed.insert(order.order_id);
+                    inner.num_delayed += 1;
+                    return FaultDecision::Delay(inner.cancel_delay);
+                }
+            }
+            _ => {}
+        }
+        FaultDecision::Accept
+    }
+
+    /// Decides whether the response is an acknowledgement to be dropped.
+    pub(crate) fn drop_ack(&self, response: &Order) -> bool {
+        let mut inner = self.0.borrow_mut();
+        let ack = response.exec_qty == 0.0
+            && (response.status == Status::New || response.status == Status::Canceled);
+        let probability = inner.drop_ack_probability;
+        if ack && probability > 0.0 && inner.rng.next_f64() < probability {
+            inner.num_dropped += 1;
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FaultDecision, FaultInjector};
+    use crate::{test_utils::request, types::Status};
+
+    #[test]
+    fn test_reject_probability() {
+        let faults = FaultInjector::new(0).reject_probability(1.0);
+        assert!(matches!(
+            faults.on_request(&request(1, Status::New)),
+            FaultDecision::Reject
+        ));
+        // Only the new orders are rejected.
+        assert!(matches!(
+            faults.on_request(&request(1, Status::Canceled)),
+            FaultDecision::Accept
+        ));
+        assert_eq!(faults.num_rejected(), 1);
+    }
+
+    #[test]
+    fn test_cancel_delay() {
+        let faults = FaultInjector::new(0).cancel_delay(1.0, 50);
+        // Only the cancels are delayed.
+        assert!(matches!(
+            faults.on_request(&request(1, Status::New)),
+            FaultDecision::Accept
+        ));
+        // A cancel is delayed once, and then processed when it arrives again.
+        let cancel = request(1, Status::Canceled);
+        assert!(matches!(
+            faults.on_request(&cancel),
+            FaultDecision::Delay(50)
+        ));
+        assert!(matches!(faults.on_request(&cancel), FaultDecision::Accept));
+        assert_eq!(faults.num_delayed(), 1);
+    }
+
+    #[test]
+    fn test_drop_ack() {
+        let faults = FaultInjector::new(0).drop_ack_probability(1.0);
+        let mut response = request(1, Status::None);
+        response.status = Status::New;
+        assert!(faults.drop_ack(&response));
+        // A fill is never dropped.
+        response.status = Status::Filled;
+        response.exec_qty = 1.0;
+        assert!(!faults.drop_ack(&response));
+        assert_eq!(faults.num_dropped(), 1);
+    }
+}
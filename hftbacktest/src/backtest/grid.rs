@@ -0,0 +1,266 @@
+#[cfg(feature = "fs")]
+use std::io::ErrorKind;
+use std::{
+    io::Error as IoError,
+    panic,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "fs")]
+use crate::backtest::data::{read_npy_file, read_npz_file};
+use crate::{
+    backtest::data::{DataSource, SharedData, SharedDataView},
+    types::Event,
+};
+
+/// The report of a run of [`BacktestGrid`].
+#[derive(Debug)]
+pub struct GridReport<R> {
+    /// The index of the parameter set used for the run.
+    pub index: usize,
+    /// The value returned by the strategy closure.
+    pub result: R,
+    /// The wall-clock time taken by the run.
+    pub elapsed: Duration,
+}
+
+/// Runs the same strategy over a grid of parameter sets in parallel, on a fixed number of worker
+/// threads.
+///
+/// The data is loaded once and shared immutably between the workers. The strategy closure receives
+/// the parameter set and the read-only views of the data in the given order, builds its own
+/// [`Backtest`](crate::backtest::Backtest) using [`DataSource::Shared`], and returns the result of
+/// the run.
+///
+/// Since the data is shared, it can't be preprocessed by the backtest, so building an asset with
+/// `latency_offset` or a time range fails. Instead, the data can be preprocessed in advance and
+/// given as [`DataSource::Data`] to the grid.
+///
+/// ```ignore
+/// let reports = BacktestGrid::new(vec![(0.5, 1.0), (1.0, 1.0), (1.0, 2.0)])
+///     .data(vec![DataSource::File("btcusdt_20240809.npz".to_string())])
+///     .num_threads(4)
+///     .run(|&(half_spread, skew), data| {
+///         let mut hbt = Backtest::builder()
+///             .add_asset(
+///                 L2AssetBuilder::new()
+///                     .data(vec![DataSource::Shared(data[0].clone())])
+///                     // ...
+///                     .build()?,
+///             )
+///             .build()?;
+///         strategy(&mut hbt, half_spread, skew)
+///     })?;
+/// ```
+pub struct BacktestGrid<P> {
+    params: Vec<P>,
+    data: Vec<DataSource<Event>>,
+    num_threads: usize,
+}
+
+impl<P> BacktestGrid<P>
+where
+    P: Sync,
+{
+    /// Constructs `BacktestGrid` with the parameter sets to run.
+    pub fn new(params: Vec<P>) -> Self {
+        Self {
+            params,
+            data: Vec::new(),
+            num_threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        }
+    }
+
+    /// Sets the data shared by all runs, which is passed to the strategy closure in this order.
+    pub fn data(self, data: Vec<DataSource<Event>>) -> Self {
+        Self { data, ..self }
+    }
+
+    /// Sets the number of worker threads. The default value is the available parallelism.
+    pub fn num_threads(self, num_threads: usize) -> Self {
+        Self {
+            num_threads,
+            ..self
+        }
+    }
+
+    /// Loads the data and runs the strategy closure for each parameter set, returning the reports
+    /// in the order of the parameter sets.
+    pub fn run<F, R>(self, strategy: F) -> Result<Vec<GridReport<R>>, IoError>
+    where
+        F: Fn(&P, &[SharedDataView<Event>]) -> R + Sync,
+        R: Send,
+    {
+        let shared = self
+            .data
+            .into_iter()
+            .map(load)
+            .collect::<Result<Vec<_>, _>>()?;
+        let params = &self.params;
+        let next = AtomicUsize::new(0);
+
+        let worker = || {
+            let data: Vec<_> = shared.iter().map(|data| data.view()).collect();
+            let mut reports = Vec::new();
+            loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= params.len() {
+                    break;
+                }
+                let instant = Instant::now();
+                let result = strategy(&params[index], &data);
+                reports.push(GridReport {
+                    index,
+                    result,
+                    elapsed: instant.elapsed(),
+                });
+            }
+            reports
+        };
+
+        let num_threads = self.num_threads.clamp(1, params.len().max(1));
+        let mut reports = if num_threads == 1 {
+            worker()
+        } else {
+            thread::scope(|s| {
+                let handles: Vec<_> = (0..num_threads).map(|_| s.spawn(worker)).collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|error| panic::resume_unwind(error))
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+        reports.sort_by_key(|report| report.index);
+        Ok(reports)
+    }
+}
+
+fn load(source: DataSource<Event>) -> Result<SharedData<Event>, IoError> {
+    let data = match source {
+        #[cfg(feature = "fs")]
+        DataSource::File(filepath) if filepath.ends_with(".npz") => {
+            read_npz_file(&filepath, "data")?
+        }
+        #[cfg(feature = "fs")]
+        DataSource::File(filepath) if filepath.ends_with(".npy") => read_npy_file(&filepath)?,
+        #[cfg(feature = "fs")]
+        DataSource::File(_) => {
+            return Err(IoError::new(
+                ErrorKind::InvalidData,
+                "unsupported data type",
+            ));
+        }
+        DataSource::Data(data) => data,
+        DataSource::Shared(data) => data.into_data(),
+    };
+    Ok(SharedData::new(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BacktestGrid;
+    use crate::{
+        backtest::{
+            assettype::LinearAsset,
+            data::{Data, DataSource},
+            models::{CommonFees, ConstantLatency, RiskAdverseQueueModel, TradingValueFeeModel},
+            Backtest,
+            L2AssetBuilder,
+        },
+        depth::{HashMapMarketDepth, MarketDepth},
+        types::{Bot, Event, BUY_EVENT, DEPTH_EVENT, EXCH_EVENT, LOCAL_EVENT},
+    };
+
+    #[test]
+    fn test_backtest_grid() {
+        let events: Vec<_> = (1..=10)
+            .map(|i| Event {
+                ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | BUY_EVENT,
+                exch_ts: i * 100,
+                local_ts: i * 100 + 10,
+                px: 100.0 + i as f64 * 0.1,
+                qty: 1.0,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            })
+            .collect();
+
+        let reports = BacktestGrid::new(vec![100, 200, 300, 400, 500])
+            .data(vec![DataSource::Data(Data::from_data(&events))])
+            .num_threads(3)
+            .run(|&interval, data| {
+                let mut hbt = Backtest::builder()
+                    .add_asset(
+                        L2AssetBuilder::new()
+                            .data(vec![DataSource::Shared(data[0].clone())])
+                            .latency_model(ConstantLatency::new(10, 10))
+                            .asset_type(LinearAsset::new(1.0))
+                            .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                            .queue_model(RiskAdverseQueueModel::new())
+                            .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                            .build()
+                            .unwrap(),
+                    )
+                    .build()
+                    .unwrap();
+                let mut num_steps = 0;
+                while hbt.elapse(interval).unwrap() {
+                    num_steps += 1;
+                }
+                (num_steps, hbt.depth(0).best_bid())
+            })
+            .unwrap();
+
+        assert_eq!(reports.len(), 5);
+        for (index, report) in reports.iter().enumerate() {
+            assert_eq!(report.index, index);
+            // Every run reads the same data to the end.
+            assert!((report.result.1 - 101.0).abs() < 1e-9);
+        }
+        assert!(reports[0].result.0 > reports[4].result.0);
+    }
+
+    #[test]
+    fn test_shared_data_cannot_be_preprocessed() {
+        let events = vec![Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | BUY_EVENT,
+            exch_ts: 100,
+            local_ts: 110,
+            px: 100.0,
+            qty: 1.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        }];
+
+        let reports = BacktestGrid::new(vec![0, 10])
+            .data(vec![DataSource::Data(Data::from_data(&events))])
+            .num_threads(2)
+            .run(|&latency_offset, data| {
+                L2AssetBuilder::new()
+                    .data(vec![DataSource::Shared(data[0].clone())])
+                    .latency_offset(latency_offset)
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .is_ok()
+            })
+            .unwrap();
+
+        assert!(reports[0].result);
+        // Applying the latency offset would modify the data read by the other workers.
+        assert!(!reports[1].result);
+    }
+}
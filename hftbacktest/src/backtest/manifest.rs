@@ -0,0 +1,185 @@
+use std::{
+    any::type_name,
+    fmt::Write as _,
+    fs::File,
+    io::{Error, Write},
+    path::Path,
+};
+
+use crate::{backtest::DataSource, types::Event};
+
+/// The setup of an asset needed to reproduce a backtest, which is filled in by the asset builder.
+#[derive(Clone, Debug, Default)]
+pub struct AssetManifest {
+    /// The data files, in the order they are read. In-memory data is denoted by `<memory>`.
+    pub data: Vec<String>,
+    /// The feed latency offset applied to the data.
+    pub latency_offset: i64,
+    /// The exchange model.
+    pub exchange: String,
+    /// The models used, as pairs of the role and the type name, such as
+    /// `("latency_model", "hftbacktest::backtest::models::ConstantLatency")`.
+    pub models: Vec<(String, String)>,
+}
+
+impl AssetManifest {
+    pub(crate) fn new(data: &[DataSource<Event>], latency_offset: i64, exchange: &str) -> Self {
+        Self {
+            data: data
+                .iter()
+                .map(|source| match source {
+                    #[cfg(feature = "fs")]
+                    DataSource::File(filepath) => filepath.clone(),
+                    DataSource::Data(_) | DataSource::Shared(_) => "<memory>".to_string(),
+                })
+                .collect(),
+            latency_offset,
+            exchange: exchange.to_string(),
+            models: Vec::new(),
+        }
+    }
+
+    pub(crate) fn model<T: ?Sized>(mut self, role: &str) -> Self {
+        self.models
+            .push((role.to_string(), type_name::<T>().to_string()));
+        self
+    }
+}
+
+/// A reproducibility manifest of a backtest, which lists the seed, the data files and the models
+/// of each asset, and the strategy parameters. It can be saved alongside the recorder output by
+/// [`to_json`](Self::to_json), so that the result can be reproduced later.
+///
+/// The models are identified by their type names. Their parameters, as well as the strategy's,
+/// can be added by [`BacktestBuilder::parameter`](crate::backtest::BacktestBuilder::parameter).
+#[derive(Clone, Debug, Default)]
+pub struct Manifest {
+    /// The seed set on the backtest builder, if any.
+    pub seed: Option<u64>,
+    /// The setup of each asset.
+    pub assets: Vec<AssetManifest>,
+    /// The user-defined parameters, as pairs of the name and the value.
+    pub parameters: Vec<(String, String)>,
+}
+
+impl Manifest {
+    /// Saves the manifest into a JSON file at the specified path.
+    pub fn to_json<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = File::create(path)?;
+        file.write_all(self.json().as_bytes())
+    }
+
+    fn json(&self) -> String {
+        let mut s = String::new();
+        s.push_str("{\n  \"seed\": ");
+        match self.seed {
+            Some(seed) => write!(s, "{seed}").unwrap(),
+            None => s.push_str("null"),
+        }
+        s.push_str(",\n  \"parameters\": ");
+        write_pairs(&mut s, &self.parameters, 2);
+        s.push_str(",\n  \"assets\": [");
+        for (asset_no, asset) in self.assets.iter().enumerate() {
+            if asset_no > 0 {
+                s.push(',');
+            }
+            s.push_str("\n    {\n      \"data\": [");
+            for (i, data) in asset.data.iter().enumerate() {
+                if i > 0 {
+                    s.push_str(", ");
+                }
+                write_str(&mut s, data);
+            }
+            write!(
+                s,
+                "],\n      \"latency_offset\": {},\n      \"exchange\": ",
+                asset.latency_offset
+            )
+            .unwrap();
+            write_str(&mut s, &asset.exchange);
+            s.push_str(",\n      \"models\": ");
+            write_pairs(&mut s, &asset.models, 6);
+            s.push_str("\n    }");
+        }
+        if !self.assets.is_empty() {
+            s.push_str("\n  ");
+        }
+        s.push_str("]\n}\n");
+        s
+    }
+}
+
+fn write_pairs(s: &mut String, pairs: &[(String, String)], indent: usize) {
+    s.push('{');
+    for (i, (key, value)) in pairs.iter().enumerate() {
+        if i > 0 {
+            s.push(',');
+        }
+        write!(s, "\n{:indent$}", "", indent = indent + 2).unwrap();
+        write_str(s, key);
+        s.push_str(": ");
+        write_str(s, value);
+    }
+    if !pairs.is_empty() {
+        write!(s, "\n{:indent$}", "", indent = indent).unwrap();
+    }
+    s.push('}');
+}
+
+fn write_str(s: &mut String, value: &str) {
+    s.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(s, "\\u{:04x}", c as u32).unwrap(),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetManifest, Manifest};
+
+    #[test]
+    fn test_manifest_json() {
+        let manifest = Manifest {
+            seed: Some(42),
+            assets: vec![AssetManifest {
+                data: vec!["C:\\data\\btcusdt.npz".to_string()],
+                latency_offset: 10,
+                exchange: "NoPartialFillExchange".to_string(),
+                models: vec![("latency_model".to_string(), "ConstantLatency".to_string())],
+            }],
+            parameters: vec![("half_spread".to_string(), "0.5".to_string())],
+        };
+        assert_eq!(
+            manifest.json(),
+            r#"{
+  "seed": 42,
+  "parameters": {
+    "half_spread": "0.5"
+  },
+  "assets": [
+    {
+      "data": ["C:\\data\\btcusdt.npz"],
+      "latency_offset": 10,
+      "exchange": "NoPartialFillExchange",
+      "models": {
+        "latency_model": "ConstantLatency"
+      }
+    }
+  ]
+}
+"#
+        );
+    }
+}
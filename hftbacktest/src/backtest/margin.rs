@@ -0,0 +1,381 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    backtest::{
+        assettype::AssetType,
+        models::{FeeModel, QueueState},
+        state::State,
+    },
+    depth::{MarketDepth, INVALID_MAX, INVALID_MIN},
+    types::{Liquidation, OrdType, Order, OrderId, PositionSide, Side, Status, TimeInForce},
+};
+
+/// The account that holds the collateral shared by the assets in cross margin mode.
+///
+/// [`MarginAccount::margin_balance`] is the balance that the liquidation of every asset in the
+/// account is checked against, with the PnL of each asset as of its last margin check.
+#[derive(Clone)]
+pub struct MarginAccount(Rc<RefCell<MarginAccountInner>>);
+
+struct MarginAccountInner {
+    collateral: f64,
+    pnl: Vec<f64>,
+}
+
+impl MarginAccount {
+    /// Constructs `MarginAccount` with the initial collateral.
+    pub fn new(collateral: f64) -> Self {
+        Self(Rc::new(RefCell::new(MarginAccountInner {
+            collateral,
+            pnl: Vec::new(),
+        })))
+    }
+
+    /// Returns the margin balance, which is the collateral plus the PnL of all assets in the
+    /// account, including unrealized PnL and fees.
+    pub fn margin_balance(&self) -> f64 {
+        let inner = self.0.borrow();
+        inner.collateral + inner.pnl.iter().sum::<f64>()
+    }
+
+    fn register(&self) -> usize {
+        let mut inner = self.0.borrow_mut();
+        inner.pnl.push(0.0);
+        inner.pnl.len() - 1
+    }
+
+    fn update_pnl(&self, slot: usize, pnl: f64) {
+        self.0.borrow_mut().pnl[slot] = pnl;
+    }
+}
+
+#[derive(Clone)]
+enum MarginMode {
+    /// The asset's margin is isolated from the other assets and limited to the given collateral.
+    Isolated(f64),
+    /// The asset shares the collateral of the account with the other assets in the account.
+    Cross(MarginAccount, usize),
+}
+
+/// Simulates the margin requirements of an asset on the exchange side.
+///
+/// * A new order is rejected if the position, after the order is fully filled, would require more
+///   initial margin than the margin balance. The initial margin is the position value divided by
+///   the leverage.
+/// * The position is liquidated at the best price when the margin balance falls below the
+///   maintenance margin, which is the position value multiplied by the maintenance margin rate.
+///   All open orders are canceled, and the liquidation fee is charged on the liquidated value.
+///
+/// In hedge mode, the margin is required for both the long and short positions, so the position
+/// value is that of the [gross position](crate::types::StateValues::gross_position), and each
+/// position side is liquidated by its own order.
+///
+/// The margin balance is the collateral plus the PnL, including unrealized PnL and fees, valued at
+/// the mid price.
+///
+/// The liquidation is reported to the local as a filled market order, with an order ID counting
+/// down from [`u64::MAX`], and can be retrieved by [`Bot::liquidations`](crate::types::Bot::liquidations).
+#[derive(Clone)]
+pub struct Margin {
+    mode: MarginMode,
+    leverage: f64,
+    maintenance_margin_rate: f64,
+    liquidation_fee_rate: f64,
+    num_liquidations: u64,
+}
+
+impl Margin {
+    /// Constructs `Margin` in isolated margin mode.
+    ///
+    /// * `collateral` - The collateral isolated to this asset.
+    /// * `leverage` - The maximum leverage, which determines the initial margin.
+    /// * `maintenance_margin_rate` - The maintenance margin rate relative to the position value.
+    /// * `liquidation_fee_rate` - The liquidation fee rate relative to the liquidated value.
+    pub fn isolated(
+        collateral: f64,
+        leverage: f64,
+        maintenance_margin_rate: f64,
+        liquidation_fee_rate: f64,
+    ) -> Self {
+        Self {
+            mode: MarginMode::Isolated(collateral),
+            leverage,
+            maintenance_margin_rate,
+            liquidation_fee_rate,
+            num_liquidations: 0,
+        }
+    }
+
+    /// Constructs `Margin` in cross margin mode, which shares the collateral of the `account` with
+    /// the other assets that use the same account.
+    ///
+    /// * `account` - The account that holds the collateral.
+    /// * `leverage` - The maximum leverage, which determines the initial margin.
+    /// * `maintenance_margin_rate` - The maintenance margin rate relative to the position value.
+    /// * `liquidation_fee_rate` - The liquidation fee rate relative to the liquidated value.
+    pub fn cross(
+        account: MarginAccount,
+        leverage: f64,
+        maintenance_margin_rate: f64,
+        liquidation_fee_rate: f64,
+    ) -> Self {
+        let slot = account.register();
+        Self {
+            mode: MarginMode::Cross(account, slot),
+            leverage,
+            maintenance_margin_rate,
+            liquidation_fee_rate,
+            num_liquidations: 0,
+        }
+    }
+
+    fn margin_balance<AT, FM>(&self, state: &State<AT, FM>, mid: f64) -> f64
+    where
+        AT: AssetType,
+        FM: FeeModel,
+    {
+        let pnl = state.equity(mid);
+        match &self.mode {
+            MarginMode::Isolated(collateral) => collateral + pnl,
+            MarginMode::Cross(account, slot) => {
+                account.update_pnl(*slot, pnl);
+                account.margin_balance()
+            }
+        }
+    }
+
+    pub(crate) fn num_liquidations(&self) -> u64 {
+        self.num_liquidations
+    }
+
+    pub(crate) fn set_num_liquidations(&mut self, num_liquidations: u64) {
+        self.num_liquidations = num_liquidations;
+    }
+
+    /// Returns whether the margin balance is sufficient for the initial margin of the position
+    /// after the order is fully filled. Orders that reduce the gross position are always allowed.
+    pub(crate) fn allows<AT, FM, MD>(
+        &self,
+        order: &Order,
+        state: &State<AT, FM>,
+        depth: &MD,
+    ) -> bool
+    where
+        AT: AssetType,
+        FM: FeeModel,
+        MD: MarketDepth,
+    {
+        let Some(mid) = mid(depth) else {
+            return true;
+        };
+        let values = state.values();
+        let delta = AsRef::<f64>::as_ref(&order.side) * order.leaves_qty;
+        let mut after = values.clone();
+        after.position += delta;
+        match order.position_side {
+            PositionSide::Long => after.long_position += delta,
+            PositionSide::Short => after.short_position -= delta,
+            PositionSide::Both | PositionSide::Unsupported => {}
+        }
+        let new_gross_position = after.gross_position();
+        if new_gross_position <= values.gross_position() {
+            return true;
+        }
+        let initial_margin = state
+            .asset_type
+            .position_value(mid, new_gross_position)
+            .abs()
+            / self.leverage;
+        self.margin_balance(state, mid) >= initial_margin
+    }
+
+    /// Returns `true` if the margin balance has fallen below the maintenance margin, in which case
+    /// the position must be liquidated.
+    pub(crate) fn check_liquidation<AT, FM, MD>(&self, state: &State<AT, FM>, depth: &MD) -> bool
+    where
+        AT: AssetType,
+        FM: FeeModel,
+        MD: MarketDepth,
+    {
+        let Some(mid) = mid(depth) else {
+            return false;
+        };
+        let gross_position = state.values().gross_position();
+        let margin_balance = self.margin_balance(state, mid);
+        if gross_position == 0.0 {
+            return false;
+        }
+        let maintenance_margin = state.asset_type.position_value(mid, gross_position).abs()
+            * self.maintenance_margin_rate;
+        margin_balance < maintenance_margin
+    }
+
+    /// Liquidates the position at the best price and returns the filled liquidation orders, one
+    /// for each position side held, which carry the [`Liquidation`] in [`Order::q`].
+    pub(crate) fn liquidate<AT, FM, MD>(
+        &mut self,
+        state: &mut State<AT, FM>,
+        depth: &MD,
+        timestamp: i64,
+    ) -> Vec<Order>
+    where
+        AT: AssetType,
+        FM: FeeModel,
+        MD: MarketDepth,
+    {
+        let values = state.values();
+        let one_way = values.position - values.long_position + values.short_position;
+        let sides = [
+            (PositionSide::Both, one_way),
+            (PositionSide::Long, values.long_position),
+            (PositionSide::Short, -values.short_position),
+        ];
+        sides
+            .into_iter()
+            .filter(|(_, position)| *position != 0.0)
+            .map(|(position_side, position)| {
+                let (side, price_tick) = if position > 0.0 {
+                    (Side::Sell, depth.best_bid_tick())
+                } else {
+                    (Side::Buy, depth.best_ask_tick())
+                };
+                self.liquidate_side(
+                    state,
+                    side,
+                    position_side,
+                    position.abs(),
+                    price_tick,
+                    depth.tick_size(),
+                    timestamp,
+                )
+            })
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn liquidate_side<AT, FM>(
+        &mut self,
+        state: &mut State<AT, FM>,
+        side: Side,
+        position_side: PositionSide,
+        qty: f64,
+        price_tick: i64,
+        tick_size: f64,
+        timestamp: i64,
+    ) -> Order
+    where
+        AT: AssetType,
+        FM: FeeModel,
+    {
+        let order_id: OrderId = u64::MAX - self.num_liquidations;
+        self.num_liquidations += 1;
+
+        let mut order = Order::new(
+            order_id,
+            price_tick,
+            tick_size,
+            qty,
+            side,
+            OrdType::Market,
+            TimeInForce::IOC,
+        );
+        order.exec_price_tick = price_tick;
+        order.exec_qty = qty;
+        order.leaves_qty = 0.0;
+        order.status = Status::Filled;
+        order.exch_timestamp = timestamp;
+        order.position_side = position_side;
+
+        let fee = self.liquidation_fee_rate * state.asset_type.amount(order.exec_price(), qty);
+        state.apply_fill(&order);
+        state.apply_fee(fee);
+        order.q = QueueState::custom(Liquidation {
+            side,
+            price: order.exec_price(),
+            qty,
+            fee,
+            exch_ts: timestamp,
+        });
+        order
+    }
+}
+
+fn mid<MD: MarketDepth>(depth: &MD) -> Option<f64> {
+    if depth.best_bid_tick() == INVALID_MIN || depth.best_ask_tick() == INVALID_MAX {
+        None
+    } else {
+        Some((depth.best_bid() + depth.best_ask()) / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Margin;
+    use crate::{
+        backtest::{
+            assettype::LinearAsset,
+            models::{CommonFees, TradingValueFeeModel},
+            state::State,
+        },
+        depth::{HashMapMarketDepth, L2MarketDepth},
+        types::{OrdType, Order, Side, TimeInForce},
+    };
+
+    #[test]
+    fn test_isolated_margin() {
+        let mut depth = HashMapMarketDepth::new(0.1, 1.0);
+        depth.update_bid_depth(99.9, 1.0, 0);
+        depth.update_ask_depth(100.1, 1.0, 0);
+
+        let mut state = State::new(
+            LinearAsset::new(1.0),
+            TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)),
+        );
+        let mut margin = Margin::isolated(100.0, 10.0, 0.05, 0.01);
+
+        let mut order = Order::new(
+            1,
+            1000,
+            0.1,
+            11.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        assert!(!margin.allows(&order, &state, &depth));
+        order.leaves_qty = 10.0;
+        assert!(margin.allows(&order, &state, &depth));
+
+        order.exec_price_tick = 1000;
+        order.exec_qty = 10.0;
+        state.apply_fill(&order);
+
+        // Reducing the position is always allowed.
+        let order = Order::new(
+            2,
+            1001,
+            0.1,
+            10.0,
+            Side::Sell,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        depth.update_bid_depth(95.0, 1.0, 1);
+        depth.update_ask_depth(95.1, 1.0, 1);
+        assert!(margin.allows(&order, &state, &depth));
+        assert!(!margin.check_liquidation(&state, &depth));
+
+        depth.update_bid_depth(94.4, 1.0, 2);
+        depth.update_ask_depth(94.6, 1.0, 2);
+        assert!(margin.check_liquidation(&state, &depth));
+
+        let orders = margin.liquidate(&mut state, &depth, 2);
+        assert_eq!(orders.len(), 1);
+        let order = &orders[0];
+        assert_eq!(order.side, Side::Sell);
+        assert_eq!(order.exec_price_tick, 944);
+        assert_eq!(order.order_id, u64::MAX);
+        assert_eq!(state.state_values.position, 0.0);
+        assert!((state.state_values.fee - 0.01 * 944.0).abs() < 1e-9);
+    }
+}
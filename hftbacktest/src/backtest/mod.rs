@@ -1,10 +1,12 @@
-use std::{collections::HashMap, io::Error as IoError, marker::PhantomData};
+use std::{collections::HashMap, fs, io::Error as IoError, marker::PhantomData, path::Path};
 
 pub use data::DataSource;
 use data::Reader;
 use models::FeeModel;
 use thiserror::Error;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backtest::throughput::Throughput;
 pub use crate::backtest::{
     models::L3QueueModel,
     proc::{L3Local, L3NoPartialFillExchange},
@@ -12,27 +14,68 @@ pub use crate::backtest::{
 use crate::{
     backtest::{
         assettype::AssetType,
-        data::FeedLatencyAdjustment,
+        calendar::SessionCalendar,
+        checkpoint::{checkpoint_error, CheckpointReader, CheckpointWriter},
+        data::{FeedLatencyAdjustment, TimeRange},
+        diagnostics::FillDiagnostics,
+        downtime::Downtime,
         evs::{EventIntentKind, EventSet},
-        models::{LatencyModel, QueueModel},
+        fault::FaultInjector,
+        manifest::{AssetManifest, Manifest},
+        margin::Margin,
+        models::{FillModel, LatencyModel, QueueModel},
         order::OrderBus,
-        proc::{Local, LocalProcessor, NoPartialFillExchange, PartialFillExchange, Processor},
-        state::State,
+        proc::{
+            Local,
+            LocalProcessor,
+            MemoryUsage,
+            NoPartialFillExchange,
+            PartialFillExchange,
+            PostOnlyBehavior,
+            Processor,
+        },
+        ratelimit::RateLimiter,
+        registry::{ExchangeParts, ExchangeRegistry},
+        rollover::DayRollover,
+        state::{FeeSettlement, ShortSelling, State},
+        throttle::Throttle,
+        throughput::ThroughputMeter,
     },
-    depth::{HashMapMarketDepth, L2MarketDepth, L3MarketDepth, MarketDepth},
+    bar::{Bar, BarBuilder},
+    clock::Clock,
+    depth::{ApplySnapshot, HashMapMarketDepth, L2MarketDepth, L3MarketDepth, MarketDepth},
     prelude::{
         Bot,
+        DepthUpdate,
+        EventStats,
+        Fill,
+        Liquidation,
         OrdType,
         Order,
         OrderId,
         OrderRequest,
+        Portfolio,
+        PositionSide,
         Side,
         StateValues,
+        Status,
         TimeInForce,
+        WaitCondition,
         WaitOrderResponse,
         UNTIL_END_OF_DATA,
     },
-    types::{BuildError, Event},
+    risk::{
+        flatten_order,
+        open_qty,
+        NewOrder,
+        PositionLimit,
+        RiskControl,
+        RiskGuard,
+        RiskStats,
+        RiskViolation,
+    },
+    types::{BuildError, Event, OrderFilter, OrderIdGenerator, WaitAny},
+    utils::SplitMix64,
 };
 
 /// Provides asset types.
@@ -52,6 +95,60 @@ pub mod state;
 /// Recorder for a bot's trading statistics.
 pub mod recorder;
 
+/// Margin requirements and forced liquidation.
+pub mod margin;
+
+/// Limits on the frequency of order requests.
+pub mod throttle;
+
+/// Fault injection into the exchange's handling of the order requests.
+pub mod fault;
+
+/// Exchange downtime windows, such as scheduled maintenance.
+pub mod downtime;
+
+/// Simulation of the exchange's rate limits on the order requests.
+pub mod ratelimit;
+
+/// Diagnostics of the resting orders' queue positions and outcomes.
+pub mod diagnostics;
+
+/// Day boundary handling of the daily files chained into one backtest run.
+pub mod rollover;
+
+/// Trading sessions and halts of exchanges with trading hours.
+pub mod calendar;
+
+/// Event throughput reporting of backtests. Since it measures the wall-clock time, which is
+/// unavailable on `wasm32`, the throughput isn't reported there.
+pub mod throughput;
+
+/// Comparison of a live session against its replay in the backtester.
+#[cfg(feature = "live")]
+pub mod divergence;
+
+/// Expectations that must hold throughout a backtest run, for strategy regression tests.
+pub mod expect;
+
+/// Parallel backtesting over a grid of parameter sets. This is not provided on `wasm32`, where
+/// threads are unavailable.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod grid;
+
+/// Comparison of a strategy's results across latency models. This is built on [`grid`], so it's
+/// not provided on `wasm32` either.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod scenario;
+
+/// Reproducibility manifest of a backtest.
+pub mod manifest;
+
+/// Checkpoints to save and restore the state of a backtest.
+pub mod checkpoint;
+
+/// Registry of user-defined exchange models.
+pub mod registry;
+
 pub mod data;
 mod evs;
 
@@ -70,6 +167,8 @@ pub enum BacktestError {
     InvalidOrderStatus,
     #[error("end of data")]
     EndOfData,
+    #[error("risk violation: {0}")]
+    RiskViolation(#[from] RiskViolation),
     #[error("data error: {0:?}")]
     DataError(#[from] IoError),
 }
@@ -78,6 +177,7 @@ pub enum BacktestError {
 pub struct Asset<L: ?Sized, E: ?Sized> {
     pub local: Box<L>,
     pub exch: Box<E>,
+    pub manifest: AssetManifest,
 }
 
 impl<L, E> Asset<L, E> {
@@ -87,6 +187,7 @@ impl<L, E> Asset<L, E> {
         Self {
             local: Box::new(local),
             exch: Box::new(exch),
+            manifest: Default::default(),
         }
     }
 
@@ -94,7 +195,7 @@ impl<L, E> Asset<L, E> {
     pub fn l2_builder<LM, AT, QM, MD, FM>() -> L2AssetBuilder<LM, AT, QM, MD, FM>
     where
         AT: AssetType + Clone + 'static,
-        MD: MarketDepth + L2MarketDepth + 'static,
+        MD: MarketDepth + L2MarketDepth + ApplySnapshot + 'static,
         QM: QueueModel<MD> + 'static,
         LM: LatencyModel + Clone + 'static,
         FM: FeeModel + Clone + 'static,
@@ -122,6 +223,9 @@ pub enum ExchangeKind {
     NoPartialFillExchange,
     /// Uses [PartialFillExchange](`PartialFillExchange`).
     PartialFillExchange,
+    /// Uses the user-defined exchange model registered under the name in the
+    /// [`ExchangeRegistry`] set on the asset builder.
+    Custom(String),
 }
 
 /// A level-2 asset builder.
@@ -130,10 +234,30 @@ pub struct L2AssetBuilder<LM, AT, QM, MD, FM> {
     asset_type: Option<AT>,
     data: Vec<DataSource<Event>>,
     parallel_load: bool,
+    memory_budget: Option<usize>,
+    time_range: Option<TimeRange>,
     latency_offset: i64,
     fee_model: Option<FM>,
+    fee_settlement: FeeSettlement,
     exch_kind: ExchangeKind,
+    exch_registry: Option<ExchangeRegistry<LM, AT, QM, MD, FM>>,
+    post_only: PostOnlyBehavior,
+    short_selling: ShortSelling,
+    fill_model: Option<Box<dyn FillModel<MD>>>,
+    margin: Option<Margin>,
+    throttle: Option<Throttle>,
+    faults: Option<FaultInjector>,
+    downtime: Option<Downtime>,
+    rate_limiter: Option<RateLimiter>,
+    fill_diagnostics: Option<FillDiagnostics>,
+    order_filter: Option<OrderFilter>,
+    rollover: Option<DayRollover>,
+    calendar: Option<SessionCalendar>,
     last_trades_cap: usize,
+    last_depth_updates_cap: usize,
+    last_fills_cap: usize,
+    last_liquidation_prints_cap: usize,
+    bars: Vec<(i64, usize)>,
     queue_model: Option<QM>,
     depth_builder: Option<Box<dyn Fn() -> MD>>,
 }
@@ -141,7 +265,7 @@ pub struct L2AssetBuilder<LM, AT, QM, MD, FM> {
 impl<LM, AT, QM, MD, FM> L2AssetBuilder<LM, AT, QM, MD, FM>
 where
     AT: AssetType + Clone + 'static,
-    MD: MarketDepth + L2MarketDepth + 'static,
+    MD: MarketDepth + L2MarketDepth + ApplySnapshot + 'static,
     QM: QueueModel<MD> + 'static,
     LM: LatencyModel + Clone + 'static,
     FM: FeeModel + Clone + 'static,
@@ -153,10 +277,30 @@ where
             asset_type: None,
             data: vec![],
             parallel_load: false,
+            memory_budget: None,
+            time_range: None,
             latency_offset: 0,
             fee_model: None,
+            fee_settlement: FeeSettlement::Immediate,
             exch_kind: ExchangeKind::NoPartialFillExchange,
+            exch_registry: None,
+            post_only: PostOnlyBehavior::Reject,
+            short_selling: ShortSelling::Allowed,
+            fill_model: None,
+            margin: None,
+            throttle: None,
+            faults: None,
+            downtime: None,
+            rate_limiter: None,
+            fill_diagnostics: None,
+            order_filter: None,
+            rollover: None,
+            calendar: None,
             last_trades_cap: 0,
+            last_depth_updates_cap: 0,
+            last_fills_cap: 0,
+            last_liquidation_prints_cap: 0,
+            bars: Vec::new(),
             queue_model: None,
             depth_builder: None,
         }
@@ -177,6 +321,30 @@ where
         }
     }
 
+    /// Sets the memory budget in bytes for the feed data. When preloading the next data would
+    /// exceed the budget, the data is loaded only when it is needed, even if `parallel_load` is
+    /// enabled. The budget only disables the preloading; each data file is loaded into memory as a
+    /// whole regardless of its size, so the usage can still exceed the budget, and the budget has
+    /// no effect without `parallel_load`. A dataset larger than the budget should be split into
+    /// smaller files. The default value is `None`, indicating no budget.
+    pub fn memory_budget(self, memory_budget: usize) -> Self {
+        Self {
+            memory_budget: Some(memory_budget),
+            ..self
+        }
+    }
+
+    /// Restricts the feed data to the events whose local timestamp in the data is within
+    /// `[start_ts, end_ts)`, so that the backtest runs on a part of the dataset without slicing
+    /// the files in advance. The files outside the range are skipped once loaded. See
+    /// [`TimeRange`].
+    pub fn time_range(self, start_ts: i64, end_ts: i64) -> Self {
+        Self {
+            time_range: Some(TimeRange::new(start_ts, end_ts)),
+            ..self
+        }
+    }
+
     /// Sets the latency offset to adjust the feed latency by the specified amount. This is
     /// particularly useful in cross-exchange backtesting, where the feed data is collected from a
     /// different site than the one where the strategy is intended to run.
@@ -211,11 +379,156 @@ where
         }
     }
 
+    /// Sets when the trading fees are settled in the cash accounting, such as at the end of each
+    /// day or month. The default value is [`FeeSettlement::Immediate`].
+    ///
+    /// The [`FeeSettlement::Periodic`] interval must be positive; otherwise, building the asset
+    /// fails with [`BuildError::InvalidArgument`].
+    pub fn fee_settlement(self, fee_settlement: FeeSettlement) -> Self {
+        Self {
+            fee_settlement,
+            ..self
+        }
+    }
+
     /// Sets an exchange model. The default value is [`NoPartialFillExchange`].
     pub fn exchange(self, exch_kind: ExchangeKind) -> Self {
         Self { exch_kind, ..self }
     }
 
+    /// Sets the [`ExchangeRegistry`] from which the exchange model selected by
+    /// [`ExchangeKind::Custom`] is constructed.
+    pub fn exchange_registry(self, registry: ExchangeRegistry<LM, AT, QM, MD, FM>) -> Self {
+        Self {
+            exch_registry: Some(registry),
+            ..self
+        }
+    }
+
+    /// Sets how the exchange model handles a post-only order that would take liquidity. The
+    /// default value is [`PostOnlyBehavior::Reject`].
+    pub fn post_only(self, post_only: PostOnlyBehavior) -> Self {
+        Self { post_only, ..self }
+    }
+
+    /// Sets how short positions are handled, such as disallowing them or charging the borrow fee
+    /// for spot assets. The default value is [`ShortSelling::Allowed`].
+    ///
+    /// The [`ShortSelling::Borrow`] interval must be positive; otherwise, building the asset fails
+    /// with [`BuildError::InvalidArgument`].
+    pub fn short_selling(self, short_selling: ShortSelling) -> Self {
+        Self {
+            short_selling,
+            ..self
+        }
+    }
+
+    /// Sets a fill model that decides the execution price and quantity of liquidity-taking orders.
+    /// This is only supported by [`PartialFillExchange`] and is passed to a user-defined exchange
+    /// model through [`ExchangeParts`].
+    pub fn fill_model<FL>(self, fill_model: FL) -> Self
+    where
+        FL: FillModel<MD> + 'static,
+    {
+        Self {
+            fill_model: Some(Box::new(fill_model)),
+            ..self
+        }
+    }
+
+    /// Sets the margin requirements, which enables the forced liquidation of the position. The
+    /// default value is `None`, indicating that the margin is not simulated.
+    pub fn margin(self, margin: Margin) -> Self {
+        Self {
+            margin: Some(margin),
+            ..self
+        }
+    }
+
+    /// Sets the [`Throttle`] that limits the frequency of order requests, such as the minimum order
+    /// lifetime and the message-to-trade ratio. The default value is `None`, indicating that the
+    /// order requests are not throttled.
+    pub fn throttle(self, throttle: Throttle) -> Self {
+        Self {
+            throttle: Some(throttle),
+            ..self
+        }
+    }
+
+    /// Sets the [`FaultInjector`] of the exchange, which rejects, delays, or drops the order
+    /// requests and responses at random. The default value is `None`, indicating that no faults
+    /// are injected.
+    pub fn faults(self, faults: FaultInjector) -> Self {
+        Self {
+            faults: Some(faults),
+            ..self
+        }
+    }
+
+    /// Sets the [`Downtime`] windows of the exchange, during which it rejects every order request
+    /// and the feed is held back until the end of the window, so the local sees the stale market
+    /// depth. The default value is `None`, indicating that the exchange is always up.
+    pub fn downtime(self, downtime: Downtime) -> Self {
+        Self {
+            downtime: Some(downtime),
+            ..self
+        }
+    }
+
+    /// Sets the [`RateLimiter`] of the exchange, such as the request weight per minute and the
+    /// number of new orders per time window, which answers the requests exceeding the limits with
+    /// [`Status::RateLimited`](crate::types::Status::RateLimited). The default value is `None`,
+    /// indicating that the order requests are not rate-limited.
+    pub fn rate_limiter(self, rate_limiter: RateLimiter) -> Self {
+        Self {
+            rate_limiter: Some(rate_limiter),
+            ..self
+        }
+    }
+
+    /// Sets the [`FillDiagnostics`] of the exchange, which records the estimated queue position
+    /// over time, the time at the touch, and the outcome of every resting order, to validate the
+    /// queue model against live fills. The default value is `None`, indicating that no
+    /// diagnostics are recorded.
+    pub fn fill_diagnostics(self, fill_diagnostics: FillDiagnostics) -> Self {
+        Self {
+            fill_diagnostics: Some(fill_diagnostics),
+            ..self
+        }
+    }
+
+    /// Sets the [`OrderFilter`] of the exchange, such as the minimum notional and the lot size,
+    /// which rejects new orders that violate it. The default value is `None`, indicating that new
+    /// orders aren't filtered.
+    pub fn order_filter(self, filter: OrderFilter) -> Self {
+        Self {
+            order_filter: Some(filter),
+            ..self
+        }
+    }
+
+    /// Sets the daily files, which must be arranged in the chronological order, to be chained
+    /// into one backtest run. The position and the balance carry over between the days, while
+    /// the working orders and the market depth are handled at each day boundary as specified by
+    /// the [`DayRollover`].
+    #[cfg(feature = "fs")]
+    pub fn daily_files(self, files: Vec<String>, rollover: DayRollover) -> Self {
+        Self {
+            data: files.into_iter().map(DataSource::File).collect(),
+            rollover: Some(rollover),
+            ..self
+        }
+    }
+
+    /// Sets the [`SessionCalendar`] of the exchange, which rejects new orders outside the
+    /// sessions and during the halts.
+    pub fn calendar(self, calendar: SessionCalendar) -> Self {
+        Self {
+            calendar: Some(calendar),
+            ..self
+        }
+    }
+
     /// Sets the initial capacity of the vector storing the last market trades.
     /// The default value is `0`, indicating that no last trades are stored.
     pub fn last_trades_capacity(self, capacity: usize) -> Self {
@@ -225,6 +538,42 @@ where
         }
     }
 
+    /// Sets the initial capacity of the vector storing the last depth updates.
+    /// The default value is `0`, indicating that no last depth updates are stored.
+    pub fn last_depth_updates_capacity(self, capacity: usize) -> Self {
+        Self {
+            last_depth_updates_cap: capacity,
+            ..self
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last fills, which can be retrieved by
+    /// [`Bot::last_fills`]. The default value is `0`, indicating that no last fills are stored.
+    pub fn last_fills_capacity(self, capacity: usize) -> Self {
+        Self {
+            last_fills_cap: capacity,
+            ..self
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last liquidation prints, which can be
+    /// retrieved by [`Bot::last_liquidation_prints`]. The default value is `0`, indicating that no
+    /// last liquidation prints are stored.
+    pub fn last_liquidation_prints_capacity(self, capacity: usize) -> Self {
+        Self {
+            last_liquidation_prints_cap: capacity,
+            ..self
+        }
+    }
+
+    /// Aggregates the market trades into bars of the interval, keeping the latest bars up to the
+    /// capacity, which can be retrieved by [`Bot::bars`]. This can be called multiple times to
+    /// build the bars of several intervals.
+    pub fn bars(mut self, interval: i64, capacity: usize) -> Self {
+        self.bars.push((interval, capacity));
+        self
+    }
+
     /// Sets a queue model.
     pub fn queue_model(self, queue_model: QM) -> Self {
         Self {
@@ -246,20 +595,41 @@ where
 
     /// Builds an `Asset`.
     pub fn build(self) -> Result<Asset<dyn LocalProcessor<MD>, dyn Processor>, BuildError> {
-        let reader = if self.latency_offset == 0 {
-            Reader::builder()
-                .parallel_load(self.parallel_load)
-                .data(self.data)
-                .build()
-                .map_err(|err| BuildError::Error(err.into()))?
-        } else {
-            Reader::builder()
-                .parallel_load(self.parallel_load)
-                .data(self.data)
-                .preprocessor(FeedLatencyAdjustment::new(self.latency_offset))
-                .build()
-                .map_err(|err| BuildError::Error(err.into()))?
+        if matches!(self.fee_settlement, FeeSettlement::Periodic { interval } if interval <= 0) {
+            return Err(BuildError::InvalidArgument("fee_settlement"));
+        }
+        if matches!(self.short_selling, ShortSelling::Borrow { interval, .. } if interval <= 0) {
+            return Err(BuildError::InvalidArgument("short_selling"));
+        }
+        let manifest = AssetManifest::new(
+            &self.data,
+            self.latency_offset,
+            match &self.exch_kind {
+                ExchangeKind::NoPartialFillExchange => "NoPartialFillExchange",
+                ExchangeKind::PartialFillExchange => "PartialFillExchange",
+                ExchangeKind::Custom(name) => name,
+            },
+        )
+        .model::<LM>("latency_model")
+        .model::<QM>("queue_model")
+        .model::<AT>("asset_type")
+        .model::<FM>("fee_model")
+        .model::<MD>("depth");
+        let reader_builder = Reader::builder()
+            .parallel_load(self.parallel_load)
+            .memory_budget(self.memory_budget)
+            .data(self.data);
+        // The time range is applied to the original local timestamp. The latency offset is
+        // applied next, so the downtime holds back the feed by the adjusted local timestamp.
+        let latency_adjustment =
+            (self.latency_offset != 0).then(|| FeedLatencyAdjustment::new(self.latency_offset));
+        let reader_builder = match (self.time_range, (latency_adjustment, self.downtime.clone())) {
+            (None, (None, None)) => reader_builder,
+            preprocessor => reader_builder.preprocessor(preprocessor),
         };
+        let reader = reader_builder
+            .build()
+            .map_err(|err| BuildError::Error(err.into()))?;
 
         let ob_local_to_exch = OrderBus::new();
         let ob_exch_to_local = OrderBus::new();
@@ -281,15 +651,32 @@ where
             .clone()
             .ok_or(BuildError::BuilderIncomplete("fee_model"))?;
 
-        let local = Local::new(
+        let mut local = Local::new(
             reader.clone(),
             create_depth(),
-            State::new(asset_type, fee_model),
+            State::new(asset_type, fee_model)
+                .fee_settlement(self.fee_settlement)
+                .short_selling(self.short_selling),
             order_latency,
             self.last_trades_cap,
             ob_local_to_exch.clone(),
             ob_exch_to_local.clone(),
-        );
+        )
+        .last_depth_updates_capacity(self.last_depth_updates_cap)
+        .last_fills_capacity(self.last_fills_cap)
+        .last_liquidation_prints_capacity(self.last_liquidation_prints_cap);
+        if let Some(throttle) = &self.throttle {
+            local = local.throttle(throttle.clone());
+        }
+        if let Some(rollover) = self.rollover {
+            local = local.rollover(rollover);
+        }
+        for &(interval, capacity) in &self.bars {
+            if interval <= 0 {
+                return Err(BuildError::InvalidArgument("bars"));
+            }
+            local = local.bar_builder(BarBuilder::new(interval, capacity));
+        }
 
         let order_latency = self
             .latency_model
@@ -309,35 +696,140 @@ where
 
         match self.exch_kind {
             ExchangeKind::NoPartialFillExchange => {
-                let exch = NoPartialFillExchange::new(
+                if self.fill_model.is_some() {
+                    return Err(BuildError::InvalidArgument("fill_model"));
+                }
+                let mut exch = NoPartialFillExchange::new(
                     reader.clone(),
                     create_depth(),
-                    State::new(asset_type, fee_model),
+                    State::new(asset_type, fee_model)
+                        .fee_settlement(self.fee_settlement)
+                        .short_selling(self.short_selling),
                     order_latency,
                     queue_model,
                     ob_exch_to_local,
                     ob_local_to_exch,
+                    self.post_only,
                 );
+                if let Some(margin) = self.margin {
+                    exch = exch.margin(margin);
+                }
+                if let Some(throttle) = self.throttle {
+                    exch = exch.throttle(throttle);
+                }
+                if let Some(faults) = self.faults {
+                    exch = exch.faults(faults);
+                }
+                if let Some(downtime) = self.downtime {
+                    exch = exch.downtime(downtime);
+                }
+                if let Some(rate_limiter) = self.rate_limiter {
+                    exch = exch.rate_limiter(rate_limiter);
+                }
+                if let Some(fill_diagnostics) = self.fill_diagnostics {
+                    exch = exch.fill_diagnostics(fill_diagnostics);
+                }
+                if let Some(filter) = self.order_filter {
+                    exch = exch.order_filter(filter);
+                }
+                if let Some(rollover) = self.rollover {
+                    exch = exch.rollover(rollover);
+                }
+                if let Some(calendar) = self.calendar.clone() {
+                    exch = exch.calendar(calendar);
+                }
 
                 Ok(Asset {
                     local: Box::new(local),
                     exch: Box::new(exch),
+                    manifest,
                 })
             }
             ExchangeKind::PartialFillExchange => {
-                let exch = PartialFillExchange::new(
+                let mut exch = PartialFillExchange::new(
                     reader.clone(),
                     create_depth(),
-                    State::new(asset_type, fee_model),
+                    State::new(asset_type, fee_model)
+                        .fee_settlement(self.fee_settlement)
+                        .short_selling(self.short_selling),
                     order_latency,
                     queue_model,
                     ob_exch_to_local,
                     ob_local_to_exch,
+                    self.post_only,
                 );
+                if let Some(fill_model) = self.fill_model {
+                    exch = exch.fill_model(fill_model);
+                }
+                if let Some(margin) = self.margin {
+                    exch = exch.margin(margin);
+                }
+                if let Some(throttle) = self.throttle {
+                    exch = exch.throttle(throttle);
+                }
+                if let Some(faults) = self.faults {
+                    exch = exch.faults(faults);
+                }
+                if let Some(downtime) = self.downtime {
+                    exch = exch.downtime(downtime);
+                }
+                if let Some(rate_limiter) = self.rate_limiter {
+                    exch = exch.rate_limiter(rate_limiter);
+                }
+                if let Some(fill_diagnostics) = self.fill_diagnostics {
+                    exch = exch.fill_diagnostics(fill_diagnostics);
+                }
+                if let Some(filter) = self.order_filter {
+                    exch = exch.order_filter(filter);
+                }
+                if let Some(rollover) = self.rollover {
+                    exch = exch.rollover(rollover);
+                }
+                if let Some(calendar) = self.calendar.clone() {
+                    exch = exch.calendar(calendar);
+                }
 
                 Ok(Asset {
                     local: Box::new(local),
                     exch: Box::new(exch),
+                    manifest,
+                })
+            }
+            ExchangeKind::Custom(name) => {
+                let registry = self
+                    .exch_registry
+                    .ok_or(BuildError::BuilderIncomplete("exchange_registry"))?;
+                let exch = registry.build(
+                    &name,
+                    ExchangeParts {
+                        reader: reader.clone(),
+                        depth: create_depth(),
+                        asset_type,
+                        fee_model,
+                        fee_settlement: self.fee_settlement,
+                        short_selling: self.short_selling,
+                        order_latency,
+                        queue_model,
+                        orders_to_local: ob_exch_to_local,
+                        orders_from_local: ob_local_to_exch,
+                        post_only: self.post_only,
+                        fill_model: self.fill_model,
+                        margin: self.margin,
+                        throttle: self.throttle,
+                        faults: self.faults,
+                        downtime: self.downtime,
+                        rate_limiter: self.rate_limiter,
+                        fill_diagnostics: self.fill_diagnostics,
+                        order_filter: self.order_filter,
+                        rollover: self.rollover,
+                        calendar: self.calendar,
+                    },
+                )?;
+
+                Ok(Asset {
+                    local: Box::new(local),
+                    exch,
+                    manifest,
                 })
             }
         }
@@ -347,7 +839,7 @@ where
 impl<LM, AT, QM, MD, FM> Default for L2AssetBuilder<LM, AT, QM, MD, FM>
 where
     AT: AssetType + Clone + 'static,
-    MD: MarketDepth + L2MarketDepth + 'static,
+    MD: MarketDepth + L2MarketDepth + ApplySnapshot + 'static,
     QM: QueueModel<MD> + 'static,
     LM: LatencyModel + Clone + 'static,
     FM: FeeModel + Clone + 'static,
@@ -363,10 +855,18 @@ pub struct L3AssetBuilder<LM, AT, QM, MD, FM> {
     asset_type: Option<AT>,
     data: Vec<DataSource<Event>>,
     parallel_load: bool,
+    memory_budget: Option<usize>,
+    time_range: Option<TimeRange>,
     latency_offset: i64,
     fee_model: Option<FM>,
+    fee_settlement: FeeSettlement,
     exch_kind: ExchangeKind,
+    exch_registry: Option<ExchangeRegistry<LM, AT, QM, MD, FM>>,
+    post_only: PostOnlyBehavior,
     last_trades_cap: usize,
+    last_fills_cap: usize,
+    last_liquidation_prints_cap: usize,
+    bars: Vec<(i64, usize)>,
     queue_model: Option<QM>,
     depth_builder: Option<Box<dyn Fn() -> MD>>,
 }
@@ -387,10 +887,18 @@ where
             asset_type: None,
             data: vec![],
             parallel_load: false,
+            memory_budget: None,
+            time_range: None,
             latency_offset: 0,
             fee_model: None,
+            fee_settlement: FeeSettlement::Immediate,
             exch_kind: ExchangeKind::NoPartialFillExchange,
+            exch_registry: None,
+            post_only: PostOnlyBehavior::Reject,
             last_trades_cap: 0,
+            last_fills_cap: 0,
+            last_liquidation_prints_cap: 0,
+            bars: Vec::new(),
             queue_model: None,
             depth_builder: None,
         }
@@ -411,6 +919,30 @@ where
         }
     }
 
+    /// Sets the memory budget in bytes for the feed data. When preloading the next data would
+    /// exceed the budget, the data is loaded only when it is needed, even if `parallel_load` is
+    /// enabled. The budget only disables the preloading; each data file is loaded into memory as a
+    /// whole regardless of its size, so the usage can still exceed the budget, and the budget has
+    /// no effect without `parallel_load`. A dataset larger than the budget should be split into
+    /// smaller files. The default value is `None`, indicating no budget.
+    pub fn memory_budget(self, memory_budget: usize) -> Self {
+        Self {
+            memory_budget: Some(memory_budget),
+            ..self
+        }
+    }
+
+    /// Restricts the feed data to the events whose local timestamp in the data is within
+    /// `[start_ts, end_ts)`, so that the backtest runs on a part of the dataset without slicing
+    /// the files in advance. The files outside the range are skipped once loaded. See
+    /// [`TimeRange`].
+    pub fn time_range(self, start_ts: i64, end_ts: i64) -> Self {
+        Self {
+            time_range: Some(TimeRange::new(start_ts, end_ts)),
+            ..self
+        }
+    }
+
     /// Sets the latency offset to adjust the feed latency by the specified amount. This is
     /// particularly useful in cross-exchange backtesting, where the feed data is collected from a
     /// different site than the one where the strategy is intended to run.
@@ -445,11 +977,38 @@ where
         }
     }
 
+    /// Sets when the trading fees are settled in the cash accounting, such as at the end of each
+    /// day or month. The default value is [`FeeSettlement::Immediate`].
+    ///
+    /// The [`FeeSettlement::Periodic`] interval must be positive; otherwise, building the asset
+    /// fails with [`BuildError::InvalidArgument`].
+    pub fn fee_settlement(self, fee_settlement: FeeSettlement) -> Self {
+        Self {
+            fee_settlement,
+            ..self
+        }
+    }
+
     /// Sets an exchange model. The default value is [`NoPartialFillExchange`].
     pub fn exchange(self, exch_kind: ExchangeKind) -> Self {
         Self { exch_kind, ..self }
     }
 
+    /// Sets the [`ExchangeRegistry`] from which the exchange model selected by
+    /// [`ExchangeKind::Custom`] is constructed.
+    pub fn exchange_registry(self, registry: ExchangeRegistry<LM, AT, QM, MD, FM>) -> Self {
+        Self {
+            exch_registry: Some(registry),
+            ..self
+        }
+    }
+
+    /// Sets how the exchange model handles a post-only order that would take liquidity. The
+    /// default value is [`PostOnlyBehavior::Reject`].
+    pub fn post_only(self, post_only: PostOnlyBehavior) -> Self {
+        Self { post_only, ..self }
+    }
+
     /// Sets the initial capacity of the vector storing the last market trades.
     /// The default value is `0`, indicating that no last trades are stored.
     pub fn last_trades_capacity(self, capacity: usize) -> Self {
@@ -459,6 +1018,33 @@ where
         }
     }
 
+    /// Sets the initial capacity of the vector storing the last fills, which can be retrieved by
+    /// [`Bot::last_fills`]. The default value is `0`, indicating that no last fills are stored.
+    pub fn last_fills_capacity(self, capacity: usize) -> Self {
+        Self {
+            last_fills_cap: capacity,
+            ..self
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last liquidation prints, which can be
+    /// retrieved by [`Bot::last_liquidation_prints`]. The default value is `0`, indicating that no
+    /// last liquidation prints are stored.
+    pub fn last_liquidation_prints_capacity(self, capacity: usize) -> Self {
+        Self {
+            last_liquidation_prints_cap: capacity,
+            ..self
+        }
+    }
+
+    /// Aggregates the market trades into bars of the interval, keeping the latest bars up to the
+    /// capacity, which can be retrieved by [`Bot::bars`]. This can be called multiple times to
+    /// build the bars of several intervals.
+    pub fn bars(mut self, interval: i64, capacity: usize) -> Self {
+        self.bars.push((interval, capacity));
+        self
+    }
+
     /// Sets a queue model.
     pub fn queue_model(self, queue_model: QM) -> Self {
         Self {
@@ -480,20 +1066,35 @@ where
 
     /// Builds an `Asset`.
     pub fn build(self) -> Result<Asset<dyn LocalProcessor<MD>, dyn Processor>, BuildError> {
-        let reader = if self.latency_offset == 0 {
-            Reader::builder()
-                .parallel_load(self.parallel_load)
-                .data(self.data)
-                .build()
-                .map_err(|err| BuildError::Error(err.into()))?
-        } else {
-            Reader::builder()
-                .parallel_load(self.parallel_load)
-                .data(self.data)
-                .preprocessor(FeedLatencyAdjustment::new(self.latency_offset))
-                .build()
-                .map_err(|err| BuildError::Error(err.into()))?
-        };
+        if matches!(self.fee_settlement, FeeSettlement::Periodic { interval } if interval <= 0) {
+            return Err(BuildError::InvalidArgument("fee_settlement"));
+        }
+        let manifest = AssetManifest::new(
+            &self.data,
+            self.latency_offset,
+            match &self.exch_kind {
+                ExchangeKind::NoPartialFillExchange => "L3NoPartialFillExchange",
+                ExchangeKind::PartialFillExchange => "L3PartialFillExchange",
+                ExchangeKind::Custom(name) => name,
+            },
+        )
+        .model::<LM>("latency_model")
+        .model::<QM>("queue_model")
+        .model::<AT>("asset_type")
+        .model::<FM>("fee_model")
+        .model::<MD>("depth");
+        let reader_builder = Reader::builder()
+            .parallel_load(self.parallel_load)
+            .memory_budget(self.memory_budget)
+            .data(self.data);
+        let latency_adjustment =
+            (self.latency_offset != 0).then(|| FeedLatencyAdjustment::new(self.latency_offset));
+        let reader = match (self.time_range, latency_adjustment) {
+            (None, None) => reader_builder,
+            preprocessor => reader_builder.preprocessor(preprocessor),
+        }
+        .build()
+        .map_err(|err| BuildError::Error(err.into()))?;
 
         let ob_local_to_exch = OrderBus::new();
         let ob_exch_to_local = OrderBus::new();
@@ -515,15 +1116,23 @@ where
             .clone()
             .ok_or(BuildError::BuilderIncomplete("fee_model"))?;
 
-        let local = L3Local::new(
+        let mut local = L3Local::new(
             reader.clone(),
             create_depth(),
-            State::new(asset_type, fee_model),
+            State::new(asset_type, fee_model).fee_settlement(self.fee_settlement),
             order_latency,
             self.last_trades_cap,
             ob_local_to_exch.clone(),
             ob_exch_to_local.clone(),
-        );
+        )
+        .last_fills_capacity(self.last_fills_cap)
+        .last_liquidation_prints_capacity(self.last_liquidation_prints_cap);
+        for &(interval, capacity) in &self.bars {
+            if interval <= 0 {
+                return Err(BuildError::InvalidArgument("bars"));
+            }
+            local = local.bar_builder(BarBuilder::new(interval, capacity));
+        }
 
         let order_latency = self
             .latency_model
@@ -546,21 +1155,60 @@ where
                 let exch = L3NoPartialFillExchange::new(
                     reader.clone(),
                     create_depth(),
-                    State::new(asset_type, fee_model),
+                    State::new(asset_type, fee_model).fee_settlement(self.fee_settlement),
                     order_latency,
                     queue_model,
                     ob_exch_to_local,
                     ob_local_to_exch,
+                    self.post_only,
                 );
 
                 Ok(Asset {
                     local: Box::new(local),
                     exch: Box::new(exch),
+                    manifest,
                 })
             }
             ExchangeKind::PartialFillExchange => {
                 unimplemented!();
             }
+            ExchangeKind::Custom(name) => {
+                let registry = self
+                    .exch_registry
+                    .ok_or(BuildError::BuilderIncomplete("exchange_registry"))?;
+                let exch = registry.build(
+                    &name,
+                    ExchangeParts {
+                        reader: reader.clone(),
+                        depth: create_depth(),
+                        asset_type,
+                        fee_model,
+                        fee_settlement: self.fee_settlement,
+                        short_selling: ShortSelling::Allowed,
+                        order_latency,
+                        queue_model,
+                        orders_to_local: ob_exch_to_local,
+                        orders_from_local: ob_local_to_exch,
+                        post_only: self.post_only,
+                        fill_model: None,
+                        margin: None,
+                        throttle: None,
+                        faults: None,
+                        downtime: None,
+                        rate_limiter: None,
+                        fill_diagnostics: None,
+                        order_filter: None,
+                        rollover: None,
+                        calendar: None,
+                    },
+                )?;
+
+                Ok(Asset {
+                    local: Box::new(local),
+                    exch,
+                    manifest,
+                })
+            }
         }
     }
 }
@@ -583,6 +1231,8 @@ where
 pub struct BacktestBuilder<MD> {
     local: Vec<Box<dyn LocalProcessor<MD>>>,
     exch: Vec<Box<dyn Processor>>,
+    manifest: Manifest,
+    risk_guard: Option<Box<dyn RiskGuard + Send>>,
 }
 
 impl<MD> BacktestBuilder<MD> {
@@ -591,20 +1241,66 @@ impl<MD> BacktestBuilder<MD> {
         let mut self_ = Self { ..self };
         self_.local.push(asset.local);
         self_.exch.push(asset.exch);
+        self_.manifest.assets.push(asset.manifest);
+        self_
+    }
+
+    /// Sets the seed of all stochastic components, such as random latency spikes, so that the
+    /// backtest produces the same result on every run. Each asset's processors are reseeded by a
+    /// seed derived from this seed and the asset number.
+    pub fn seed(self, seed: u64) -> Self {
+        let mut self_ = Self { ..self };
+        self_.manifest.seed = Some(seed);
+        self_
+    }
+
+    /// Adds a parameter to the reproducibility [`Manifest`], such as a model parameter or a
+    /// strategy parameter.
+    pub fn parameter<V: ToString>(self, name: &str, value: V) -> Self {
+        let mut self_ = Self { ..self };
         self_
+            .manifest
+            .parameters
+            .push((name.to_string(), value.to_string()));
+        self_
+    }
+
+    /// Sets the [`RiskGuard`] that checks every new order before it's submitted. An order that
+    /// fails the check isn't submitted and [`BacktestError::RiskViolation`] is returned.
+    pub fn risk_guard<G>(self, guard: G) -> Self
+    where
+        G: RiskGuard + Send + 'static,
+    {
+        Self {
+            risk_guard: Some(Box::new(guard)),
+            ..self
+        }
     }
 
     /// Builds [`Backtest`].
-    pub fn build(self) -> Result<Backtest<MD>, BuildError> {
+    pub fn build(mut self) -> Result<Backtest<MD>, BuildError> {
         let num_assets = self.local.len();
         if self.local.len() != num_assets || self.exch.len() != num_assets {
             panic!();
         }
+        if let Some(seed) = self.manifest.seed {
+            let mut rng = SplitMix64::new(seed);
+            for (local, exch) in self.local.iter_mut().zip(self.exch.iter_mut()) {
+                let asset_seed = rng.next_u64();
+                local.seed(asset_seed);
+                exch.seed(asset_seed);
+            }
+        }
         Ok(Backtest {
             cur_ts: i64::MAX,
             evs: EventSet::new(num_assets),
             local: self.local,
             exch: self.exch,
+            manifest: self.manifest,
+            warmup_end: None,
+            order_ids: OrderIdGenerator::new(0, 0),
+            risk: RiskControl::new(self.risk_guard),
+            throughput: Default::default(),
         })
     }
 }
@@ -612,11 +1308,21 @@ impl<MD> BacktestBuilder<MD> {
 /// This backtester provides multi-asset and multi-exchange model backtesting, allowing you to
 /// configure different setups such as queue models or asset types for each asset. However, this may
 /// result in slightly slower performance compared to [`Backtest`].
+///
+/// Since all assets share one event loop, assets from different venues can be backtested together,
+/// such as for cross-venue arbitrage. Each asset can have its own exchange model and latency model
+/// that represent its venue, while the events of all venues are processed in the order of a single
+/// clock and [`Bot::portfolio`] aggregates the state across the venues.
 pub struct Backtest<MD> {
     cur_ts: i64,
     evs: EventSet,
     local: Vec<Box<dyn LocalProcessor<MD>>>,
     exch: Vec<Box<dyn Processor>>,
+    manifest: Manifest,
+    warmup_end: Option<i64>,
+    order_ids: OrderIdGenerator,
+    risk: RiskControl,
+    throughput: ThroughputMeter,
 }
 
 impl<MD> Backtest<MD>
@@ -627,6 +1333,8 @@ where
         BacktestBuilder {
             local: vec![],
             exch: vec![],
+            manifest: Default::default(),
+            risk_guard: None,
         }
     }
 
@@ -640,55 +1348,240 @@ where
             evs: EventSet::new(num_assets),
             local,
             exch,
+            manifest: Default::default(),
+            warmup_end: None,
+            order_ids: OrderIdGenerator::new(0, 0),
+            risk: RiskControl::default(),
+            throughput: Default::default(),
         }
     }
 
-    fn initialize_evs(&mut self) -> Result<(), BacktestError> {
-        for (asset_no, local) in self.local.iter_mut().enumerate() {
-            match local.initialize_data() {
-                Ok(ts) => self.evs.update_local_data(asset_no, ts),
-                Err(BacktestError::EndOfData) => {
-                    self.evs.invalidate_local_data(asset_no);
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
-        for (asset_no, exch) in self.exch.iter_mut().enumerate() {
-            match exch.initialize_data() {
-                Ok(ts) => self.evs.update_exch_data(asset_no, ts),
-                Err(BacktestError::EndOfData) => {
-                    self.evs.invalidate_exch_data(asset_no);
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
-        Ok(())
+    /// Returns the reproducibility [`Manifest`], which can be saved alongside the recorder output.
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
     }
 
-    pub fn goto_end(&mut self) -> Result<bool, BacktestError> {
-        if self.cur_ts == i64::MAX {
-            self.initialize_evs()?;
-            match self.evs.next() {
-                Some(ev) => {
-                    self.cur_ts = ev.timestamp;
-                }
-                None => {
-                    return Ok(false);
-                }
-            }
-        }
-        self.goto::<false>(UNTIL_END_OF_DATA, WaitOrderResponse::None)
+    /// Returns the [`Throughput`] of the backtest since its first elapse.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn throughput(&self) -> Throughput {
+        self.throughput.throughput(self.cur_ts)
     }
 
-    fn goto<const WAIT_NEXT_FEED: bool>(
+    fn check_risk(
+        &mut self,
+        asset_no: usize,
+        side: Side,
+        price: f64,
+        qty: f64,
+    ) -> Result<(), BacktestError> {
+        let local = self.local.get(asset_no).unwrap();
+        let (open_buy_qty, open_sell_qty) = open_qty(local.orders().values());
+        let order = NewOrder {
+            timestamp: self.cur_ts,
+            asset_no,
+            side,
+            price,
+            qty,
+            position: local.position(),
+            mid: local.depth().mid(),
+            open_orders: local
+                .orders()
+                .values()
+                .filter(|order| order.active())
+                .count(),
+            open_buy_qty,
+            open_sell_qty,
+        };
+        self.risk.check(&order)?;
+        Ok(())
+    }
+
+    /// Sets the [`PositionLimit`] of the asset, or removes it if `None`, which can be changed
+    /// while backtesting.
+    pub fn set_position_limit(&mut self, asset_no: usize, limit: Option<PositionLimit>) {
+        self.risk.set_position_limit(asset_no, limit);
+    }
+
+    /// Returns `true` if the asset is in the reduce-only mode since its [`PositionLimit`] is
+    /// breached.
+    pub fn is_reduce_only(&self, asset_no: usize) -> bool {
+        self.risk.is_reduce_only(asset_no)
+    }
+
+    fn enforce_position_limits(&mut self) -> Result<(), BacktestError> {
+        for asset_no in 0..self.local.len() {
+            let local = self.local.get(asset_no).unwrap();
+            let flatten = self
+                .risk
+                .update_position(asset_no, local.position(), |order_id| {
+                    local
+                        .orders()
+                        .get(&order_id)
+                        .is_some_and(|order| order.active() || order.pending())
+                });
+            if !flatten {
+                continue;
+            }
+            let Some((side, price, qty)) = flatten_order(local.position(), local.depth()) else {
+                continue;
+            };
+            let order_id = self.next_order_id();
+            let local = self.local.get_mut(asset_no).unwrap();
+            local.submit_order(
+                order_id,
+                side,
+                price,
+                qty,
+                OrdType::Limit,
+                TimeInForce::IOC,
+                true,
+                PositionSide::Both,
+                self.cur_ts,
+            )?;
+            self.risk.set_flatten_order(asset_no, order_id);
+        }
+        Ok(())
+    }
+
+    /// Sets the warm-up period, which lasts until the backtest reaches the `timestamp`. During the
+    /// warm-up, the feed is replayed as usual to build the market depth and the strategy's
+    /// indicators, but submitting an order fails with [`BacktestError::InvalidOrderRequest`], so
+    /// no position and PnL accrue. [`BacktestRecorder`](crate::backtest::recorder::BacktestRecorder)
+    /// skips the records during the warm-up, so that the statistics exclude the warm-up window.
+    ///
+    /// This must be set before any order is submitted.
+    pub fn warmup_until(&mut self, timestamp: i64) -> Result<(), BacktestError> {
+        let traded = self
+            .local
+            .iter()
+            .any(|local| !local.orders().is_empty() || local.state_values().num_trades > 0);
+        if traded {
+            return Err(BacktestError::InvalidOrderRequest);
+        }
+        self.warmup_end = Some(timestamp);
+        Ok(())
+    }
+
+    /// Returns the approximate memory usage of the specified asset, combining its local and
+    /// exchange processors. Both processors share the feed data, so it is counted once.
+    pub fn memory_usage(&self, asset_no: usize) -> MemoryUsage {
+        MemoryUsage::of_asset(
+            self.local.get(asset_no).unwrap(),
+            self.exch.get(asset_no).unwrap(),
+        )
+    }
+
+    /// Saves the state of the backtest into a checkpoint file at the specified path, so that a
+    /// long backtest can be resumed later by [`load_checkpoint`](Self::load_checkpoint), such as
+    /// in another session or after a crash.
+    ///
+    /// The checkpoint includes the position in the feed data, the market depth, the orders,
+    /// including those in transit, the state values, the stored trades, bars, fills and
+    /// liquidation prints, the open interest, the event statistics, and the internal state of the
+    /// latency and queue models. The following are not included, so a resumed backtest may diverge
+    /// if they are in use:
+    /// * The shared [`FeeAccount`](crate::backtest::models::FeeAccount),
+    ///   [`MarginAccount`](crate::backtest::margin::MarginAccount),
+    ///   [`Throttle`](crate::backtest::throttle::Throttle) and
+    ///   [`FaultInjector`](crate::backtest::fault::FaultInjector).
+    /// * The state of the [`FillModel`](crate::backtest::models::FillModel).
+    /// * The queue position of the orders in transit.
+    ///
+    /// Only the level-2 processors support checkpoints.
+    pub fn save_checkpoint<P>(&self, path: P) -> Result<(), BacktestError>
+    where
+        P: AsRef<Path>,
+    {
+        if self.cur_ts == i64::MAX {
+            return Err(checkpoint_error("the backtest hasn't started"));
+        }
+        let mut writer = CheckpointWriter::new();
+        writer.write(&(self.local.len(), self.cur_ts))?;
+        self.evs.save_checkpoint(&mut writer)?;
+        for (local, exch) in self.local.iter().zip(self.exch.iter()) {
+            local.save_checkpoint(&mut writer)?;
+            exch.save_checkpoint(&mut writer)?;
+        }
+        fs::write(path, writer.into_bytes()).map_err(BacktestError::DataError)
+    }
+
+    /// Restores the state of the backtest from a checkpoint file saved by
+    /// [`save_checkpoint`](Self::save_checkpoint). The backtest must be newly built with the same
+    /// configuration and data as the one that saved the checkpoint, and must not have started.
+    /// If this fails, the backtest is left in an undefined state and should be discarded.
+    pub fn load_checkpoint<P>(&mut self, path: P) -> Result<(), BacktestError>
+    where
+        P: AsRef<Path>,
+    {
+        if self.cur_ts != i64::MAX {
+            return Err(checkpoint_error("the backtest has already started"));
+        }
+        let buf = fs::read(path).map_err(BacktestError::DataError)?;
+        let mut reader = CheckpointReader::new(&buf)?;
+        let (num_assets, cur_ts): (usize, i64) = reader.read()?;
+        if num_assets != self.local.len() {
+            return Err(checkpoint_error("the number of assets doesn't match"));
+        }
+        self.evs.load_checkpoint(&mut reader)?;
+        for (local, exch) in self.local.iter_mut().zip(self.exch.iter_mut()) {
+            local.load_checkpoint(&mut reader)?;
+            exch.load_checkpoint(&mut reader)?;
+        }
+        if !reader.is_empty() {
+            return Err(checkpoint_error("the checkpoint has unread data"));
+        }
+        self.cur_ts = cur_ts;
+        Ok(())
+    }
+
+    fn initialize_evs(&mut self) -> Result<(), BacktestError> {
+        for (asset_no, local) in self.local.iter_mut().enumerate() {
+            match local.initialize_data() {
+                Ok(ts) => self.evs.update_local_data(asset_no, ts),
+                Err(BacktestError::EndOfData) => {
+                    self.evs.invalidate_local_data(asset_no);
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+        for (asset_no, exch) in self.exch.iter_mut().enumerate() {
+            match exch.initialize_data() {
+                Ok(ts) => self.evs.update_exch_data(asset_no, ts),
+                Err(BacktestError::EndOfData) => {
+                    self.evs.invalidate_exch_data(asset_no);
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn goto_end(&mut self) -> Result<bool, BacktestError> {
+        if self.cur_ts == i64::MAX {
+            self.initialize_evs()?;
+            match self.evs.next() {
+                Some(ev) => {
+                    self.cur_ts = ev.timestamp;
+                }
+                None => {
+                    return Ok(false);
+                }
+            }
+        }
+        self.goto::<false>(UNTIL_END_OF_DATA, WaitOrderResponse::None)
+    }
+
+    fn goto<const WAIT_NEXT_FEED: bool>(
         &mut self,
         timestamp: i64,
         wait_order_response: WaitOrderResponse,
     ) -> Result<bool, BacktestError> {
+        self.enforce_position_limits()?;
+        self.throughput.start(self.cur_ts);
         let mut timestamp = timestamp;
         for (asset_no, local) in self.local.iter().enumerate() {
             self.evs
@@ -705,6 +1598,7 @@ where
                     }
                     match ev.kind {
                         EventIntentKind::LocalData => {
+                            self.throughput.count();
                             let local = unsafe { self.local.get_unchecked_mut(ev.asset_no) };
                             match local.process_data() {
                                 Ok((next_ts, _)) => {
@@ -723,15 +1617,16 @@ where
                         }
                         EventIntentKind::LocalOrder => {
                             let local = unsafe { self.local.get_unchecked_mut(ev.asset_no) };
-                            let wait_order_resp_id = match wait_order_response {
-                                WaitOrderResponse::Specified {
-                                    asset_no: wait_order_asset_no,
-                                    order_id: wait_order_id,
-                                } if ev.asset_no == wait_order_asset_no => Some(wait_order_id),
-                                _ => None,
-                            };
-                            if local.process_recv_order(ev.timestamp, wait_order_resp_id)?
-                                || wait_order_response == WaitOrderResponse::Any
+                            let wait_order_resp_id = wait_order_response.order_id(ev.asset_no);
+                            let received =
+                                local.process_recv_order(ev.timestamp, wait_order_resp_id)?;
+                            if wait_order_response == WaitOrderResponse::Any
+                                || (received
+                                    && wait_order_resp_id
+                                        .and_then(|order_id| local.orders().get(&order_id))
+                                        .is_none_or(|order| {
+                                            wait_order_response.is_met(ev.asset_no, order)
+                                        }))
                             {
                                 timestamp = ev.timestamp;
                             }
@@ -741,6 +1636,7 @@ where
                             );
                         }
                         EventIntentKind::ExchData => {
+                            self.throughput.count();
                             let exch = unsafe { self.exch.get_unchecked_mut(ev.asset_no) };
                             match exch.process_data() {
                                 Ok((next_ts, _)) => {
@@ -776,16 +1672,22 @@ where
     }
 }
 
-impl<MD> Bot<MD> for Backtest<MD>
+impl<MD> Clock for Backtest<MD>
 where
     MD: MarketDepth,
 {
-    type Error = BacktestError;
-
+    /// Returns the simulated wall clock, which advances by the timestamps in the data.
     #[inline]
-    fn current_timestamp(&self) -> i64 {
+    fn now(&self) -> i64 {
         self.cur_ts
     }
+}
+
+impl<MD> Bot<MD> for Backtest<MD>
+where
+    MD: MarketDepth,
+{
+    type Error = BacktestError;
 
     #[inline]
     fn num_assets(&self) -> usize {
@@ -802,6 +1704,14 @@ where
         self.local.get(asset_no).unwrap().state_values()
     }
 
+    fn portfolio(&self) -> Portfolio {
+        let mut portfolio = Portfolio::default();
+        for local in &self.local {
+            portfolio += local.portfolio();
+        }
+        portfolio
+    }
+
     fn depth(&self, asset_no: usize) -> &MD {
         self.local.get(asset_no).unwrap().depth()
     }
@@ -825,11 +1735,100 @@ where
         }
     }
 
+    fn last_depth_updates(&self, asset_no: usize) -> &[DepthUpdate] {
+        self.local.get(asset_no).unwrap().last_depth_updates()
+    }
+
+    #[inline]
+    fn bars(&self, asset_no: usize, interval: i64) -> &[Bar] {
+        self.local.get(asset_no).unwrap().bars(interval)
+    }
+
+    #[inline]
+    fn clear_last_depth_updates(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(an) => {
+                let local = self.local.get_mut(an).unwrap();
+                local.clear_last_depth_updates();
+            }
+            None => {
+                for local in self.local.iter_mut() {
+                    local.clear_last_depth_updates();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn liquidations(&self, asset_no: usize) -> &[Liquidation] {
+        self.local.get(asset_no).unwrap().liquidations()
+    }
+
+    #[inline]
+    fn last_fills(&self, asset_no: usize) -> &[Fill] {
+        self.local.get(asset_no).unwrap().last_fills()
+    }
+
+    #[inline]
+    fn clear_last_fills(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(an) => {
+                let local = self.local.get_mut(an).unwrap();
+                local.clear_last_fills();
+            }
+            None => {
+                for local in self.local.iter_mut() {
+                    local.clear_last_fills();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn last_liquidation_prints(&self, asset_no: usize) -> &[Event] {
+        self.local.get(asset_no).unwrap().last_liquidation_prints()
+    }
+
+    #[inline]
+    fn clear_last_liquidation_prints(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(an) => {
+                let local = self.local.get_mut(an).unwrap();
+                local.clear_last_liquidation_prints();
+            }
+            None => {
+                for local in self.local.iter_mut() {
+                    local.clear_last_liquidation_prints();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn open_interest(&self, asset_no: usize) -> Option<f64> {
+        self.local.get(asset_no).unwrap().open_interest()
+    }
+
     #[inline]
     fn orders(&self, asset_no: usize) -> &HashMap<u64, Order> {
         self.local.get(asset_no).unwrap().orders()
     }
 
+    #[inline]
+    fn next_order_id(&mut self) -> OrderId {
+        // Skips the order IDs restored from a checkpoint.
+        loop {
+            let order_id = self.order_ids.next_order_id();
+            if !self
+                .local
+                .iter()
+                .any(|local| local.orders().contains_key(&order_id))
+            {
+                return order_id;
+            }
+        }
+    }
+
     #[inline]
     fn submit_buy_order(
         &mut self,
@@ -841,6 +1840,10 @@ where
         order_type: OrdType,
         wait: bool,
     ) -> Result<bool, Self::Error> {
+        if self.is_warming_up() {
+            return Err(BacktestError::InvalidOrderRequest);
+        }
+        self.check_risk(asset_no, Side::Buy, price, qty)?;
         let local = self.local.get_mut(asset_no).unwrap();
         local.submit_order(
             order_id,
@@ -849,6 +1852,8 @@ where
             qty,
             order_type,
             time_in_force,
+            false,
+            PositionSide::Both,
             self.cur_ts,
         )?;
 
@@ -872,6 +1877,10 @@ where
         order_type: OrdType,
         wait: bool,
     ) -> Result<bool, Self::Error> {
+        if self.is_warming_up() {
+            return Err(BacktestError::InvalidOrderRequest);
+        }
+        self.check_risk(asset_no, Side::Sell, price, qty)?;
         let local = self.local.get_mut(asset_no).unwrap();
         local.submit_order(
             order_id,
@@ -880,6 +1889,8 @@ where
             qty,
             order_type,
             time_in_force,
+            false,
+            PositionSide::Both,
             self.cur_ts,
         )?;
 
@@ -898,14 +1909,20 @@ where
         order: OrderRequest,
         wait: bool,
     ) -> Result<bool, Self::Error> {
+        if self.is_warming_up() {
+            return Err(BacktestError::InvalidOrderRequest);
+        }
+        self.check_risk(asset_no, order.side, order.price, order.qty)?;
         let local = self.local.get_mut(asset_no).unwrap();
         local.submit_order(
             order.order_id,
-            Side::Sell,
+            order.side,
             order.price,
             order.qty,
             order.order_type,
             order.time_in_force,
+            order.reduce_only,
+            order.position_side,
             self.cur_ts,
         )?;
 
@@ -970,6 +1987,26 @@ where
         )
     }
 
+    fn wait_order_status(
+        &mut self,
+        asset_no: usize,
+        order_id: OrderId,
+        status: Status,
+        timeout: i64,
+    ) -> Result<bool, BacktestError> {
+        let wait = WaitOrderResponse::UntilStatus {
+            asset_no,
+            order_id,
+            status,
+        };
+        if let Some(order) = self.local.get(asset_no).unwrap().orders().get(&order_id) {
+            if wait.is_met(asset_no, order) {
+                return Ok(true);
+            }
+        }
+        self.goto::<false>(self.cur_ts + timeout, wait)
+    }
+
     #[inline]
     fn wait_next_feed(
         &mut self,
@@ -994,6 +2031,32 @@ where
         }
     }
 
+    fn wait_any(
+        &mut self,
+        conditions: &[WaitCondition],
+    ) -> Result<Option<WaitCondition>, Self::Error> {
+        if self.cur_ts == i64::MAX {
+            self.initialize_evs()?;
+            match self.evs.next() {
+                Some(ev) => {
+                    self.cur_ts = ev.timestamp;
+                }
+                None => {
+                    return Ok(None);
+                }
+            }
+        }
+        let wait = WaitAny::new(self, conditions);
+        loop {
+            // The events processed right before the end of the data can still fire a condition.
+            let has_next = self.goto::<true>(wait.deadline(), WaitOrderResponse::Any)?;
+            let fired = wait.check(self);
+            if fired.is_some() || !has_next {
+                return Ok(fired);
+            }
+        }
+    }
+
     #[inline]
     fn elapse(&mut self, duration: i64) -> Result<bool, Self::Error> {
         if self.cur_ts == i64::MAX {
@@ -1017,6 +2080,9 @@ where
 
     #[inline]
     fn close(&mut self) -> Result<(), Self::Error> {
+        for asset_no in 0..self.local.len() {
+            self.memory_usage(asset_no).log(asset_no);
+        }
         Ok(())
     }
 
@@ -1029,12 +2095,32 @@ where
     fn order_latency(&self, asset_no: usize) -> Option<(i64, i64, i64)> {
         self.local.get(asset_no).unwrap().order_latency()
     }
+
+    #[inline]
+    fn event_stats(&mut self, asset_no: usize) -> EventStats {
+        self.local.get_mut(asset_no).unwrap().take_event_stats()
+    }
+
+    #[inline]
+    fn risk_stats(&self, asset_no: usize) -> RiskStats {
+        self.risk.stats(asset_no)
+    }
+
+    #[inline]
+    fn is_warming_up(&self) -> bool {
+        match self.warmup_end {
+            Some(warmup_end) => self.cur_ts == i64::MAX || self.cur_ts < warmup_end,
+            None => false,
+        }
+    }
 }
 
 /// `MultiAssetSingleExchangeBacktest` builder.
 pub struct MultiAssetSingleExchangeBacktestBuilder<Local, Exchange> {
     local: Vec<Local>,
     exch: Vec<Exchange>,
+    manifest: Manifest,
+    risk_guard: Option<Box<dyn RiskGuard + Send>>,
 }
 
 impl<Local, Exchange> MultiAssetSingleExchangeBacktestBuilder<Local, Exchange>
@@ -1047,37 +2133,105 @@ where
         let mut self_ = Self { ..self };
         self_.local.push(*asset.local);
         self_.exch.push(*asset.exch);
+        self_.manifest.assets.push(asset.manifest);
+        self_
+    }
+
+    /// Sets the seed of all stochastic components. See [`BacktestBuilder::seed`].
+    pub fn seed(self, seed: u64) -> Self {
+        let mut self_ = Self { ..self };
+        self_.manifest.seed = Some(seed);
+        self_
+    }
+
+    /// Adds a parameter to the reproducibility [`Manifest`].
+    pub fn parameter<V: ToString>(self, name: &str, value: V) -> Self {
+        let mut self_ = Self { ..self };
         self_
+            .manifest
+            .parameters
+            .push((name.to_string(), value.to_string()));
+        self_
+    }
+
+    /// Sets the [`RiskGuard`]. See [`BacktestBuilder::risk_guard`].
+    pub fn risk_guard<G>(self, guard: G) -> Self
+    where
+        G: RiskGuard + Send + 'static,
+    {
+        Self {
+            risk_guard: Some(Box::new(guard)),
+            ..self
+        }
     }
 
     /// Builds [`MultiAssetSingleExchangeBacktest`].
     pub fn build(
-        self,
+        mut self,
     ) -> Result<MultiAssetSingleExchangeBacktest<HashMapMarketDepth, Local, Exchange>, BuildError>
     {
         let num_assets = self.local.len();
         if self.local.len() != num_assets || self.exch.len() != num_assets {
             panic!();
         }
+        if let Some(seed) = self.manifest.seed {
+            let mut rng = SplitMix64::new(seed);
+            for (local, exch) in self.local.iter_mut().zip(self.exch.iter_mut()) {
+                let asset_seed = rng.next_u64();
+                local.seed(asset_seed);
+                exch.seed(asset_seed);
+            }
+        }
         Ok(MultiAssetSingleExchangeBacktest {
             cur_ts: i64::MAX,
             evs: EventSet::new(num_assets),
             local: self.local,
             exch: self.exch,
+            manifest: self.manifest,
+            warmup_end: None,
+            order_ids: OrderIdGenerator::new(0, 0),
+            risk: RiskControl::new(self.risk_guard),
+            throughput: Default::default(),
             _md_marker: Default::default(),
         })
     }
 }
 
+impl
+    MultiAssetSingleExchangeBacktestBuilder<
+        Box<dyn LocalProcessor<HashMapMarketDepth>>,
+        Box<dyn Processor>,
+    >
+{
+    /// Adds [`Asset`] built by [`L2AssetBuilder`] or [`L3AssetBuilder`], so that the assets can
+    /// use different exchange models at the cost of the dynamic dispatch.
+    pub fn add_dyn_asset(
+        self,
+        asset: Asset<dyn LocalProcessor<HashMapMarketDepth>, dyn Processor>,
+    ) -> Self {
+        let mut self_ = Self { ..self };
+        self_.local.push(asset.local);
+        self_.exch.push(asset.exch);
+        self_.manifest.assets.push(asset.manifest);
+        self_
+    }
+}
+
 /// This backtester provides multi-asset and single-exchange model backtesting, meaning all assets
 /// have the same setups for models such as asset type or queue model. However, this can be slightly
 /// faster than [`Backtest`]. If you need to configure different models for each asset, use
-/// [`Backtest`].
+/// [`Backtest`], or combine the processor types with [`Either`](proc::Either) or, at the cost of
+/// the dynamic dispatch, with [`MultiAssetSingleExchangeBacktestBuilder::add_dyn_asset`].
 pub struct MultiAssetSingleExchangeBacktest<MD, Local, Exchange> {
     cur_ts: i64,
     evs: EventSet,
     local: Vec<Local>,
     exch: Vec<Exchange>,
+    manifest: Manifest,
+    warmup_end: Option<i64>,
+    order_ids: OrderIdGenerator,
+    risk: RiskControl,
+    throughput: ThroughputMeter,
     _md_marker: PhantomData<MD>,
 }
 
@@ -1091,6 +2245,8 @@ where
         MultiAssetSingleExchangeBacktestBuilder {
             local: vec![],
             exch: vec![],
+            manifest: Default::default(),
+            risk_guard: None,
         }
     }
 
@@ -1104,41 +2260,226 @@ where
             evs: EventSet::new(num_assets),
             local,
             exch,
+            manifest: Default::default(),
+            warmup_end: None,
+            order_ids: OrderIdGenerator::new(0, 0),
+            risk: RiskControl::default(),
+            throughput: Default::default(),
             _md_marker: Default::default(),
         }
     }
 
-    fn initialize_evs(&mut self) -> Result<(), BacktestError> {
-        for (asset_no, local) in self.local.iter_mut().enumerate() {
-            match local.initialize_data() {
-                Ok(ts) => self.evs.update_local_data(asset_no, ts),
-                Err(BacktestError::EndOfData) => {
-                    self.evs.invalidate_local_data(asset_no);
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
-        for (asset_no, exch) in self.exch.iter_mut().enumerate() {
-            match exch.initialize_data() {
-                Ok(ts) => self.evs.update_exch_data(asset_no, ts),
-                Err(BacktestError::EndOfData) => {
-                    self.evs.invalidate_exch_data(asset_no);
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
-        Ok(())
+    /// Returns the reproducibility [`Manifest`], which can be saved alongside the recorder output.
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
     }
 
-    pub fn goto<const WAIT_NEXT_FEED: bool>(
+    /// Returns the [`Throughput`] of the backtest since its first elapse.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn throughput(&self) -> Throughput {
+        self.throughput.throughput(self.cur_ts)
+    }
+
+    fn check_risk(
         &mut self,
-        timestamp: i64,
-        wait_order_response: WaitOrderResponse,
-    ) -> Result<bool, BacktestError> {
+        asset_no: usize,
+        side: Side,
+        price: f64,
+        qty: f64,
+    ) -> Result<(), BacktestError> {
+        let local = self.local.get(asset_no).unwrap();
+        let (open_buy_qty, open_sell_qty) = open_qty(local.orders().values());
+        let order = NewOrder {
+            timestamp: self.cur_ts,
+            asset_no,
+            side,
+            price,
+            qty,
+            position: local.position(),
+            mid: local.depth().mid(),
+            open_orders: local
+                .orders()
+                .values()
+                .filter(|order| order.active())
+                .count(),
+            open_buy_qty,
+            open_sell_qty,
+        };
+        self.risk.check(&order)?;
+        Ok(())
+    }
+
+    /// Sets the [`PositionLimit`] of the asset, or removes it if `None`, which can be changed
+    /// while backtesting.
+    pub fn set_position_limit(&mut self, asset_no: usize, limit: Option<PositionLimit>) {
+        self.risk.set_position_limit(asset_no, limit);
+    }
+
+    /// Returns `true` if the asset is in the reduce-only mode since its [`PositionLimit`] is
+    /// breached.
+    pub fn is_reduce_only(&self, asset_no: usize) -> bool {
+        self.risk.is_reduce_only(asset_no)
+    }
+
+    fn enforce_position_limits(&mut self) -> Result<(), BacktestError> {
+        for asset_no in 0..self.local.len() {
+            let local = self.local.get(asset_no).unwrap();
+            let flatten = self
+                .risk
+                .update_position(asset_no, local.position(), |order_id| {
+                    local
+                        .orders()
+                        .get(&order_id)
+                        .is_some_and(|order| order.active() || order.pending())
+                });
+            if !flatten {
+                continue;
+            }
+            let Some((side, price, qty)) = flatten_order(local.position(), local.depth()) else {
+                continue;
+            };
+            let order_id = self.next_order_id();
+            let local = self.local.get_mut(asset_no).unwrap();
+            local.submit_order(
+                order_id,
+                side,
+                price,
+                qty,
+                OrdType::Limit,
+                TimeInForce::IOC,
+                true,
+                PositionSide::Both,
+                self.cur_ts,
+            )?;
+            self.risk.set_flatten_order(asset_no, order_id);
+        }
+        Ok(())
+    }
+
+    /// Sets the warm-up period, which lasts until the backtest reaches the `timestamp`. During the
+    /// warm-up, the feed is replayed as usual to build the market depth and the strategy's
+    /// indicators, but submitting an order fails with [`BacktestError::InvalidOrderRequest`], so
+    /// no position and PnL accrue. [`BacktestRecorder`](crate::backtest::recorder::BacktestRecorder)
+    /// skips the records during the warm-up, so that the statistics exclude the warm-up window.
+    ///
+    /// This must be set before any order is submitted.
+    pub fn warmup_until(&mut self, timestamp: i64) -> Result<(), BacktestError> {
+        let traded = self
+            .local
+            .iter()
+            .any(|local| !local.orders().is_empty() || local.state_values().num_trades > 0);
+        if traded {
+            return Err(BacktestError::InvalidOrderRequest);
+        }
+        self.warmup_end = Some(timestamp);
+        Ok(())
+    }
+
+    /// Returns the approximate memory usage of the specified asset, combining its local and
+    /// exchange processors. Both processors share the feed data, so it is counted once.
+    pub fn memory_usage(&self, asset_no: usize) -> MemoryUsage {
+        MemoryUsage::of_asset(
+            self.local.get(asset_no).unwrap(),
+            self.exch.get(asset_no).unwrap(),
+        )
+    }
+
+    /// Saves the state of the backtest into a checkpoint file at the specified path, so that a
+    /// long backtest can be resumed later by [`load_checkpoint`](Self::load_checkpoint), such as
+    /// in another session or after a crash.
+    ///
+    /// The checkpoint includes the position in the feed data, the market depth, the orders,
+    /// including those in transit, the state values, the stored trades, bars, fills and
+    /// liquidation prints, the open interest, the event statistics, and the internal state of the
+    /// latency and queue models. The following are not included, so a resumed backtest may diverge
+    /// if they are in use:
+    /// * The shared [`FeeAccount`](crate::backtest::models::FeeAccount),
+    ///   [`MarginAccount`](crate::backtest::margin::MarginAccount),
+    ///   [`Throttle`](crate::backtest::throttle::Throttle) and
+    ///   [`FaultInjector`](crate::backtest::fault::FaultInjector).
+    /// * The state of the [`FillModel`](crate::backtest::models::FillModel).
+    /// * The queue position of the orders in transit.
+    ///
+    /// Only the level-2 processors support checkpoints.
+    pub fn save_checkpoint<P>(&self, path: P) -> Result<(), BacktestError>
+    where
+        P: AsRef<Path>,
+    {
+        if self.cur_ts == i64::MAX {
+            return Err(checkpoint_error("the backtest hasn't started"));
+        }
+        let mut writer = CheckpointWriter::new();
+        writer.write(&(self.local.len(), self.cur_ts))?;
+        self.evs.save_checkpoint(&mut writer)?;
+        for (local, exch) in self.local.iter().zip(self.exch.iter()) {
+            local.save_checkpoint(&mut writer)?;
+            exch.save_checkpoint(&mut writer)?;
+        }
+        fs::write(path, writer.into_bytes()).map_err(BacktestError::DataError)
+    }
+
+    /// Restores the state of the backtest from a checkpoint file saved by
+    /// [`save_checkpoint`](Self::save_checkpoint). The backtest must be newly built with the same
+    /// configuration and data as the one that saved the checkpoint, and must not have started.
+    /// If this fails, the backtest is left in an undefined state and should be discarded.
+    pub fn load_checkpoint<P>(&mut self, path: P) -> Result<(), BacktestError>
+    where
+        P: AsRef<Path>,
+    {
+        if self.cur_ts != i64::MAX {
+            return Err(checkpoint_error("the backtest has already started"));
+        }
+        let buf = fs::read(path).map_err(BacktestError::DataError)?;
+        let mut reader = CheckpointReader::new(&buf)?;
+        let (num_assets, cur_ts): (usize, i64) = reader.read()?;
+        if num_assets != self.local.len() {
+            return Err(checkpoint_error("the number of assets doesn't match"));
+        }
+        self.evs.load_checkpoint(&mut reader)?;
+        for (local, exch) in self.local.iter_mut().zip(self.exch.iter_mut()) {
+            local.load_checkpoint(&mut reader)?;
+            exch.load_checkpoint(&mut reader)?;
+        }
+        if !reader.is_empty() {
+            return Err(checkpoint_error("the checkpoint has unread data"));
+        }
+        self.cur_ts = cur_ts;
+        Ok(())
+    }
+
+    fn initialize_evs(&mut self) -> Result<(), BacktestError> {
+        for (asset_no, local) in self.local.iter_mut().enumerate() {
+            match local.initialize_data() {
+                Ok(ts) => self.evs.update_local_data(asset_no, ts),
+                Err(BacktestError::EndOfData) => {
+                    self.evs.invalidate_local_data(asset_no);
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+        for (asset_no, exch) in self.exch.iter_mut().enumerate() {
+            match exch.initialize_data() {
+                Ok(ts) => self.evs.update_exch_data(asset_no, ts),
+                Err(BacktestError::EndOfData) => {
+                    self.evs.invalidate_exch_data(asset_no);
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn goto<const WAIT_NEXT_FEED: bool>(
+        &mut self,
+        timestamp: i64,
+        wait_order_response: WaitOrderResponse,
+    ) -> Result<bool, BacktestError> {
+        self.enforce_position_limits()?;
+        self.throughput.start(self.cur_ts);
         let mut timestamp = timestamp;
         for (asset_no, local) in self.local.iter().enumerate() {
             self.evs
@@ -1155,6 +2496,7 @@ where
                     }
                     match ev.kind {
                         EventIntentKind::LocalData => {
+                            self.throughput.count();
                             let local = unsafe { self.local.get_unchecked_mut(ev.asset_no) };
                             match local.process_data() {
                                 Ok((next_ts, _)) => {
@@ -1173,15 +2515,16 @@ where
                         }
                         EventIntentKind::LocalOrder => {
                             let local = unsafe { self.local.get_unchecked_mut(ev.asset_no) };
-                            let wait_order_resp_id = match wait_order_response {
-                                WaitOrderResponse::Specified {
-                                    asset_no: wait_order_asset_no,
-                                    order_id: wait_order_id,
-                                } if ev.asset_no == wait_order_asset_no => Some(wait_order_id),
-                                _ => None,
-                            };
-                            if local.process_recv_order(ev.timestamp, wait_order_resp_id)?
-                                || wait_order_response == WaitOrderResponse::Any
+                            let wait_order_resp_id = wait_order_response.order_id(ev.asset_no);
+                            let received =
+                                local.process_recv_order(ev.timestamp, wait_order_resp_id)?;
+                            if wait_order_response == WaitOrderResponse::Any
+                                || (received
+                                    && wait_order_resp_id
+                                        .and_then(|order_id| local.orders().get(&order_id))
+                                        .is_none_or(|order| {
+                                            wait_order_response.is_met(ev.asset_no, order)
+                                        }))
                             {
                                 timestamp = ev.timestamp;
                             }
@@ -1191,6 +2534,7 @@ where
                             );
                         }
                         EventIntentKind::ExchData => {
+                            self.throughput.count();
                             let exch = unsafe { self.exch.get_unchecked_mut(ev.asset_no) };
                             match exch.process_data() {
                                 Ok((next_ts, _)) => {
@@ -1226,18 +2570,26 @@ where
     }
 }
 
-impl<MD, Local, Exchange> Bot<MD> for MultiAssetSingleExchangeBacktest<MD, Local, Exchange>
+impl<MD, Local, Exchange> Clock for MultiAssetSingleExchangeBacktest<MD, Local, Exchange>
 where
     MD: MarketDepth,
     Local: LocalProcessor<MD>,
     Exchange: Processor,
 {
-    type Error = BacktestError;
-
+    /// Returns the simulated wall clock, which advances by the timestamps in the data.
     #[inline]
-    fn current_timestamp(&self) -> i64 {
+    fn now(&self) -> i64 {
         self.cur_ts
     }
+}
+
+impl<MD, Local, Exchange> Bot<MD> for MultiAssetSingleExchangeBacktest<MD, Local, Exchange>
+where
+    MD: MarketDepth,
+    Local: LocalProcessor<MD>,
+    Exchange: Processor,
+{
+    type Error = BacktestError;
 
     #[inline]
     fn num_assets(&self) -> usize {
@@ -1254,6 +2606,14 @@ where
         self.local.get(asset_no).unwrap().state_values()
     }
 
+    fn portfolio(&self) -> Portfolio {
+        let mut portfolio = Portfolio::default();
+        for local in &self.local {
+            portfolio += local.portfolio();
+        }
+        portfolio
+    }
+
     fn depth(&self, asset_no: usize) -> &MD {
         self.local.get(asset_no).unwrap().depth()
     }
@@ -1277,11 +2637,100 @@ where
         }
     }
 
+    fn last_depth_updates(&self, asset_no: usize) -> &[DepthUpdate] {
+        self.local.get(asset_no).unwrap().last_depth_updates()
+    }
+
+    #[inline]
+    fn bars(&self, asset_no: usize, interval: i64) -> &[Bar] {
+        self.local.get(asset_no).unwrap().bars(interval)
+    }
+
+    #[inline]
+    fn clear_last_depth_updates(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(an) => {
+                let local = self.local.get_mut(an).unwrap();
+                local.clear_last_depth_updates();
+            }
+            None => {
+                for local in self.local.iter_mut() {
+                    local.clear_last_depth_updates();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn liquidations(&self, asset_no: usize) -> &[Liquidation] {
+        self.local.get(asset_no).unwrap().liquidations()
+    }
+
+    #[inline]
+    fn last_fills(&self, asset_no: usize) -> &[Fill] {
+        self.local.get(asset_no).unwrap().last_fills()
+    }
+
+    #[inline]
+    fn clear_last_fills(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(an) => {
+                let local = self.local.get_mut(an).unwrap();
+                local.clear_last_fills();
+            }
+            None => {
+                for local in self.local.iter_mut() {
+                    local.clear_last_fills();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn last_liquidation_prints(&self, asset_no: usize) -> &[Event] {
+        self.local.get(asset_no).unwrap().last_liquidation_prints()
+    }
+
+    #[inline]
+    fn clear_last_liquidation_prints(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(an) => {
+                let local = self.local.get_mut(an).unwrap();
+                local.clear_last_liquidation_prints();
+            }
+            None => {
+                for local in self.local.iter_mut() {
+                    local.clear_last_liquidation_prints();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn open_interest(&self, asset_no: usize) -> Option<f64> {
+        self.local.get(asset_no).unwrap().open_interest()
+    }
+
     #[inline]
     fn orders(&self, asset_no: usize) -> &HashMap<OrderId, Order> {
         self.local.get(asset_no).unwrap().orders()
     }
 
+    #[inline]
+    fn next_order_id(&mut self) -> OrderId {
+        // Skips the order IDs restored from a checkpoint.
+        loop {
+            let order_id = self.order_ids.next_order_id();
+            if !self
+                .local
+                .iter()
+                .any(|local| local.orders().contains_key(&order_id))
+            {
+                return order_id;
+            }
+        }
+    }
+
     #[inline]
     fn submit_buy_order(
         &mut self,
@@ -1293,6 +2742,10 @@ where
         order_type: OrdType,
         wait: bool,
     ) -> Result<bool, Self::Error> {
+        if self.is_warming_up() {
+            return Err(BacktestError::InvalidOrderRequest);
+        }
+        self.check_risk(asset_no, Side::Buy, price, qty)?;
         let local = self.local.get_mut(asset_no).unwrap();
         local.submit_order(
             order_id,
@@ -1301,6 +2754,8 @@ where
             qty,
             order_type,
             time_in_force,
+            false,
+            PositionSide::Both,
             self.cur_ts,
         )?;
 
@@ -1324,6 +2779,10 @@ where
         order_type: OrdType,
         wait: bool,
     ) -> Result<bool, Self::Error> {
+        if self.is_warming_up() {
+            return Err(BacktestError::InvalidOrderRequest);
+        }
+        self.check_risk(asset_no, Side::Sell, price, qty)?;
         let local = self.local.get_mut(asset_no).unwrap();
         local.submit_order(
             order_id,
@@ -1332,6 +2791,8 @@ where
             qty,
             order_type,
             time_in_force,
+            false,
+            PositionSide::Both,
             self.cur_ts,
         )?;
 
@@ -1350,14 +2811,20 @@ where
         order: OrderRequest,
         wait: bool,
     ) -> Result<bool, Self::Error> {
+        if self.is_warming_up() {
+            return Err(BacktestError::InvalidOrderRequest);
+        }
+        self.check_risk(asset_no, order.side, order.price, order.qty)?;
         let local = self.local.get_mut(asset_no).unwrap();
         local.submit_order(
             order.order_id,
-            Side::Sell,
+            order.side,
             order.price,
             order.qty,
             order.order_type,
             order.time_in_force,
+            order.reduce_only,
+            order.position_side,
             self.cur_ts,
         )?;
 
@@ -1422,6 +2889,26 @@ where
         )
     }
 
+    fn wait_order_status(
+        &mut self,
+        asset_no: usize,
+        order_id: OrderId,
+        status: Status,
+        timeout: i64,
+    ) -> Result<bool, BacktestError> {
+        let wait = WaitOrderResponse::UntilStatus {
+            asset_no,
+            order_id,
+            status,
+        };
+        if let Some(order) = self.local.get(asset_no).unwrap().orders().get(&order_id) {
+            if wait.is_met(asset_no, order) {
+                return Ok(true);
+            }
+        }
+        self.goto::<false>(self.cur_ts + timeout, wait)
+    }
+
     fn wait_next_feed(
         &mut self,
         include_order_resp: bool,
@@ -1445,6 +2932,32 @@ where
         }
     }
 
+    fn wait_any(
+        &mut self,
+        conditions: &[WaitCondition],
+    ) -> Result<Option<WaitCondition>, Self::Error> {
+        if self.cur_ts == i64::MAX {
+            self.initialize_evs()?;
+            match self.evs.next() {
+                Some(ev) => {
+                    self.cur_ts = ev.timestamp;
+                }
+                None => {
+                    return Ok(None);
+                }
+            }
+        }
+        let wait = WaitAny::new(self, conditions);
+        loop {
+            // The events processed right before the end of the data can still fire a condition.
+            let has_next = self.goto::<true>(wait.deadline(), WaitOrderResponse::Any)?;
+            let fired = wait.check(self);
+            if fired.is_some() || !has_next {
+                return Ok(fired);
+            }
+        }
+    }
+
     #[inline]
     fn elapse(&mut self, duration: i64) -> Result<bool, Self::Error> {
         if self.cur_ts == i64::MAX {
@@ -1468,6 +2981,9 @@ where
 
     #[inline]
     fn close(&mut self) -> Result<(), Self::Error> {
+        for asset_no in 0..self.local.len() {
+            self.memory_usage(asset_no).log(asset_no);
+        }
         Ok(())
     }
 
@@ -1480,4 +2996,1131 @@ where
     fn order_latency(&self, asset_no: usize) -> Option<(i64, i64, i64)> {
         self.local.get(asset_no).unwrap().order_latency()
     }
+
+    #[inline]
+    fn event_stats(&mut self, asset_no: usize) -> EventStats {
+        self.local.get_mut(asset_no).unwrap().take_event_stats()
+    }
+
+    #[inline]
+    fn risk_stats(&self, asset_no: usize) -> RiskStats {
+        self.risk.stats(asset_no)
+    }
+
+    #[inline]
+    fn is_warming_up(&self) -> bool {
+        match self.warmup_end {
+            Some(warmup_end) => self.cur_ts == i64::MAX || self.cur_ts < warmup_end,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        assettype::LinearAsset,
+        data::{Data, DataSource},
+        models::{
+            CommonFees,
+            ConstantLatency,
+            PowerProbQueueFunc3,
+            ProbQueueModel,
+            RiskAdverseQueueModel,
+            TradingValueFeeModel,
+        },
+        recorder::BacktestRecorder,
+        state::{FeeSettlement, ShortSelling},
+        Backtest,
+        BacktestError,
+        ExchangeKind,
+        L2AssetBuilder,
+        MultiAssetSingleExchangeBacktest,
+    };
+    #[cfg(feature = "fs")]
+    use super::{
+        data::write_npy_file,
+        rollover::{DayRollover, WorkingOrders},
+    };
+    use crate::{
+        depth::{HashMapMarketDepth, MarketDepth},
+        test_utils::feed,
+        types::{
+            Bot,
+            BuildError,
+            DepthUpdate,
+            Event,
+            Fill,
+            OrdType,
+            Recorder,
+            Side,
+            StateValues,
+            Status,
+            TimeInForce,
+            WaitCondition,
+            BUY_EVENT,
+            DEPTH_EVENT,
+            EXCH_EVENT,
+            INSTRUMENT_EVENT,
+            LIQUIDATION_EVENT,
+            LOCAL_EVENT,
+            OPEN_INTEREST_EVENT,
+            SELL_EVENT,
+            TRADE_EVENT,
+        },
+    };
+
+    #[test]
+    fn test_cross_venue_arbitrage() {
+        // Venue A briefly bids above venue B's ask, then both converge.
+        let venue_a = feed(
+            5,
+            &[
+                (100, BUY_EVENT, 100.0, 5.0),
+                (100, SELL_EVENT, 100.1, 5.0),
+                (1000, SELL_EVENT, 100.1, 0.0),
+                (1000, BUY_EVENT, 100.5, 5.0),
+                (1000, SELL_EVENT, 100.6, 5.0),
+                (5000, BUY_EVENT, 100.5, 0.0),
+                (5000, SELL_EVENT, 100.6, 0.0),
+                (5000, SELL_EVENT, 100.1, 5.0),
+            ],
+        );
+        let venue_b = feed(
+            20,
+            &[
+                (100, BUY_EVENT, 99.8, 5.0),
+                (100, SELL_EVENT, 99.9, 5.0),
+                (5000, BUY_EVENT, 99.8, 0.0),
+                (5000, SELL_EVENT, 99.9, 0.0),
+                (5000, BUY_EVENT, 100.0, 5.0),
+                (5000, SELL_EVENT, 100.1, 5.0),
+            ],
+        );
+
+        // Each venue has its own exchange, latency, queue, and fee models.
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![venue_a])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .exchange(ExchangeKind::NoPartialFillExchange)
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![venue_b])
+                    .latency_model(ConstantLatency::new(100, 100))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.001)))
+                    .exchange(ExchangeKind::PartialFillExchange)
+                    .queue_model(ProbQueueModel::new(PowerProbQueueFunc3::new(3.0)))
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let mut order_id = 0;
+        while hbt.elapse(100).unwrap() {
+            let bid = hbt.depth(0).best_bid();
+            let ask = hbt.depth(1).best_ask();
+            if bid - ask > 0.3 && hbt.position(0) == 0.0 && hbt.orders(0).is_empty() {
+                order_id += 1;
+                hbt.submit_sell_order(
+                    0,
+                    order_id,
+                    bid,
+                    1.0,
+                    TimeInForce::IOC,
+                    OrdType::Limit,
+                    false,
+                )
+                .unwrap();
+                hbt.submit_buy_order(
+                    1,
+                    order_id,
+                    ask,
+                    1.0,
+                    TimeInForce::IOC,
+                    OrdType::Limit,
+                    false,
+                )
+                .unwrap();
+            }
+        }
+
+        assert_eq!(order_id, 1);
+        assert_eq!(hbt.position(0), -1.0);
+        assert_eq!(hbt.position(1), 1.0);
+
+        // Both venues share one clock, so the portfolio reflects the converged prices.
+        let portfolio = hbt.portfolio();
+        let fee = 0.001 * 99.9;
+        assert!((portfolio.equity - (0.6 - fee)).abs() < 1e-9);
+        assert!(portfolio.net_exposure.abs() < 1e-9);
+        assert!((portfolio.gross_exposure - 2.0 * 100.05).abs() < 1e-9);
+        assert!((portfolio.fee - fee).abs() < 1e-9);
+    }
+
+    /// Builds a backtest of two assets over an oscillating market, whose data is split into
+    /// several files.
+    fn oscillating_backtest() -> Backtest<HashMapMarketDepth> {
+        let mut updates = Vec::new();
+        let mut prev_tick = None;
+        for i in 1..=300 {
+            let tick = 1000 + (i % 20 - 10_i64).abs();
+            if let Some(prev_tick) = prev_tick {
+                updates.push((i * 100, BUY_EVENT, prev_tick as f64 * 0.1, 0.0));
+                updates.push((i * 100, SELL_EVENT, (prev_tick + 1) as f64 * 0.1, 0.0));
+            }
+            updates.push((i * 100, BUY_EVENT, tick as f64 * 0.1, 2.0));
+            updates.push((i * 100, SELL_EVENT, (tick + 1) as f64 * 0.1, 2.0));
+            prev_tick = Some(tick);
+        }
+        let data = || {
+            updates
+                .chunks(updates.len() / 3)
+                .map(|chunk| feed(5, chunk))
+                .collect::<Vec<_>>()
+        };
+        Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(data())
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(-0.0001, 0.0005)))
+                    .exchange(ExchangeKind::NoPartialFillExchange)
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(data())
+                    .latency_model(ConstantLatency::new(30, 30))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(-0.0001, 0.0005)))
+                    .exchange(ExchangeKind::PartialFillExchange)
+                    .queue_model(ProbQueueModel::new(PowerProbQueueFunc3::new(3.0)))
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    /// Quotes both sides at the best prices and requotes periodically, for the given number of
+    /// steps or until the end of the data.
+    fn quote(
+        hbt: &mut Backtest<HashMapMarketDepth>,
+        num_steps: usize,
+    ) -> Result<(), BacktestError> {
+        for _ in 0..num_steps {
+            if !hbt.elapse(250)? {
+                break;
+            }
+            for asset_no in 0..2 {
+                hbt.clear_inactive_orders(Some(asset_no));
+                let ts = hbt.current_timestamp() as u64;
+                if hbt.orders(asset_no).is_empty() {
+                    let bid = hbt.depth(asset_no).best_bid();
+                    let ask = hbt.depth(asset_no).best_ask();
+                    hbt.submit_buy_order(
+                        asset_no,
+                        2 * ts,
+                        bid,
+                        1.0,
+                        TimeInForce::GTC,
+                        OrdType::Limit,
+                        false,
+                    )?;
+                    hbt.submit_sell_order(
+                        asset_no,
+                        2 * ts + 1,
+                        ask,
+                        1.0,
+                        TimeInForce::GTC,
+                        OrdType::Limit,
+                        false,
+                    )?;
+                } else if ts % 2000 == 0 {
+                    let order_ids: Vec<_> = hbt
+                        .orders(asset_no)
+                        .values()
+                        .filter(|order| order.cancellable())
+                        .map(|order| order.order_id)
+                        .collect();
+                    for order_id in order_ids {
+                        hbt.cancel(asset_no, order_id, false)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let path =
+            std::env::temp_dir().join(format!("hftbacktest_checkpoint_{}.bin", std::process::id()));
+
+        let mut hbt = oscillating_backtest();
+        quote(&mut hbt, usize::MAX).unwrap();
+        let expected: Vec<StateValues> = (0..2)
+            .map(|asset_no| hbt.state_values(asset_no).clone())
+            .collect();
+        assert!(expected.iter().all(|values| values.num_trades > 0));
+
+        let mut hbt = oscillating_backtest();
+        quote(&mut hbt, 70).unwrap();
+        hbt.save_checkpoint(&path).unwrap();
+        drop(hbt);
+
+        let mut hbt = oscillating_backtest();
+        hbt.load_checkpoint(&path).unwrap();
+        quote(&mut hbt, usize::MAX).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for (asset_no, expected) in expected.iter().enumerate() {
+            assert_eq!(hbt.state_values(asset_no), expected);
+        }
+
+        // A backtest that has already started cannot be restored.
+        let mut hbt = oscillating_backtest();
+        quote(&mut hbt, 1).unwrap();
+        assert!(hbt.save_checkpoint(&path).is_ok());
+        assert!(hbt.load_checkpoint(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_event_stats() {
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![feed(
+                        5,
+                        &[
+                            (100, BUY_EVENT, 100.0, 5.0),
+                            (100, SELL_EVENT, 100.1, 5.0),
+                            (200, BUY_EVENT, 100.0, 4.0),
+                            (1000, SELL_EVENT, 100.1, 3.0),
+                        ],
+                    )])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        hbt.elapse(300).unwrap();
+        hbt.submit_buy_order(0, 1, 99.0, 1.0, TimeInForce::GTC, OrdType::Limit, true)
+            .unwrap();
+        let stats = hbt.event_stats(0);
+        assert_eq!(stats.depth_updates, 3);
+        assert_eq!(stats.order_updates, 1);
+        assert_eq!(stats.trades, 0);
+
+        // The counters are reset on each query.
+        hbt.elapse(1000).unwrap();
+        let stats = hbt.event_stats(0);
+        assert_eq!(stats.depth_updates, 1);
+        assert_eq!(stats.order_updates, 0);
+
+        // Each event is counted by both the local and the exchange.
+        let throughput = hbt.throughput();
+        assert_eq!(throughput.events, 8);
+        assert_eq!(throughput.simulated, hbt.current_timestamp() - 100);
+    }
+
+    #[test]
+    fn test_last_depth_updates() {
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![feed(
+                        5,
+                        &[
+                            (100, BUY_EVENT, 100.0, 5.0),
+                            (100, SELL_EVENT, 100.1, 5.0),
+                            (200, BUY_EVENT, 100.0, 4.0),
+                            (1000, SELL_EVENT, 100.1, 3.0),
+                        ],
+                    )])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .last_depth_updates_capacity(10)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        hbt.elapse(300).unwrap();
+        let updates = hbt.last_depth_updates(0);
+        assert_eq!(updates.len(), 3);
+        assert_eq!(
+            updates[2],
+            DepthUpdate {
+                price_tick: 1000,
+                prev_qty: 5.0,
+                qty: 4.0,
+                timestamp: 205,
+                side: Side::Buy,
+            }
+        );
+
+        hbt.clear_last_depth_updates(None);
+        assert!(hbt.last_depth_updates(0).is_empty());
+        hbt.elapse(1000).unwrap();
+        assert_eq!(hbt.last_depth_updates(0)[0].prev_qty, 5.0);
+        assert_eq!(hbt.last_depth_updates(0)[0].side, Side::Sell);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_daily_files() {
+        let dir = std::env::temp_dir();
+        let mut files = Vec::new();
+        for (day, updates) in [
+            (
+                "day1",
+                [
+                    (100, BUY_EVENT, 100.0, 5.0),
+                    (100, SELL_EVENT, 100.1, 5.0),
+                    (1000, BUY_EVENT, 99.9, 1.0),
+                ],
+            ),
+            (
+                "day2",
+                [
+                    (2000, BUY_EVENT, 101.0, 5.0),
+                    (2000, SELL_EVENT, 101.1, 5.0),
+                    (3000, BUY_EVENT, 100.9, 1.0),
+                ],
+            ),
+        ] {
+            let DataSource::Data(data) = feed(5, &updates) else {
+                unreachable!()
+            };
+            let events: Vec<_> = (0..data.len()).map(|i| data[i].clone()).collect();
+            let path = dir.join(format!("hftbacktest_test_daily_{day}.npy"));
+            write_npy_file(path.to_str().unwrap(), &events).unwrap();
+            files.push(path.to_str().unwrap().to_string());
+        }
+
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .daily_files(
+                        files,
+                        DayRollover::new()
+                            .working_orders(WorkingOrders::CancelAtClose)
+                            .reset_depth(true),
+                    )
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        hbt.elapse(300).unwrap();
+        hbt.submit_buy_order(0, 1, 99.0, 1.0, TimeInForce::GTC, OrdType::Limit, true)
+            .unwrap();
+        assert_eq!(hbt.orders(0)[&1].status, Status::New);
+
+        hbt.elapse(2500).unwrap();
+        assert_eq!(hbt.orders(0)[&1].status, Status::Canceled);
+        // The previous day's levels are cleared.
+        assert_eq!(hbt.depth(0).best_bid_tick(), 1010);
+        assert_eq!(hbt.depth(0).bid_qty_at_tick(1000), 0.0);
+    }
+
+    #[test]
+    fn test_instrument_change() {
+        let DataSource::Data(data) = feed(
+            5,
+            &[
+                (100, BUY_EVENT, 100.0, 5.0),
+                (100, BUY_EVENT, 100.3, 2.0),
+                (100, SELL_EVENT, 100.4, 3.0),
+                (100, SELL_EVENT, 100.6, 1.0),
+            ],
+        ) else {
+            unreachable!()
+        };
+        let mut events: Vec<_> = (0..data.len()).map(|i| data[i].clone()).collect();
+        // The tick size changes from 0.1 to 0.5, and then a trade sweeps the bids.
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | INSTRUMENT_EVENT,
+            exch_ts: 1000,
+            local_ts: 1005,
+            px: 0.5,
+            qty: 0.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | TRADE_EVENT | SELL_EVENT,
+            exch_ts: 2000,
+            local_ts: 2005,
+            px: 99.5,
+            qty: 10.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | BUY_EVENT,
+            exch_ts: 3000,
+            local_ts: 3005,
+            px: 99.5,
+            qty: 1.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![DataSource::Data(Data::from_data(&events))])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        hbt.elapse(300).unwrap();
+        hbt.submit_buy_order(0, 1, 100.2, 1.0, TimeInForce::GTC, OrdType::Limit, true)
+            .unwrap();
+
+        hbt.elapse(1200).unwrap();
+        let depth = hbt.depth(0);
+        assert_eq!(depth.tick_size(), 0.5);
+        // The bids are rounded down and the asks up into the new ticks.
+        assert_eq!(depth.best_bid_tick(), 200);
+        assert_eq!(depth.bid_qty_at_tick(200), 7.0);
+        assert_eq!(depth.best_ask_tick(), 201);
+        assert_eq!(depth.ask_qty_at_tick(202), 1.0);
+        assert_eq!(hbt.orders(0)[&1].price(), 100.0);
+
+        // The re-normalized order is filled at the new price.
+        hbt.elapse(1000).unwrap();
+        let order = &hbt.orders(0)[&1];
+        assert_eq!(order.status, Status::Filled);
+        assert_eq!(order.exec_price(), 100.0);
+    }
+
+    #[test]
+    fn test_instrument_change_with_order_in_flight() {
+        let DataSource::Data(data) = feed(
+            5,
+            &[
+                (100, BUY_EVENT, 100.0, 5.0),
+                (100, BUY_EVENT, 100.3, 2.0),
+                (100, SELL_EVENT, 100.4, 3.0),
+                (100, SELL_EVENT, 100.6, 1.0),
+            ],
+        ) else {
+            unreachable!()
+        };
+        let mut events: Vec<_> = (0..data.len()).map(|i| data[i].clone()).collect();
+        // The tick size changes from 0.1 to 0.5 at the exchange while the order is in flight, and
+        // the local learns about it after the order response arrives.
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | INSTRUMENT_EVENT,
+            exch_ts: 1000,
+            local_ts: 1050,
+            px: 0.5,
+            qty: 0.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | TRADE_EVENT | SELL_EVENT,
+            exch_ts: 2000,
+            local_ts: 2005,
+            px: 99.5,
+            qty: 10.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | BUY_EVENT,
+            exch_ts: 3000,
+            local_ts: 3005,
+            px: 99.5,
+            qty: 1.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![DataSource::Data(Data::from_data(&events))])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        // The order priced in the old tick size reaches the exchange after the change.
+        hbt.elapse(300).unwrap();
+        hbt.elapse(995 - hbt.current_timestamp()).unwrap();
+        hbt.submit_buy_order(0, 1, 100.2, 1.0, TimeInForce::GTC, OrdType::Limit, false)
+            .unwrap();
+        hbt.elapse(15).unwrap();
+
+        // The order is re-normalized into the new tick at the exchange, rather than its price tick
+        // being read in the new tick size, and the response that arrives before the local learns
+        // about the change is normalized back into the local tick size.
+        hbt.elapse(30).unwrap();
+        assert_eq!(hbt.depth(0).tick_size(), 0.1);
+        let order = &hbt.orders(0)[&1];
+        assert_eq!(order.status, Status::New);
+        assert_eq!(order.tick_size, 0.1);
+        assert_eq!(order.price_tick, 1000);
+
+        hbt.elapse(100).unwrap();
+        assert_eq!(hbt.depth(0).tick_size(), 0.5);
+        let order = &hbt.orders(0)[&1];
+        assert_eq!(order.tick_size, 0.5);
+        assert_eq!(order.price_tick, 200);
+
+        hbt.elapse(1000).unwrap();
+        let order = &hbt.orders(0)[&1];
+        assert_eq!(order.status, Status::Filled);
+        assert_eq!(order.exec_price(), 100.0);
+        assert_eq!(hbt.position(0), 1.0);
+    }
+
+    /// Builds a backtest on the partial-fill exchange whose bid at 100.0 has 1.0 ahead in the
+    /// queue, and that sell trades of 2.0 and 1.0 at 100.0 then hit.
+    fn partial_fill_backtest() -> Backtest<HashMapMarketDepth> {
+        let DataSource::Data(data) = feed(
+            5,
+            &[(100, BUY_EVENT, 100.0, 1.0), (100, SELL_EVENT, 100.1, 5.0)],
+        ) else {
+            unreachable!()
+        };
+        let mut events: Vec<_> = (0..data.len()).map(|i| data[i].clone()).collect();
+        for (exch_ts, qty) in [(1000, 2.0), (2000, 1.0)] {
+            events.push(Event {
+                ev: EXCH_EVENT | LOCAL_EVENT | TRADE_EVENT | SELL_EVENT,
+                exch_ts,
+                local_ts: exch_ts + 5,
+                px: 100.0,
+                qty,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            });
+        }
+        // Keeps the feed running until the fill responses are received.
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | SELL_EVENT,
+            exch_ts: 10_000,
+            local_ts: 10_005,
+            px: 100.2,
+            qty: 1.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+
+        Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![DataSource::Data(Data::from_data(&events))])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(-0.0001, 0.0)))
+                    .exchange(ExchangeKind::PartialFillExchange)
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .last_fills_capacity(10)
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_partial_fills() {
+        let mut hbt = partial_fill_backtest();
+        hbt.elapse(300).unwrap();
+        hbt.submit_buy_order(0, 1, 100.0, 3.0, TimeInForce::GTC, OrdType::Limit, false)
+            .unwrap();
+
+        // The first trade passes the queue ahead and fills 1.0.
+        hbt.elapse(1000).unwrap();
+        let order = &hbt.orders(0)[&1];
+        assert_eq!(order.status, Status::PartiallyFilled);
+        assert_eq!(order.leaves_qty, 2.0);
+        assert_eq!(hbt.position(0), 1.0);
+
+        // The second trade fills 1.0 more, and each execution is accounted for.
+        hbt.elapse(1000).unwrap();
+        let order = &hbt.orders(0)[&1];
+        assert_eq!(order.status, Status::PartiallyFilled);
+        assert_eq!(order.leaves_qty, 1.0);
+        let values = hbt.state_values(0);
+        assert_eq!(values.position, 2.0);
+        assert!((values.balance + 200.0).abs() < 1e-9);
+        assert!((values.fee + 0.02).abs() < 1e-9);
+        assert_eq!(values.num_trades, 2);
+        assert_eq!(values.trading_volume, 2.0);
+    }
+
+    #[test]
+    fn test_partial_fill_stream() {
+        let mut hbt = partial_fill_backtest();
+        hbt.elapse(300).unwrap();
+        hbt.submit_buy_order(0, 1, 100.0, 3.0, TimeInForce::GTC, OrdType::Limit, false)
+            .unwrap();
+        hbt.elapse(2000).unwrap();
+
+        // Each partial execution is reported by its own fill.
+        let fill = |trade_id: &str, exch_ts| Fill {
+            order_id: 1,
+            trade_id: trade_id.to_string(),
+            side: Side::Buy,
+            price: 100.0,
+            qty: 1.0,
+            fee: -0.01,
+            maker: true,
+            exch_ts,
+        };
+        assert_eq!(hbt.last_fills(0), [fill("1", 1000), fill("2", 2000)]);
+    }
+
+    #[test]
+    fn test_checkpoint_fills_and_event_stats() {
+        let path = std::env::temp_dir().join(format!(
+            "hftbacktest_checkpoint_fills_{}.bin",
+            std::process::id()
+        ));
+
+        let mut hbt = partial_fill_backtest();
+        hbt.elapse(300).unwrap();
+        hbt.submit_buy_order(0, 1, 100.0, 3.0, TimeInForce::GTC, OrdType::Limit, false)
+            .unwrap();
+        hbt.elapse(1000).unwrap();
+        hbt.save_checkpoint(&path).unwrap();
+        let fills = hbt.last_fills(0).to_vec();
+        assert_eq!(fills.len(), 1);
+        let stats = hbt.event_stats(0);
+
+        let mut hbt = partial_fill_backtest();
+        hbt.load_checkpoint(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(hbt.last_fills(0), fills.as_slice());
+        assert_eq!(hbt.event_stats(0), stats);
+
+        hbt.elapse(1000).unwrap();
+        assert_eq!(hbt.last_fills(0).len(), 2);
+    }
+
+    #[test]
+    fn test_open_interest_and_liquidation_prints() {
+        let DataSource::Data(data) = feed(
+            5,
+            &[(100, BUY_EVENT, 100.0, 5.0), (100, SELL_EVENT, 100.1, 5.0)],
+        ) else {
+            unreachable!()
+        };
+        let mut events: Vec<_> = (0..data.len()).map(|i| data[i].clone()).collect();
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | OPEN_INTEREST_EVENT,
+            exch_ts: 1000,
+            local_ts: 1005,
+            px: 0.0,
+            qty: 1200.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+        let liquidation = Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | LIQUIDATION_EVENT | SELL_EVENT,
+            exch_ts: 2000,
+            local_ts: 2005,
+            px: 99.0,
+            qty: 30.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        };
+        events.push(liquidation.clone());
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | OPEN_INTEREST_EVENT,
+            exch_ts: 3000,
+            local_ts: 3005,
+            px: 0.0,
+            qty: 1170.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+
+        let build = || {
+            Backtest::builder()
+                .add_asset(
+                    L2AssetBuilder::new()
+                        .data(vec![DataSource::Data(Data::from_data(&events))])
+                        .latency_model(ConstantLatency::new(10, 10))
+                        .asset_type(LinearAsset::new(1.0))
+                        .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                        .queue_model(RiskAdverseQueueModel::new())
+                        .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                        .last_liquidation_prints_capacity(10)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+        let mut hbt = build();
+
+        hbt.elapse(500).unwrap();
+        assert_eq!(hbt.open_interest(0), None);
+
+        hbt.elapse(2000).unwrap();
+        assert_eq!(hbt.open_interest(0), Some(1200.0));
+        assert_eq!(
+            hbt.last_liquidation_prints(0),
+            std::slice::from_ref(&liquidation)
+        );
+        // The market liquidations aren't the bot's own.
+        assert!(hbt.liquidations(0).is_empty());
+        // The liquidation print isn't a trade and leaves the market depth intact.
+        assert!(hbt.last_trades(0).is_empty());
+        assert_eq!(hbt.depth(0).best_bid(), 100.0);
+
+        // The open interest and the liquidation prints are restored from a checkpoint.
+        let path = std::env::temp_dir().join(format!(
+            "hftbacktest_checkpoint_liquidation_{}.bin",
+            std::process::id()
+        ));
+        hbt.save_checkpoint(&path).unwrap();
+        let mut restored = build();
+        restored.load_checkpoint(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(restored.open_interest(0), Some(1200.0));
+        assert_eq!(restored.last_liquidation_prints(0), [liquidation]);
+
+        hbt.clear_last_liquidation_prints(None);
+        assert!(hbt.last_liquidation_prints(0).is_empty());
+
+        hbt.elapse(1000).unwrap();
+        assert_eq!(hbt.open_interest(0), Some(1170.0));
+
+        // Without the capacity, the liquidation prints aren't stored.
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![DataSource::Data(Data::from_data(&events))])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        hbt.elapse(2500).unwrap();
+        assert!(hbt.last_liquidation_prints(0).is_empty());
+    }
+
+    #[test]
+    fn test_wait_any() {
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![feed(
+                        5,
+                        &[
+                            (100, BUY_EVENT, 100.0, 5.0),
+                            (100, SELL_EVENT, 100.1, 5.0),
+                            (1000, SELL_EVENT, 100.1, 3.0),
+                            (3000, BUY_EVENT, 100.0, 0.0),
+                            (5000, SELL_EVENT, 100.1, 2.0),
+                        ],
+                    )])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        hbt.elapse(300).unwrap();
+        hbt.submit_buy_order(0, 1, 99.0, 1.0, TimeInForce::GTC, OrdType::Limit, false)
+            .unwrap();
+        let order_resp = WaitCondition::OrderResponse {
+            asset_no: 0,
+            order_id: 1,
+        };
+        let bbo_change = WaitCondition::BboChange { asset_no: 0 };
+        let timeout = WaitCondition::Timeout(10_000);
+        assert_eq!(
+            hbt.wait_any(&[order_resp, bbo_change, timeout]).unwrap(),
+            Some(order_resp)
+        );
+        assert_eq!(hbt.current_timestamp(), 420);
+
+        // The quantity change at the best ask doesn't fire.
+        assert_eq!(
+            hbt.wait_any(&[bbo_change, timeout]).unwrap(),
+            Some(bbo_change)
+        );
+        assert_eq!(hbt.current_timestamp(), 3005);
+
+        let timeout = WaitCondition::Timeout(100);
+        assert_eq!(hbt.wait_any(&[bbo_change, timeout]).unwrap(), Some(timeout));
+        assert_eq!(hbt.current_timestamp(), 3105);
+
+        assert_eq!(hbt.wait_any(&[bbo_change]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_wait_order_status() {
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![feed(
+                        5,
+                        &[
+                            (100, BUY_EVENT, 100.0, 5.0),
+                            (100, SELL_EVENT, 100.1, 5.0),
+                            (2000, SELL_EVENT, 99.0, 2.0),
+                            (5000, SELL_EVENT, 99.0, 1.0),
+                        ],
+                    )])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .last_fills_capacity(10)
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        hbt.elapse(300).unwrap();
+        hbt.submit_buy_order(0, 1, 99.0, 1.0, TimeInForce::GTC, OrdType::Limit, false)
+            .unwrap();
+        // The acknowledgement doesn't end the wait.
+        assert!(hbt.wait_order_status(0, 1, Status::Filled, 10_000).unwrap());
+        assert_eq!(hbt.orders(0)[&1].status, Status::Filled);
+        assert_eq!(hbt.current_timestamp(), 2010);
+        assert_eq!(
+            hbt.last_fills(0),
+            [Fill {
+                order_id: 1,
+                trade_id: "1".to_string(),
+                side: Side::Buy,
+                price: 99.0,
+                qty: 1.0,
+                fee: 0.0,
+                maker: true,
+                exch_ts: 2000,
+            }]
+        );
+        hbt.clear_last_fills(None);
+        assert!(hbt.last_fills(0).is_empty());
+
+        // Returns immediately since the order is already in a final status.
+        assert!(hbt
+            .wait_order_status(0, 1, Status::Canceled, 10_000)
+            .unwrap());
+        assert_eq!(hbt.current_timestamp(), 2010);
+    }
+
+    #[test]
+    fn test_heterogeneous_exchange_models() {
+        let asset = |exch_kind| {
+            L2AssetBuilder::new()
+                .data(vec![feed(
+                    5,
+                    &[
+                        (100, BUY_EVENT, 100.0, 5.0),
+                        (100, SELL_EVENT, 100.1, 5.0),
+                        (1000, SELL_EVENT, 100.1, 3.0),
+                    ],
+                )])
+                .latency_model(ConstantLatency::new(10, 10))
+                .asset_type(LinearAsset::new(1.0))
+                .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                .queue_model(RiskAdverseQueueModel::new())
+                .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                .exchange(exch_kind)
+                .build()
+                .unwrap()
+        };
+        let mut hbt = MultiAssetSingleExchangeBacktest::builder()
+            .add_dyn_asset(asset(ExchangeKind::NoPartialFillExchange))
+            .add_dyn_asset(asset(ExchangeKind::PartialFillExchange))
+            .build()
+            .unwrap();
+
+        hbt.elapse(300).unwrap();
+        for asset_no in 0..2 {
+            hbt.submit_buy_order(
+                asset_no,
+                1,
+                100.1,
+                8.0,
+                TimeInForce::IOC,
+                OrdType::Limit,
+                false,
+            )
+            .unwrap();
+        }
+        hbt.elapse(100).unwrap();
+        hbt.elapse(100).unwrap();
+
+        // Only the partial-fill exchange fills the order up to the quantity at the best ask.
+        assert_eq!(hbt.orders(0)[&1].status, Status::Filled);
+        assert_eq!(hbt.position(0), 8.0);
+        assert_eq!(hbt.orders(1)[&1].status, Status::Expired);
+        assert_eq!(hbt.orders(1)[&1].leaves_qty, 3.0);
+    }
+
+    #[test]
+    fn test_warmup() {
+        let mut hbt = Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![feed(
+                        5,
+                        &[
+                            (100, BUY_EVENT, 100.0, 5.0),
+                            (100, SELL_EVENT, 100.1, 5.0),
+                            (2000, BUY_EVENT, 100.0, 4.0),
+                        ],
+                    )])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        hbt.warmup_until(1000).unwrap();
+        let mut recorder = BacktestRecorder::new(&hbt);
+
+        assert!(hbt.is_warming_up());
+        hbt.elapse(500).unwrap();
+        recorder.record(&mut hbt).unwrap();
+        // The depth is built during the warm-up, but orders cannot be submitted.
+        assert_eq!(hbt.depth(0).best_bid(), 100.0);
+        assert!(matches!(
+            hbt.submit_buy_order(0, 1, 100.0, 1.0, TimeInForce::GTC, OrdType::Limit, false),
+            Err(BacktestError::InvalidOrderRequest)
+        ));
+
+        hbt.elapse(500).unwrap();
+        assert!(!hbt.is_warming_up());
+        recorder.record(&mut hbt).unwrap();
+        hbt.submit_buy_order(0, 1, 100.0, 1.0, TimeInForce::GTC, OrdType::Limit, false)
+            .unwrap();
+        assert!(hbt.warmup_until(2000).is_err());
+
+        // Only the record after the warm-up is kept.
+        let dir = std::env::temp_dir();
+        let prefix = format!("hftbacktest_warmup_{}_", std::process::id());
+        recorder.to_csv(&prefix, &dir).unwrap();
+        let path = dir.join(format!("{prefix}0.csv"));
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.lines().nth(1).unwrap().starts_with("1100,"));
+    }
+
+    #[test]
+    fn test_non_positive_interval() {
+        let builder = || {
+            L2AssetBuilder::new()
+                .data(vec![feed(5, &[(100, BUY_EVENT, 100.0, 5.0)])])
+                .latency_model(ConstantLatency::new(10, 10))
+                .asset_type(LinearAsset::new(1.0))
+                .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                .queue_model(RiskAdverseQueueModel::new())
+                .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+        };
+        assert!(matches!(
+            builder()
+                .fee_settlement(FeeSettlement::Periodic { interval: 0 })
+                .build(),
+            Err(BuildError::InvalidArgument("fee_settlement"))
+        ));
+        assert!(matches!(
+            builder()
+                .short_selling(ShortSelling::Borrow {
+                    rate: 0.0001,
+                    interval: -1,
+                })
+                .build(),
+            Err(BuildError::InvalidArgument("short_selling"))
+        ));
+        assert!(builder()
+            .fee_settlement(FeeSettlement::Periodic { interval: 100 })
+            .short_selling(ShortSelling::Borrow {
+                rate: 0.0001,
+                interval: 100,
+            })
+            .build()
+            .is_ok());
+    }
 }
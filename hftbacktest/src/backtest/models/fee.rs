@@ -1,4 +1,13 @@
-use crate::{prelude::Side, types::Order};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+};
+
+use crate::{
+    prelude::Side,
+    types::{BuildError, Order, OrderId},
+};
 
 /// Common transaction fees
 /// Fee calculation is determined by the fee model.
@@ -151,3 +160,289 @@ impl FeeModel for FlatPerTradeFeeModel<CommonFees> {
         }
     }
 }
+
+/// A fee tier that applies once the rolling trading volume reaches `volume`.
+#[derive(Clone, Debug)]
+pub struct FeeTier {
+    /// The minimum rolling trading value required for this tier.
+    pub volume: f64,
+    /// Fee for adding liquidity (maker order).
+    pub maker_fee: f64,
+    /// Fee for removing liquidity (taker order).
+    pub taker_fee: f64,
+}
+
+impl FeeTier {
+    /// Constructs `FeeTier`.
+    pub fn new(volume: f64, maker_fee: f64, taker_fee: f64) -> Self {
+        Self {
+            volume,
+            maker_fee,
+            taker_fee,
+        }
+    }
+}
+
+/// Identifies a fill of an order by the exchange timestamp and the quantity left after it, which
+/// distinguishes the fills of the same order at the same timestamp and price.
+type FillKey = (OrderId, i64, u64);
+
+struct FeeAccountInner {
+    window: i64,
+    initial_volume: f64,
+    volume: f64,
+    fills: VecDeque<(i64, f64)>,
+    // The rates of the fills applied by one side and waiting for the other, in the order they
+    // were applied, for each fee model.
+    rates: HashMap<usize, VecDeque<(FillKey, f64)>>,
+    fee_price: f64,
+    fee_discount: f64,
+    fee_paid: f64,
+    next_id: usize,
+}
+
+impl FeeAccountInner {
+    fn expire(&mut self, timestamp: i64) {
+        while let Some(&(fill_timestamp, value)) = self.fills.front() {
+            if fill_timestamp > timestamp - self.window {
+                break;
+            }
+            self.volume -= value;
+            self.fills.pop_front();
+        }
+    }
+}
+
+/// The account shared by the [`TieredFeeModel`]s of the assets, which accumulates the rolling
+/// trading value that determines the fee tier, and the fees paid in the fee currency.
+///
+/// Each fill is applied by both the local and the exchange, so the account accounts for each fill
+/// only once and returns the same fee rate to both. Since the local receives the fills in the
+/// order the exchange applied them, a fill that is still waiting when a later one is matched
+/// never reaches the other side, such as when the data ends, and is discarded.
+///
+/// [`FeeAccount::volume`] shows the tier the rolling trading value has reached, and
+/// [`FeeAccount::fee_paid`] the fees paid in the fee currency, which the state values of the
+/// individual assets don't account for.
+#[derive(Clone)]
+pub struct FeeAccount(Rc<RefCell<FeeAccountInner>>);
+
+impl FeeAccount {
+    /// Constructs `FeeAccount`.
+    ///
+    /// * `window` - The rolling window of the trading volume. The unit should be the same as the
+    ///   data's timestamp unit. For example, 30 days is `30 * 86_400_000_000_000` in
+    ///   nanoseconds.
+    pub fn new(window: i64) -> Self {
+        Self(Rc::new(RefCell::new(FeeAccountInner {
+            window,
+            initial_volume: 0.0,
+            volume: 0.0,
+            fills: Default::default(),
+            rates: Default::default(),
+            fee_price: 1.0,
+            fee_discount: 0.0,
+            fee_paid: 0.0,
+            next_id: 0,
+        })))
+    }
+
+    /// Sets the trading value accumulated before the backtest begins, which is added to the
+    /// rolling trading value. The default value is `0`.
+    pub fn initial_volume(self, volume: f64) -> Self {
+        self.0.borrow_mut().initial_volume = volume;
+        self
+    }
+
+    /// Sets the fee currency different from the quote currency, such as BNB, in which the fees
+    /// are paid at a discount.
+    ///
+    /// * `price` - The fixed price of the fee currency in the quote currency.
+    /// * `discount` - The discount rate applied to the fees paid in the fee currency. For example,
+    ///   `0.1` is a 10% discount.
+    pub fn fee_currency(self, price: f64, discount: f64) -> Self {
+        {
+            let mut inner = self.0.borrow_mut();
+            inner.fee_price = price;
+            inner.fee_discount = discount;
+        }
+        self
+    }
+
+    /// Returns the current rolling trading value, including the initial volume.
+    pub fn volume(&self) -> f64 {
+        let inner = self.0.borrow();
+        inner.initial_volume + inner.volume
+    }
+
+    /// Returns the total fees paid in the fee currency.
+    pub fn fee_paid(&self) -> f64 {
+        self.0.borrow().fee_paid
+    }
+
+    fn next_id(&self) -> usize {
+        let mut inner = self.0.borrow_mut();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        id
+    }
+}
+
+/// Fee based on the transaction value, with the rate determined by the fee tier that the rolling
+/// trading value of the [`FeeAccount`] reaches.
+///
+/// Passing the same [`FeeAccount`] to the fee models of multiple assets accumulates the trading
+/// value across the assets, while each asset can override the rates by using its own tiers.
+#[derive(Clone)]
+pub struct TieredFeeModel {
+    id: usize,
+    tiers: Vec<FeeTier>,
+    account: FeeAccount,
+}
+
+impl TieredFeeModel {
+    /// Constructs `TieredFeeModel`.
+    ///
+    /// * `tiers` - The fee tiers. The lowest tier applies if the rolling trading value doesn't
+    ///   reach any tier.
+    /// * `account` - The account that accumulates the rolling trading value.
+    ///
+    /// Returns [`BuildError::InvalidArgument`] if `tiers` is empty.
+    pub fn new(mut tiers: Vec<FeeTier>, account: FeeAccount) -> Result<Self, BuildError> {
+        if tiers.is_empty() {
+            return Err(BuildError::InvalidArgument("tiers"));
+        }
+        tiers.sort_by(|a, b| a.volume.total_cmp(&b.volume));
+        Ok(Self {
+            id: account.next_id(),
+            tiers,
+            account,
+        })
+    }
+
+    fn tier(&self, volume: f64) -> &FeeTier {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| volume >= tier.volume)
+            .unwrap_or(&self.tiers[0])
+    }
+}
+
+impl FeeModel for TieredFeeModel {
+    fn amount(&self, order: &Order, amount: f64) -> f64 {
+        let key = (
+            order.order_id,
+            order.exch_timestamp,
+            order.leaves_qty.to_bits(),
+        );
+        let mut inner = self.account.0.borrow_mut();
+        let rates = inner.rates.entry(self.id).or_default();
+        let rate = match rates.iter().position(|(k, _)| *k == key) {
+            // The other side has already applied this fill, and the unmatched fills before it
+            // are discarded.
+            Some(i) => rates.drain(..=i).next_back().unwrap().1,
+            None => {
+                inner.expire(order.exch_timestamp);
+                let tier = self.tier(inner.initial_volume + inner.volume);
+                let rate = if order.maker {
+                    tier.maker_fee
+                } else {
+                    tier.taker_fee
+                } * (1.0 - inner.fee_discount);
+                inner.volume += amount;
+                inner.fills.push_back((order.exch_timestamp, amount));
+                inner
+                    .rates
+                    .get_mut(&self.id)
+                    .unwrap()
+                    .push_back((key, rate));
+                inner.fee_paid += rate * amount / inner.fee_price;
+                rate
+            }
+        };
+        rate * amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FeeAccount, FeeModel, FeeTier, TieredFeeModel};
+    use crate::types::{BuildError, OrdType, Order, Side, TimeInForce};
+
+    fn fill(order_id: u64, timestamp: i64) -> Order {
+        let mut order = Order::new(
+            order_id,
+            1000,
+            0.1,
+            1.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        order.exch_timestamp = timestamp;
+        order.exec_price_tick = 1000;
+        order.exec_qty = 1.0;
+        order
+    }
+
+    #[test]
+    fn test_tiered_fee_model() {
+        let account = FeeAccount::new(100).fee_currency(2.0, 0.5);
+        let tiers = vec![FeeTier::new(0.0, 0.0, 0.1), FeeTier::new(150.0, 0.0, 0.05)];
+        let local = TieredFeeModel::new(tiers.clone(), account.clone()).unwrap();
+        let exch = local.clone();
+
+        // Each fill is accounted for only once even though both sides apply it.
+        assert_eq!(exch.amount(&fill(1, 10), 100.0), 5.0);
+        assert_eq!(exch.amount(&fill(2, 20), 100.0), 5.0);
+        assert_eq!(local.amount(&fill(1, 10), 100.0), 5.0);
+        assert_eq!(account.volume(), 200.0);
+
+        // Reaches the second tier.
+        assert_eq!(exch.amount(&fill(3, 30), 100.0), 2.5);
+        assert_eq!(account.fee_paid(), 6.25);
+
+        // Other assets share the account.
+        let other = TieredFeeModel::new(tiers, account.clone()).unwrap();
+        assert_eq!(other.amount(&fill(1, 40), 100.0), 2.5);
+
+        // The fills older than the window are expired.
+        assert_eq!(exch.amount(&fill(4, 125), 100.0), 2.5);
+        assert_eq!(account.volume(), 300.0);
+        assert_eq!(exch.amount(&fill(5, 145), 100.0), 5.0);
+    }
+    #[test]
+    fn test_tiered_fee_model_fills_at_same_time() {
+        let account = FeeAccount::new(100);
+        let tiers = vec![FeeTier::new(0.0, 0.0, 0.1), FeeTier::new(150.0, 0.0, 0.05)];
+        let local = TieredFeeModel::new(tiers, account.clone()).unwrap();
+        let exch = local.clone();
+
+        // Two partial fills of the same order at the same timestamp and price.
+        let mut first = fill(1, 10);
+        first.leaves_qty = 0.5;
+        let mut second = fill(1, 10);
+        second.leaves_qty = 0.0;
+        assert_eq!(exch.amount(&first, 100.0), 10.0);
+        assert_eq!(exch.amount(&second, 100.0), 10.0);
+        assert_eq!(local.amount(&first, 100.0), 10.0);
+        assert_eq!(local.amount(&second, 100.0), 10.0);
+        assert_eq!(account.volume(), 200.0);
+
+        // The fill that never reaches the other side is discarded once a later one is matched.
+        assert_eq!(exch.amount(&fill(2, 20), 100.0), 5.0);
+        assert_eq!(exch.amount(&fill(3, 30), 100.0), 5.0);
+        assert_eq!(local.amount(&fill(3, 30), 100.0), 5.0);
+        assert!(account.0.borrow().rates[&exch.id].is_empty());
+        assert_eq!(account.volume(), 400.0);
+    }
+
+    #[test]
+    fn test_tiered_fee_model_without_tiers() {
+        assert!(matches!(
+            TieredFeeModel::new(Vec::new(), FeeAccount::new(100)),
+            Err(BuildError::InvalidArgument("tiers"))
+        ));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    depth::{MarketDepth, INVALID_MAX, INVALID_MIN},
+    types::{OrdType, Order, Side},
+};
+
+/// Decides the execution price and quantity of a liquidity-taking order. This allows you to
+/// simulate market impact and slippage, rather than always filling at the displayed levels.
+pub trait FillModel<MD>
+where
+    MD: MarketDepth,
+{
+    /// Appends the executions of the marketable `order` against the `depth` to `fills`, each as a
+    /// tuple of the execution price in ticks and the executed quantity.
+    ///
+    /// The total executed quantity must not exceed the order's leaves quantity, and a limit order
+    /// must not be executed at a price worse than its price. The exchange model handles the
+    /// remaining quantity according to the order's time-in-force.
+    fn take(&mut self, order: &Order, depth: &MD, fills: &mut Vec<(i64, f64)>);
+}
+
+impl<MD> FillModel<MD> for Box<dyn FillModel<MD>>
+where
+    MD: MarketDepth,
+{
+    fn take(&mut self, order: &Order, depth: &MD, fills: &mut Vec<(i64, f64)>) {
+        (**self).take(order, depth, fills)
+    }
+}
+
+/// Walks the displayed market depth from the best price, assuming that only a fraction of the
+/// displayed quantity at each level is actually executable, since other market participants compete
+/// for the same liquidity and the displayed quantity may be cancelled before your order arrives.
+/// As a result, the larger the order relative to the displayed depth, the deeper it walks into the
+/// book and the worse the average execution price becomes.
+///
+/// With a `fill_ratio` of `1.0`, the order is filled at the displayed levels.
+#[derive(Clone)]
+pub struct DepthFillModel {
+    fill_ratio: f64,
+    max_ticks: i64,
+}
+
+impl DepthFillModel {
+    /// Constructs `DepthFillModel`.
+    ///
+    /// * `fill_ratio` - The fraction of the displayed quantity at each level that is executable.
+    /// * `max_ticks` - The maximum number of ticks to walk from the best price.
+    pub fn new(fill_ratio: f64, max_ticks: i64) -> Self {
+        Self {
+            fill_ratio,
+            max_ticks,
+        }
+    }
+}
+
+impl<MD> FillModel<MD> for DepthFillModel
+where
+    MD: MarketDepth,
+{
+    fn take(&mut self, order: &Order, depth: &MD, fills: &mut Vec<(i64, f64)>) {
+        let lot_size = depth.lot_size();
+        let mut leaves_qty = order.leaves_qty;
+        let (best_tick, step) = match order.side {
+            Side::Buy if depth.best_ask_tick() != INVALID_MAX => (depth.best_ask_tick(), 1),
+            Side::Sell if depth.best_bid_tick() != INVALID_MIN => (depth.best_bid_tick(), -1),
+            _ => return,
+        };
+        for i in 0..self.max_ticks {
+            let price_tick = best_tick + step * i;
+            if order.order_type == OrdType::Limit
+                && ((order.side == Side::Buy && price_tick > order.price_tick)
+                    || (order.side == Side::Sell && price_tick < order.price_tick))
+            {
+                break;
+            }
+            let qty = if order.side == Side::Buy {
+                depth.ask_qty_at_tick(price_tick)
+            } else {
+                depth.bid_qty_at_tick(price_tick)
+            };
+            let executable_qty = (qty * self.fill_ratio / lot_size).floor() * lot_size;
+            if executable_qty > 0.0 {
+                let exec_qty = executable_qty.min(leaves_qty);
+                fills.push((price_tick, exec_qty));
+                leaves_qty -= exec_qty;
+                if (leaves_qty / lot_size).round() <= 0.0 {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DepthFillModel, FillModel};
+    use crate::{
+        depth::{HashMapMarketDepth, L2MarketDepth},
+        types::{OrdType, Order, Side, TimeInForce},
+    };
+
+    #[test]
+    fn test_depth_fill_model() {
+        let mut depth = HashMapMarketDepth::new(0.1, 1.0);
+        depth.update_ask_depth(100.0, 10.0, 0);
+        depth.update_ask_depth(100.1, 4.0, 0);
+        depth.update_ask_depth(100.3, 20.0, 0);
+
+        let mut fill_model = DepthFillModel::new(0.5, 10);
+        let order = Order::new(
+            1,
+            1002,
+            0.1,
+            9.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::IOC,
+        );
+        let mut fills = Vec::new();
+        fill_model.take(&order, &depth, &mut fills);
+        assert_eq!(fills, vec![(1000, 5.0), (1001, 2.0)]);
+
+        let order = Order::new(2, 0, 0.1, 9.0, Side::Buy, OrdType::Market, TimeInForce::IOC);
+        let mut fills = Vec::new();
+        fill_model.take(&order, &depth, &mut fills);
+        assert_eq!(fills, vec![(1000, 5.0), (1001, 2.0), (1003, 2.0)]);
+    }
+}
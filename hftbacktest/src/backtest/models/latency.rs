@@ -4,10 +4,12 @@ use hftbacktest_derive::NpyDTyped;
 
 use crate::{
     backtest::{
+        checkpoint::{CheckpointReader, CheckpointWriter},
         data::{Data, DataPreprocess, DataSource, Reader, POD},
         BacktestError,
     },
-    types::Order,
+    types::{Event, Order, Status, EXCH_EVENT, LOCAL_EVENT, TRADE_EVENT},
+    utils::SplitMix64,
 };
 
 /// Provides the order entry latency and the order response latency.
@@ -17,6 +19,20 @@ pub trait LatencyModel {
 
     /// Returns the order response latency for the given timestamp and order.
     fn response(&mut self, timestamp: i64, order: &Order) -> i64;
+
+    /// Reseeds the pseudo-random number generator of the model, if it has any, so that the
+    /// backtest can be reproduced by the seed set on the backtest builder.
+    fn seed(&mut self, _seed: u64) {}
+
+    /// Writes the internal state of the model into the checkpoint, if it has any.
+    fn save_checkpoint(&self, _writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        Ok(())
+    }
+
+    /// Restores the internal state of the model from the checkpoint.
+    fn load_checkpoint(&mut self, _reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        Ok(())
+    }
 }
 
 /// Provides constant order latency.
@@ -293,3 +309,891 @@ impl DataPreprocess<OrderLatencyRow> for OrderLatencyAdjustment {
         Ok(())
     }
 }
+
+/// Provides combinators to compose a latency model from a base model and latency components,
+/// which allows expressing venue-specific latency structures declaratively.
+///
+/// Negative latency, which indicates an order rejection, is passed through unchanged by the
+/// combinators.
+///
+/// **Example**
+/// ```
+/// use hftbacktest::backtest::models::{ConstantLatency, LatencyModelExt};
+///
+/// const HOUR: i64 = 3_600_000_000_000;
+///
+/// let latency_model = ConstantLatency::new(1_000_000, 1_000_000)
+///     // Latency doubles during the busiest hours of the day.
+///     .seasonal(24 * HOUR, [vec![1.0; 13], vec![2.0; 4], vec![1.0; 7]].concat())
+///     // 0.1% of the requests experience an additional 50ms latency.
+///     .spikes(0.001, 50_000_000, 1)
+///     // The gateway processes a request every 100us.
+///     .gateway_queue(100_000);
+/// ```
+pub trait LatencyModelExt: LatencyModel + Sized {
+    /// Adds the latency of the `other` model to this model's latency.
+    fn add<Other: LatencyModel>(self, other: Other) -> AddLatency<Self, Other> {
+        AddLatency { a: self, b: other }
+    }
+
+    /// Multiplies the latency by the multiplier for the time of the period, dividing the `period`
+    /// into `multipliers.len()` equal buckets, e.g. hourly multipliers over a day.
+    fn seasonal(self, period: i64, multipliers: Vec<f64>) -> SeasonalLatency<Self> {
+        assert!(!multipliers.is_empty());
+        SeasonalLatency {
+            model: self,
+            period,
+            multipliers,
+        }
+    }
+
+    /// Adds `extra` latency to the order entry at the given `probability`, using the
+    /// pseudo-random sequence generated from the `seed`.
+    fn spikes(self, probability: f64, extra: i64, seed: u64) -> SpikeLatency<Self> {
+        SpikeLatency {
+            model: self,
+            probability,
+            extra,
+            rng: SplitMix64::new(seed),
+        }
+    }
+
+    /// Makes the order entry go through a gateway that processes the requests one at a time,
+    /// taking `processing_time` for each request. The requests sent in a burst are queued at the
+    /// gateway and experience additional latency.
+    fn gateway_queue(self, processing_time: i64) -> GatewayQueueLatency<Self> {
+        GatewayQueueLatency {
+            model: self,
+            processing_time,
+            busy_until: 0,
+        }
+    }
+
+    /// Scales the latency with the market activity given by the `profile`, so that the latency
+    /// increases during bursts of events. The multiplier is `(1 + sensitivity * rate) /
+    /// (1 + sensitivity)`, where `rate` is the event rate relative to the average, so the latency
+    /// is unchanged at the average activity. The `sensitivity` can be calibrated from recorded
+    /// order latency by [`ActivityProfile::calibrate`].
+    fn activity(self, profile: ActivityProfile, sensitivity: f64) -> ActivityLatency<Self> {
+        assert!(sensitivity >= 0.0);
+        ActivityLatency {
+            model: self,
+            profile,
+            sensitivity,
+        }
+    }
+
+    /// Switches the latency between the [`LatencyRegimes`] by the market activity, to stress-test
+    /// the strategy under exchange overload conditions. Bursts are drawn from the pseudo-random
+    /// sequence generated from the `seed`.
+    fn regimes(self, regimes: LatencyRegimes, seed: u64) -> RegimeLatency<Self> {
+        RegimeLatency {
+            model: self,
+            regimes,
+            rng: SplitMix64::new(seed),
+        }
+    }
+}
+
+impl<LM: LatencyModel> LatencyModelExt for LM {}
+
+/// The sum of two latency models. See [`LatencyModelExt::add`].
+#[derive(Clone)]
+pub struct AddLatency<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> LatencyModel for AddLatency<A, B>
+where
+    A: LatencyModel,
+    B: LatencyModel,
+{
+    fn entry(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.a.entry(timestamp, order);
+        if latency < 0 {
+            return latency;
+        }
+        latency + self.b.entry(timestamp, order)
+    }
+
+    fn response(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.a.response(timestamp, order);
+        if latency < 0 {
+            return latency;
+        }
+        latency + self.b.response(timestamp, order)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        // Derives a distinct seed for the other model so that their sequences are uncorrelated.
+        self.a.seed(seed);
+        self.b.seed(SplitMix64::new(seed).next_u64());
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        self.a.save_checkpoint(writer)?;
+        self.b.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        self.a.load_checkpoint(reader)?;
+        self.b.load_checkpoint(reader)
+    }
+}
+
+/// Latency with a time-of-period multiplier. See [`LatencyModelExt::seasonal`].
+#[derive(Clone)]
+pub struct SeasonalLatency<LM> {
+    model: LM,
+    period: i64,
+    multipliers: Vec<f64>,
+}
+
+impl<LM> SeasonalLatency<LM> {
+    fn scale(&self, timestamp: i64, latency: i64) -> i64 {
+        if latency < 0 {
+            return latency;
+        }
+        let bucket = (timestamp.rem_euclid(self.period) as i128 * self.multipliers.len() as i128
+            / self.period as i128) as usize;
+        (latency as f64 * self.multipliers[bucket]) as i64
+    }
+}
+
+impl<LM> LatencyModel for SeasonalLatency<LM>
+where
+    LM: LatencyModel,
+{
+    fn entry(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.model.entry(timestamp, order);
+        self.scale(timestamp, latency)
+    }
+
+    fn response(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.model.response(timestamp, order);
+        self.scale(timestamp, latency)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.model.seed(seed);
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        self.model.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        self.model.load_checkpoint(reader)
+    }
+}
+
+/// Latency with randomly injected spikes. See [`LatencyModelExt::spikes`].
+#[derive(Clone)]
+pub struct SpikeLatency<LM> {
+    model: LM,
+    probability: f64,
+    extra: i64,
+    rng: SplitMix64,
+}
+
+impl<LM> LatencyModel for SpikeLatency<LM>
+where
+    LM: LatencyModel,
+{
+    fn entry(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.model.entry(timestamp, order);
+        if latency >= 0 && self.rng.next_f64() < self.probability {
+            latency + self.extra
+        } else {
+            latency
+        }
+    }
+
+    fn response(&mut self, timestamp: i64, order: &Order) -> i64 {
+        self.model.response(timestamp, order)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = SplitMix64::new(seed);
+        self.model.seed(SplitMix64::new(seed).next_u64());
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        writer.write(&self.rng)?;
+        self.model.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        self.rng = reader.read()?;
+        self.model.load_checkpoint(reader)
+    }
+}
+
+/// Latency with the queueing at the order gateway. See [`LatencyModelExt::gateway_queue`].
+#[derive(Clone)]
+pub struct GatewayQueueLatency<LM> {
+    model: LM,
+    processing_time: i64,
+    busy_until: i64,
+}
+
+impl<LM> LatencyModel for GatewayQueueLatency<LM>
+where
+    LM: LatencyModel,
+{
+    fn entry(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.model.entry(timestamp, order);
+        if latency < 0 {
+            return latency;
+        }
+        let arrival = timestamp + latency;
+        let done = arrival.max(self.busy_until) + self.processing_time;
+        self.busy_until = done;
+        done - timestamp
+    }
+
+    fn response(&mut self, timestamp: i64, order: &Order) -> i64 {
+        self.model.response(timestamp, order)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.model.seed(seed);
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        writer.write(&self.busy_until)?;
+        self.model.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        self.busy_until = reader.read()?;
+        self.model.load_checkpoint(reader)
+    }
+}
+
+/// The event rate of the market over time, counted from the feed data in fixed windows of the
+/// exchange timestamp. See [`LatencyModelExt::activity`].
+#[derive(Clone, Debug)]
+pub struct ActivityProfile {
+    start: i64,
+    window: i64,
+    rates: Vec<f64>,
+}
+
+impl ActivityProfile {
+    /// Builds the profile from the feed data, counting the exchange events in each `window`.
+    pub fn build(data: Vec<DataSource<Event>>, window: i64) -> Result<Self, BacktestError> {
+        Self::count(data, window, EXCH_EVENT)
+    }
+
+    /// Builds the profile from the feed data, counting only the market trades in each `window`.
+    pub fn trades(data: Vec<DataSource<Event>>, window: i64) -> Result<Self, BacktestError> {
+        Self::count(data, window, EXCH_EVENT | TRADE_EVENT)
+    }
+
+    fn count(data: Vec<DataSource<Event>>, window: i64, event: u64) -> Result<Self, BacktestError> {
+        assert!(window > 0);
+        let mut reader = Reader::builder().data(data).build()?;
+        let mut start = None;
+        let mut counts: Vec<u64> = Vec::new();
+        loop {
+            let data = match reader.next_data() {
+                Ok(data) => data,
+                Err(BacktestError::EndOfData) => break,
+                Err(e) => return Err(e),
+            };
+            for row_num in 0..data.len() {
+                let ev = &data[row_num];
+                if !ev.is(event) {
+                    continue;
+                }
+                let start = *start.get_or_insert(ev.exch_ts - ev.exch_ts.rem_euclid(window));
+                // Out-of-order events before the first window are counted in the first window.
+                let i = ((ev.exch_ts - start).max(0) / window) as usize;
+                if i >= counts.len() {
+                    counts.resize(i + 1, 0);
+                }
+                counts[i] += 1;
+            }
+            reader.release(data);
+        }
+        Ok(Self::from_counts(start.unwrap_or(0), window, &counts))
+    }
+
+    /// Constructs the profile from the number of events in each `window`, starting at `start`.
+    pub fn from_counts(start: i64, window: i64, counts: &[u64]) -> Self {
+        assert!(window > 0);
+        let mean = counts.iter().sum::<u64>() as f64 / counts.len().max(1) as f64;
+        let rates = counts
+            .iter()
+            .map(|&count| if mean > 0.0 { count as f64 / mean } else { 1.0 })
+            .collect();
+        Self {
+            start,
+            window,
+            rates,
+        }
+    }
+
+    /// Returns the event rate of the window containing the timestamp, relative to the average.
+    /// Outside the profile, the average rate `1.0` is returned.
+    pub fn rate(&self, timestamp: i64) -> f64 {
+        if timestamp < self.start {
+            return 1.0;
+        }
+        let i = ((timestamp - self.start) / self.window) as usize;
+        self.rates.get(i).copied().unwrap_or(1.0)
+    }
+
+    /// Estimates the sensitivity of the order entry latency to the event rate from the recorded
+    /// order latency, by fitting `latency = a + b * rate` and returning `b / a`. The rows whose
+    /// requests were rejected, whose exchange timestamp is not positive, are excluded. If the
+    /// latency doesn't increase with the event rate, `0.0` is returned.
+    pub fn calibrate(&self, rows: &[OrderLatencyRow]) -> f64 {
+        let samples: Vec<(f64, f64)> = rows
+            .iter()
+            .filter(|row| row.exch_ts > 0)
+            .map(|row| (self.rate(row.req_ts), (row.exch_ts - row.req_ts) as f64))
+            .collect();
+        let n = samples.len() as f64;
+        if n < 2.0 {
+            return 0.0;
+        }
+        let mean_x = samples.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = samples.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let cov = samples
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum::<f64>();
+        let var = samples
+            .iter()
+            .map(|(x, _)| (x - mean_x).powi(2))
+            .sum::<f64>();
+        if var == 0.0 {
+            return 0.0;
+        }
+        let b = cov / var;
+        let a = mean_y - b * mean_x;
+        if b <= 0.0 || a <= 0.0 {
+            return 0.0;
+        }
+        b / a
+    }
+}
+
+/// Latency scaled with the market activity. See [`LatencyModelExt::activity`].
+#[derive(Clone)]
+pub struct ActivityLatency<LM> {
+    model: LM,
+    profile: ActivityProfile,
+    sensitivity: f64,
+}
+
+impl<LM> ActivityLatency<LM> {
+    fn scale(&self, timestamp: i64, latency: i64) -> i64 {
+        if latency < 0 {
+            return latency;
+        }
+        let rate = self.profile.rate(timestamp);
+        (latency as f64 * (1.0 + self.sensitivity * rate) / (1.0 + self.sensitivity)).round() as i64
+    }
+}
+
+impl<LM> LatencyModel for ActivityLatency<LM>
+where
+    LM: LatencyModel,
+{
+    fn entry(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.model.entry(timestamp, order);
+        self.scale(timestamp, latency)
+    }
+
+    fn response(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.model.response(timestamp, order);
+        self.scale(timestamp, latency)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.model.seed(seed);
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        self.model.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        self.model.load_checkpoint(reader)
+    }
+}
+
+/// A latency regime that applies while the market activity is at or above `min_rate`, relative
+/// to the average. See [`LatencyRegimes`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LatencyRegime {
+    /// The minimum activity rate, relative to the average, at which the regime applies.
+    pub min_rate: f64,
+    /// The multiplier of the order entry and response latency.
+    pub multiplier: f64,
+    /// The probability that an order entry hits a burst.
+    pub burst_probability: f64,
+    /// The extra order entry latency of a burst.
+    pub burst_latency: i64,
+    /// The extra feed latency added to the local timestamp of the feed.
+    pub feed_delay: i64,
+}
+
+impl LatencyRegime {
+    /// Constructs a `LatencyRegime` that multiplies the order latency by the `multiplier` while
+    /// the activity rate is at or above `min_rate`.
+    pub fn new(min_rate: f64, multiplier: f64) -> Self {
+        assert!(multiplier >= 0.0);
+        Self {
+            min_rate,
+            multiplier,
+            burst_probability: 0.0,
+            burst_latency: 0,
+            feed_delay: 0,
+        }
+    }
+
+    /// Adds `latency` to the order entry at the given `probability` within the regime.
+    pub fn burst(self, probability: f64, latency: i64) -> Self {
+        Self {
+            burst_probability: probability,
+            burst_latency: latency,
+            ..self
+        }
+    }
+
+    /// Delays the feed by `feed_delay` within the regime.
+    pub fn feed_delay(self, feed_delay: i64) -> Self {
+        Self { feed_delay, ..self }
+    }
+}
+
+/// A set of [`LatencyRegime`]s switched by the market activity given by the [`ActivityProfile`],
+/// typically built from the trade rate by [`ActivityProfile::trades`]. At each timestamp, the
+/// regime with the highest `min_rate` not exceeding the activity rate applies, and if there is no
+/// such regime, the latency is unchanged.
+///
+/// The order latency is switched by [`LatencyModelExt::regimes`]. The feed latency is switched by
+/// preprocessing the feed data with this as a [`DataPreprocess`] before running the backtest, in
+/// which case the local timestamp is kept from going backwards across the regime changes.
+///
+/// ```
+/// use hftbacktest::backtest::models::{ActivityProfile, LatencyRegime, LatencyRegimes};
+///
+/// let profile = ActivityProfile::from_counts(0, 1_000_000_000, &[10, 10, 80, 10]);
+/// let regimes = LatencyRegimes::new(
+///     profile,
+///     vec![
+///         LatencyRegime::new(0.0, 1.0),
+///         // Overloaded while the trade rate is more than twice the average.
+///         LatencyRegime::new(2.0, 3.0)
+///             .burst(0.1, 50_000_000)
+///             .feed_delay(5_000_000),
+///     ],
+/// );
+/// assert_eq!(regimes.regime(2_500_000_000).unwrap().multiplier, 3.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LatencyRegimes {
+    profile: ActivityProfile,
+    regimes: Vec<LatencyRegime>,
+}
+
+impl LatencyRegimes {
+    /// Constructs `LatencyRegimes` from the activity profile and the regimes in any order.
+    pub fn new(profile: ActivityProfile, mut regimes: Vec<LatencyRegime>) -> Self {
+        regimes.sort_by(|a, b| a.min_rate.total_cmp(&b.min_rate));
+        Self { profile, regimes }
+    }
+
+    /// Returns the regime that applies at the timestamp.
+    pub fn regime(&self, timestamp: i64) -> Option<&LatencyRegime> {
+        let rate = self.profile.rate(timestamp);
+        self.regimes
+            .iter()
+            .rev()
+            .find(|regime| regime.min_rate <= rate)
+    }
+}
+
+impl DataPreprocess<Event> for LatencyRegimes {
+    fn preprocess(&self, data: &mut Data<Event>) -> Result<(), IoError> {
+        let mut last_local_ts = i64::MIN;
+        let mut last_delayed_ts = i64::MIN;
+        for i in 0..data.len() {
+            let ev = &mut data[i];
+            if !ev.is(LOCAL_EVENT) {
+                continue;
+            }
+            let feed_delay = self
+                .regime(ev.exch_ts)
+                .map_or(0, |regime| regime.feed_delay);
+            let mut local_ts = ev.local_ts + feed_delay;
+            if ev.local_ts >= last_local_ts {
+                // The delay shrinks when the regime calms down, which must not reorder the feed.
+                local_ts = local_ts.max(last_delayed_ts);
+                last_local_ts = ev.local_ts;
+                last_delayed_ts = local_ts;
+            }
+            ev.local_ts = local_ts;
+        }
+        Ok(())
+    }
+}
+
+/// Latency switched between the regimes by the market activity. See
+/// [`LatencyModelExt::regimes`].
+#[derive(Clone)]
+pub struct RegimeLatency<LM> {
+    model: LM,
+    regimes: LatencyRegimes,
+    rng: SplitMix64,
+}
+
+impl<LM> LatencyModel for RegimeLatency<LM>
+where
+    LM: LatencyModel,
+{
+    fn entry(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.model.entry(timestamp, order);
+        if latency < 0 {
+            return latency;
+        }
+        let Some(regime) = self.regimes.regime(timestamp) else {
+            return latency;
+        };
+        let latency = (latency as f64 * regime.multiplier).round() as i64;
+        if self.rng.next_f64() < regime.burst_probability {
+            latency + regime.burst_latency
+        } else {
+            latency
+        }
+    }
+
+    fn response(&mut self, timestamp: i64, order: &Order) -> i64 {
+        let latency = self.model.response(timestamp, order);
+        if latency < 0 {
+            return latency;
+        }
+        match self.regimes.regime(timestamp) {
+            Some(regime) => (latency as f64 * regime.multiplier).round() as i64,
+            None => latency,
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = SplitMix64::new(seed);
+        self.model.seed(SplitMix64::new(seed).next_u64());
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        writer.write(&self.rng)?;
+        self.model.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        self.rng = reader.read()?;
+        self.model.load_checkpoint(reader)
+    }
+}
+
+/// Order latency with separate models for the entry of new orders, the entry of cancels, and the
+/// responses, since the cancel latency often differs materially from the new order latency on real
+/// venues. The entry latencies are taken from [`LatencyModel::entry`] of `new` and `cancel`, and
+/// the response latency, including the acks and the fills, from [`LatencyModel::response`] of
+/// `ack`.
+///
+/// ```
+/// use hftbacktest::backtest::models::{ConstantLatency, RequestLatency};
+///
+/// // Cancels reach the matching engine faster than new orders, which go through risk checks.
+/// let latency = RequestLatency::new(
+///     ConstantLatency::new(200_000, 0),
+///     ConstantLatency::new(80_000, 0),
+///     ConstantLatency::new(0, 150_000),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct RequestLatency<New, Cancel, Ack> {
+    new: New,
+    cancel: Cancel,
+    ack: Ack,
+}
+
+impl<New, Cancel, Ack> RequestLatency<New, Cancel, Ack> {
+    /// Constructs an instance of `RequestLatency`.
+    pub fn new(new: New, cancel: Cancel, ack: Ack) -> Self {
+        Self { new, cancel, ack }
+    }
+}
+
+impl<New, Cancel, Ack> LatencyModel for RequestLatency<New, Cancel, Ack>
+where
+    New: LatencyModel,
+    Cancel: LatencyModel,
+    Ack: LatencyModel,
+{
+    fn entry(&mut self, timestamp: i64, order: &Order) -> i64 {
+        if order.req == Status::Canceled {
+            self.cancel.entry(timestamp, order)
+        } else {
+            self.new.entry(timestamp, order)
+        }
+    }
+
+    fn response(&mut self, timestamp: i64, order: &Order) -> i64 {
+        self.ack.response(timestamp, order)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        // Derives distinct seeds for the models so that their sequences are uncorrelated.
+        let mut rng = SplitMix64::new(seed);
+        self.new.seed(seed);
+        self.cancel.seed(rng.next_u64());
+        self.ack.seed(rng.next_u64());
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        self.new.save_checkpoint(writer)?;
+        self.cancel.save_checkpoint(writer)?;
+        self.ack.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        self.new.load_checkpoint(reader)?;
+        self.cancel.load_checkpoint(reader)?;
+        self.ack.load_checkpoint(reader)
+    }
+}
+
+/// A type-erased latency model, which allows latency models of different types to be chosen at
+/// runtime, such as when comparing latency scenarios.
+pub struct BoxedLatency(Box<dyn CloneLatencyModel>);
+
+impl BoxedLatency {
+    /// Constructs a `BoxedLatency` wrapping the latency model.
+    pub fn new<LM>(model: LM) -> Self
+    where
+        LM: LatencyModel + Clone + 'static,
+    {
+        Self(Box::new(model))
+    }
+}
+
+impl Clone for BoxedLatency {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl LatencyModel for BoxedLatency {
+    fn entry(&mut self, timestamp: i64, order: &Order) -> i64 {
+        self.0.entry(timestamp, order)
+    }
+
+    fn response(&mut self, timestamp: i64, order: &Order) -> i64 {
+        self.0.response(timestamp, order)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.0.seed(seed);
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        self.0.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        self.0.load_checkpoint(reader)
+    }
+}
+
+trait CloneLatencyModel: LatencyModel {
+    fn clone_box(&self) -> Box<dyn CloneLatencyModel>;
+}
+
+impl<LM> CloneLatencyModel for LM
+where
+    LM: LatencyModel + Clone + 'static,
+{
+    fn clone_box(&self) -> Box<dyn CloneLatencyModel> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ActivityProfile,
+        ConstantLatency,
+        LatencyModel,
+        LatencyModelExt,
+        LatencyRegime,
+        LatencyRegimes,
+        OrderLatencyRow,
+        RequestLatency,
+    };
+    use crate::{
+        backtest::data::{Data, DataPreprocess, DataSource},
+        types::{
+            Event,
+            OrdType,
+            Order,
+            Side,
+            Status,
+            TimeInForce,
+            DEPTH_EVENT,
+            EXCH_EVENT,
+            LOCAL_EVENT,
+            TRADE_EVENT,
+        },
+    };
+
+    #[test]
+    fn test_latency_composition() {
+        let order = Order::new(1, 0, 0.1, 1.0, Side::Buy, OrdType::Limit, TimeInForce::GTC);
+
+        let mut model = ConstantLatency::new(100, 50)
+            .add(ConstantLatency::new(10, 0))
+            .seasonal(1000, vec![1.0, 2.0]);
+        assert_eq!(model.entry(0, &order), 110);
+        assert_eq!(model.entry(500, &order), 220);
+        assert_eq!(model.response(1700, &order), 100);
+
+        // Cancels and new orders have their own entry latencies.
+        let mut model = RequestLatency::new(
+            ConstantLatency::new(100, 0),
+            ConstantLatency::new(30, 0),
+            ConstantLatency::new(0, 70),
+        );
+        let mut cancel = order.clone();
+        cancel.req = Status::Canceled;
+        assert_eq!(model.entry(0, &order), 100);
+        assert_eq!(model.entry(0, &cancel), 30);
+        assert_eq!(model.response(0, &cancel), 70);
+
+        // Burst requests are queued at the gateway.
+        let mut model = ConstantLatency::new(100, 50).gateway_queue(20);
+        assert_eq!(model.entry(0, &order), 120);
+        assert_eq!(model.entry(0, &order), 140);
+        assert_eq!(model.entry(1000, &order), 120);
+
+        // Rejections are passed through.
+        let mut model = ConstantLatency::new(-100, 50)
+            .gateway_queue(20)
+            .spikes(1.0, 10, 0);
+        assert_eq!(model.entry(0, &order), -100);
+
+        let mut model = ConstantLatency::new(100, 50).spikes(1.0, 10, 0);
+        assert_eq!(model.entry(0, &order), 110);
+        assert_eq!(model.response(0, &order), 50);
+    }
+
+    #[test]
+    fn test_latency_seed() {
+        let order = Order::new(1, 0, 0.1, 1.0, Side::Buy, OrdType::Limit, TimeInForce::GTC);
+
+        let mut a = ConstantLatency::new(100, 50).spikes(0.5, 10, 1);
+        let mut b = ConstantLatency::new(100, 50).spikes(0.5, 10, 2);
+        a.seed(7);
+        b.seed(7);
+        let a: Vec<_> = (0..100).map(|i| a.entry(i, &order)).collect();
+        let b: Vec<_> = (0..100).map(|i| b.entry(i, &order)).collect();
+        assert_eq!(a, b);
+        assert!(a.contains(&100) && a.contains(&110));
+    }
+
+    #[test]
+    fn test_activity_latency() {
+        let order = Order::new(1, 0, 0.1, 1.0, Side::Buy, OrdType::Limit, TimeInForce::GTC);
+
+        // One event per window, except a burst of 7 events in the third window.
+        let events: Vec<_> = [100, 1100, 2100, 2200, 2300, 2400, 2500, 2600, 2700, 3100]
+            .iter()
+            .map(|&ts| Event {
+                ev: EXCH_EVENT | LOCAL_EVENT,
+                exch_ts: ts,
+                local_ts: ts + 10,
+                px: 0.0,
+                qty: 0.0,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            })
+            .collect();
+        let profile =
+            ActivityProfile::build(vec![DataSource::Data(Data::from_data(&events))], 1000).unwrap();
+        assert_eq!(profile.rate(500), 0.4);
+        assert_eq!(profile.rate(2000), 2.8);
+        assert_eq!(profile.rate(10_000), 1.0);
+
+        let mut model = ConstantLatency::new(100, 50).activity(profile.clone(), 1.0);
+        assert_eq!(model.entry(500, &order), 70);
+        assert_eq!(model.entry(2500, &order), 190);
+        assert_eq!(model.response(2500, &order), 95);
+
+        // Recorded latency of 100 + 100 * rate is calibrated to the sensitivity of 1.
+        let rows: Vec<_> = [500, 1500, 2500]
+            .iter()
+            .map(|&ts| OrderLatencyRow {
+                req_ts: ts,
+                exch_ts: ts + 100 + (100.0 * profile.rate(ts)) as i64,
+                resp_ts: 0,
+                _padding: 0,
+            })
+            .collect();
+        assert!((profile.calibrate(&rows) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_regime_latency() {
+        let order = Order::new(1, 0, 0.1, 1.0, Side::Buy, OrdType::Limit, TimeInForce::GTC);
+
+        // One trade per window, except a burst of 5 trades in the second window.
+        let events: Vec<_> = [100, 1100, 1200, 1300, 1400, 1500, 2050, 2100, 3100]
+            .iter()
+            .enumerate()
+            .map(|(i, &ts)| Event {
+                ev: EXCH_EVENT | LOCAL_EVENT | if i == 6 { DEPTH_EVENT } else { TRADE_EVENT },
+                exch_ts: ts,
+                local_ts: ts + 10,
+                px: 0.0,
+                qty: 0.0,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            })
+            .collect();
+        let profile =
+            ActivityProfile::trades(vec![DataSource::Data(Data::from_data(&events))], 1000)
+                .unwrap();
+        assert_eq!(profile.rate(1000), 2.5);
+        let regimes = LatencyRegimes::new(
+            profile,
+            vec![
+                LatencyRegime::new(2.0, 3.0)
+                    .burst(1.0, 1000)
+                    .feed_delay(1000),
+                LatencyRegime::new(0.0, 1.0),
+            ],
+        );
+
+        let mut model = ConstantLatency::new(100, 50).regimes(regimes.clone(), 0);
+        assert_eq!(model.entry(500, &order), 100);
+        assert_eq!(model.entry(1500, &order), 1300);
+        assert_eq!(model.response(1500, &order), 150);
+
+        // The feed is delayed in the busy window and doesn't go backwards after it.
+        let mut data = Data::from_data(&events);
+        regimes.preprocess(&mut data).unwrap();
+        let local_ts: Vec<_> = (0..data.len()).map(|i| data[i].local_ts).collect();
+        assert_eq!(
+            local_ts,
+            vec![110, 2110, 2210, 2310, 2410, 2510, 2510, 2510, 3110]
+        );
+    }
+}
@@ -4,18 +4,40 @@
 //! * [Latency Models](https://hftbacktest.readthedocs.io/en/latest/latency_models.html)
 //! * [Order Fill](https://hftbacktest.readthedocs.io/en/latest/order_fill.html)
 mod fee;
+mod fill;
 mod latency;
 mod queue;
 
 pub use fee::{
     CommonFees,
     DirectionalFees,
+    FeeAccount,
     FeeModel,
+    FeeTier,
     FlatPerTradeFeeModel,
+    TieredFeeModel,
     TradingQtyFeeModel,
     TradingValueFeeModel,
 };
-pub use latency::{ConstantLatency, IntpOrderLatency, LatencyModel, OrderLatencyRow};
+pub use fill::{DepthFillModel, FillModel};
+pub use latency::{
+    ActivityLatency,
+    ActivityProfile,
+    AddLatency,
+    BoxedLatency,
+    ConstantLatency,
+    GatewayQueueLatency,
+    IntpOrderLatency,
+    LatencyModel,
+    LatencyModelExt,
+    LatencyRegime,
+    LatencyRegimes,
+    OrderLatencyRow,
+    RegimeLatency,
+    RequestLatency,
+    SeasonalLatency,
+    SpikeLatency,
+};
 pub use queue::{
     L3FIFOQueueModel,
     L3QueueModel,
@@ -28,5 +50,7 @@ pub use queue::{
     Probability,
     QueueModel,
     QueuePos,
+    QueueState,
     RiskAdverseQueueModel,
+    TieredQueueModel,
 };
@@ -2,10 +2,14 @@ use std::{
     any::Any,
     collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     marker::PhantomData,
+    mem,
 };
 
 use crate::{
-    backtest::BacktestError,
+    backtest::{
+        checkpoint::{checkpoint_error, CheckpointReader, CheckpointWriter},
+        BacktestError,
+    },
     depth::{MarketDepth, INVALID_MAX, INVALID_MIN},
     types::{
         AnyClone,
@@ -13,12 +17,14 @@ use crate::{
         OrdType,
         Order,
         OrderId,
+        PositionSide,
         Side,
         Status,
         TimeInForce,
         BUY_EVENT,
         SELL_EVENT,
     },
+    utils::SplitMix64,
 };
 
 /// Provides an estimation of the order's queue position.
@@ -37,6 +43,85 @@ where
     fn depth(&self, order: &mut Order, prev_qty: f64, new_qty: f64, depth: &MD);
 
     fn is_filled(&self, order: &Order, depth: &MD) -> f64;
+
+    /// Returns the estimated quantity ahead of the order in the queue, if the model can estimate
+    /// it. It's used by [`FillDiagnostics`](crate::backtest::diagnostics::FillDiagnostics).
+    fn queue_position(&self, _order: &Order) -> Option<f64> {
+        None
+    }
+
+    /// Reseeds the pseudo-random number generator of the model, if it has any, so that the
+    /// backtest can be reproduced by the seed set on the backtest builder. The built-in models are
+    /// deterministic, as they use the expected fill probability.
+    fn seed(&mut self, _seed: u64) {}
+
+    /// Writes the queue position of the order into the checkpoint.
+    fn save_checkpoint(
+        &self,
+        _order: &Order,
+        _writer: &mut CheckpointWriter,
+    ) -> Result<(), BacktestError> {
+        Err(checkpoint_error(
+            "the queue model doesn't support checkpoints",
+        ))
+    }
+
+    /// Restores the queue position of the order from the checkpoint.
+    fn load_checkpoint(
+        &self,
+        _order: &mut Order,
+        _reader: &mut CheckpointReader,
+    ) -> Result<(), BacktestError> {
+        Err(checkpoint_error(
+            "the queue model doesn't support checkpoints",
+        ))
+    }
+}
+
+impl<MD, QM> QueueModel<MD> for Box<QM>
+where
+    MD: MarketDepth,
+    QM: QueueModel<MD> + ?Sized,
+{
+    fn new_order(&self, order: &mut Order, depth: &MD) {
+        (**self).new_order(order, depth)
+    }
+
+    fn trade(&self, order: &mut Order, qty: f64, depth: &MD) {
+        (**self).trade(order, qty, depth)
+    }
+
+    fn depth(&self, order: &mut Order, prev_qty: f64, new_qty: f64, depth: &MD) {
+        (**self).depth(order, prev_qty, new_qty, depth)
+    }
+
+    fn is_filled(&self, order: &Order, depth: &MD) -> f64 {
+        (**self).is_filled(order, depth)
+    }
+
+    fn queue_position(&self, order: &Order) -> Option<f64> {
+        (**self).queue_position(order)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        (**self).seed(seed)
+    }
+
+    fn save_checkpoint(
+        &self,
+        order: &Order,
+        writer: &mut CheckpointWriter,
+    ) -> Result<(), BacktestError> {
+        (**self).save_checkpoint(order, writer)
+    }
+
+    fn load_checkpoint(
+        &self,
+        order: &mut Order,
+        reader: &mut CheckpointReader,
+    ) -> Result<(), BacktestError> {
+        (**self).load_checkpoint(order, reader)
+    }
 }
 
 /// Provides a conservative queue position model, where your order's queue position advances only
@@ -70,7 +155,7 @@ where
         } else {
             depth.ask_qty_at_tick(order.price_tick)
         };
-        order.q = Box::new(front_q_qty);
+        order.q = QueueState::Qty(front_q_qty);
     }
 
     fn trade(&self, order: &mut Order, qty: f64, _depth: &MD) {
@@ -91,6 +176,27 @@ where
             0.0
         }
     }
+
+    fn queue_position(&self, order: &Order) -> Option<f64> {
+        order.q.as_any().downcast_ref::<f64>().copied()
+    }
+
+    fn save_checkpoint(
+        &self,
+        order: &Order,
+        writer: &mut CheckpointWriter,
+    ) -> Result<(), BacktestError> {
+        writer.write(order.q.as_any().downcast_ref::<f64>().unwrap())
+    }
+
+    fn load_checkpoint(
+        &self,
+        order: &mut Order,
+        reader: &mut CheckpointReader,
+    ) -> Result<(), BacktestError> {
+        order.q = QueueState::Qty(reader.read::<f64>()?);
+        Ok(())
+    }
 }
 
 /// Stores the values needed for queue position estimation and adjustment for [`ProbQueueModel`].
@@ -119,6 +225,58 @@ impl Default for QueuePos {
     }
 }
 
+/// The queue state of an order, which is stored in [`order.q`](crate::types::Order::q) by the
+/// queue model.
+///
+/// The states of the built-in queue models are stored inline, so that neither a new order nor an
+/// order response, which carries a copy of the order, allocates. The other states are boxed into
+/// [`QueueState::Custom`]. In any case, [`as_any`](QueueState::as_any) downcasts the state to its
+/// type.
+#[derive(Clone, Default)]
+pub enum QueueState {
+    /// No queue state, as with the orders not yet at the exchange or in a live bot.
+    #[default]
+    None,
+    /// The quantity ahead of the order, for [`RiskAdverseQueueModel`].
+    Qty(f64),
+    /// The queue position for [`ProbQueueModel`].
+    Pos(QueuePos),
+    /// The order source for [`L3FIFOQueueModel`].
+    L3(L3OrderSource),
+    /// Any other state, such as the one of a custom queue model.
+    Custom(Box<dyn AnyClone + Send>),
+}
+
+impl QueueState {
+    /// Boxes a custom state.
+    pub fn custom<T: AnyClone + Send + 'static>(state: T) -> Self {
+        Self::Custom(Box::new(state))
+    }
+
+    /// Returns the state as [`Any`], which is `()` for [`QueueState::None`].
+    pub fn as_any(&self) -> &dyn Any {
+        match self {
+            QueueState::None => &(),
+            QueueState::Qty(qty) => qty,
+            QueueState::Pos(pos) => pos,
+            QueueState::L3(source) => source,
+            QueueState::Custom(state) => state.as_any(),
+        }
+    }
+
+    /// Returns the state as mutable [`Any`], which is `()` for [`QueueState::None`].
+    pub fn as_any_mut(&mut self) -> &mut dyn Any {
+        match self {
+            // Boxing a zero-sized value doesn't allocate.
+            QueueState::None => Box::leak(Box::new(())),
+            QueueState::Qty(qty) => qty,
+            QueueState::Pos(pos) => pos,
+            QueueState::L3(source) => source,
+            QueueState::Custom(state) => state.as_any_mut(),
+        }
+    }
+}
+
 /// Provides the probability of a decrease behind the order's queue position.
 pub trait Probability {
     /// Returns the probability based on the quantity ahead and behind the order.
@@ -167,7 +325,7 @@ where
         } else {
             q.front_q_qty = depth.ask_qty_at_tick(order.price_tick);
         }
-        order.q = Box::new(q);
+        order.q = QueueState::Pos(q);
     }
 
     fn trade(&self, order: &mut Order, qty: f64, _depth: &MD) {
@@ -210,6 +368,36 @@ where
             0.0
         }
     }
+
+    fn queue_position(&self, order: &Order) -> Option<f64> {
+        order
+            .q
+            .as_any()
+            .downcast_ref::<QueuePos>()
+            .map(|q| q.front_q_qty)
+    }
+
+    fn save_checkpoint(
+        &self,
+        order: &Order,
+        writer: &mut CheckpointWriter,
+    ) -> Result<(), BacktestError> {
+        let q = order.q.as_any().downcast_ref::<QueuePos>().unwrap();
+        writer.write(&(q.front_q_qty, q.cum_trade_qty))
+    }
+
+    fn load_checkpoint(
+        &self,
+        order: &mut Order,
+        reader: &mut CheckpointReader,
+    ) -> Result<(), BacktestError> {
+        let (front_q_qty, cum_trade_qty) = reader.read()?;
+        order.q = QueueState::Pos(QueuePos {
+            front_q_qty,
+            cum_trade_qty,
+        });
+        Ok(())
+    }
 }
 
 /// This probability model uses a power function `f(x) = x ** n` to adjust the probability which is
@@ -325,10 +513,204 @@ impl Probability for PowerProbQueueFunc3 {
     }
 }
 
+/// Selects the queue model of an order by its distance from the mid-price when the order is
+/// accepted, such as a precise model for the orders near the touch and a more conservative one for
+/// the orders deep in the book. The selected model is kept for the lifetime of the order. If either
+/// side of the book is empty, the `near` model is selected.
+///
+/// The queue model is set per asset by its asset builder, so a different model can be used for
+/// each instrument, and this allows to vary it by the price level as well.
+///
+/// ```
+/// use hftbacktest::{
+///     backtest::models::{PowerProbQueueFunc3, ProbQueueModel, RiskAdverseQueueModel, TieredQueueModel},
+///     depth::HashMapMarketDepth,
+/// };
+///
+/// // The orders within 5 ticks of the mid-price use the probabilistic model.
+/// let queue_model: TieredQueueModel<_, _, HashMapMarketDepth> = TieredQueueModel::new(
+///     ProbQueueModel::new(PowerProbQueueFunc3::new(3.0)),
+///     RiskAdverseQueueModel::new(),
+///     5.0,
+/// );
+/// ```
+pub struct TieredQueueModel<Near, Far, MD> {
+    near: Near,
+    far: Far,
+    max_ticks: f64,
+    _md_marker: PhantomData<MD>,
+}
+
+/// Stores the queue position of the model selected by [`TieredQueueModel`].
+#[derive(Clone)]
+struct TieredQueuePos {
+    near: bool,
+    q: QueueState,
+}
+
+impl AnyClone for TieredQueuePos {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<Near, Far, MD> TieredQueueModel<Near, Far, MD>
+where
+    Near: QueueModel<MD>,
+    Far: QueueModel<MD>,
+    MD: MarketDepth,
+{
+    /// Constructs an instance of `TieredQueueModel`, which selects the `near` model for the orders
+    /// within `max_ticks` of the mid-price, and the `far` model for the others.
+    pub fn new(near: Near, far: Far, max_ticks: f64) -> Self {
+        Self {
+            near,
+            far,
+            max_ticks,
+            _md_marker: Default::default(),
+        }
+    }
+
+    /// Calls `f` with the order holding the queue position of the selected model, and stores the
+    /// queue position back.
+    fn with_inner<R>(order: &mut Order, f: impl FnOnce(bool, &mut Order) -> R) -> R {
+        let tiered = order
+            .q
+            .as_any_mut()
+            .downcast_mut::<TieredQueuePos>()
+            .unwrap();
+        let near = tiered.near;
+        let inner = mem::take(&mut tiered.q);
+        let outer = mem::replace(&mut order.q, inner);
+        let result = f(near, order);
+        let inner = mem::replace(&mut order.q, outer);
+        order
+            .q
+            .as_any_mut()
+            .downcast_mut::<TieredQueuePos>()
+            .unwrap()
+            .q = inner;
+        result
+    }
+
+    /// Returns a copy of the order holding the queue position of the selected model.
+    fn inner(order: &Order) -> (bool, Order) {
+        let tiered = order.q.as_any().downcast_ref::<TieredQueuePos>().unwrap();
+        let mut inner = order.clone();
+        inner.q = tiered.q.clone();
+        (tiered.near, inner)
+    }
+
+    /// Wraps the queue position set by the selected model.
+    fn wrap(near: bool, order: &mut Order) {
+        let q = mem::take(&mut order.q);
+        order.q = QueueState::custom(TieredQueuePos { near, q });
+    }
+}
+
+impl<Near, Far, MD> QueueModel<MD> for TieredQueueModel<Near, Far, MD>
+where
+    Near: QueueModel<MD>,
+    Far: QueueModel<MD>,
+    MD: MarketDepth,
+{
+    fn new_order(&self, order: &mut Order, depth: &MD) {
+        let near = if depth.best_bid_tick() == INVALID_MIN || depth.best_ask_tick() == INVALID_MAX {
+            true
+        } else {
+            let mid_tick = (depth.best_bid_tick() as f64 + depth.best_ask_tick() as f64) / 2.0;
+            (order.price_tick as f64 - mid_tick).abs() <= self.max_ticks
+        };
+        if near {
+            self.near.new_order(order, depth);
+        } else {
+            self.far.new_order(order, depth);
+        }
+        Self::wrap(near, order);
+    }
+
+    fn trade(&self, order: &mut Order, qty: f64, depth: &MD) {
+        Self::with_inner(order, |near, order| {
+            if near {
+                self.near.trade(order, qty, depth)
+            } else {
+                self.far.trade(order, qty, depth)
+            }
+        })
+    }
+
+    fn depth(&self, order: &mut Order, prev_qty: f64, new_qty: f64, depth: &MD) {
+        Self::with_inner(order, |near, order| {
+            if near {
+                self.near.depth(order, prev_qty, new_qty, depth)
+            } else {
+                self.far.depth(order, prev_qty, new_qty, depth)
+            }
+        })
+    }
+
+    fn is_filled(&self, order: &Order, depth: &MD) -> f64 {
+        let (near, order) = Self::inner(order);
+        if near {
+            self.near.is_filled(&order, depth)
+        } else {
+            self.far.is_filled(&order, depth)
+        }
+    }
+
+    fn queue_position(&self, order: &Order) -> Option<f64> {
+        let (near, order) = Self::inner(order);
+        if near {
+            self.near.queue_position(&order)
+        } else {
+            self.far.queue_position(&order)
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        // Derives a distinct seed for the other model so that their sequences are uncorrelated.
+        self.near.seed(seed);
+        self.far.seed(SplitMix64::new(seed).next_u64());
+    }
+
+    fn save_checkpoint(
+        &self,
+        order: &Order,
+        writer: &mut CheckpointWriter,
+    ) -> Result<(), BacktestError> {
+        let (near, order) = Self::inner(order);
+        writer.write(&near)?;
+        if near {
+            self.near.save_checkpoint(&order, writer)
+        } else {
+            self.far.save_checkpoint(&order, writer)
+        }
+    }
+
+    fn load_checkpoint(
+        &self,
+        order: &mut Order,
+        reader: &mut CheckpointReader,
+    ) -> Result<(), BacktestError> {
+        let near = reader.read::<bool>()?;
+        if near {
+            self.near.load_checkpoint(order, reader)?;
+        } else {
+            self.far.load_checkpoint(order, reader)?;
+        }
+        Self::wrap(near, order);
+        Ok(())
+    }
+}
+
 /// Represents the order source for the Level 3 Market-By-Order queue model, which is stored in
 /// [`order.q`](crate::types::Order::q)
-#[derive(Copy, Clone, Eq, PartialEq)]
-enum L3OrderSource {
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum L3OrderSource {
     /// Represents an order originating from the market feed.
     MarketFeed,
     /// Represents an order originating from the backtest.
@@ -464,6 +846,10 @@ pub trait L3QueueModel<MD> {
     /// Due to these challenges, HftBacktest opts to clear all backtest orders upon receiving a
     /// clear message, even though this may differ from the exchange's actual behavior.
     fn clear_orders(&mut self, side: Side) -> Vec<Order>;
+
+    /// Reseeds the pseudo-random number generator of the model, if it has any, so that the
+    /// backtest can be reproduced by the seed set on the backtest builder.
+    fn seed(&mut self, _seed: u64) {}
 }
 
 /// This provides a Level 3 Market-By-Order queue model for backtesting in a FIFO manner. This means
@@ -622,7 +1008,7 @@ where
         let side = order.side;
         let order_id = order.order_id;
 
-        order.q = Box::new(L3OrderSource::Backtest);
+        order.q = QueueState::L3(L3OrderSource::Backtest);
 
         let queue = match side {
             Side::Buy => self.bid_queue.entry(order_price_tick).or_default(),
@@ -662,7 +1048,7 @@ where
             leaves_qty: order.qty,
             price_tick: order_price_tick,
             exch_timestamp: order.exch_ts,
-            q: Box::new(L3OrderSource::MarketFeed),
+            q: QueueState::L3(L3OrderSource::MarketFeed),
             tick_size,
             order_id,
             side,
@@ -675,6 +1061,8 @@ where
             req: Status::None,
             status: Status::None,
             time_in_force: TimeInForce::GTC,
+            reduce_only: false,
+            position_side: PositionSide::Both,
         });
 
         match self.mkt_feed_orders.entry(order_id) {
@@ -774,7 +1162,7 @@ where
         mut order: Order,
         _depth: &MD,
     ) -> Result<(), BacktestError> {
-        order.q = Box::new(L3OrderSource::Backtest);
+        order.q = QueueState::L3(L3OrderSource::Backtest);
 
         let (side, order_price_tick) = self
             .backtest_orders
@@ -1120,13 +1508,17 @@ where
 #[cfg(test)]
 mod l3_tests {
     use crate::{
-        backtest::{models::L3FIFOQueueModel, L3QueueModel},
+        backtest::{
+            models::{L3FIFOQueueModel, QueueState},
+            L3QueueModel,
+        },
         prelude::{
             Event,
             HashMapMarketDepth,
             L3MarketDepth,
             OrdType,
             Order,
+            PositionSide,
             Side,
             Status,
             TimeInForce,
@@ -1182,13 +1574,15 @@ mod l3_tests {
                 exch_timestamp: 0,
                 local_timestamp: 0,
                 order_id: 1,
-                q: Box::new(()),
+                q: QueueState::None,
                 maker: false,
                 order_type: OrdType::Limit,
                 req: Status::None,
                 status: Status::None,
                 side: Side::Buy,
                 time_in_force: TimeInForce::GTC,
+                reduce_only: false,
+                position_side: PositionSide::Both,
             },
             &depth,
         )
@@ -1216,13 +1610,15 @@ mod l3_tests {
                 exch_timestamp: 0,
                 local_timestamp: 0,
                 order_id: 1,
-                q: Box::new(()),
+                q: QueueState::None,
                 maker: false,
                 order_type: OrdType::Limit,
                 req: Status::None,
                 status: Status::None,
                 side: Side::Sell,
                 time_in_force: TimeInForce::GTC,
+                reduce_only: false,
+                position_side: PositionSide::Both,
             },
             &depth,
         )
@@ -1272,13 +1668,15 @@ mod l3_tests {
                 exch_timestamp: 0,
                 local_timestamp: 0,
                 order_id: 1,
-                q: Box::new(()),
+                q: QueueState::None,
                 maker: false,
                 order_type: OrdType::Limit,
                 req: Status::None,
                 status: Status::None,
                 side: Side::Buy,
                 time_in_force: TimeInForce::GTC,
+                reduce_only: false,
+                position_side: PositionSide::Both,
             },
             &depth,
         )
@@ -1354,3 +1752,58 @@ mod l3_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{QueueModel, RiskAdverseQueueModel, TieredQueueModel};
+    use crate::{
+        backtest::models::{PowerProbQueueFunc3, ProbQueueModel},
+        depth::{HashMapMarketDepth, L2MarketDepth},
+        types::{OrdType, Order, Side, TimeInForce},
+    };
+
+    #[test]
+    fn test_tiered_queue_model() {
+        let mut depth = HashMapMarketDepth::new(0.1, 1.0);
+        depth.update_bid_depth(100.0, 10.0, 0);
+        depth.update_bid_depth(99.0, 10.0, 0);
+        depth.update_ask_depth(100.1, 10.0, 0);
+        let model = TieredQueueModel::new(
+            ProbQueueModel::new(PowerProbQueueFunc3::new(3.0)),
+            RiskAdverseQueueModel::new(),
+            2.0,
+        );
+
+        let mut near = Order::new(
+            1,
+            1000,
+            0.1,
+            1.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        let mut far = Order::new(
+            2,
+            990,
+            0.1,
+            1.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        model.new_order(&mut near, &depth);
+        model.new_order(&mut far, &depth);
+
+        // The probabilistic model advances the queue position by the cancels ahead, while the
+        // risk-averse model doesn't.
+        model.depth(&mut near, 10.0, 4.0, &depth);
+        model.depth(&mut far, 10.0, 4.0, &depth);
+        assert!(model.queue_position(&near).unwrap() < 10.0);
+        assert_eq!(model.queue_position(&far), Some(4.0));
+
+        model.trade(&mut far, 5.0, &depth);
+        assert_eq!(model.is_filled(&far, &depth), 1.0);
+        assert_eq!(model.is_filled(&near, &depth), 0.0);
+    }
+}
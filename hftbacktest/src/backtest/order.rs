@@ -1,6 +1,12 @@
 use std::{cell::UnsafeCell, collections::VecDeque, rc::Rc};
 
-use crate::types::Order;
+use crate::{
+    backtest::{
+        checkpoint::{CheckpointReader, CheckpointWriter},
+        BacktestError,
+    },
+    types::Order,
+};
 
 /// Provides a bus for transporting backtesting orders between the exchange and the local model
 /// based on the given timestamp.
@@ -65,4 +71,31 @@ impl OrderBus {
     pub fn pop_front(&mut self) -> Option<(Order, i64)> {
         unsafe { &mut *self.order_list.get() }.pop_front()
     }
+
+    /// Removes the last order and its timestamp and returns it if the predicate holds for the
+    /// order.
+    pub(crate) fn pop_back_if<F>(&mut self, f: F) -> Option<(Order, i64)>
+    where
+        F: FnOnce(&Order) -> bool,
+    {
+        let order_list = unsafe { &mut *self.order_list.get() };
+        if f(&order_list.back()?.0) {
+            order_list.pop_back()
+        } else {
+            None
+        }
+    }
+
+    /// Writes the orders in transit into the checkpoint.
+    pub fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        let order_list: Vec<_> = unsafe { &*self.order_list.get() }.iter().cloned().collect();
+        writer.write(&order_list)
+    }
+
+    /// Restores the orders in transit from the checkpoint.
+    pub fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        let order_list: Vec<(Order, i64)> = reader.read()?;
+        *unsafe { &mut *self.order_list.get() } = order_list.into();
+        Ok(())
+    }
 }
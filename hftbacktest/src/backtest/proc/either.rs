@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use crate::{
+    backtest::{
+        checkpoint::{CheckpointReader, CheckpointWriter},
+        proc::{LocalProcessor, MemoryUsage, Processor},
+        BacktestError,
+    },
+    bar::Bar,
+    depth::MarketDepth,
+    types::{
+        DepthUpdate,
+        Event,
+        EventStats,
+        Fill,
+        Liquidation,
+        OrdType,
+        Order,
+        OrderId,
+        Portfolio,
+        PositionSide,
+        Side,
+        StateValues,
+        TimeInForce,
+    },
+};
+
+/// Either of two processors, which lets
+/// [`MultiAssetSingleExchangeBacktest`](crate::backtest::MultiAssetSingleExchangeBacktest) combine
+/// assets with different processor types while keeping the static dispatch. It can be nested to
+/// combine more than two types.
+///
+/// ```
+/// use hftbacktest::{
+///     backtest::{
+///         assettype::LinearAsset,
+///         models::{CommonFees, ConstantLatency, RiskAdverseQueueModel, TradingValueFeeModel},
+///         proc::{Either, NoPartialFillExchange, PartialFillExchange},
+///     },
+///     depth::HashMapMarketDepth,
+/// };
+///
+/// type MD = HashMapMarketDepth;
+/// type QM = RiskAdverseQueueModel<MD>;
+/// type FM = TradingValueFeeModel<CommonFees>;
+///
+/// // The exchange processor type of the assets, each of which fills either fully or partially.
+/// type Exchange = Either<
+///     NoPartialFillExchange<LinearAsset, ConstantLatency, QM, MD, FM>,
+///     PartialFillExchange<LinearAsset, ConstantLatency, QM, MD, FM>,
+/// >;
+/// ```
+#[derive(Clone, Debug)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $p:ident => $body:expr) => {
+        match $self {
+            Either::Left($p) => $body,
+            Either::Right($p) => $body,
+        }
+    };
+}
+
+impl<L, R> Processor for Either<L, R>
+where
+    L: Processor,
+    R: Processor,
+{
+    fn initialize_data(&mut self) -> Result<i64, BacktestError> {
+        dispatch!(self, p => p.initialize_data())
+    }
+
+    fn process_data(&mut self) -> Result<(i64, i64), BacktestError> {
+        dispatch!(self, p => p.process_data())
+    }
+
+    fn process_recv_order(
+        &mut self,
+        timestamp: i64,
+        wait_resp_order_id: Option<OrderId>,
+    ) -> Result<bool, BacktestError> {
+        dispatch!(self, p => p.process_recv_order(timestamp, wait_resp_order_id))
+    }
+
+    fn earliest_recv_order_timestamp(&self) -> i64 {
+        dispatch!(self, p => p.earliest_recv_order_timestamp())
+    }
+
+    fn earliest_send_order_timestamp(&self) -> i64 {
+        dispatch!(self, p => p.earliest_send_order_timestamp())
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        dispatch!(self, p => p.memory_usage())
+    }
+
+    fn seed(&mut self, seed: u64) {
+        dispatch!(self, p => p.seed(seed))
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        dispatch!(self, p => p.save_checkpoint(writer))
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        dispatch!(self, p => p.load_checkpoint(reader))
+    }
+}
+
+impl<MD, L, R> LocalProcessor<MD> for Either<L, R>
+where
+    MD: MarketDepth,
+    L: LocalProcessor<MD>,
+    R: LocalProcessor<MD>,
+{
+    fn submit_order(
+        &mut self,
+        order_id: OrderId,
+        side: Side,
+        price: f64,
+        qty: f64,
+        order_type: OrdType,
+        time_in_force: TimeInForce,
+        reduce_only: bool,
+        position_side: PositionSide,
+        current_timestamp: i64,
+    ) -> Result<(), BacktestError> {
+        dispatch!(self, p => p.submit_order(
+            order_id,
+            side,
+            price,
+            qty,
+            order_type,
+            time_in_force,
+            reduce_only,
+            position_side,
+            current_timestamp,
+        ))
+    }
+
+    fn cancel(&mut self, order_id: OrderId, current_timestamp: i64) -> Result<(), BacktestError> {
+        dispatch!(self, p => p.cancel(order_id, current_timestamp))
+    }
+
+    fn clear_inactive_orders(&mut self) {
+        dispatch!(self, p => p.clear_inactive_orders())
+    }
+
+    fn position(&self) -> f64 {
+        dispatch!(self, p => p.position())
+    }
+
+    fn state_values(&self) -> &StateValues {
+        dispatch!(self, p => p.state_values())
+    }
+
+    fn portfolio(&self) -> Portfolio {
+        dispatch!(self, p => p.portfolio())
+    }
+
+    fn depth(&self) -> &MD {
+        dispatch!(self, p => p.depth())
+    }
+
+    fn orders(&self) -> &HashMap<OrderId, Order> {
+        dispatch!(self, p => p.orders())
+    }
+
+    fn last_trades(&self) -> &[Event] {
+        dispatch!(self, p => p.last_trades())
+    }
+
+    fn clear_last_trades(&mut self) {
+        dispatch!(self, p => p.clear_last_trades())
+    }
+
+    fn last_depth_updates(&self) -> &[DepthUpdate] {
+        dispatch!(self, p => p.last_depth_updates())
+    }
+
+    fn clear_last_depth_updates(&mut self) {
+        dispatch!(self, p => p.clear_last_depth_updates())
+    }
+
+    fn bars(&self, interval: i64) -> &[Bar] {
+        dispatch!(self, p => p.bars(interval))
+    }
+
+    fn liquidations(&self) -> &[Liquidation] {
+        dispatch!(self, p => p.liquidations())
+    }
+
+    fn last_fills(&self) -> &[Fill] {
+        dispatch!(self, p => p.last_fills())
+    }
+
+    fn clear_last_fills(&mut self) {
+        dispatch!(self, p => p.clear_last_fills())
+    }
+
+    fn last_liquidation_prints(&self) -> &[Event] {
+        dispatch!(self, p => p.last_liquidation_prints())
+    }
+
+    fn clear_last_liquidation_prints(&mut self) {
+        dispatch!(self, p => p.clear_last_liquidation_prints())
+    }
+
+    fn open_interest(&self) -> Option<f64> {
+        dispatch!(self, p => p.open_interest())
+    }
+
+    fn feed_latency(&self) -> Option<(i64, i64)> {
+        dispatch!(self, p => p.feed_latency())
+    }
+
+    fn order_latency(&self) -> Option<(i64, i64, i64)> {
+        dispatch!(self, p => p.order_latency())
+    }
+
+    fn take_event_stats(&mut self) -> EventStats {
+        dispatch!(self, p => p.take_event_stats())
+    }
+}
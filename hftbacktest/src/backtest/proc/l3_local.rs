@@ -1,6 +1,6 @@
 use std::{
     collections::{hash_map::Entry, HashMap},
-    mem,
+    mem::{self, size_of},
 };
 
 use crate::{
@@ -9,16 +9,23 @@ use crate::{
         data::{Data, Reader},
         models::{FeeModel, LatencyModel},
         order::OrderBus,
-        proc::{LocalProcessor, Processor},
+        proc::{is_execution, LocalProcessor, MemoryUsage, Processor},
         state::State,
         BacktestError,
     },
+    bar::{Bar, BarBuilder},
     depth::L3MarketDepth,
     types::{
+        DepthUpdate,
         Event,
+        EventStats,
+        Fill,
+        Liquidation,
         OrdType,
         Order,
         OrderId,
+        Portfolio,
+        PositionSide,
         Side,
         StateValues,
         Status,
@@ -30,7 +37,9 @@ use crate::{
         LOCAL_CANCEL_ORDER_EVENT,
         LOCAL_DEPTH_CLEAR_EVENT,
         LOCAL_EVENT,
+        LOCAL_LIQUIDATION_EVENT,
         LOCAL_MODIFY_ORDER_EVENT,
+        LOCAL_OPEN_INTEREST_EVENT,
         LOCAL_TRADE_EVENT,
     },
 };
@@ -53,8 +62,14 @@ where
     state: State<AT, FM>,
     order_latency: LM,
     trades: Vec<Event>,
+    bars: Vec<BarBuilder>,
+    liquidations: Vec<Liquidation>,
+    fills: Vec<Fill>,
+    liquidation_prints: Vec<Event>,
+    open_interest: Option<f64>,
     last_feed_latency: Option<(i64, i64)>,
     last_order_latency: Option<(i64, i64, i64)>,
+    event_stats: EventStats,
 }
 
 impl<AT, LM, MD, FM> L3Local<AT, LM, MD, FM>
@@ -85,34 +100,85 @@ where
             state,
             order_latency,
             trades: Vec::with_capacity(trade_len),
+            bars: Vec::new(),
+            liquidations: Vec::new(),
+            fills: Vec::new(),
+            liquidation_prints: Vec::new(),
+            open_interest: None,
             last_feed_latency: None,
             last_order_latency: None,
+            event_stats: Default::default(),
         }
     }
 
+    /// Sets the initial capacity of the vector storing the last fills. The default value is `0`,
+    /// indicating that no last fills are stored.
+    pub fn last_fills_capacity(self, capacity: usize) -> Self {
+        Self {
+            fills: Vec::with_capacity(capacity),
+            ..self
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last liquidation prints. The default
+    /// value is `0`, indicating that no last liquidation prints are stored.
+    pub fn last_liquidation_prints_capacity(self, capacity: usize) -> Self {
+        Self {
+            liquidation_prints: Vec::with_capacity(capacity),
+            ..self
+        }
+    }
+
+    /// Adds a [`BarBuilder`] that aggregates the market trades into bars.
+    pub fn bar_builder(mut self, builder: BarBuilder) -> Self {
+        self.bars.push(builder);
+        self
+    }
+
     fn process_recv_order_(&mut self, order: Order) -> Result<(), BacktestError> {
+        if is_execution(&order) {
+            let fee = self.state.apply_fill(&order);
+            if self.fills.capacity() > 0 {
+                let trade_id = self.state.values().num_trades.to_string();
+                self.fills.push(Fill::from_order(&order, trade_id, fee));
+            }
+        }
         if order.status == Status::Filled {
-            self.state.apply_fill(&order);
+            if let Some(liquidation) = order.q.as_any().downcast_ref::<Liquidation>() {
+                self.state.apply_fee(liquidation.fee);
+                self.liquidations.push(liquidation.clone());
+            }
         }
+        self.event_stats.order_updates += 1;
         // Applies the received order response to the local orders.
         match self.orders.entry(order.order_id) {
             Entry::Occupied(mut entry) => {
                 let local_order = entry.get_mut();
-                if order.req == Status::Rejected {
+                if order.req == Status::Rejected || order.req == Status::RateLimited {
                     if order.local_timestamp == local_order.local_timestamp {
                         if local_order.req == Status::New {
                             local_order.req = Status::None;
-                            local_order.status = Status::Expired;
+                            local_order.status = if order.req == Status::RateLimited {
+                                Status::RateLimited
+                            } else {
+                                Status::Expired
+                            };
                         } else {
                             local_order.req = Status::None;
                         }
+                    } else {
+                        // The rejection is for a previous request.
+                        self.event_stats.dropped += 1;
                     }
-                } else {
+                } else if local_order.status.can_transition_to(order.status) {
                     local_order.update(&order);
+                } else {
+                    // The order can't be in the status after the current status.
+                    self.event_stats.dropped += 1;
                 }
             }
             Entry::Vacant(entry) => {
-                if order.req != Status::Rejected {
+                if order.req != Status::Rejected && order.req != Status::RateLimited {
                     entry.insert(order);
                 }
             }
@@ -137,6 +203,8 @@ where
         qty: f64,
         order_type: OrdType,
         time_in_force: TimeInForce,
+        reduce_only: bool,
+        position_side: PositionSide,
         current_timestamp: i64,
     ) -> Result<(), BacktestError> {
         if self.orders.contains_key(&order_id) {
@@ -154,6 +222,8 @@ where
             time_in_force,
         );
         order.req = Status::New;
+        order.reduce_only = reduce_only;
+        order.position_side = position_side;
         order.local_timestamp = current_timestamp;
         self.orders.insert(order.order_id, order.clone());
 
@@ -182,6 +252,9 @@ where
         if order.req != Status::None {
             return Err(BacktestError::OrderRequestInProcess);
         }
+        if order.status.is_final() {
+            return Err(BacktestError::InvalidOrderStatus);
+        }
 
         order.req = Status::Canceled;
         let order_entry_latency = self.order_latency.entry(current_timestamp, order);
@@ -217,6 +290,10 @@ where
         self.state.values()
     }
 
+    fn portfolio(&self) -> Portfolio {
+        self.state.portfolio(&self.depth)
+    }
+
     fn depth(&self) -> &MD {
         &self.depth
     }
@@ -233,6 +310,45 @@ where
         self.trades.clear();
     }
 
+    fn last_depth_updates(&self) -> &[DepthUpdate] {
+        // The changes of the price levels are not tracked by the L3 market depth.
+        &[]
+    }
+
+    fn bars(&self, interval: i64) -> &[Bar] {
+        self.bars
+            .iter()
+            .find(|builder| builder.interval() == interval)
+            .map(|builder| builder.bars())
+            .unwrap_or(&[])
+    }
+
+    fn clear_last_depth_updates(&mut self) {}
+
+    fn liquidations(&self) -> &[Liquidation] {
+        &self.liquidations
+    }
+
+    fn last_fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    fn clear_last_fills(&mut self) {
+        self.fills.clear();
+    }
+
+    fn last_liquidation_prints(&self) -> &[Event] {
+        self.liquidation_prints.as_slice()
+    }
+
+    fn clear_last_liquidation_prints(&mut self) {
+        self.liquidation_prints.clear();
+    }
+
+    fn open_interest(&self) -> Option<f64> {
+        self.open_interest
+    }
+
     fn feed_latency(&self) -> Option<(i64, i64)> {
         self.last_feed_latency
     }
@@ -240,6 +356,10 @@ where
     fn order_latency(&self) -> Option<(i64, i64, i64)> {
         self.last_order_latency
     }
+
+    fn take_event_stats(&mut self) -> EventStats {
+        mem::take(&mut self.event_stats)
+    }
 }
 
 impl<AT, LM, MD, FM> Processor for L3Local<AT, LM, MD, FM>
@@ -266,31 +386,54 @@ where
         let ev = &self.data[self.row_num];
         // Processes a depth event
         if ev.is(LOCAL_BID_DEPTH_CLEAR_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth.clear_orders(Side::Buy);
         } else if ev.is(LOCAL_ASK_DEPTH_CLEAR_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth.clear_orders(Side::Sell);
         } else if ev.is(LOCAL_DEPTH_CLEAR_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth.clear_orders(Side::None);
         } else if ev.is(LOCAL_BID_ADD_ORDER_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth
                 .add_buy_order(ev.order_id, ev.px, ev.qty, ev.local_ts)?;
         } else if ev.is(LOCAL_ASK_ADD_ORDER_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth
                 .add_sell_order(ev.order_id, ev.px, ev.qty, ev.local_ts)?;
         } else if ev.is(LOCAL_MODIFY_ORDER_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth
                 .modify_order(ev.order_id, ev.px, ev.qty, ev.local_ts)?;
         } else if ev.is(LOCAL_CANCEL_ORDER_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth.delete_order(ev.order_id, ev.local_ts)?;
         }
         // Processes a trade event
-        else if ev.is(LOCAL_TRADE_EVENT) && self.trades.capacity() > 0 {
-            self.trades.push(ev.clone());
+        else if ev.is(LOCAL_TRADE_EVENT) {
+            self.event_stats.trades += 1;
+            if self.trades.capacity() > 0 {
+                self.trades.push(ev.clone());
+            }
+            for builder in self.bars.iter_mut() {
+                builder.update(ev);
+            }
+        }
+        // Processes a market liquidation or an open interest update
+        else if ev.is(LOCAL_LIQUIDATION_EVENT) {
+            if self.liquidation_prints.capacity() > 0 {
+                self.liquidation_prints.push(ev.clone());
+            }
+        } else if ev.is(LOCAL_OPEN_INTEREST_EVENT) {
+            self.open_interest = Some(ev.qty);
         }
 
         // Stores the current feed latency
         self.last_feed_latency = Some((ev.exch_ts, ev.local_ts));
 
+        self.state.settle_fee(ev.local_ts);
+
         // Checks
         let mut next_ts = 0;
         for rn in (self.row_num + 1)..self.data.len() {
@@ -355,4 +498,16 @@ where
     fn earliest_send_order_timestamp(&self) -> i64 {
         self.orders_to.earliest_timestamp().unwrap_or(i64::MAX)
     }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            data: self.reader.peak_memory_usage(),
+            depth: self.depth.memory_usage(),
+            orders: self.orders.capacity() * size_of::<(OrderId, Order)>(),
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.order_latency.seed(seed);
+    }
 }
@@ -6,7 +6,7 @@ use crate::{
         data::{Data, Reader},
         models::{FeeModel, L3QueueModel, LatencyModel},
         order::OrderBus,
-        proc::Processor,
+        proc::{apply_reduce_only, MemoryUsage, PostOnlyBehavior, Processor},
         state::State,
         BacktestError,
     },
@@ -31,6 +31,7 @@ use crate::{
         EXCH_MODIFY_ORDER_EVENT,
         SELL_EVENT,
     },
+    utils::SplitMix64,
 };
 
 /// The exchange model without partial fills.
@@ -76,6 +77,7 @@ where
     state: State<AT, FM>,
     order_latency: LM,
     queue_model: QM,
+    post_only: PostOnlyBehavior,
 }
 
 impl<AT, LM, QM, MD, FM> L3NoPartialFillExchange<AT, LM, QM, MD, FM>
@@ -88,6 +90,7 @@ where
     BacktestError: From<<MD as L3MarketDepth>::Error>,
 {
     /// Constructs an instance of `NoPartialFillExchange`.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         reader: Reader<Event>,
         depth: MD,
@@ -96,6 +99,7 @@ where
         queue_model: QM,
         orders_to: OrderBus,
         orders_from: OrderBus,
+        post_only: PostOnlyBehavior,
     ) -> Self {
         Self {
             reader,
@@ -107,6 +111,7 @@ where
             state,
             order_latency,
             queue_model,
+            post_only,
         }
     }
 
@@ -115,6 +120,15 @@ where
         mut order: Order,
         recv_timestamp: i64,
     ) -> Result<(), BacktestError> {
+        if order.req == Status::New && !apply_reduce_only(&mut order, &self.state.state_values) {
+            order.req = Status::Rejected;
+            order.exch_timestamp = recv_timestamp;
+            let local_recv_timestamp =
+                recv_timestamp + self.order_latency.response(recv_timestamp, &order);
+            self.orders_to.append(order, local_recv_timestamp);
+            return Ok(());
+        }
+
         // Processes a new order.
         if order.req == Status::New {
             order.req = Status::None;
@@ -212,6 +226,9 @@ where
             return Err(BacktestError::OrderIdExist);
         }
 
+        // Depending on the exchange, a crossing post-only order is repriced instead of rejected.
+        self.post_only.reprice(&mut order, &self.depth);
+
         if order.side == Side::Buy {
             match order.order_type {
                 OrdType::Limit => {
@@ -482,6 +499,8 @@ where
             }
         }
 
+        self.state.settle_fee(self.data[row_num].exch_ts);
+
         // Checks
         let mut next_ts = 0;
         for rn in (self.row_num + 1)..self.data.len() {
@@ -529,4 +548,18 @@ where
     fn earliest_send_order_timestamp(&self) -> i64 {
         self.orders_to.earliest_timestamp().unwrap_or(i64::MAX)
     }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            data: self.reader.peak_memory_usage(),
+            depth: self.depth.memory_usage(),
+            // Backtest orders are held by the queue model and are not accounted for.
+            orders: 0,
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.order_latency.seed(seed);
+        self.queue_model.seed(SplitMix64::new(seed).next_u64());
+    }
 }
@@ -1,24 +1,41 @@
 use std::{
     collections::{hash_map::Entry, HashMap},
-    mem,
+    mem::{self, size_of},
 };
 
 use crate::{
     backtest::{
         assettype::AssetType,
+        checkpoint::{
+            load_feed,
+            load_orders,
+            save_feed,
+            save_orders,
+            CheckpointReader,
+            CheckpointWriter,
+        },
         data::{Data, Reader},
         models::{FeeModel, LatencyModel},
         order::OrderBus,
-        proc::{LocalProcessor, Processor},
+        proc::{instrument_change, is_execution, LocalProcessor, MemoryUsage, Processor},
+        rollover::DayRollover,
         state::State,
+        throttle::Throttle,
         BacktestError,
     },
-    depth::{L2MarketDepth, MarketDepth},
+    bar::{Bar, BarBuilder},
+    depth::{ApplySnapshot, L2MarketDepth, MarketDepth},
     types::{
+        DepthUpdate,
         Event,
+        EventStats,
+        Fill,
+        Liquidation,
         OrdType,
         Order,
         OrderId,
+        Portfolio,
+        PositionSide,
         Side,
         StateValues,
         Status,
@@ -31,6 +48,9 @@ use crate::{
         LOCAL_BID_DEPTH_SNAPSHOT_EVENT,
         LOCAL_DEPTH_CLEAR_EVENT,
         LOCAL_EVENT,
+        LOCAL_INSTRUMENT_EVENT,
+        LOCAL_LIQUIDATION_EVENT,
+        LOCAL_OPEN_INTEREST_EVENT,
         LOCAL_TRADE_EVENT,
     },
 };
@@ -53,8 +73,17 @@ where
     state: State<AT, FM>,
     order_latency: LM,
     trades: Vec<Event>,
+    depth_updates: Vec<DepthUpdate>,
+    bars: Vec<BarBuilder>,
+    liquidations: Vec<Liquidation>,
+    fills: Vec<Fill>,
+    liquidation_prints: Vec<Event>,
+    open_interest: Option<f64>,
+    throttle: Option<Throttle>,
+    rollover: Option<DayRollover>,
     last_feed_latency: Option<(i64, i64)>,
     last_order_latency: Option<(i64, i64, i64)>,
+    event_stats: EventStats,
 }
 
 impl<AT, LM, MD, FM> Local<AT, LM, MD, FM>
@@ -85,34 +114,125 @@ where
             state,
             order_latency,
             trades: Vec::with_capacity(last_trades_cap),
+            depth_updates: Vec::new(),
+            bars: Vec::new(),
+            liquidations: Vec::new(),
+            fills: Vec::new(),
+            liquidation_prints: Vec::new(),
+            open_interest: None,
+            throttle: None,
+            rollover: None,
             last_feed_latency: None,
             last_order_latency: None,
+            event_stats: Default::default(),
         }
     }
 
-    fn process_recv_order_(&mut self, order: Order) -> Result<(), BacktestError> {
+    /// Sets the initial capacity of the vector storing the last depth updates. The default value
+    /// is `0`, indicating that no last depth updates are stored.
+    pub fn last_depth_updates_capacity(self, capacity: usize) -> Self {
+        Self {
+            depth_updates: Vec::with_capacity(capacity),
+            ..self
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last fills. The default value is `0`,
+    /// indicating that no last fills are stored.
+    pub fn last_fills_capacity(self, capacity: usize) -> Self {
+        Self {
+            fills: Vec::with_capacity(capacity),
+            ..self
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last liquidation prints. The default
+    /// value is `0`, indicating that no last liquidation prints are stored.
+    pub fn last_liquidation_prints_capacity(self, capacity: usize) -> Self {
+        Self {
+            liquidation_prints: Vec::with_capacity(capacity),
+            ..self
+        }
+    }
+
+    /// Adds a [`BarBuilder`] that aggregates the market trades into bars.
+    pub fn bar_builder(mut self, builder: BarBuilder) -> Self {
+        self.bars.push(builder);
+        self
+    }
+
+    /// Sets the [`Throttle`] shared with the exchange, through which the penalty fees charged by
+    /// the exchange are applied.
+    pub fn throttle(self, throttle: Throttle) -> Self {
+        Self {
+            throttle: Some(throttle),
+            ..self
+        }
+    }
+
+    /// Sets the [`DayRollover`], which clears the market depth at the boundaries between the
+    /// chained daily files if it resets the depth.
+    pub fn rollover(self, rollover: DayRollover) -> Self {
+        Self {
+            rollover: Some(rollover),
+            ..self
+        }
+    }
+
+    fn process_recv_order_(&mut self, mut order: Order) -> Result<(), BacktestError> {
+        // The response sent across the tick size change is normalized into the local tick size,
+        // into which the local orders are re-normalized when the change arrives.
+        order.normalize_tick(self.depth.tick_size());
+        if let Some(penalty) = self
+            .throttle
+            .as_ref()
+            .and_then(|throttle| throttle.take_penalty(&order))
+        {
+            self.state.apply_fee(penalty);
+        }
+        if is_execution(&order) {
+            let fee = self.state.apply_fill(&order);
+            if self.fills.capacity() > 0 {
+                let trade_id = self.state.values().num_trades.to_string();
+                self.fills.push(Fill::from_order(&order, trade_id, fee));
+            }
+        }
         if order.status == Status::Filled {
-            self.state.apply_fill(&order);
+            if let Some(liquidation) = order.q.as_any().downcast_ref::<Liquidation>() {
+                self.state.apply_fee(liquidation.fee);
+                self.liquidations.push(liquidation.clone());
+            }
         }
+        self.event_stats.order_updates += 1;
         // Applies the received order response to the local orders.
         match self.orders.entry(order.order_id) {
             Entry::Occupied(mut entry) => {
                 let local_order = entry.get_mut();
-                if order.req == Status::Rejected {
+                if order.req == Status::Rejected || order.req == Status::RateLimited {
                     if order.local_timestamp == local_order.local_timestamp {
                         if local_order.req == Status::New {
                             local_order.req = Status::None;
-                            local_order.status = Status::Expired;
+                            local_order.status = if order.req == Status::RateLimited {
+                                Status::RateLimited
+                            } else {
+                                Status::Expired
+                            };
                         } else {
                             local_order.req = Status::None;
                         }
+                    } else {
+                        // The rejection is for a previous request.
+                        self.event_stats.dropped += 1;
                     }
-                } else {
+                } else if local_order.status.can_transition_to(order.status) {
                     local_order.update(&order);
+                } else {
+                    // The order can't be in the status after the current status.
+                    self.event_stats.dropped += 1;
                 }
             }
             Entry::Vacant(entry) => {
-                if order.req != Status::Rejected {
+                if order.req != Status::Rejected && order.req != Status::RateLimited {
                     entry.insert(order);
                 }
             }
@@ -125,7 +245,7 @@ impl<AT, LM, MD, FM> LocalProcessor<MD> for Local<AT, LM, MD, FM>
 where
     AT: AssetType,
     LM: LatencyModel,
-    MD: MarketDepth + L2MarketDepth,
+    MD: MarketDepth + L2MarketDepth + ApplySnapshot,
     FM: FeeModel,
 {
     fn submit_order(
@@ -136,6 +256,8 @@ where
         qty: f64,
         order_type: OrdType,
         time_in_force: TimeInForce,
+        reduce_only: bool,
+        position_side: PositionSide,
         current_timestamp: i64,
     ) -> Result<(), BacktestError> {
         if self.orders.contains_key(&order_id) {
@@ -153,6 +275,8 @@ where
             time_in_force,
         );
         order.req = Status::New;
+        order.reduce_only = reduce_only;
+        order.position_side = position_side;
         order.local_timestamp = current_timestamp;
         self.orders.insert(order.order_id, order.clone());
 
@@ -181,6 +305,9 @@ where
         if order.req != Status::None {
             return Err(BacktestError::OrderRequestInProcess);
         }
+        if order.status.is_final() {
+            return Err(BacktestError::InvalidOrderStatus);
+        }
 
         order.req = Status::Canceled;
         let order_entry_latency = self.order_latency.entry(current_timestamp, order);
@@ -216,6 +343,10 @@ where
         self.state.values()
     }
 
+    fn portfolio(&self) -> Portfolio {
+        self.state.portfolio(&self.depth)
+    }
+
     fn depth(&self) -> &MD {
         &self.depth
     }
@@ -232,6 +363,46 @@ where
         self.trades.clear();
     }
 
+    fn last_depth_updates(&self) -> &[DepthUpdate] {
+        self.depth_updates.as_slice()
+    }
+
+    fn bars(&self, interval: i64) -> &[Bar] {
+        self.bars
+            .iter()
+            .find(|builder| builder.interval() == interval)
+            .map(|builder| builder.bars())
+            .unwrap_or(&[])
+    }
+
+    fn clear_last_depth_updates(&mut self) {
+        self.depth_updates.clear();
+    }
+
+    fn liquidations(&self) -> &[Liquidation] {
+        &self.liquidations
+    }
+
+    fn last_fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    fn clear_last_fills(&mut self) {
+        self.fills.clear();
+    }
+
+    fn last_liquidation_prints(&self) -> &[Event] {
+        self.liquidation_prints.as_slice()
+    }
+
+    fn clear_last_liquidation_prints(&mut self) {
+        self.liquidation_prints.clear();
+    }
+
+    fn open_interest(&self) -> Option<f64> {
+        self.open_interest
+    }
+
     fn feed_latency(&self) -> Option<(i64, i64)> {
         self.last_feed_latency
     }
@@ -239,13 +410,17 @@ where
     fn order_latency(&self) -> Option<(i64, i64, i64)> {
         self.last_order_latency
     }
+
+    fn take_event_stats(&mut self) -> EventStats {
+        mem::take(&mut self.event_stats)
+    }
 }
 
 impl<AT, LM, MD, FM> Processor for Local<AT, LM, MD, FM>
 where
     AT: AssetType,
     LM: LatencyModel,
-    MD: MarketDepth + L2MarketDepth,
+    MD: MarketDepth + L2MarketDepth + ApplySnapshot,
     FM: FeeModel,
 {
     fn initialize_data(&mut self) -> Result<i64, BacktestError> {
@@ -264,24 +439,68 @@ where
         let ev = &self.data[self.row_num];
         // Processes a depth event
         if ev.is(LOCAL_BID_DEPTH_CLEAR_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth.clear_depth(Side::Buy, ev.px);
         } else if ev.is(LOCAL_ASK_DEPTH_CLEAR_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth.clear_depth(Side::Sell, ev.px);
         } else if ev.is(LOCAL_DEPTH_CLEAR_EVENT) {
+            self.event_stats.depth_updates += 1;
             self.depth.clear_depth(Side::None, 0.0);
-        } else if ev.is(LOCAL_BID_DEPTH_EVENT) || ev.is(LOCAL_BID_DEPTH_SNAPSHOT_EVENT) {
-            self.depth.update_bid_depth(ev.px, ev.qty, ev.local_ts);
-        } else if ev.is(LOCAL_ASK_DEPTH_EVENT) || ev.is(LOCAL_ASK_DEPTH_SNAPSHOT_EVENT) {
-            self.depth.update_ask_depth(ev.px, ev.qty, ev.local_ts);
+        } else if ev.is(LOCAL_BID_DEPTH_EVENT) {
+            self.event_stats.depth_updates += 1;
+            let update = self.depth.update_bid_depth(ev.px, ev.qty, ev.local_ts);
+            DepthUpdate::record(&mut self.depth_updates, Side::Buy, update);
+        } else if ev.is(LOCAL_ASK_DEPTH_EVENT) {
+            self.event_stats.depth_updates += 1;
+            let update = self.depth.update_ask_depth(ev.px, ev.qty, ev.local_ts);
+            DepthUpdate::record(&mut self.depth_updates, Side::Sell, update);
+        } else if ev.is(LOCAL_BID_DEPTH_SNAPSHOT_EVENT) {
+            self.event_stats.depth_updates += 1;
+            let update = self.depth.update_bid_snapshot(ev.px, ev.qty, ev.local_ts);
+            DepthUpdate::record(&mut self.depth_updates, Side::Buy, update);
+        } else if ev.is(LOCAL_ASK_DEPTH_SNAPSHOT_EVENT) {
+            self.event_stats.depth_updates += 1;
+            let update = self.depth.update_ask_snapshot(ev.px, ev.qty, ev.local_ts);
+            DepthUpdate::record(&mut self.depth_updates, Side::Sell, update);
+        }
+        // Processes an instrument parameter change
+        else if ev.is(LOCAL_INSTRUMENT_EVENT) {
+            if let Some((tick_size, lot_size)) = instrument_change(ev, &self.depth) {
+                for order in self.orders.values_mut() {
+                    order.rebucket(tick_size);
+                }
+                self.depth.rebucket(tick_size, lot_size);
+            }
+            if ev.fval > 0.0 {
+                self.state.asset_type.set_contract_size(ev.fval);
+            }
         }
         // Processes a trade event
-        else if ev.is(LOCAL_TRADE_EVENT) && self.trades.capacity() > 0 {
-            self.trades.push(ev.clone());
+        else if ev.is(LOCAL_TRADE_EVENT) {
+            self.event_stats.trades += 1;
+            if self.trades.capacity() > 0 {
+                self.trades.push(ev.clone());
+            }
+            for builder in self.bars.iter_mut() {
+                builder.update(ev);
+            }
+        }
+        // Processes a market liquidation or an open interest update
+        else if ev.is(LOCAL_LIQUIDATION_EVENT) {
+            if self.liquidation_prints.capacity() > 0 {
+                self.liquidation_prints.push(ev.clone());
+            }
+        } else if ev.is(LOCAL_OPEN_INTEREST_EVENT) {
+            self.open_interest = Some(ev.qty);
         }
 
         // Stores the current feed latency
         self.last_feed_latency = Some((ev.exch_ts, ev.local_ts));
 
+        self.state.accrue_borrow_fee(ev.local_ts, &self.depth);
+        self.state.settle_fee(ev.local_ts);
+
         // Checks
         let mut next_ts = 0;
         for rn in (self.row_num + 1)..self.data.len() {
@@ -294,6 +513,12 @@ where
 
         if next_ts <= 0 {
             let next_data = self.reader.next_data()?;
+            if self
+                .rollover
+                .is_some_and(|rollover| rollover.resets_depth())
+            {
+                self.depth.clear_depth(Side::None, 0.0);
+            }
             let next_row = &next_data[0];
             next_ts = next_row.local_ts;
             let data = mem::replace(&mut self.data, next_data);
@@ -346,4 +571,57 @@ where
     fn earliest_send_order_timestamp(&self) -> i64 {
         self.orders_to.earliest_timestamp().unwrap_or(i64::MAX)
     }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            data: self.reader.peak_memory_usage(),
+            depth: self.depth.memory_usage(),
+            orders: self.orders.capacity() * size_of::<(OrderId, Order)>(),
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.order_latency.seed(seed);
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        save_feed(writer, &self.reader, self.row_num, &self.depth)?;
+        save_orders(writer, self.orders.values(), |_, _| Ok(()))?;
+        // The order buses are shared with the exchange, so they are saved only by the local.
+        self.orders_to.save_checkpoint(writer)?;
+        self.orders_from.save_checkpoint(writer)?;
+        self.state.save_checkpoint(writer)?;
+        writer.write(&self.trades)?;
+        writer.write(&self.bars)?;
+        writer.write(&self.liquidations)?;
+        writer.write(&self.fills)?;
+        writer.write(&self.liquidation_prints)?;
+        writer.write(&self.open_interest)?;
+        writer.write(&self.event_stats)?;
+        writer.write(&(self.last_feed_latency, self.last_order_latency))?;
+        self.order_latency.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        (self.data, self.row_num) = load_feed(reader, &mut self.reader, &mut self.depth)?;
+        self.orders = load_orders(reader, |_, _| Ok(()))?
+            .into_iter()
+            .map(|order| (order.order_id, order))
+            .collect();
+        self.orders_to.load_checkpoint(reader)?;
+        self.orders_from.load_checkpoint(reader)?;
+        self.state.load_checkpoint(reader)?;
+        self.trades.clear();
+        self.trades.extend(reader.read::<Vec<Event>>()?);
+        self.bars = reader.read()?;
+        self.liquidations = reader.read()?;
+        self.fills.clear();
+        self.fills.extend(reader.read::<Vec<Fill>>()?);
+        self.liquidation_prints.clear();
+        self.liquidation_prints.extend(reader.read::<Vec<Event>>()?);
+        self.open_interest = reader.read()?;
+        self.event_stats = reader.read()?;
+        (self.last_feed_latency, self.last_order_latency) = reader.read()?;
+        self.order_latency.load_checkpoint(reader)
+    }
 }
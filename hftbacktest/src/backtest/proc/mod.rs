@@ -1,9 +1,13 @@
+mod either;
 mod local;
 mod nopartialfillexchange;
 mod partialfillexchange;
 
 use std::collections::HashMap;
 
+use tracing::info;
+
+pub use either::Either;
 pub use local::Local;
 pub use nopartialfillexchange::NoPartialFillExchange;
 pub use partialfillexchange::PartialFillExchange;
@@ -16,11 +20,159 @@ pub use l3_local::L3Local;
 pub use l3_nopartialfillexchange::L3NoPartialFillExchange;
 
 use crate::{
-    backtest::BacktestError,
+    backtest::{
+        checkpoint::{checkpoint_error, CheckpointReader, CheckpointWriter},
+        BacktestError,
+    },
+    bar::Bar,
     depth::MarketDepth,
-    prelude::{Event, OrdType, Order, OrderId, Side, StateValues, TimeInForce},
+    prelude::{
+        DepthUpdate,
+        Event,
+        EventStats,
+        Fill,
+        Liquidation,
+        OrdType,
+        Order,
+        OrderId,
+        Portfolio,
+        PositionSide,
+        Side,
+        StateValues,
+        Status,
+        TimeInForce,
+    },
 };
 
+/// Returns `true` if the order response reports an execution, whose `exec_qty` is the quantity
+/// executed by this response only. A partially filled order is executed by several responses, and
+/// a rejected request echoes the order including its last execution, which isn't a new one.
+pub(crate) fn is_execution(order: &Order) -> bool {
+    matches!(order.status, Status::Filled | Status::PartiallyFilled)
+        && order.req != Status::Rejected
+        && order.req != Status::RateLimited
+        && order.exec_qty > 0.0
+}
+
+/// Returns the tick size and the lot size that the instrument event changes the market depth
+/// into, or `None` if it changes neither.
+pub(crate) fn instrument_change<MD: MarketDepth>(ev: &Event, depth: &MD) -> Option<(f64, f64)> {
+    if ev.px <= 0.0 && ev.qty <= 0.0 {
+        return None;
+    }
+    let tick_size = if ev.px > 0.0 {
+        ev.px
+    } else {
+        depth.tick_size()
+    };
+    let lot_size = if ev.qty > 0.0 {
+        ev.qty
+    } else {
+        depth.lot_size()
+    };
+    Some((tick_size, lot_size))
+}
+
+/// Shrinks the order that may only reduce the position, either since it's reduce-only or since it
+/// closes a position side in hedge mode, to the position that it can reduce. Returns `false` if it
+/// would open or increase the position, in which case it must be rejected.
+pub(crate) fn apply_reduce_only(order: &mut Order, state_values: &StateValues) -> bool {
+    if !order.reduce_only && !order.position_side.is_closing(order.side) {
+        return true;
+    }
+    let position = state_values.side_position(order.position_side);
+    let reducible = match order.side {
+        Side::Buy => -position,
+        Side::Sell => position,
+        Side::None | Side::Unsupported => 0.0,
+    };
+    if reducible <= 0.0 {
+        return false;
+    }
+    if order.qty > reducible {
+        order.qty = reducible;
+        order.leaves_qty = reducible;
+    }
+    true
+}
+
+/// Determines how the exchange model handles a post-only ([`TimeInForce::GTX`]) order that would
+/// cross the opposite best price and take liquidity upon arrival.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum PostOnlyBehavior {
+    /// The order is rejected and expires, as on Binance Futures and Bybit.
+    #[default]
+    Reject,
+    /// The order is repriced to one tick inside the opposite best price and rests on the book as a
+    /// maker order, as on Deribit.
+    Adjust,
+}
+
+impl PostOnlyBehavior {
+    /// Reprices the post-only order if it crosses the opposite best price and the behavior is
+    /// [`PostOnlyBehavior::Adjust`]. Otherwise, the order is left unchanged so that the exchange
+    /// model rejects it.
+    pub(crate) fn reprice<MD: MarketDepth>(&self, order: &mut Order, depth: &MD) {
+        if *self != PostOnlyBehavior::Adjust
+            || order.order_type != OrdType::Limit
+            || order.time_in_force != TimeInForce::GTX
+        {
+            return;
+        }
+        if order.side == Side::Buy && order.price_tick >= depth.best_ask_tick() {
+            order.price_tick = depth.best_ask_tick() - 1;
+        } else if order.side == Side::Sell && order.price_tick <= depth.best_bid_tick() {
+            order.price_tick = depth.best_bid_tick() + 1;
+        }
+    }
+}
+
+/// Approximate memory usage in bytes by component.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub struct MemoryUsage {
+    /// The peak size of the feed data buffers held at the same time.
+    pub data: usize,
+    /// The size of the market depth.
+    pub depth: usize,
+    /// The size of the order store.
+    pub orders: usize,
+}
+
+impl MemoryUsage {
+    /// Combines the memory usage of the local and the exchange processors of an asset. Both
+    /// processors share the feed data, so it is counted once.
+    pub(crate) fn of_asset<L, E>(local: &L, exch: &E) -> Self
+    where
+        L: Processor + ?Sized,
+        E: Processor + ?Sized,
+    {
+        let local = local.memory_usage();
+        let exch = exch.memory_usage();
+        Self {
+            data: local.data.max(exch.data),
+            depth: local.depth + exch.depth,
+            orders: local.orders + exch.orders,
+        }
+    }
+
+    /// Returns the total size.
+    pub fn total(&self) -> usize {
+        self.data + self.depth + self.orders
+    }
+
+    /// Logs the memory usage of the asset when the backtest is closed.
+    pub(crate) fn log(&self, asset_no: usize) {
+        info!(
+            %asset_no,
+            data = self.data,
+            depth = self.depth,
+            orders = self.orders,
+            total = self.total(),
+            "Memory usage in bytes, where the data usage is the peak."
+        );
+    }
+}
+
 /// Provides local-specific interaction.
 pub trait LocalProcessor<MD>: Processor
 where
@@ -36,6 +188,9 @@ where
     ///                   the exchange model for details.
     /// * `time_in_force` - Available [`TimeInForce`] options vary depending on the exchange model.
     ///                     See to the exchange model for details.
+    /// * `reduce_only` - Whether the order may only reduce the position.
+    /// * `position_side` - The position side in hedge mode, or [`PositionSide::Both`] in one-way
+    ///   mode.
     /// * `current_timestamp` - The current backtesting timestamp.
     #[allow(clippy::too_many_arguments)]
     fn submit_order(
@@ -46,6 +201,8 @@ where
         qty: f64,
         order_type: OrdType,
         time_in_force: TimeInForce,
+        reduce_only: bool,
+        position_side: PositionSide,
         current_timestamp: i64,
     ) -> Result<(), BacktestError>;
 
@@ -66,6 +223,9 @@ where
     /// Returns the state's values such as balance, fee, and so on.
     fn state_values(&self) -> &StateValues;
 
+    /// Returns the asset's share of the [`Portfolio`].
+    fn portfolio(&self) -> Portfolio;
+
     /// Returns the [`MarketDepth`].
     fn depth(&self) -> &MD;
 
@@ -78,12 +238,42 @@ where
     /// Clears the last market trades from the buffer.
     fn clear_last_trades(&mut self);
 
+    /// Returns the last changes applied to the price levels of the market depth.
+    fn last_depth_updates(&self) -> &[DepthUpdate];
+
+    /// Clears the last depth updates from the buffer.
+    fn clear_last_depth_updates(&mut self);
+
+    /// Returns the bars of the interval, or an empty slice if no bars of the interval are built.
+    fn bars(&self, interval: i64) -> &[Bar];
+
+    /// Returns the forced liquidations of the position that have occurred so far.
+    fn liquidations(&self) -> &[Liquidation];
+
+    /// Returns the fills of the orders since the fills were last cleared.
+    fn last_fills(&self) -> &[Fill];
+
+    /// Clears the last fills.
+    fn clear_last_fills(&mut self);
+
+    /// Returns the forced liquidations in the market since they were last cleared.
+    fn last_liquidation_prints(&self) -> &[Event];
+
+    /// Clears the last liquidation prints.
+    fn clear_last_liquidation_prints(&mut self);
+
+    /// Returns the latest open interest, if it has been received.
+    fn open_interest(&self) -> Option<f64>;
+
     /// Returns the last feed's exchange timestamp and local receipt timestamp.
     fn feed_latency(&self) -> Option<(i64, i64)>;
 
     /// Returns the last order's request timestamp, exchange timestamp, and response receipt
     /// timestamp.
     fn order_latency(&self) -> Option<(i64, i64, i64)>;
+
+    /// Returns the [`EventStats`] counted since the last call, and resets the counters.
+    fn take_event_stats(&mut self) -> EventStats;
 }
 
 /// Processes the historical feed data and the order interaction.
@@ -112,4 +302,187 @@ pub trait Processor {
     /// Returns the foremost timestamp at which an order sent by this processor is to be received by
     /// the corresponding processor.
     fn earliest_send_order_timestamp(&self) -> i64;
+
+    /// Returns the approximate memory usage of this processor. Since the capacity of the hash maps
+    /// does not shrink, the size of the market depth and the order store reflects their peak.
+    /// Processors that don't track their memory usage report zero.
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage::default()
+    }
+
+    /// Reseeds the stochastic models of this processor, such as the latency model and the queue
+    /// model. This is invoked when the backtest is built with a seed.
+    fn seed(&mut self, _seed: u64) {}
+
+    /// Writes the state of this processor into the checkpoint.
+    fn save_checkpoint(&self, _writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        Err(checkpoint_error(
+            "the processor doesn't support checkpoints",
+        ))
+    }
+
+    /// Restores the state of this processor from the checkpoint. This must be invoked on a
+    /// processor that has not processed any data yet.
+    fn load_checkpoint(&mut self, _reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        Err(checkpoint_error(
+            "the processor doesn't support checkpoints",
+        ))
+    }
+}
+
+impl<P: Processor + ?Sized> Processor for Box<P> {
+    fn initialize_data(&mut self) -> Result<i64, BacktestError> {
+        (**self).initialize_data()
+    }
+
+    fn process_data(&mut self) -> Result<(i64, i64), BacktestError> {
+        (**self).process_data()
+    }
+
+    fn process_recv_order(
+        &mut self,
+        timestamp: i64,
+        wait_resp_order_id: Option<OrderId>,
+    ) -> Result<bool, BacktestError> {
+        (**self).process_recv_order(timestamp, wait_resp_order_id)
+    }
+
+    fn earliest_recv_order_timestamp(&self) -> i64 {
+        (**self).earliest_recv_order_timestamp()
+    }
+
+    fn earliest_send_order_timestamp(&self) -> i64 {
+        (**self).earliest_send_order_timestamp()
+    }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        (**self).memory_usage()
+    }
+
+    fn seed(&mut self, seed: u64) {
+        (**self).seed(seed)
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        (**self).save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        (**self).load_checkpoint(reader)
+    }
+}
+
+impl<MD, L> LocalProcessor<MD> for Box<L>
+where
+    MD: MarketDepth,
+    L: LocalProcessor<MD> + ?Sized,
+{
+    fn submit_order(
+        &mut self,
+        order_id: OrderId,
+        side: Side,
+        price: f64,
+        qty: f64,
+        order_type: OrdType,
+        time_in_force: TimeInForce,
+        reduce_only: bool,
+        position_side: PositionSide,
+        current_timestamp: i64,
+    ) -> Result<(), BacktestError> {
+        (**self).submit_order(
+            order_id,
+            side,
+            price,
+            qty,
+            order_type,
+            time_in_force,
+            reduce_only,
+            position_side,
+            current_timestamp,
+        )
+    }
+
+    fn cancel(&mut self, order_id: OrderId, current_timestamp: i64) -> Result<(), BacktestError> {
+        (**self).cancel(order_id, current_timestamp)
+    }
+
+    fn clear_inactive_orders(&mut self) {
+        (**self).clear_inactive_orders()
+    }
+
+    fn position(&self) -> f64 {
+        (**self).position()
+    }
+
+    fn state_values(&self) -> &StateValues {
+        (**self).state_values()
+    }
+
+    fn portfolio(&self) -> Portfolio {
+        (**self).portfolio()
+    }
+
+    fn depth(&self) -> &MD {
+        (**self).depth()
+    }
+
+    fn orders(&self) -> &HashMap<OrderId, Order> {
+        (**self).orders()
+    }
+
+    fn last_trades(&self) -> &[Event] {
+        (**self).last_trades()
+    }
+
+    fn clear_last_trades(&mut self) {
+        (**self).clear_last_trades()
+    }
+
+    fn last_depth_updates(&self) -> &[DepthUpdate] {
+        (**self).last_depth_updates()
+    }
+
+    fn clear_last_depth_updates(&mut self) {
+        (**self).clear_last_depth_updates()
+    }
+
+    fn bars(&self, interval: i64) -> &[Bar] {
+        (**self).bars(interval)
+    }
+
+    fn liquidations(&self) -> &[Liquidation] {
+        (**self).liquidations()
+    }
+
+    fn last_fills(&self) -> &[Fill] {
+        (**self).last_fills()
+    }
+
+    fn clear_last_fills(&mut self) {
+        (**self).clear_last_fills()
+    }
+
+    fn last_liquidation_prints(&self) -> &[Event] {
+        (**self).last_liquidation_prints()
+    }
+
+    fn clear_last_liquidation_prints(&mut self) {
+        (**self).clear_last_liquidation_prints()
+    }
+
+    fn open_interest(&self) -> Option<f64> {
+        (**self).open_interest()
+    }
+
+    fn feed_latency(&self) -> Option<(i64, i64)> {
+        (**self).feed_latency()
+    }
+
+    fn order_latency(&self) -> Option<(i64, i64, i64)> {
+        (**self).order_latency()
+    }
+
+    fn take_event_stats(&mut self) -> EventStats {
+        (**self).take_event_stats()
+    }
 }
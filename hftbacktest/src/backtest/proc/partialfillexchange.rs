@@ -2,25 +2,42 @@ use std::{
     cell::RefCell,
     cmp::Ordering,
     collections::{HashMap, HashSet},
-    mem,
+    mem::{self, size_of},
     rc::Rc,
 };
 
 use crate::{
     backtest::{
         assettype::AssetType,
+        calendar::SessionCalendar,
+        checkpoint::{
+            load_feed,
+            load_orders,
+            save_feed,
+            save_orders,
+            CheckpointReader,
+            CheckpointWriter,
+        },
         data::{Data, Reader},
-        models::{FeeModel, LatencyModel, QueueModel},
+        diagnostics::FillDiagnostics,
+        downtime::Downtime,
+        fault::{FaultDecision, FaultInjector},
+        margin::Margin,
+        models::{FeeModel, FillModel, LatencyModel, QueueModel},
         order::OrderBus,
-        proc::Processor,
+        proc::{apply_reduce_only, instrument_change, MemoryUsage, PostOnlyBehavior, Processor},
+        ratelimit::RateLimiter,
+        rollover::DayRollover,
         state::State,
+        throttle::{Throttle, ThrottleDecision},
         BacktestError,
     },
-    depth::{L2MarketDepth, MarketDepth, INVALID_MAX, INVALID_MIN},
+    depth::{ApplySnapshot, L2MarketDepth, MarketDepth, INVALID_MAX, INVALID_MIN},
     prelude::OrdType,
     types::{
         Event,
         Order,
+        OrderFilter,
         OrderId,
         Side,
         Status,
@@ -34,8 +51,10 @@ use crate::{
         EXCH_BUY_TRADE_EVENT,
         EXCH_DEPTH_CLEAR_EVENT,
         EXCH_EVENT,
+        EXCH_INSTRUMENT_EVENT,
         EXCH_SELL_TRADE_EVENT,
     },
+    utils::SplitMix64,
 };
 
 /// The exchange model with partial fills.
@@ -66,10 +85,17 @@ use crate::{
 /// - Filled by (remaining) buy trade quantity: your order is at the front of the queue && your
 ///   order price == buy trade price
 ///
+/// The executable quantity is the part of the trade quantity that exceeds the queue ahead of your
+/// order, and a single trade's quantity is shared among your orders at the same price. A partially
+/// filled order remains at the front of the queue and is filled incrementally by subsequent trades
+/// at its price, rather than all-or-nothing.
+///
 /// **Liquidity-Taking Order**
 /// Liquidity-taking orders will be executed based on the quantity of the order book, even though
 /// the best price and quantity do not change due to your execution. Be aware that this may cause
-/// unrealistic fill simulations if you attempt to execute a large quantity.
+/// unrealistic fill simulations if you attempt to execute a large quantity. To account for market
+/// impact and slippage, you can set a [`FillModel`] that decides the executions of liquidity-taking
+/// orders instead.
 ///
 /// **General Comment**
 /// Simulating partial fills accurately can be challenging, as they may indicate potential market
@@ -102,8 +128,20 @@ where
     state: State<AT, FM>,
     order_latency: LM,
     queue_model: QM,
+    post_only: PostOnlyBehavior,
+    margin: Option<Margin>,
+    throttle: Option<Throttle>,
+    faults: Option<FaultInjector>,
+    downtime: Option<Downtime>,
+    rate_limiter: Option<RateLimiter>,
+    fill_diagnostics: Option<FillDiagnostics>,
+    rollover: Option<DayRollover>,
+    calendar: Option<SessionCalendar>,
+    order_filter: Option<OrderFilter>,
+    fill_model: Option<Box<dyn FillModel<MD>>>,
 
     filled_orders: Vec<OrderId>,
+    fills: Vec<(i64, f64)>,
 }
 
 impl<AT, LM, QM, MD, FM> PartialFillExchange<AT, LM, QM, MD, FM>
@@ -115,6 +153,7 @@ where
     FM: FeeModel,
 {
     /// Constructs an instance of `PartialFillExchange`.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         reader: Reader<Event>,
         depth: MD,
@@ -123,6 +162,7 @@ where
         queue_model: QM,
         orders_to: OrderBus,
         orders_from: OrderBus,
+        post_only: PostOnlyBehavior,
     ) -> Self {
         Self {
             reader,
@@ -137,7 +177,239 @@ where
             state,
             order_latency,
             queue_model,
+            post_only,
+            margin: None,
+            throttle: None,
+            faults: None,
+            downtime: None,
+            rate_limiter: None,
+            fill_diagnostics: None,
+            rollover: None,
+            calendar: None,
+            order_filter: None,
+            fill_model: None,
             filled_orders: Default::default(),
+            fills: Default::default(),
+        }
+    }
+
+    /// Sets a [`FillModel`] that decides the execution price and quantity of liquidity-taking
+    /// orders. By default, liquidity-taking orders are executed at the displayed levels.
+    pub fn fill_model<FL>(self, fill_model: FL) -> Self
+    where
+        FL: FillModel<MD> + 'static,
+    {
+        Self {
+            fill_model: Some(Box::new(fill_model)),
+            ..self
+        }
+    }
+
+    /// Sets the [`Margin`] requirements. By default, the margin is not simulated, so the position
+    /// is never liquidated.
+    pub fn margin(self, margin: Margin) -> Self {
+        Self {
+            margin: Some(margin),
+            ..self
+        }
+    }
+
+    /// Sets the [`Throttle`] that limits the frequency of order requests. By default, the order
+    /// requests are not throttled.
+    pub fn throttle(self, throttle: Throttle) -> Self {
+        Self {
+            throttle: Some(throttle),
+            ..self
+        }
+    }
+
+    /// Sets the [`FaultInjector`] that rejects, delays, or drops the order requests and responses.
+    /// By default, no faults are injected.
+    pub fn faults(self, faults: FaultInjector) -> Self {
+        Self {
+            faults: Some(faults),
+            ..self
+        }
+    }
+
+    /// Sets the [`Downtime`] windows, during which the exchange rejects every order request.
+    /// By default, the exchange is always up.
+    pub fn downtime(self, downtime: Downtime) -> Self {
+        Self {
+            downtime: Some(downtime),
+            ..self
+        }
+    }
+
+    /// Sets the [`RateLimiter`], which answers the requests exceeding the rate limits with
+    /// [`Status::RateLimited`]. By default, the requests aren't rate-limited.
+    pub fn rate_limiter(self, rate_limiter: RateLimiter) -> Self {
+        Self {
+            rate_limiter: Some(rate_limiter),
+            ..self
+        }
+    }
+
+    /// Sets the [`FillDiagnostics`], which records the queue position, the time at the touch, and
+    /// the outcome of every resting order. By default, no diagnostics are recorded.
+    pub fn fill_diagnostics(self, fill_diagnostics: FillDiagnostics) -> Self {
+        Self {
+            fill_diagnostics: Some(fill_diagnostics),
+            ..self
+        }
+    }
+
+    /// Sets the [`DayRollover`], which handles the working orders and the market depth at the
+    /// boundaries between the chained daily files. By default, both carry over.
+    pub fn rollover(self, rollover: DayRollover) -> Self {
+        Self {
+            rollover: Some(rollover),
+            ..self
+        }
+    }
+
+    /// Sets the [`SessionCalendar`], outside whose sessions and during whose halts the exchange
+    /// rejects new orders. By default, the exchange trades around the clock.
+    pub fn calendar(self, calendar: SessionCalendar) -> Self {
+        Self {
+            calendar: Some(calendar),
+            ..self
+        }
+    }
+
+    /// Sets the [`OrderFilter`] that new orders must pass, otherwise they're rejected. By default,
+    /// new orders aren't filtered.
+    pub fn order_filter(self, filter: OrderFilter) -> Self {
+        Self {
+            order_filter: Some(filter),
+            ..self
+        }
+    }
+
+    /// Liquidates the position if the margin balance has fallen below the maintenance margin,
+    /// canceling all open orders.
+    /// Observes the resting orders for the fill diagnostics.
+    fn diagnose(&self, timestamp: i64) {
+        let Some(fill_diagnostics) = &self.fill_diagnostics else {
+            return;
+        };
+        let orders = self.orders.borrow();
+        let mut order_ids: Vec<_> = orders.keys().copied().collect();
+        order_ids.sort_unstable();
+        for order_id in order_ids {
+            let order = &orders[&order_id];
+            let at_touch = if order.side == Side::Buy {
+                order.price_tick >= self.depth.best_bid_tick()
+            } else {
+                order.price_tick <= self.depth.best_ask_tick()
+            };
+            fill_diagnostics.observe(
+                order,
+                self.queue_model.queue_position(order),
+                at_touch,
+                timestamp,
+            );
+        }
+    }
+
+    fn check_margin(&mut self, timestamp: i64) -> Result<(), BacktestError> {
+        let Some(margin) = self.margin.as_mut() else {
+            return Ok(());
+        };
+        if !margin.check_liquidation(&self.state, &self.depth) {
+            return Ok(());
+        }
+        let liquidations = margin.liquidate(&mut self.state, &self.depth, timestamp);
+        self.cancel_all(timestamp);
+
+        for order in liquidations {
+            let local_recv_timestamp = timestamp + self.order_latency.response(timestamp, &order);
+            self.orders_to.append(order, local_recv_timestamp);
+        }
+        Ok(())
+    }
+
+    /// Cancels all working orders and notifies the local.
+    fn cancel_all(&mut self, timestamp: i64) {
+        let canceled: Vec<Order> = self
+            .orders
+            .borrow_mut()
+            .drain()
+            .map(|(_, order)| order)
+            .collect();
+        self.buy_orders.clear();
+        self.sell_orders.clear();
+        for mut order in canceled {
+            order.status = Status::Canceled;
+            if let Some(fill_diagnostics) = &self.fill_diagnostics {
+                fill_diagnostics.close(&order, timestamp);
+            }
+            order.exch_timestamp = timestamp;
+            let local_recv_timestamp = timestamp + self.order_latency.response(timestamp, &order);
+            self.orders_to.append(order, local_recv_timestamp);
+        }
+    }
+
+    /// Returns `true` if the order takes liquidity upon arrival and should be executed by the
+    /// [`FillModel`].
+    fn is_marketable(&self, order: &Order) -> bool {
+        match (order.order_type, order.time_in_force) {
+            (OrdType::Market, _) => true,
+            (OrdType::Limit, TimeInForce::GTC | TimeInForce::FOK | TimeInForce::IOC) => {
+                match order.side {
+                    Side::Buy => order.price_tick >= self.depth.best_ask_tick(),
+                    Side::Sell => order.price_tick <= self.depth.best_bid_tick(),
+                    Side::None | Side::Unsupported => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn take_by_fill_model(
+        &mut self,
+        mut order: Order,
+        timestamp: i64,
+    ) -> Result<(), BacktestError> {
+        let mut fills = mem::take(&mut self.fills);
+        fills.clear();
+        if let Some(fill_model) = self.fill_model.as_mut() {
+            fill_model.take(&order, &self.depth, &mut fills);
+        }
+
+        let lot_size = self.depth.lot_size();
+        let total_qty: f64 = fills.iter().map(|(_, qty)| qty).sum();
+        // The order must be executed immediately in its entirety; otherwise, the entire order will
+        // be cancelled.
+        let execute = order.time_in_force != TimeInForce::FOK
+            || (total_qty / lot_size).round() >= (order.qty / lot_size).round();
+        if execute {
+            for &(price_tick, qty) in fills.iter() {
+                let exec_qty = qty.min(order.leaves_qty);
+                if (exec_qty / lot_size).round() > 0.0 {
+                    self.fill(&mut order, timestamp, false, price_tick, exec_qty)?;
+                }
+                if order.status == Status::Filled {
+                    break;
+                }
+            }
+        }
+        self.fills = fills;
+
+        if order.status == Status::Filled {
+            Ok(())
+        } else if order.order_type == OrdType::Limit && order.time_in_force == TimeInForce::GTC {
+            // As with the displayed levels, the remaining quantity cannot remain in the book, so it
+            // is executed at the order price.
+            let (price_tick, leaves_qty) = (order.price_tick, order.leaves_qty);
+            self.fill(&mut order, timestamp, false, price_tick, leaves_qty)
+        } else {
+            order.status = Status::Expired;
+
+            order.exch_timestamp = timestamp;
+            let local_recv_timestamp = timestamp + self.order_latency.response(timestamp, &order);
+            self.orders_to.append(order.clone(), local_recv_timestamp);
+            Ok(())
         }
     }
 
@@ -146,6 +418,93 @@ where
         mut order: Order,
         recv_timestamp: i64,
     ) -> Result<(), BacktestError> {
+        // The request sent before the tick size change is re-normalized into the new tick size.
+        order.normalize_tick(self.depth.tick_size());
+        // The exchange rejects every request, including cancels, while it's down.
+        let decision = if self
+            .downtime
+            .as_ref()
+            .is_some_and(|downtime| downtime.is_down(recv_timestamp))
+        {
+            Some(FaultDecision::Reject)
+        } else if order.req == Status::New
+            && self
+                .calendar
+                .as_ref()
+                .is_some_and(|calendar| !calendar.is_open(recv_timestamp))
+        {
+            // Only the new orders are rejected outside the sessions, so that the resting orders
+            // can still be canceled.
+            Some(FaultDecision::Reject)
+        } else {
+            self.faults.as_ref().map(|faults| faults.on_request(&order))
+        };
+        if let Some(decision) = decision {
+            match decision {
+                FaultDecision::Accept => {}
+                FaultDecision::Reject => {
+                    order.req = Status::Rejected;
+                    order.exch_timestamp = recv_timestamp;
+                    let local_recv_timestamp =
+                        recv_timestamp + self.order_latency.response(recv_timestamp, &order);
+                    self.orders_to.append(order, local_recv_timestamp);
+                    return Ok(());
+                }
+                FaultDecision::Delay(delay) => {
+                    self.orders_from.append(order, recv_timestamp + delay);
+                    return Ok(());
+                }
+            }
+        }
+        if self
+            .rate_limiter
+            .as_ref()
+            .is_some_and(|rate_limiter| rate_limiter.on_request(&order, recv_timestamp))
+        {
+            order.req = Status::RateLimited;
+            order.exch_timestamp = recv_timestamp;
+            let local_recv_timestamp =
+                recv_timestamp + self.order_latency.response(recv_timestamp, &order);
+            self.orders_to.append(order, local_recv_timestamp);
+            return Ok(());
+        }
+        if let Some(throttle) = &self.throttle {
+            match throttle.on_message(&order, recv_timestamp, self.state.values().num_trades) {
+                ThrottleDecision::Accept => {}
+                ThrottleDecision::Reject => {
+                    order.req = Status::Rejected;
+                    order.exch_timestamp = recv_timestamp;
+                    let local_recv_timestamp =
+                        recv_timestamp + self.order_latency.response(recv_timestamp, &order);
+                    self.orders_to.append(order, local_recv_timestamp);
+                    return Ok(());
+                }
+                ThrottleDecision::Penalty(fee) => self.state.apply_fee(fee),
+            }
+        }
+
+        let rejected = order.req == Status::New
+            && (self.order_filter.as_ref().is_some_and(|filter| {
+                filter
+                    .check(
+                        order.side,
+                        order.price_tick as f64 * order.tick_size,
+                        order.qty,
+                        self.depth.mid(),
+                        self.orders.borrow().len(),
+                    )
+                    .is_err()
+            }) || !apply_reduce_only(&mut order, &self.state.state_values));
+        if rejected {
+            order.req = Status::Rejected;
+            order.exch_timestamp = recv_timestamp;
+            let local_recv_timestamp =
+                recv_timestamp + self.order_latency.response(recv_timestamp, &order);
+            self.orders_to.append(order, local_recv_timestamp);
+            return Ok(());
+        }
+
+        let num_responses = self.orders_to.len();
         // Processes a new order.
         if order.req == Status::New {
             order.req = Status::None;
@@ -158,6 +517,15 @@ where
         } else {
             return Err(BacktestError::InvalidOrderRequest);
         }
+
+        // Only a sole acknowledgement can be dropped, not the responses of an immediate fill.
+        if let Some(faults) = &self.faults {
+            if self.orders_to.len() == num_responses + 1 {
+                self.orders_to
+                    .pop_back_if(|response| faults.drop_ack(response));
+            }
+        }
+        self.diagnose(recv_timestamp);
         Ok(())
     }
 
@@ -166,6 +534,7 @@ where
         order: &mut Order,
         price_tick: i64,
         qty: f64,
+        remaining_qty: &mut f64,
         timestamp: i64,
     ) -> Result<(), BacktestError> {
         match order.price_tick.cmp(&price_tick) {
@@ -178,15 +547,18 @@ where
                 // Updates the order's queue position.
                 self.queue_model.trade(order, qty, &self.depth);
                 let filled_qty = self.queue_model.is_filled(order, &self.depth);
-                if filled_qty > 0.0 {
-                    // q_ahead is negative since is_filled is true and its value represents the
-                    // executable quantity of this order after execution in the queue ahead of this
-                    // order.
-                    // let q_qty =
-                    //     (-order.front_q_qty / self.depth.lot_size()).floor() * self.depth.lot_size();
-                    let exec_qty = filled_qty.min(qty).min(order.leaves_qty);
-                    self.filled_orders.push(order.order_id);
-                    return self.fill(order, timestamp, true, order.price_tick, exec_qty);
+                // The trade quantity that has passed the queue ahead of this order is executable,
+                // but it is shared with the other orders at the same price that have already been
+                // filled by this trade.
+                let exec_qty = filled_qty.min(*remaining_qty).min(order.leaves_qty);
+                if (exec_qty / self.depth.lot_size()).round() > 0.0 {
+                    *remaining_qty -= exec_qty;
+                    self.fill(order, timestamp, true, order.price_tick, exec_qty)?;
+                    // A partially filled order remains in the queue, at the front, and is filled
+                    // further by subsequent trades.
+                    if order.status == Status::Filled {
+                        self.filled_orders.push(order.order_id);
+                    }
                 }
             }
         }
@@ -198,6 +570,7 @@ where
         order: &mut Order,
         price_tick: i64,
         qty: f64,
+        remaining_qty: &mut f64,
         timestamp: i64,
     ) -> Result<(), BacktestError> {
         match order.price_tick.cmp(&price_tick) {
@@ -210,15 +583,18 @@ where
                 // Updates the order's queue position.
                 self.queue_model.trade(order, qty, &self.depth);
                 let filled_qty = self.queue_model.is_filled(order, &self.depth);
-                if filled_qty > 0.0 {
-                    // q_ahead is negative since is_filled is true and its value represents the
-                    // executable quantity of this order after execution in the queue ahead of this
-                    // order.
-                    // let q_qty =
-                    //     (-order.front_q_qty / self.depth.lot_size()).floor() * self.depth.lot_size();
-                    let exec_qty = filled_qty.min(qty).min(order.leaves_qty);
-                    self.filled_orders.push(order.order_id);
-                    return self.fill(order, timestamp, true, order.price_tick, exec_qty);
+                // The trade quantity that has passed the queue ahead of this order is executable,
+                // but it is shared with the other orders at the same price that have already been
+                // filled by this trade.
+                let exec_qty = filled_qty.min(*remaining_qty).min(order.leaves_qty);
+                if (exec_qty / self.depth.lot_size()).round() > 0.0 {
+                    *remaining_qty -= exec_qty;
+                    self.fill(order, timestamp, true, order.price_tick, exec_qty)?;
+                    // A partially filled order remains in the queue, at the front, and is filled
+                    // further by subsequent trades.
+                    if order.status == Status::Filled {
+                        self.filled_orders.push(order.order_id);
+                    }
                 }
             }
         }
@@ -253,6 +629,9 @@ where
             order.status = Status::PartiallyFilled;
         } else {
             order.status = Status::Filled;
+            if let Some(fill_diagnostics) = &self.fill_diagnostics {
+                fill_diagnostics.close(order, timestamp);
+            }
         }
         order.exch_timestamp = timestamp;
         let local_recv_timestamp =
@@ -384,6 +763,42 @@ where
             return Err(BacktestError::OrderIdExist);
         }
 
+        // Rejects the order if the margin balance is insufficient for the initial margin.
+        if let Some(margin) = &self.margin {
+            if !margin.allows(&order, &self.state, &self.depth) {
+                order.status = Status::Expired;
+                order.exch_timestamp = timestamp;
+                let local_recv_timestamp =
+                    timestamp + self.order_latency.response(timestamp, &order);
+                self.orders_to.append(order, local_recv_timestamp);
+                return Ok(());
+            }
+        }
+
+        // Rejects the sell order if it could make the position negative when short selling is
+        // disallowed.
+        let open_sell_qty = self
+            .orders
+            .borrow()
+            .values()
+            .filter(|order| order.side == Side::Sell)
+            .map(|order| order.leaves_qty)
+            .sum();
+        if !self.state.allows_short(&order, open_sell_qty) {
+            order.status = Status::Expired;
+            order.exch_timestamp = timestamp;
+            let local_recv_timestamp = timestamp + self.order_latency.response(timestamp, &order);
+            self.orders_to.append(order, local_recv_timestamp);
+            return Ok(());
+        }
+
+        // Depending on the exchange, a crossing post-only order is repriced instead of rejected.
+        self.post_only.reprice(&mut order, &self.depth);
+
+        if self.fill_model.is_some() && self.is_marketable(&order) {
+            return self.take_by_fill_model(order, timestamp);
+        }
+
         if order.side == Side::Buy {
             match order.order_type {
                 OrdType::Limit => {
@@ -717,6 +1132,9 @@ where
 
         // Makes the response.
         exch_order.status = Status::Canceled;
+        if let Some(fill_diagnostics) = &self.fill_diagnostics {
+            fill_diagnostics.close(&exch_order, timestamp);
+        }
         exch_order.exch_timestamp = timestamp;
         let local_recv_timestamp = timestamp + self.order_latency.response(timestamp, &exch_order);
         self.orders_to
@@ -861,7 +1279,7 @@ where
     AT: AssetType,
     LM: LatencyModel,
     QM: QueueModel<MD>,
-    MD: MarketDepth + L2MarketDepth,
+    MD: MarketDepth + L2MarketDepth + ApplySnapshot,
     FM: FeeModel,
 {
     fn initialize_data(&mut self) -> Result<i64, BacktestError> {
@@ -887,11 +1305,19 @@ where
             || self.data[row_num].is(EXCH_BID_DEPTH_SNAPSHOT_EVENT)
         {
             let (price_tick, prev_best_bid_tick, best_bid_tick, prev_qty, new_qty, timestamp) =
-                self.depth.update_bid_depth(
-                    self.data[row_num].px,
-                    self.data[row_num].qty,
-                    self.data[row_num].exch_ts,
-                );
+                if self.data[row_num].is(EXCH_BID_DEPTH_SNAPSHOT_EVENT) {
+                    self.depth.update_bid_snapshot(
+                        self.data[row_num].px,
+                        self.data[row_num].qty,
+                        self.data[row_num].exch_ts,
+                    )
+                } else {
+                    self.depth.update_bid_depth(
+                        self.data[row_num].px,
+                        self.data[row_num].qty,
+                        self.data[row_num].exch_ts,
+                    )
+                };
             self.on_bid_qty_chg(price_tick, prev_qty, new_qty);
             if best_bid_tick > prev_best_bid_tick {
                 self.on_best_bid_update(prev_best_bid_tick, best_bid_tick, timestamp)?;
@@ -900,18 +1326,51 @@ where
             || self.data[row_num].is(EXCH_ASK_DEPTH_SNAPSHOT_EVENT)
         {
             let (price_tick, prev_best_ask_tick, best_ask_tick, prev_qty, new_qty, timestamp) =
-                self.depth.update_ask_depth(
-                    self.data[row_num].px,
-                    self.data[row_num].qty,
-                    self.data[row_num].exch_ts,
-                );
+                if self.data[row_num].is(EXCH_ASK_DEPTH_SNAPSHOT_EVENT) {
+                    self.depth.update_ask_snapshot(
+                        self.data[row_num].px,
+                        self.data[row_num].qty,
+                        self.data[row_num].exch_ts,
+                    )
+                } else {
+                    self.depth.update_ask_depth(
+                        self.data[row_num].px,
+                        self.data[row_num].qty,
+                        self.data[row_num].exch_ts,
+                    )
+                };
             self.on_ask_qty_chg(price_tick, prev_qty, new_qty);
             if best_ask_tick < prev_best_ask_tick {
                 self.on_best_ask_update(prev_best_ask_tick, best_ask_tick, timestamp)?;
             }
+        } else if self.data[row_num].is(EXCH_INSTRUMENT_EVENT) {
+            // Re-buckets the market depth and re-normalizes the prices of the working orders into
+            // the new tick size, keeping their queue positions.
+            let ev = &self.data[row_num];
+            if let Some((tick_size, lot_size)) = instrument_change(ev, &self.depth) {
+                self.buy_orders.clear();
+                self.sell_orders.clear();
+                for order in self.orders.borrow_mut().values_mut() {
+                    order.rebucket(tick_size);
+                    let orders = if order.side == Side::Buy {
+                        &mut self.buy_orders
+                    } else {
+                        &mut self.sell_orders
+                    };
+                    orders
+                        .entry(order.price_tick)
+                        .or_default()
+                        .insert(order.order_id);
+                }
+                self.depth.rebucket(tick_size, lot_size);
+            }
+            if ev.fval > 0.0 {
+                self.state.asset_type.set_contract_size(ev.fval);
+            }
         } else if self.data[row_num].is(EXCH_BUY_TRADE_EVENT) {
             let price_tick = (self.data[row_num].px / self.depth.tick_size()).round() as i64;
             let qty = self.data[row_num].qty;
+            let mut remaining_qty = qty;
             {
                 let orders = self.orders.clone();
                 let mut orders_borrowed = orders.borrow_mut();
@@ -924,6 +1383,7 @@ where
                                 order,
                                 price_tick,
                                 qty,
+                                &mut remaining_qty,
                                 self.data[row_num].exch_ts,
                             )?;
                         }
@@ -937,6 +1397,7 @@ where
                                     order,
                                     price_tick,
                                     qty,
+                                    &mut remaining_qty,
                                     self.data[row_num].exch_ts,
                                 )?;
                             }
@@ -948,6 +1409,7 @@ where
         } else if self.data[row_num].is(EXCH_SELL_TRADE_EVENT) {
             let price_tick = (self.data[row_num].px / self.depth.tick_size()).round() as i64;
             let qty = self.data[row_num].qty;
+            let mut remaining_qty = qty;
             {
                 let orders = self.orders.clone();
                 let mut orders_borrowed = orders.borrow_mut();
@@ -960,6 +1422,7 @@ where
                                 order,
                                 price_tick,
                                 qty,
+                                &mut remaining_qty,
                                 self.data[row_num].exch_ts,
                             )?;
                         }
@@ -973,6 +1436,7 @@ where
                                     order,
                                     price_tick,
                                     qty,
+                                    &mut remaining_qty,
                                     self.data[row_num].exch_ts,
                                 )?;
                             }
@@ -983,6 +1447,12 @@ where
             self.remove_filled_orders();
         }
 
+        self.state
+            .accrue_borrow_fee(self.data[row_num].exch_ts, &self.depth);
+        self.state.settle_fee(self.data[row_num].exch_ts);
+        self.check_margin(self.data[row_num].exch_ts)?;
+        self.diagnose(self.data[row_num].exch_ts);
+
         // Checks
         let mut next_ts = 0;
         for rn in (self.row_num + 1)..self.data.len() {
@@ -995,6 +1465,15 @@ where
 
         if next_ts <= 0 {
             let next_data = self.reader.next_data()?;
+            // Moving on to the next file crosses the day boundary when daily files are chained.
+            if let Some(rollover) = self.rollover {
+                if rollover.cancels_at_close() {
+                    self.cancel_all(self.data[row_num].exch_ts);
+                }
+                if rollover.resets_depth() {
+                    self.depth.clear_depth(Side::None, 0.0);
+                }
+            }
             let next_row = &next_data[0];
             next_ts = next_row.exch_ts;
             let data = mem::replace(&mut self.data, next_data);
@@ -1030,4 +1509,133 @@ where
     fn earliest_send_order_timestamp(&self) -> i64 {
         self.orders_to.earliest_timestamp().unwrap_or(i64::MAX)
     }
+
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            data: self.reader.peak_memory_usage(),
+            depth: self.depth.memory_usage(),
+            orders: self.orders.borrow().capacity() * size_of::<(OrderId, Order)>(),
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.order_latency.seed(seed);
+        self.queue_model.seed(SplitMix64::new(seed).next_u64());
+    }
+
+    fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        save_feed(writer, &self.reader, self.row_num, &self.depth)?;
+        save_orders(writer, self.orders.borrow().values(), |order, writer| {
+            self.queue_model.save_checkpoint(order, writer)
+        })?;
+        self.state.save_checkpoint(writer)?;
+        writer.write(&self.margin.as_ref().map(|margin| margin.num_liquidations()))?;
+        self.order_latency.save_checkpoint(writer)
+    }
+
+    fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        (self.data, self.row_num) = load_feed(reader, &mut self.reader, &mut self.depth)?;
+        let orders = load_orders(reader, |order, reader| {
+            self.queue_model.load_checkpoint(order, reader)
+        })?;
+        self.buy_orders.clear();
+        self.sell_orders.clear();
+        for order in &orders {
+            let price_orders = if order.side == Side::Buy {
+                &mut self.buy_orders
+            } else {
+                &mut self.sell_orders
+            };
+            price_orders
+                .entry(order.price_tick)
+                .or_default()
+                .insert(order.order_id);
+        }
+        *self.orders.borrow_mut() = orders
+            .into_iter()
+            .map(|order| (order.order_id, order))
+            .collect();
+        self.state.load_checkpoint(reader)?;
+        let num_liquidations: Option<u64> = reader.read()?;
+        if let (Some(margin), Some(num_liquidations)) = (self.margin.as_mut(), num_liquidations) {
+            margin.set_num_liquidations(num_liquidations);
+        }
+        self.order_latency.load_checkpoint(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        backtest::{
+            assettype::LinearAsset,
+            models::{CommonFees, ConstantLatency, RiskAdverseQueueModel, TradingValueFeeModel},
+            proc::PostOnlyBehavior,
+            Backtest,
+            ExchangeKind,
+            L2AssetBuilder,
+        },
+        depth::HashMapMarketDepth,
+        test_utils::feed,
+        types::{Bot, OrdType, Status, TimeInForce, BUY_EVENT, SELL_EVENT},
+    };
+
+    fn backtest(post_only: PostOnlyBehavior) -> Backtest<HashMapMarketDepth> {
+        Backtest::builder()
+            .add_asset(
+                L2AssetBuilder::new()
+                    .data(vec![feed(
+                        5,
+                        &[
+                            (100, BUY_EVENT, 100.0, 5.0),
+                            (100, SELL_EVENT, 100.5, 5.0),
+                            (5000, BUY_EVENT, 100.0, 4.0),
+                        ],
+                    )])
+                    .latency_model(ConstantLatency::new(10, 10))
+                    .asset_type(LinearAsset::new(1.0))
+                    .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                    .exchange(ExchangeKind::PartialFillExchange)
+                    .post_only(post_only)
+                    .queue_model(RiskAdverseQueueModel::new())
+                    .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_post_only_adjust() {
+        let mut hbt = backtest(PostOnlyBehavior::Adjust);
+        hbt.elapse(1000).unwrap();
+        hbt.submit_buy_order(0, 1, 101.0, 1.0, TimeInForce::GTX, OrdType::Limit, false)
+            .unwrap();
+        hbt.submit_sell_order(0, 2, 99.0, 1.0, TimeInForce::GTX, OrdType::Limit, false)
+            .unwrap();
+        hbt.elapse(5000).unwrap();
+
+        // Both crossing orders rest one tick inside the opposite best price instead of taking.
+        let buy = &hbt.orders(0)[&1];
+        assert_eq!(buy.status, Status::New);
+        assert_eq!(buy.price_tick, 1004);
+        let sell = &hbt.orders(0)[&2];
+        assert_eq!(sell.status, Status::New);
+        assert_eq!(sell.price_tick, 1001);
+        assert_eq!(hbt.position(0), 0.0);
+    }
+
+    #[test]
+    fn test_post_only_reject() {
+        let mut hbt = backtest(PostOnlyBehavior::Reject);
+        hbt.elapse(1000).unwrap();
+        hbt.submit_buy_order(0, 1, 101.0, 1.0, TimeInForce::GTX, OrdType::Limit, false)
+            .unwrap();
+        hbt.elapse(5000).unwrap();
+
+        let buy = &hbt.orders(0)[&1];
+        assert_eq!(buy.status, Status::Expired);
+        assert_eq!(buy.price_tick, 1010);
+    }
 }
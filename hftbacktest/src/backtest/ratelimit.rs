@@ -0,0 +1,220 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use crate::types::{Order, Status};
+
+/// Simulates the exchange's rate limits on the order requests, such as the request weight per
+/// minute and the number of new orders per time window. A request that exceeds any of the limits
+/// is answered with [`Status::RateLimited`] instead of being processed, and a new order rejected
+/// this way ends in [`Status::RateLimited`] on the local side.
+///
+/// Every request consumes its weight, even if it's rate-limited, as the exchanges count the
+/// rejected requests as well, while only the accepted new orders count towards the order limits.
+///
+/// [`RateLimiter::num_limited`] counts the requests that the venue would have refused, out of the
+/// [`RateLimiter::num_requests`] received by the exchange, cancels included.
+///
+/// ```
+/// use hftbacktest::backtest::ratelimit::RateLimiter;
+///
+/// const SECOND: i64 = 1_000_000_000;
+///
+/// // A request weight of 2400 per minute, where a new order weighs 1 and a cancel weighs 1, and
+/// // up to 300 new orders per 10 seconds and 1200 per minute.
+/// let rate_limiter = RateLimiter::new()
+///     .weights(1, 1)
+///     .weight_limit(2400, 60 * SECOND)
+///     .order_limit(300, 10 * SECOND)
+///     .order_limit(1200, 60 * SECOND);
+/// ```
+#[derive(Clone)]
+pub struct RateLimiter(Rc<RefCell<RateLimiterInner>>);
+
+struct RateLimiterInner {
+    new_weight: u64,
+    cancel_weight: u64,
+    weight_limits: Vec<RollingWindow>,
+    order_limits: Vec<RollingWindow>,
+    num_requests: u64,
+    num_limited: u64,
+}
+
+/// Sums the amounts within the rolling window.
+struct RollingWindow {
+    limit: u64,
+    window: i64,
+    entries: VecDeque<(i64, u64)>,
+    used: u64,
+}
+
+impl RollingWindow {
+    fn new(limit: u64, window: i64) -> Self {
+        Self {
+            limit,
+            window,
+            entries: VecDeque::new(),
+            used: 0,
+        }
+    }
+
+    fn evict(&mut self, timestamp: i64) {
+        while let Some(&(front, amount)) = self.entries.front() {
+            if front > timestamp - self.window {
+                break;
+            }
+            self.entries.pop_front();
+            self.used -= amount;
+        }
+    }
+
+    fn add(&mut self, timestamp: i64, amount: u64) {
+        self.entries.push_back((timestamp, amount));
+        self.used += amount;
+    }
+}
+
+impl RateLimiter {
+    /// Constructs `RateLimiter` with no limits, where every request weighs `1`.
+    pub fn new() -> Self {
+        Self(Rc::new(RefCell::new(RateLimiterInner {
+            new_weight: 1,
+            cancel_weight: 1,
+            weight_limits: Vec::new(),
+            order_limits: Vec::new(),
+            num_requests: 0,
+            num_limited: 0,
+        })))
+    }
+
+    /// Sets the weights of a new order request and a cancel request.
+    pub fn weights(self, new_weight: u64, cancel_weight: u64) -> Self {
+        {
+            let mut inner = self.0.borrow_mut();
+            inner.new_weight = new_weight;
+            inner.cancel_weight = cancel_weight;
+        }
+        self
+    }
+
+    /// Adds a limit on the total weight of the requests within the rolling `window`. The unit of
+    /// the `window` should be the same as the data's timestamp unit. This can be called multiple
+    /// times to add multiple limits.
+    pub fn weight_limit(self, max_weight: u64, window: i64) -> Self {
+        self.0
+            .borrow_mut()
+            .weight_limits
+            .push(RollingWindow::new(max_weight, window));
+        self
+    }
+
+    /// Adds a limit on the number of new orders within the rolling `window`. The unit of the
+    /// `window` should be the same as the data's timestamp unit. This can be called multiple times
+    /// to add multiple limits.
+    pub fn order_limit(self, max_orders: u64, window: i64) -> Self {
+        self.0
+            .borrow_mut()
+            .order_limits
+            .push(RollingWindow::new(max_orders, window));
+        self
+    }
+
+    /// Returns the number of requests received by the exchange, including the rate-limited ones.
+    pub fn num_requests(&self) -> u64 {
+        self.0.borrow().num_requests
+    }
+
+    /// Returns the number of requests that were rate-limited.
+    pub fn num_limited(&self) -> u64 {
+        self.0.borrow().num_limited
+    }
+
+    /// Counts the order request received by the exchange and returns `true` if it exceeds any of
+    /// the limits.
+    pub(crate) fn on_request(&self, order: &Order, timestamp: i64) -> bool {
+        let mut inner = self.0.borrow_mut();
+        inner.num_requests += 1;
+        let is_new = order.req == Status::New;
+        let weight = if is_new {
+            inner.new_weight
+        } else {
+            inner.cancel_weight
+        };
+
+        let mut limited = false;
+        for limit in inner.weight_limits.iter_mut() {
+            limit.evict(timestamp);
+            limit.add(timestamp, weight);
+            limited |= limit.used > limit.limit;
+        }
+        if is_new {
+            for limit in inner.order_limits.iter_mut() {
+                limit.evict(timestamp);
+                limited |= limit.used + 1 > limit.limit;
+            }
+            if !limited {
+                for limit in inner.order_limits.iter_mut() {
+                    limit.add(timestamp, 1);
+                }
+            }
+        }
+        if limited {
+            inner.num_limited += 1;
+        }
+        limited
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimiter;
+    use crate::{test_utils::request, types::Status};
+
+    #[test]
+    fn test_weight_limit() {
+        let rate_limiter = RateLimiter::new().weights(2, 1).weight_limit(5, 100);
+
+        assert!(!rate_limiter.on_request(&request(1, Status::New), 0));
+        assert!(!rate_limiter.on_request(&request(2, Status::New), 10));
+        assert!(!rate_limiter.on_request(&request(1, Status::Canceled), 20));
+        assert!(rate_limiter.on_request(&request(3, Status::New), 60));
+        // The first three requests have left the window.
+        assert!(!rate_limiter.on_request(&request(4, Status::New), 125));
+        assert_eq!(rate_limiter.num_requests(), 5);
+        assert_eq!(rate_limiter.num_limited(), 1);
+    }
+
+    #[test]
+    fn test_order_limit() {
+        let rate_limiter = RateLimiter::new().order_limit(1, 50);
+
+        assert!(!rate_limiter.on_request(&request(1, Status::New), 0));
+        assert!(rate_limiter.on_request(&request(2, Status::New), 10));
+        // A cancel doesn't count towards the order limit.
+        assert!(!rate_limiter.on_request(&request(1, Status::Canceled), 20));
+        // The rate-limited new order didn't count towards the order limit.
+        assert!(!rate_limiter.on_request(&request(3, Status::New), 55));
+        assert_eq!(rate_limiter.num_requests(), 4);
+        assert_eq!(rate_limiter.num_limited(), 1);
+    }
+
+    #[test]
+    fn test_limited_request_consumes_weight() {
+        let rate_limiter = RateLimiter::new()
+            .weights(2, 1)
+            .weight_limit(5, 100)
+            .order_limit(1, 50);
+
+        assert!(!rate_limiter.on_request(&request(1, Status::New), 0));
+        // The order limit is exceeded, but the request still consumes its weight.
+        assert!(rate_limiter.on_request(&request(2, Status::New), 10));
+        assert!(!rate_limiter.on_request(&request(1, Status::Canceled), 20));
+        // The weight limit is exceeded only because of the rate-limited request's weight.
+        assert!(rate_limiter.on_request(&request(3, Status::New), 60));
+        assert_eq!(rate_limiter.num_limited(), 2);
+    }
+}
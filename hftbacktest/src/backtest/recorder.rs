@@ -5,14 +5,33 @@ use std::{
 };
 
 use hftbacktest_derive::NpyDTyped;
+#[cfg(feature = "fs")]
 use zip::{write::SimpleFileOptions, ZipWriter};
 
+#[cfg(feature = "fs")]
+use crate::backtest::data::{write_npy, write_npy_bytes, Field};
 use crate::{
-    backtest::data::{write_npy, POD},
+    backtest::{
+        assettype::AssetType,
+        calendar::{SessionCalendar, SessionSummary},
+        data::POD,
+    },
     depth::MarketDepth,
     types::{Bot, Recorder},
 };
 
+/// The compression of the files archived in the NPZ file by [`BacktestRecorder::to_npz_with`].
+#[cfg(feature = "fs")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NpzCompression {
+    /// Deflate at the given level from 0 to 9, which `numpy` can read.
+    Deflate(i64),
+    /// Zstandard at the given level from 1 to 22, which is faster to read and write at a similar
+    /// ratio, but isn't supported by `numpy`. It requires the `compression` feature.
+    #[cfg(feature = "compression")]
+    Zstd(i64),
+}
+
 #[repr(C)]
 #[derive(NpyDTyped)]
 struct Record {
@@ -30,8 +49,20 @@ unsafe impl POD for Record {}
 
 /// Provides recording of the backtesting strategy's state values, which are needed to compute
 /// performance metrics.
+///
+/// The records are skipped during the warm-up period of the backtest, set by
+/// [`Backtest::warmup_until`](crate::backtest::Backtest::warmup_until).
+///
+/// Custom metrics, such as a model's signal value or the target inventory, can be registered by
+/// [`register_metric`](Self::register_metric) and are recorded alongside the state values, so that
+/// the strategy's internals are aligned on the same timeline.
 pub struct BacktestRecorder {
     values: Vec<Vec<Record>>,
+    metric_names: Vec<String>,
+    // The current value of each metric, per asset.
+    metrics: Vec<Vec<f64>>,
+    // The recorded values of each metric, per asset.
+    metric_values: Vec<Vec<Vec<f64>>>,
 }
 
 impl Recorder for BacktestRecorder {
@@ -42,6 +73,9 @@ impl Recorder for BacktestRecorder {
         MD: MarketDepth,
         I: Bot<MD>,
     {
+        if hbt.is_warming_up() {
+            return Ok(());
+        }
         let timestamp = hbt.current_timestamp();
         for asset_no in 0..hbt.num_assets() {
             let depth = hbt.depth(asset_no);
@@ -58,6 +92,11 @@ impl Recorder for BacktestRecorder {
                 trading_value: state_values.trading_value,
                 num_trades: state_values.num_trades,
             });
+            let metrics = unsafe { self.metrics.get_unchecked(asset_no) };
+            let metric_values = unsafe { self.metric_values.get_unchecked_mut(asset_no) };
+            for (value, recorded) in metrics.iter().zip(metric_values.iter_mut()) {
+                recorded.push(*value);
+            }
         }
         Ok(())
     }
@@ -78,13 +117,89 @@ impl BacktestRecorder {
                 }
                 vec
             },
+            metric_names: Vec::new(),
+            metrics: vec![Vec::new(); hbt.num_assets()],
+            metric_values: vec![Vec::new(); hbt.num_assets()],
+        }
+    }
+
+    /// Registers a custom metric with the given name and returns its metric number, which is used
+    /// to set its value by [`set_metric`](Self::set_metric). If the metric is already registered,
+    /// its existing metric number is returned. The metric's value is `NaN` until it is set,
+    /// including in the records made before the registration.
+    pub fn register_metric(&mut self, name: &str) -> usize {
+        if let Some(metric_no) = self.metric_names.iter().position(|n| n == name) {
+            return metric_no;
+        }
+        self.metric_names.push(name.to_string());
+        for (asset_no, metrics) in self.metrics.iter_mut().enumerate() {
+            metrics.push(f64::NAN);
+            self.metric_values[asset_no].push(vec![f64::NAN; self.values[asset_no].len()]);
+        }
+        self.metric_names.len() - 1
+    }
+
+    /// Sets the value of the custom metric for the asset. The value is held and recorded on every
+    /// subsequent [`record`](Recorder::record) until it is set again.
+    pub fn set_metric(&mut self, asset_no: usize, metric_no: usize, value: f64) {
+        self.metrics[asset_no][metric_no] = value;
+    }
+
+    /// Returns the names of the registered custom metrics, in the order of their metric numbers.
+    pub fn metric_names(&self) -> &[String] {
+        &self.metric_names
+    }
+
+    /// Returns the recorded values of the custom metric for the asset.
+    pub fn metric_values(&self, asset_no: usize, metric_no: usize) -> &[f64] {
+        &self.metric_values[asset_no][metric_no]
+    }
+
+    /// Summarizes the recorded state values of the asset per session of the calendar. The
+    /// sessions without any records are omitted.
+    pub fn session_summary<AT: AssetType>(
+        &self,
+        asset_no: usize,
+        calendar: &SessionCalendar,
+        asset_type: &AT,
+    ) -> Vec<SessionSummary> {
+        let values = &self.values[asset_no];
+        let equity = |r: &Record| asset_type.equity(r.price, r.balance, r.position, r.fee);
+        let mut summaries = Vec::new();
+        for &(open, close) in calendar.sessions() {
+            let start = values.partition_point(|r| r.timestamp < open);
+            let end = values.partition_point(|r| r.timestamp < close);
+            if start == end {
+                continue;
+            }
+            let base = if start > 0 && values[start - 1].timestamp <= open {
+                &values[start - 1]
+            } else {
+                &values[start]
+            };
+            let last = &values[end - 1];
+            summaries.push(SessionSummary {
+                open,
+                close,
+                pnl: equity(last) - equity(base),
+                fee: last.fee - base.fee,
+                num_trades: last.num_trades - base.num_trades,
+                trading_volume: last.trading_volume - base.trading_volume,
+                trading_value: last.trading_value - base.trading_value,
+                closing_position: last.position,
+                max_position: values[start..end]
+                    .iter()
+                    .map(|r| r.position.abs())
+                    .fold(0.0, f64::max),
+            });
         }
+        summaries
     }
 
     /// Saves record data into a CSV file at the specified path. It creates a separate CSV file for
     /// each asset, with the filename `{prefix}_{asset_no}.csv`.
     /// The columns are `timestamp`, `mid`, `balance`, `position`, `fee`, `trade_num`,
-    /// `trade_amount`, `trade_qty`.
+    /// `trade_amount`, `trade_qty`, followed by the custom metrics.
     pub fn to_csv<Prefix, P>(&self, prefix: Prefix, path: P) -> Result<(), Error>
     where
         Prefix: AsRef<str>,
@@ -94,22 +209,29 @@ impl BacktestRecorder {
         for (asset_no, values) in self.values.iter().enumerate() {
             let file_path = path.as_ref().join(format!("{prefix}{asset_no}.csv"));
             let mut file = File::create(file_path)?;
-            writeln!(
+            write!(
                 file,
                 "timestamp,balance,position,fee,trading_volume,trading_value,num_trades,price",
             )?;
-            for Record {
-                timestamp,
-                balance,
-                position,
-                fee,
-                trading_volume,
-                trading_value,
-                num_trades,
-                price: mid_price,
-            } in values
+            for name in &self.metric_names {
+                write!(file, ",{name}")?;
+            }
+            writeln!(file)?;
+            for (
+                row,
+                Record {
+                    timestamp,
+                    balance,
+                    position,
+                    fee,
+                    trading_volume,
+                    trading_value,
+                    num_trades,
+                    price: mid_price,
+                },
+            ) in values.iter().enumerate()
             {
-                writeln!(
+                write!(
                     file,
                     "{},{},{},{},{},{},{},{}",
                     timestamp,
@@ -121,12 +243,30 @@ impl BacktestRecorder {
                     num_trades,
                     mid_price,
                 )?;
+                for metric_values in &self.metric_values[asset_no] {
+                    write!(file, ",{}", metric_values[row])?;
+                }
+                writeln!(file)?;
             }
         }
         Ok(())
     }
 
+    /// Saves record data into a NPZ file at the specified path, storing each asset's records as
+    /// `{asset_no}.npy`. If custom metrics are registered, they are stored as `{asset_no}_metrics.npy`
+    /// with the `timestamp` field followed by a field for each metric.
+    #[cfg(feature = "fs")]
     pub fn to_npz<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.to_npz_with(path, NpzCompression::Deflate(9))
+    }
+
+    /// Saves record data into a NPZ file at the specified path as [`to_npz`](Self::to_npz) does,
+    /// compressing the archived files with the given [`NpzCompression`].
+    #[cfg(feature = "fs")]
+    pub fn to_npz_with<P>(&self, path: P, compression: NpzCompression) -> Result<(), Error>
     where
         P: AsRef<Path>,
     {
@@ -134,13 +274,40 @@ impl BacktestRecorder {
 
         let mut zip = ZipWriter::new(file);
 
+        let (method, level) = match compression {
+            NpzCompression::Deflate(level) => (zip::CompressionMethod::DEFLATE, level),
+            #[cfg(feature = "compression")]
+            NpzCompression::Zstd(level) => (zip::CompressionMethod::ZSTD, level),
+        };
         let options = SimpleFileOptions::default()
-            .compression_method(zip::CompressionMethod::DEFLATE)
-            .compression_level(Some(9));
+            .compression_method(method)
+            .compression_level(Some(level));
 
         for (asset_no, values) in self.values.iter().enumerate() {
             zip.start_file(format!("{asset_no}.npy"), options)?;
             write_npy(&mut zip, values)?;
+
+            if !self.metric_names.is_empty() {
+                let mut descr = vec![Field {
+                    name: "timestamp".to_string(),
+                    ty: "<i8".to_string(),
+                }];
+                for name in &self.metric_names {
+                    descr.push(Field {
+                        name: name.clone(),
+                        ty: "<f8".to_string(),
+                    });
+                }
+                let mut bytes = Vec::with_capacity(values.len() * descr.len() * 8);
+                for (row, record) in values.iter().enumerate() {
+                    bytes.extend_from_slice(&record.timestamp.to_le_bytes());
+                    for metric_values in &self.metric_values[asset_no] {
+                        bytes.extend_from_slice(&metric_values[row].to_le_bytes());
+                    }
+                }
+                zip.start_file(format!("{asset_no}_metrics.npy"), options)?;
+                write_npy_bytes(&mut zip, descr, values.len(), &bytes)?;
+            }
         }
 
         zip.finish()?;
@@ -0,0 +1,274 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    backtest::{
+        assettype::AssetType,
+        calendar::SessionCalendar,
+        data::Reader,
+        diagnostics::FillDiagnostics,
+        downtime::Downtime,
+        fault::FaultInjector,
+        margin::Margin,
+        models::{FeeModel, FillModel},
+        order::OrderBus,
+        proc::{PostOnlyBehavior, Processor},
+        ratelimit::RateLimiter,
+        rollover::DayRollover,
+        state::{FeeSettlement, ShortSelling, State},
+        throttle::Throttle,
+    },
+    types::{BuildError, Event, OrderFilter},
+};
+
+/// The components prepared by the asset builder, from which a user-defined exchange model is
+/// constructed.
+pub struct ExchangeParts<LM, AT, QM, MD, FM> {
+    /// The reader of the feed data.
+    pub reader: Reader<Event>,
+    /// The market depth of the exchange.
+    pub depth: MD,
+    /// The asset type.
+    pub asset_type: AT,
+    /// The fee model.
+    pub fee_model: FM,
+    /// When the trading fees are settled.
+    pub fee_settlement: FeeSettlement,
+    /// How short positions are handled.
+    pub short_selling: ShortSelling,
+    /// The order latency model.
+    pub order_latency: LM,
+    /// The queue model.
+    pub queue_model: QM,
+    /// The bus carrying the order responses to the local.
+    pub orders_to_local: OrderBus,
+    /// The bus carrying the order requests from the local.
+    pub orders_from_local: OrderBus,
+    /// How a post-only order that would take liquidity is handled.
+    pub post_only: PostOnlyBehavior,
+    /// The fill model, if set on the asset builder.
+    pub fill_model: Option<Box<dyn FillModel<MD>>>,
+    /// The margin requirements, if set on the asset builder.
+    pub margin: Option<Margin>,
+    /// The throttle of the order requests, if set on the asset builder. The same throttle is
+    /// shared with the local.
+    pub throttle: Option<Throttle>,
+    /// The fault injector of the order requests, if set on the asset builder.
+    pub faults: Option<FaultInjector>,
+    /// The downtime windows of the exchange, if set on the asset builder.
+    pub downtime: Option<Downtime>,
+    /// The rate limiter of the order requests, if set on the asset builder.
+    pub rate_limiter: Option<RateLimiter>,
+    /// The fill diagnostics of the resting orders, if set on the asset builder.
+    pub fill_diagnostics: Option<FillDiagnostics>,
+    /// The filter of new orders, if set on the asset builder.
+    pub order_filter: Option<OrderFilter>,
+    /// The handling of the boundaries between the chained daily files, if set on the asset
+    /// builder.
+    pub rollover: Option<DayRollover>,
+    /// The session calendar of the exchange, if set on the asset builder.
+    pub calendar: Option<SessionCalendar>,
+}
+
+impl<LM, AT, QM, MD, FM> ExchangeParts<LM, AT, QM, MD, FM>
+where
+    AT: AssetType + Clone,
+    FM: FeeModel + Clone,
+{
+    /// Constructs the trading state of the exchange with the asset type, the fee model, and the
+    /// settings of the asset builder.
+    pub fn state(&self) -> State<AT, FM> {
+        State::new(self.asset_type.clone(), self.fee_model.clone())
+            .fee_settlement(self.fee_settlement)
+            .short_selling(self.short_selling)
+    }
+}
+
+type ExchangeFactory<LM, AT, QM, MD, FM> =
+    Rc<dyn Fn(ExchangeParts<LM, AT, QM, MD, FM>) -> Result<Box<dyn Processor>, BuildError>>;
+
+/// A registry of user-defined exchange models, which can be selected by name on the asset builder
+/// through [`ExchangeKind::Custom`](crate::backtest::ExchangeKind::Custom). This allows a venue
+/// with its own matching rules to be implemented out of the crate as a [`Processor`].
+///
+/// ```ignore
+/// let registry = ExchangeRegistry::new().register("my_venue", |parts| {
+///     Ok(Box::new(MyVenueExchange::new(parts)) as Box<dyn Processor>)
+/// });
+///
+/// let asset = L2AssetBuilder::new()
+///     .exchange_registry(registry)
+///     .exchange(ExchangeKind::Custom("my_venue".to_string()))
+///     // ...
+///     .build()?;
+/// ```
+pub struct ExchangeRegistry<LM, AT, QM, MD, FM> {
+    factories: HashMap<String, ExchangeFactory<LM, AT, QM, MD, FM>>,
+}
+
+impl<LM, AT, QM, MD, FM> ExchangeRegistry<LM, AT, QM, MD, FM> {
+    /// Constructs an empty `ExchangeRegistry`.
+    pub fn new() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Registers the factory of an exchange model under the name. A factory already registered
+    /// under the same name is replaced.
+    pub fn register<F>(mut self, name: &str, factory: F) -> Self
+    where
+        F: Fn(ExchangeParts<LM, AT, QM, MD, FM>) -> Result<Box<dyn Processor>, BuildError>
+            + 'static,
+    {
+        self.factories.insert(name.to_string(), Rc::new(factory));
+        self
+    }
+
+    /// Returns `true` if an exchange model is registered under the name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    pub(crate) fn build(
+        &self,
+        name: &str,
+        parts: ExchangeParts<LM, AT, QM, MD, FM>,
+    ) -> Result<Box<dyn Processor>, BuildError> {
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or(BuildError::InvalidArgument("exchange"))?;
+        factory(parts)
+    }
+}
+
+impl<LM, AT, QM, MD, FM> Clone for ExchangeRegistry<LM, AT, QM, MD, FM> {
+    fn clone(&self) -> Self {
+        Self {
+            factories: self.factories.clone(),
+        }
+    }
+}
+
+impl<LM, AT, QM, MD, FM> Default for ExchangeRegistry<LM, AT, QM, MD, FM> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::ExchangeRegistry;
+    use crate::{
+        backtest::{
+            assettype::LinearAsset,
+            data::{Data, DataSource},
+            models::{CommonFees, ConstantLatency, RiskAdverseQueueModel, TradingValueFeeModel},
+            proc::{NoPartialFillExchange, Processor},
+            Backtest,
+            ExchangeKind,
+            L2AssetBuilder,
+        },
+        depth::HashMapMarketDepth,
+        types::{
+            Bot,
+            BuildError,
+            Event,
+            OrdType,
+            Status,
+            TimeInForce,
+            BUY_EVENT,
+            DEPTH_EVENT,
+            EXCH_EVENT,
+            LOCAL_EVENT,
+            SELL_EVENT,
+        },
+    };
+
+    type Registry = ExchangeRegistry<
+        ConstantLatency,
+        LinearAsset,
+        RiskAdverseQueueModel<HashMapMarketDepth>,
+        HashMapMarketDepth,
+        TradingValueFeeModel<CommonFees>,
+    >;
+
+    fn builder(
+        registry: Registry,
+        name: &str,
+    ) -> L2AssetBuilder<
+        ConstantLatency,
+        LinearAsset,
+        RiskAdverseQueueModel<HashMapMarketDepth>,
+        HashMapMarketDepth,
+        TradingValueFeeModel<CommonFees>,
+    > {
+        let events: Vec<_> = [
+            (100, BUY_EVENT, 100.0),
+            (100, SELL_EVENT, 100.1),
+            (5000, BUY_EVENT, 100.0),
+        ]
+        .iter()
+        .map(|&(exch_ts, side, px)| Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | side,
+            exch_ts,
+            local_ts: exch_ts + 5,
+            px,
+            qty: 1.0,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        })
+        .collect();
+        L2AssetBuilder::new()
+            .data(vec![DataSource::Data(Data::from_data(&events))])
+            .latency_model(ConstantLatency::new(10, 10))
+            .asset_type(LinearAsset::new(1.0))
+            .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+            .queue_model(RiskAdverseQueueModel::new())
+            .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+            .exchange_registry(registry)
+            .exchange(ExchangeKind::Custom(name.to_string()))
+    }
+
+    #[test]
+    fn test_custom_exchange() {
+        let built = Rc::new(Cell::new(false));
+        let registry = Registry::new().register("venue", {
+            let built = built.clone();
+            move |parts| {
+                built.set(true);
+                let state = parts.state();
+                Ok(Box::new(NoPartialFillExchange::new(
+                    parts.reader,
+                    parts.depth,
+                    state,
+                    parts.order_latency,
+                    parts.queue_model,
+                    parts.orders_to_local,
+                    parts.orders_from_local,
+                    parts.post_only,
+                )) as Box<dyn Processor>)
+            }
+        });
+        assert!(registry.contains("venue"));
+
+        assert!(matches!(
+            builder(registry.clone(), "unknown").build(),
+            Err(BuildError::InvalidArgument("exchange"))
+        ));
+
+        let asset = builder(registry, "venue").build().unwrap();
+        assert!(built.get());
+        assert_eq!(asset.manifest.exchange, "venue");
+
+        let mut hbt = Backtest::builder().add_asset(asset).build().unwrap();
+        hbt.elapse(100).unwrap();
+        hbt.submit_buy_order(0, 1, 99.0, 1.0, TimeInForce::GTC, OrdType::Limit, true)
+            .unwrap();
+        assert_eq!(hbt.orders(0).get(&1).unwrap().status, Status::New);
+        assert_eq!(hbt.orders(0).get(&1).unwrap().req, Status::None);
+    }
+}
@@ -0,0 +1,62 @@
+/// What happens to the orders working at the exchange when a day ends.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum WorkingOrders {
+    /// The orders carry over to the next day, keeping their queue positions.
+    #[default]
+    Carry,
+    /// The exchange cancels all working orders at the last event of the day, as with the
+    /// day orders of a venue with a daily close.
+    CancelAtClose,
+}
+
+/// The handling of the boundaries between the daily files chained into one backtest run. The
+/// position, the balance, and the fees always carry over.
+///
+/// ```
+/// use hftbacktest::backtest::rollover::{DayRollover, WorkingOrders};
+///
+/// let rollover = DayRollover::new()
+///     .working_orders(WorkingOrders::CancelAtClose)
+///     .reset_depth(true);
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DayRollover {
+    working_orders: WorkingOrders,
+    reset_depth: bool,
+}
+
+impl DayRollover {
+    /// Constructs `DayRollover` that carries over the working orders and the market depth.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets what happens to the working orders at the end of the day. The default is
+    /// [`WorkingOrders::Carry`].
+    pub fn working_orders(self, working_orders: WorkingOrders) -> Self {
+        Self {
+            working_orders,
+            ..self
+        }
+    }
+
+    /// Sets whether to clear the market depth at the start of the next day, so that the levels
+    /// left over from the previous day don't linger when the next file starts with a fresh
+    /// snapshot. The default is `false`.
+    pub fn reset_depth(self, reset_depth: bool) -> Self {
+        Self {
+            reset_depth,
+            ..self
+        }
+    }
+
+    /// Returns `true` if the working orders are canceled at the end of the day.
+    pub fn cancels_at_close(&self) -> bool {
+        self.working_orders == WorkingOrders::CancelAtClose
+    }
+
+    /// Returns `true` if the market depth is cleared at the start of the next day.
+    pub fn resets_depth(&self) -> bool {
+        self.reset_depth
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    fmt::{Display, Formatter},
+    io::Error as IoError,
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    backtest::{
+        data::{DataSource, SharedDataView},
+        grid::BacktestGrid,
+        models::{BoxedLatency, LatencyModel},
+    },
+    depth::MarketDepth,
+    types::{Bot, Event},
+};
+
+type ModelFactory = Box<dyn Fn() -> BoxedLatency + Sync>;
+
+/// Runs the same strategy and data against several latency models in parallel, and compares the
+/// results against the first scenario as the baseline, to quantify the latency sensitivity of the
+/// strategy.
+///
+/// Each scenario's latency model is constructed by its closure on the worker thread, since some
+/// latency models, such as [`IntpOrderLatency`](crate::backtest::models::IntpOrderLatency), cannot
+/// be sent across threads. The strategy closure receives the latency model as [`BoxedLatency`],
+/// builds its own [`Backtest`](crate::backtest::Backtest) with it, and returns the
+/// [`ScenarioMetrics`] of the run. The data is shared as in [`BacktestGrid`].
+///
+/// ```ignore
+/// let report = LatencyScenarios::new()
+///     .scenario("1ms", || ConstantLatency::new(1_000_000, 1_000_000))
+///     .scenario("5ms", || ConstantLatency::new(5_000_000, 5_000_000))
+///     .scenario("real", || {
+///         IntpOrderLatency::new(
+///             vec![DataSource::File("latency_20240809.npz".to_string())],
+///             0,
+///         )
+///     })
+///     .data(vec![DataSource::File("btcusdt_20240809.npz".to_string())])
+///     .run(|latency, data| {
+///         let mut hbt = Backtest::builder()
+///             .add_asset(
+///                 L2AssetBuilder::new()
+///                     .data(vec![DataSource::Shared(data[0].clone())])
+///                     .latency_model(latency)
+///                     // ...
+///                     .build()?,
+///             )
+///             .build()?;
+///         strategy(&mut hbt)?;
+///         Ok::<_, BacktestError>(ScenarioMetrics::from_bot(&hbt))
+///     })?;
+/// println!("{report}");
+/// ```
+pub struct LatencyScenarios {
+    scenarios: Vec<(String, ModelFactory)>,
+    data: Vec<DataSource<Event>>,
+    num_threads: usize,
+}
+
+impl LatencyScenarios {
+    /// Constructs `LatencyScenarios` without any scenarios.
+    pub fn new() -> Self {
+        Self {
+            scenarios: Vec::new(),
+            data: Vec::new(),
+            num_threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        }
+    }
+
+    /// Adds a scenario with the closure constructing its latency model. The first scenario is the
+    /// baseline of the comparison.
+    pub fn scenario<F, LM>(mut self, name: &str, model: F) -> Self
+    where
+        F: Fn() -> LM + Sync + 'static,
+        LM: LatencyModel + Clone + 'static,
+    {
+        self.scenarios.push((
+            name.to_string(),
+            Box::new(move || BoxedLatency::new(model())),
+        ));
+        self
+    }
+
+    /// Sets the data shared by all runs, which is passed to the strategy closure in this order.
+    pub fn data(self, data: Vec<DataSource<Event>>) -> Self {
+        Self { data, ..self }
+    }
+
+    /// Sets the number of worker threads. The default value is the available parallelism.
+    pub fn num_threads(self, num_threads: usize) -> Self {
+        Self {
+            num_threads,
+            ..self
+        }
+    }
+
+    /// Loads the data and runs the strategy closure for each scenario, returning the comparative
+    /// report. If any run fails, the error of the first failed scenario is returned.
+    pub fn run<F, E>(self, strategy: F) -> Result<LatencyReport, E>
+    where
+        F: Fn(BoxedLatency, &[SharedDataView<Event>]) -> Result<ScenarioMetrics, E> + Sync,
+        E: From<IoError> + Send,
+    {
+        let (names, models): (Vec<_>, Vec<_>) = self.scenarios.into_iter().unzip();
+        let reports = BacktestGrid::new(models)
+            .data(self.data)
+            .num_threads(self.num_threads)
+            .run(|model, data| strategy(model(), data))?;
+        let scenarios = names
+            .into_iter()
+            .zip(reports)
+            .map(|(name, report)| {
+                Ok(ScenarioReport {
+                    name,
+                    metrics: report.result?,
+                    elapsed: report.elapsed,
+                })
+            })
+            .collect::<Result<_, E>>()?;
+        Ok(LatencyReport { scenarios })
+    }
+}
+
+impl Default for LatencyScenarios {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The results of a scenario run that are compared across the scenarios.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScenarioMetrics {
+    /// The final equity summed across all assets.
+    pub equity: f64,
+    /// The total fees paid.
+    pub fee: f64,
+    /// The total number of trades.
+    pub num_trades: i64,
+    /// The total trading volume.
+    pub trading_volume: f64,
+    /// The total trading value.
+    pub trading_value: f64,
+    /// The user-defined metrics, such as the fill ratio.
+    pub custom: Vec<(String, f64)>,
+}
+
+impl ScenarioMetrics {
+    /// Collects the metrics from the final state of the bot.
+    pub fn from_bot<MD, I>(bot: &I) -> Self
+    where
+        MD: MarketDepth,
+        I: Bot<MD>,
+    {
+        let portfolio = bot.portfolio();
+        let mut metrics = Self {
+            equity: portfolio.equity,
+            fee: portfolio.fee,
+            ..Default::default()
+        };
+        for asset_no in 0..bot.num_assets() {
+            let state_values = bot.state_values(asset_no);
+            metrics.num_trades += state_values.num_trades;
+            metrics.trading_volume += state_values.trading_volume;
+            metrics.trading_value += state_values.trading_value;
+        }
+        metrics
+    }
+
+    /// Adds a user-defined metric.
+    pub fn with(mut self, name: &str, value: f64) -> Self {
+        self.custom.push((name.to_string(), value));
+        self
+    }
+}
+
+/// The result of a scenario run of [`LatencyScenarios`].
+#[derive(Clone, Debug)]
+pub struct ScenarioReport {
+    /// The name of the scenario.
+    pub name: String,
+    /// The metrics returned by the strategy closure.
+    pub metrics: ScenarioMetrics,
+    /// The wall-clock time taken by the run.
+    pub elapsed: Duration,
+}
+
+/// The comparative report of [`LatencyScenarios`], in the order the scenarios were added.
+#[derive(Clone, Debug)]
+pub struct LatencyReport {
+    pub scenarios: Vec<ScenarioReport>,
+}
+
+impl LatencyReport {
+    /// Returns the report of the baseline scenario, which is the first one added.
+    pub fn baseline(&self) -> Option<&ScenarioReport> {
+        self.scenarios.first()
+    }
+
+    /// Returns the report of the scenario with the name.
+    pub fn get(&self, name: &str) -> Option<&ScenarioReport> {
+        self.scenarios.iter().find(|scenario| scenario.name == name)
+    }
+
+    /// Returns the equity of each scenario relative to the baseline.
+    pub fn equity_changes(&self) -> Vec<(&str, f64)> {
+        let baseline = self.baseline().map(|scenario| scenario.metrics.equity);
+        self.scenarios
+            .iter()
+            .map(|scenario| {
+                (
+                    scenario.name.as_str(),
+                    scenario.metrics.equity - baseline.unwrap_or(0.0),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Display for LatencyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<16} {:>16} {:>16} {:>12} {:>16} {:>16}",
+            "scenario", "equity", "vs baseline", "num_trades", "trading_volume", "fee"
+        )?;
+        if let Some(baseline) = self.baseline() {
+            for (name, _) in &baseline.metrics.custom {
+                write!(f, " {name:>16}")?;
+            }
+        }
+        for (scenario, (_, change)) in self.scenarios.iter().zip(self.equity_changes()) {
+            let metrics = &scenario.metrics;
+            write!(
+                f,
+                "\n{:<16} {:>16.6} {:>+16.6} {:>12} {:>16.6} {:>16.6}",
+                scenario.name,
+                metrics.equity,
+                change,
+                metrics.num_trades,
+                metrics.trading_volume,
+                metrics.fee
+            )?;
+            for (_, value) in &metrics.custom {
+                write!(f, " {value:>16.6}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LatencyScenarios, ScenarioMetrics};
+    use crate::{
+        backtest::{
+            assettype::LinearAsset,
+            data::{Data, DataSource},
+            models::{CommonFees, ConstantLatency, RiskAdverseQueueModel, TradingValueFeeModel},
+            Backtest,
+            BacktestError,
+            L2AssetBuilder,
+        },
+        depth::HashMapMarketDepth,
+        types::{
+            Bot,
+            Event,
+            OrdType,
+            TimeInForce,
+            BUY_EVENT,
+            DEPTH_EVENT,
+            EXCH_EVENT,
+            LOCAL_EVENT,
+        },
+    };
+
+    #[test]
+    fn test_latency_scenarios() {
+        let events: Vec<_> = (1..=10)
+            .map(|i| Event {
+                ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | BUY_EVENT,
+                exch_ts: i * 100,
+                local_ts: i * 100 + 10,
+                px: 100.0,
+                qty: 1.0,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            })
+            .collect();
+
+        let report = LatencyScenarios::new()
+            .scenario("fast", || ConstantLatency::new(10, 10))
+            .scenario("slow", || ConstantLatency::new(300, 200))
+            .data(vec![DataSource::Data(Data::from_data(&events))])
+            .num_threads(2)
+            .run(|latency, data| {
+                let mut hbt = Backtest::builder()
+                    .add_asset(
+                        L2AssetBuilder::new()
+                            .data(vec![DataSource::Shared(data[0].clone())])
+                            .latency_model(latency)
+                            .asset_type(LinearAsset::new(1.0))
+                            .fee_model(TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)))
+                            .queue_model(RiskAdverseQueueModel::new())
+                            .depth(|| HashMapMarketDepth::new(0.1, 1.0))
+                            .build()
+                            .unwrap(),
+                    )
+                    .build()
+                    .unwrap();
+                hbt.elapse(200)?;
+                let submitted_at = hbt.current_timestamp();
+                hbt.submit_buy_order(0, 1, 99.0, 1.0, TimeInForce::GTC, OrdType::Limit, true)?;
+                let round_trip = hbt.current_timestamp() - submitted_at;
+                Ok::<_, BacktestError>(
+                    ScenarioMetrics::from_bot(&hbt).with("round_trip", round_trip as f64),
+                )
+            })
+            .unwrap();
+
+        assert_eq!(report.baseline().unwrap().name, "fast");
+        assert_eq!(report.get("fast").unwrap().metrics.custom[0].1, 20.0);
+        assert_eq!(report.get("slow").unwrap().metrics.custom[0].1, 500.0);
+        assert_eq!(report.equity_changes(), vec![("fast", 0.0), ("slow", 0.0)]);
+        assert!(report.to_string().contains("round_trip"));
+    }
+}
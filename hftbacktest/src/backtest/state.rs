@@ -1,8 +1,71 @@
 use crate::{
-    backtest::{assettype::AssetType, models::FeeModel},
-    types::{Order, StateValues},
+    backtest::{
+        assettype::AssetType,
+        checkpoint::{CheckpointReader, CheckpointWriter},
+        models::FeeModel,
+        BacktestError,
+    },
+    depth::{MarketDepth, INVALID_MAX, INVALID_MIN},
+    types::{Order, Portfolio, PositionSide, Side, StateValues},
 };
 
+/// Determines whether the position can go negative, which matters for spot assets that must be
+/// borrowed to be sold short.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ShortSelling {
+    /// Short positions are allowed without any cost, as with derivatives.
+    #[default]
+    Allowed,
+    /// Sell orders that could make the position negative, counting the open sell orders, are
+    /// rejected by the exchange.
+    Disallowed,
+    /// Short positions are allowed, but the borrow fee is charged on the value of the short
+    /// position, valued at the mid price, at `rate` at every `interval` boundary. The `interval`
+    /// must be positive, and its unit should be the same as the data's timestamp unit.
+    Borrow { rate: f64, interval: i64 },
+}
+
+/// Determines when the trading fees are settled in the cash accounting, which affects the equity
+/// and the margin available between settlements. Until settled, the fees are held as unsettled and
+/// are not included in [`StateValues::fee`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FeeSettlement {
+    /// The fees are settled at fill time.
+    #[default]
+    Immediate,
+    /// The fees are settled at every `interval` boundary, such as the end of each day. The
+    /// `interval` must be positive, and its unit should be the same as the data's timestamp unit.
+    Periodic { interval: i64 },
+    /// The fees are settled at the start of every calendar month in UTC, as with rebate programs.
+    /// The timestamps must be in nanoseconds.
+    Monthly,
+}
+
+impl FeeSettlement {
+    fn period(&self, timestamp: i64) -> i64 {
+        match self {
+            FeeSettlement::Immediate => timestamp,
+            FeeSettlement::Periodic { interval } => timestamp.div_euclid(*interval),
+            FeeSettlement::Monthly => month_index(timestamp),
+        }
+    }
+}
+
+/// Returns the number of months since January 1970 of the nanosecond timestamp, using the
+/// civil-from-days algorithm.
+fn month_index(timestamp: i64) -> i64 {
+    let days = timestamp.div_euclid(86_400_000_000_000);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year - 1970) * 12 + month - 1
+}
+
 #[derive(Debug)]
 pub struct State<AT, FM>
 where
@@ -12,6 +75,11 @@ where
     pub state_values: StateValues,
     pub asset_type: AT,
     pub fee_model: FM,
+    pub short_selling: ShortSelling,
+    borrow_period: Option<i64>,
+    pub fee_settlement: FeeSettlement,
+    unsettled_fee: f64,
+    settlement_period: Option<i64>,
 }
 
 impl<AT, FM> State<AT, FM>
@@ -28,21 +96,136 @@ where
                 num_trades: 0,
                 trading_volume: 0.0,
                 trading_value: 0.0,
+                long_position: 0.0,
+                short_position: 0.0,
+                long_balance: 0.0,
+                short_balance: 0.0,
             },
             fee_model,
             asset_type,
+            short_selling: ShortSelling::Allowed,
+            borrow_period: None,
+            fee_settlement: FeeSettlement::Immediate,
+            unsettled_fee: 0.0,
+            settlement_period: None,
+        }
+    }
+
+    /// Sets when the trading fees are settled. The default value is [`FeeSettlement::Immediate`].
+    pub fn fee_settlement(self, fee_settlement: FeeSettlement) -> Self {
+        Self {
+            fee_settlement,
+            ..self
         }
     }
 
+    /// Returns the trading fees that have been charged but not yet settled.
+    pub fn unsettled_fee(&self) -> f64 {
+        self.unsettled_fee
+    }
+
+    /// Settles the unsettled trading fees if a [`FeeSettlement`] boundary has been crossed since
+    /// the last call.
     #[inline]
-    pub fn apply_fill(&mut self, order: &Order) {
+    pub fn settle_fee(&mut self, timestamp: i64) {
+        if self.fee_settlement == FeeSettlement::Immediate {
+            return;
+        }
+        let period = self.fee_settlement.period(timestamp);
+        match self.settlement_period {
+            Some(prev_period) if period > prev_period => {
+                self.state_values.fee += self.unsettled_fee;
+                self.unsettled_fee = 0.0;
+                self.settlement_period = Some(period);
+            }
+            Some(_) => {}
+            None => {
+                self.settlement_period = Some(period);
+            }
+        }
+    }
+
+    /// Sets how short positions are handled. The default value is [`ShortSelling::Allowed`].
+    pub fn short_selling(self, short_selling: ShortSelling) -> Self {
+        Self {
+            short_selling,
+            ..self
+        }
+    }
+
+    /// Returns whether the order is allowed under [`ShortSelling::Disallowed`], given the quantity
+    /// of the open sell orders, which could also be filled.
+    pub fn allows_short(&self, order: &Order, open_sell_qty: f64) -> bool {
+        if self.short_selling != ShortSelling::Disallowed || order.side != Side::Sell {
+            return true;
+        }
+        let remaining = self.state_values.position - open_sell_qty - order.leaves_qty;
+        // Tolerates the floating-point error accumulated in the position.
+        remaining >= -order.qty * 1e-9
+    }
+
+    /// Charges the borrow fee on the short position for each [`ShortSelling::Borrow`] interval
+    /// boundary crossed since the last call.
+    #[inline]
+    pub fn accrue_borrow_fee<MD: MarketDepth>(&mut self, timestamp: i64, depth: &MD) {
+        let ShortSelling::Borrow { rate, interval } = self.short_selling else {
+            return;
+        };
+        let period = timestamp.div_euclid(interval);
+        match self.borrow_period {
+            Some(prev_period) if period > prev_period => {
+                if self.state_values.position < 0.0
+                    && depth.best_bid_tick() != INVALID_MIN
+                    && depth.best_ask_tick() != INVALID_MAX
+                {
+                    let mid = (depth.best_bid() + depth.best_ask()) / 2.0;
+                    self.state_values.fee +=
+                        rate * (period - prev_period) as f64 * self.position_value(mid).abs();
+                }
+                self.borrow_period = Some(period);
+            }
+            Some(_) => {}
+            None => {
+                self.borrow_period = Some(period);
+            }
+        }
+    }
+
+    /// Applies the fill of the order and returns the fee charged for it.
+    #[inline]
+    pub fn apply_fill(&mut self, order: &Order) -> f64 {
         let amount = self.asset_type.amount(order.exec_price(), order.exec_qty);
-        self.state_values.position += order.exec_qty * AsRef::<f64>::as_ref(&order.side);
-        self.state_values.balance -= amount * AsRef::<f64>::as_ref(&order.side);
-        self.state_values.fee += self.fee_model.amount(order, amount);
+        let sign = *AsRef::<f64>::as_ref(&order.side);
+        self.state_values.position += order.exec_qty * sign;
+        self.state_values.balance -= amount * sign;
+        // In hedge mode, the fill is also attributed to its position side.
+        match order.position_side {
+            PositionSide::Long => {
+                self.state_values.long_position += order.exec_qty * sign;
+                self.state_values.long_balance -= amount * sign;
+            }
+            PositionSide::Short => {
+                self.state_values.short_position -= order.exec_qty * sign;
+                self.state_values.short_balance -= amount * sign;
+            }
+            PositionSide::Both | PositionSide::Unsupported => {}
+        }
+        let fee = self.fee_model.amount(order, amount);
+        if self.fee_settlement == FeeSettlement::Immediate {
+            self.state_values.fee += fee;
+        } else {
+            self.unsettled_fee += fee;
+        }
         self.state_values.num_trades += 1;
         self.state_values.trading_volume += order.exec_qty;
         self.state_values.trading_value += amount;
+        fee
+    }
+
+    /// Applies an additional fee, such as the liquidation fee.
+    #[inline]
+    pub fn apply_fee(&mut self, fee: f64) {
+        self.state_values.fee += fee;
     }
 
     #[inline]
@@ -55,8 +238,262 @@ where
         )
     }
 
+    /// Returns the value of the position in the settlement currency.
+    #[inline]
+    pub fn position_value(&self, price: f64) -> f64 {
+        self.asset_type
+            .position_value(price, self.state_values.position)
+    }
+
+    /// Returns the PnL of the position side in hedge mode, including unrealized PnL but excluding
+    /// fees, which are accounted for in total only.
+    pub fn side_pnl(&self, position_side: PositionSide, mid: f64) -> f64 {
+        let balance = match position_side {
+            PositionSide::Long => self.state_values.long_balance,
+            PositionSide::Short => self.state_values.short_balance,
+            PositionSide::Both | PositionSide::Unsupported => {
+                self.state_values.balance
+                    - self.state_values.long_balance
+                    - self.state_values.short_balance
+            }
+        };
+        let position = match position_side {
+            PositionSide::Both | PositionSide::Unsupported => {
+                self.state_values.position - self.state_values.long_position
+                    + self.state_values.short_position
+            }
+            _ => self.state_values.side_position(position_side),
+        };
+        self.asset_type.equity(mid, balance, position, 0.0)
+    }
+
+    /// Returns the asset's share of the [`Portfolio`], valued at the mid price. Without a
+    /// position, the values remain valid even if the market depth is empty.
+    pub fn portfolio<MD: MarketDepth>(&self, depth: &MD) -> Portfolio {
+        let position = self.state_values.position;
+        let price = if position == 0.0 {
+            // The price doesn't affect the values without a position.
+            1.0
+        } else if depth.best_bid_tick() == INVALID_MIN || depth.best_ask_tick() == INVALID_MAX {
+            f64::NAN
+        } else {
+            (depth.best_bid() + depth.best_ask()) / 2.0
+        };
+        let position_value = self.position_value(price);
+        Portfolio {
+            equity: self.equity(price),
+            gross_exposure: position_value.abs(),
+            net_exposure: position_value,
+            fee: self.state_values.fee,
+        }
+    }
+
     #[inline]
     pub fn values(&self) -> &StateValues {
         &self.state_values
     }
+
+    /// Writes the state values and the pending fee accruals into the checkpoint.
+    pub fn save_checkpoint(&self, writer: &mut CheckpointWriter) -> Result<(), BacktestError> {
+        let values = &self.state_values;
+        writer.write(&(
+            values.position,
+            values.balance,
+            values.fee,
+            values.num_trades,
+            values.trading_volume,
+            values.trading_value,
+        ))?;
+        writer.write(&(
+            values.long_position,
+            values.short_position,
+            values.long_balance,
+            values.short_balance,
+        ))?;
+        writer.write(&(
+            self.borrow_period,
+            self.unsettled_fee,
+            self.settlement_period,
+        ))
+    }
+
+    /// Restores the state values and the pending fee accruals from the checkpoint.
+    pub fn load_checkpoint(&mut self, reader: &mut CheckpointReader) -> Result<(), BacktestError> {
+        let (position, balance, fee, num_trades, trading_volume, trading_value) = reader.read()?;
+        let (long_position, short_position, long_balance, short_balance) = reader.read()?;
+        self.state_values = StateValues {
+            position,
+            balance,
+            fee,
+            num_trades,
+            trading_volume,
+            trading_value,
+            long_position,
+            short_position,
+            long_balance,
+            short_balance,
+        };
+        (
+            self.borrow_period,
+            self.unsettled_fee,
+            self.settlement_period,
+        ) = reader.read()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{month_index, FeeSettlement, ShortSelling, State};
+    use crate::{
+        backtest::{
+            assettype::LinearAsset,
+            models::{CommonFees, TradingValueFeeModel},
+        },
+        depth::{HashMapMarketDepth, L2MarketDepth},
+        types::{OrdType, Order, PositionSide, Side, TimeInForce},
+    };
+
+    fn new_state(
+        short_selling: ShortSelling,
+    ) -> State<LinearAsset, TradingValueFeeModel<CommonFees>> {
+        State::new(
+            LinearAsset::new(1.0),
+            TradingValueFeeModel::new(CommonFees::new(0.0, 0.0)),
+        )
+        .short_selling(short_selling)
+    }
+
+    #[test]
+    fn test_short_selling() {
+        let mut state = new_state(ShortSelling::Disallowed);
+        let mut order = Order::new(
+            1,
+            1000,
+            0.1,
+            1.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        order.exec_price_tick = 1000;
+        order.exec_qty = 1.0;
+        state.apply_fill(&order);
+
+        let order = Order::new(
+            2,
+            1000,
+            0.1,
+            0.6,
+            Side::Sell,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        assert!(state.allows_short(&order, 0.0));
+        assert!(state.allows_short(&order, 0.4));
+        assert!(!state.allows_short(&order, 0.5));
+
+        let mut depth = HashMapMarketDepth::new(0.1, 1.0);
+        depth.update_bid_depth(99.9, 1.0, 0);
+        depth.update_ask_depth(100.1, 1.0, 0);
+
+        let mut state = new_state(ShortSelling::Borrow {
+            rate: 0.001,
+            interval: 100,
+        });
+        let mut order = Order::new(
+            1,
+            1000,
+            0.1,
+            2.0,
+            Side::Sell,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        order.exec_price_tick = 1000;
+        order.exec_qty = 2.0;
+        state.apply_fill(&order);
+        assert!(state.allows_short(&order, 10.0));
+
+        state.accrue_borrow_fee(50, &depth);
+        assert_eq!(state.state_values.fee, 0.0);
+        state.accrue_borrow_fee(99, &depth);
+        assert_eq!(state.state_values.fee, 0.0);
+        state.accrue_borrow_fee(310, &depth);
+        assert!((state.state_values.fee - 3.0 * 0.001 * 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fee_settlement() {
+        const DAY: i64 = 86_400_000_000_000;
+        assert_eq!(month_index(0), 0);
+        assert_eq!(month_index(31 * DAY - 1), 0);
+        assert_eq!(month_index(31 * DAY), 1);
+        // 2024-02-29 and 2024-03-01
+        assert_eq!(month_index(19_782 * DAY), 649);
+        assert_eq!(month_index(19_783 * DAY), 650);
+
+        let mut state = new_state(ShortSelling::Allowed)
+            .fee_settlement(FeeSettlement::Periodic { interval: 100 });
+        state.fee_model = TradingValueFeeModel::new(CommonFees::new(-0.001, 0.001));
+        state.settle_fee(10);
+
+        let mut order = Order::new(
+            1,
+            1000,
+            0.1,
+            1.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        order.exec_price_tick = 1000;
+        order.exec_qty = 1.0;
+        order.maker = true;
+        state.apply_fill(&order);
+        assert_eq!(state.state_values.fee, 0.0);
+        assert!((state.unsettled_fee() + 0.1).abs() < 1e-9);
+
+        state.settle_fee(99);
+        assert_eq!(state.state_values.fee, 0.0);
+        state.settle_fee(100);
+        assert!((state.state_values.fee + 0.1).abs() < 1e-9);
+        assert_eq!(state.unsettled_fee(), 0.0);
+    }
+
+    #[test]
+    fn test_hedge_mode() {
+        let mut state = new_state(ShortSelling::Allowed);
+        let fill = |side, position_side, price_tick, qty| {
+            let mut order = Order::new(
+                0,
+                price_tick,
+                0.1,
+                qty,
+                side,
+                OrdType::Limit,
+                TimeInForce::GTC,
+            );
+            order.position_side = position_side;
+            order.exec_price_tick = price_tick;
+            order.exec_qty = qty;
+            order
+        };
+        state.apply_fill(&fill(Side::Buy, PositionSide::Long, 1000, 2.0));
+        state.apply_fill(&fill(Side::Sell, PositionSide::Short, 1000, 1.5));
+        assert_eq!(state.state_values.position, 0.5);
+        assert_eq!(state.state_values.long_position, 2.0);
+        assert_eq!(state.state_values.short_position, 1.5);
+        assert_eq!(state.state_values.gross_position(), 3.5);
+
+        state.apply_fill(&fill(Side::Sell, PositionSide::Long, 1010, 1.0));
+        assert_eq!(state.state_values.long_position, 1.0);
+        assert!((state.state_values.position + 0.5).abs() < 1e-9);
+
+        // The long side gains 1.0 from the closed quantity and 1.0 from the open quantity, while
+        // the short side loses 3.0.
+        assert!((state.side_pnl(PositionSide::Long, 102.0) - 3.0).abs() < 1e-9);
+        assert!((state.side_pnl(PositionSide::Short, 102.0) + 3.0).abs() < 1e-9);
+        assert!(state.equity(102.0).abs() < 1e-9);
+    }
 }
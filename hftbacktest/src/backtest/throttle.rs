@@ -0,0 +1,220 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
+
+use crate::types::{Order, OrderId, Status};
+
+/// Determines how the exchange handles an order request that exceeds the [`Throttle`] limits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThrottleAction {
+    /// The order request is rejected.
+    Reject,
+    /// The order request is accepted, but the given penalty fee is charged for it.
+    Penalty(f64),
+}
+
+pub(crate) enum ThrottleDecision {
+    Accept,
+    Reject,
+    Penalty(f64),
+}
+
+/// Simulates the exchange's limits on the frequency of order requests, such as the minimum order
+/// lifetime before it can be canceled and the message-to-trade ratio. Every new and cancel request
+/// counts as a message. The limits on the number of requests per time window are simulated by
+/// [`RateLimiter`](crate::backtest::ratelimit::RateLimiter).
+///
+/// The throttle is shared by the local and the exchange of an asset, so that the penalty fee
+/// charged by the exchange is also reflected in the local state.
+/// [`Throttle::message_to_trade_ratio`] is the ratio the venue evaluates, which can be compared
+/// with the one observed in live trading, and [`Throttle::penalty`] is the total penalty fee.
+///
+/// ```
+/// use hftbacktest::backtest::throttle::{Throttle, ThrottleAction};
+///
+/// // Rejects the cancels of orders younger than 100ms, and the messages beyond a
+/// // message-to-trade ratio of 50 after the first 1000 messages.
+/// let throttle = Throttle::new(ThrottleAction::Reject)
+///     .min_order_lifetime(100_000_000)
+///     .max_message_to_trade_ratio(50.0, 1000);
+/// ```
+#[derive(Clone)]
+pub struct Throttle(Rc<RefCell<ThrottleInner>>);
+
+struct ThrottleInner {
+    action: ThrottleAction,
+    min_order_lifetime: Option<i64>,
+    max_ratio: Option<(f64, u64)>,
+    new_orders: VecDeque<(i64, OrderId)>,
+    young_orders: HashSet<OrderId>,
+    num_messages: u64,
+    num_trades: i64,
+    num_throttled: u64,
+    penalty: f64,
+    pending_penalties: HashMap<(OrderId, i64), f64>,
+}
+
+impl Throttle {
+    /// Constructs `Throttle` with no limits, which only tracks the statistics until the limits are
+    /// set.
+    pub fn new(action: ThrottleAction) -> Self {
+        Self(Rc::new(RefCell::new(ThrottleInner {
+            action,
+            min_order_lifetime: None,
+            max_ratio: None,
+            new_orders: Default::default(),
+            young_orders: Default::default(),
+            num_messages: 0,
+            num_trades: 0,
+            num_throttled: 0,
+            penalty: 0.0,
+            pending_penalties: Default::default(),
+        })))
+    }
+
+    /// Sets the minimum time an order must rest before it can be canceled, which limits the cancel
+    /// frequency per order. The unit should be the same as the data's timestamp unit.
+    pub fn min_order_lifetime(self, min_order_lifetime: i64) -> Self {
+        self.0.borrow_mut().min_order_lifetime = Some(min_order_lifetime);
+        self
+    }
+
+    /// Sets the maximum ratio of the number of messages to the number of trades, which is applied
+    /// once the number of messages reaches `min_messages`.
+    pub fn max_message_to_trade_ratio(self, max_ratio: f64, min_messages: u64) -> Self {
+        self.0.borrow_mut().max_ratio = Some((max_ratio, min_messages));
+        self
+    }
+
+    /// Returns the number of messages sent to the exchange, including the throttled ones.
+    pub fn num_messages(&self) -> u64 {
+        self.0.borrow().num_messages
+    }
+
+    /// Returns the number of messages that exceeded the limits.
+    pub fn num_throttled(&self) -> u64 {
+        self.0.borrow().num_throttled
+    }
+
+    /// Returns the ratio of the number of messages to the number of trades. If there is no trade,
+    /// the number of messages is returned.
+    pub fn message_to_trade_ratio(&self) -> f64 {
+        let inner = self.0.borrow();
+        inner.num_messages as f64 / inner.num_trades.max(1) as f64
+    }
+
+    /// Returns the total penalty fee charged.
+    pub fn penalty(&self) -> f64 {
+        self.0.borrow().penalty
+    }
+
+    /// Counts the order request received by the exchange and decides whether it exceeds the limits.
+    pub(crate) fn on_message(
+        &self,
+        order: &Order,
+        timestamp: i64,
+        num_trades: i64,
+    ) -> ThrottleDecision {
+        let mut inner = self.0.borrow_mut();
+        inner.num_messages += 1;
+        inner.num_trades = num_trades;
+
+        let mut exceeded = false;
+        if let Some(min_order_lifetime) = inner.min_order_lifetime {
+            // Only the orders younger than the minimum lifetime need to be tracked.
+            while let Some(&(order_timestamp, order_id)) = inner.new_orders.front() {
+                if timestamp - order_timestamp < min_order_lifetime {
+                    break;
+                }
+                inner.new_orders.pop_front();
+                inner.young_orders.remove(&order_id);
+            }
+            if order.req == Status::New {
+                inner.new_orders.push_back((timestamp, order.order_id));
+                inner.young_orders.insert(order.order_id);
+            } else if order.req == Status::Canceled {
+                exceeded |= inner.young_orders.contains(&order.order_id);
+            }
+        }
+        if let Some((max_ratio, min_messages)) = inner.max_ratio {
+            let ratio = inner.num_messages as f64 / inner.num_trades.max(1) as f64;
+            exceeded |= inner.num_messages >= min_messages && ratio > max_ratio;
+        }
+
+        if !exceeded {
+            return ThrottleDecision::Accept;
+        }
+        inner.num_throttled += 1;
+        match inner.action {
+            ThrottleAction::Reject => ThrottleDecision::Reject,
+            ThrottleAction::Penalty(fee) => {
+                inner.penalty += fee;
+                *inner
+                    .pending_penalties
+                    .entry((order.order_id, timestamp))
+                    .or_insert(0.0) += fee;
+                ThrottleDecision::Penalty(fee)
+            }
+        }
+    }
+
+    /// Takes the penalty fee charged for the order request that the response corresponds to, so
+    /// that the local can apply it when receiving the response.
+    pub(crate) fn take_penalty(&self, order: &Order) -> Option<f64> {
+        self.0
+            .borrow_mut()
+            .pending_penalties
+            .remove(&(order.order_id, order.exch_timestamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Throttle, ThrottleAction, ThrottleDecision};
+    use crate::{test_utils::request, types::Status};
+
+    #[test]
+    fn test_min_order_lifetime() {
+        let throttle = Throttle::new(ThrottleAction::Reject).min_order_lifetime(15);
+        let new = request(1, Status::New);
+        let cancel = request(1, Status::Canceled);
+
+        assert!(matches!(
+            throttle.on_message(&new, 0, 0),
+            ThrottleDecision::Accept
+        ));
+        assert!(matches!(
+            throttle.on_message(&cancel, 10, 0),
+            ThrottleDecision::Reject
+        ));
+        assert!(matches!(
+            throttle.on_message(&cancel, 20, 0),
+            ThrottleDecision::Accept
+        ));
+        assert_eq!(throttle.num_messages(), 3);
+        assert_eq!(throttle.num_throttled(), 1);
+    }
+
+    #[test]
+    fn test_message_to_trade_ratio_penalty() {
+        let throttle =
+            Throttle::new(ThrottleAction::Penalty(0.5)).max_message_to_trade_ratio(2.0, 4);
+        for i in 0..3 {
+            assert!(matches!(
+                throttle.on_message(&request(i, Status::New), i as i64, 1),
+                ThrottleDecision::Accept
+            ));
+        }
+        let mut order = request(3, Status::New);
+        assert!(matches!(
+            throttle.on_message(&order, 3, 1),
+            ThrottleDecision::Penalty(_)
+        ));
+        order.exch_timestamp = 3;
+        assert_eq!(throttle.take_penalty(&order), Some(0.5));
+        assert_eq!(throttle.take_penalty(&order), None);
+        assert_eq!(throttle.message_to_trade_ratio(), 4.0);
+    }
+}
@@ -0,0 +1,101 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+/// The processing speed of a backtest, so that the performance regressions across releases
+/// become visible and the hardware can be sized for the data volume.
+///
+/// The wall-clock time includes the time spent in the strategy, as it's measured from the first
+/// elapse of the backtest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Throughput {
+    /// The number of feed events processed, counting once for each of the local and the exchange
+    /// processors.
+    pub events: u64,
+    /// The wall-clock time elapsed.
+    pub elapsed: Duration,
+    /// The simulated time elapsed in nanoseconds.
+    pub simulated: i64,
+}
+
+impl Throughput {
+    /// Returns the number of feed events processed per wall-clock second.
+    pub fn events_per_sec(&self) -> f64 {
+        self.events as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Returns how many times faster than real time the backtest runs.
+    pub fn speedup(&self) -> f64 {
+        self.simulated as f64 / self.elapsed.as_nanos() as f64
+    }
+}
+
+impl Display for Throughput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} events in {:.3}s: {:.0} events/s, {:.1}x real time",
+            self.events,
+            self.elapsed.as_secs_f64(),
+            self.events_per_sec(),
+            self.speedup()
+        )
+    }
+}
+
+/// Counts the processed events since the first elapse of the backtest.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+pub(crate) struct ThroughputMeter {
+    events: u64,
+    start: Option<(Instant, i64)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ThroughputMeter {
+    /// Starts the measurement at the current timestamp if it's not started yet. The timestamp is
+    /// `i64::MAX` until the backtest is initialized.
+    #[inline]
+    pub fn start(&mut self, timestamp: i64) {
+        if self.start.is_none() && timestamp != i64::MAX {
+            self.start = Some((Instant::now(), timestamp));
+        }
+    }
+
+    #[inline]
+    pub fn count(&mut self) {
+        self.events += 1;
+    }
+
+    pub fn throughput(&self, timestamp: i64) -> Throughput {
+        match self.start {
+            Some((instant, start_timestamp)) => Throughput {
+                events: self.events,
+                elapsed: instant.elapsed(),
+                simulated: timestamp - start_timestamp,
+            },
+            None => Throughput {
+                events: 0,
+                elapsed: Duration::ZERO,
+                simulated: 0,
+            },
+        }
+    }
+}
+
+/// Measures nothing, since the wall clock is unavailable on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+#[derive(Default)]
+pub(crate) struct ThroughputMeter;
+
+#[cfg(target_arch = "wasm32")]
+impl ThroughputMeter {
+    #[inline]
+    pub fn start(&mut self, _timestamp: i64) {}
+
+    #[inline]
+    pub fn count(&mut self) {}
+}
@@ -0,0 +1,180 @@
+use bincode::{Decode, Encode};
+
+use crate::types::{Event, BUY_EVENT, SELL_EVENT};
+
+/// An OHLCV bar aggregated from the market trades over a time interval.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Decode, Encode)]
+pub struct Bar {
+    /// The start timestamp of the interval, in the exchange timestamp.
+    pub timestamp: i64,
+    /// The price of the first trade.
+    pub open: f64,
+    /// The highest traded price.
+    pub high: f64,
+    /// The lowest traded price.
+    pub low: f64,
+    /// The price of the last trade.
+    pub close: f64,
+    /// The traded quantity.
+    pub volume: f64,
+    /// The traded quantity initiated by buyers.
+    pub buy_volume: f64,
+    /// The traded quantity initiated by sellers.
+    pub sell_volume: f64,
+    /// The number of trades.
+    pub num_trades: u64,
+}
+
+impl Bar {
+    fn new(timestamp: i64, trade: &Event) -> Self {
+        let mut bar = Self {
+            timestamp,
+            open: trade.px,
+            high: trade.px,
+            low: trade.px,
+            close: trade.px,
+            volume: 0.0,
+            buy_volume: 0.0,
+            sell_volume: 0.0,
+            num_trades: 0,
+        };
+        bar.update(trade);
+        bar
+    }
+
+    fn update(&mut self, trade: &Event) {
+        self.high = self.high.max(trade.px);
+        self.low = self.low.min(trade.px);
+        self.close = trade.px;
+        self.volume += trade.qty;
+        if trade.is(BUY_EVENT) {
+            self.buy_volume += trade.qty;
+        } else if trade.is(SELL_EVENT) {
+            self.sell_volume += trade.qty;
+        }
+        self.num_trades += 1;
+    }
+
+    /// Returns the volume imbalance, `(buy_volume - sell_volume) / volume`, which ranges from -1
+    /// to 1. The trades without the initiator's side count only toward the volume.
+    pub fn volume_imbalance(&self) -> f64 {
+        if self.volume > 0.0 {
+            (self.buy_volume - self.sell_volume) / self.volume
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Incrementally aggregates the market trades into [`Bar`]s of a fixed interval, keeping the
+/// latest bars up to the capacity.
+#[derive(Clone, Debug, Decode, Encode)]
+pub struct BarBuilder {
+    interval: i64,
+    capacity: usize,
+    bars: Vec<Bar>,
+}
+
+impl BarBuilder {
+    /// Constructs a `BarBuilder`.
+    ///
+    /// * `interval` - The interval of the bars, in the same unit as the timestamps.
+    /// * `capacity` - The number of the latest bars to keep.
+    pub fn new(interval: i64, capacity: usize) -> Self {
+        assert!(interval > 0, "the interval must be positive");
+        Self {
+            interval,
+            capacity,
+            bars: Vec::with_capacity(capacity.max(1) * 2),
+        }
+    }
+
+    /// Returns the interval of the bars.
+    pub fn interval(&self) -> i64 {
+        self.interval
+    }
+
+    /// Updates the bars with the trade. An interval without trades produces no bar, and a trade
+    /// that arrives out of order is aggregated into the latest bar.
+    pub fn update(&mut self, trade: &Event) {
+        let timestamp = trade.exch_ts - trade.exch_ts.rem_euclid(self.interval);
+        match self.bars.last_mut() {
+            Some(bar) if bar.timestamp >= timestamp => bar.update(trade),
+            _ => {
+                // Drops the old bars in bulk, so that the latest bars remain contiguous.
+                if self.bars.len() >= self.capacity.max(1) * 2 {
+                    self.bars.drain(..self.bars.len() - self.capacity);
+                }
+                self.bars.push(Bar::new(timestamp, trade));
+            }
+        }
+    }
+
+    /// Returns the latest bars in chronological order. The last bar is still being formed until a
+    /// trade in a later interval arrives.
+    pub fn bars(&self) -> &[Bar] {
+        &self.bars[self.bars.len().saturating_sub(self.capacity)..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BarBuilder;
+    use crate::{
+        test_utils::trade,
+        types::{BUY_EVENT, SELL_EVENT},
+    };
+
+    #[test]
+    fn test_aggregate_trades_into_bar() {
+        let mut builder = BarBuilder::new(100, 2);
+        builder.update(&trade(110, BUY_EVENT, 100.0, 1.0));
+        builder.update(&trade(150, SELL_EVENT, 99.5, 2.0));
+        builder.update(&trade(190, BUY_EVENT, 100.5, 1.0));
+
+        let bars = builder.bars();
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].timestamp, 100);
+        assert_eq!(
+            (bars[0].open, bars[0].high, bars[0].low, bars[0].close),
+            (100.0, 100.5, 99.5, 100.5)
+        );
+        assert_eq!(bars[0].volume, 4.0);
+        assert_eq!(bars[0].num_trades, 3);
+        assert_eq!(bars[0].volume_imbalance(), 0.0);
+    }
+
+    #[test]
+    fn test_empty_interval_and_late_trade() {
+        let mut builder = BarBuilder::new(100, 3);
+        builder.update(&trade(110, BUY_EVENT, 100.0, 1.0));
+        // The interval from 200 has no trades, so it produces no bar.
+        builder.update(&trade(310, SELL_EVENT, 101.0, 1.0));
+        // A trade arriving out of order is aggregated into the latest bar.
+        builder.update(&trade(290, SELL_EVENT, 101.5, 1.0));
+        builder.update(&trade(420, BUY_EVENT, 102.0, 1.0));
+
+        let bars = builder.bars();
+        assert_eq!(
+            bars.iter().map(|bar| bar.timestamp).collect::<Vec<_>>(),
+            [100, 300, 400]
+        );
+        assert_eq!(bars[1].num_trades, 2);
+        assert_eq!(bars[1].high, 101.5);
+    }
+
+    #[test]
+    fn test_keep_latest_bars_up_to_capacity() {
+        let mut builder = BarBuilder::new(100, 2);
+        for ts in [110, 310, 420, 530] {
+            builder.update(&trade(ts, BUY_EVENT, 100.0, 1.0));
+        }
+
+        let bars = builder.bars();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].timestamp, 400);
+        assert_eq!(bars[1].timestamp, 500);
+        assert_eq!(bars[1].volume_imbalance(), 1.0);
+    }
+}
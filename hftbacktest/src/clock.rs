@@ -0,0 +1,103 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One second in nanoseconds.
+pub const SECOND: i64 = 1_000_000_000;
+
+/// One minute in nanoseconds.
+pub const MINUTE: i64 = 60 * SECOND;
+
+/// One hour in nanoseconds.
+pub const HOUR: i64 = 60 * MINUTE;
+
+/// One day in nanoseconds.
+pub const DAY: i64 = 24 * HOUR;
+
+/// Provides the current timestamp in nanoseconds since the Unix epoch.
+///
+/// Every [`Bot`](crate::types::Bot) is a clock, which is the simulated wall clock driven by the
+/// data timestamps in backtesting and the system clock in live trading, so that the absolute-time
+/// actions are scheduled identically in both.
+///
+/// ```
+/// use hftbacktest::{
+///     clock::{Clock, HOUR, MINUTE},
+///     prelude::*,
+/// };
+///
+/// fn rebalance_daily<MD, I>(hbt: &mut I) -> Result<(), I::Error>
+/// where
+///     MD: MarketDepth,
+///     I: Bot<MD>,
+/// {
+///     // Acts at 14:30:00.000 UTC every day.
+///     while hbt.elapse_until(hbt.next_time_of_day(14 * HOUR + 30 * MINUTE))? {
+///         // Rebalances here.
+///     }
+///     Ok(())
+/// }
+/// ```
+pub trait Clock {
+    /// Returns the current timestamp.
+    fn now(&self) -> i64;
+
+    /// Returns the time elapsed since midnight UTC.
+    fn time_of_day(&self) -> i64 {
+        self.now().rem_euclid(DAY)
+    }
+
+    /// Returns the timestamp of the next time of day in UTC, which is today's if it's now or later.
+    fn next_time_of_day(&self, time_of_day: i64) -> i64 {
+        let now = self.now();
+        let at = now - now.rem_euclid(DAY) + time_of_day.rem_euclid(DAY);
+        if at >= now {
+            at
+        } else {
+            at + DAY
+        }
+    }
+}
+
+/// The wall clock of the system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, DAY, HOUR, MINUTE, SECOND};
+
+    struct FixedClock(i64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> i64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_next_time_of_day() {
+        // 2024-01-02 09:15:00 UTC
+        let midnight = 19724 * DAY;
+        let clock = FixedClock(midnight + 9 * HOUR + 15 * MINUTE);
+        assert_eq!(clock.time_of_day(), 9 * HOUR + 15 * MINUTE);
+
+        let at = 14 * HOUR + 30 * MINUTE;
+        assert_eq!(clock.next_time_of_day(at), midnight + at);
+        assert_eq!(
+            FixedClock(midnight + at).next_time_of_day(at),
+            midnight + at
+        );
+        assert_eq!(
+            FixedClock(midnight + at + SECOND).next_time_of_day(at),
+            midnight + DAY + at
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use super::{
+    rebucket_snapshot,
+    ApplySnapshot,
+    L1MarketDepth,
+    L2MarketDepth,
+    MarketDepth,
+    SnapshotBoundary,
+    INVALID_MAX,
+    INVALID_MIN,
+};
+use crate::{
+    backtest::data::Data,
+    prelude::Side,
+    types::{Event, BUY_EVENT, DEPTH_SNAPSHOT_EVENT, EXCH_EVENT, LOCAL_EVENT, SELL_EVENT},
+};
+
+/// L1 market depth implementation that maintains only the best bid and ask.
+///
+/// Every update takes constant time and no memory is allocated, which suits strategies that never
+/// read deeper levels. Since the levels behind the best are not maintained, when the quantity at
+/// the best bid or ask is depleted by an L2 update, the best bid or ask becomes unknown until a
+/// new level arrives at a better price. Updates behind the best bid or ask are ignored. It is
+/// therefore best fed by a BBO feed or by L1 updates through [`L1MarketDepth`].
+#[derive(Clone, Debug)]
+pub struct BboMarketDepth {
+    pub tick_size: f64,
+    pub lot_size: f64,
+    pub timestamp: i64,
+    pub best_bid_tick: i64,
+    pub best_ask_tick: i64,
+    pub best_bid_qty: f64,
+    pub best_ask_qty: f64,
+    bid_snapshot: SnapshotBoundary,
+    ask_snapshot: SnapshotBoundary,
+}
+
+impl BboMarketDepth {
+    /// Constructs an instance of `BboMarketDepth`.
+    pub fn new(tick_size: f64, lot_size: f64) -> Self {
+        Self {
+            tick_size,
+            lot_size,
+            timestamp: 0,
+            best_bid_tick: INVALID_MIN,
+            best_ask_tick: INVALID_MAX,
+            best_bid_qty: 0.0,
+            best_ask_qty: 0.0,
+            bid_snapshot: Default::default(),
+            ask_snapshot: Default::default(),
+        }
+    }
+
+    #[inline(always)]
+    fn clear_bid(&mut self) {
+        self.best_bid_tick = INVALID_MIN;
+        self.best_bid_qty = 0.0;
+    }
+
+    #[inline(always)]
+    fn clear_ask(&mut self) {
+        self.best_ask_tick = INVALID_MAX;
+        self.best_ask_qty = 0.0;
+    }
+}
+
+impl L2MarketDepth for BboMarketDepth {
+    fn update_bid_depth(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let price_tick = (price / self.tick_size).round() as i64;
+        let qty_lot = (qty / self.lot_size).round() as i64;
+        let prev_best_bid_tick = self.best_bid_tick;
+        let mut prev_qty = 0.0;
+        if price_tick == self.best_bid_tick {
+            prev_qty = self.best_bid_qty;
+            if qty_lot > 0 {
+                self.best_bid_qty = qty;
+            } else {
+                self.clear_bid();
+            }
+        } else if price_tick > self.best_bid_tick && qty_lot > 0 {
+            self.best_bid_tick = price_tick;
+            self.best_bid_qty = qty;
+            if self.best_bid_tick >= self.best_ask_tick {
+                self.clear_ask();
+            }
+        }
+        (
+            price_tick,
+            prev_best_bid_tick,
+            self.best_bid_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn update_ask_depth(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let price_tick = (price / self.tick_size).round() as i64;
+        let qty_lot = (qty / self.lot_size).round() as i64;
+        let prev_best_ask_tick = self.best_ask_tick;
+        let mut prev_qty = 0.0;
+        if price_tick == self.best_ask_tick {
+            prev_qty = self.best_ask_qty;
+            if qty_lot > 0 {
+                self.best_ask_qty = qty;
+            } else {
+                self.clear_ask();
+            }
+        } else if price_tick < self.best_ask_tick && qty_lot > 0 {
+            self.best_ask_tick = price_tick;
+            self.best_ask_qty = qty;
+            if self.best_bid_tick >= self.best_ask_tick {
+                self.clear_bid();
+            }
+        }
+        (
+            price_tick,
+            prev_best_ask_tick,
+            self.best_ask_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn clear_depth(&mut self, side: Side, clear_upto_price: f64) {
+        match side {
+            Side::Buy => {
+                self.bid_snapshot.clear();
+                if !clear_upto_price.is_finite()
+                    || self.best_bid_tick >= (clear_upto_price / self.tick_size).round() as i64
+                {
+                    self.clear_bid();
+                }
+            }
+            Side::Sell => {
+                self.ask_snapshot.clear();
+                if !clear_upto_price.is_finite()
+                    || self.best_ask_tick <= (clear_upto_price / self.tick_size).round() as i64
+                {
+                    self.clear_ask();
+                }
+            }
+            Side::None => {
+                self.bid_snapshot.clear();
+                self.ask_snapshot.clear();
+                self.clear_bid();
+                self.clear_ask();
+            }
+            Side::Unsupported => {
+                unreachable!();
+            }
+        }
+    }
+
+    fn update_bid_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let prev_best_bid_tick = self.best_bid_tick;
+        if self.bid_snapshot.is_boundary(timestamp) {
+            self.clear_bid();
+        }
+        self.bid_snapshot.apply(timestamp);
+        let (price_tick, _, best_bid_tick, prev_qty, qty, timestamp) =
+            self.update_bid_depth(price, qty, timestamp);
+        (
+            price_tick,
+            prev_best_bid_tick,
+            best_bid_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn update_ask_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let prev_best_ask_tick = self.best_ask_tick;
+        if self.ask_snapshot.is_boundary(timestamp) {
+            self.clear_ask();
+        }
+        self.ask_snapshot.apply(timestamp);
+        let (price_tick, _, best_ask_tick, prev_qty, qty, timestamp) =
+            self.update_ask_depth(price, qty, timestamp);
+        (
+            price_tick,
+            prev_best_ask_tick,
+            best_ask_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn rebucket(&mut self, tick_size: f64, lot_size: f64) {
+        let levels = rebucket_snapshot(&self.snapshot(), self.tick_size, tick_size);
+        self.tick_size = tick_size;
+        self.lot_size = lot_size;
+        self.apply_snapshot(&Data::from_data(&levels));
+    }
+}
+
+impl L1MarketDepth for BboMarketDepth {
+    fn update_best_bid(
+        &mut self,
+        px: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let price_tick = (px / self.tick_size).round() as i64;
+        let prev_best_bid_tick = self.best_bid_tick;
+        let prev_qty = if price_tick == self.best_bid_tick {
+            self.best_bid_qty
+        } else {
+            0.0
+        };
+        if (qty / self.lot_size).round() as i64 > 0 {
+            self.best_bid_tick = price_tick;
+            self.best_bid_qty = qty;
+            if self.best_bid_tick >= self.best_ask_tick {
+                self.clear_ask();
+            }
+        } else {
+            self.clear_bid();
+        }
+        (
+            price_tick,
+            prev_best_bid_tick,
+            self.best_bid_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn update_best_ask(
+        &mut self,
+        px: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let price_tick = (px / self.tick_size).round() as i64;
+        let prev_best_ask_tick = self.best_ask_tick;
+        let prev_qty = if price_tick == self.best_ask_tick {
+            self.best_ask_qty
+        } else {
+            0.0
+        };
+        if (qty / self.lot_size).round() as i64 > 0 {
+            self.best_ask_tick = price_tick;
+            self.best_ask_qty = qty;
+            if self.best_bid_tick >= self.best_ask_tick {
+                self.clear_bid();
+            }
+        } else {
+            self.clear_ask();
+        }
+        (
+            price_tick,
+            prev_best_ask_tick,
+            self.best_ask_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+}
+
+impl MarketDepth for BboMarketDepth {
+    #[inline(always)]
+    fn best_bid(&self) -> f64 {
+        if self.best_bid_tick == INVALID_MIN {
+            f64::NAN
+        } else {
+            self.best_bid_tick as f64 * self.tick_size
+        }
+    }
+
+    #[inline(always)]
+    fn best_ask(&self) -> f64 {
+        if self.best_ask_tick == INVALID_MAX {
+            f64::NAN
+        } else {
+            self.best_ask_tick as f64 * self.tick_size
+        }
+    }
+
+    #[inline(always)]
+    fn best_bid_tick(&self) -> i64 {
+        self.best_bid_tick
+    }
+
+    #[inline(always)]
+    fn best_ask_tick(&self) -> i64 {
+        self.best_ask_tick
+    }
+
+    #[inline(always)]
+    fn tick_size(&self) -> f64 {
+        self.tick_size
+    }
+
+    #[inline(always)]
+    fn lot_size(&self) -> f64 {
+        self.lot_size
+    }
+
+    #[inline(always)]
+    fn bid_qty_at_tick(&self, price_tick: i64) -> f64 {
+        if price_tick == self.best_bid_tick {
+            self.best_bid_qty
+        } else {
+            0.0
+        }
+    }
+
+    #[inline(always)]
+    fn ask_qty_at_tick(&self, price_tick: i64) -> f64 {
+        if price_tick == self.best_ask_tick {
+            self.best_ask_qty
+        } else {
+            0.0
+        }
+    }
+
+    fn bid_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        if self.best_bid_tick != INVALID_MIN && (low_tick..=high_tick).contains(&self.best_bid_tick)
+        {
+            self.best_bid_qty
+        } else {
+            0.0
+        }
+    }
+
+    fn ask_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        if self.best_ask_tick != INVALID_MAX && (low_tick..=high_tick).contains(&self.best_ask_tick)
+        {
+            self.best_ask_qty
+        } else {
+            0.0
+        }
+    }
+}
+
+impl ApplySnapshot for BboMarketDepth {
+    fn apply_snapshot(&mut self, data: &Data<Event>) {
+        self.clear_bid();
+        self.clear_ask();
+        for row_num in 0..data.len() {
+            let price_tick = (data[row_num].px / self.tick_size).round() as i64;
+            let qty = data[row_num].qty;
+            if data[row_num].ev & BUY_EVENT == BUY_EVENT {
+                if price_tick > self.best_bid_tick {
+                    self.best_bid_tick = price_tick;
+                    self.best_bid_qty = qty;
+                }
+            } else if data[row_num].ev & SELL_EVENT == SELL_EVENT && price_tick < self.best_ask_tick
+            {
+                self.best_ask_tick = price_tick;
+                self.best_ask_qty = qty;
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Vec<Event> {
+        let mut events = Vec::new();
+        if self.best_bid_tick != INVALID_MIN {
+            events.push(Event {
+                ev: EXCH_EVENT | LOCAL_EVENT | BUY_EVENT | DEPTH_SNAPSHOT_EVENT,
+                exch_ts: 0,
+                local_ts: 0,
+                px: self.best_bid_tick as f64 * self.tick_size,
+                qty: self.best_bid_qty,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            });
+        }
+        if self.best_ask_tick != INVALID_MAX {
+            events.push(Event {
+                ev: EXCH_EVENT | LOCAL_EVENT | SELL_EVENT | DEPTH_SNAPSHOT_EVENT,
+                exch_ts: 0,
+                local_ts: 0,
+                px: self.best_ask_tick as f64 * self.tick_size,
+                qty: self.best_ask_qty,
+                order_id: 0,
+                ival: 0,
+                fval: 0.0,
+            });
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        depth::{BboMarketDepth, L1MarketDepth, L2MarketDepth, MarketDepth, INVALID_MAX},
+        types::Side,
+    };
+
+    #[test]
+    fn test_bbo_depth() {
+        let mut depth = BboMarketDepth::new(0.1, 0.001);
+
+        depth.update_bid_depth(100.0, 1.0, 0);
+        depth.update_ask_depth(100.2, 2.0, 0);
+        // Behind the best bid.
+        depth.update_bid_depth(99.9, 3.0, 0);
+        assert_eq!(depth.best_bid_tick(), 1000);
+        assert_eq!(depth.bid_qty_at_tick(1000), 1.0);
+        assert_eq!(depth.bid_qty_at_tick(999), 0.0);
+
+        let (_, prev_best, best, prev_qty, _, _) = depth.update_bid_depth(100.1, 4.0, 0);
+        assert_eq!((prev_best, best, prev_qty), (1000, 1001, 0.0));
+
+        // Crossing the best ask invalidates it.
+        depth.update_bid_depth(100.2, 5.0, 0);
+        assert_eq!(depth.best_ask_tick(), INVALID_MAX);
+
+        depth.update_best_ask(100.3, 6.0, 0);
+        assert_eq!(depth.best_ask_tick(), 1003);
+        assert_eq!(depth.ask_qty_at_tick(1003), 6.0);
+
+        depth.update_ask_depth(100.3, 0.0, 0);
+        assert!(depth.best_ask().is_nan());
+
+        depth.clear_depth(Side::Buy, 100.5);
+        assert_eq!(depth.best_bid_tick(), 1002);
+        depth.clear_depth(Side::Buy, 100.2);
+        assert!(depth.best_bid().is_nan());
+    }
+}
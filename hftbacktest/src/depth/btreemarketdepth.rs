@@ -1,40 +1,96 @@
-use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    mem::size_of,
+    ops::Bound,
+};
 
 use super::{
+    check_tick_range,
+    key,
+    rebucket_snapshot,
     ApplySnapshot,
     L2MarketDepth,
     L3MarketDepth,
     L3Order,
     MarketDepth,
+    SnapshotBoundary,
+    Tick,
+    TickOverflow,
     INVALID_MAX,
     INVALID_MIN,
 };
 use crate::{
     backtest::{data::Data, BacktestError},
     prelude::{OrderId, Side},
-    types::{Event, BUY_EVENT, SELL_EVENT},
+    types::{Event, BUY_EVENT, DEPTH_SNAPSHOT_EVENT, EXCH_EVENT, LOCAL_EVENT, SELL_EVENT},
 };
 
+/// Sums the quantities within `low_tick..=high_tick`, where the bounds that overflow `T` are
+/// clamped to its range.
+fn qty_in_range<T: Tick>(depth: &BTreeMap<T, f64>, low_tick: i64, high_tick: i64) -> f64 {
+    if low_tick > high_tick {
+        return 0.0;
+    }
+    let low = match T::from_i64(low_tick) {
+        Some(t) => Bound::Included(t),
+        None if low_tick < 0 => Bound::Unbounded,
+        None => return 0.0,
+    };
+    let high = match T::from_i64(high_tick) {
+        Some(t) => Bound::Included(t),
+        None if high_tick > 0 => Bound::Unbounded,
+        None => return 0.0,
+    };
+    depth.range((low, high)).map(|(_, qty)| *qty).sum()
+}
+
 /// L2 Market depth implementation based on a B-Tree map.
 ///
 /// If feed data is missing, it may result in the crossing of the best bid and ask, making it
 /// impossible to restore them to the most recent values through natural refreshing.
 /// Ensuring data integrity is imperative.
+///
+/// The price ticks of the price levels are stored as `T`, which is `i64` by default. Since a B-Tree
+/// map stores the keys apart from the values, a narrower type such as `i32`, constructed by
+/// [`with_max_price`](Self::with_max_price), reduces the memory and improves the cache behavior of
+/// the searches where the price range permits.
 #[derive(Debug)]
-pub struct BTreeMarketDepth {
+pub struct BTreeMarketDepth<T = i64> {
     pub tick_size: f64,
     pub lot_size: f64,
     pub timestamp: i64,
-    pub bid_depth: BTreeMap<i64, f64>,
-    pub ask_depth: BTreeMap<i64, f64>,
+    pub bid_depth: BTreeMap<T, f64>,
+    pub ask_depth: BTreeMap<T, f64>,
     pub best_bid_tick: i64,
     pub best_ask_tick: i64,
     pub orders: HashMap<OrderId, L3Order>,
+    bid_snapshot: SnapshotBoundary,
+    ask_snapshot: SnapshotBoundary,
 }
 
 impl BTreeMarketDepth {
     /// Constructs an instance of `BTreeMarketDepth`.
     pub fn new(tick_size: f64, lot_size: f64) -> Self {
+        Self::with_tick_size(tick_size, lot_size)
+    }
+}
+
+impl<T: Tick> BTreeMarketDepth<T> {
+    /// Constructs an instance of `BTreeMarketDepth` that stores the price ticks as `T`, such as
+    /// `i32`, which halves the memory of the keys where the price range permits.
+    ///
+    /// Returns an error if the prices within `-max_price..=max_price` cannot be represented in
+    /// ticks by `T`. Updating the market depth with a price outside this range panics.
+    pub fn with_max_price(
+        tick_size: f64,
+        lot_size: f64,
+        max_price: f64,
+    ) -> Result<Self, TickOverflow> {
+        check_tick_range::<T>(tick_size, max_price)?;
+        Ok(Self::with_tick_size(tick_size, lot_size))
+    }
+
+    fn with_tick_size(tick_size: f64, lot_size: f64) -> Self {
         Self {
             tick_size,
             lot_size,
@@ -44,6 +100,8 @@ impl BTreeMarketDepth {
             best_bid_tick: INVALID_MIN,
             best_ask_tick: INVALID_MAX,
             orders: Default::default(),
+            bid_snapshot: Default::default(),
+            ask_snapshot: Default::default(),
         }
     }
 
@@ -53,15 +111,15 @@ impl BTreeMarketDepth {
             Entry::Vacant(entry) => entry.insert(order),
         };
         if order.side == Side::Buy {
-            *self.bid_depth.entry(order.price_tick).or_insert(0.0) += order.qty;
+            *self.bid_depth.entry(key(order.price_tick)).or_insert(0.0) += order.qty;
         } else {
-            *self.ask_depth.entry(order.price_tick).or_insert(0.0) += order.qty;
+            *self.ask_depth.entry(key(order.price_tick)).or_insert(0.0) += order.qty;
         }
         Ok(())
     }
 }
 
-impl L2MarketDepth for BTreeMarketDepth {
+impl<T: Tick> L2MarketDepth for BTreeMarketDepth<T> {
     fn update_bid_depth(
         &mut self,
         price: f64,
@@ -69,15 +127,25 @@ impl L2MarketDepth for BTreeMarketDepth {
         timestamp: i64,
     ) -> (i64, i64, i64, f64, f64, i64) {
         let price_tick = (price / self.tick_size).round() as i64;
-        let prev_best_bid_tick = *self.bid_depth.keys().last().unwrap_or(&INVALID_MIN);
-        let prev_qty = *self.bid_depth.get(&prev_best_bid_tick).unwrap_or(&0.0);
+        let prev_best_bid_tick = self
+            .bid_depth
+            .keys()
+            .last()
+            .map_or(INVALID_MIN, |t| t.to_i64());
+        let prev_qty = T::from_i64(prev_best_bid_tick)
+            .and_then(|t| self.bid_depth.get(&t))
+            .map_or(0.0, |qty| *qty);
 
         if (qty / self.lot_size).round() as i64 == 0 {
-            self.bid_depth.remove(&price_tick);
+            self.bid_depth.remove(&key::<T>(price_tick));
         } else {
-            *self.bid_depth.entry(price_tick).or_insert(qty) = qty;
+            *self.bid_depth.entry(key(price_tick)).or_insert(qty) = qty;
         }
-        self.best_bid_tick = *self.bid_depth.keys().last().unwrap_or(&INVALID_MIN);
+        self.best_bid_tick = self
+            .bid_depth
+            .keys()
+            .last()
+            .map_or(INVALID_MIN, |t| t.to_i64());
         (
             price_tick,
             prev_best_bid_tick,
@@ -95,15 +163,25 @@ impl L2MarketDepth for BTreeMarketDepth {
         timestamp: i64,
     ) -> (i64, i64, i64, f64, f64, i64) {
         let price_tick = (price / self.tick_size).round() as i64;
-        let prev_best_ask_tick = *self.bid_depth.keys().next().unwrap_or(&INVALID_MAX);
-        let prev_qty = *self.ask_depth.get(&prev_best_ask_tick).unwrap_or(&0.0);
+        let prev_best_ask_tick = self
+            .bid_depth
+            .keys()
+            .next()
+            .map_or(INVALID_MAX, |t| t.to_i64());
+        let prev_qty = T::from_i64(prev_best_ask_tick)
+            .and_then(|t| self.ask_depth.get(&t))
+            .map_or(0.0, |qty| *qty);
 
         if (qty / self.lot_size).round() as i64 == 0 {
-            self.ask_depth.remove(&price_tick);
+            self.ask_depth.remove(&key::<T>(price_tick));
         } else {
-            *self.ask_depth.entry(price_tick).or_insert(qty) = qty;
+            *self.ask_depth.entry(key(price_tick)).or_insert(qty) = qty;
         }
-        self.best_ask_tick = *self.ask_depth.keys().next().unwrap_or(&INVALID_MAX);
+        self.best_ask_tick = self
+            .ask_depth
+            .keys()
+            .next()
+            .map_or(INVALID_MAX, |t| t.to_i64());
         (
             price_tick,
             prev_best_ask_tick,
@@ -117,38 +195,50 @@ impl L2MarketDepth for BTreeMarketDepth {
     fn clear_depth(&mut self, side: Side, clear_upto_price: f64) {
         match side {
             Side::Buy => {
+                self.bid_snapshot.clear();
                 if clear_upto_price.is_finite() {
                     let clear_upto = (clear_upto_price / self.tick_size).round() as i64;
                     if self.best_bid_tick != INVALID_MIN {
                         for t in clear_upto..(self.best_bid_tick + 1) {
-                            if self.bid_depth.contains_key(&t) {
+                            if let Some(t) = T::from_i64(t) {
                                 self.bid_depth.remove(&t);
                             }
                         }
                     }
-                    self.best_bid_tick = *self.bid_depth.keys().last().unwrap_or(&INVALID_MIN);
+                    self.best_bid_tick = self
+                        .bid_depth
+                        .keys()
+                        .last()
+                        .map_or(INVALID_MIN, |t| t.to_i64());
                 } else {
                     self.bid_depth.clear();
                     self.best_bid_tick = INVALID_MIN;
                 }
             }
             Side::Sell => {
+                self.ask_snapshot.clear();
                 if clear_upto_price.is_finite() {
                     let clear_upto = (clear_upto_price / self.tick_size).round() as i64;
                     if self.best_ask_tick != INVALID_MAX {
                         for t in self.best_ask_tick..(clear_upto + 1) {
-                            if self.ask_depth.contains_key(&t) {
+                            if let Some(t) = T::from_i64(t) {
                                 self.ask_depth.remove(&t);
                             }
                         }
                     }
-                    self.best_ask_tick = *self.ask_depth.keys().next().unwrap_or(&INVALID_MAX);
+                    self.best_ask_tick = self
+                        .ask_depth
+                        .keys()
+                        .next()
+                        .map_or(INVALID_MAX, |t| t.to_i64());
                 } else {
                     self.ask_depth.clear();
                     self.best_ask_tick = INVALID_MAX;
                 }
             }
             Side::None => {
+                self.bid_snapshot.clear();
+                self.ask_snapshot.clear();
                 self.bid_depth.clear();
                 self.ask_depth.clear();
                 self.best_bid_tick = INVALID_MIN;
@@ -159,9 +249,62 @@ impl L2MarketDepth for BTreeMarketDepth {
             }
         }
     }
+
+    fn update_bid_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let prev_best_bid_tick = self.best_bid_tick;
+        if self.bid_snapshot.is_boundary(timestamp) {
+            self.clear_depth(Side::Buy, f64::NAN);
+        }
+        self.bid_snapshot.apply(timestamp);
+        let (price_tick, _, best_bid_tick, prev_qty, qty, timestamp) =
+            self.update_bid_depth(price, qty, timestamp);
+        (
+            price_tick,
+            prev_best_bid_tick,
+            best_bid_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn update_ask_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let prev_best_ask_tick = self.best_ask_tick;
+        if self.ask_snapshot.is_boundary(timestamp) {
+            self.clear_depth(Side::Sell, f64::NAN);
+        }
+        self.ask_snapshot.apply(timestamp);
+        let (price_tick, _, best_ask_tick, prev_qty, qty, timestamp) =
+            self.update_ask_depth(price, qty, timestamp);
+        (
+            price_tick,
+            prev_best_ask_tick,
+            best_ask_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn rebucket(&mut self, tick_size: f64, lot_size: f64) {
+        let levels = rebucket_snapshot(&self.snapshot(), self.tick_size, tick_size);
+        self.tick_size = tick_size;
+        self.lot_size = lot_size;
+        self.apply_snapshot(&Data::from_data(&levels));
+    }
 }
 
-impl MarketDepth for BTreeMarketDepth {
+impl<T: Tick> MarketDepth for BTreeMarketDepth<T> {
     #[inline(always)]
     fn best_bid(&self) -> f64 {
         if self.best_bid_tick == INVALID_MIN {
@@ -202,16 +345,34 @@ impl MarketDepth for BTreeMarketDepth {
 
     #[inline(always)]
     fn bid_qty_at_tick(&self, price_tick: i64) -> f64 {
-        *self.bid_depth.get(&price_tick).unwrap_or(&0.0)
+        T::from_i64(price_tick)
+            .and_then(|t| self.bid_depth.get(&t))
+            .map_or(0.0, |qty| *qty)
     }
 
     #[inline(always)]
     fn ask_qty_at_tick(&self, price_tick: i64) -> f64 {
-        *self.ask_depth.get(&price_tick).unwrap_or(&0.0)
+        T::from_i64(price_tick)
+            .and_then(|t| self.ask_depth.get(&t))
+            .map_or(0.0, |qty| *qty)
+    }
+
+    fn bid_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        qty_in_range(&self.bid_depth, low_tick, high_tick)
+    }
+
+    fn ask_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        qty_in_range(&self.ask_depth, low_tick, high_tick)
+    }
+
+    fn memory_usage(&self) -> usize {
+        size_of::<Self>()
+            + (self.bid_depth.len() + self.ask_depth.len()) * (size_of::<T>() + size_of::<f64>())
+            + self.orders.capacity() * size_of::<(OrderId, L3Order)>()
     }
 }
 
-impl ApplySnapshot for BTreeMarketDepth {
+impl<T: Tick> ApplySnapshot for BTreeMarketDepth<T> {
     fn apply_snapshot(&mut self, data: &Data<Event>) {
         self.bid_depth.clear();
         self.ask_depth.clear();
@@ -221,21 +382,48 @@ impl ApplySnapshot for BTreeMarketDepth {
 
             let price_tick = (price / self.tick_size).round() as i64;
             if data[row_num].ev & BUY_EVENT == BUY_EVENT {
-                *self.bid_depth.entry(price_tick).or_insert(0f64) = qty;
+                *self.bid_depth.entry(key(price_tick)).or_insert(0f64) = qty;
             } else if data[row_num].ev & SELL_EVENT == SELL_EVENT {
-                *self.ask_depth.entry(price_tick).or_insert(0f64) = qty;
+                *self.ask_depth.entry(key(price_tick)).or_insert(0f64) = qty;
             }
         }
-        self.best_bid_tick = *self.bid_depth.keys().last().unwrap_or(&INVALID_MIN);
-        self.best_ask_tick = *self.ask_depth.keys().next().unwrap_or(&INVALID_MAX);
+        self.best_bid_tick = self
+            .bid_depth
+            .keys()
+            .last()
+            .map_or(INVALID_MIN, |t| t.to_i64());
+        self.best_ask_tick = self
+            .ask_depth
+            .keys()
+            .next()
+            .map_or(INVALID_MAX, |t| t.to_i64());
     }
 
     fn snapshot(&self) -> Vec<Event> {
-        todo!()
+        let level = |ev: u64, price_tick: i64, qty: f64| Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | ev | DEPTH_SNAPSHOT_EVENT,
+            exch_ts: 0,
+            local_ts: 0,
+            px: price_tick as f64 * self.tick_size,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        };
+        self.bid_depth
+            .iter()
+            .rev()
+            .map(|(t, qty)| level(BUY_EVENT, t.to_i64(), *qty))
+            .chain(
+                self.ask_depth
+                    .iter()
+                    .map(|(t, qty)| level(SELL_EVENT, t.to_i64(), *qty)),
+            )
+            .collect()
     }
 }
 
-impl L3MarketDepth for BTreeMarketDepth {
+impl<T: Tick> L3MarketDepth for BTreeMarketDepth<T> {
     type Error = BacktestError;
 
     fn add_buy_order(
@@ -255,7 +443,11 @@ impl L3MarketDepth for BTreeMarketDepth {
         })?;
         let prev_best_tick = self.best_bid_tick;
         if price_tick > self.best_bid_tick {
-            self.best_bid_tick = *self.bid_depth.keys().last().unwrap_or(&INVALID_MIN);
+            self.best_bid_tick = self
+                .bid_depth
+                .keys()
+                .last()
+                .map_or(INVALID_MIN, |t| t.to_i64());
         }
         Ok((prev_best_tick, self.best_bid_tick))
     }
@@ -277,7 +469,11 @@ impl L3MarketDepth for BTreeMarketDepth {
         })?;
         let prev_best_tick = self.best_ask_tick;
         if price_tick < self.best_ask_tick {
-            self.best_ask_tick = *self.ask_depth.keys().next().unwrap_or(&INVALID_MAX);
+            self.best_ask_tick = self
+                .ask_depth
+                .keys()
+                .next()
+                .map_or(INVALID_MAX, |t| t.to_i64());
         }
         Ok((prev_best_tick, self.best_ask_tick))
     }
@@ -294,24 +490,32 @@ impl L3MarketDepth for BTreeMarketDepth {
         if order.side == Side::Buy {
             let prev_best_tick = self.best_bid_tick;
 
-            let depth_qty = self.bid_depth.get_mut(&order.price_tick).unwrap();
+            let depth_qty = self.bid_depth.get_mut(&key::<T>(order.price_tick)).unwrap();
             *depth_qty -= order.qty;
             if (*depth_qty / self.lot_size).round() as i64 == 0 {
-                self.bid_depth.remove(&order.price_tick).unwrap();
+                self.bid_depth.remove(&key::<T>(order.price_tick)).unwrap();
                 if order.price_tick == self.best_bid_tick {
-                    self.best_bid_tick = *self.bid_depth.keys().next().unwrap_or(&INVALID_MIN);
+                    self.best_bid_tick = self
+                        .bid_depth
+                        .keys()
+                        .next()
+                        .map_or(INVALID_MIN, |t| t.to_i64());
                 }
             }
             Ok((Side::Buy, prev_best_tick, self.best_bid_tick))
         } else {
             let prev_best_tick = self.best_ask_tick;
 
-            let depth_qty = self.ask_depth.get_mut(&order.price_tick).unwrap();
+            let depth_qty = self.ask_depth.get_mut(&key::<T>(order.price_tick)).unwrap();
             *depth_qty -= order.qty;
             if (*depth_qty / self.lot_size).round() as i64 == 0 {
-                self.ask_depth.remove(&order.price_tick).unwrap();
+                self.ask_depth.remove(&key::<T>(order.price_tick)).unwrap();
                 if order.price_tick == self.best_ask_tick {
-                    self.best_ask_tick = *self.ask_depth.keys().next().unwrap_or(&INVALID_MAX);
+                    self.best_ask_tick = self
+                        .ask_depth
+                        .keys()
+                        .next()
+                        .map_or(INVALID_MAX, |t| t.to_i64());
                 }
             }
             Ok((Side::Sell, prev_best_tick, self.best_ask_tick))
@@ -333,12 +537,16 @@ impl L3MarketDepth for BTreeMarketDepth {
             let prev_best_tick = self.best_bid_tick;
             let price_tick = (px / self.tick_size).round() as i64;
             if price_tick != order.price_tick {
-                let depth_qty = self.bid_depth.get_mut(&order.price_tick).unwrap();
+                let depth_qty = self.bid_depth.get_mut(&key::<T>(order.price_tick)).unwrap();
                 *depth_qty -= order.qty;
                 if (*depth_qty / self.lot_size).round() as i64 == 0 {
-                    self.bid_depth.remove(&order.price_tick).unwrap();
+                    self.bid_depth.remove(&key::<T>(order.price_tick)).unwrap();
                     if order.price_tick == self.best_bid_tick {
-                        self.best_bid_tick = *self.bid_depth.keys().last().unwrap_or(&INVALID_MIN);
+                        self.best_bid_tick = self
+                            .bid_depth
+                            .keys()
+                            .last()
+                            .map_or(INVALID_MIN, |t| t.to_i64());
                     }
                 }
 
@@ -346,14 +554,18 @@ impl L3MarketDepth for BTreeMarketDepth {
                 order.qty = qty;
                 order.timestamp = timestamp;
 
-                *self.bid_depth.entry(order.price_tick).or_insert(0.0) += order.qty;
+                *self.bid_depth.entry(key(order.price_tick)).or_insert(0.0) += order.qty;
 
                 if price_tick > self.best_bid_tick {
-                    self.best_bid_tick = *self.bid_depth.keys().last().unwrap_or(&INVALID_MIN);
+                    self.best_bid_tick = self
+                        .bid_depth
+                        .keys()
+                        .last()
+                        .map_or(INVALID_MIN, |t| t.to_i64());
                 }
                 Ok((Side::Buy, prev_best_tick, self.best_bid_tick))
             } else {
-                let depth_qty = self.bid_depth.get_mut(&order.price_tick).unwrap();
+                let depth_qty = self.bid_depth.get_mut(&key::<T>(order.price_tick)).unwrap();
                 *depth_qty += qty - order.qty;
                 order.qty = qty;
                 Ok((Side::Buy, self.best_bid_tick, self.best_bid_tick))
@@ -362,12 +574,16 @@ impl L3MarketDepth for BTreeMarketDepth {
             let prev_best_tick = self.best_ask_tick;
             let price_tick = (px / self.tick_size).round() as i64;
             if price_tick != order.price_tick {
-                let depth_qty = self.ask_depth.get_mut(&order.price_tick).unwrap();
+                let depth_qty = self.ask_depth.get_mut(&key::<T>(order.price_tick)).unwrap();
                 *depth_qty -= order.qty;
                 if (*depth_qty / self.lot_size).round() as i64 == 0 {
-                    self.ask_depth.remove(&order.price_tick).unwrap();
+                    self.ask_depth.remove(&key::<T>(order.price_tick)).unwrap();
                     if order.price_tick == self.best_ask_tick {
-                        self.best_ask_tick = *self.ask_depth.keys().next().unwrap_or(&INVALID_MAX);
+                        self.best_ask_tick = self
+                            .ask_depth
+                            .keys()
+                            .next()
+                            .map_or(INVALID_MAX, |t| t.to_i64());
                     }
                 }
 
@@ -375,14 +591,18 @@ impl L3MarketDepth for BTreeMarketDepth {
                 order.qty = qty;
                 order.timestamp = timestamp;
 
-                *self.ask_depth.entry(order.price_tick).or_insert(0.0) += order.qty;
+                *self.ask_depth.entry(key(order.price_tick)).or_insert(0.0) += order.qty;
 
                 if price_tick < self.best_ask_tick {
-                    self.best_ask_tick = *self.ask_depth.keys().next().unwrap_or(&INVALID_MAX);
+                    self.best_ask_tick = self
+                        .ask_depth
+                        .keys()
+                        .next()
+                        .map_or(INVALID_MAX, |t| t.to_i64());
                 }
                 Ok((Side::Sell, prev_best_tick, self.best_ask_tick))
             } else {
-                let depth_qty = self.ask_depth.get_mut(&order.price_tick).unwrap();
+                let depth_qty = self.ask_depth.get_mut(&key::<T>(order.price_tick)).unwrap();
                 *depth_qty += qty - order.qty;
                 order.qty = qty;
                 Ok((Side::Sell, self.best_ask_tick, self.best_ask_tick))
@@ -434,7 +654,14 @@ impl L3MarketDepth for BTreeMarketDepth {
 #[cfg(test)]
 mod tests {
     use crate::{
-        depth::{BTreeMarketDepth, L3MarketDepth, MarketDepth, INVALID_MAX, INVALID_MIN},
+        depth::{
+            BTreeMarketDepth,
+            L2MarketDepth,
+            L3MarketDepth,
+            MarketDepth,
+            INVALID_MAX,
+            INVALID_MIN,
+        },
         types::Side,
     };
 
@@ -658,4 +885,22 @@ mod tests {
         assert_eq_qty!(depth.ask_qty_at_tick(4981), 0.0, lot_size);
         assert_eq_qty!(depth.ask_qty_at_tick(5002), 0.002, lot_size);
     }
+
+    #[test]
+    fn test_i32_tick() {
+        assert!(BTreeMarketDepth::<i32>::with_max_price(0.1, 0.001, 1e9).is_err());
+
+        let lot_size = 0.001;
+        let mut depth = BTreeMarketDepth::<i32>::with_max_price(0.1, lot_size, 1e6).unwrap();
+        depth.update_bid_depth(500.1, 0.001, 0);
+        depth.update_bid_depth(500.3, 0.002, 0);
+        depth.update_ask_depth(500.5, 0.003, 0);
+        assert_eq!(depth.best_bid_tick(), 5003);
+        assert_eq!(depth.best_ask_tick(), 5005);
+        assert_eq_qty!(depth.bid_qty_at_tick(5001), 0.001, lot_size);
+        assert_eq_qty!(depth.bid_qty_at_tick(i64::MAX), 0.0, lot_size);
+
+        depth.update_bid_depth(500.3, 0.0, 0);
+        assert_eq!(depth.best_bid_tick(), 5001);
+    }
 }
@@ -1,6 +1,17 @@
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    mem::size_of,
+};
 
-use super::{ApplySnapshot, L1MarketDepth, L3Order, MarketDepth, INVALID_MAX, INVALID_MIN};
+use super::{
+    rebucket_snapshot,
+    ApplySnapshot,
+    L1MarketDepth,
+    L3Order,
+    MarketDepth,
+    INVALID_MAX,
+    INVALID_MIN,
+};
 use crate::{
     backtest::{data::Data, BacktestError},
     prelude::{L2MarketDepth, Side, DEPTH_SNAPSHOT_EVENT, EXCH_EVENT, LOCAL_EVENT},
@@ -273,6 +284,13 @@ impl L2MarketDepth for FusedHashMapMarketDepth {
             self.high_ask_tick = INVALID_MIN;
         }
     }
+
+    fn rebucket(&mut self, tick_size: f64, lot_size: f64) {
+        let levels = rebucket_snapshot(&self.snapshot(), self.tick_size, tick_size);
+        self.tick_size = tick_size;
+        self.lot_size = lot_size;
+        self.apply_snapshot(&Data::from_data(&levels));
+    }
 }
 
 impl MarketDepth for FusedHashMapMarketDepth {
@@ -329,6 +347,13 @@ impl MarketDepth for FusedHashMapMarketDepth {
             .unwrap_or(&Default::default())
             .qty
     }
+
+    fn memory_usage(&self) -> usize {
+        size_of::<Self>()
+            + (self.bid_depth.capacity() + self.ask_depth.capacity())
+                * size_of::<(i64, QtyTimestamp)>()
+            + self.orders.capacity() * size_of::<(OrderId, L3Order)>()
+    }
 }
 
 impl ApplySnapshot for FusedHashMapMarketDepth {
@@ -1,6 +1,18 @@
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    mem::size_of,
+};
 
-use super::{ApplySnapshot, L3MarketDepth, L3Order, MarketDepth, INVALID_MAX, INVALID_MIN};
+use super::{
+    rebucket_snapshot,
+    ApplySnapshot,
+    L3MarketDepth,
+    L3Order,
+    MarketDepth,
+    SnapshotBoundary,
+    INVALID_MAX,
+    INVALID_MIN,
+};
 use crate::{
     backtest::{data::Data, BacktestError},
     prelude::{L2MarketDepth, OrderId, Side},
@@ -29,6 +41,27 @@ pub struct HashMapMarketDepth {
     pub low_bid_tick: i64,
     pub high_ask_tick: i64,
     pub orders: HashMap<OrderId, L3Order>,
+    bid_snapshot: SnapshotBoundary,
+    ask_snapshot: SnapshotBoundary,
+}
+
+/// Sums the quantities within `low_tick..=high_tick`, iterating over either the range or the
+/// entries, whichever is smaller.
+fn qty_in_range(depth: &HashMap<i64, f64>, low_tick: i64, high_tick: i64) -> f64 {
+    if low_tick > high_tick {
+        return 0.0;
+    }
+    if high_tick.abs_diff(low_tick) < depth.len() as u64 {
+        (low_tick..=high_tick)
+            .map(|t| *depth.get(&t).unwrap_or(&0.0))
+            .sum()
+    } else {
+        depth
+            .iter()
+            .filter(|(t, _)| (low_tick..=high_tick).contains(*t))
+            .map(|(_, qty)| *qty)
+            .sum()
+    }
 }
 
 #[inline(always)]
@@ -65,6 +98,8 @@ impl HashMapMarketDepth {
             low_bid_tick: INVALID_MAX,
             high_ask_tick: INVALID_MIN,
             orders: HashMap::new(),
+            bid_snapshot: Default::default(),
+            ask_snapshot: Default::default(),
         }
     }
 
@@ -196,6 +231,7 @@ impl L2MarketDepth for HashMapMarketDepth {
     fn clear_depth(&mut self, side: Side, clear_upto_price: f64) {
         match side {
             Side::Buy => {
+                self.bid_snapshot.clear();
                 if clear_upto_price.is_finite() {
                     let clear_upto = (clear_upto_price / self.tick_size).round() as i64;
                     if self.best_bid_tick != INVALID_MIN {
@@ -216,6 +252,7 @@ impl L2MarketDepth for HashMapMarketDepth {
                 }
             }
             Side::Sell => {
+                self.ask_snapshot.clear();
                 if clear_upto_price.is_finite() {
                     let clear_upto = (clear_upto_price / self.tick_size).round() as i64;
                     if self.best_ask_tick != INVALID_MAX {
@@ -236,6 +273,8 @@ impl L2MarketDepth for HashMapMarketDepth {
                 }
             }
             Side::None => {
+                self.bid_snapshot.clear();
+                self.ask_snapshot.clear();
                 self.bid_depth.clear();
                 self.ask_depth.clear();
                 self.best_bid_tick = INVALID_MIN;
@@ -248,6 +287,59 @@ impl L2MarketDepth for HashMapMarketDepth {
             }
         }
     }
+
+    fn update_bid_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let prev_best_bid_tick = self.best_bid_tick;
+        if self.bid_snapshot.is_boundary(timestamp) {
+            self.clear_depth(Side::Buy, f64::NAN);
+        }
+        self.bid_snapshot.apply(timestamp);
+        let (price_tick, _, best_bid_tick, prev_qty, qty, timestamp) =
+            self.update_bid_depth(price, qty, timestamp);
+        (
+            price_tick,
+            prev_best_bid_tick,
+            best_bid_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn update_ask_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let prev_best_ask_tick = self.best_ask_tick;
+        if self.ask_snapshot.is_boundary(timestamp) {
+            self.clear_depth(Side::Sell, f64::NAN);
+        }
+        self.ask_snapshot.apply(timestamp);
+        let (price_tick, _, best_ask_tick, prev_qty, qty, timestamp) =
+            self.update_ask_depth(price, qty, timestamp);
+        (
+            price_tick,
+            prev_best_ask_tick,
+            best_ask_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn rebucket(&mut self, tick_size: f64, lot_size: f64) {
+        let levels = rebucket_snapshot(&self.snapshot(), self.tick_size, tick_size);
+        self.tick_size = tick_size;
+        self.lot_size = lot_size;
+        self.apply_snapshot(&Data::from_data(&levels));
+    }
 }
 
 impl MarketDepth for HashMapMarketDepth {
@@ -298,6 +390,34 @@ impl MarketDepth for HashMapMarketDepth {
     fn ask_qty_at_tick(&self, price_tick: i64) -> f64 {
         *self.ask_depth.get(&price_tick).unwrap_or(&0.0)
     }
+
+    fn bid_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        if self.best_bid_tick == INVALID_MIN {
+            return 0.0;
+        }
+        qty_in_range(
+            &self.bid_depth,
+            low_tick.max(self.low_bid_tick),
+            high_tick.min(self.best_bid_tick),
+        )
+    }
+
+    fn ask_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        if self.best_ask_tick == INVALID_MAX {
+            return 0.0;
+        }
+        qty_in_range(
+            &self.ask_depth,
+            low_tick.max(self.best_ask_tick),
+            high_tick.min(self.high_ask_tick),
+        )
+    }
+
+    fn memory_usage(&self) -> usize {
+        size_of::<Self>()
+            + (self.bid_depth.capacity() + self.ask_depth.capacity()) * size_of::<(i64, f64)>()
+            + self.orders.capacity() * size_of::<(OrderId, L3Order)>()
+    }
 }
 
 impl ApplySnapshot for HashMapMarketDepth {
@@ -607,7 +727,16 @@ impl L3MarketDepth for HashMapMarketDepth {
 #[cfg(test)]
 mod tests {
     use crate::{
-        depth::{HashMapMarketDepth, L3MarketDepth, MarketDepth, INVALID_MAX, INVALID_MIN},
+        depth::{
+            BTreeMarketDepth,
+            HashMapMarketDepth,
+            L2MarketDepth,
+            L3MarketDepth,
+            MarketDepth,
+            ROIVectorMarketDepth,
+            INVALID_MAX,
+            INVALID_MIN,
+        },
         types::Side,
     };
 
@@ -831,4 +960,67 @@ mod tests {
         assert_eq_qty!(depth.ask_qty_at_tick(4981), 0.0, lot_size);
         assert_eq_qty!(depth.ask_qty_at_tick(5002), 0.002, lot_size);
     }
+
+    #[test]
+    fn test_snapshot_boundary() {
+        let lot_size = 0.001;
+        let mut depth = HashMapMarketDepth::new(0.1, lot_size);
+
+        depth.update_bid_snapshot(500.1, 0.001, 100);
+        depth.update_bid_snapshot(500.0, 0.002, 100);
+        depth.update_ask_snapshot(500.3, 0.003, 100);
+        depth.update_bid_depth(499.5, 0.004, 150);
+        assert_eq!(depth.best_bid_tick(), 5001);
+        assert_eq_qty!(depth.bid_qty_at_tick(4995), 0.004, lot_size);
+
+        // A resent snapshot replaces the side.
+        let (_, prev_best, best, _, _, _) = depth.update_bid_snapshot(499.9, 0.005, 200);
+        assert_eq!(prev_best, 5001);
+        assert_eq!(best, 4999);
+        depth.update_bid_snapshot(499.8, 0.006, 200);
+        assert_eq_qty!(depth.bid_qty_at_tick(5000), 0.0, lot_size);
+        assert_eq_qty!(depth.bid_qty_at_tick(4995), 0.0, lot_size);
+        assert_eq_qty!(depth.bid_qty_at_tick(4998), 0.006, lot_size);
+        assert_eq!(depth.best_ask_tick(), 5003);
+
+        // A snapshot following a depth clear event is applied on top of the remaining depth.
+        depth.clear_depth(Side::Sell, 500.5);
+        depth.update_ask_depth(501.0, 0.007, 250);
+        depth.clear_depth(Side::Sell, 500.5);
+        depth.update_ask_snapshot(500.4, 0.008, 300);
+        assert_eq!(depth.best_ask_tick(), 5004);
+        assert_eq_qty!(depth.ask_qty_at_tick(5010), 0.007, lot_size);
+    }
+
+    fn check_depth_stats<MD: MarketDepth + L2MarketDepth>(mut depth: MD) {
+        for (px, qty) in [(100.0, 3.0), (99.9, 1.0), (99.5, 4.0)] {
+            depth.update_bid_depth(px, qty, 0);
+        }
+        for (px, qty) in [(100.1, 1.0), (100.3, 2.0), (101.0, 5.0)] {
+            depth.update_ask_depth(px, qty, 0);
+        }
+
+        assert_eq!(depth.bid_qty_in_range(995, 1000), 8.0);
+        assert_eq!(depth.bid_qty_in_range(999, 2000), 4.0);
+        assert_eq!(depth.ask_qty_in_range(0, 1003), 3.0);
+        assert_eq!(depth.ask_qty_in_range(1011, 1020), 0.0);
+        assert!((depth.imbalance(2) - 1.0 / 7.0).abs() < 1e-9);
+        assert!((depth.microprice() - 100.075).abs() < 1e-9);
+        assert_eq!(depth.qty_within_bps(Side::Buy, 10.0), 3.0);
+        assert_eq!(depth.qty_within_bps(Side::Sell, 10.0), 1.0);
+        assert_eq!(depth.qty_within_bps(Side::Buy, 100.0), 8.0);
+        assert_eq!(depth.qty_within_bps(Side::Sell, 100.0), 8.0);
+
+        depth.clear_depth(Side::Buy, f64::NAN);
+        assert_eq!(depth.bid_qty_in_range(995, 1000), 0.0);
+        assert_eq!(depth.imbalance(2), -1.0);
+        assert!(depth.microprice().is_nan());
+    }
+
+    #[test]
+    fn test_depth_stats() {
+        check_depth_stats(HashMapMarketDepth::new(0.1, 1.0));
+        check_depth_stats(BTreeMarketDepth::new(0.1, 1.0));
+        check_depth_stats(ROIVectorMarketDepth::new(0.1, 1.0, 90.0, 110.0));
+    }
 }
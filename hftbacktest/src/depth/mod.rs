@@ -1,24 +1,33 @@
-use std::collections::HashMap;
+use std::{
+    any::type_name,
+    collections::{hash_map::Entry, HashMap},
+    fmt::Debug,
+    mem::size_of_val,
+};
 
+pub use bbomarketdepth::BboMarketDepth;
 pub use btreemarketdepth::BTreeMarketDepth;
 pub use hashmapmarketdepth::HashMapMarketDepth;
 pub use roivectormarketdepth::ROIVectorMarketDepth;
 
 use crate::prelude::Side;
 
+mod bbomarketdepth;
 mod btreemarketdepth;
 mod hashmapmarketdepth;
 mod roivectormarketdepth;
+mod simd;
 
 #[cfg(any(feature = "unstable_fuse", doc))]
 mod fuse;
 
 #[cfg(any(feature = "unstable_fuse", doc))]
 pub use fuse::FusedHashMapMarketDepth;
+use thiserror::Error;
 
 use crate::{
     backtest::data::Data,
-    types::{Event, OrderId},
+    types::{Event, OrderId, BUY_EVENT, SELL_EVENT},
 };
 
 /// Represents no best bid in ticks.
@@ -27,6 +36,86 @@ pub const INVALID_MIN: i64 = i64::MIN;
 /// Represents no best ask in ticks.
 pub const INVALID_MAX: i64 = i64::MAX;
 
+/// The integer type in which a market depth stores the price ticks of its price levels.
+///
+/// The ticks are exchanged as `i64` through the [`MarketDepth`] interface regardless of the type
+/// used for storage.
+pub trait Tick: Copy + Ord + Debug {
+    /// Converts the price in ticks into this type, returning `None` if it overflows.
+    fn from_i64(tick: i64) -> Option<Self>;
+
+    /// Converts this into the price in ticks.
+    fn to_i64(self) -> i64;
+}
+
+impl Tick for i64 {
+    #[inline(always)]
+    fn from_i64(tick: i64) -> Option<Self> {
+        Some(tick)
+    }
+
+    #[inline(always)]
+    fn to_i64(self) -> i64 {
+        self
+    }
+}
+
+impl Tick for i32 {
+    #[inline(always)]
+    fn from_i64(tick: i64) -> Option<Self> {
+        i32::try_from(tick).ok()
+    }
+
+    #[inline(always)]
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+/// Error returned when the price range of a market depth cannot be represented in ticks by its
+/// tick type.
+#[derive(Error, Debug)]
+#[error("the max price {max_price} with tick size {tick_size} overflows `{tick_type}`")]
+pub struct TickOverflow {
+    pub max_price: f64,
+    pub tick_size: f64,
+    pub tick_type: &'static str,
+}
+
+/// Checks that the prices within `-max_price..=max_price` can be represented in ticks by `T`.
+pub(crate) fn check_tick_range<T: Tick>(
+    tick_size: f64,
+    max_price: f64,
+) -> Result<(), TickOverflow> {
+    let max_tick = (max_price / tick_size).round();
+    let fits = max_tick.is_finite()
+        && max_tick.abs() < i64::MAX as f64
+        && T::from_i64(max_tick.abs() as i64).is_some()
+        && T::from_i64(-(max_tick.abs() as i64)).is_some();
+    if fits {
+        Ok(())
+    } else {
+        Err(TickOverflow {
+            max_price,
+            tick_size,
+            tick_type: type_name::<T>(),
+        })
+    }
+}
+
+/// Converts the price in ticks into the key of the price level.
+///
+/// # Panics
+/// Panics if the price in ticks overflows `T`, which means the price is out of the range given at
+/// construction.
+#[inline(always)]
+pub(crate) fn key<T: Tick>(tick: i64) -> T {
+    match T::from_i64(tick) {
+        Some(key) => key,
+        None => panic!("the price tick {tick} overflows `{}`", type_name::<T>()),
+    }
+}
+
 /// Provides MarketDepth interface.
 pub trait MarketDepth {
     /// Returns the best bid price.
@@ -56,6 +145,98 @@ pub trait MarketDepth {
 
     /// Returns the quantity at the ask market depth for a given price in ticks.
     fn ask_qty_at_tick(&self, price_tick: i64) -> f64;
+
+    /// Returns the cumulative quantity at the bid market depth within the price range in ticks,
+    /// from `low_tick` to `high_tick` inclusive.
+    ///
+    /// The default implementation scans every tick in the range up to the best bid, so the range
+    /// should be bounded. Each market depth overrides it to scan its own storage.
+    fn bid_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        let high_tick = high_tick.min(self.best_bid_tick());
+        if self.best_bid_tick() == INVALID_MIN || low_tick > high_tick {
+            return 0.0;
+        }
+        (low_tick..=high_tick)
+            .map(|price_tick| self.bid_qty_at_tick(price_tick))
+            .sum()
+    }
+
+    /// Returns the cumulative quantity at the ask market depth within the price range in ticks,
+    /// from `low_tick` to `high_tick` inclusive.
+    ///
+    /// The default implementation scans every tick in the range from the best ask, so the range
+    /// should be bounded. Each market depth overrides it to scan its own storage.
+    fn ask_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        let low_tick = low_tick.max(self.best_ask_tick());
+        if self.best_ask_tick() == INVALID_MAX || low_tick > high_tick {
+            return 0.0;
+        }
+        (low_tick..=high_tick)
+            .map(|price_tick| self.ask_qty_at_tick(price_tick))
+            .sum()
+    }
+
+    /// Returns the order book imbalance, `(bid_qty - ask_qty) / (bid_qty + ask_qty)`, of the
+    /// cumulative quantities within `ticks` of the best bid and the best ask. It ranges from -1 to
+    /// 1, and is [`f64::NAN`] if both sides are empty.
+    fn imbalance(&self, ticks: i64) -> f64 {
+        let bid_qty = match self.best_bid_tick() {
+            INVALID_MIN => 0.0,
+            best_bid_tick => self.bid_qty_in_range(best_bid_tick - ticks, best_bid_tick),
+        };
+        let ask_qty = match self.best_ask_tick() {
+            INVALID_MAX => 0.0,
+            best_ask_tick => self.ask_qty_in_range(best_ask_tick, best_ask_tick + ticks),
+        };
+        let total = bid_qty + ask_qty;
+        if total > 0.0 {
+            (bid_qty - ask_qty) / total
+        } else {
+            f64::NAN
+        }
+    }
+
+    /// Returns the mid-price. If either side is empty, it returns [`f64::NAN`].
+    fn mid(&self) -> f64 {
+        if self.best_bid_tick() == INVALID_MIN || self.best_ask_tick() == INVALID_MAX {
+            return f64::NAN;
+        }
+        (self.best_bid() + self.best_ask()) / 2.0
+    }
+
+    /// Returns the microprice, the mid-price weighted by the quantities at the best bid and ask
+    /// so that it leans toward the side with less quantity. If either side is empty, it returns
+    /// [`f64::NAN`].
+    fn microprice(&self) -> f64 {
+        if self.best_bid_tick() == INVALID_MIN || self.best_ask_tick() == INVALID_MAX {
+            return f64::NAN;
+        }
+        let bid_qty = self.bid_qty_at_tick(self.best_bid_tick());
+        let ask_qty = self.ask_qty_at_tick(self.best_ask_tick());
+        (self.best_bid() * ask_qty + self.best_ask() * bid_qty) / (bid_qty + ask_qty)
+    }
+
+    /// Returns the cumulative quantity on the given side within `bps` basis points of the
+    /// mid-price. If either side is empty, it returns `0`.
+    fn qty_within_bps(&self, side: Side, bps: f64) -> f64 {
+        let best_bid_tick = self.best_bid_tick();
+        let best_ask_tick = self.best_ask_tick();
+        if best_bid_tick == INVALID_MIN || best_ask_tick == INVALID_MAX {
+            return 0.0;
+        }
+        let mid_tick = (best_bid_tick as f64 + best_ask_tick as f64) / 2.0;
+        let band = mid_tick * bps / 10_000.0;
+        match side {
+            Side::Buy => self.bid_qty_in_range((mid_tick - band).ceil() as i64, best_bid_tick),
+            Side::Sell => self.ask_qty_in_range(best_ask_tick, (mid_tick + band).floor() as i64),
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the approximate number of bytes of memory used by the market depth.
+    fn memory_usage(&self) -> usize {
+        size_of_val(self)
+    }
 }
 
 /// Provides Level2-specific market depth functions.
@@ -89,6 +270,123 @@ pub trait L2MarketDepth {
     /// Clears the market depth. If the side is [Side::None], both sides are cleared. In this case,
     /// `clear_upto_price` is ignored.
     fn clear_depth(&mut self, side: Side, clear_upto_price: f64);
+
+    /// Changes the tick size and the lot size, such as on a re-denomination of the instrument.
+    ///
+    /// The price levels are re-bucketed into the new tick size, aggregating the quantities of the
+    /// levels that fall into the same price. See [`rebucket_tick`] for the rounding.
+    fn rebucket(&mut self, tick_size: f64, lot_size: f64);
+
+    /// Updates the bid-side market depth by a price level of a depth snapshot and returns the same
+    /// tuple as [`update_bid_depth`](Self::update_bid_depth), with the best bid before the update.
+    ///
+    /// A level with a timestamp different from the previous snapshot level starts a new snapshot,
+    /// which clears the bid side before being applied, so that a full book resent by the feed
+    /// rebuilds the side. If the side has just been cleared by a depth clear event, which
+    /// determines the range the snapshot replaces, it is applied without clearing.
+    ///
+    /// By default, the level is applied in the same way as a depth update.
+    fn update_bid_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        self.update_bid_depth(price, qty, timestamp)
+    }
+
+    /// Updates the ask-side market depth by a price level of a depth snapshot and returns the same
+    /// tuple as [`update_ask_depth`](Self::update_ask_depth), with the best ask before the update.
+    ///
+    /// See [`update_bid_snapshot`](Self::update_bid_snapshot) for the snapshot boundaries.
+    fn update_ask_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        self.update_ask_depth(price, qty, timestamp)
+    }
+}
+
+/// Converts the price in ticks of `tick_size` into the price in ticks of `new_tick_size`. A bid is
+/// rounded down and an ask up, so that a re-bucketed book doesn't cross and a re-normalized order
+/// doesn't become more aggressive.
+pub fn rebucket_tick(price_tick: i64, side: Side, tick_size: f64, new_tick_size: f64) -> i64 {
+    let price_tick = price_tick as f64 * tick_size / new_tick_size;
+    // Allows for the floating-point error of the prices that fall on the new tick.
+    let eps = 1e-9;
+    match side {
+        Side::Buy => (price_tick + eps).floor() as i64,
+        Side::Sell => (price_tick - eps).ceil() as i64,
+        _ => price_tick.round() as i64,
+    }
+}
+
+/// Re-buckets the depth snapshot levels into `new_tick_size`, aggregating the quantities of the
+/// levels that fall into the same price.
+pub(crate) fn rebucket_snapshot(
+    levels: &[Event],
+    tick_size: f64,
+    new_tick_size: f64,
+) -> Vec<Event> {
+    let mut rebucketed: Vec<Event> = Vec::with_capacity(levels.len());
+    let mut index: HashMap<(u64, i64), usize> = HashMap::new();
+    for level in levels {
+        let (side, side_ev) = if level.ev & BUY_EVENT == BUY_EVENT {
+            (Side::Buy, BUY_EVENT)
+        } else {
+            (Side::Sell, SELL_EVENT)
+        };
+        let price_tick = (level.px / tick_size).round() as i64;
+        let price_tick = rebucket_tick(price_tick, side, tick_size, new_tick_size);
+        match index.entry((side_ev, price_tick)) {
+            Entry::Occupied(entry) => {
+                let merged = &mut rebucketed[*entry.get()];
+                merged.qty += level.qty;
+                merged.exch_ts = merged.exch_ts.max(level.exch_ts);
+                merged.local_ts = merged.local_ts.max(level.local_ts);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(rebucketed.len());
+                rebucketed.push(Event {
+                    px: price_tick as f64 * new_tick_size,
+                    ..level.clone()
+                });
+            }
+        }
+    }
+    rebucketed
+}
+
+/// Tracks the snapshot boundaries on a side of the market depth.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SnapshotBoundary {
+    timestamp: Option<i64>,
+    cleared: bool,
+}
+
+impl SnapshotBoundary {
+    /// Returns `true` if a snapshot level at the timestamp starts a new snapshot that replaces the
+    /// side.
+    #[inline]
+    pub fn is_boundary(&self, timestamp: i64) -> bool {
+        self.timestamp != Some(timestamp) && !self.cleared
+    }
+
+    /// Marks that a snapshot level at the timestamp has been applied.
+    #[inline]
+    pub fn apply(&mut self, timestamp: i64) {
+        self.timestamp = Some(timestamp);
+        self.cleared = false;
+    }
+
+    /// Marks that the side has been cleared.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.timestamp = None;
+        self.cleared = true;
+    }
 }
 
 /// Provides a method to initialize the `MarketDepth` from the given snapshot data, such as
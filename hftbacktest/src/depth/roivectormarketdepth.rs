@@ -1,10 +1,24 @@
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    mem::size_of,
+};
 
-use super::{ApplySnapshot, L3MarketDepth, L3Order, MarketDepth, INVALID_MAX, INVALID_MIN};
+use super::{
+    rebucket_snapshot,
+    rebucket_tick,
+    simd,
+    ApplySnapshot,
+    L3MarketDepth,
+    L3Order,
+    MarketDepth,
+    SnapshotBoundary,
+    INVALID_MAX,
+    INVALID_MIN,
+};
 use crate::{
     backtest::{data::Data, BacktestError},
     prelude::{L2MarketDepth, OrderId, Side},
-    types::{Event, BUY_EVENT, SELL_EVENT},
+    types::{Event, BUY_EVENT, DEPTH_SNAPSHOT_EVENT, EXCH_EVENT, LOCAL_EVENT, SELL_EVENT},
 };
 
 /// L2/L3 market depth implementation based on a vector within the range of interest.
@@ -25,30 +39,50 @@ pub struct ROIVectorMarketDepth {
     pub roi_ub: i64,
     pub roi_lb: i64,
     pub orders: HashMap<OrderId, L3Order>,
+    bid_snapshot: SnapshotBoundary,
+    ask_snapshot: SnapshotBoundary,
+    auto_recenter: bool,
 }
 
 #[inline(always)]
 fn depth_below(depth: &[f64], start: i64, end: i64, roi_lb: i64, roi_ub: i64) -> i64 {
     let start = (start.min(roi_ub) - roi_lb) as usize;
     let end = (end.max(roi_lb) - roi_lb) as usize;
-    for t in (end..start).rev() {
-        if unsafe { *depth.get_unchecked(t) } > 0f64 {
-            return t as i64 + roi_lb;
-        }
+    if end >= start {
+        return INVALID_MIN;
+    }
+    match simd::last_positive(&depth[end..start]) {
+        Some(t) => (end + t) as i64 + roi_lb,
+        None => INVALID_MIN,
     }
-    INVALID_MIN
 }
 
 #[inline(always)]
 fn depth_above(depth: &[f64], start: i64, end: i64, roi_lb: i64, roi_ub: i64) -> i64 {
     let start = (start.max(roi_lb) - roi_lb) as usize;
     let end = (end.min(roi_ub) - roi_lb) as usize;
-    for t in (start + 1)..(end + 1) {
-        if unsafe { *depth.get_unchecked(t) } > 0f64 {
-            return t as i64 + roi_lb;
-        }
+    if start >= end {
+        return INVALID_MAX;
+    }
+    match simd::first_positive(&depth[start + 1..end + 1]) {
+        Some(t) => (start + 1 + t) as i64 + roi_lb,
+        None => INVALID_MAX,
+    }
+}
+
+#[inline]
+fn shift_depth(depth: &mut [f64], shift: i64) {
+    let len = depth.len();
+    let n = shift.unsigned_abs() as usize;
+    if n >= len {
+        depth.fill(0.0);
+    } else if shift > 0 {
+        depth.copy_within(n.., 0);
+        depth[len - n..].fill(0.0);
+    } else {
+        depth.copy_within(..len - n, n);
+        depth[..n].fill(0.0);
     }
-    INVALID_MAX
 }
 
 impl ROIVectorMarketDepth {
@@ -78,6 +112,92 @@ impl ROIVectorMarketDepth {
             roi_lb,
             roi_ub,
             orders: HashMap::new(),
+            bid_snapshot: Default::default(),
+            ask_snapshot: Default::default(),
+            auto_recenter: false,
+        }
+    }
+
+    /// Sets whether to re-center the range of interest around the mid-price when the price trends
+    /// out of it. The default value is `false`.
+    ///
+    /// The range is re-centered by L2 updates when the mid-price leaves the middle half of the
+    /// range, or when a side is empty and a price level arrives outside the range.
+    pub fn auto_recenter(self, auto_recenter: bool) -> Self {
+        Self {
+            auto_recenter,
+            ..self
+        }
+    }
+
+    /// Moves the range of interest to be centered around the given price in ticks, keeping its
+    /// size.
+    ///
+    /// The levels that fall outside the new range are dropped, and the levels that come into the
+    /// range are empty until they are updated again, except for the levels of the L3 orders, which
+    /// are restored from the orders.
+    pub fn recenter(&mut self, center_tick: i64) {
+        let shift = center_tick - (self.roi_lb + (self.roi_ub - self.roi_lb) / 2);
+        if shift == 0 {
+            return;
+        }
+        shift_depth(&mut self.bid_depth, shift);
+        shift_depth(&mut self.ask_depth, shift);
+        self.roi_lb += shift;
+        self.roi_ub += shift;
+
+        if !self.orders.is_empty() {
+            self.bid_depth.fill(0.0);
+            self.ask_depth.fill(0.0);
+            for order in self.orders.values() {
+                if order.price_tick < self.roi_lb || order.price_tick > self.roi_ub {
+                    continue;
+                }
+                let t = (order.price_tick - self.roi_lb) as usize;
+                if order.side == Side::Buy {
+                    self.bid_depth[t] += order.qty;
+                } else {
+                    self.ask_depth[t] += order.qty;
+                }
+            }
+        }
+
+        // All bids are dropped if the best bid falls below the range, and all asks are dropped if
+        // the best ask falls above the range.
+        if self.best_bid_tick < self.roi_lb {
+            self.best_bid_tick = INVALID_MIN;
+        } else if self.best_bid_tick > self.roi_ub {
+            self.best_bid_tick = self
+                .bid_depth
+                .iter()
+                .rposition(|qty| *qty > 0.0)
+                .map_or(INVALID_MIN, |t| t as i64 + self.roi_lb);
+        }
+        if self.best_ask_tick > self.roi_ub {
+            self.best_ask_tick = INVALID_MAX;
+        } else if self.best_ask_tick < self.roi_lb {
+            self.best_ask_tick = self
+                .ask_depth
+                .iter()
+                .position(|qty| *qty > 0.0)
+                .map_or(INVALID_MAX, |t| t as i64 + self.roi_lb);
+        }
+        if self.best_bid_tick == INVALID_MIN {
+            self.low_bid_tick = INVALID_MAX;
+        }
+        if self.best_ask_tick == INVALID_MAX {
+            self.high_ask_tick = INVALID_MIN;
+        }
+    }
+
+    fn check_recenter(&mut self) {
+        if self.best_bid_tick == INVALID_MIN || self.best_ask_tick == INVALID_MAX {
+            return;
+        }
+        let mid_tick = (self.best_bid_tick + self.best_ask_tick) / 2;
+        let margin = (self.roi_ub - self.roi_lb) / 4;
+        if mid_tick < self.roi_lb + margin || mid_tick > self.roi_ub - margin {
+            self.recenter(mid_tick);
         }
     }
 
@@ -110,6 +230,46 @@ impl ROIVectorMarketDepth {
     pub fn ask_depth(&self) -> &[f64] {
         self.ask_depth.as_slice()
     }
+
+    /// Returns up to `n` bid price levels within the range of interest as the price in ticks and
+    /// the quantity, from the best bid downward.
+    pub fn bid_levels(&self, n: usize) -> Vec<(i64, f64)> {
+        let mut levels = Vec::with_capacity(n);
+        if self.best_bid_tick == INVALID_MIN {
+            return levels;
+        }
+        let mut end = (self.best_bid_tick.min(self.roi_ub) - self.roi_lb + 1) as usize;
+        while levels.len() < n {
+            match simd::last_positive(&self.bid_depth[..end]) {
+                Some(t) => {
+                    levels.push((t as i64 + self.roi_lb, self.bid_depth[t]));
+                    end = t;
+                }
+                None => break,
+            }
+        }
+        levels
+    }
+
+    /// Returns up to `n` ask price levels within the range of interest as the price in ticks and
+    /// the quantity, from the best ask upward.
+    pub fn ask_levels(&self, n: usize) -> Vec<(i64, f64)> {
+        let mut levels = Vec::with_capacity(n);
+        if self.best_ask_tick == INVALID_MAX {
+            return levels;
+        }
+        let mut start = (self.best_ask_tick.max(self.roi_lb) - self.roi_lb) as usize;
+        while levels.len() < n {
+            match simd::first_positive(&self.ask_depth[start..]) {
+                Some(t) => {
+                    levels.push(((start + t) as i64 + self.roi_lb, self.ask_depth[start + t]));
+                    start += t + 1;
+                }
+                None => break,
+            }
+        }
+        levels
+    }
 }
 
 impl L2MarketDepth for ROIVectorMarketDepth {
@@ -124,6 +284,14 @@ impl L2MarketDepth for ROIVectorMarketDepth {
         let prev_best_bid_tick = self.best_bid_tick;
         let prev_qty;
 
+        if self.auto_recenter
+            && qty_lot > 0
+            && self.best_bid_tick == INVALID_MIN
+            && (price_tick < self.roi_lb || price_tick > self.roi_ub)
+        {
+            // The side has moved out of the range of interest.
+            self.recenter(price_tick);
+        }
         if price_tick < self.roi_lb || price_tick > self.roi_ub {
             // This is outside the range of interest.
             return (
@@ -170,6 +338,9 @@ impl L2MarketDepth for ROIVectorMarketDepth {
             }
             self.low_bid_tick = self.low_bid_tick.min(price_tick);
         }
+        if self.auto_recenter {
+            self.check_recenter();
+        }
         (
             price_tick,
             prev_best_bid_tick,
@@ -191,6 +362,14 @@ impl L2MarketDepth for ROIVectorMarketDepth {
         let prev_best_ask_tick = self.best_ask_tick;
         let prev_qty;
 
+        if self.auto_recenter
+            && qty_lot > 0
+            && self.best_ask_tick == INVALID_MAX
+            && (price_tick < self.roi_lb || price_tick > self.roi_ub)
+        {
+            // The side has moved out of the range of interest.
+            self.recenter(price_tick);
+        }
         if price_tick < self.roi_lb || price_tick > self.roi_ub {
             // This is outside the range of interest.
             return (
@@ -237,6 +416,9 @@ impl L2MarketDepth for ROIVectorMarketDepth {
             }
             self.high_ask_tick = self.high_ask_tick.max(price_tick);
         }
+        if self.auto_recenter {
+            self.check_recenter();
+        }
         (
             price_tick,
             prev_best_ask_tick,
@@ -250,6 +432,7 @@ impl L2MarketDepth for ROIVectorMarketDepth {
     fn clear_depth(&mut self, side: Side, clear_upto_price: f64) {
         match side {
             Side::Buy => {
+                self.bid_snapshot.clear();
                 if clear_upto_price.is_finite() {
                     let clear_upto = (clear_upto_price / self.tick_size).round() as i64;
                     if self.best_bid_tick != INVALID_MIN {
@@ -289,6 +472,7 @@ impl L2MarketDepth for ROIVectorMarketDepth {
                 }
             }
             Side::Sell => {
+                self.ask_snapshot.clear();
                 if clear_upto_price.is_finite() {
                     let clear_upto = (clear_upto_price / self.tick_size).round() as i64;
                     if self.best_ask_tick != INVALID_MAX {
@@ -328,6 +512,8 @@ impl L2MarketDepth for ROIVectorMarketDepth {
                 }
             }
             Side::None => {
+                self.bid_snapshot.clear();
+                self.ask_snapshot.clear();
                 self.bid_depth.iter_mut().for_each(|q| *q = 0.0);
                 self.ask_depth.iter_mut().for_each(|q| *q = 0.0);
                 self.best_bid_tick = INVALID_MIN;
@@ -340,6 +526,67 @@ impl L2MarketDepth for ROIVectorMarketDepth {
             }
         }
     }
+
+    fn update_bid_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let prev_best_bid_tick = self.best_bid_tick;
+        if self.bid_snapshot.is_boundary(timestamp) {
+            self.clear_depth(Side::Buy, f64::NAN);
+        }
+        self.bid_snapshot.apply(timestamp);
+        let (price_tick, _, best_bid_tick, prev_qty, qty, timestamp) =
+            self.update_bid_depth(price, qty, timestamp);
+        (
+            price_tick,
+            prev_best_bid_tick,
+            best_bid_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn update_ask_snapshot(
+        &mut self,
+        price: f64,
+        qty: f64,
+        timestamp: i64,
+    ) -> (i64, i64, i64, f64, f64, i64) {
+        let prev_best_ask_tick = self.best_ask_tick;
+        if self.ask_snapshot.is_boundary(timestamp) {
+            self.clear_depth(Side::Sell, f64::NAN);
+        }
+        self.ask_snapshot.apply(timestamp);
+        let (price_tick, _, best_ask_tick, prev_qty, qty, timestamp) =
+            self.update_ask_depth(price, qty, timestamp);
+        (
+            price_tick,
+            prev_best_ask_tick,
+            best_ask_tick,
+            prev_qty,
+            qty,
+            timestamp,
+        )
+    }
+
+    fn rebucket(&mut self, tick_size: f64, lot_size: f64) {
+        let levels = rebucket_snapshot(&self.snapshot(), self.tick_size, tick_size);
+        // Keeps the range of interest in prices.
+        self.roi_lb = rebucket_tick(self.roi_lb, Side::Buy, self.tick_size, tick_size);
+        self.roi_ub = rebucket_tick(self.roi_ub, Side::Sell, self.tick_size, tick_size);
+        let roi_range = (self.roi_ub + 1 - self.roi_lb) as usize;
+        self.bid_depth.resize(roi_range, 0.0);
+        self.ask_depth.resize(roi_range, 0.0);
+        self.bid_depth.shrink_to_fit();
+        self.ask_depth.shrink_to_fit();
+        self.tick_size = tick_size;
+        self.lot_size = lot_size;
+        self.apply_snapshot(&Data::from_data(&levels));
+    }
 }
 
 impl MarketDepth for ROIVectorMarketDepth {
@@ -408,6 +655,34 @@ impl MarketDepth for ROIVectorMarketDepth {
             }
         }
     }
+
+    fn bid_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        let low_tick = low_tick.max(self.roi_lb);
+        let high_tick = high_tick.min(self.best_bid_tick).min(self.roi_ub);
+        if self.best_bid_tick == INVALID_MIN || low_tick > high_tick {
+            return 0.0;
+        }
+        simd::sum(
+            &self.bid_depth[(low_tick - self.roi_lb) as usize..=(high_tick - self.roi_lb) as usize],
+        )
+    }
+
+    fn ask_qty_in_range(&self, low_tick: i64, high_tick: i64) -> f64 {
+        let low_tick = low_tick.max(self.best_ask_tick).max(self.roi_lb);
+        let high_tick = high_tick.min(self.roi_ub);
+        if self.best_ask_tick == INVALID_MAX || low_tick > high_tick {
+            return 0.0;
+        }
+        simd::sum(
+            &self.ask_depth[(low_tick - self.roi_lb) as usize..=(high_tick - self.roi_lb) as usize],
+        )
+    }
+
+    fn memory_usage(&self) -> usize {
+        size_of::<Self>()
+            + (self.bid_depth.capacity() + self.ask_depth.capacity()) * size_of::<f64>()
+            + self.orders.capacity() * size_of::<(OrderId, L3Order)>()
+    }
 }
 
 impl ApplySnapshot for ROIVectorMarketDepth {
@@ -422,6 +697,21 @@ impl ApplySnapshot for ROIVectorMarketDepth {
         for qty in &mut self.ask_depth {
             *qty = 0.0;
         }
+        if self.auto_recenter {
+            let mut best_bid_tick = INVALID_MIN;
+            let mut best_ask_tick = INVALID_MAX;
+            for row_num in 0..data.len() {
+                let price_tick = (data[row_num].px / self.tick_size).round() as i64;
+                if data[row_num].ev & BUY_EVENT == BUY_EVENT {
+                    best_bid_tick = best_bid_tick.max(price_tick);
+                } else if data[row_num].ev & SELL_EVENT == SELL_EVENT {
+                    best_ask_tick = best_ask_tick.min(price_tick);
+                }
+            }
+            if best_bid_tick != INVALID_MIN && best_ask_tick != INVALID_MAX {
+                self.recenter((best_bid_tick + best_ask_tick) / 2);
+            }
+        }
         for row_num in 0..data.len() {
             let price = data[row_num].px;
             let qty = data[row_num].qty;
@@ -449,7 +739,30 @@ impl ApplySnapshot for ROIVectorMarketDepth {
     }
 
     fn snapshot(&self) -> Vec<Event> {
-        unimplemented!();
+        let level = |ev: u64, t: usize, qty: f64| Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | ev | DEPTH_SNAPSHOT_EVENT,
+            exch_ts: 0,
+            local_ts: 0,
+            px: (t as i64 + self.roi_lb) as f64 * self.tick_size,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        };
+        self.bid_depth
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, qty)| **qty > 0.0)
+            .map(|(t, qty)| level(BUY_EVENT, t, *qty))
+            .chain(
+                self.ask_depth
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, qty)| **qty > 0.0)
+                    .map(|(t, qty)| level(SELL_EVENT, t, *qty)),
+            )
+            .collect()
     }
 }
 
@@ -754,7 +1067,14 @@ impl L3MarketDepth for ROIVectorMarketDepth {
 #[cfg(test)]
 mod tests {
     use crate::{
-        depth::{L3MarketDepth, MarketDepth, ROIVectorMarketDepth, INVALID_MAX, INVALID_MIN},
+        depth::{
+            L2MarketDepth,
+            L3MarketDepth,
+            MarketDepth,
+            ROIVectorMarketDepth,
+            INVALID_MAX,
+            INVALID_MIN,
+        },
         types::Side,
     };
 
@@ -978,4 +1298,54 @@ mod tests {
         assert_eq_qty!(depth.ask_qty_at_tick(4981), 0.0, lot_size);
         assert_eq_qty!(depth.ask_qty_at_tick(5002), 0.002, lot_size);
     }
+
+    #[test]
+    fn test_auto_recenter() {
+        let lot_size = 0.001;
+        let mut depth = ROIVectorMarketDepth::new(0.1, lot_size, 90.0, 110.0).auto_recenter(true);
+
+        depth.update_bid_depth(100.0, 0.001, 0);
+        depth.update_ask_depth(100.1, 0.001, 0);
+        assert_eq!(depth.roi_lb, 900);
+
+        // The mid-price leaves the middle half of the range.
+        depth.update_bid_depth(105.5, 0.002, 0);
+        depth.update_ask_depth(105.6, 0.003, 0);
+        assert_eq!(depth.roi_lb, 955);
+        assert_eq!(depth.roi_ub, 1155);
+        assert_eq!(depth.best_bid_tick(), 1055);
+        assert_eq!(depth.best_ask_tick(), 1056);
+        assert_eq_qty!(depth.bid_qty_at_tick(1000), 0.001, lot_size);
+        assert_eq_qty!(depth.ask_qty_at_tick(1056), 0.003, lot_size);
+
+        // The price levels arrive outside the range while the book is empty.
+        let mut depth = ROIVectorMarketDepth::new(0.1, lot_size, 90.0, 110.0).auto_recenter(true);
+        depth.update_bid_depth(200.0, 0.001, 0);
+        assert_eq!(depth.roi_lb, 1900);
+        assert_eq!(depth.best_bid_tick(), 2000);
+        assert_eq_qty!(depth.bid_qty_at_tick(2000), 0.001, lot_size);
+    }
+
+    #[test]
+    fn test_levels() {
+        let lot_size = 0.001;
+        let mut depth = ROIVectorMarketDepth::new(0.1, lot_size, 90.0, 110.0);
+        for (px, qty) in [(100.0, 1.0), (99.0, 2.0), (95.0, 3.0)] {
+            depth.update_bid_depth(px, qty, 0);
+        }
+        for (px, qty) in [(100.1, 1.0), (102.0, 2.0)] {
+            depth.update_ask_depth(px, qty, 0);
+        }
+
+        assert_eq!(depth.bid_levels(2), vec![(1000, 1.0), (990, 2.0)]);
+        assert_eq!(depth.bid_levels(5).len(), 3);
+        assert_eq!(depth.ask_levels(5), vec![(1001, 1.0), (1020, 2.0)]);
+        assert_eq!(depth.bid_qty_in_range(950, 990), 5.0);
+
+        // The best bid is removed, so the next level is found by the scan.
+        depth.update_bid_depth(100.0, 0.0, 0);
+        assert_eq!(depth.best_bid_tick(), 990);
+        depth.update_ask_depth(100.1, 0.0, 0);
+        assert_eq!(depth.best_ask_tick(), 1020);
+    }
 }
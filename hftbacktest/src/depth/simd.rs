@@ -0,0 +1,137 @@
+//! Vectorized scans over the quantity arrays of [`ROIVectorMarketDepth`](super::ROIVectorMarketDepth).
+//!
+//! `std::simd` is not available on stable Rust, so the `x86_64` versions use the SSE2 intrinsics,
+//! which are part of the `x86_64` baseline and don't need the runtime feature detection. The other
+//! architectures use the fixed-width lane arrays, which the compiler vectorizes.
+
+/// The number of values processed per iteration.
+const LANES: usize = 8;
+
+/// Returns the sum of the values.
+///
+/// The values are summed in lanes, so the result may differ from the sequential sum by rounding.
+#[inline]
+pub(crate) fn sum(values: &[f64]) -> f64 {
+    let chunks = values.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    sum_chunks(chunks) + remainder.iter().sum::<f64>()
+}
+
+/// Returns the index of the first positive value.
+#[inline]
+pub(crate) fn first_positive(values: &[f64]) -> Option<usize> {
+    let chunks = values.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        if any_positive(chunk) {
+            return chunk.iter().position(|&v| v > 0.0).map(|j| i * LANES + j);
+        }
+    }
+    let offset = values.len() - remainder.len();
+    remainder.iter().position(|&v| v > 0.0).map(|j| offset + j)
+}
+
+/// Returns the index of the last positive value.
+#[inline]
+pub(crate) fn last_positive(values: &[f64]) -> Option<usize> {
+    let chunks = values.rchunks_exact(LANES);
+    let remainder = chunks.remainder();
+    for (i, chunk) in chunks.enumerate() {
+        if any_positive(chunk) {
+            let offset = values.len() - (i + 1) * LANES;
+            return chunk.iter().rposition(|&v| v > 0.0).map(|j| offset + j);
+        }
+    }
+    remainder.iter().rposition(|&v| v > 0.0)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn sum_chunks(chunks: std::slice::ChunksExact<'_, f64>) -> f64 {
+    use std::arch::x86_64::{_mm_add_pd, _mm_loadu_pd, _mm_setzero_pd, _mm_storeu_pd};
+
+    // SAFETY: SSE2 is always available on x86_64, and each chunk has `LANES` values to load.
+    unsafe {
+        let mut acc = [_mm_setzero_pd(); LANES / 2];
+        for chunk in chunks {
+            let ptr = chunk.as_ptr();
+            for (i, acc) in acc.iter_mut().enumerate() {
+                *acc = _mm_add_pd(*acc, _mm_loadu_pd(ptr.add(2 * i)));
+            }
+        }
+        let acc = _mm_add_pd(_mm_add_pd(acc[0], acc[1]), _mm_add_pd(acc[2], acc[3]));
+        let mut out = [0.0; 2];
+        _mm_storeu_pd(out.as_mut_ptr(), acc);
+        out[0] + out[1]
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[inline]
+fn sum_chunks(chunks: std::slice::ChunksExact<'_, f64>) -> f64 {
+    let mut acc = [0.0; LANES];
+    for chunk in chunks {
+        for (acc, v) in acc.iter_mut().zip(chunk) {
+            *acc += v;
+        }
+    }
+    acc.iter().sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn any_positive(chunk: &[f64]) -> bool {
+    use std::arch::x86_64::{
+        _mm_cmpgt_pd,
+        _mm_loadu_pd,
+        _mm_movemask_pd,
+        _mm_or_pd,
+        _mm_setzero_pd,
+    };
+
+    debug_assert_eq!(chunk.len(), LANES);
+    // SAFETY: SSE2 is always available on x86_64, and the chunk has `LANES` values to load.
+    unsafe {
+        let ptr = chunk.as_ptr();
+        let zero = _mm_setzero_pd();
+        let a = _mm_or_pd(
+            _mm_cmpgt_pd(_mm_loadu_pd(ptr), zero),
+            _mm_cmpgt_pd(_mm_loadu_pd(ptr.add(2)), zero),
+        );
+        let b = _mm_or_pd(
+            _mm_cmpgt_pd(_mm_loadu_pd(ptr.add(4)), zero),
+            _mm_cmpgt_pd(_mm_loadu_pd(ptr.add(6)), zero),
+        );
+        _mm_movemask_pd(_mm_or_pd(a, b)) != 0
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[inline(always)]
+fn any_positive(chunk: &[f64]) -> bool {
+    chunk.iter().fold(false, |any, &v| any | (v > 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{first_positive, last_positive, sum};
+
+    #[test]
+    fn test_scans() {
+        let mut values = vec![0.0; 37];
+        assert_eq!(sum(&values), 0.0);
+        assert_eq!(first_positive(&values), None);
+        assert_eq!(last_positive(&values), None);
+
+        for (i, v) in [(3, 1.0), (17, 2.0), (35, 4.0)] {
+            values[i] = v;
+        }
+        assert_eq!(sum(&values), 7.0);
+        assert_eq!(sum(&values[4..35]), 2.0);
+        assert_eq!(first_positive(&values), Some(3));
+        assert_eq!(first_positive(&values[4..]), Some(13));
+        assert_eq!(last_positive(&values), Some(35));
+        assert_eq!(last_positive(&values[..35]), Some(17));
+        assert_eq!(last_positive(&values[..3]), None);
+    }
+}
@@ -0,0 +1,382 @@
+use std::collections::VecDeque;
+
+use crate::{
+    depth::{MarketDepth, INVALID_MAX, INVALID_MIN},
+    types::{Bot, Event},
+};
+
+/// An indicator that is incrementally updated from the market events, which works identically in
+/// backtesting and live trading.
+pub trait Indicator {
+    /// Updates the indicator with the market trade.
+    fn on_trade(&mut self, trade: &Event);
+
+    /// Updates the indicator with the market depth after it has changed.
+    ///
+    /// * `timestamp` - The timestamp at which the market depth is observed.
+    /// * `depth` - The market depth.
+    fn on_depth(&mut self, timestamp: i64, depth: &dyn MarketDepth);
+
+    /// Resets the state of the indicator. This should be called when the market depth is rebuilt
+    /// from a snapshot, such as after a reconnection, since the events in between are missing.
+    fn reset(&mut self);
+
+    /// Returns the current value, or `None` if the indicator has not received enough data yet.
+    fn value(&self) -> Option<f64>;
+}
+
+/// Updates the indicators with the last market trades and the current market depth of the asset.
+/// This is intended to be called on every iteration of the event loop, followed by
+/// [`Bot::clear_last_trades`] so that the same trades are not applied again.
+///
+/// * `bot` - The bot from which the market data is retrieved.
+/// * `asset_no` - Asset number from which the market data is retrieved.
+/// * `indicators` - The indicators to update.
+pub fn update_indicators<MD, I>(bot: &I, asset_no: usize, indicators: &mut [&mut dyn Indicator])
+where
+    MD: MarketDepth,
+    I: Bot<MD>,
+{
+    let timestamp = bot.current_timestamp();
+    let depth = bot.depth(asset_no);
+    for indicator in indicators.iter_mut() {
+        for trade in bot.last_trades(asset_no) {
+            indicator.on_trade(trade);
+        }
+        indicator.on_depth(timestamp, depth);
+    }
+}
+
+/// An exponentially weighted moving average with a time-based decay, which is fed the trade
+/// prices.
+#[derive(Clone, Debug)]
+pub struct Ewma {
+    half_life: f64,
+    value: Option<f64>,
+    last_timestamp: i64,
+}
+
+impl Ewma {
+    /// Constructs an `Ewma`.
+    ///
+    /// * `half_life` - The time over which the weight of an observation decays by half, in the
+    ///   same unit as the timestamps.
+    pub fn new(half_life: i64) -> Self {
+        Self {
+            half_life: half_life as f64,
+            value: None,
+            last_timestamp: 0,
+        }
+    }
+
+    /// Updates the average with an arbitrary observation, which allows the average to be applied
+    /// to a series other than the trade prices.
+    pub fn update(&mut self, timestamp: i64, value: f64) {
+        self.value = Some(match self.value {
+            Some(prev) => {
+                let elapsed = (timestamp - self.last_timestamp).max(0) as f64;
+                let alpha = 1.0 - 0.5f64.powf(elapsed / self.half_life);
+                prev + alpha * (value - prev)
+            }
+            None => value,
+        });
+        self.last_timestamp = timestamp;
+    }
+}
+
+impl Indicator for Ewma {
+    fn on_trade(&mut self, trade: &Event) {
+        self.update(trade.local_ts, trade.px);
+    }
+
+    fn on_depth(&mut self, _timestamp: i64, _depth: &dyn MarketDepth) {}
+
+    fn reset(&mut self) {
+        self.value = None;
+        self.last_timestamp = 0;
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+/// The standard deviation of the log returns between the consecutive trade prices over a rolling
+/// time window. The value isn't annualized.
+#[derive(Clone, Debug)]
+pub struct RollingVolatility {
+    window: i64,
+    last_px: Option<f64>,
+    returns: VecDeque<(i64, f64)>,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl RollingVolatility {
+    /// Constructs a `RollingVolatility`.
+    ///
+    /// * `window` - The length of the window, in the same unit as the timestamps.
+    pub fn new(window: i64) -> Self {
+        Self {
+            window,
+            last_px: None,
+            returns: VecDeque::new(),
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    fn evict(&mut self, timestamp: i64) {
+        while let Some(&(ts, ret)) = self.returns.front() {
+            if ts > timestamp - self.window {
+                break;
+            }
+            self.sum -= ret;
+            self.sum_sq -= ret * ret;
+            self.returns.pop_front();
+        }
+    }
+}
+
+impl Indicator for RollingVolatility {
+    fn on_trade(&mut self, trade: &Event) {
+        if let Some(last_px) = self.last_px {
+            let ret = (trade.px / last_px).ln();
+            self.returns.push_back((trade.local_ts, ret));
+            self.sum += ret;
+            self.sum_sq += ret * ret;
+        }
+        self.last_px = Some(trade.px);
+        self.evict(trade.local_ts);
+    }
+
+    fn on_depth(&mut self, timestamp: i64, _depth: &dyn MarketDepth) {
+        self.evict(timestamp);
+    }
+
+    fn reset(&mut self) {
+        self.last_px = None;
+        self.returns.clear();
+        self.sum = 0.0;
+        self.sum_sq = 0.0;
+    }
+
+    fn value(&self) -> Option<f64> {
+        let n = self.returns.len() as f64;
+        if n < 2.0 {
+            return None;
+        }
+        let mean = self.sum / n;
+        Some(((self.sum_sq / n - mean * mean).max(0.0) * n / (n - 1.0)).sqrt())
+    }
+}
+
+/// The volume-weighted average trade price over a rolling time window.
+#[derive(Clone, Debug)]
+pub struct RollingVwap {
+    window: i64,
+    trades: VecDeque<(i64, f64, f64)>,
+    sum_value: f64,
+    sum_qty: f64,
+}
+
+impl RollingVwap {
+    /// Constructs a `RollingVwap`.
+    ///
+    /// * `window` - The length of the window, in the same unit as the timestamps.
+    pub fn new(window: i64) -> Self {
+        Self {
+            window,
+            trades: VecDeque::new(),
+            sum_value: 0.0,
+            sum_qty: 0.0,
+        }
+    }
+
+    fn evict(&mut self, timestamp: i64) {
+        while let Some(&(ts, value, qty)) = self.trades.front() {
+            if ts > timestamp - self.window {
+                break;
+            }
+            self.sum_value -= value;
+            self.sum_qty -= qty;
+            self.trades.pop_front();
+        }
+    }
+}
+
+impl Indicator for RollingVwap {
+    fn on_trade(&mut self, trade: &Event) {
+        let value = trade.px * trade.qty;
+        self.trades.push_back((trade.local_ts, value, trade.qty));
+        self.sum_value += value;
+        self.sum_qty += trade.qty;
+        self.evict(trade.local_ts);
+    }
+
+    fn on_depth(&mut self, timestamp: i64, _depth: &dyn MarketDepth) {
+        self.evict(timestamp);
+    }
+
+    fn reset(&mut self) {
+        self.trades.clear();
+        self.sum_value = 0.0;
+        self.sum_qty = 0.0;
+    }
+
+    fn value(&self) -> Option<f64> {
+        if self.trades.is_empty() || self.sum_qty <= 0.0 {
+            None
+        } else {
+            Some(self.sum_value / self.sum_qty)
+        }
+    }
+}
+
+/// The order flow imbalance (OFI) over a rolling time window, which is the net quantity added to
+/// the best bid minus that added to the best ask, as defined by Cont, Kukanov and Stoikov (2014).
+/// It is updated from the changes in the best bid and ask observed in the market depth.
+#[derive(Clone, Debug)]
+pub struct OrderFlowImbalance {
+    window: i64,
+    prev: Option<(i64, f64, i64, f64)>,
+    flows: VecDeque<(i64, f64)>,
+    sum: f64,
+}
+
+impl OrderFlowImbalance {
+    /// Constructs an `OrderFlowImbalance`.
+    ///
+    /// * `window` - The length of the window, in the same unit as the timestamps.
+    pub fn new(window: i64) -> Self {
+        Self {
+            window,
+            prev: None,
+            flows: VecDeque::new(),
+            sum: 0.0,
+        }
+    }
+
+    fn evict(&mut self, timestamp: i64) {
+        while let Some(&(ts, flow)) = self.flows.front() {
+            if ts > timestamp - self.window {
+                break;
+            }
+            self.sum -= flow;
+            self.flows.pop_front();
+        }
+    }
+}
+
+impl Indicator for OrderFlowImbalance {
+    fn on_trade(&mut self, _trade: &Event) {}
+
+    fn on_depth(&mut self, timestamp: i64, depth: &dyn MarketDepth) {
+        let bid_tick = depth.best_bid_tick();
+        let ask_tick = depth.best_ask_tick();
+        if bid_tick == INVALID_MIN || ask_tick == INVALID_MAX {
+            self.evict(timestamp);
+            return;
+        }
+        let bid_qty = depth.bid_qty_at_tick(bid_tick);
+        let ask_qty = depth.ask_qty_at_tick(ask_tick);
+        if let Some((prev_bid_tick, prev_bid_qty, prev_ask_tick, prev_ask_qty)) = self.prev {
+            let mut flow = 0.0;
+            if bid_tick >= prev_bid_tick {
+                flow += bid_qty;
+            }
+            if bid_tick <= prev_bid_tick {
+                flow -= prev_bid_qty;
+            }
+            if ask_tick <= prev_ask_tick {
+                flow -= ask_qty;
+            }
+            if ask_tick >= prev_ask_tick {
+                flow += prev_ask_qty;
+            }
+            if flow != 0.0 {
+                self.flows.push_back((timestamp, flow));
+                self.sum += flow;
+            }
+        }
+        self.prev = Some((bid_tick, bid_qty, ask_tick, ask_qty));
+        self.evict(timestamp);
+    }
+
+    fn reset(&mut self) {
+        self.prev = None;
+        self.flows.clear();
+        self.sum = 0.0;
+    }
+
+    fn value(&self) -> Option<f64> {
+        self.prev.map(|_| self.sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ewma, Indicator, OrderFlowImbalance, RollingVolatility, RollingVwap};
+    use crate::{
+        depth::{HashMapMarketDepth, L2MarketDepth},
+        types::{Event, BUY_EVENT, LOCAL_TRADE_EVENT},
+    };
+
+    fn trade(local_ts: i64, px: f64, qty: f64) -> Event {
+        Event {
+            ev: LOCAL_TRADE_EVENT | BUY_EVENT,
+            exch_ts: local_ts - 5,
+            local_ts,
+            px,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_indicators() {
+        let mut ewma = Ewma::new(100);
+        ewma.on_trade(&trade(100, 100.0, 1.0));
+        ewma.on_trade(&trade(200, 102.0, 1.0));
+        assert!((ewma.value().unwrap() - 101.0).abs() < 1e-9);
+        ewma.reset();
+        assert_eq!(ewma.value(), None);
+
+        let mut vol = RollingVolatility::new(1000);
+        vol.on_trade(&trade(100, 100.0, 1.0));
+        vol.on_trade(&trade(200, 101.0, 1.0));
+        assert_eq!(vol.value(), None);
+        vol.on_trade(&trade(300, 100.0, 1.0));
+        let (r1, r2) = ((101.0f64 / 100.0).ln(), (100.0f64 / 101.0).ln());
+        let mean = (r1 + r2) / 2.0;
+        let expected = ((r1 - mean).powi(2) + (r2 - mean).powi(2)).sqrt();
+        assert!((vol.value().unwrap() - expected).abs() < 1e-12);
+
+        let mut vwap = RollingVwap::new(100);
+        vwap.on_trade(&trade(100, 100.0, 1.0));
+        vwap.on_trade(&trade(150, 103.0, 2.0));
+        assert!((vwap.value().unwrap() - 102.0).abs() < 1e-9);
+        // The first trade falls out of the window.
+        vwap.on_trade(&trade(210, 101.0, 2.0));
+        assert!((vwap.value().unwrap() - 102.0).abs() < 1e-9);
+
+        let mut depth = HashMapMarketDepth::new(0.1, 1.0);
+        depth.update_bid_depth(100.0, 5.0, 0);
+        depth.update_ask_depth(100.1, 5.0, 0);
+        let mut ofi = OrderFlowImbalance::new(1000);
+        ofi.on_depth(100, &depth);
+        assert_eq!(ofi.value(), Some(0.0));
+        depth.update_bid_depth(100.0, 8.0, 0);
+        ofi.on_depth(200, &depth);
+        assert_eq!(ofi.value(), Some(3.0));
+        depth.update_ask_depth(100.1, 2.0, 0);
+        ofi.on_depth(300, &depth);
+        assert_eq!(ofi.value(), Some(6.0));
+        ofi.on_depth(1250, &depth);
+        assert_eq!(ofi.value(), Some(3.0));
+        ofi.reset();
+        assert_eq!(ofi.value(), None);
+    }
+}
@@ -19,15 +19,35 @@
 //!
 //! ## Feature flags
 //!
-//! Currently, `default` enables `backtest`, `live` features.
+//! Currently, `default` enables `backtest`, `fs`, `live`, `compression` features.
 //!
 //! - `backtest`: Enables backtesting features.
+//! - `fs`: Enables reading and writing the `numpy` data files, including loading the data from
+//!         files via `DataSource::File`.
+//! - `compression`: Enables reading and writing zstd and LZ4 compressed data files.
 //! - `live`: Enables a live trading bot.
 //! - `unstable_l3`: Enables Level3 Market-By-Order backtesting.
 //! - `unstable_fuse`: Enables the market depth fusion feature, which aggregates different market
 //!                    depth streams to provide the finest granularity and the most frequent,
 //!                    up-to-date market depth information
 //!
+//! ## WebAssembly
+//!
+//! The backtesting core, including the exchange models, market depth, and queue models, compiles
+//! to `wasm32` with only the `backtest` feature enabled, which is useful for browser-based demos
+//! on small datasets.
+//!
+//! ```toml
+//! hftbacktest = { version = "0.5.0", default-features = false, features = ["backtest"] }
+//! ```
+//!
+//! Since threads and file system access are unavailable in the browser, the data is loaded
+//! sequentially and should be provided in memory via
+//! [`DataSource::Data`](crate::backtest::DataSource::Data), without the `fs` feature.
+//!
+//! For the same reason, the throughput measurement, which needs the wall clock, and the parallel
+//! runs of `backtest::grid` and `backtest::scenario` are not provided on `wasm32`.
+//!
 
 /// Provides backtesting features.
 #[cfg(any(feature = "backtest", doc))]
@@ -40,6 +60,23 @@ pub mod depth;
 #[cfg(feature = "live")]
 pub mod live;
 
+/// Provides bar aggregation from trades.
+pub mod bar;
+
+/// Provides the buffer of the last market trades.
+pub mod trades;
+
+/// Provides indicators updated from the market events.
+pub mod indicator;
+
+/// Provides the clock shared by backtesting and live trading.
+pub mod clock;
+
+/// Provides order execution helpers that work identically in backtesting and live trading.
+pub mod algo;
+
+pub mod risk;
+
 /// Defines HftBacktest types.
 pub mod types;
 
@@ -48,3 +85,7 @@ pub mod prelude;
 
 /// Provides utilities.
 mod utils;
+
+/// Provides the fixtures shared by the unit tests.
+#[cfg(test)]
+mod test_utils;
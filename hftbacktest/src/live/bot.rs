@@ -1,35 +1,85 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
+    hint,
+    io,
+    mem,
+    net::SocketAddr,
+    thread,
     time::{Duration, Instant},
 };
 
 use chrono::Utc;
 use rand::Rng;
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    depth::{L2MarketDepth, MarketDepth},
-    live::{ipc::Channel, Instrument},
+    backtest::models::QueueState,
+    bar::Bar,
+    clock::{Clock, SystemClock},
+    depth::{L2MarketDepth, MarketDepth, INVALID_MAX, INVALID_MIN},
+    live::{
+        control::{ControlCommand, ControlServer, ControlStatus, InstrumentStatus, OrderStatus},
+        ipc::Channel,
+        journal::Journal,
+        signal,
+        store::{SavedInstrument, SavedState, StateStore},
+        supervisor::Supervisor,
+        Instrument,
+    },
+    risk::{
+        flatten_order,
+        open_qty,
+        NewOrder,
+        PositionLimit,
+        RiskControl,
+        RiskGuard,
+        RiskStats,
+        RiskViolation,
+    },
     types::{
         Bot,
         BuildError,
+        DepthUpdate,
+        ErrorKind,
         Event,
+        EventStats,
+        Fill,
+        FilterViolation,
+        FundingRate,
+        InstrumentInfo,
+        Liquidation,
         LiveError,
         LiveEvent,
         LiveRequest,
+        MarginState,
+        OptionMetadata,
         OrdType,
         Order,
+        OrderFilter,
         OrderId,
+        OrderIdGenerator,
         OrderRequest,
+        Portfolio,
+        PositionSide,
         Side,
         StateValues,
         Status,
         TimeInForce,
+        Value,
+        WaitAny,
+        WaitCondition,
         WaitOrderResponse,
+        LOCAL_ASK_DEPTH_CLEAR_EVENT,
         LOCAL_ASK_DEPTH_EVENT,
+        LOCAL_ASK_DEPTH_SNAPSHOT_EVENT,
+        LOCAL_BID_DEPTH_CLEAR_EVENT,
         LOCAL_BID_DEPTH_EVENT,
+        LOCAL_BID_DEPTH_SNAPSHOT_EVENT,
         LOCAL_BUY_TRADE_EVENT,
+        LOCAL_DEPTH_CLEAR_EVENT,
+        LOCAL_LIQUIDATION_EVENT,
+        LOCAL_OPEN_INTEREST_EVENT,
         LOCAL_SELL_TRADE_EVENT,
     },
 };
@@ -48,6 +98,10 @@ pub enum BotError {
     Timeout,
     #[error("Interrupted")]
     Interrupted,
+    #[error("FilterViolation: {0}")]
+    FilterViolation(#[from] FilterViolation),
+    #[error("RiskViolation: {0}")]
+    RiskViolation(#[from] RiskViolation),
     #[error("Custom: {0}")]
     Custom(String),
 }
@@ -55,6 +109,15 @@ pub enum BotError {
 pub type ErrorHandler = Box<dyn Fn(LiveError) -> Result<(), BotError>>;
 pub type OrderRecvHook = Box<dyn Fn(&Order, &Order) -> Result<(), BotError>>;
 
+/// The time to wait for the connectors to answer the instrument information queries.
+const INSTRUMENT_INFO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The default time to wait for the cancellations of the working orders when the bot is closed.
+const DEFAULT_CLOSE_TIMEOUT: i64 = 10_000_000_000;
+
+/// The time to wait for the connectors to report the orders restored from the state store.
+const RECONCILE_TIMEOUT: i64 = 10_000_000_000;
+
 fn generate_random_id() -> u64 {
     // Initialize the random number generator
     let mut rng = rand::thread_rng();
@@ -63,12 +126,150 @@ fn generate_random_id() -> u64 {
     rng.gen::<u64>()
 }
 
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(core_id: usize) -> Result<(), io::Error> {
+    if core_id >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("CPU core {core_id} is out of range"),
+        ));
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        libc::CPU_SET(core_id, &mut set);
+        if libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_core_id: usize) -> Result<(), io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "CPU affinity is only supported on Linux",
+    ))
+}
+
+/// Queries the instrument information of the instruments to be auto-filled, and replaces their
+/// tick size, lot size and market depth with the ones from the exchange.
+fn auto_fill_instruments<CH, MD>(
+    id: u64,
+    channel: &mut CH,
+    instruments: &mut [Instrument<MD>],
+) -> Result<(), BuildError>
+where
+    CH: Channel,
+{
+    let mut pending = HashSet::new();
+    for (inst_no, instrument) in instruments.iter().enumerate() {
+        if instrument.auto_fill.is_none() {
+            continue;
+        }
+        let request = LiveRequest::QueryInstrumentInfo {
+            symbol: instrument.symbol.clone(),
+        };
+        channel
+            .send(id, inst_no, request)
+            .map_err(|error| BuildError::Error(anyhow::Error::from(error)))?;
+        pending.insert(inst_no);
+    }
+
+    let instant = Instant::now();
+    while !pending.is_empty() {
+        let remaining = INSTRUMENT_INFO_TIMEOUT
+            .checked_sub(instant.elapsed())
+            .ok_or_else(|| {
+                BuildError::Error(anyhow::anyhow!(
+                    "The instrument information query timed out."
+                ))
+            })?;
+        match channel.recv_timeout(id, remaining) {
+            Ok((inst_no, LiveEvent::InstrumentInfo { symbol, info }))
+                if pending.remove(&inst_no) =>
+            {
+                let info = info.ok_or_else(|| {
+                    BuildError::Error(anyhow::anyhow!(
+                        "The connector doesn't provide the instrument information of {symbol}."
+                    ))
+                })?;
+                info!(%symbol, ?info, "Auto-fills the instrument.");
+                let instrument = &mut instruments[inst_no];
+                let depth = instrument.auto_fill.take().unwrap();
+                instrument.depth = depth(info.tick_size, info.lot_size);
+                instrument.tick_size = info.tick_size;
+                instrument.lot_size = info.lot_size;
+                if instrument.order_filter.is_none() {
+                    instrument.order_filter = Some(OrderFilter::from(&info));
+                }
+                instrument.info = Some(info);
+            }
+            // The other events before the registration are irrelevant.
+            Ok(_) | Err(BotError::Timeout) => {}
+            Err(error) => return Err(BuildError::Error(anyhow::Error::from(error))),
+        }
+    }
+    Ok(())
+}
+
+/// How [`LiveBot`] waits for the next event from the connectors while elapsing, which trades the
+/// CPU usage for the wake-up latency.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum WaitStrategy {
+    /// Waits in the IPC channel until an event arrives or the duration elapses.
+    #[default]
+    Block,
+    /// Polls the connectors without waiting, spinning on a CPU core until the duration elapses.
+    /// This has the lowest wake-up latency but fully occupies the core.
+    BusySpin,
+    /// Spins for the period since the last event, and then waits as with
+    /// [`WaitStrategy::Block`], so that the bursts of events are received with the spinning
+    /// latency while an idle market doesn't occupy the core.
+    SpinThenPark(Duration),
+    /// Polls the connectors, sleeping for the interval between the polls. This has the least CPU
+    /// usage, but the wake-up latency is up to the interval plus the timer slack of the OS.
+    Sleep(Duration),
+}
+
+impl WaitStrategy {
+    /// Returns the timeout to receive the next event, given the time since the last event and the
+    /// remaining duration.
+    fn recv_timeout(&self, idle: Duration, remaining: Duration) -> Duration {
+        match self {
+            WaitStrategy::Block => remaining,
+            WaitStrategy::BusySpin | WaitStrategy::Sleep(_) => Duration::ZERO,
+            WaitStrategy::SpinThenPark(spin) if idle < *spin => Duration::ZERO,
+            WaitStrategy::SpinThenPark(_) => remaining,
+        }
+    }
+
+    /// Idles between the polls.
+    fn idle(&self, remaining: Duration) {
+        match self {
+            WaitStrategy::Sleep(interval) => thread::sleep((*interval).min(remaining)),
+            _ => hint::spin_loop(),
+        }
+    }
+}
+
 /// Live [`LiveBot`] builder.
 pub struct LiveBotBuilder<MD> {
     id: u64,
     instruments: Vec<Instrument<MD>>,
     error_handler: Option<ErrorHandler>,
     order_hook: Option<OrderRecvHook>,
+    cpu_affinity: Option<usize>,
+    wait_strategy: WaitStrategy,
+    control_addr: Option<String>,
+    journal: Option<(String, bool)>,
+    heartbeat_timeout: Option<i64>,
+    restart_commands: HashMap<String, Vec<String>>,
+    state_store: Option<(String, bool)>,
+    cancel_on_close: bool,
+    close_timeout: i64,
+    handle_signals: bool,
+    risk_guard: Option<Box<dyn RiskGuard + Send>>,
 }
 
 impl<MD> Default for LiveBotBuilder<MD> {
@@ -85,6 +286,17 @@ impl<MD> LiveBotBuilder<MD> {
             instruments: Default::default(),
             error_handler: None,
             order_hook: None,
+            cpu_affinity: None,
+            wait_strategy: WaitStrategy::Block,
+            control_addr: None,
+            journal: None,
+            heartbeat_timeout: None,
+            restart_commands: HashMap::new(),
+            state_store: None,
+            cancel_on_close: true,
+            close_timeout: DEFAULT_CLOSE_TIMEOUT,
+            handle_signals: false,
+            risk_guard: None,
         }
     }
 
@@ -122,22 +334,250 @@ impl<MD> LiveBotBuilder<MD> {
         }
     }
 
-    /// Sets the bot ID. It must be unique among all bots connected to the same `Connector`.
+    /// Sets the bot ID. It must be unique among all bots connected to the same `Connector`. The
+    /// lower 16 bits are used as the bot ID of the order IDs from [`Bot::next_order_id`], so they
+    /// should also be unique for the order IDs not to collide. By default, a random ID is used.
     pub fn id(self, id: u64) -> Self {
         Self { id, ..self }
     }
 
+    /// Pins the bot's event loop to the CPU core. The thread building the bot is pinned, so the
+    /// bot should be built on the thread that runs it. This is only supported on Linux, and
+    /// building the bot fails elsewhere.
+    pub fn cpu_affinity(self, core_id: usize) -> Self {
+        Self {
+            cpu_affinity: Some(core_id),
+            ..self
+        }
+    }
+
+    /// Sets whether the bot busy-polls the connectors, spinning without waiting, instead of
+    /// waiting for an event with a timeout while elapsing. This reduces the latency of receiving
+    /// an event at the cost of occupying a CPU core. The default value is `false`.
+    ///
+    /// This is a shorthand for [`WaitStrategy::BusySpin`] and [`WaitStrategy::Block`].
+    pub fn busy_poll(self, busy_poll: bool) -> Self {
+        self.wait_strategy(if busy_poll {
+            WaitStrategy::BusySpin
+        } else {
+            WaitStrategy::Block
+        })
+    }
+
+    /// Sets how the bot waits for the next event while elapsing. The default is
+    /// [`WaitStrategy::Block`]. Spinning is best combined with
+    /// [`cpu_affinity`](Self::cpu_affinity), so that the receive loop owns an isolated core.
+    pub fn wait_strategy(self, wait_strategy: WaitStrategy) -> Self {
+        Self {
+            wait_strategy,
+            ..self
+        }
+    }
+
+    /// Embeds a JSON-over-TCP control server bound to the address, through which an operator can
+    /// query the positions, open orders and PnL of the running bot, and send the commands to halt,
+    /// cancel all orders, and adjust the parameters. See [`ControlCommand`] for the protocol.
+    /// The commands are applied, and the status is built for the pending queries, each time the
+    /// bot elapses.
+    pub fn control_server(self, addr: &str) -> Self {
+        Self {
+            control_addr: Some(addr.to_string()),
+            ..self
+        }
+    }
+
+    /// Records every order request, order response, fill and error to the append-only journal
+    /// file as a JSON object per line, for audits and post-mortems.
+    ///
+    /// * `path` - The path of the journal file. If it exists, the records are appended to it.
+    /// * `sync` - If `true`, each record is flushed to the disk, which survives a crash of the
+    ///   operating system at the cost of latency. Otherwise, it survives only a crash of the
+    ///   process.
+    pub fn journal(self, path: &str, sync: bool) -> Self {
+        Self {
+            journal: Some((path.to_string(), sync)),
+            ..self
+        }
+    }
+
+    /// Supervises the connectors by the heartbeats they send. If a connector sends no heartbeat
+    /// within the timeout, it's considered dead and a [`LiveError`] of
+    /// [`ErrorKind::ConnectorUnresponsive`] with the connector name is passed to the error
+    /// handler. Once it sends a heartbeat again, its instruments are registered again. The
+    /// heartbeats are checked each time the bot elapses.
+    ///
+    /// * `timeout` - The timeout in nanoseconds.
+    pub fn heartbeat_timeout(self, timeout: i64) -> Self {
+        Self {
+            heartbeat_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Sets the command by which the connector is relaunched when it's considered dead by the
+    /// heartbeat supervision, such as `["connector", "binancefutures", "binancefutures",
+    /// "config.toml"]`. The relaunched process is killed when the bot is dropped. This takes
+    /// effect only if [`heartbeat_timeout`](Self::heartbeat_timeout) is set.
+    pub fn restart_command(mut self, connector_name: &str, command: &[&str]) -> Self {
+        self.restart_commands.insert(
+            connector_name.to_string(),
+            command.iter().map(|arg| arg.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Persists the open orders, the positions and the values saved by
+    /// [`LiveBot::save_value`] to the file, so that the bot can restart mid-session without
+    /// double-quoting. The state is saved each time the bot elapses or submits an order, if it has
+    /// changed, by a background thread so that saving doesn't block the trading loop.
+    ///
+    /// When the bot is built, the saved state is restored. The restored orders are reconciled
+    /// against the ones reported by the connectors: an order reported by its order ID is kept, and
+    /// an external order with the same side, price and quantity is adopted under the saved order
+    /// ID. The restored orders that aren't reported within 10 seconds are considered expired.
+    ///
+    /// * `path` - The path of the state file.
+    /// * `sync` - If `true`, each save is flushed to the disk, which survives a crash of the
+    ///   operating system. Otherwise, it survives only a crash of the process.
+    pub fn state_store(self, path: &str, sync: bool) -> Self {
+        Self {
+            state_store: Some((path.to_string(), sync)),
+            ..self
+        }
+    }
+
+    /// Sets whether the working orders are canceled when the bot is closed. The default value is
+    /// `true`.
+    pub fn cancel_on_close(self, cancel_on_close: bool) -> Self {
+        Self {
+            cancel_on_close,
+            ..self
+        }
+    }
+
+    /// Sets the time to wait for the cancellations of the working orders to be confirmed when the
+    /// bot is closed. The default value is 10 seconds.
+    ///
+    /// * `timeout` - The timeout in nanoseconds.
+    pub fn close_timeout(self, close_timeout: i64) -> Self {
+        Self {
+            close_timeout,
+            ..self
+        }
+    }
+
+    /// Handles `SIGINT` and `SIGTERM`, so that a container or an operator can stop the bot
+    /// cleanly. Once either signal is received, the bot shuts down by [`LiveBot::shutdown`] and
+    /// then [`BotError::Interrupted`] is returned from [`Bot::elapse`], [`Bot::wait_next_feed`] and
+    /// the other waiting methods. The signal handlers are process-wide. This is only supported on
+    /// Unix, and building the bot fails elsewhere.
+    pub fn handle_signals(self, handle_signals: bool) -> Self {
+        Self {
+            handle_signals,
+            ..self
+        }
+    }
+
+    /// Sets the [`RiskGuard`] that checks every new order before it's submitted. An order that
+    /// fails the check isn't submitted and [`BotError::RiskViolation`] is returned.
+    pub fn risk_guard<G>(self, guard: G) -> Self
+    where
+        G: RiskGuard + Send + 'static,
+    {
+        Self {
+            risk_guard: Some(Box::new(guard)),
+            ..self
+        }
+    }
+
     /// Builds a live [`LiveBot`] based on the registered connectors and assets.
     pub fn build<CH>(self) -> Result<LiveBot<CH, MD>, BuildError>
     where
         CH: Channel,
     {
+        if let Some(core_id) = self.cpu_affinity {
+            set_cpu_affinity(core_id)
+                .map_err(|error| BuildError::Error(anyhow::Error::from(error)))?;
+        }
+
+        let control = match &self.control_addr {
+            Some(addr) => Some(
+                ControlServer::bind(addr)
+                    .map_err(|error| BuildError::Error(anyhow::Error::from(error)))?,
+            ),
+            None => None,
+        };
+        let journal = match &self.journal {
+            Some((path, sync)) => Some(
+                Journal::open(path, *sync)
+                    .map_err(|error| BuildError::Error(anyhow::Error::from(error)))?,
+            ),
+            None => None,
+        };
+
+        if self.handle_signals {
+            signal::install().map_err(|error| BuildError::Error(anyhow::Error::from(error)))?;
+        }
+
+        let store = self
+            .state_store
+            .as_ref()
+            .map(|(path, sync)| StateStore::new(path, *sync));
+        let saved = match &store {
+            Some(store) => store
+                .load()
+                .map_err(|error| BuildError::Error(anyhow::Error::from(error)))?,
+            None => None,
+        };
+
+        let supervisor = self.heartbeat_timeout.map(|timeout| {
+            Supervisor::new(
+                timeout,
+                self.instruments
+                    .iter()
+                    .map(|instrument| instrument.connector_name.as_str()),
+                self.restart_commands,
+                Utc::now().timestamp_nanos_opt().unwrap(),
+            )
+        });
+
         let id = self.id;
         let mut channel = CH::build(&self.instruments)?;
+        let mut instruments = self.instruments;
+        let now = Utc::now().timestamp_nanos_opt().unwrap();
+        for instrument in instruments.iter_mut() {
+            instrument.last_feed_time = now;
+        }
+
+        auto_fill_instruments(id, &mut channel, &mut instruments)?;
+
+        // Restores the saved state before the registration so that the orders and position
+        // reported by the connectors take precedence.
+        let mut unreconciled = HashSet::new();
+        let mut values = HashMap::new();
+        if let Some(mut saved) = saved {
+            for (inst_no, instrument) in instruments.iter_mut().enumerate() {
+                let Some(saved) = saved.instruments.remove(&instrument.symbol) else {
+                    continue;
+                };
+                info!(
+                    symbol = instrument.symbol,
+                    position = saved.position,
+                    orders = saved.orders.len(),
+                    "Restores the saved state."
+                );
+                instrument.state.position = saved.position;
+                for order in saved.orders {
+                    unreconciled.insert((inst_no, order.order_id));
+                    instrument.orders.insert(order.order_id, order);
+                }
+            }
+            values = saved.values;
+        }
 
         // Requests to prepare a given asset for trading.
         // The Connector will send the current orders on this asset.
-        for (inst_no, instrument) in self.instruments.iter().enumerate() {
+        for (inst_no, instrument) in instruments.iter().enumerate() {
             info!(
                 connector_name = instrument.connector_name,
                 symbol = instrument.symbol,
@@ -151,6 +591,7 @@ impl<MD> LiveBotBuilder<MD> {
                         symbol: instrument.symbol.clone(),
                         tick_size: instrument.tick_size,
                         lot_size: instrument.lot_size,
+                        depth: instrument.depth_level,
                     },
                 )
                 .map_err(|error| BuildError::Error(anyhow::Error::from(error)))?;
@@ -159,9 +600,27 @@ impl<MD> LiveBotBuilder<MD> {
         Ok(LiveBot {
             id,
             channel,
-            instruments: self.instruments,
+            instruments,
             error_handler: self.error_handler,
             order_hook: self.order_hook,
+            wait_strategy: self.wait_strategy,
+            control,
+            journal,
+            halted: false,
+            params: HashMap::new(),
+            supervisor,
+            margins: HashMap::new(),
+            order_ids: OrderIdGenerator::new(id as u16, Utc::now().timestamp() as u32),
+            store,
+            values,
+            unreconciled,
+            reconcile_deadline: now + RECONCILE_TIMEOUT,
+            dirty: false,
+            cancel_on_close: self.cancel_on_close,
+            close_timeout: self.close_timeout,
+            closed: false,
+            handle_signals: self.handle_signals,
+            risk: RiskControl::new(self.risk_guard),
         })
     }
 }
@@ -194,6 +653,45 @@ pub struct LiveBot<CH, MD> {
     instruments: Vec<Instrument<MD>>,
     error_handler: Option<ErrorHandler>,
     order_hook: Option<OrderRecvHook>,
+    wait_strategy: WaitStrategy,
+    control: Option<ControlServer>,
+    journal: Option<Journal>,
+    halted: bool,
+    params: HashMap<String, f64>,
+    supervisor: Option<Supervisor>,
+    margins: HashMap<String, MarginState>,
+    order_ids: OrderIdGenerator,
+    store: Option<StateStore>,
+    values: HashMap<String, f64>,
+    unreconciled: HashSet<(usize, OrderId)>,
+    reconcile_deadline: i64,
+    dirty: bool,
+    cancel_on_close: bool,
+    close_timeout: i64,
+    closed: bool,
+    handle_signals: bool,
+    risk: RiskControl,
+}
+
+/// The result of shutting down an asset by [`LiveBot::shutdown`].
+#[derive(Clone, Debug)]
+pub struct ShutdownResult {
+    pub asset_no: usize,
+    pub symbol: String,
+    /// The number of the working orders requested to be canceled.
+    pub canceled: usize,
+    /// The order IDs of the orders still working after the timeout, whose cancellations aren't
+    /// confirmed.
+    pub remaining: Vec<OrderId>,
+    /// The position when the asset is shut down.
+    pub position: f64,
+}
+
+impl ShutdownResult {
+    /// Returns `true` if no order is left working.
+    pub fn is_clean(&self) -> bool {
+        self.remaining.is_empty()
+    }
 }
 
 impl<CH, MD> LiveBot<CH, MD>
@@ -201,6 +699,522 @@ where
     CH: Channel,
     MD: MarketDepth + L2MarketDepth,
 {
+    /// Returns the ratio of the number of order requests sent to the number of fills received for
+    /// the asset, which venues may limit. If there is no fill, the number of order requests is
+    /// returned.
+    pub fn message_to_trade_ratio(&self, asset_no: usize) -> f64 {
+        let instrument = self.instruments.get(asset_no).unwrap();
+        instrument.num_messages as f64 / instrument.num_fills.max(1) as f64
+    }
+
+    /// Clears the market depth of the asset and requests a fresh market depth snapshot from the
+    /// connector, so that an operator can recover from a suspected corruption of the order book
+    /// without restarting the bot. The snapshot is applied while elapsing, as with the other feeds.
+    pub fn resync(&mut self, asset_no: usize) -> Result<(), BotError> {
+        let instrument = self
+            .instruments
+            .get_mut(asset_no)
+            .ok_or(BotError::InstrumentNotFound)?;
+        instrument.depth.clear_depth(Side::None, 0.0);
+        let symbol = instrument.symbol.clone();
+        info!(%symbol, "Requests the market depth resync.");
+        self.channel
+            .send(self.id, asset_no, LiveRequest::Resync { symbol })
+    }
+
+    /// Returns the address to which the control server is bound, if it is enabled.
+    pub fn control_addr(&self) -> Option<SocketAddr> {
+        self.control.as_ref().map(|control| control.local_addr())
+    }
+
+    /// Returns `true` if the bot is halted through the control server. While halted, new orders
+    /// are rejected.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Returns the parameter set through the control server.
+    pub fn control_param(&self, name: &str) -> Option<f64> {
+        self.params.get(name).copied()
+    }
+
+    fn process_control(&mut self) -> Result<(), BotError> {
+        let commands = match &self.control {
+            Some(control) => control.take_commands(),
+            None => return Ok(()),
+        };
+        for command in commands {
+            info!(?command, "Applies the control command.");
+            match command {
+                ControlCommand::Halt => self.halted = true,
+                ControlCommand::Resume => self.halted = false,
+                ControlCommand::CancelAll => {
+                    let orders: Vec<_> = self
+                        .instruments
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(asset_no, instrument)| {
+                            instrument
+                                .orders
+                                .values()
+                                .filter(|order| order.cancellable())
+                                .map(move |order| (asset_no, order.order_id))
+                        })
+                        .collect();
+                    for (asset_no, order_id) in orders {
+                        self.cancel(asset_no, order_id, false)?;
+                    }
+                }
+                ControlCommand::SetParam { name, value } => {
+                    self.params.insert(name, value);
+                }
+                ControlCommand::Status => {}
+            }
+        }
+
+        if let Some(control) = &self.control {
+            if control.status_requested() {
+                control.publish(self.control_status());
+            }
+        }
+        Ok(())
+    }
+
+    fn control_status(&self) -> ControlStatus {
+        ControlStatus {
+            timestamp: self.current_timestamp(),
+            halted: self.halted,
+            params: self.params.clone(),
+            instruments: self
+                .instruments
+                .iter()
+                .map(|instrument| {
+                    let mid_price = (instrument.depth.best_bid_tick() != INVALID_MIN
+                        && instrument.depth.best_ask_tick() != INVALID_MAX)
+                        .then(|| (instrument.depth.best_bid() + instrument.depth.best_ask()) / 2.0);
+                    InstrumentStatus {
+                        symbol: instrument.symbol.clone(),
+                        position: instrument.state.position,
+                        balance: instrument.state.balance,
+                        fee: instrument.state.fee,
+                        mid_price,
+                        equity: mid_price.map(|mid_price| {
+                            instrument.state.balance + instrument.state.position * mid_price
+                                - instrument.state.fee
+                        }),
+                        open_orders: instrument
+                            .orders
+                            .values()
+                            .filter(|order| order.active())
+                            .map(|order| OrderStatus {
+                                order_id: order.order_id,
+                                side: format!("{:?}", order.side),
+                                price: order.price(),
+                                qty: order.qty,
+                                leaves_qty: order.leaves_qty,
+                                status: format!("{:?}", order.status),
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn supervise(&mut self) -> Result<(), BotError> {
+        let dead = match self.supervisor.as_mut() {
+            Some(supervisor) => supervisor.check(Utc::now().timestamp_nanos_opt().unwrap()),
+            None => return Ok(()),
+        };
+        for connector_name in dead {
+            self.handle_error(LiveError::with(
+                ErrorKind::ConnectorUnresponsive,
+                Value::String(connector_name),
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn check_staleness(&mut self) -> Result<(), BotError> {
+        let now = Utc::now().timestamp_nanos_opt().unwrap();
+        for asset_no in 0..self.instruments.len() {
+            let instrument = unsafe { self.instruments.get_unchecked_mut(asset_no) };
+            let Some(threshold) = instrument.stale_threshold else {
+                continue;
+            };
+            let elapsed = now - instrument.last_feed_time;
+            if instrument.stale || elapsed <= threshold {
+                continue;
+            }
+            instrument.stale = true;
+            warn!(symbol = instrument.symbol, %elapsed, "The market data is stale.");
+            let value = Value::Map(HashMap::from([
+                ("asset_no".to_string(), Value::Int(asset_no as i64)),
+                (
+                    "symbol".to_string(),
+                    Value::String(instrument.symbol.clone()),
+                ),
+                ("elapsed".to_string(), Value::Int(elapsed)),
+            ]));
+            self.handle_error(LiveError::with(ErrorKind::StaleFeed, value))?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the market data of the asset is stale, which is checked only if the
+    /// stale threshold of the instrument is set.
+    pub fn is_stale(&self, asset_no: usize) -> bool {
+        self.instruments
+            .get(asset_no)
+            .map(|instrument| instrument.stale)
+            .unwrap_or(false)
+    }
+
+    /// Returns the time in nanoseconds since the last market data event of the asset was received.
+    pub fn feed_age(&self, asset_no: usize) -> Option<i64> {
+        self.instruments
+            .get(asset_no)
+            .map(|instrument| Utc::now().timestamp_nanos_opt().unwrap() - instrument.last_feed_time)
+    }
+
+    /// Returns the contract specification of the asset if it's an option.
+    pub fn option_metadata(&self, asset_no: usize) -> Option<&OptionMetadata> {
+        self.instruments.get(asset_no)?.option.as_ref()
+    }
+
+    /// Returns the trading rules of the asset, if they're queried from the connector by
+    /// [`Instrument::auto_fill`].
+    pub fn instrument_info(&self, asset_no: usize) -> Option<&InstrumentInfo> {
+        self.instruments.get(asset_no)?.info.as_ref()
+    }
+
+    /// Returns the latest mark price of the asset, if it's published by the connector.
+    pub fn mark_price(&self, asset_no: usize) -> Option<f64> {
+        self.instruments.get(asset_no)?.mark_price
+    }
+
+    /// Returns the latest index price of the asset, if it's published by the connector.
+    pub fn index_price(&self, asset_no: usize) -> Option<f64> {
+        self.instruments.get(asset_no)?.index_price
+    }
+
+    /// Returns the latest predicted funding rate of the asset, if it's a perpetual contract and
+    /// the connector publishes it.
+    pub fn funding_rate(&self, asset_no: usize) -> Option<&FundingRate> {
+        self.instruments.get(asset_no)?.funding_rate.as_ref()
+    }
+
+    /// Returns the open orders of the asset on the exchange that weren't placed by the bot, such as
+    /// the ones placed manually or left over from a crash, keyed by the exchange order ID. They're
+    /// reported by the connectors that support it when the instrument is registered.
+    pub fn external_orders(&self, asset_no: usize) -> Option<&HashMap<String, Order>> {
+        Some(&self.instruments.get(asset_no)?.external_orders)
+    }
+
+    /// Takes over the external order so that it's managed as the bot's order with the given order
+    /// ID, which can then be retrieved by [`Bot::orders`] and canceled by [`Bot::cancel`].
+    pub fn adopt_order(
+        &mut self,
+        asset_no: usize,
+        exch_order_id: &str,
+        order_id: OrderId,
+    ) -> Result<(), BotError> {
+        let instrument = self
+            .instruments
+            .get_mut(asset_no)
+            .ok_or(BotError::InstrumentNotFound)?;
+        if instrument.orders.contains_key(&order_id) {
+            return Err(BotError::OrderIdExist);
+        }
+        let mut order = instrument
+            .external_orders
+            .remove(exch_order_id)
+            .ok_or(BotError::OrderNotFound)?;
+        order.order_id = order_id;
+        order.req = Status::None;
+        instrument.orders.insert(order_id, order.clone());
+        let symbol = instrument.symbol.clone();
+        info!(%symbol, %exch_order_id, %order_id, "Adopts the external order.");
+        self.channel.send(
+            self.id,
+            asset_no,
+            LiveRequest::AdoptOrder {
+                symbol,
+                exch_order_id: exch_order_id.to_string(),
+                order,
+            },
+        )
+    }
+
+    /// Requests to cancel the external order. It's removed from
+    /// [`external_orders`](Self::external_orders) once the connector reports it's canceled.
+    pub fn cancel_external(
+        &mut self,
+        asset_no: usize,
+        exch_order_id: &str,
+    ) -> Result<(), BotError> {
+        let instrument = self
+            .instruments
+            .get(asset_no)
+            .ok_or(BotError::InstrumentNotFound)?;
+        if !instrument.external_orders.contains_key(exch_order_id) {
+            return Err(BotError::OrderNotFound);
+        }
+        let symbol = instrument.symbol.clone();
+        self.channel.send(
+            self.id,
+            asset_no,
+            LiveRequest::CancelExternal {
+                symbol,
+                exch_order_id: exch_order_id.to_string(),
+            },
+        )
+    }
+
+    /// Returns the value saved by [`save_value`](Self::save_value), which is restored from the
+    /// state store when the bot is restarted.
+    pub fn saved_value(&self, name: &str) -> Option<f64> {
+        self.values.get(name).copied()
+    }
+
+    /// Saves the strategy value to be persisted by the state store set by
+    /// [`LiveBotBuilder::state_store`].
+    pub fn save_value(&mut self, name: &str, value: f64) {
+        self.values.insert(name.to_string(), value);
+        self.dirty = true;
+    }
+
+    /// Saves the state to the state store if it has changed since the last save.
+    fn persist(&mut self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        if !self.dirty {
+            return;
+        }
+        let state = SavedState {
+            instruments: self
+                .instruments
+                .iter()
+                .map(|instrument| {
+                    (
+                        instrument.symbol.clone(),
+                        SavedInstrument {
+                            position: instrument.state.position,
+                            orders: instrument
+                                .orders
+                                .values()
+                                .filter(|order| order.active())
+                                .cloned()
+                                .collect(),
+                        },
+                    )
+                })
+                .collect(),
+            values: self.values.clone(),
+        };
+        store.save(state);
+        self.dirty = false;
+    }
+
+    /// Expires the restored orders that the connectors haven't reported by the deadline, since
+    /// they're no longer open on the exchange.
+    fn expire_unreconciled(&mut self) {
+        if self.unreconciled.is_empty()
+            || Utc::now().timestamp_nanos_opt().unwrap() < self.reconcile_deadline
+        {
+            return;
+        }
+        for (inst_no, order_id) in self.unreconciled.drain() {
+            let instrument = unsafe { self.instruments.get_unchecked_mut(inst_no) };
+            if let Some(order) = instrument.orders.get_mut(&order_id) {
+                warn!(
+                    symbol = instrument.symbol,
+                    %order_id,
+                    "The restored order isn't reported by the connector and is considered expired."
+                );
+                order.status = Status::Expired;
+                order.req = Status::None;
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Adopts the external order if it matches a restored order that isn't reconciled yet,
+    /// returning `true` if it's adopted.
+    fn reconcile_external(
+        &mut self,
+        inst_no: usize,
+        exch_order_id: &str,
+        order: &Order,
+    ) -> Result<bool, BotError> {
+        let instrument = unsafe { self.instruments.get_unchecked_mut(inst_no) };
+        let Some(order_id) = self.unreconciled.iter().find_map(|&(no, order_id)| {
+            let saved = instrument.orders.get(&order_id)?;
+            (no == inst_no
+                && saved.side == order.side
+                && saved.price_tick == order.price_tick
+                && saved.qty == order.qty)
+                .then_some(order_id)
+        }) else {
+            return Ok(false);
+        };
+        self.unreconciled.remove(&(inst_no, order_id));
+        let mut order = order.clone();
+        order.order_id = order_id;
+        order.req = Status::None;
+        instrument.orders.insert(order_id, order.clone());
+        self.dirty = true;
+        let symbol = instrument.symbol.clone();
+        info!(%symbol, %exch_order_id, %order_id, "Adopts the external order as the restored order.");
+        self.channel.send(
+            self.id,
+            inst_no,
+            LiveRequest::AdoptOrder {
+                symbol,
+                exch_order_id: exch_order_id.to_string(),
+                order,
+            },
+        )?;
+        Ok(true)
+    }
+
+    /// Shuts down the bot gracefully. The working orders are canceled unless disabled by
+    /// [`LiveBotBuilder::cancel_on_close`], and their cancellations are awaited until
+    /// [`LiveBotBuilder::close_timeout`]. Then, the journal and the state store are flushed, and the
+    /// instruments are deregistered from the connectors. [`Bot::close`] calls this.
+    ///
+    /// Returns the result for each asset. Calling this again after the bot is shut down returns
+    /// an empty result.
+    pub fn shutdown(&mut self) -> Result<Vec<ShutdownResult>, BotError> {
+        if self.closed {
+            return Ok(Vec::new());
+        }
+        info!("Shuts down the bot.");
+
+        let mut canceled = vec![0; self.instruments.len()];
+        if self.cancel_on_close {
+            let orders: Vec<_> = self
+                .instruments
+                .iter()
+                .enumerate()
+                .flat_map(|(asset_no, instrument)| {
+                    instrument
+                        .orders
+                        .values()
+                        .filter(|order| order.cancellable())
+                        .map(move |order| (asset_no, order.order_id))
+                })
+                .collect();
+            for (asset_no, order_id) in orders {
+                match self.cancel(asset_no, order_id, false) {
+                    Ok(_) => canceled[asset_no] += 1,
+                    Err(error) => {
+                        error!(?error, %asset_no, %order_id, "Couldn't cancel the order.");
+                    }
+                }
+            }
+
+            let instant = Instant::now();
+            let timeout = Duration::from_nanos(self.close_timeout as u64);
+            while self
+                .instruments
+                .iter()
+                .any(|instrument| instrument.orders.values().any(|order| order.active()))
+            {
+                let Some(remaining) = timeout.checked_sub(instant.elapsed()) else {
+                    break;
+                };
+                let duration = remaining.min(Duration::from_millis(100));
+                if !self.elapse_::<false>(duration.as_nanos() as i64, WaitOrderResponse::Any)? {
+                    break;
+                }
+            }
+        }
+
+        if let Some(journal) = self.journal.as_mut() {
+            journal.flush();
+        }
+        self.dirty = true;
+        self.persist();
+        if let Some(store) = &self.store {
+            store.flush();
+        }
+
+        let mut results = Vec::with_capacity(self.instruments.len());
+        for (asset_no, instrument) in self.instruments.iter().enumerate() {
+            let result = ShutdownResult {
+                asset_no,
+                symbol: instrument.symbol.clone(),
+                canceled: canceled[asset_no],
+                remaining: instrument
+                    .orders
+                    .values()
+                    .filter(|order| order.active())
+                    .map(|order| order.order_id)
+                    .collect(),
+                position: instrument.state.position,
+            };
+            if result.is_clean() {
+                info!(
+                    symbol = result.symbol,
+                    position = result.position,
+                    "Shut down the asset."
+                );
+            } else {
+                warn!(
+                    symbol = result.symbol,
+                    position = result.position,
+                    remaining = ?result.remaining,
+                    "Shut down the asset with the working orders."
+                );
+            }
+            results.push(result);
+            self.channel.send(
+                self.id,
+                asset_no,
+                LiveRequest::DeregisterInstrument {
+                    symbol: instrument.symbol.clone(),
+                },
+            )?;
+        }
+        self.closed = true;
+        Ok(results)
+    }
+
+    /// Shuts down the bot and returns [`BotError::Interrupted`] if a termination signal has been
+    /// received while the signals are handled.
+    fn check_signal(&mut self) -> Result<(), BotError> {
+        if !self.handle_signals {
+            return Ok(());
+        }
+        let Some(signum) = signal::received() else {
+            return Ok(());
+        };
+        warn!(%signum, "The termination signal is received.");
+        // Prevents the shutdown, which elapses while waiting for the cancellations, from being
+        // interrupted again.
+        self.handle_signals = false;
+        self.shutdown()?;
+        Err(BotError::Interrupted)
+    }
+
+    /// Returns the latest margin state of the account for the currency, as reported by the
+    /// connectors that support it.
+    pub fn margin(&self, currency: &str) -> Option<&MarginState> {
+        self.margins.get(currency)
+    }
+
+    fn handle_error(&mut self, error: LiveError) -> Result<(), BotError> {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.error(&error);
+        }
+        if let Some(handler) = self.error_handler.as_mut() {
+            handler(error)?;
+        }
+        Ok(())
+    }
+
     fn process_event<const WAIT_NEXT_FEED: bool>(
         &mut self,
         inst_no: usize,
@@ -211,36 +1225,87 @@ where
             LiveEvent::Feed { event, .. } => {
                 let instrument = unsafe { self.instruments.get_unchecked_mut(inst_no) };
                 instrument.last_feed_latency = Some((event.exch_ts, event.local_ts));
-                if event.is(LOCAL_BID_DEPTH_EVENT) {
-                    instrument
-                        .depth
-                        .update_bid_depth(event.px, event.qty, event.exch_ts);
+                instrument.last_feed_time = Utc::now().timestamp_nanos_opt().unwrap();
+                if instrument.stale {
+                    info!(
+                        symbol = instrument.symbol,
+                        "The market data is fresh again."
+                    );
+                    instrument.stale = false;
+                }
+                if event.is(LOCAL_BID_DEPTH_CLEAR_EVENT) {
+                    instrument.event_stats.depth_updates += 1;
+                    instrument.depth.clear_depth(Side::Buy, event.px);
+                } else if event.is(LOCAL_ASK_DEPTH_CLEAR_EVENT) {
+                    instrument.event_stats.depth_updates += 1;
+                    instrument.depth.clear_depth(Side::Sell, event.px);
+                } else if event.is(LOCAL_DEPTH_CLEAR_EVENT) {
+                    instrument.event_stats.depth_updates += 1;
+                    instrument.depth.clear_depth(Side::None, 0.0);
+                } else if event.is(LOCAL_BID_DEPTH_EVENT) {
+                    instrument.event_stats.depth_updates += 1;
+                    let update =
+                        instrument
+                            .depth
+                            .update_bid_depth(event.px, event.qty, event.exch_ts);
+                    DepthUpdate::record(&mut instrument.last_depth_updates, Side::Buy, update);
                 } else if event.is(LOCAL_ASK_DEPTH_EVENT) {
-                    instrument
-                        .depth
-                        .update_ask_depth(event.px, event.qty, event.exch_ts);
-                } else if (event.is(LOCAL_BUY_TRADE_EVENT) || event.is(LOCAL_SELL_TRADE_EVENT))
-                    && instrument.last_trades.capacity() > 0
-                {
+                    instrument.event_stats.depth_updates += 1;
+                    let update =
+                        instrument
+                            .depth
+                            .update_ask_depth(event.px, event.qty, event.exch_ts);
+                    DepthUpdate::record(&mut instrument.last_depth_updates, Side::Sell, update);
+                } else if event.is(LOCAL_BID_DEPTH_SNAPSHOT_EVENT) {
+                    instrument.event_stats.depth_updates += 1;
+                    let update =
+                        instrument
+                            .depth
+                            .update_bid_snapshot(event.px, event.qty, event.exch_ts);
+                    DepthUpdate::record(&mut instrument.last_depth_updates, Side::Buy, update);
+                } else if event.is(LOCAL_ASK_DEPTH_SNAPSHOT_EVENT) {
+                    instrument.event_stats.depth_updates += 1;
+                    let update =
+                        instrument
+                            .depth
+                            .update_ask_snapshot(event.px, event.qty, event.exch_ts);
+                    DepthUpdate::record(&mut instrument.last_depth_updates, Side::Sell, update);
+                } else if event.is(LOCAL_BUY_TRADE_EVENT) || event.is(LOCAL_SELL_TRADE_EVENT) {
+                    instrument.event_stats.trades += 1;
+                    for builder in instrument.bars.iter_mut() {
+                        builder.update(&event);
+                    }
                     instrument.last_trades.push(event);
+                } else if event.is(LOCAL_LIQUIDATION_EVENT) {
+                    if instrument.last_liquidation_prints.capacity() > 0 {
+                        instrument.last_liquidation_prints.push(event);
+                    }
+                } else if event.is(LOCAL_OPEN_INTEREST_EVENT) {
+                    instrument.open_interest = Some(event.qty);
+                } else {
+                    instrument.event_stats.dropped += 1;
                 }
             }
             LiveEvent::Order { order, .. } => {
                 debug!(%inst_no, ?order, "Event::Order");
-                let received_order_resp = match wait_order_response {
-                    WaitOrderResponse::Any => true,
-                    WaitOrderResponse::Specified {
-                        asset_no: wait_order_asset_no,
-                        order_id: wait_order_id,
-                    } if wait_order_id == order.order_id && wait_order_asset_no == inst_no => true,
-                    _ => false,
-                };
+                let order_id = order.order_id;
                 let instrument = unsafe { self.instruments.get_unchecked_mut(inst_no) };
                 instrument.last_order_latency = Some((
                     order.local_timestamp,
                     order.exch_timestamp,
                     Utc::now().timestamp_nanos_opt().unwrap(),
                 ));
+                if order.exec_qty > 0.0
+                    && (order.status == Status::Filled || order.status == Status::PartiallyFilled)
+                {
+                    instrument.num_fills += 1;
+                }
+                instrument.event_stats.order_updates += 1;
+                self.unreconciled.remove(&(inst_no, order.order_id));
+                self.dirty = true;
+                if let Some(journal) = self.journal.as_mut() {
+                    journal.order_response(inst_no, &instrument.symbol, &order);
+                }
                 match instrument.orders.entry(order.order_id) {
                     Entry::Occupied(mut entry) => {
                         let ex_order = entry.get_mut();
@@ -248,32 +1313,122 @@ where
                             hook(ex_order, &order)?;
                         }
                         if order.exch_timestamp >= ex_order.exch_timestamp {
-                            if ex_order.status == Status::Canceled
-                                || ex_order.status == Status::Expired
-                                || ex_order.status == Status::Filled
-                            {
-                                // Ignores the update since the current status is the final status.
+                            if !ex_order.status.can_transition_to(order.status) {
+                                // Ignores the update since the order can't be in the status after
+                                // the current status, such as after the final status.
+                                instrument.event_stats.dropped += 1;
                             } else {
                                 ex_order.update(&order);
                             }
+                        } else {
+                            // Ignores the out-of-order update.
+                            instrument.event_stats.dropped += 1;
                         }
                     }
                     Entry::Vacant(entry) => {
                         entry.insert(order);
                     }
                 }
-                if received_order_resp {
+                if instrument
+                    .orders
+                    .get(&order_id)
+                    .is_some_and(|order| wait_order_response.is_met(inst_no, order))
+                {
                     return Ok(true);
                 }
             }
-            LiveEvent::Position { qty, .. } => {
-                unsafe { self.instruments.get_unchecked_mut(inst_no) }
-                    .state
-                    .position = qty;
+            LiveEvent::Position { side, qty, .. } => {
+                let state = &mut unsafe { self.instruments.get_unchecked_mut(inst_no) }.state;
+                match side {
+                    PositionSide::Long => {
+                        state.long_position = qty;
+                        state.position = state.long_position - state.short_position;
+                    }
+                    PositionSide::Short => {
+                        state.short_position = qty.abs();
+                        state.position = state.long_position - state.short_position;
+                    }
+                    PositionSide::Both | PositionSide::Unsupported => {
+                        state.position = qty;
+                    }
+                }
+                self.dirty = true;
+            }
+            LiveEvent::Liquidation { liquidation, .. } => {
+                warn!(%inst_no, ?liquidation, "The position is forcibly liquidated.");
+                let instrument = unsafe { self.instruments.get_unchecked_mut(inst_no) };
+                if instrument.liquidations_capacity > 0 {
+                    if instrument.liquidations.len() >= instrument.liquidations_capacity {
+                        instrument.liquidations.remove(0);
+                    }
+                    instrument.liquidations.push(liquidation);
+                }
+            }
+            LiveEvent::Fill { fill, .. } => {
+                debug!(%inst_no, ?fill, "Event::Fill");
+                let instrument = unsafe { self.instruments.get_unchecked_mut(inst_no) };
+                instrument.state.fee += fill.fee;
+                if instrument.last_fills.capacity() > 0 {
+                    instrument.last_fills.push(fill);
+                }
+                self.dirty = true;
+            }
+            LiveEvent::MarkPrice { price, .. } => {
+                unsafe { self.instruments.get_unchecked_mut(inst_no) }.mark_price = Some(price);
+            }
+            LiveEvent::IndexPrice { price, .. } => {
+                unsafe { self.instruments.get_unchecked_mut(inst_no) }.index_price = Some(price);
+            }
+            LiveEvent::FundingRate { funding, .. } => {
+                unsafe { self.instruments.get_unchecked_mut(inst_no) }.funding_rate = Some(funding);
             }
             LiveEvent::Error(error) => {
-                if let Some(handler) = self.error_handler.as_mut() {
-                    handler(error)?;
+                self.handle_error(error)?;
+            }
+            LiveEvent::Heartbeat { .. } => {
+                let timestamp = Utc::now().timestamp_nanos_opt().unwrap();
+                let inst_nos = self
+                    .supervisor
+                    .as_mut()
+                    .and_then(|supervisor| supervisor.on_heartbeat(inst_no, timestamp));
+                // The connector may have been relaunched and lost its state, so registers the
+                // instruments again and rebuilds their market depth.
+                for inst_no in inst_nos.unwrap_or_default() {
+                    let instrument = unsafe { self.instruments.get_unchecked(inst_no) };
+                    let request = LiveRequest::RegisterInstrument {
+                        symbol: instrument.symbol.clone(),
+                        tick_size: instrument.tick_size,
+                        lot_size: instrument.lot_size,
+                        depth: instrument.depth_level,
+                    };
+                    self.channel.send(self.id, inst_no, request)?;
+                    self.resync(inst_no)?;
+                }
+            }
+            LiveEvent::Margin(margin) => {
+                self.margins.insert(margin.currency.clone(), margin);
+            }
+            LiveEvent::ExternalOrder {
+                exch_order_id,
+                order,
+                ..
+            } => {
+                if !order.status.is_final()
+                    && self.reconcile_external(inst_no, &exch_order_id, &order)?
+                {
+                    return Ok(false);
+                }
+                let instrument = unsafe { self.instruments.get_unchecked_mut(inst_no) };
+                if order.status.is_final() {
+                    instrument.external_orders.remove(&exch_order_id);
+                } else {
+                    instrument.external_orders.insert(exch_order_id, order);
+                }
+            }
+            LiveEvent::InstrumentInfo { info, .. } => {
+                let instrument = unsafe { self.instruments.get_unchecked_mut(inst_no) };
+                if info.is_some() {
+                    instrument.info = info;
                 }
             }
             LiveEvent::BatchStart | LiveEvent::BatchEnd => {
@@ -288,14 +1443,31 @@ where
         duration: i64,
         wait_order_response: WaitOrderResponse,
     ) -> Result<bool, BotError> {
+        self.process_control()?;
+        self.supervise()?;
+        self.check_staleness()?;
+        self.enforce_position_limits()?;
+        self.expire_unreconciled();
+        self.persist();
         let instant = Instant::now();
         let duration = Duration::from_nanos(duration as u64);
         let mut remaining_duration = duration;
         let mut batch_mode = false;
         let mut wait_resp_received = false;
+        let mut last_event = instant;
 
         loop {
-            match self.channel.recv_timeout(self.id, remaining_duration) {
+            self.check_signal()?;
+            // Polling receives an event without waiting, and then idles until the duration
+            // elapses.
+            let timeout = self
+                .wait_strategy
+                .recv_timeout(last_event.elapsed(), remaining_duration);
+            let received = self.channel.recv_timeout(self.id, timeout);
+            if received.is_ok() {
+                last_event = Instant::now();
+            }
+            match received {
                 Ok((_, LiveEvent::BatchStart)) => {
                     batch_mode = true;
                 }
@@ -314,9 +1486,14 @@ where
                     }
                 }
                 Err(BotError::Timeout) => {
-                    return Ok(true);
+                    let elapsed = instant.elapsed();
+                    if !timeout.is_zero() || elapsed >= duration {
+                        return Ok(true);
+                    }
+                    self.wait_strategy.idle(duration - elapsed);
                 }
                 Err(BotError::Interrupted) => {
+                    self.check_signal()?;
                     return Ok(false);
                 }
                 Err(error) => {
@@ -342,9 +1519,14 @@ where
         qty: f64,
         time_in_force: TimeInForce,
         order_type: OrdType,
+        reduce_only: bool,
+        position_side: PositionSide,
         wait: bool,
         side: Side,
     ) -> Result<bool, BotError> {
+        if self.halted {
+            return Err(BotError::Custom("Halted".to_string()));
+        }
         let instrument = self
             .instruments
             .get_mut(asset_no)
@@ -352,6 +1534,62 @@ where
         if instrument.orders.contains_key(&order_id) {
             return Err(BotError::OrderIdExist);
         }
+        let mid = instrument.depth.mid();
+        let open_orders = instrument
+            .orders
+            .values()
+            .filter(|order| order.active())
+            .count();
+        if let Some(filter) = &instrument.order_filter {
+            filter.check(side, price, qty, mid, open_orders)?;
+        }
+        let (open_buy_qty, open_sell_qty) = open_qty(instrument.orders.values());
+        self.risk.check(&NewOrder {
+            timestamp: Utc::now().timestamp_nanos_opt().unwrap(),
+            asset_no,
+            side,
+            price,
+            qty,
+            position: instrument.state.position,
+            mid,
+            open_orders,
+            open_buy_qty,
+            open_sell_qty,
+        })?;
+        self.send_new_order(
+            asset_no,
+            order_id,
+            price,
+            qty,
+            time_in_force,
+            order_type,
+            reduce_only,
+            position_side,
+            side,
+        )?;
+
+        if wait {
+            // fixme: timeout should be specified by the argument.
+            return self.wait_order_response(asset_no, order_id, 60_000_000_000);
+        }
+        Ok(true)
+    }
+
+    /// Sends the new order to the connector without any checks.
+    #[allow(clippy::too_many_arguments)]
+    fn send_new_order(
+        &mut self,
+        asset_no: usize,
+        order_id: OrderId,
+        price: f64,
+        qty: f64,
+        time_in_force: TimeInForce,
+        order_type: OrdType,
+        reduce_only: bool,
+        position_side: PositionSide,
+        side: Side,
+    ) -> Result<(), BotError> {
+        let instrument = unsafe { self.instruments.get_unchecked_mut(asset_no) };
         let symbol = instrument.symbol.clone();
         let tick_size = instrument.tick_size;
         let order = Order {
@@ -370,34 +1608,99 @@ where
             exch_timestamp: 0,
             exec_qty: 0.0,
             // Invalid information
-            q: Box::new(()),
+            q: QueueState::None,
             maker: false,
+            reduce_only,
+            position_side,
         };
-        let order_id = order.order_id;
         instrument.orders.insert(order_id, order.clone());
+        instrument.num_messages += 1;
+        if let Some(journal) = self.journal.as_mut() {
+            journal.order_request(asset_no, &symbol, &order);
+        }
 
         self.channel
             .send(self.id, asset_no, LiveRequest::Order { symbol, order })?;
+        self.dirty = true;
+        self.persist();
+        if let Some(store) = &self.store {
+            store.flush();
+        }
+        Ok(())
+    }
 
-        if wait {
-            // fixme: timeout should be specified by the argument.
-            return self.wait_order_response(asset_no, order_id, 60_000_000_000);
+    /// Sets the [`PositionLimit`] of the asset, or removes it if `None`, which can be changed
+    /// while trading.
+    pub fn set_position_limit(&mut self, asset_no: usize, limit: Option<PositionLimit>) {
+        self.risk.set_position_limit(asset_no, limit);
+    }
+
+    /// Returns `true` if the asset is in the reduce-only mode since its [`PositionLimit`] is
+    /// breached.
+    pub fn is_reduce_only(&self, asset_no: usize) -> bool {
+        self.risk.is_reduce_only(asset_no)
+    }
+
+    fn enforce_position_limits(&mut self) -> Result<(), BotError> {
+        for asset_no in 0..self.instruments.len() {
+            let instrument = unsafe { self.instruments.get_unchecked(asset_no) };
+            let position = instrument.state.position;
+            let was_reduce_only = self.risk.is_reduce_only(asset_no);
+            let flatten = self.risk.update_position(asset_no, position, |order_id| {
+                instrument
+                    .orders
+                    .get(&order_id)
+                    .is_some_and(|order| order.active() || order.pending())
+            });
+            if !was_reduce_only && self.risk.is_reduce_only(asset_no) {
+                warn!(
+                    symbol = instrument.symbol,
+                    %position,
+                    "The position limit is breached, so only reducing orders are allowed."
+                );
+            }
+            if !flatten || self.halted {
+                continue;
+            }
+            let Some((side, price, qty)) = flatten_order(position, &instrument.depth) else {
+                continue;
+            };
+            info!(symbol = instrument.symbol, ?side, %price, %qty, "Flattens the position.");
+            let order_id = self.order_ids.next_order_id();
+            self.send_new_order(
+                asset_no,
+                order_id,
+                price,
+                qty,
+                TimeInForce::IOC,
+                OrdType::Limit,
+                true,
+                PositionSide::Both,
+                side,
+            )?;
+            self.risk.set_flatten_order(asset_no, order_id);
         }
-        Ok(true)
+        Ok(())
     }
 }
 
-impl<CH, MD> Bot<MD> for LiveBot<CH, MD>
+impl<CH, MD> Clock for LiveBot<CH, MD>
 where
     CH: Channel,
     MD: MarketDepth + L2MarketDepth,
 {
-    type Error = BotError;
-
     #[inline]
-    fn current_timestamp(&self) -> i64 {
-        Utc::now().timestamp_nanos_opt().unwrap()
+    fn now(&self) -> i64 {
+        SystemClock.now()
     }
+}
+
+impl<CH, MD> Bot<MD> for LiveBot<CH, MD>
+where
+    CH: Channel,
+    MD: MarketDepth + L2MarketDepth,
+{
+    type Error = BotError;
 
     #[inline]
     fn num_assets(&self) -> usize {
@@ -416,6 +1719,29 @@ where
         &self.instruments.get(asset_no).unwrap().state
     }
 
+    fn portfolio(&self) -> Portfolio {
+        let mut portfolio = Portfolio::default();
+        for instrument in &self.instruments {
+            let state = &instrument.state;
+            let position_value = if state.position == 0.0 {
+                0.0
+            } else if instrument.depth.best_bid_tick() == INVALID_MIN
+                || instrument.depth.best_ask_tick() == INVALID_MAX
+            {
+                f64::NAN
+            } else {
+                state.position * (instrument.depth.best_bid() + instrument.depth.best_ask()) / 2.0
+            };
+            portfolio += Portfolio {
+                equity: state.balance + position_value - state.fee,
+                gross_exposure: position_value.abs(),
+                net_exposure: position_value,
+                fee: state.fee,
+            };
+        }
+        portfolio
+    }
+
     #[inline]
     fn depth(&self, asset_no: usize) -> &MD {
         &self.instruments.get(asset_no).unwrap().depth
@@ -451,11 +1777,120 @@ where
         }
     }
 
+    #[inline]
+    fn last_depth_updates(&self, asset_no: usize) -> &[DepthUpdate] {
+        self.instruments
+            .get(asset_no)
+            .unwrap()
+            .last_depth_updates
+            .as_slice()
+    }
+
+    #[inline]
+    fn bars(&self, asset_no: usize, interval: i64) -> &[Bar] {
+        self.instruments
+            .get(asset_no)
+            .unwrap()
+            .bars
+            .iter()
+            .find(|builder| builder.interval() == interval)
+            .map(|builder| builder.bars())
+            .unwrap_or(&[])
+    }
+
+    fn clear_last_depth_updates(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(asset_no) => {
+                self.instruments
+                    .get_mut(asset_no)
+                    .unwrap()
+                    .last_depth_updates
+                    .clear();
+            }
+            None => {
+                for instrument in self.instruments.iter_mut() {
+                    instrument.last_depth_updates.clear();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn liquidations(&self, asset_no: usize) -> &[Liquidation] {
+        self.instruments
+            .get(asset_no)
+            .unwrap()
+            .liquidations
+            .as_slice()
+    }
+
+    #[inline]
+    fn last_fills(&self, asset_no: usize) -> &[Fill] {
+        self.instruments
+            .get(asset_no)
+            .unwrap()
+            .last_fills
+            .as_slice()
+    }
+
+    fn clear_last_fills(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(asset_no) => {
+                self.instruments
+                    .get_mut(asset_no)
+                    .unwrap()
+                    .last_fills
+                    .clear();
+            }
+            None => {
+                for instrument in self.instruments.iter_mut() {
+                    instrument.last_fills.clear();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn last_liquidation_prints(&self, asset_no: usize) -> &[Event] {
+        self.instruments
+            .get(asset_no)
+            .unwrap()
+            .last_liquidation_prints
+            .as_slice()
+    }
+
+    fn clear_last_liquidation_prints(&mut self, asset_no: Option<usize>) {
+        match asset_no {
+            Some(asset_no) => {
+                self.instruments
+                    .get_mut(asset_no)
+                    .unwrap()
+                    .last_liquidation_prints
+                    .clear();
+            }
+            None => {
+                for instrument in self.instruments.iter_mut() {
+                    instrument.last_liquidation_prints.clear();
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn open_interest(&self, asset_no: usize) -> Option<f64> {
+        self.instruments.get(asset_no).unwrap().open_interest
+    }
+
     #[inline]
     fn orders(&self, asset_no: usize) -> &HashMap<OrderId, Order> {
         &self.instruments.get(asset_no).unwrap().orders
     }
 
+    #[inline]
+    fn next_order_id(&mut self) -> OrderId {
+        self.order_ids.next_order_id()
+    }
+
     #[inline]
     fn submit_buy_order(
         &mut self,
@@ -474,6 +1909,8 @@ where
             qty,
             time_in_force,
             order_type,
+            false,
+            PositionSide::Both,
             wait,
             Side::Buy,
         )
@@ -497,6 +1934,8 @@ where
             qty,
             time_in_force,
             order_type,
+            false,
+            PositionSide::Both,
             wait,
             Side::Sell,
         )
@@ -515,6 +1954,8 @@ where
             order.qty,
             order.time_in_force,
             order.order_type,
+            order.reduce_only,
+            order.position_side,
             wait,
             order.side,
         )
@@ -541,6 +1982,10 @@ where
         }
         order.req = Status::Canceled;
         order.local_timestamp = Utc::now().timestamp_nanos_opt().unwrap();
+        instrument.num_messages += 1;
+        if let Some(journal) = self.journal.as_mut() {
+            journal.order_request(asset_no, &symbol, order);
+        }
 
         self.channel.send(
             self.id,
@@ -584,6 +2029,26 @@ where
         self.elapse_::<false>(timeout, WaitOrderResponse::Specified { asset_no, order_id })
     }
 
+    fn wait_order_status(
+        &mut self,
+        asset_no: usize,
+        order_id: OrderId,
+        status: Status,
+        timeout: i64,
+    ) -> Result<bool, Self::Error> {
+        let wait = WaitOrderResponse::UntilStatus {
+            asset_no,
+            order_id,
+            status,
+        };
+        if let Some(order) = self.orders(asset_no).get(&order_id) {
+            if wait.is_met(asset_no, order) {
+                return Ok(true);
+            }
+        }
+        self.elapse_::<false>(timeout, wait)
+    }
+
     #[inline]
     fn wait_next_feed(
         &mut self,
@@ -597,6 +2062,22 @@ where
         }
     }
 
+    fn wait_any(
+        &mut self,
+        conditions: &[WaitCondition],
+    ) -> Result<Option<WaitCondition>, Self::Error> {
+        let wait = WaitAny::new(self, conditions);
+        loop {
+            let remaining = wait.deadline().saturating_sub(self.current_timestamp());
+            if remaining > 0 && !self.elapse_::<true>(remaining, WaitOrderResponse::Any)? {
+                return Ok(None);
+            }
+            if let Some(condition) = wait.check(self) {
+                return Ok(Some(condition));
+            }
+        }
+    }
+
     #[inline]
     fn elapse(&mut self, duration: i64) -> Result<bool, Self::Error> {
         self.elapse_::<false>(duration, WaitOrderResponse::None)
@@ -607,7 +2088,13 @@ where
         Ok(true)
     }
 
+    #[inline]
+    fn risk_stats(&self, asset_no: usize) -> RiskStats {
+        self.risk.stats(asset_no)
+    }
+
     fn close(&mut self) -> Result<(), Self::Error> {
+        self.shutdown()?;
         Ok(())
     }
 
@@ -618,4 +2105,37 @@ where
     fn order_latency(&self, asset_no: usize) -> Option<(i64, i64, i64)> {
         self.instruments.get(asset_no).unwrap().last_order_latency
     }
+
+    #[inline]
+    fn event_stats(&mut self, asset_no: usize) -> EventStats {
+        mem::take(&mut self.instruments.get_mut(asset_no).unwrap().event_stats)
+    }
+
+    #[inline]
+    fn is_warming_up(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::WaitStrategy;
+
+    #[test]
+    fn test_wait_strategy_timeout() {
+        let ms = Duration::from_millis;
+        assert_eq!(WaitStrategy::Block.recv_timeout(ms(5), ms(10)), ms(10));
+        assert_eq!(WaitStrategy::BusySpin.recv_timeout(ms(5), ms(10)), ms(0));
+        assert_eq!(
+            WaitStrategy::Sleep(ms(1)).recv_timeout(ms(5), ms(10)),
+            ms(0)
+        );
+
+        // Spins right after an event, and parks once idle for the spin period.
+        let strategy = WaitStrategy::SpinThenPark(ms(2));
+        assert_eq!(strategy.recv_timeout(ms(1), ms(10)), ms(0));
+        assert_eq!(strategy.recv_timeout(ms(2), ms(10)), ms(10));
+    }
 }
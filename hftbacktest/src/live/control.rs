@@ -0,0 +1,259 @@
+use std::{
+    collections::HashMap,
+    io,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        Condvar,
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{error, info};
+
+/// A command sent to the bot through the control server. Each request is a JSON object on a
+/// single line, tagged by `command`, such as `{"command": "set_param", "name": "skew", "value":
+/// 0.5}`, and is answered with a JSON object on a single line.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Returns the current positions, open orders and PnL of the bot. The status is built by the
+    /// bot the next time it elapses, so the request waits until then. If the bot doesn't elapse
+    /// within a second, the last published status is returned.
+    Status,
+    /// Halts the bot. While halted, new orders are rejected.
+    Halt,
+    /// Resumes the halted bot.
+    Resume,
+    /// Cancels all open orders.
+    CancelAll,
+    /// Sets the parameter, which can be read by the strategy through
+    /// [`LiveBot::control_param`](crate::live::LiveBot::control_param).
+    SetParam { name: String, value: f64 },
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct OrderStatus {
+    pub order_id: u64,
+    pub side: String,
+    pub price: f64,
+    pub qty: f64,
+    pub leaves_qty: f64,
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct InstrumentStatus {
+    pub symbol: String,
+    pub position: f64,
+    pub balance: f64,
+    pub fee: f64,
+    pub mid_price: Option<f64>,
+    pub equity: Option<f64>,
+    pub open_orders: Vec<OrderStatus>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct ControlStatus {
+    pub timestamp: i64,
+    pub halted: bool,
+    pub params: HashMap<String, f64>,
+    pub instruments: Vec<InstrumentStatus>,
+}
+
+/// How long a `status` request waits for the bot to publish its status.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct Shared {
+    status: ControlStatus,
+    status_requested: bool,
+    status_seq: u64,
+    commands: Vec<ControlCommand>,
+}
+
+/// An embedded JSON-over-TCP server through which an operator can supervise a running bot. The
+/// bot takes the received commands each time it elapses, and publishes its status only when a
+/// `status` request is pending, since building it walks every instrument and open order.
+pub(crate) struct ControlServer {
+    shared: Arc<(Mutex<Shared>, Condvar)>,
+    stop: Arc<AtomicBool>,
+    local_addr: SocketAddr,
+}
+
+impl ControlServer {
+    /// Binds the control server to the address and starts accepting connections on a background
+    /// thread.
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let shared = Arc::new((Mutex::new(Shared::default()), Condvar::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        info!(%local_addr, "The control server is listening.");
+
+        let shared_ = shared.clone();
+        let stop_ = stop.clone();
+        thread::spawn(move || {
+            while !stop_.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, peer_addr)) => {
+                        info!(%peer_addr, "The control client is connected.");
+                        let shared = shared_.clone();
+                        thread::spawn(move || {
+                            if let Err(error) = handle_client(stream, shared) {
+                                error!(?error, %peer_addr, "The control client failed.");
+                            }
+                        });
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(50));
+                    }
+                    Err(error) => {
+                        error!(?error, "The control server failed to accept a connection.");
+                        thread::sleep(Duration::from_millis(50));
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            shared,
+            stop,
+            local_addr,
+        })
+    }
+
+    /// Returns the address to which the control server is bound.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns `true` if a `status` request is waiting for the bot to publish its status.
+    pub fn status_requested(&self) -> bool {
+        self.shared.0.lock().unwrap().status_requested
+    }
+
+    /// Publishes the status of the bot, which is answered to the pending `status` requests.
+    pub fn publish(&self, status: ControlStatus) {
+        let (lock, cvar) = &*self.shared;
+        let mut shared = lock.lock().unwrap();
+        shared.status = status;
+        shared.status_requested = false;
+        shared.status_seq += 1;
+        cvar.notify_all();
+    }
+
+    /// Takes the commands received since the last call.
+    pub fn take_commands(&self) -> Vec<ControlCommand> {
+        std::mem::take(&mut self.shared.0.lock().unwrap().commands)
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn handle_client(stream: TcpStream, shared: Arc<(Mutex<Shared>, Condvar)>) -> io::Result<()> {
+    let (lock, cvar) = &*shared;
+    stream.set_nonblocking(false)?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let resp = match serde_json::from_str::<ControlCommand>(&line) {
+            Ok(ControlCommand::Status) => {
+                let mut shared = lock.lock().unwrap();
+                shared.status_requested = true;
+                let status_seq = shared.status_seq;
+                let (shared, _) = cvar
+                    .wait_timeout_while(shared, STATUS_TIMEOUT, |shared| {
+                        shared.status_seq == status_seq
+                    })
+                    .unwrap();
+                json!({ "ok": true, "status": shared.status })
+            }
+            Ok(command) => {
+                lock.lock().unwrap().commands.push(command);
+                json!({ "ok": true })
+            }
+            Err(error) => json!({ "ok": false, "error": error.to_string() }),
+        };
+        writeln!(writer, "{resp}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpStream,
+        thread,
+        time::Duration,
+    };
+
+    use super::{ControlCommand, ControlServer, ControlStatus};
+
+    #[test]
+    fn test_control_server() {
+        let server = ControlServer::bind("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request = |req: &str| {
+            writeln!(stream, "{req}").unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            serde_json::from_str::<serde_json::Value>(&line).unwrap()
+        };
+
+        // The status is published only once it's requested.
+        assert!(!server.status_requested());
+        let resp = thread::scope(|s| {
+            s.spawn(|| {
+                while !server.status_requested() {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                server.publish(ControlStatus {
+                    timestamp: 100,
+                    halted: true,
+                    ..Default::default()
+                });
+            });
+            request(r#"{"command": "status"}"#)
+        });
+        assert!(!server.status_requested());
+        assert_eq!(resp["ok"], true);
+        assert_eq!(resp["status"]["timestamp"], 100);
+        assert_eq!(resp["status"]["halted"], true);
+
+        assert_eq!(request(r#"{"command": "cancel_all"}"#)["ok"], true);
+        assert_eq!(
+            request(r#"{"command": "set_param", "name": "skew", "value": 0.5}"#)["ok"],
+            true
+        );
+        assert_eq!(request(r#"{"command": "unknown"}"#)["ok"], false);
+
+        assert_eq!(
+            server.take_commands(),
+            vec![
+                ControlCommand::CancelAll,
+                ControlCommand::SetParam {
+                    name: "skew".to_string(),
+                    value: 0.5
+                }
+            ]
+        );
+        assert!(server.take_commands().is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use bincode::{
+    config,
+    error::{DecodeError, EncodeError},
+    Decode,
+    Encode,
+};
+
+use crate::types::{DepthLevel, LiveEvent, LiveRequest};
+
+/// The version of the IPC message encoding of [`LiveEvent`](crate::types::LiveEvent) and
+/// [`LiveRequest`](crate::types::LiveRequest).
+///
+/// A new version may only append new variants and new trailing fields, so that a message from a
+/// newer version remains decodable as long as it doesn't use them. A message from an older version
+/// that lacks a trailing field is decoded by its [`Message`] implementation, which fills in the
+/// field.
+pub const PROTOCOL_VERSION: u8 = 4;
+
+/// The version in which [`LiveRequest::RegisterInstrument`] gained the `depth` field.
+const REGISTER_INSTRUMENT_DEPTH_VERSION: u8 = 3;
+
+/// Precedes the version in a versioned message. The messages encoded before the versioning start
+/// with the bincode variant index of the enum, which is never `0xff` as it's reserved in the
+/// variable-length integer encoding, so they're decoded as version `0`.
+const VERSION_MARKER: u8 = 0xff;
+
+const HEADER_LEN: usize = 2;
+
+/// Encodes the message with the version header.
+pub(crate) fn encode_to_vec<T: Encode>(data: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut buf = vec![VERSION_MARKER, PROTOCOL_VERSION];
+    bincode::encode_into_std_write(data, &mut buf, config::standard())?;
+    Ok(buf)
+}
+
+/// Encodes the message with the version header into the slice, returning the encoded length.
+pub(crate) fn encode_into_slice<T: Encode>(data: &T, dst: &mut [u8]) -> Result<usize, EncodeError> {
+    if dst.len() < HEADER_LEN {
+        return Err(EncodeError::UnexpectedEnd);
+    }
+    dst[0] = VERSION_MARKER;
+    dst[1] = PROTOCOL_VERSION;
+    let len = bincode::encode_into_slice(data, &mut dst[HEADER_LEN..], config::standard())?;
+    Ok(HEADER_LEN + len)
+}
+
+/// A message exchanged through the IPC, which can be decoded from the layout of any version.
+pub trait Message: Decode + Sized {
+    /// Decodes the payload encoded by the version. A message whose layout has changed decodes the
+    /// older layouts here.
+    fn decode_version(version: u8, payload: &[u8]) -> Result<Self, DecodeError> {
+        let _ = version;
+        Ok(bincode::decode_from_slice(payload, config::standard())?.0)
+    }
+}
+
+impl Message for LiveEvent {}
+
+impl Message for LiveRequest {
+    fn decode_version(version: u8, payload: &[u8]) -> Result<Self, DecodeError> {
+        if version < REGISTER_INSTRUMENT_DEPTH_VERSION {
+            // `RegisterInstrument`, the variant `1`, has no `depth` in the older layout, in which
+            // the full market depth was always provided.
+            if let Ok(((1u32, symbol, tick_size, lot_size), _)) =
+                bincode::decode_from_slice::<(u32, String, f64, f64), _>(
+                    payload,
+                    config::standard(),
+                )
+            {
+                return Ok(LiveRequest::RegisterInstrument {
+                    symbol,
+                    tick_size,
+                    lot_size,
+                    depth: DepthLevel::Full,
+                });
+            }
+        }
+        Ok(bincode::decode_from_slice(payload, config::standard())?.0)
+    }
+}
+
+/// Decodes the message of any version. Returns `None` if the message is from a newer version and
+/// uses what this version doesn't know, such as a new variant, so that it can be skipped.
+pub(crate) fn decode_from_slice<T: Message>(bytes: &[u8]) -> Result<Option<T>, DecodeError> {
+    let (version, payload) = match bytes {
+        [VERSION_MARKER, version, payload @ ..] => (*version, payload),
+        _ => (0, bytes),
+    };
+    match T::decode_version(version, payload) {
+        Ok(decoded) => Ok(Some(decoded)),
+        Err(_) if version > PROTOCOL_VERSION => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::config;
+
+    use super::{decode_from_slice, encode_to_vec, PROTOCOL_VERSION, VERSION_MARKER};
+    use crate::types::{DepthLevel, LiveEvent, LiveRequest};
+
+    #[test]
+    fn test_versioned_decoding() {
+        let event = LiveEvent::Heartbeat { timestamp: 10 };
+
+        let encoded = encode_to_vec(&event).unwrap();
+        assert_eq!(encoded[..2], [VERSION_MARKER, PROTOCOL_VERSION]);
+        let decoded: Option<LiveEvent> = decode_from_slice(&encoded).unwrap();
+        assert!(matches!(
+            decoded,
+            Some(LiveEvent::Heartbeat { timestamp: 10 })
+        ));
+
+        // A message encoded before the versioning.
+        let legacy = bincode::encode_to_vec(&event, config::standard()).unwrap();
+        let decoded: Option<LiveEvent> = decode_from_slice(&legacy).unwrap();
+        assert!(matches!(
+            decoded,
+            Some(LiveEvent::Heartbeat { timestamp: 10 })
+        ));
+
+        // A message with a trailing field appended by a newer version.
+        let mut newer = encoded.clone();
+        newer[1] = PROTOCOL_VERSION + 1;
+        newer.push(1);
+        let decoded: Option<LiveEvent> = decode_from_slice(&newer).unwrap();
+        assert!(matches!(
+            decoded,
+            Some(LiveEvent::Heartbeat { timestamp: 10 })
+        ));
+
+        // A message with a variant unknown to this version.
+        let unknown = [VERSION_MARKER, PROTOCOL_VERSION + 1, 200];
+        assert!(decode_from_slice::<LiveEvent>(&unknown).unwrap().is_none());
+        assert!(decode_from_slice::<LiveEvent>(&unknown[2..]).is_err());
+    }
+
+    #[test]
+    fn test_decoding_register_instrument_of_version_2() {
+        // `RegisterInstrument` as encoded by version 2, before the `depth` field.
+        let mut encoded = vec![VERSION_MARKER, 2];
+        let fields = (1u32, "BTCUSDT".to_string(), 0.1f64, 0.001f64);
+        encoded.extend(bincode::encode_to_vec(&fields, config::standard()).unwrap());
+
+        let decoded: Option<LiveRequest> = decode_from_slice(&encoded).unwrap();
+        assert!(matches!(
+            decoded,
+            Some(LiveRequest::RegisterInstrument {
+                symbol,
+                tick_size: 0.1,
+                lot_size: 0.001,
+                depth: DepthLevel::Full,
+            }) if symbol == "BTCUSDT"
+        ));
+
+        // The other requests of version 2 are unchanged.
+        let request = LiveRequest::Resync {
+            symbol: "BTCUSDT".to_string(),
+        };
+        let mut encoded = vec![VERSION_MARKER, 2];
+        encoded.extend(bincode::encode_to_vec(&request, config::standard()).unwrap());
+        let decoded: Option<LiveRequest> = decode_from_slice(&encoded).unwrap();
+        assert!(matches!(decoded, Some(LiveRequest::Resync { symbol }) if symbol == "BTCUSDT"));
+
+        // The current version encodes the depth level.
+        let request = LiveRequest::RegisterInstrument {
+            symbol: "BTCUSDT".to_string(),
+            tick_size: 0.1,
+            lot_size: 0.001,
+            depth: DepthLevel::Bbo,
+        };
+        let decoded: Option<LiveRequest> =
+            decode_from_slice(&encode_to_vec(&request).unwrap()).unwrap();
+        assert!(matches!(
+            decoded,
+            Some(LiveRequest::RegisterInstrument {
+                depth: DepthLevel::Bbo,
+                ..
+            })
+        ));
+    }
+}
@@ -2,30 +2,25 @@ use std::{
     collections::{hash_map::Entry, HashMap},
     marker::PhantomData,
     rc::Rc,
-    string::FromUtf8Error,
     time::{Duration, Instant},
 };
 
-use bincode::{
-    config,
-    error::{DecodeError, EncodeError},
-    Decode,
-    Encode,
-};
+use bincode::Encode;
 use iceoryx2::{
-    port::{
-        publisher::{Publisher, PublisherLoanError, PublisherSendError},
-        subscriber::{Subscriber, SubscriberReceiveError},
-    },
+    port::{publisher::Publisher, subscriber::Subscriber},
     prelude::{ipc, Node, NodeBuilder, NodeEvent, ServiceName},
 };
-use thiserror::Error;
 
+pub use crate::live::ipc::ChannelError;
 use crate::{
     live::{
         ipc::{
+            codec,
             config::{ChannelConfig, MAX_PAYLOAD_SIZE},
             Channel,
+            Message,
+            PubSubReceiver,
+            PubSubSender,
         },
         BotError,
         Instrument,
@@ -41,24 +36,6 @@ pub struct CustomHeader {
     pub len: usize,
 }
 
-#[derive(Error, Debug)]
-pub enum ChannelError {
-    #[error("BuildError - {0}")]
-    BuildError(String),
-    #[error("{0:?}")]
-    SubscriberReceive(#[from] SubscriberReceiveError),
-    #[error("{0:?}")]
-    PublisherLoan(#[from] PublisherLoanError),
-    #[error("{0:?}")]
-    PublisherSend(#[from] PublisherSendError),
-    #[error("{0:?}")]
-    Decode(#[from] DecodeError),
-    #[error("{0:?}")]
-    Encode(#[from] EncodeError),
-    #[error("{0:?}")]
-    FromUtf8(#[from] FromUtf8Error),
-}
-
 pub struct IceoryxBuilder {
     name: String,
     bot: bool,
@@ -174,7 +151,7 @@ where
         let mut sample = unsafe { sample.assume_init() };
 
         let payload = sample.payload_mut();
-        let length = bincode::encode_into_slice(data, payload, config::standard())?;
+        let length = codec::encode_into_slice(data, payload)?;
 
         sample.user_header_mut().id = id;
         sample.user_header_mut().len = length;
@@ -185,6 +162,15 @@ where
     }
 }
 
+impl<T> PubSubSender<T> for IceoryxSender<T>
+where
+    T: Encode,
+{
+    fn send(&self, id: u64, data: &T) -> Result<(), ChannelError> {
+        IceoryxSender::send(self, id, data)
+    }
+}
+
 pub struct IceoryxReceiver<T> {
     subscriber: Subscriber<ipc::Service, [u8], CustomHeader>,
     _t_marker: PhantomData<T>,
@@ -192,7 +178,7 @@ pub struct IceoryxReceiver<T> {
 
 impl<T> IceoryxReceiver<T>
 where
-    T: Decode,
+    T: Message,
 {
     pub fn receive(&self) -> Result<Option<(u64, T)>, ChannelError> {
         match self.subscriber.receive()? {
@@ -202,14 +188,22 @@ where
                 let len = sample.user_header().len;
 
                 let bytes = &sample.payload()[0..len];
-                let (decoded, _len): (T, usize) =
-                    bincode::decode_from_slice(bytes, config::standard())?;
-                Ok(Some((id, decoded)))
+                // A message from a newer version that can't be decoded is skipped.
+                Ok(codec::decode_from_slice(bytes)?.map(|decoded| (id, decoded)))
             }
         }
     }
 }
 
+impl<T> PubSubReceiver<T> for IceoryxReceiver<T>
+where
+    T: Message,
+{
+    fn receive(&self) -> Result<Option<(u64, T)>, ChannelError> {
+        IceoryxReceiver::receive(self)
+    }
+}
+
 pub struct IceoryxChannel<S, R> {
     publisher: IceoryxSender<S>,
     subscriber: IceoryxReceiver<R>,
@@ -219,7 +213,7 @@ pub struct IceoryxChannel<S, R> {
 impl<S, R> IceoryxChannel<S, R>
 where
     S: Encode,
-    R: Decode,
+    R: Message,
 {
     pub fn new(name: &str) -> Result<Self, ChannelError> {
         let publisher = IceoryxBuilder::new(name).sender()?;
@@ -328,14 +322,15 @@ impl Channel for IceoryxUnifiedChannel {
 
     fn recv_timeout(&mut self, id: u64, timeout: Duration) -> Result<(usize, LiveEvent), BotError> {
         let instant = Instant::now();
+        // A zero timeout polls a channel once without waiting.
+        let cycle_time = if timeout.is_zero() {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos(1)
+        };
         loop {
-            let elapsed = instant.elapsed();
-            if elapsed > timeout {
-                return Err(BotError::Timeout);
-            }
-
             // todo: this needs to retrieve Iox2Event without waiting.
-            match self.node.wait(Duration::from_nanos(1)) {
+            match self.node.wait(cycle_time) {
                 NodeEvent::Tick => {
                     let ch = unsafe { self.unique_channel.get_unchecked(self.ch_i) };
 
@@ -356,9 +351,22 @@ impl Channel for IceoryxUnifiedChannel {
                                     // todo: it may cause incorrect usage.
                                     return Ok((0, ev));
                                 }
+                                LiveEvent::Heartbeat { .. } | LiveEvent::Margin(_) => {
+                                    // Identifies the connector by its first instrument.
+                                    if let Some(inst_no) = ch.symbol_to_inst_no.values().min() {
+                                        return Ok((*inst_no, ev));
+                                    }
+                                }
                                 LiveEvent::Feed { symbol, .. }
                                 | LiveEvent::Order { symbol, .. }
-                                | LiveEvent::Position { symbol, .. } => {
+                                | LiveEvent::Position { symbol, .. }
+                                | LiveEvent::Liquidation { symbol, .. }
+                                | LiveEvent::Fill { symbol, .. }
+                                | LiveEvent::MarkPrice { symbol, .. }
+                                | LiveEvent::IndexPrice { symbol, .. }
+                                | LiveEvent::FundingRate { symbol, .. }
+                                | LiveEvent::InstrumentInfo { symbol, .. }
+                                | LiveEvent::ExternalOrder { symbol, .. } => {
                                     if let Some(inst_no) = ch.symbol_to_inst_no.get(symbol) {
                                         return Ok((*inst_no, ev));
                                     }
@@ -371,6 +379,10 @@ impl Channel for IceoryxUnifiedChannel {
                     return Err(BotError::Interrupted);
                 }
             }
+
+            if instant.elapsed() > timeout {
+                return Err(BotError::Timeout);
+            }
         }
     }
 
@@ -0,0 +1,312 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{
+        mpsc,
+        mpsc::{Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc,
+        LazyLock,
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    live::{
+        ipc::{Channel, ChannelError, PubSubReceiver, PubSubSender, TO_ALL},
+        BotError,
+        Instrument,
+    },
+    prelude::{LiveEvent, LiveRequest},
+    types::BuildError,
+};
+
+/// An event published to a bot, along with the number of the hub from which it is published.
+type Inbox = (usize, u64, LiveEvent);
+
+/// Connects the bots to a connector within the same process.
+struct Hub {
+    requests: Sender<(u64, LiveRequest)>,
+    subscribers: Mutex<Vec<(usize, Sender<Inbox>)>>,
+}
+
+static HUBS: LazyLock<Mutex<HashMap<String, Arc<Hub>>>> = LazyLock::new(Default::default);
+
+/// The connector side of the in-process transport, through which a connector embedded in the bot
+/// process exchanges messages with the bots directly, without the IPC. This allows a single
+/// binary deployment with the lowest internal latency.
+///
+/// The bots use [`InProcessUnifiedChannel`] with the name to which the server is bound as the
+/// connector name. The server must be bound before the bots are built. The connector crate runs a
+/// connector over it through `connector::run` with `Transport::InProcess`; see its `inprocess`
+/// example.
+pub struct InProcessServer {
+    name: String,
+    hub: Arc<Hub>,
+    requests: Arc<Mutex<Receiver<(u64, LiveRequest)>>>,
+}
+
+impl InProcessServer {
+    /// Binds the server to the connector name, which must be unique within the process.
+    pub fn bind(name: &str) -> Result<Self, ChannelError> {
+        let mut hubs = HUBS.lock().unwrap();
+        match hubs.entry(name.to_string()) {
+            Entry::Occupied(_) => Err(ChannelError::BuildError(format!(
+                "`{name}` is already bound"
+            ))),
+            Entry::Vacant(entry) => {
+                let (requests_tx, requests_rx) = mpsc::channel();
+                let hub = Arc::new(Hub {
+                    requests: requests_tx,
+                    subscribers: Default::default(),
+                });
+                entry.insert(hub.clone());
+                Ok(Self {
+                    name: name.to_string(),
+                    hub,
+                    requests: Arc::new(Mutex::new(requests_rx)),
+                })
+            }
+        }
+    }
+
+    /// Returns a sender that publishes the events to the bots.
+    pub fn sender(&self) -> InProcessSender {
+        InProcessSender {
+            hub: self.hub.clone(),
+        }
+    }
+
+    /// Returns a receiver of the requests from the bots.
+    pub fn receiver(&self) -> InProcessReceiver {
+        InProcessReceiver {
+            requests: self.requests.clone(),
+        }
+    }
+}
+
+impl Drop for InProcessServer {
+    fn drop(&mut self) {
+        HUBS.lock().unwrap().remove(&self.name);
+    }
+}
+
+pub struct InProcessSender {
+    hub: Arc<Hub>,
+}
+
+impl PubSubSender<LiveEvent> for InProcessSender {
+    fn send(&self, id: u64, data: &LiveEvent) -> Result<(), ChannelError> {
+        // Every bot receives the event and discards it if it's not destined for it. The bots that
+        // have gone are removed.
+        self.hub
+            .subscribers
+            .lock()
+            .unwrap()
+            .retain(|(hub_no, tx)| tx.send((*hub_no, id, data.clone())).is_ok());
+        Ok(())
+    }
+}
+
+pub struct InProcessReceiver {
+    requests: Arc<Mutex<Receiver<(u64, LiveRequest)>>>,
+}
+
+impl PubSubReceiver<LiveRequest> for InProcessReceiver {
+    fn receive(&self) -> Result<Option<(u64, LiveRequest)>, ChannelError> {
+        match self.requests.lock().unwrap().try_recv() {
+            Ok(received) => Ok(Some(received)),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => Ok(None),
+        }
+    }
+}
+
+/// The bot side of the in-process transport. The connector name of each [`Instrument`] is the
+/// name to which its [`InProcessServer`] is bound.
+pub struct InProcessUnifiedChannel {
+    requests: Vec<Sender<(u64, LiveRequest)>>,
+    symbol_to_inst_no: HashMap<(usize, String), usize>,
+    inbox: Receiver<Inbox>,
+}
+
+impl Channel for InProcessUnifiedChannel {
+    fn build<MD>(instruments: &[Instrument<MD>]) -> Result<Self, BuildError>
+    where
+        Self: Sized,
+    {
+        let hubs = HUBS.lock().unwrap();
+        let (inbox_tx, inbox) = mpsc::channel();
+        let mut name_to_hub: HashMap<String, (usize, Sender<(u64, LiveRequest)>)> = HashMap::new();
+        let mut requests = Vec::new();
+        let mut symbol_to_inst_no = HashMap::new();
+        for (inst_no, instrument) in instruments.iter().enumerate() {
+            let (hub_no, requests_tx) = match name_to_hub.entry(instrument.connector_name.clone()) {
+                Entry::Occupied(entry) => entry.get().clone(),
+                Entry::Vacant(entry) => {
+                    let hub = hubs
+                        .get(entry.key())
+                        .ok_or_else(|| BuildError::ConnectorNotFound(entry.key().clone()))?;
+                    // The number of the first instrument traded through the hub identifies it.
+                    let hub_no = inst_no;
+                    hub.subscribers
+                        .lock()
+                        .unwrap()
+                        .push((hub_no, inbox_tx.clone()));
+                    entry.insert((hub_no, hub.requests.clone())).clone()
+                }
+            };
+            if symbol_to_inst_no
+                .insert((hub_no, instrument.symbol.clone()), inst_no)
+                .is_some()
+            {
+                return Err(BuildError::Duplicate(
+                    instrument.connector_name.clone(),
+                    instrument.symbol.clone(),
+                ));
+            }
+            requests.push(requests_tx);
+        }
+        Ok(Self {
+            requests,
+            symbol_to_inst_no,
+            inbox,
+        })
+    }
+
+    fn recv_timeout(&mut self, id: u64, timeout: Duration) -> Result<(usize, LiveEvent), BotError> {
+        let instant = Instant::now();
+        loop {
+            let (hub_no, dst_id, ev) = if timeout.is_zero() {
+                self.inbox.try_recv().map_err(|_| BotError::Timeout)?
+            } else {
+                match self
+                    .inbox
+                    .recv_timeout(timeout.saturating_sub(instant.elapsed()))
+                {
+                    Ok(received) => received,
+                    Err(RecvTimeoutError::Timeout) => return Err(BotError::Timeout),
+                    Err(RecvTimeoutError::Disconnected) => {
+                        return Err(BotError::Custom("the connectors have gone".to_string()));
+                    }
+                }
+            };
+            if dst_id == TO_ALL || dst_id == id {
+                match &ev {
+                    LiveEvent::BatchStart | LiveEvent::BatchEnd | LiveEvent::Error(_) => {
+                        return Ok((0, ev));
+                    }
+                    LiveEvent::Heartbeat { .. } | LiveEvent::Margin(_) => {
+                        return Ok((hub_no, ev));
+                    }
+                    LiveEvent::Feed { symbol, .. }
+                    | LiveEvent::Order { symbol, .. }
+                    | LiveEvent::Position { symbol, .. }
+                    | LiveEvent::Liquidation { symbol, .. }
+                    | LiveEvent::Fill { symbol, .. }
+                    | LiveEvent::MarkPrice { symbol, .. }
+                    | LiveEvent::IndexPrice { symbol, .. }
+                    | LiveEvent::FundingRate { symbol, .. }
+                    | LiveEvent::InstrumentInfo { symbol, .. }
+                    | LiveEvent::ExternalOrder { symbol, .. } => {
+                        if let Some(inst_no) = self.symbol_to_inst_no.get(&(hub_no, symbol.clone()))
+                        {
+                            return Ok((*inst_no, ev));
+                        }
+                    }
+                }
+            }
+            if instant.elapsed() > timeout {
+                return Err(BotError::Timeout);
+            }
+        }
+    }
+
+    fn send(&mut self, id: u64, inst_no: usize, request: LiveRequest) -> Result<(), BotError> {
+        self.requests
+            .get(inst_no)
+            .ok_or(BotError::InstrumentNotFound)?
+            .send((id, request))
+            .map_err(|_| BotError::Custom("the connector has gone".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{InProcessServer, InProcessUnifiedChannel};
+    use crate::{
+        live::{
+            ipc::{Channel, PubSubReceiver, PubSubSender, TO_ALL},
+            BotError,
+            Instrument,
+        },
+        prelude::{HashMapMarketDepth, LiveEvent, LiveRequest, PositionSide},
+        types::BuildError,
+    };
+
+    #[test]
+    fn test_inprocess_transport() {
+        let server = InProcessServer::bind("test_inprocess").unwrap();
+        assert!(InProcessServer::bind("test_inprocess").is_err());
+        let server_tx = server.sender();
+        let server_rx = server.receiver();
+
+        let instruments = vec![Instrument::new(
+            "test_inprocess",
+            "a",
+            0.1,
+            1.0,
+            HashMapMarketDepth::new(0.1, 1.0),
+            0,
+        )];
+        let mut channel = InProcessUnifiedChannel::build(&instruments).unwrap();
+
+        channel
+            .send(
+                7,
+                0,
+                LiveRequest::Resync {
+                    symbol: "a".to_string(),
+                },
+            )
+            .unwrap();
+        let (id, req) = server_rx.receive().unwrap().unwrap();
+        assert_eq!(id, 7);
+        assert!(matches!(req, LiveRequest::Resync { symbol } if symbol == "a"));
+        assert!(server_rx.receive().unwrap().is_none());
+
+        server_tx.send(8, &LiveEvent::BatchStart).unwrap();
+        server_tx
+            .send(
+                TO_ALL,
+                &LiveEvent::Position {
+                    symbol: "a".to_string(),
+                    side: PositionSide::Both,
+                    qty: 1.0,
+                    exch_ts: 0,
+                },
+            )
+            .unwrap();
+        let (inst_no, ev) = channel.recv_timeout(7, Duration::from_secs(1)).unwrap();
+        assert_eq!(inst_no, 0);
+        assert!(matches!(ev, LiveEvent::Position { qty, .. } if qty == 1.0));
+        assert!(matches!(
+            channel.recv_timeout(7, Duration::ZERO),
+            Err(BotError::Timeout)
+        ));
+
+        drop(server);
+        let instruments = vec![Instrument::new(
+            "test_inprocess",
+            "a",
+            0.1,
+            1.0,
+            HashMapMarketDepth::new(0.1, 1.0),
+            0,
+        )];
+        assert!(matches!(
+            InProcessUnifiedChannel::build(&instruments),
+            Err(BuildError::ConnectorNotFound(_))
+        ));
+    }
+}
@@ -1,4 +1,11 @@
-use std::time::Duration;
+use std::{io, string::FromUtf8Error, time::Duration};
+
+use bincode::error::{DecodeError, EncodeError};
+use iceoryx2::port::{
+    publisher::{PublisherLoanError, PublisherSendError},
+    subscriber::SubscriberReceiveError,
+};
+use thiserror::Error;
 
 use crate::{
     live::{BotError, Instrument},
@@ -6,11 +13,49 @@ use crate::{
     types::{LiveEvent, LiveRequest},
 };
 
+mod codec;
 mod config;
 pub mod iceoryx;
+pub mod inprocess;
+pub mod socket;
+
+pub use codec::{Message, PROTOCOL_VERSION};
 
 pub const TO_ALL: u64 = 0;
 
+#[derive(Error, Debug)]
+pub enum ChannelError {
+    #[error("BuildError - {0}")]
+    BuildError(String),
+    #[error("{0:?}")]
+    SubscriberReceive(#[from] SubscriberReceiveError),
+    #[error("{0:?}")]
+    PublisherLoan(#[from] PublisherLoanError),
+    #[error("{0:?}")]
+    PublisherSend(#[from] PublisherSendError),
+    #[error("{0:?}")]
+    Decode(#[from] DecodeError),
+    #[error("{0:?}")]
+    Encode(#[from] EncodeError),
+    #[error("{0:?}")]
+    FromUtf8(#[from] FromUtf8Error),
+    #[error("{0:?}")]
+    Io(#[from] io::Error),
+}
+
+/// Publishes messages to the other side of the transport, such as from a connector to the bots.
+pub trait PubSubSender<T> {
+    /// Sends the message to the destination `id`, or to all subscribers if `id` is [`TO_ALL`].
+    fn send(&self, id: u64, data: &T) -> Result<(), ChannelError>;
+}
+
+/// Receives messages published by the other side of the transport.
+pub trait PubSubReceiver<T> {
+    /// Receives a message without waiting, returning the ID it was sent with, or `None` if no
+    /// message is available.
+    fn receive(&self) -> Result<Option<(u64, T)>, ChannelError>;
+}
+
 /// Provides the IPC communication methods.
 pub trait Channel {
     /// Builds a [`Channel`] based on a list of [`Instrument`].
@@ -23,7 +68,12 @@ pub trait Channel {
     /// If the ID of the received message does not match the provided ID, the message will be
     /// ignored and this will attempt to receive a [`LiveEvent`] again until the timeout is reached.
     ///
-    /// `(instrument_no, LiveEvent)` will be returned if the message is received.
+    /// If the `timeout` is zero, it attempts to receive without waiting, which is used for
+    /// busy-polling.
+    ///
+    /// `(instrument_no, LiveEvent)` will be returned if the message is received. For a
+    /// [`LiveEvent::Heartbeat`] or [`LiveEvent::Margin`], the number of the first instrument traded through the connector
+    /// that sent it is returned.
     fn recv_timeout(&mut self, id: u64, timeout: Duration) -> Result<(usize, LiveEvent), BotError>;
 
     /// Sends a [`LiveRequest`] to the connector corresponding to the `inst_no`.
@@ -0,0 +1,819 @@
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    env,
+    io,
+    io::{Read, Write},
+    marker::PhantomData,
+    net::{TcpListener, TcpStream},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+        mpsc::{Receiver, RecvTimeoutError, Sender, SyncSender, TryRecvError, TrySendError},
+        Arc,
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bincode::Encode;
+use tracing::{error, info, warn};
+
+use crate::{
+    live::{
+        ipc::{
+            codec,
+            Channel,
+            ChannelError,
+            Message,
+            PubSubReceiver,
+            PubSubSender,
+            TO_ALL,
+        },
+        BotError,
+        Instrument,
+    },
+    prelude::{LiveEvent, LiveRequest},
+    types::BuildError,
+};
+
+/// The maximum size of a message, which guards against a corrupted length prefix.
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// The maximum number of messages queued for a connection. A connection that falls this far
+/// behind is closed, so that a slow or stalled peer doesn't hold up the others.
+const MAX_QUEUED_FRAMES: usize = 65536;
+
+/// The time within which a connecting peer must complete the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The environment variable holding the shared secret that the bots present to the connector on
+/// connecting. Both sides must have the same value. The transport is neither authenticated beyond
+/// this nor encrypted, so across an untrusted network it should be run through a tunnel such as
+/// SSH, WireGuard or a TLS proxy.
+pub const SECRET_ENV: &str = "HFTBACKTEST_SOCKET_SECRET";
+
+fn shared_secret() -> Vec<u8> {
+    env::var(SECRET_ENV).unwrap_or_default().into_bytes()
+}
+
+/// Compares the secrets in constant time, so that the comparison doesn't reveal the length of the
+/// matching prefix.
+fn secret_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The endpoint of a socket transport, written as `tcp://host:port` or `unix:///path/to/socket`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(String),
+}
+
+impl FromStr for Endpoint {
+    type Err = ChannelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix("tcp://") {
+            Ok(Endpoint::Tcp(addr.to_string()))
+        } else if let Some(path) = s.strip_prefix("unix://") {
+            #[cfg(unix)]
+            return Ok(Endpoint::Unix(path.to_string()));
+            #[cfg(not(unix))]
+            return Err(ChannelError::BuildError(format!(
+                "Unix domain sockets are not supported: {path}"
+            )));
+        } else {
+            Err(ChannelError::BuildError(format!("invalid endpoint: {s}")))
+        }
+    }
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn connect(endpoint: &Endpoint) -> io::Result<Self> {
+        match endpoint {
+            Endpoint::Tcp(addr) => {
+                let stream = TcpStream::connect(addr)?;
+                stream.set_nodelay(true)?;
+                Ok(Stream::Tcp(stream))
+            }
+            #[cfg(unix)]
+            Endpoint::Unix(path) => Ok(Stream::Unix(UnixStream::connect(path)?)),
+        }
+    }
+
+    fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Stream::Tcp(stream) => Ok(Stream::Tcp(stream.try_clone()?)),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Ok(Stream::Unix(stream.try_clone()?)),
+        }
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.set_read_timeout(timeout),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+
+    fn shutdown(&self) {
+        let _ = match self {
+            Stream::Tcp(stream) => stream.shutdown(std::net::Shutdown::Both),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.shutdown(std::net::Shutdown::Both),
+        };
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Frames a message by the destination ID and the payload length.
+fn frame(id: u64, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(12 + payload.len());
+    frame.extend_from_slice(&id.to_le_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn read_frame(stream: &mut Stream) -> io::Result<(u64, Vec<u8>)> {
+    let mut header = [0u8; 12];
+    stream.read_exact(&mut header)?;
+    let id = u64::from_le_bytes(header[..8].try_into().unwrap());
+    let len = u32::from_le_bytes(header[8..].try_into().unwrap()) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("the frame size {len} exceeds the maximum"),
+        ));
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload)?;
+    Ok((id, payload))
+}
+
+/// A message received on the connection `conn_no`.
+type Inbox = (usize, u64, Vec<u8>);
+
+/// The write queues of the connections, and on the connector side, the connection from which each
+/// ID was first received, so that a message destined for an ID is sent only through its
+/// connection.
+#[derive(Default)]
+struct Connections {
+    writers: Mutex<HashMap<usize, SyncSender<Vec<u8>>>>,
+    routes: Mutex<HashMap<u64, usize>>,
+    routing: bool,
+    next_conn_no: AtomicUsize,
+}
+
+impl Connections {
+    /// Adds the connection, after the handshake has been completed, spawning a thread that reads
+    /// its messages into the inbox and another that writes its queued messages.
+    fn add(self: &Arc<Self>, stream: Stream, inbox: Sender<Inbox>) -> io::Result<usize> {
+        let conn_no = self.next_conn_no.fetch_add(1, Ordering::Relaxed);
+        let mut reader = stream.try_clone()?;
+        let (queue_tx, queue_rx) = mpsc::sync_channel::<Vec<u8>>(MAX_QUEUED_FRAMES);
+        self.writers.lock().unwrap().insert(conn_no, queue_tx);
+
+        let mut writer = stream;
+        thread::spawn(move || {
+            for frame in queue_rx {
+                if let Err(error) = writer.write_all(&frame) {
+                    error!(?error, conn_no, "Couldn't write to the socket connection.");
+                    break;
+                }
+            }
+            // Makes the reading thread end, which removes the connection.
+            writer.shutdown();
+        });
+
+        let conns = self.clone();
+        thread::spawn(move || {
+            loop {
+                match read_frame(&mut reader) {
+                    Ok((id, payload)) => {
+                        if conns.routing && id != TO_ALL {
+                            // An ID is bound to the connection from which it was first received,
+                            // so that another connection can't take over its messages.
+                            let mut routes = conns.routes.lock().unwrap();
+                            let route = *routes.entry(id).or_insert(conn_no);
+                            if route != conn_no {
+                                warn!(
+                                    id,
+                                    conn_no,
+                                    "A message with the ID bound to another connection is dropped."
+                                );
+                                continue;
+                            }
+                        }
+                        if inbox.send((conn_no, id, payload)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(error) => {
+                        if error.kind() != io::ErrorKind::UnexpectedEof {
+                            error!(?error, conn_no, "The socket connection failed.");
+                        }
+                        break;
+                    }
+                }
+            }
+            info!(conn_no, "The socket connection is closed.");
+            reader.shutdown();
+            conns.writers.lock().unwrap().remove(&conn_no);
+            conns.routes.lock().unwrap().retain(|_, c| *c != conn_no);
+        });
+        Ok(conn_no)
+    }
+
+    /// Queues the message on the connection. The connection is closed if it has fallen too far
+    /// behind.
+    fn send_to(&self, conn_no: usize, id: u64, payload: &[u8]) -> Result<(), ChannelError> {
+        let mut writers = self.writers.lock().unwrap();
+        let queue = writers
+            .get(&conn_no)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+        match queue.try_send(frame(id, payload)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                error!(conn_no, "The socket connection is too slow and is closed.");
+                writers.remove(&conn_no);
+                Err(io::Error::from(io::ErrorKind::WouldBlock).into())
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                writers.remove(&conn_no);
+                Err(io::Error::from(io::ErrorKind::NotConnected).into())
+            }
+        }
+    }
+
+    fn send(&self, id: u64, payload: &[u8]) -> Result<(), ChannelError> {
+        let route = if id == TO_ALL {
+            None
+        } else {
+            self.routes.lock().unwrap().get(&id).copied()
+        };
+        match route {
+            Some(conn_no) => self.send_to(conn_no, id, payload),
+            None => {
+                // Broadcasts to all connections; each bot discards the messages not destined for
+                // it.
+                let frame = frame(id, payload);
+                let mut writers = self.writers.lock().unwrap();
+                writers.retain(|conn_no, queue| match queue.try_send(frame.clone()) {
+                    Ok(()) => true,
+                    Err(TrySendError::Full(_)) => {
+                        error!(conn_no, "The socket connection is too slow and is closed.");
+                        false
+                    }
+                    Err(TrySendError::Disconnected(_)) => false,
+                });
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads the handshake of a connecting bot and checks its secret.
+fn accept_handshake(stream: &mut Stream, secret: &[u8]) -> io::Result<()> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let (_, presented) = read_frame(stream)?;
+    stream.set_read_timeout(None)?;
+    if !secret_eq(&presented, secret) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the handshake secret doesn't match",
+        ));
+    }
+    Ok(())
+}
+
+/// Sends the handshake, which is the first frame of a connection and carries the shared secret.
+fn send_handshake(stream: &mut Stream) -> io::Result<()> {
+    stream.write_all(&frame(TO_ALL, &shared_secret()))
+}
+
+/// The connector side of the socket transport, which listens for the bots on a TCP or Unix domain
+/// socket. This allows the connector to run on a different host, or in a container without shared
+/// memory, from the bots.
+pub struct SocketServer {
+    conns: Arc<Connections>,
+    inbox: Arc<Mutex<Receiver<Inbox>>>,
+    endpoint: String,
+}
+
+impl SocketServer {
+    /// Binds to the endpoint and starts accepting the bots' connections on a background thread.
+    /// A bot's connection is accepted only if it presents the shared secret in [`SECRET_ENV`],
+    /// and a TCP endpoint on an address other than loopback requires that a secret is set.
+    pub fn bind(endpoint: &str) -> Result<Self, ChannelError> {
+        Self::bind_with_secret(endpoint, shared_secret())
+    }
+
+    fn bind_with_secret(endpoint: &str, secret: Vec<u8>) -> Result<Self, ChannelError> {
+        let endpoint: Endpoint = endpoint.parse()?;
+        let conns = Arc::new(Connections {
+            routing: true,
+            ..Default::default()
+        });
+        let (inbox_tx, inbox_rx) = mpsc::channel();
+        let secret = Arc::new(secret);
+
+        let endpoint = match endpoint {
+            Endpoint::Tcp(addr) => {
+                let listener = TcpListener::bind(&addr)?;
+                let addr = listener.local_addr()?;
+                if !addr.ip().is_loopback() && secret.is_empty() {
+                    return Err(ChannelError::BuildError(format!(
+                        "listening on the non-loopback address {addr} requires a shared secret \
+                        in {SECRET_ENV}"
+                    )));
+                }
+                info!(%addr, "The socket transport is listening.");
+                let conns = conns.clone();
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        let stream = match stream.and_then(|stream| {
+                            stream.set_nodelay(true)?;
+                            Ok(Stream::Tcp(stream))
+                        }) {
+                            Ok(stream) => stream,
+                            Err(error) => {
+                                error!(?error, "Couldn't accept a socket connection.");
+                                continue;
+                            }
+                        };
+                        Self::accept(stream, &conns, &secret, inbox_tx.clone());
+                    }
+                });
+                format!("tcp://{addr}")
+            }
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                // Removes the socket file left by a previous run.
+                let _ = std::fs::remove_file(&path);
+                let listener = UnixListener::bind(&path)?;
+                info!(%path, "The socket transport is listening.");
+                let conns = conns.clone();
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        match stream {
+                            Ok(stream) => Self::accept(
+                                Stream::Unix(stream),
+                                &conns,
+                                &secret,
+                                inbox_tx.clone(),
+                            ),
+                            Err(error) => {
+                                error!(?error, "Couldn't accept a socket connection.");
+                            }
+                        }
+                    }
+                });
+                format!("unix://{path}")
+            }
+        };
+
+        Ok(Self {
+            conns,
+            inbox: Arc::new(Mutex::new(inbox_rx)),
+            endpoint,
+        })
+    }
+
+    /// Completes the handshake on a separate thread, so that a peer that doesn't send it doesn't
+    /// hold up the other connections, and adds the connection.
+    fn accept(
+        mut stream: Stream,
+        conns: &Arc<Connections>,
+        secret: &Arc<Vec<u8>>,
+        inbox: Sender<Inbox>,
+    ) {
+        let conns = conns.clone();
+        let secret = secret.clone();
+        thread::spawn(move || {
+            let result =
+                accept_handshake(&mut stream, &secret).and_then(|_| conns.add(stream, inbox));
+            if let Err(error) = result {
+                error!(?error, "Couldn't accept a socket connection.");
+            }
+        });
+    }
+
+    /// Returns the endpoint to which the server is bound, with the actual port if it was bound to
+    /// port 0.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Returns a sender that publishes the messages to the connected bots.
+    pub fn sender<T>(&self) -> SocketSender<T> {
+        SocketSender {
+            conns: self.conns.clone(),
+            _t_marker: Default::default(),
+        }
+    }
+
+    /// Returns a receiver of the messages from the connected bots.
+    pub fn receiver<T>(&self) -> SocketReceiver<T> {
+        SocketReceiver {
+            inbox: self.inbox.clone(),
+            _t_marker: Default::default(),
+        }
+    }
+}
+
+pub struct SocketSender<T> {
+    conns: Arc<Connections>,
+    _t_marker: PhantomData<T>,
+}
+
+impl<T> PubSubSender<T> for SocketSender<T>
+where
+    T: Encode,
+{
+    fn send(&self, id: u64, data: &T) -> Result<(), ChannelError> {
+        let payload = codec::encode_to_vec(data)?;
+        self.conns.send(id, &payload)
+    }
+}
+
+pub struct SocketReceiver<T> {
+    inbox: Arc<Mutex<Receiver<Inbox>>>,
+    _t_marker: PhantomData<T>,
+}
+
+impl<T> PubSubReceiver<T> for SocketReceiver<T>
+where
+    T: Message,
+{
+    fn receive(&self) -> Result<Option<(u64, T)>, ChannelError> {
+        let inbox = self.inbox.lock().unwrap();
+        loop {
+            match inbox.try_recv() {
+                Ok((conn_no, id, payload)) => match codec::decode_from_slice(&payload) {
+                    Ok(Some(decoded)) => return Ok(Some((id, decoded))),
+                    // A message from a newer version that can't be decoded is skipped.
+                    Ok(None) => {}
+                    // A malformed message from a peer is skipped rather than failing the
+                    // transport for all peers.
+                    Err(error) => {
+                        error!(?error, conn_no, id, "A malformed message is skipped.");
+                    }
+                },
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return Ok(None),
+            }
+        }
+    }
+}
+
+/// The bot side of the socket transport. The connector name of each [`Instrument`] is the
+/// endpoint on which its connector listens, such as `tcp://10.0.0.2:9000` or
+/// `unix:///tmp/binancefutures.sock`.
+pub struct SocketUnifiedChannel {
+    conns: Arc<Connections>,
+    inst_conn_no: Vec<usize>,
+    symbol_to_inst_no: HashMap<(usize, String), usize>,
+    inbox: Receiver<Inbox>,
+}
+
+impl Channel for SocketUnifiedChannel {
+    fn build<MD>(instruments: &[Instrument<MD>]) -> Result<Self, BuildError>
+    where
+        Self: Sized,
+    {
+        let conns: Arc<Connections> = Default::default();
+        let (inbox_tx, inbox) = mpsc::channel();
+        let mut endpoint_conn_no: HashMap<String, usize> = HashMap::new();
+        let mut inst_conn_no = Vec::new();
+        let mut symbol_to_inst_no = HashMap::new();
+        for (inst_no, instrument) in instruments.iter().enumerate() {
+            let conn_no = match endpoint_conn_no.entry(instrument.connector_name.clone()) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    let endpoint: Endpoint = entry
+                        .key()
+                        .parse()
+                        .map_err(|error| BuildError::Error(anyhow::Error::from(error)))?;
+                    let conn_no = Stream::connect(&endpoint)
+                        .and_then(|mut stream| {
+                            send_handshake(&mut stream)?;
+                            conns.add(stream, inbox_tx.clone())
+                        })
+                        .map_err(|error| BuildError::Error(anyhow::Error::from(error)))?;
+                    *entry.insert(conn_no)
+                }
+            };
+            if symbol_to_inst_no
+                .insert((conn_no, instrument.symbol.clone()), inst_no)
+                .is_some()
+            {
+                return Err(BuildError::Duplicate(
+                    instrument.connector_name.clone(),
+                    instrument.symbol.clone(),
+                ));
+            }
+            inst_conn_no.push(conn_no);
+        }
+        Ok(Self {
+            conns,
+            inst_conn_no,
+            symbol_to_inst_no,
+            inbox,
+        })
+    }
+
+    fn recv_timeout(&mut self, id: u64, timeout: Duration) -> Result<(usize, LiveEvent), BotError> {
+        let instant = Instant::now();
+        loop {
+            let received = if timeout.is_zero() {
+                self.inbox.try_recv().map_err(|_| BotError::Timeout)
+            } else {
+                match self
+                    .inbox
+                    .recv_timeout(timeout.saturating_sub(instant.elapsed()))
+                {
+                    Ok(received) => Ok(received),
+                    Err(RecvTimeoutError::Timeout) => Err(BotError::Timeout),
+                    Err(RecvTimeoutError::Disconnected) => Err(BotError::Custom(
+                        "the socket connections are closed".to_string(),
+                    )),
+                }
+            };
+            let (conn_no, dst_id, payload) = received?;
+            if dst_id == TO_ALL || dst_id == id {
+                // A message from a newer version that can't be decoded, or a malformed one, is
+                // skipped.
+                let ev: Option<LiveEvent> = codec::decode_from_slice(&payload)
+                    .map_err(|error| error!(?error, conn_no, "A malformed message is skipped."))
+                    .ok()
+                    .flatten();
+                if let Some(ev) = ev {
+                    match &ev {
+                        LiveEvent::BatchStart | LiveEvent::BatchEnd | LiveEvent::Error(_) => {
+                            return Ok((0, ev));
+                        }
+                        LiveEvent::Heartbeat { .. } | LiveEvent::Margin(_) => {
+                            // Identifies the connector by its first instrument.
+                            if let Some(inst_no) =
+                                self.inst_conn_no.iter().position(|c| *c == conn_no)
+                            {
+                                return Ok((inst_no, ev));
+                            }
+                        }
+                        LiveEvent::Feed { symbol, .. }
+                        | LiveEvent::Order { symbol, .. }
+                        | LiveEvent::Position { symbol, .. }
+                        | LiveEvent::Liquidation { symbol, .. }
+                        | LiveEvent::Fill { symbol, .. }
+                        | LiveEvent::MarkPrice { symbol, .. }
+                        | LiveEvent::IndexPrice { symbol, .. }
+                        | LiveEvent::FundingRate { symbol, .. }
+                        | LiveEvent::InstrumentInfo { symbol, .. }
+                        | LiveEvent::ExternalOrder { symbol, .. } => {
+                            if let Some(inst_no) =
+                                self.symbol_to_inst_no.get(&(conn_no, symbol.clone()))
+                            {
+                                return Ok((*inst_no, ev));
+                            }
+                        }
+                    }
+                }
+            }
+            if instant.elapsed() > timeout {
+                return Err(BotError::Timeout);
+            }
+        }
+    }
+
+    fn send(&mut self, id: u64, inst_no: usize, request: LiveRequest) -> Result<(), BotError> {
+        let conn_no = *self
+            .inst_conn_no
+            .get(inst_no)
+            .ok_or(BotError::InstrumentNotFound)?;
+        let payload =
+            codec::encode_to_vec(&request).map_err(|err| BotError::Custom(err.to_string()))?;
+        self.conns
+            .send_to(conn_no, id, &payload)
+            .map_err(|err| BotError::Custom(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{ErrorKind, Read, Write},
+        net::TcpStream,
+        thread,
+        time::Duration,
+    };
+
+    use super::{frame, SocketServer};
+    use crate::{
+        live::{
+            ipc::{
+                codec,
+                socket::SocketUnifiedChannel,
+                Channel,
+                PubSubReceiver,
+                PubSubSender,
+                TO_ALL,
+            },
+            Instrument,
+        },
+        prelude::{HashMapMarketDepth, LiveEvent, LiveRequest, PositionSide},
+    };
+
+    fn wait_for<T>(mut f: impl FnMut() -> Option<T>) -> T {
+        for _ in 0..500 {
+            if let Some(v) = f() {
+                return v;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("timed out");
+    }
+
+    #[test]
+    fn test_socket_transport() {
+        let server = SocketServer::bind("tcp://127.0.0.1:0").unwrap();
+        let endpoint = server.endpoint().to_string();
+        let server_tx = server.sender::<LiveEvent>();
+        let server_rx = server.receiver::<LiveRequest>();
+
+        let instruments = vec![
+            Instrument::new(
+                &endpoint,
+                "a",
+                0.1,
+                1.0,
+                HashMapMarketDepth::new(0.1, 1.0),
+                0,
+            ),
+            Instrument::new(
+                &endpoint,
+                "b",
+                0.1,
+                1.0,
+                HashMapMarketDepth::new(0.1, 1.0),
+                0,
+            ),
+        ];
+        let mut channel = SocketUnifiedChannel::build(&instruments).unwrap();
+
+        channel
+            .send(
+                7,
+                1,
+                LiveRequest::Resync {
+                    symbol: "b".to_string(),
+                },
+            )
+            .unwrap();
+        let (id, req) = wait_for(|| server_rx.receive().unwrap());
+        assert_eq!(id, 7);
+        assert!(matches!(req, LiveRequest::Resync { symbol } if symbol == "b"));
+
+        // A message for another bot is discarded.
+        server_tx.send(8, &LiveEvent::BatchStart).unwrap();
+        server_tx
+            .send(
+                TO_ALL,
+                &LiveEvent::Position {
+                    symbol: "b".to_string(),
+                    side: PositionSide::Both,
+                    qty: 1.0,
+                    exch_ts: 0,
+                },
+            )
+            .unwrap();
+        server_tx.send(7, &LiveEvent::BatchEnd).unwrap();
+
+        let (inst_no, ev) = channel.recv_timeout(7, Duration::from_secs(5)).unwrap();
+        assert_eq!(inst_no, 1);
+        assert!(matches!(ev, LiveEvent::Position { qty, .. } if qty == 1.0));
+        let (_, ev) = channel.recv_timeout(7, Duration::from_secs(5)).unwrap();
+        assert!(matches!(ev, LiveEvent::BatchEnd));
+        assert!(channel.recv_timeout(7, Duration::ZERO).is_err());
+    }
+
+    fn connect(server: &SocketServer, secret: &[u8]) -> TcpStream {
+        let addr = server.endpoint().strip_prefix("tcp://").unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(&frame(TO_ALL, secret)).unwrap();
+        stream
+    }
+
+    fn resync(symbol: &str) -> Vec<u8> {
+        codec::encode_to_vec(&LiveRequest::Resync {
+            symbol: symbol.to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_non_loopback_requires_secret() {
+        assert!(SocketServer::bind_with_secret("tcp://0.0.0.0:0", Vec::new()).is_err());
+        assert!(SocketServer::bind_with_secret("tcp://0.0.0.0:0", b"secret".to_vec()).is_ok());
+    }
+
+    #[test]
+    fn test_handshake() {
+        let server =
+            SocketServer::bind_with_secret("tcp://127.0.0.1:0", b"secret".to_vec()).unwrap();
+        let server_rx = server.receiver::<LiveRequest>();
+
+        // The connection with a wrong secret is closed without its messages being received.
+        let mut stream = connect(&server, b"wrong");
+        let _ = stream.write_all(&frame(7, &resync("a")));
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut buf = [0u8; 1];
+        assert!(!matches!(stream.read(&mut buf), Ok(n) if n > 0));
+        assert!(server_rx.receive().unwrap().is_none());
+
+        let mut stream = connect(&server, b"secret");
+        stream.write_all(&frame(7, &resync("b"))).unwrap();
+        let (id, req) = wait_for(|| server_rx.receive().unwrap());
+        assert_eq!(id, 7);
+        assert!(matches!(req, LiveRequest::Resync { symbol } if symbol == "b"));
+    }
+
+    #[test]
+    fn test_route_binding_and_malformed_messages() {
+        let server = SocketServer::bind_with_secret("tcp://127.0.0.1:0", Vec::new()).unwrap();
+        let server_tx = server.sender::<LiveEvent>();
+        let server_rx = server.receiver::<LiveRequest>();
+
+        let mut first = connect(&server, b"");
+        // The malformed message is skipped.
+        first.write_all(&frame(7, &[0xff, 0, 0xff, 0xff])).unwrap();
+        first.write_all(&frame(7, &resync("a"))).unwrap();
+        let (id, _) = wait_for(|| server_rx.receive().unwrap());
+        assert_eq!(id, 7);
+
+        // Another connection can't take over the ID bound to the first one.
+        let mut second = connect(&server, b"");
+        second.write_all(&frame(7, &resync("b"))).unwrap();
+        second.write_all(&frame(8, &resync("c"))).unwrap();
+        let (id, req) = wait_for(|| server_rx.receive().unwrap());
+        assert_eq!(id, 8);
+        assert!(matches!(req, LiveRequest::Resync { symbol } if symbol == "c"));
+
+        server_tx.send(7, &LiveEvent::BatchStart).unwrap();
+        first
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut header = [0u8; 12];
+        first.read_exact(&mut header).unwrap();
+        assert_eq!(u64::from_le_bytes(header[..8].try_into().unwrap()), 7);
+
+        second
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        let error = second.read_exact(&mut header).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::WouldBlock | ErrorKind::TimedOut
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    io::Write,
+};
+
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+use tracing::error;
+
+use crate::types::{LiveError, Order, Status};
+
+fn order_json(order: &Order) -> JsonValue {
+    json!({
+        "order_id": order.order_id,
+        "side": format!("{:?}", order.side),
+        "price": order.price(),
+        "qty": order.qty,
+        "leaves_qty": order.leaves_qty,
+        "exec_qty": order.exec_qty,
+        "exec_price": order.exec_price(),
+        "status": format!("{:?}", order.status),
+        "req": format!("{:?}", order.req),
+        "time_in_force": format!("{:?}", order.time_in_force),
+        "order_type": format!("{:?}", order.order_type),
+        "maker": order.maker,
+        "local_ts": order.local_timestamp,
+        "exch_ts": order.exch_timestamp,
+    })
+}
+
+/// An append-only journal that records every order request, order response, fill and error of
+/// the bot as a JSON object per line, with the timestamp in nanoseconds at which it is recorded.
+///
+/// Each record is written by a single write to the file, so a crash of the process leaves no
+/// partially written record except possibly the last one. If `sync` is enabled, each record is
+/// also flushed to the disk, which survives a crash of the operating system at the cost of
+/// latency.
+pub(crate) struct Journal {
+    file: File,
+    sync: bool,
+}
+
+impl Journal {
+    /// Opens the journal file, creating it if it doesn't exist and appending to it otherwise.
+    pub fn open(path: &str, sync: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file, sync })
+    }
+
+    fn write(&mut self, kind: &str, mut record: JsonValue) {
+        record["ts"] = json!(Utc::now().timestamp_nanos_opt().unwrap());
+        record["type"] = json!(kind);
+        let mut line = record.to_string().into_bytes();
+        line.push(b'\n');
+        let result = self.file.write_all(&line).and_then(|_| {
+            if self.sync {
+                self.file.sync_data()
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(error) = result {
+            error!(?error, "Couldn't write the journal record.");
+        }
+    }
+
+    /// Records the order request sent to the connector.
+    pub fn order_request(&mut self, asset_no: usize, symbol: &str, order: &Order) {
+        self.write(
+            "order_request",
+            json!({ "asset_no": asset_no, "symbol": symbol, "order": order_json(order) }),
+        );
+    }
+
+    /// Records the order response received from the connector, and the fill if it executes the
+    /// order.
+    pub fn order_response(&mut self, asset_no: usize, symbol: &str, order: &Order) {
+        self.write(
+            "order_response",
+            json!({ "asset_no": asset_no, "symbol": symbol, "order": order_json(order) }),
+        );
+        if order.exec_qty > 0.0
+            && (order.status == Status::Filled || order.status == Status::PartiallyFilled)
+        {
+            self.write(
+                "fill",
+                json!({
+                    "asset_no": asset_no,
+                    "symbol": symbol,
+                    "order_id": order.order_id,
+                    "side": format!("{:?}", order.side),
+                    "exec_price": order.exec_price(),
+                    "exec_qty": order.exec_qty,
+                    "leaves_qty": order.leaves_qty,
+                    "maker": order.maker,
+                    "exch_ts": order.exch_timestamp,
+                }),
+            );
+        }
+    }
+
+    /// Flushes the records to the disk.
+    pub fn flush(&mut self) {
+        if let Err(error) = self.file.sync_data() {
+            error!(?error, "Couldn't flush the journal.");
+        }
+    }
+
+    /// Records the error reported by the connector.
+    pub fn error(&mut self, error: &LiveError) {
+        self.write(
+            "error",
+            json!({
+                "kind": format!("{:?}", error.kind),
+                "value": format!("{:?}", error.value()),
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Journal;
+    use crate::types::{ErrorKind, LiveError, OrdType, Order, Side, Status, TimeInForce};
+
+    #[test]
+    fn test_journal() {
+        let path = std::env::temp_dir().join(format!("hftbacktest_journal_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut journal = Journal::open(path.to_str().unwrap(), false).unwrap();
+
+        let mut order = Order::new(
+            1,
+            1000,
+            0.1,
+            2.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        journal.order_request(0, "BTCUSDT", &order);
+        order.status = Status::PartiallyFilled;
+        order.exec_qty = 1.0;
+        order.exec_price_tick = 1000;
+        order.leaves_qty = 1.0;
+        journal.order_response(0, "BTCUSDT", &order);
+        journal.error(&LiveError::new(ErrorKind::OrderError));
+
+        let records: Vec<serde_json::Value> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        fs::remove_file(&path).unwrap();
+
+        let kinds: Vec<_> = records
+            .iter()
+            .map(|r| r["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(kinds, ["order_request", "order_response", "fill", "error"]);
+        assert!(records.iter().all(|r| r["ts"].as_i64().unwrap() > 0));
+        assert_eq!(records[0]["order"]["order_id"], 1);
+        assert_eq!(records[2]["exec_qty"], 1.0);
+        assert_eq!(records[3]["kind"], "OrderError");
+    }
+}
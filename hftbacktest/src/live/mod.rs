@@ -1,16 +1,40 @@
 use std::collections::HashMap;
 
-pub use bot::{BotError, LiveBot, LiveBotBuilder};
+pub use bot::{BotError, LiveBot, LiveBotBuilder, ShutdownResult, WaitStrategy};
+pub use control::ControlCommand;
 pub use recorder::LoggingRecorder;
 
 use crate::{
+    bar::BarBuilder,
     prelude::StateValues,
-    types::{Event, Order, OrderId},
+    trades::TradeBuffer,
+    types::{
+        DepthLevel,
+        DepthUpdate,
+        Event,
+        EventStats,
+        Fill,
+        FundingRate,
+        InstrumentInfo,
+        Liquidation,
+        OptionMetadata,
+        Order,
+        OrderFilter,
+        OrderId,
+    },
 };
 
 mod bot;
+mod control;
 pub mod ipc;
+mod journal;
 mod recorder;
+mod signal;
+mod store;
+mod supervisor;
+
+/// Builds the market depth from the tick size and lot size.
+pub type DepthBuilder<MD> = Box<dyn FnOnce(f64, f64) -> MD + Send>;
 
 /// Provides asset information for internal use.
 pub struct Instrument<MD> {
@@ -19,11 +43,33 @@ pub struct Instrument<MD> {
     tick_size: f64,
     lot_size: f64,
     depth: MD,
-    last_trades: Vec<Event>,
+    last_trades: TradeBuffer,
+    last_depth_updates: Vec<DepthUpdate>,
+    bars: Vec<BarBuilder>,
+    liquidations: Vec<Liquidation>,
+    liquidations_capacity: usize,
+    last_fills: Vec<Fill>,
+    last_liquidation_prints: Vec<Event>,
+    open_interest: Option<f64>,
+    mark_price: Option<f64>,
+    index_price: Option<f64>,
+    funding_rate: Option<FundingRate>,
     orders: HashMap<OrderId, Order>,
+    external_orders: HashMap<String, Order>,
     last_feed_latency: Option<(i64, i64)>,
     last_order_latency: Option<(i64, i64, i64)>,
     state: StateValues,
+    num_messages: u64,
+    num_fills: u64,
+    event_stats: EventStats,
+    stale_threshold: Option<i64>,
+    last_feed_time: i64,
+    stale: bool,
+    option: Option<OptionMetadata>,
+    info: Option<InstrumentInfo>,
+    auto_fill: Option<DepthBuilder<MD>>,
+    order_filter: Option<OrderFilter>,
+    depth_level: DepthLevel,
 }
 
 impl<MD> Instrument<MD> {
@@ -49,11 +95,149 @@ impl<MD> Instrument<MD> {
             tick_size,
             lot_size,
             depth,
-            last_trades: Vec::with_capacity(last_trades_capacity),
+            last_trades: TradeBuffer::new(last_trades_capacity),
+            last_depth_updates: Vec::new(),
+            bars: Vec::new(),
+            liquidations: Vec::new(),
+            liquidations_capacity: 64,
+            last_fills: Vec::new(),
+            last_liquidation_prints: Vec::new(),
+            open_interest: None,
+            mark_price: None,
+            index_price: None,
+            funding_rate: None,
             orders: Default::default(),
+            external_orders: Default::default(),
             last_feed_latency: None,
             last_order_latency: None,
             state: Default::default(),
+            num_messages: 0,
+            num_fills: 0,
+            event_stats: Default::default(),
+            stale_threshold: None,
+            last_feed_time: 0,
+            stale: false,
+            option: None,
+            info: None,
+            auto_fill: None,
+            order_filter: None,
+            depth_level: DepthLevel::Full,
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last depth updates. The default value
+    /// is `0`, indicating that no last depth updates are stored.
+    pub fn last_depth_updates_capacity(self, capacity: usize) -> Self {
+        Self {
+            last_depth_updates: Vec::with_capacity(capacity),
+            ..self
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last fills. The default value is `0`,
+    /// indicating that no last fills are stored.
+    pub fn last_fills_capacity(self, capacity: usize) -> Self {
+        Self {
+            last_fills: Vec::with_capacity(capacity),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of the forced liquidations of the position that are kept, reported by
+    /// the connector on the exchange's liquidation or auto-deleveraging. Once full, the oldest is
+    /// dropped. The default value is `64`.
+    pub fn liquidations_capacity(self, capacity: usize) -> Self {
+        Self {
+            liquidations_capacity: capacity,
+            ..self
+        }
+    }
+
+    /// Sets the initial capacity of the vector storing the last liquidation prints. The default
+    /// value is `0`, indicating that no last liquidation prints are stored.
+    pub fn last_liquidation_prints_capacity(self, capacity: usize) -> Self {
+        Self {
+            last_liquidation_prints: Vec::with_capacity(capacity),
+            ..self
+        }
+    }
+
+    /// Replaces the buffer of the last trades, to bound it by count or by time window, or to
+    /// aggregate the prints of a burst. This overrides the `last_trades_capacity` given to
+    /// [`Instrument::new`].
+    pub fn last_trades(self, buffer: TradeBuffer) -> Self {
+        Self {
+            last_trades: buffer,
+            ..self
+        }
+    }
+
+    /// Aggregates the market trades into bars of the interval, keeping the latest bars up to the
+    /// capacity. This can be called multiple times to build the bars of several intervals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval isn't positive.
+    pub fn bars(mut self, interval: i64, capacity: usize) -> Self {
+        self.bars.push(BarBuilder::new(interval, capacity));
+        self
+    }
+
+    /// Sets the threshold in nanoseconds of the time since the last market data event, beyond
+    /// which the market data is considered stale. When it becomes stale, a
+    /// [`LiveError`](crate::types::LiveError) of
+    /// [`ErrorKind::StaleFeed`](crate::types::ErrorKind::StaleFeed) is passed to the error
+    /// handler, so that the strategy can stop quoting on a silently dead market data stream. The
+    /// staleness is checked each time the bot elapses. By default, the staleness isn't checked.
+    pub fn stale_threshold(self, threshold: i64) -> Self {
+        Self {
+            stale_threshold: Some(threshold),
+            ..self
+        }
+    }
+
+    /// Queries the trading rules of the instrument from the connector when the bot is built, and
+    /// replaces the tick size and lot size given to [`Instrument::new`] with the ones from the
+    /// exchange. The market depth is rebuilt by `depth` with them. Unless [`Instrument::order_filter`]
+    /// is set, the minimum notional and the lot size are also used as the order filter. Building
+    /// the bot fails if the connector doesn't provide the instrument information.
+    pub fn auto_fill<F>(self, depth: F) -> Self
+    where
+        F: FnOnce(f64, f64) -> MD + Send + 'static,
+    {
+        Self {
+            auto_fill: Some(Box::new(depth)),
+            ..self
+        }
+    }
+
+    /// Sets the [`OrderFilter`] that new orders are checked against before they're sent, which
+    /// fails the submission with
+    /// [`BotError::FilterViolation`](crate::live::BotError::FilterViolation). By default, new
+    /// orders aren't checked.
+    pub fn order_filter(self, filter: OrderFilter) -> Self {
+        Self {
+            order_filter: Some(filter),
+            ..self
+        }
+    }
+
+    /// Sets the granularity of the market depth that the connector subscribes to, which reduces
+    /// the bandwidth and the processing if the strategy doesn't need the full market depth. The
+    /// connectors that don't support it provide the full market depth. The default value is
+    /// [`DepthLevel::Full`].
+    pub fn depth_level(self, level: DepthLevel) -> Self {
+        Self {
+            depth_level: level,
+            ..self
+        }
+    }
+
+    /// Sets the contract specification if the asset is an option.
+    pub fn option(self, option: OptionMetadata) -> Self {
+        Self {
+            option: Some(option),
+            ..self
         }
     }
 }
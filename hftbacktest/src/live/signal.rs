@@ -0,0 +1,94 @@
+use std::{
+    io,
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+/// The number of the last termination signal received, or zero if none is received.
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        io,
+        mem,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use libc::c_int;
+
+    use super::SIGNAL;
+
+    /// The previous handlers of `SIGINT` and `SIGTERM`, which are chained so that the other
+    /// facilities handling the signals, such as the IPC, keep working.
+    static PREV_SIGINT: AtomicUsize = AtomicUsize::new(0);
+    static PREV_SIGTERM: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn handle(signum: c_int) {
+        SIGNAL.store(signum, Ordering::SeqCst);
+        let prev = match signum {
+            libc::SIGINT => PREV_SIGINT.load(Ordering::SeqCst),
+            libc::SIGTERM => PREV_SIGTERM.load(Ordering::SeqCst),
+            _ => 0,
+        };
+        if prev != 0 {
+            let prev: extern "C" fn(c_int) = unsafe { mem::transmute(prev) };
+            prev(signum);
+        }
+    }
+
+    fn install_one(signum: c_int, prev_handler: &AtomicUsize) -> io::Result<()> {
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handle as extern "C" fn(c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            let mut prev: libc::sigaction = mem::zeroed();
+            if libc::sigaction(signum, &action, &mut prev) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // Only the plain handlers are chained. The default action would terminate the process
+            // before the bot shuts down.
+            let siginfo = prev.sa_flags & libc::SA_SIGINFO != 0;
+            let prev = prev.sa_sigaction;
+            if !siginfo
+                && prev != libc::SIG_DFL
+                && prev != libc::SIG_IGN
+                && prev != handle as extern "C" fn(c_int) as libc::sighandler_t
+            {
+                prev_handler.store(prev, Ordering::SeqCst);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn install() -> io::Result<()> {
+        install_one(libc::SIGINT, &PREV_SIGINT)?;
+        install_one(libc::SIGTERM, &PREV_SIGTERM)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+
+    pub fn install() -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Signal handling is only supported on Unix",
+        ))
+    }
+}
+
+/// Installs the handler of `SIGINT` and `SIGTERM`, which records the received signal to be
+/// checked by [`received`].
+pub(crate) fn install() -> io::Result<()> {
+    imp::install()
+}
+
+/// Returns the number of the termination signal if one has been received.
+pub(crate) fn received() -> Option<i32> {
+    match SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signum => Some(signum),
+    }
+}
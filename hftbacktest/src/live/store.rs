@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    fs,
+    fs::File,
+    io,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Sender},
+    thread,
+};
+
+use bincode::{config, Decode, Encode};
+use tracing::error;
+
+use crate::types::Order;
+
+/// The state of an instrument saved by the [`StateStore`].
+#[derive(Default, Encode, Decode)]
+pub(crate) struct SavedInstrument {
+    pub position: f64,
+    pub orders: Vec<Order>,
+}
+
+/// The state of the bot saved by the [`StateStore`], from which the bot is restored when it's
+/// restarted.
+#[derive(Default, Encode, Decode)]
+pub(crate) struct SavedState {
+    /// The open orders and position, keyed by the symbol.
+    pub instruments: HashMap<String, SavedInstrument>,
+    /// The key-values saved by the strategy.
+    pub values: HashMap<String, f64>,
+}
+
+enum Command {
+    Save(SavedState),
+    Flush(Sender<()>),
+}
+
+fn write(path: &Path, tmp_path: &Path, state: &SavedState, sync: bool) -> io::Result<()> {
+    let buf = bincode::encode_to_vec(state, config::standard())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    let mut file = File::create(tmp_path)?;
+    file.write_all(&buf)?;
+    if sync {
+        file.sync_data()?;
+    }
+    fs::rename(tmp_path, path)
+}
+
+/// A flat file that holds the latest state of the bot.
+///
+/// The state is written to a temporary file and then renamed to the file, so a crash while saving
+/// leaves the previously saved state intact. The state is written by a background thread so that
+/// saving doesn't block the trading loop; if the states are saved faster than they are written,
+/// only the latest one is written. If `sync` is enabled, the file is also flushed to the disk,
+/// which survives a crash of the operating system.
+pub(crate) struct StateStore {
+    path: PathBuf,
+    tx: Sender<Command>,
+}
+
+impl StateStore {
+    pub fn new(path: &str, sync: bool) -> Self {
+        let path = PathBuf::from(path);
+        let mut tmp_path = path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let (tx, rx) = channel();
+        let writer_path = path.clone();
+        thread::spawn(move || {
+            while let Ok(command) = rx.recv() {
+                let (mut state, mut flush) = match command {
+                    Command::Save(state) => (state, None),
+                    Command::Flush(reply) => {
+                        let _ = reply.send(());
+                        continue;
+                    }
+                };
+                // Skips the states superseded by the ones saved while the previous was written.
+                while flush.is_none() {
+                    match rx.try_recv() {
+                        Ok(Command::Save(next)) => state = next,
+                        Ok(Command::Flush(reply)) => flush = Some(reply),
+                        Err(_) => break,
+                    }
+                }
+                if let Err(error) = write(&writer_path, &tmp_path, &state, sync) {
+                    error!(?error, path = ?writer_path, "Couldn't save the state.");
+                }
+                if let Some(reply) = flush {
+                    let _ = reply.send(());
+                }
+            }
+        });
+
+        Self { path, tx }
+    }
+
+    /// Loads the saved state, or returns `None` if nothing has been saved yet.
+    pub fn load(&self) -> io::Result<Option<SavedState>> {
+        let buf = match fs::read(&self.path) {
+            Ok(buf) => buf,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let (state, _) = bincode::decode_from_slice(&buf, config::standard())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Ok(Some(state))
+    }
+
+    /// Queues the state to be saved, replacing the previously saved one.
+    pub fn save(&self, state: SavedState) {
+        let _ = self.tx.send(Command::Save(state));
+    }
+
+    /// Blocks until the queued states are written.
+    pub fn flush(&self) {
+        let (reply_tx, reply_rx) = channel();
+        if self.tx.send(Command::Flush(reply_tx)).is_ok() {
+            let _ = reply_rx.recv();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{SavedInstrument, SavedState, StateStore};
+    use crate::types::{OrdType, Order, Side, Status, TimeInForce};
+
+    #[test]
+    fn test_state_store() {
+        let path = std::env::temp_dir().join(format!("hftbacktest_state_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let store = StateStore::new(path.to_str().unwrap(), false);
+        assert!(store.load().unwrap().is_none());
+
+        let mut order = Order::new(
+            7,
+            1000,
+            0.1,
+            2.0,
+            Side::Sell,
+            OrdType::Limit,
+            TimeInForce::GTX,
+        );
+        order.status = Status::PartiallyFilled;
+        order.leaves_qty = 1.5;
+        let mut state = SavedState::default();
+        state.instruments.insert(
+            "BTCUSDT".to_string(),
+            SavedInstrument {
+                position: -0.5,
+                orders: vec![order],
+            },
+        );
+        state.values.insert("skew".to_string(), 0.25);
+        store.save(state);
+        store.flush();
+
+        let loaded = store.load().unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let instrument = loaded.instruments.get("BTCUSDT").unwrap();
+        assert_eq!(instrument.position, -0.5);
+        assert_eq!(instrument.orders.len(), 1);
+        assert_eq!(instrument.orders[0].order_id, 7);
+        assert_eq!(instrument.orders[0].price_tick, 1000);
+        assert_eq!(instrument.orders[0].leaves_qty, 1.5);
+        assert_eq!(instrument.orders[0].status, Status::PartiallyFilled);
+        assert_eq!(loaded.values.get("skew"), Some(&0.25));
+    }
+}
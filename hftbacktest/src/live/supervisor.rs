@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    process::{Child, Command},
+};
+
+use tracing::{error, info, warn};
+
+/// The supervision state of a connector.
+struct ConnectorState {
+    inst_nos: Vec<usize>,
+    last_heartbeat: i64,
+    alive: bool,
+    restart_command: Option<Vec<String>>,
+    child: Option<Child>,
+}
+
+impl ConnectorState {
+    fn restart(&mut self, connector_name: &str) {
+        let Some(command) = self.restart_command.as_ref().filter(|c| !c.is_empty()) else {
+            return;
+        };
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        info!(%connector_name, ?command, "Relaunches the connector.");
+        match Command::new(&command[0]).args(&command[1..]).spawn() {
+            Ok(child) => self.child = Some(child),
+            Err(error) => {
+                error!(?error, %connector_name, "Couldn't relaunch the connector.");
+            }
+        }
+    }
+}
+
+/// Detects the dead or unresponsive connectors by the heartbeats they send, and optionally
+/// relaunches them.
+pub(crate) struct Supervisor {
+    timeout: i64,
+    connectors: HashMap<String, ConnectorState>,
+}
+
+impl Supervisor {
+    /// Constructs a `Supervisor`.
+    ///
+    /// * `timeout` - The time in nanoseconds without a heartbeat after which a connector is
+    ///   considered dead.
+    /// * `connector_names` - The connector name of each instrument.
+    /// * `restart_commands` - The command by which each connector is relaunched, if any.
+    /// * `timestamp` - The current timestamp, from which the first heartbeat is awaited.
+    pub fn new<'a>(
+        timeout: i64,
+        connector_names: impl Iterator<Item = &'a str>,
+        mut restart_commands: HashMap<String, Vec<String>>,
+        timestamp: i64,
+    ) -> Self {
+        let mut connectors: HashMap<String, ConnectorState> = HashMap::new();
+        for (inst_no, connector_name) in connector_names.enumerate() {
+            connectors
+                .entry(connector_name.to_string())
+                .or_insert_with(|| ConnectorState {
+                    inst_nos: Vec::new(),
+                    last_heartbeat: timestamp,
+                    alive: true,
+                    restart_command: restart_commands.remove(connector_name),
+                    child: None,
+                })
+                .inst_nos
+                .push(inst_no);
+        }
+        Self {
+            timeout,
+            connectors,
+        }
+    }
+
+    /// Records the heartbeat from the connector of the instrument. If the connector was
+    /// considered dead, returns the instruments traded through it, which need to be registered
+    /// again since the connector may have lost its state.
+    pub fn on_heartbeat(&mut self, inst_no: usize, timestamp: i64) -> Option<Vec<usize>> {
+        let (connector_name, state) = self
+            .connectors
+            .iter_mut()
+            .find(|(_, state)| state.inst_nos.contains(&inst_no))?;
+        state.last_heartbeat = timestamp;
+        if state.alive {
+            None
+        } else {
+            info!(%connector_name, "The connector is alive again.");
+            state.alive = true;
+            Some(state.inst_nos.clone())
+        }
+    }
+
+    /// Checks the heartbeats and returns the names of the connectors that are newly considered
+    /// dead. A dead connector with a restart command is relaunched, and relaunched again each
+    /// time the timeout elapses until it sends a heartbeat.
+    pub fn check(&mut self, timestamp: i64) -> Vec<String> {
+        let mut dead = Vec::new();
+        for (connector_name, state) in self.connectors.iter_mut() {
+            if timestamp - state.last_heartbeat <= self.timeout {
+                continue;
+            }
+            if state.alive {
+                warn!(%connector_name, "The connector is unresponsive.");
+                state.alive = false;
+                dead.push(connector_name.clone());
+            }
+            state.restart(connector_name);
+            state.last_heartbeat = timestamp;
+        }
+        dead
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        for state in self.connectors.values_mut() {
+            if let Some(mut child) = state.child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::Supervisor;
+
+    #[test]
+    fn test_supervisor() {
+        let mut supervisor =
+            Supervisor::new(100, ["a", "b", "a"].into_iter(), HashMap::new(), 1000);
+
+        assert_eq!(supervisor.on_heartbeat(0, 1050), None);
+        assert!(supervisor.check(1100).is_empty());
+
+        // `b` has sent no heartbeat since the start.
+        assert_eq!(supervisor.check(1101), vec!["b".to_string()]);
+        // It's reported only once.
+        assert!(supervisor.check(1150).is_empty());
+
+        let mut dead = supervisor.check(1300);
+        dead.sort();
+        assert_eq!(dead, vec!["a".to_string()]);
+
+        let mut inst_nos = supervisor.on_heartbeat(2, 1310).unwrap();
+        inst_nos.sort();
+        assert_eq!(inst_nos, vec![0, 2]);
+        assert_eq!(supervisor.on_heartbeat(0, 1320), None);
+        assert_eq!(supervisor.on_heartbeat(1, 1330), Some(vec![1]));
+    }
+}
@@ -0,0 +1,575 @@
+//! Pre-trade risk checks, which are applied to every new order before it's submitted, in both
+//! backtesting and live trading.
+use std::collections::{HashMap, VecDeque};
+
+use thiserror::Error;
+
+use crate::{
+    depth::{MarketDepth, INVALID_MAX, INVALID_MIN},
+    types::{Order, OrderId, Side, Status},
+};
+
+/// A new order to be checked by a [`RiskGuard`], along with the state of the asset at the time of
+/// the submission.
+#[derive(Clone, Debug)]
+pub struct NewOrder {
+    /// The current timestamp in nanoseconds.
+    pub timestamp: i64,
+    pub asset_no: usize,
+    pub side: Side,
+    pub price: f64,
+    pub qty: f64,
+    /// The current position of the asset.
+    pub position: f64,
+    /// The current mid price, which is `NaN` if the market depth is empty.
+    pub mid: f64,
+    /// The number of the open orders of the asset, excluding the new order.
+    pub open_orders: usize,
+    /// The leaves quantity of the open buy orders, including the ones not yet acknowledged.
+    pub open_buy_qty: f64,
+    /// The leaves quantity of the open sell orders, including the ones not yet acknowledged.
+    pub open_sell_qty: f64,
+}
+
+impl NewOrder {
+    /// Returns the position if the order is fully filled.
+    pub fn position_after(&self) -> f64 {
+        match self.side {
+            Side::Buy => self.position + self.qty,
+            Side::Sell => self.position - self.qty,
+            Side::None | Side::Unsupported => self.position,
+        }
+    }
+
+    /// Returns the position if the order and all the open orders on the same side are fully
+    /// filled, which is the worst case for the position limits.
+    pub fn worst_position_after(&self) -> f64 {
+        match self.side {
+            Side::Buy => self.position + self.open_buy_qty + self.qty,
+            Side::Sell => self.position - self.open_sell_qty - self.qty,
+            Side::None | Side::Unsupported => self.position,
+        }
+    }
+}
+
+/// Returns the leaves quantities of the open buy orders and sell orders, including the new orders
+/// not yet acknowledged, since they can all be filled.
+pub(crate) fn open_qty<'a>(orders: impl Iterator<Item = &'a Order>) -> (f64, f64) {
+    orders
+        .filter(|order| {
+            order.active() || (order.status == Status::None && order.req == Status::New)
+        })
+        .fold((0.0, 0.0), |(buy, sell), order| match order.side {
+            Side::Buy => (buy + order.leaves_qty, sell),
+            Side::Sell => (buy, sell + order.leaves_qty),
+            Side::None | Side::Unsupported => (buy, sell),
+        })
+}
+
+/// Describes which risk limit a new order violates.
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum RiskViolation {
+    #[error("the position {position} would exceed the maximum position {max_position}")]
+    MaxPosition { position: f64, max_position: f64 },
+    #[error("the position value {notional} would exceed the maximum notional {max_notional}")]
+    MaxNotional { notional: f64, max_notional: f64 },
+    #[error("the order quantity {qty} exceeds the maximum order quantity {max_order_qty}")]
+    MaxOrderQty { qty: f64, max_order_qty: f64 },
+    #[error("more than {max_orders} orders are submitted within {interval}ns")]
+    OrderRate { max_orders: usize, interval: i64 },
+    #[error("the order price {price} is outside the collar of {rate} from the mid price {mid}")]
+    PriceCollar { price: f64, mid: f64, rate: f64 },
+    #[error(
+        "the position {position} exceeds the position limit, so only reducing orders are allowed"
+    )]
+    ReduceOnly { position: f64 },
+    #[error("{0}")]
+    Custom(String),
+}
+
+/// Provides a pre-trade risk check, which is applied to every new order before it's submitted.
+/// If the check fails, the order isn't submitted and the violation is returned as an error.
+pub trait RiskGuard {
+    /// Checks the new order, returning the violated limit if it must not be submitted. This is
+    /// called only once for each new order, so a stateful guard can count the passed orders.
+    fn check(&mut self, order: &NewOrder) -> Result<(), RiskViolation>;
+}
+
+/// The counters of the risk checks for an asset.
+#[repr(C)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct RiskStats {
+    /// The number of the orders checked.
+    pub checked: u64,
+    /// The number of the orders rejected by each limit.
+    pub max_position: u64,
+    pub max_notional: u64,
+    pub max_order_qty: u64,
+    pub order_rate: u64,
+    pub price_collar: u64,
+    pub reduce_only: u64,
+    pub custom: u64,
+}
+
+impl RiskStats {
+    /// Returns the number of the orders rejected.
+    pub fn rejected(&self) -> u64 {
+        self.max_position
+            + self.max_notional
+            + self.max_order_qty
+            + self.order_rate
+            + self.price_collar
+            + self.reduce_only
+            + self.custom
+    }
+
+    fn record(&mut self, result: &Result<(), RiskViolation>) {
+        self.checked += 1;
+        match result {
+            Ok(()) => {}
+            Err(RiskViolation::MaxPosition { .. }) => self.max_position += 1,
+            Err(RiskViolation::MaxNotional { .. }) => self.max_notional += 1,
+            Err(RiskViolation::MaxOrderQty { .. }) => self.max_order_qty += 1,
+            Err(RiskViolation::OrderRate { .. }) => self.order_rate += 1,
+            Err(RiskViolation::PriceCollar { .. }) => self.price_collar += 1,
+            Err(RiskViolation::ReduceOnly { .. }) => self.reduce_only += 1,
+            Err(RiskViolation::Custom(_)) => self.custom += 1,
+        }
+    }
+}
+
+/// A [`RiskGuard`] that applies the common limits to every asset.
+///
+/// The position and notional limits only reject an order that would increase the absolute
+/// position beyond the limit, so that an order reducing the position is always allowed. They're
+/// checked against the worst case, in which the open orders on the same side are filled as well.
+///
+/// ```
+/// use hftbacktest::risk::RiskLimits;
+///
+/// let limits = RiskLimits::new()
+///     .max_position(10.0)
+///     .max_notional(100_000.0)
+///     .max_order_qty(1.0)
+///     .max_orders_per_interval(50, 1_000_000_000)
+///     .price_collar(0.02);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RiskLimits {
+    max_position: Option<f64>,
+    max_notional: Option<f64>,
+    max_order_qty: Option<f64>,
+    order_rate: Option<(usize, i64)>,
+    price_collar: Option<f64>,
+    submitted: HashMap<usize, VecDeque<i64>>,
+}
+
+impl RiskLimits {
+    /// Constructs `RiskLimits` without any limit.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the maximum absolute position.
+    pub fn max_position(self, max_position: f64) -> Self {
+        Self {
+            max_position: Some(max_position),
+            ..self
+        }
+    }
+
+    /// Sets the maximum absolute position value, valued at the order price.
+    pub fn max_notional(self, max_notional: f64) -> Self {
+        Self {
+            max_notional: Some(max_notional),
+            ..self
+        }
+    }
+
+    /// Sets the maximum order quantity.
+    pub fn max_order_qty(self, max_order_qty: f64) -> Self {
+        Self {
+            max_order_qty: Some(max_order_qty),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of the orders submitted for an asset within the interval.
+    ///
+    /// * `interval` - The interval in nanoseconds.
+    pub fn max_orders_per_interval(self, max_orders: usize, interval: i64) -> Self {
+        Self {
+            order_rate: Some((max_orders, interval)),
+            ..self
+        }
+    }
+
+    /// Sets the maximum deviation of the order price from the mid price, as a fraction of the mid
+    /// price, beyond which an aggressive order is rejected. The collar isn't checked while the
+    /// market depth is empty.
+    pub fn price_collar(self, rate: f64) -> Self {
+        Self {
+            price_collar: Some(rate),
+            ..self
+        }
+    }
+}
+
+impl RiskGuard for RiskLimits {
+    fn check(&mut self, order: &NewOrder) -> Result<(), RiskViolation> {
+        if let Some(max_order_qty) = self.max_order_qty {
+            if order.qty > max_order_qty {
+                return Err(RiskViolation::MaxOrderQty {
+                    qty: order.qty,
+                    max_order_qty,
+                });
+            }
+        }
+        let position = order.worst_position_after();
+        let increasing = position.abs() > order.position.abs();
+        if let Some(max_position) = self.max_position {
+            if increasing && position.abs() > max_position {
+                return Err(RiskViolation::MaxPosition {
+                    position,
+                    max_position,
+                });
+            }
+        }
+        if let Some(max_notional) = self.max_notional {
+            let notional = position.abs() * order.price;
+            if increasing && notional > max_notional {
+                return Err(RiskViolation::MaxNotional {
+                    notional,
+                    max_notional,
+                });
+            }
+        }
+        if let Some(rate) = self.price_collar {
+            let outside = match order.side {
+                Side::Buy => order.price > order.mid * (1.0 + rate),
+                Side::Sell => order.price < order.mid * (1.0 - rate),
+                Side::None | Side::Unsupported => false,
+            };
+            if outside {
+                return Err(RiskViolation::PriceCollar {
+                    price: order.price,
+                    mid: order.mid,
+                    rate,
+                });
+            }
+        }
+        if let Some((max_orders, interval)) = self.order_rate {
+            let submitted = self.submitted.entry(order.asset_no).or_default();
+            while submitted
+                .front()
+                .is_some_and(|&timestamp| timestamp <= order.timestamp - interval)
+            {
+                submitted.pop_front();
+            }
+            if submitted.len() >= max_orders {
+                return Err(RiskViolation::OrderRate {
+                    max_orders,
+                    interval,
+                });
+            }
+            submitted.push_back(order.timestamp);
+        }
+        Ok(())
+    }
+}
+
+/// A position limit of an asset, which can be changed while the bot is running.
+///
+/// Once the absolute position exceeds the limit, the asset switches into the reduce-only mode, in
+/// which a new order that would increase the absolute position is rejected with
+/// [`RiskViolation::ReduceOnly`], until the absolute position is back within the limit. If
+/// flattening is enabled, the bot also submits an IOC order at the best opposite price to close
+/// the whole position, which is resubmitted while the limit is breached and no flattening order is
+/// open. The limit is checked each time the bot elapses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionLimit {
+    max_position: f64,
+    flatten: bool,
+}
+
+impl PositionLimit {
+    /// Constructs a `PositionLimit` with the maximum absolute position, without flattening.
+    pub fn new(max_position: f64) -> Self {
+        Self {
+            max_position,
+            flatten: false,
+        }
+    }
+
+    /// Sets whether the position is flattened once the limit is breached.
+    pub fn flatten(self, flatten: bool) -> Self {
+        Self { flatten, ..self }
+    }
+
+    /// Returns the maximum absolute position.
+    pub fn max_position(&self) -> f64 {
+        self.max_position
+    }
+}
+
+#[derive(Default, Clone)]
+struct AssetRisk {
+    stats: RiskStats,
+    limit: Option<PositionLimit>,
+    reduce_only: bool,
+    flatten_order: Option<OrderId>,
+}
+
+/// Holds the [`RiskGuard`] of a bot along with the [`PositionLimit`] and the [`RiskStats`] of each
+/// asset.
+#[derive(Default)]
+pub(crate) struct RiskControl {
+    guard: Option<Box<dyn RiskGuard + Send>>,
+    assets: Vec<AssetRisk>,
+}
+
+impl RiskControl {
+    pub fn new(guard: Option<Box<dyn RiskGuard + Send>>) -> Self {
+        Self {
+            guard,
+            assets: Vec::new(),
+        }
+    }
+
+    fn asset_mut(&mut self, asset_no: usize) -> &mut AssetRisk {
+        if self.assets.len() <= asset_no {
+            self.assets.resize(asset_no + 1, AssetRisk::default());
+        }
+        &mut self.assets[asset_no]
+    }
+
+    /// Checks the new order against the reduce-only mode and the guard, if either applies.
+    pub fn check(&mut self, order: &NewOrder) -> Result<(), RiskViolation> {
+        let reduce_only = self.is_reduce_only(order.asset_no);
+        if self.guard.is_none() && !reduce_only {
+            return Ok(());
+        }
+        let position = order.position_after();
+        let result = if reduce_only && position.abs() > order.position.abs() {
+            Err(RiskViolation::ReduceOnly {
+                position: order.position,
+            })
+        } else if let Some(guard) = self.guard.as_mut() {
+            guard.check(order)
+        } else {
+            Ok(())
+        };
+        self.asset_mut(order.asset_no).stats.record(&result);
+        result
+    }
+
+    pub fn stats(&self, asset_no: usize) -> RiskStats {
+        self.assets
+            .get(asset_no)
+            .map(|asset| asset.stats)
+            .unwrap_or_default()
+    }
+
+    pub fn set_position_limit(&mut self, asset_no: usize, limit: Option<PositionLimit>) {
+        let asset = self.asset_mut(asset_no);
+        asset.limit = limit;
+        if limit.is_none() {
+            asset.reduce_only = false;
+        }
+    }
+
+    pub fn is_reduce_only(&self, asset_no: usize) -> bool {
+        self.assets
+            .get(asset_no)
+            .map(|asset| asset.reduce_only)
+            .unwrap_or(false)
+    }
+
+    /// Updates the reduce-only mode of the asset by the current position. Returns `true` if a
+    /// flattening order needs to be submitted, which is when the limit with flattening is breached
+    /// and the previous flattening order, checked by `is_open`, isn't open.
+    pub fn update_position<F>(&mut self, asset_no: usize, position: f64, is_open: F) -> bool
+    where
+        F: Fn(OrderId) -> bool,
+    {
+        let Some(asset) = self.assets.get_mut(asset_no) else {
+            return false;
+        };
+        let Some(limit) = asset.limit else {
+            return false;
+        };
+        asset.reduce_only = position.abs() > limit.max_position;
+        if !asset.reduce_only || !limit.flatten {
+            return false;
+        }
+        !asset.flatten_order.is_some_and(is_open)
+    }
+
+    /// Records the flattening order submitted for the asset.
+    pub fn set_flatten_order(&mut self, asset_no: usize, order_id: OrderId) {
+        self.asset_mut(asset_no).flatten_order = Some(order_id);
+    }
+}
+
+/// Returns the side, price and quantity of the IOC order that closes the position at the best
+/// opposite price, or `None` if there is no position or the opposite side of the book is empty.
+pub(crate) fn flatten_order<MD: MarketDepth>(
+    position: f64,
+    depth: &MD,
+) -> Option<(Side, f64, f64)> {
+    if position > 0.0 && depth.best_bid_tick() != INVALID_MIN {
+        Some((Side::Sell, depth.best_bid(), position))
+    } else if position < 0.0 && depth.best_ask_tick() != INVALID_MAX {
+        Some((Side::Buy, depth.best_ask(), -position))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        open_qty,
+        NewOrder,
+        PositionLimit,
+        RiskControl,
+        RiskGuard,
+        RiskLimits,
+        RiskViolation,
+    };
+    use crate::types::{OrdType, Order, Side, Status, TimeInForce};
+
+    fn order(timestamp: i64, side: Side, price: f64, qty: f64, position: f64) -> NewOrder {
+        NewOrder {
+            timestamp,
+            asset_no: 0,
+            side,
+            price,
+            qty,
+            position,
+            mid: 100.0,
+            open_orders: 0,
+            open_buy_qty: 0.0,
+            open_sell_qty: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_risk_limits() {
+        let mut limits = RiskLimits::new()
+            .max_position(5.0)
+            .max_notional(450.0)
+            .max_order_qty(2.0)
+            .max_orders_per_interval(2, 100)
+            .price_collar(0.01);
+
+        assert!(matches!(
+            limits.check(&order(0, Side::Buy, 100.0, 3.0, 0.0)),
+            Err(RiskViolation::MaxOrderQty { .. })
+        ));
+        assert!(matches!(
+            limits.check(&order(0, Side::Buy, 100.0, 2.0, 4.0)),
+            Err(RiskViolation::MaxPosition { .. })
+        ));
+        // Reducing the position is allowed even beyond the limit.
+        assert!(limits.check(&order(0, Side::Sell, 100.0, 1.0, 7.0)).is_ok());
+        assert!(matches!(
+            limits.check(&order(0, Side::Buy, 100.0, 1.0, 4.0)),
+            Err(RiskViolation::MaxNotional { .. })
+        ));
+        assert!(matches!(
+            limits.check(&order(0, Side::Buy, 102.0, 1.0, 0.0)),
+            Err(RiskViolation::PriceCollar { .. })
+        ));
+        assert!(limits.check(&order(50, Side::Buy, 100.0, 1.0, 0.0)).is_ok());
+        assert!(matches!(
+            limits.check(&order(60, Side::Buy, 100.0, 1.0, 0.0)),
+            Err(RiskViolation::OrderRate { .. })
+        ));
+        assert!(limits
+            .check(&order(150, Side::Buy, 100.0, 1.0, 0.0))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_risk_limits_with_open_orders() {
+        let mut limits = RiskLimits::new().max_position(5.0);
+
+        // The open buy orders would take the position beyond the limit if they're all filled.
+        let mut new_order = order(0, Side::Buy, 100.0, 1.0, 2.0);
+        new_order.open_buy_qty = 3.0;
+        assert!(matches!(
+            limits.check(&new_order),
+            Err(RiskViolation::MaxPosition { position, .. }) if position == 6.0
+        ));
+
+        // The open orders on the other side don't count.
+        new_order.open_buy_qty = 2.0;
+        new_order.open_sell_qty = 10.0;
+        assert!(limits.check(&new_order).is_ok());
+
+        let mut new_order = order(0, Side::Sell, 100.0, 1.0, 2.0);
+        new_order.open_sell_qty = 7.0;
+        assert!(matches!(
+            limits.check(&new_order),
+            Err(RiskViolation::MaxPosition { position, .. }) if position == -6.0
+        ));
+    }
+
+    #[test]
+    fn test_open_qty() {
+        let order = |order_id, side, qty, status| {
+            let mut order = Order::new(
+                order_id,
+                1000,
+                0.1,
+                qty,
+                side,
+                OrdType::Limit,
+                TimeInForce::GTC,
+            );
+            order.status = status;
+            order
+        };
+        let mut partially_filled = order(1, Side::Buy, 2.0, Status::PartiallyFilled);
+        partially_filled.leaves_qty = 1.5;
+        // A new order not yet acknowledged.
+        let mut pending = order(2, Side::Buy, 1.0, Status::None);
+        pending.req = Status::New;
+        let orders = [
+            partially_filled,
+            pending,
+            order(3, Side::Sell, 3.0, Status::New),
+            order(4, Side::Sell, 3.0, Status::Canceled),
+        ];
+
+        assert_eq!(open_qty(orders.iter()), (2.5, 3.0));
+    }
+
+    #[test]
+    fn test_position_limit() {
+        let mut risk = RiskControl::new(None);
+        risk.set_position_limit(0, Some(PositionLimit::new(5.0).flatten(true)));
+
+        assert!(!risk.update_position(0, 5.0, |_| false));
+        assert!(!risk.is_reduce_only(0));
+        assert!(risk.check(&order(0, Side::Buy, 100.0, 1.0, 5.0)).is_ok());
+
+        assert!(risk.update_position(0, 6.0, |_| false));
+        assert!(risk.is_reduce_only(0));
+        assert!(matches!(
+            risk.check(&order(0, Side::Buy, 100.0, 1.0, 6.0)),
+            Err(RiskViolation::ReduceOnly { .. })
+        ));
+        assert!(risk.check(&order(0, Side::Sell, 100.0, 6.0, 6.0)).is_ok());
+        assert_eq!(risk.stats(0).reduce_only, 1);
+
+        // The flattening order isn't resubmitted while it's open.
+        risk.set_flatten_order(0, 1);
+        assert!(!risk.update_position(0, 6.0, |order_id| order_id == 1));
+        assert!(risk.update_position(0, 6.0, |_| false));
+
+        assert!(!risk.update_position(0, 0.0, |_| false));
+        assert!(!risk.is_reduce_only(0));
+    }
+}
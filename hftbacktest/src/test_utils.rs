@@ -0,0 +1,53 @@
+#[cfg(feature = "backtest")]
+use crate::backtest::data::{Data, DataSource};
+#[cfg(feature = "backtest")]
+use crate::types::{DEPTH_EVENT, EXCH_EVENT, LOCAL_EVENT};
+use crate::types::{Event, OrdType, Order, Side, Status, TimeInForce, LOCAL_TRADE_EVENT};
+
+/// Returns a buy limit order carrying the given request.
+pub fn request(order_id: u64, req: Status) -> Order {
+    let mut order = Order::new(
+        order_id,
+        1000,
+        0.1,
+        1.0,
+        Side::Buy,
+        OrdType::Limit,
+        TimeInForce::GTC,
+    );
+    order.req = req;
+    order
+}
+
+/// Returns a local trade event received 5ns after the exchange timestamp.
+pub fn trade(exch_ts: i64, side: u64, px: f64, qty: f64) -> Event {
+    Event {
+        ev: LOCAL_TRADE_EVENT | side,
+        exch_ts,
+        local_ts: exch_ts + 5,
+        px,
+        qty,
+        order_id: 0,
+        ival: 0,
+        fval: 0.0,
+    }
+}
+
+/// Builds the depth feed of a venue, whose events arrive at the local after the feed latency.
+#[cfg(feature = "backtest")]
+pub fn feed(feed_latency: i64, updates: &[(i64, u64, f64, f64)]) -> DataSource<Event> {
+    let events: Vec<_> = updates
+        .iter()
+        .map(|&(exch_ts, side, px, qty)| Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | DEPTH_EVENT | side,
+            exch_ts,
+            local_ts: exch_ts + feed_latency,
+            px,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        })
+        .collect();
+    DataSource::Data(Data::from_data(&events))
+}
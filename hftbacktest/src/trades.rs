@@ -0,0 +1,217 @@
+use crate::types::{Event, BUY_EVENT, SELL_EVENT};
+
+/// Stores the last market trades, which are returned by
+/// [`Bot::last_trades`](crate::types::Bot::last_trades) until they're cleared.
+///
+/// By default, every trade is stored until it's cleared. Bounding the buffer by count or by time
+/// window keeps the memory bounded when the trades aren't cleared regularly, and aggregating the
+/// prints of a burst reduces the number of trades that the strategy processes.
+#[derive(Clone, Debug)]
+pub struct TradeBuffer {
+    trades: Vec<Event>,
+    start: usize,
+    max_count: usize,
+    max_age: i64,
+    aggregation: Option<i64>,
+    dedup: bool,
+    /// The raw prints at the latest exchange timestamp, to detect the duplicates.
+    recent: Vec<Event>,
+}
+
+impl TradeBuffer {
+    /// Constructs a `TradeBuffer` with the initial capacity. A capacity of `0` indicates that no
+    /// trades are stored.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            trades: Vec::with_capacity(capacity),
+            start: 0,
+            max_count: usize::MAX,
+            max_age: i64::MAX,
+            aggregation: None,
+            dedup: false,
+            recent: Vec::new(),
+        }
+    }
+
+    /// Keeps only the latest `count` trades.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the count is zero.
+    pub fn max_count(self, count: usize) -> Self {
+        assert!(count > 0, "the count must be positive");
+        Self {
+            trades: Vec::with_capacity(count * 2),
+            max_count: count,
+            ..self
+        }
+    }
+
+    /// Keeps only the trades within `window` of the exchange timestamp of the latest trade.
+    pub fn max_age(self, window: i64) -> Self {
+        Self {
+            max_age: window,
+            ..self
+        }
+    }
+
+    /// Aggregates a print into the previous trade if it has the same price and aggressor side and
+    /// arrives within `window` of the exchange timestamp of the previous trade. The aggregated
+    /// trade has the summed quantity and the timestamps of the latest print.
+    pub fn aggregate(self, window: i64) -> Self {
+        Self {
+            aggregation: Some(window),
+            ..self
+        }
+    }
+
+    /// Drops a print identical to one already received at the same exchange timestamp, such as
+    /// the one replayed by the connector after reconnecting.
+    pub fn dedup(self, dedup: bool) -> Self {
+        Self { dedup, ..self }
+    }
+
+    /// Returns `true` if the buffer stores trades.
+    pub fn enabled(&self) -> bool {
+        self.trades.capacity() > 0
+    }
+
+    /// Stores the trade.
+    pub fn push(&mut self, trade: Event) {
+        if !self.enabled() {
+            return;
+        }
+        if self.dedup && !self.check_unique(&trade) {
+            return;
+        }
+        if let (Some(window), Some(last)) = (self.aggregation, self.trades[self.start..].last_mut())
+        {
+            let side = BUY_EVENT | SELL_EVENT;
+            if last.px == trade.px
+                && last.ev & side == trade.ev & side
+                && trade.exch_ts - last.exch_ts <= window
+            {
+                last.qty += trade.qty;
+                last.exch_ts = last.exch_ts.max(trade.exch_ts);
+                last.local_ts = last.local_ts.max(trade.local_ts);
+                return;
+            }
+        }
+        self.trades.push(trade);
+        self.evict();
+    }
+
+    fn check_unique(&mut self, trade: &Event) -> bool {
+        match self.recent.first() {
+            Some(first) if first.exch_ts == trade.exch_ts => {
+                if self.recent.iter().any(|recent| recent == trade) {
+                    return false;
+                }
+            }
+            Some(first) if first.exch_ts > trade.exch_ts => return true,
+            _ => self.recent.clear(),
+        }
+        self.recent.push(trade.clone());
+        true
+    }
+
+    fn evict(&mut self) {
+        let latest = self.trades.last().unwrap().exch_ts;
+        let len = self.trades.len();
+        if len - self.start > self.max_count {
+            self.start = len - self.max_count;
+        }
+        while self.start < len && latest - self.trades[self.start].exch_ts > self.max_age {
+            self.start += 1;
+        }
+        // Drops the evicted trades in bulk, so that the latest trades remain contiguous without
+        // shifting them on every trade.
+        if self.start >= len - self.start {
+            self.trades.drain(..self.start);
+            self.start = 0;
+        }
+    }
+
+    /// Returns the stored trades in the order received.
+    pub fn as_slice(&self) -> &[Event] {
+        &self.trades[self.start..]
+    }
+
+    /// Returns the latest trade.
+    pub fn latest(&self) -> Option<&Event> {
+        self.as_slice().last()
+    }
+
+    /// Returns the number of the stored trades.
+    pub fn len(&self) -> usize {
+        self.trades.len() - self.start
+    }
+
+    /// Returns `true` if no trades are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Clears the stored trades.
+    pub fn clear(&mut self) {
+        self.trades.clear();
+        self.start = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TradeBuffer;
+    use crate::{
+        test_utils::trade,
+        types::{BUY_EVENT, SELL_EVENT},
+    };
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut buffer = TradeBuffer::new(0);
+        buffer.push(trade(100, BUY_EVENT, 100.0, 1.0));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_aggregate_and_dedup() {
+        let mut buffer = TradeBuffer::new(16).aggregate(10).dedup(true);
+        buffer.push(trade(100, BUY_EVENT, 100.0, 1.0));
+        // The replayed print is dropped.
+        buffer.push(trade(100, BUY_EVENT, 100.0, 1.0));
+        buffer.push(trade(105, BUY_EVENT, 100.0, 2.0));
+        buffer.push(trade(112, BUY_EVENT, 100.0, 1.0));
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(buffer.latest().unwrap().qty, 4.0);
+        assert_eq!(buffer.latest().unwrap().exch_ts, 112);
+    }
+
+    #[test]
+    fn test_new_trade_and_max_count() {
+        let mut buffer = TradeBuffer::new(16).max_count(3).aggregate(10);
+        buffer.push(trade(100, BUY_EVENT, 100.0, 1.0));
+        // A different aggressor side, a later print or a different price starts a new trade.
+        buffer.push(trade(105, SELL_EVENT, 100.0, 1.0));
+        buffer.push(trade(130, SELL_EVENT, 100.0, 1.0));
+        buffer.push(trade(131, SELL_EVENT, 99.5, 1.0));
+        let trades = buffer.as_slice();
+        assert_eq!(trades.len(), 3);
+        assert_eq!(
+            trades.iter().map(|t| t.exch_ts).collect::<Vec<_>>(),
+            [105, 130, 131]
+        );
+    }
+
+    #[test]
+    fn test_max_age() {
+        let mut buffer = TradeBuffer::new(16).max_age(50);
+        for ts in (0..1000).step_by(10) {
+            buffer.push(trade(ts, BUY_EVENT, 100.0, 1.0));
+        }
+        assert_eq!(buffer.len(), 6);
+        assert_eq!(buffer.as_slice()[0].exch_ts, 940);
+        buffer.clear();
+        assert!(buffer.is_empty());
+    }
+}
@@ -2,6 +2,7 @@ use std::{
     any::Any,
     collections::HashMap,
     fmt::{Debug, Formatter},
+    ops::AddAssign,
 };
 
 use anyhow::Error;
@@ -17,7 +18,13 @@ use dyn_clone::DynClone;
 use hftbacktest_derive::NpyDTyped;
 use thiserror::Error;
 
-use crate::{backtest::data::POD, depth::MarketDepth};
+use crate::{
+    backtest::{data::POD, models::QueueState},
+    bar::Bar,
+    clock::Clock,
+    depth::{rebucket_tick, MarketDepth},
+    risk::RiskStats,
+};
 
 #[derive(Clone, Debug, Decode, Encode)]
 pub enum Value {
@@ -120,6 +127,17 @@ pub enum ErrorKind {
     ConnectionInterrupted,
     CriticalConnectionError,
     OrderError,
+    /// The connector has sent no heartbeat within the timeout, so it's considered dead or
+    /// unresponsive.
+    ConnectorUnresponsive,
+    /// No market data event of the instrument has been received within the stale threshold.
+    StaleFeed,
+    /// The connector has detected a gap in the exchange's sequence numbers of the market depth
+    /// updates. The market depth is recovered from a fresh snapshot.
+    SeqGap,
+    /// The connector has failed to authenticate or to keep its private stream authenticated, so
+    /// the order and position updates may be missed until it reconnects.
+    AuthFailure,
     Custom(i64),
 }
 
@@ -138,10 +156,311 @@ pub enum LiveEvent {
     },
     Position {
         symbol: String,
+        /// In hedge mode, the position of each side is delivered separately.
+        side: PositionSide,
         qty: f64,
         exch_ts: i64,
     },
+    Liquidation {
+        symbol: String,
+        liquidation: Liquidation,
+    },
     Error(LiveError),
+    /// Sent periodically by the connector, so that the bot can detect a dead or unresponsive
+    /// connector.
+    Heartbeat {
+        timestamp: i64,
+    },
+    /// The margin state of the account, which is not tied to an instrument.
+    Margin(MarginState),
+    /// The response to [`LiveRequest::QueryInstrumentInfo`], which is `None` if the connector
+    /// doesn't provide the instrument information.
+    InstrumentInfo {
+        symbol: String,
+        info: Option<InstrumentInfo>,
+    },
+    /// An order on the exchange that wasn't placed through the connector, such as one placed
+    /// manually or left over from a crash. `exch_order_id` identifies the order to the connector,
+    /// and the order ID of `order` is meaningless. It's reported again with a final status once
+    /// the order is closed.
+    ExternalOrder {
+        symbol: String,
+        exch_order_id: String,
+        order: Order,
+    },
+    /// An execution report of the bot's order, sent in addition to the order update by the
+    /// connectors that report the individual executions.
+    Fill {
+        symbol: String,
+        fill: Fill,
+    },
+    /// The mark price of the instrument, by which the exchange calculates the unrealized PnL and
+    /// triggers the liquidation.
+    MarkPrice {
+        symbol: String,
+        price: f64,
+        exch_ts: i64,
+    },
+    /// The index price of the instrument's underlying, on which the mark price and the funding
+    /// rate are based.
+    IndexPrice {
+        symbol: String,
+        price: f64,
+        exch_ts: i64,
+    },
+    /// The predicted funding rate of the perpetual contract for the next funding.
+    FundingRate {
+        symbol: String,
+        funding: FundingRate,
+    },
+}
+
+/// The trading rules of an instrument, as specified by the exchange.
+#[derive(Clone, Debug, PartialEq, Decode, Encode)]
+pub struct InstrumentInfo {
+    /// The minimum price fluctuation.
+    pub tick_size: f64,
+    /// The minimum order quantity step.
+    pub lot_size: f64,
+    /// The minimum order value, which is `0` if there is no minimum.
+    pub min_notional: f64,
+    /// The number of decimal places of the price.
+    pub price_precision: u32,
+    /// The number of decimal places of the quantity.
+    pub qty_precision: u32,
+    /// The quantity of the underlying asset represented by a unit of the order quantity, which
+    /// is `1` unless the order quantity is in contracts.
+    pub contract_multiplier: f64,
+}
+
+/// The exchange's filters on new orders, which are checked before the order is sent so that an
+/// order the exchange would reject fails locally with a descriptive [`FilterViolation`].
+///
+/// ```
+/// use hftbacktest::types::OrderFilter;
+///
+/// let filter = OrderFilter::new()
+///     .min_notional(5.0)
+///     .lot_size(0.001)
+///     .price_band(0.05)
+///     .max_open_orders(200);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderFilter {
+    min_notional: f64,
+    lot_size: Option<f64>,
+    price_band: Option<f64>,
+    max_open_orders: Option<usize>,
+}
+
+impl OrderFilter {
+    /// Constructs `OrderFilter` without any filter.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the minimum order value, which is the price multiplied by the quantity.
+    pub fn min_notional(self, min_notional: f64) -> Self {
+        Self {
+            min_notional,
+            ..self
+        }
+    }
+
+    /// Sets the order quantity step, of which the order quantity must be a multiple.
+    pub fn lot_size(self, lot_size: f64) -> Self {
+        Self {
+            lot_size: Some(lot_size),
+            ..self
+        }
+    }
+
+    /// Sets the maximum deviation of the order price from the mid price, as a fraction of the mid
+    /// price. For example, `0.05` rejects a buy order above 105% and a sell order below 95% of the
+    /// mid price. The band isn't checked while the market depth is empty.
+    pub fn price_band(self, rate: f64) -> Self {
+        Self {
+            price_band: Some(rate),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of open orders of the asset, including the new order.
+    pub fn max_open_orders(self, max_open_orders: usize) -> Self {
+        Self {
+            max_open_orders: Some(max_open_orders),
+            ..self
+        }
+    }
+
+    /// Checks a new order against the filters.
+    ///
+    /// * `mid` - The current mid price, which can be `NaN` if it's unavailable.
+    /// * `open_orders` - The number of open orders of the asset, excluding the new order.
+    pub fn check(
+        &self,
+        side: Side,
+        price: f64,
+        qty: f64,
+        mid: f64,
+        open_orders: usize,
+    ) -> Result<(), FilterViolation> {
+        let notional = price * qty;
+        if notional < self.min_notional {
+            return Err(FilterViolation::MinNotional {
+                notional,
+                min_notional: self.min_notional,
+            });
+        }
+        if let Some(lot_size) = self.lot_size {
+            let lots = qty / lot_size;
+            if (lots - lots.round()).abs() > 1e-9 * lots.abs().max(1.0) {
+                return Err(FilterViolation::LotSize { qty, lot_size });
+            }
+        }
+        if let Some(rate) = self.price_band {
+            let out_of_band = match side {
+                Side::Buy => price > mid * (1.0 + rate),
+                Side::Sell => price < mid * (1.0 - rate),
+                Side::None | Side::Unsupported => false,
+            };
+            if out_of_band {
+                return Err(FilterViolation::PriceBand { price, mid, rate });
+            }
+        }
+        if let Some(max_open_orders) = self.max_open_orders {
+            if open_orders >= max_open_orders {
+                return Err(FilterViolation::MaxOpenOrders { max_open_orders });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&InstrumentInfo> for OrderFilter {
+    fn from(info: &InstrumentInfo) -> Self {
+        Self::new()
+            .min_notional(info.min_notional / info.contract_multiplier)
+            .lot_size(info.lot_size)
+    }
+}
+
+/// Describes which [`OrderFilter`] a new order violates.
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum FilterViolation {
+    #[error("the order value {notional} is less than the minimum notional {min_notional}")]
+    MinNotional { notional: f64, min_notional: f64 },
+    #[error("the order quantity {qty} is not a multiple of the lot size {lot_size}")]
+    LotSize { qty: f64, lot_size: f64 },
+    #[error("the order price {price} is outside the band of {rate} from the mid price {mid}")]
+    PriceBand { price: f64, mid: f64, rate: f64 },
+    #[error("the number of open orders reaches the maximum {max_open_orders}")]
+    MaxOpenOrders { max_open_orders: usize },
+}
+
+/// The margin state of the account for a currency, as reported by the exchange.
+#[derive(Clone, Debug, Decode, Encode)]
+pub struct MarginState {
+    /// The currency in which the margin is denominated.
+    pub currency: String,
+    /// The account equity, including the unrealized profit and loss.
+    pub equity: f64,
+    /// The wallet balance.
+    pub balance: f64,
+    /// The margin balance.
+    pub margin_balance: f64,
+    /// The margin required to maintain the open orders and positions.
+    pub initial_margin: f64,
+    /// The margin below which the positions are liquidated.
+    pub maintenance_margin: f64,
+    /// The funds available to open new orders and positions.
+    pub available_funds: f64,
+    /// The time at which the exchange reports the margin state.
+    pub exch_ts: i64,
+}
+
+/// The type of an option.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+/// The contract specification of an option.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionMetadata {
+    /// The symbol of the underlying asset.
+    pub underlying: String,
+    /// The strike price.
+    pub strike: f64,
+    /// The expiration timestamp in nanoseconds.
+    pub expiry: i64,
+    pub kind: OptionKind,
+}
+
+/// Forced liquidation of the position by the exchange due to insufficient margin.
+#[derive(Clone, Debug, Decode, Encode)]
+pub struct Liquidation {
+    /// The side of the liquidation order, which is opposite to the liquidated position.
+    pub side: Side,
+    /// The price at which the position is liquidated.
+    pub price: f64,
+    /// The liquidated quantity.
+    pub qty: f64,
+    /// The liquidation fee charged in addition to the trading fee.
+    pub fee: f64,
+    /// The time at which the exchange liquidates the position.
+    pub exch_ts: i64,
+}
+
+/// The predicted funding rate of a perpetual contract.
+#[derive(Clone, Copy, PartialEq, Debug, Decode, Encode)]
+pub struct FundingRate {
+    /// The funding rate, which is paid by the longs to the shorts if positive.
+    pub rate: f64,
+    /// The time of the next funding, in nanoseconds.
+    pub next_funding_time: i64,
+    /// The time at which the exchange published the funding rate.
+    pub exch_ts: i64,
+}
+
+/// An execution of the bot's order, reported separately from the order so that each fill is seen
+/// even if several fills occur between the order updates.
+#[derive(Clone, PartialEq, Debug, Decode, Encode)]
+pub struct Fill {
+    /// The order ID of the executed order.
+    pub order_id: OrderId,
+    /// The trade ID assigned by the exchange. In a backtest, it's the sequence number of the fills
+    /// of the asset.
+    pub trade_id: String,
+    /// The side of the executed order.
+    pub side: Side,
+    /// The executed price.
+    pub price: f64,
+    /// The executed quantity.
+    pub qty: f64,
+    /// The fee charged for the fill, which is negative for a rebate.
+    pub fee: f64,
+    /// Whether the order provided liquidity.
+    pub maker: bool,
+    /// The time at which the exchange executed the order.
+    pub exch_ts: i64,
+}
+
+impl Fill {
+    /// Constructs the fill of the last executed quantity of the order.
+    pub fn from_order(order: &Order, trade_id: String, fee: f64) -> Self {
+        Self {
+            order_id: order.order_id,
+            trade_id,
+            side: order.side,
+            price: order.exec_price(),
+            qty: order.exec_qty,
+            fee,
+            maker: order.maker,
+            exch_ts: order.exch_timestamp,
+        }
+    }
 }
 
 /// Indicates a buy, with specific meaning that can vary depending on the situation. For example,
@@ -169,6 +488,20 @@ pub const DEPTH_SNAPSHOT_EVENT: u64 = 4;
 /// Indicates that the best bid and best ask update event is received.
 pub const DEPTH_BBO_EVENT: u64 = 5;
 
+/// Indicates that the instrument parameters are changed, such as by a re-denomination. `px` is the
+/// new tick size, `qty` is the new lot size, and `fval` is the new contract size, where a
+/// non-positive value leaves the parameter unchanged.
+pub const INSTRUMENT_EVENT: u64 = 6;
+
+/// Indicates that the open interest of the instrument is updated. `qty` is the new open interest.
+pub const OPEN_INTEREST_EVENT: u64 = 7;
+
+/// Indicates that a position in the market is forcibly liquidated. When combined with
+/// [`BUY_EVENT`] or [`SELL_EVENT`], it means the side of the liquidation order; for example, a
+/// sell liquidation closes a long position. `px` and `qty` are the price and the quantity of the
+/// liquidation order.
+pub const LIQUIDATION_EVENT: u64 = 8;
+
 /// Indicates that an order has been added to the order book.
 pub const ADD_ORDER_EVENT: u64 = 10;
 
@@ -296,16 +629,251 @@ pub const EXCH_MODIFY_ORDER_EVENT: u64 = EXCH_EVENT | MODIFY_ORDER_EVENT;
 /// Represents a combination of [`EXCH_EVENT`] and [`FILL_EVENT`].
 pub const EXCH_FILL_EVENT: u64 = EXCH_EVENT | FILL_EVENT;
 
+/// Represents a combination of [`LOCAL_EVENT`] and [`INSTRUMENT_EVENT`].
+pub const LOCAL_INSTRUMENT_EVENT: u64 = LOCAL_EVENT | INSTRUMENT_EVENT;
+
+/// Represents a combination of [`EXCH_EVENT`] and [`INSTRUMENT_EVENT`].
+pub const EXCH_INSTRUMENT_EVENT: u64 = EXCH_EVENT | INSTRUMENT_EVENT;
+
+/// Represents a combination of [`LOCAL_EVENT`] and [`OPEN_INTEREST_EVENT`].
+pub const LOCAL_OPEN_INTEREST_EVENT: u64 = LOCAL_EVENT | OPEN_INTEREST_EVENT;
+
+/// Represents a combination of [`EXCH_EVENT`] and [`OPEN_INTEREST_EVENT`].
+pub const EXCH_OPEN_INTEREST_EVENT: u64 = EXCH_EVENT | OPEN_INTEREST_EVENT;
+
+/// Represents a combination of [`LOCAL_EVENT`] and [`LIQUIDATION_EVENT`].
+pub const LOCAL_LIQUIDATION_EVENT: u64 = LOCAL_EVENT | LIQUIDATION_EVENT;
+
+/// Represents a combination of [`LOCAL_LIQUIDATION_EVENT`] and [`BUY_EVENT`].
+pub const LOCAL_BUY_LIQUIDATION_EVENT: u64 = LOCAL_LIQUIDATION_EVENT | BUY_EVENT;
+
+/// Represents a combination of [`LOCAL_LIQUIDATION_EVENT`] and [`SELL_EVENT`].
+pub const LOCAL_SELL_LIQUIDATION_EVENT: u64 = LOCAL_LIQUIDATION_EVENT | SELL_EVENT;
+
+/// Represents a combination of [`EXCH_EVENT`] and [`LIQUIDATION_EVENT`].
+pub const EXCH_LIQUIDATION_EVENT: u64 = EXCH_EVENT | LIQUIDATION_EVENT;
+
 /// Indicates that one should continue until the end of the data.
 pub const UNTIL_END_OF_DATA: i64 = i64::MAX;
 
 pub type OrderId = u64;
 
+/// Generates order IDs that are unique across the bots sharing a connector and across restarts
+/// of the same bot. An order ID is composed of the following, from the most significant bits:
+///
+/// * The bot ID, 16 bits, which distinguishes the bots sharing a connector.
+/// * The epoch, 24 bits, which distinguishes the runs of the same bot. For a live bot, it's the
+///   start time in seconds, which wraps around every 194 days.
+/// * The sequence, 24 bits, which increases monotonically from 1 within the run. When the sequence
+///   is exhausted, the epoch advances by one.
+///
+/// ```
+/// use hftbacktest::types::OrderIdGenerator;
+///
+/// let mut generator = OrderIdGenerator::new(7, 1000);
+/// let order_id = generator.next_order_id();
+/// assert_eq!(OrderIdGenerator::bot_id(order_id), 7);
+/// assert_eq!(OrderIdGenerator::epoch(order_id), 1000);
+/// assert_eq!(OrderIdGenerator::sequence(order_id), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrderIdGenerator {
+    bot_id: u16,
+    epoch: u32,
+    seq: u32,
+}
+
+impl OrderIdGenerator {
+    const EPOCH_BITS: u32 = 24;
+    const SEQ_BITS: u32 = 24;
+    const EPOCH_MASK: u64 = (1 << Self::EPOCH_BITS) - 1;
+    const SEQ_MASK: u64 = (1 << Self::SEQ_BITS) - 1;
+
+    /// Constructs `OrderIdGenerator`. Only the lower 24 bits of the `epoch` are used.
+    pub fn new(bot_id: u16, epoch: u32) -> Self {
+        Self {
+            bot_id,
+            epoch: (epoch as u64 & Self::EPOCH_MASK) as u32,
+            seq: 0,
+        }
+    }
+
+    /// Returns the next order ID.
+    pub fn next_order_id(&mut self) -> OrderId {
+        if self.seq as u64 == Self::SEQ_MASK {
+            self.epoch = ((self.epoch as u64 + 1) & Self::EPOCH_MASK) as u32;
+            self.seq = 0;
+        }
+        self.seq += 1;
+        ((self.bot_id as u64) << (Self::EPOCH_BITS + Self::SEQ_BITS))
+            | ((self.epoch as u64) << Self::SEQ_BITS)
+            | self.seq as u64
+    }
+
+    /// Returns the bot ID of the order ID.
+    pub fn bot_id(order_id: OrderId) -> u16 {
+        (order_id >> (Self::EPOCH_BITS + Self::SEQ_BITS)) as u16
+    }
+
+    /// Returns the epoch of the order ID.
+    pub fn epoch(order_id: OrderId) -> u32 {
+        ((order_id >> Self::SEQ_BITS) & Self::EPOCH_MASK) as u32
+    }
+
+    /// Returns the sequence of the order ID.
+    pub fn sequence(order_id: OrderId) -> u32 {
+        (order_id & Self::SEQ_MASK) as u32
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum WaitOrderResponse {
     None,
     Any,
-    Specified { asset_no: usize, order_id: OrderId },
+    Specified {
+        asset_no: usize,
+        order_id: OrderId,
+    },
+    /// Waits until the order is in the given status, or in a final status after which the status
+    /// can no longer change.
+    UntilStatus {
+        asset_no: usize,
+        order_id: OrderId,
+        status: Status,
+    },
+}
+
+impl WaitOrderResponse {
+    /// Returns the order ID of the order to wait for if it belongs to the asset.
+    #[inline]
+    pub(crate) fn order_id(&self, asset_no: usize) -> Option<OrderId> {
+        match *self {
+            WaitOrderResponse::Specified {
+                asset_no: wait_asset_no,
+                order_id,
+            }
+            | WaitOrderResponse::UntilStatus {
+                asset_no: wait_asset_no,
+                order_id,
+                ..
+            } if wait_asset_no == asset_no => Some(order_id),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the order of the asset, updated by a received response, ends the wait.
+    #[inline]
+    pub(crate) fn is_met(&self, asset_no: usize, order: &Order) -> bool {
+        match *self {
+            WaitOrderResponse::None => false,
+            WaitOrderResponse::Any => true,
+            WaitOrderResponse::Specified { .. } => self.order_id(asset_no) == Some(order.order_id),
+            WaitOrderResponse::UntilStatus { status, .. } => {
+                self.order_id(asset_no) == Some(order.order_id)
+                    && (order.status == status || order.status.is_final())
+            }
+        }
+    }
+}
+
+/// A condition to wait for in [`Bot::wait_any`].
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WaitCondition {
+    /// The response of the order with the given order ID is received, which is detected as a
+    /// change in the order's state.
+    OrderResponse { asset_no: usize, order_id: OrderId },
+    /// The best bid or the best ask of the asset changes.
+    BboChange { asset_no: usize },
+    /// The given duration elapses.
+    Timeout(i64),
+}
+
+#[derive(PartialEq)]
+enum WaitState {
+    Order(Option<(Status, Status, i64, f64, f64, f64, i64)>),
+    Bbo(i64, i64),
+    Timeout,
+}
+
+/// Tracks the conditions of [`Bot::wait_any`] against the state at the start of the wait.
+pub(crate) struct WaitAny<'a> {
+    conditions: &'a [WaitCondition],
+    states: Vec<WaitState>,
+    deadline: i64,
+}
+
+impl<'a> WaitAny<'a> {
+    pub fn new<MD, B>(bot: &B, conditions: &'a [WaitCondition]) -> Self
+    where
+        MD: MarketDepth,
+        B: Bot<MD>,
+    {
+        let timestamp = bot.current_timestamp();
+        let deadline = conditions
+            .iter()
+            .filter_map(|condition| match condition {
+                WaitCondition::Timeout(duration) => Some(timestamp.saturating_add(*duration)),
+                _ => None,
+            })
+            .min()
+            .unwrap_or(UNTIL_END_OF_DATA);
+        Self {
+            conditions,
+            states: conditions
+                .iter()
+                .map(|condition| Self::state(bot, condition))
+                .collect(),
+            deadline,
+        }
+    }
+
+    /// Returns the timestamp at which the earliest [`WaitCondition::Timeout`] fires.
+    pub fn deadline(&self) -> i64 {
+        self.deadline
+    }
+
+    /// Returns the first condition that has fired since the start of the wait.
+    pub fn check<MD, B>(&self, bot: &B) -> Option<WaitCondition>
+    where
+        MD: MarketDepth,
+        B: Bot<MD>,
+    {
+        self.conditions
+            .iter()
+            .zip(self.states.iter())
+            .find(|(condition, state)| match condition {
+                WaitCondition::Timeout(_) => bot.current_timestamp() >= self.deadline,
+                _ => Self::state(bot, condition) != **state,
+            })
+            .map(|(condition, _)| *condition)
+    }
+
+    fn state<MD, B>(bot: &B, condition: &WaitCondition) -> WaitState
+    where
+        MD: MarketDepth,
+        B: Bot<MD>,
+    {
+        match *condition {
+            WaitCondition::OrderResponse { asset_no, order_id } => {
+                WaitState::Order(bot.orders(asset_no).get(&order_id).map(|order| {
+                    (
+                        order.req,
+                        order.status,
+                        order.exch_timestamp,
+                        order.qty,
+                        order.leaves_qty,
+                        order.exec_qty,
+                        order.price_tick,
+                    )
+                }))
+            }
+            WaitCondition::BboChange { asset_no } => {
+                let depth = bot.depth(asset_no);
+                WaitState::Bbo(depth.best_bid_tick(), depth.best_ask_tick())
+            }
+            WaitCondition::Timeout(_) => WaitState::Timeout,
+        }
+    }
 }
 
 /// Feed event data.
@@ -389,6 +957,45 @@ impl AsRef<str> for Side {
     }
 }
 
+/// The position side that an order opens or closes. In hedge mode, also known as dual-position-side
+/// mode, the long and short positions of an asset coexist and are held separately.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default, Decode, Encode)]
+#[repr(u8)]
+pub enum PositionSide {
+    /// One-way mode, in which the order trades the single net position.
+    #[default]
+    Both = 0,
+    /// Hedge mode's long position, which a buy order opens and a sell order closes.
+    Long = 1,
+    /// Hedge mode's short position, which a sell order opens and a buy order closes.
+    Short = 2,
+    /// This occurs when the [`Connector`](`crate::connector::Connector`) receives a position side
+    /// value that does not have a corresponding enum value.
+    Unsupported = 255,
+}
+
+impl PositionSide {
+    /// Returns `true` if the order on the given side closes this position side, which is always
+    /// `false` in one-way mode.
+    pub fn is_closing(&self, side: Side) -> bool {
+        matches!(
+            (self, side),
+            (PositionSide::Long, Side::Sell) | (PositionSide::Short, Side::Buy)
+        )
+    }
+}
+
+impl AsRef<str> for PositionSide {
+    fn as_ref(&self) -> &'static str {
+        match self {
+            PositionSide::Both => "BOTH",
+            PositionSide::Long => "LONG",
+            PositionSide::Short => "SHORT",
+            PositionSide::Unsupported => panic!("PositionSide::Unsupported"),
+        }
+    }
+}
+
 /// Order status
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Decode, Encode)]
 #[repr(u8)]
@@ -400,11 +1007,56 @@ pub enum Status {
     Canceled = 4,
     PartiallyFilled = 5,
     Rejected = 6,
+    /// A new order request is in flight, as returned by [`Order::state`].
+    PendingNew = 7,
+    /// A cancel request is in flight, as returned by [`Order::state`].
+    PendingCancel = 8,
+    /// A replace request is in flight, as returned by [`Order::state`]. The exchanges and
+    /// connectors that support modifying an order set [`Order::req`] to this value while the
+    /// request is in flight.
+    PendingReplace = 9,
+    /// The request exceeded the exchange's rate limits and was not processed. A new order rejected
+    /// this way ends in this status.
+    RateLimited = 10,
     /// This occurs when the [`Connector`](`crate::connector::Connector`) receives an order status
     /// value that does not have a corresponding enum value.
     Unsupported = 255,
 }
 
+impl Status {
+    /// Returns whether the order status is final, after which the order no longer changes.
+    pub fn is_final(self) -> bool {
+        matches!(
+            self,
+            Status::Expired
+                | Status::Filled
+                | Status::Canceled
+                | Status::Rejected
+                | Status::RateLimited
+        )
+    }
+
+    /// Returns whether the order status can change from this status to the `next` status. An
+    /// order can't leave a final status, and a partially filled order can't become a new order
+    /// again.
+    pub fn can_transition_to(self, next: Status) -> bool {
+        match self {
+            Status::None | Status::Unsupported => true,
+            Status::New => next != Status::None,
+            Status::PartiallyFilled => !matches!(next, Status::None | Status::New),
+            Status::PendingNew | Status::PendingCancel | Status::PendingReplace => {
+                // The pending states are not order statuses reported by the exchange.
+                true
+            }
+            Status::Expired
+            | Status::Filled
+            | Status::Canceled
+            | Status::Rejected
+            | Status::RateLimited => false,
+        }
+    }
+}
+
 /// Time In Force
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Decode, Encode)]
 #[repr(u8)]
@@ -453,8 +1105,8 @@ impl AsRef<str> for OrdType {
     }
 }
 
-/// Provides cloning of `Box<dyn Any>`, which is utilized in [Order] for the additional data used in
-/// [`QueueModel`](`crate::backtest::models::QueueModel`).
+/// Provides cloning of `Box<dyn Any>`, which is utilized in [`QueueState::Custom`] for the
+/// additional data used in [`QueueModel`](`crate::backtest::models::QueueModel`).
 ///
 /// **Usage:**
 /// ```
@@ -474,6 +1126,16 @@ pub trait AnyClone: DynClone {
 }
 dyn_clone::clone_trait_object!(AnyClone);
 
+impl AnyClone for Liquidation {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 impl AnyClone for () {
     fn as_any(&self) -> &dyn Any {
         self
@@ -484,6 +1146,42 @@ impl AnyClone for () {
     }
 }
 
+/// An applied change in a price level of the market depth.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DepthUpdate {
+    /// The price of the level in ticks.
+    pub price_tick: i64,
+    /// The quantity of the level before the change.
+    pub prev_qty: f64,
+    /// The quantity of the level after the change.
+    pub qty: f64,
+    /// The timestamp of the change.
+    pub timestamp: i64,
+    /// The side of the level.
+    pub side: Side,
+}
+
+impl DepthUpdate {
+    /// Appends the change returned by an L2 market depth update to the buffer, unless the buffer
+    /// has no capacity, which indicates that the depth updates are not stored.
+    pub(crate) fn record(
+        updates: &mut Vec<DepthUpdate>,
+        side: Side,
+        (price_tick, _, _, prev_qty, qty, timestamp): (i64, i64, i64, f64, f64, i64),
+    ) {
+        if updates.capacity() > 0 {
+            updates.push(DepthUpdate {
+                price_tick,
+                prev_qty,
+                qty,
+                timestamp,
+                side,
+            });
+        }
+    }
+}
+
 /// Order
 #[derive(Clone)]
 #[repr(C)]
@@ -509,18 +1207,27 @@ pub struct Order {
     pub local_timestamp: i64,
     pub order_id: u64,
     /// Additional data used for [`QueueModel`](`crate::backtest::models::QueueModel`).
-    /// This is only available in backtesting, and the type `Q` is set to `()` in a live bot.
-    pub q: Box<dyn AnyClone + Send>,
+    /// This is only available in backtesting, and is [`QueueState::None`] in a live bot.
+    pub q: QueueState,
     /// Whether the order is executed as a maker, only available when this order is executed.
     pub maker: bool,
     pub order_type: OrdType,
     /// Request status:
     ///   * [`Status::New`]: Request to open a new order.
     ///   * [`Status::Canceled`]: Request to cancel an opened order.
+    ///   * [`Status::PendingReplace`]: Request to modify an opened order.
+    ///
+    /// [`Order::state`] combines it with the status.
     pub req: Status,
     pub status: Status,
     pub side: Side,
     pub time_in_force: TimeInForce,
+    /// Whether the order may only reduce the position. The exchange shrinks the order to the
+    /// position or rejects it if it would open or increase the position.
+    pub reduce_only: bool,
+    /// The position side in hedge mode. An order that closes a position side is handled as
+    /// reduce-only against that side's position.
+    pub position_side: PositionSide,
 }
 
 impl Order {
@@ -548,9 +1255,11 @@ impl Order {
             exec_price_tick: 0,
             exec_qty: 0.0,
             order_id,
-            q: Box::new(()),
+            q: QueueState::None,
             maker: false,
             order_type,
+            reduce_only: false,
+            position_side: PositionSide::Both,
         }
     }
 
@@ -580,6 +1289,35 @@ impl Order {
         self.req != Status::None
     }
 
+    /// Returns the state of this order as seen by the local, which is [`Status::PendingNew`],
+    /// [`Status::PendingCancel`] or [`Status::PendingReplace`] while the corresponding request is
+    /// in flight, and the status otherwise.
+    pub fn state(&self) -> Status {
+        match self.req {
+            Status::New => Status::PendingNew,
+            Status::Canceled => Status::PendingCancel,
+            Status::PendingReplace => Status::PendingReplace,
+            _ => self.status,
+        }
+    }
+
+    /// Re-normalizes the price into the new tick size, rounding down a buy order and up a sell
+    /// order as the market depth does. See [`rebucket_tick`].
+    pub(crate) fn rebucket(&mut self, tick_size: f64) {
+        self.price_tick = rebucket_tick(self.price_tick, self.side, self.tick_size, tick_size);
+        self.exec_price_tick =
+            (self.exec_price_tick as f64 * self.tick_size / tick_size).round() as i64;
+        self.tick_size = tick_size;
+    }
+
+    /// Re-normalizes the price into the tick size if it's normalized into another one, as an order
+    /// in flight across a tick size change is.
+    pub(crate) fn normalize_tick(&mut self, tick_size: f64) {
+        if self.tick_size != tick_size {
+            self.rebucket(tick_size);
+        }
+    }
+
     /// Updates this order with the given order. This is used only by the processor in backtesting
     /// or by a bot in live trading.
     pub fn update(&mut self, order: &Order) {
@@ -626,12 +1364,14 @@ impl Debug for Order {
             .field("order_id", &self.order_id)
             .field("maker", &self.maker)
             .field("order_type", &self.order_type)
+            .field("reduce_only", &self.reduce_only)
+            .field("position_side", &self.position_side)
             .finish()
     }
 }
 
-impl Decode for Order {
-    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+impl<Ctx> Decode<Ctx> for Order {
+    fn decode<D: Decoder<Context = Ctx>>(decoder: &mut D) -> Result<Self, DecodeError> {
         Ok(Self {
             qty: Decode::decode(decoder)?,
             leaves_qty: Decode::decode(decoder)?,
@@ -643,19 +1383,21 @@ impl Decode for Order {
             local_timestamp: Decode::decode(decoder)?,
             order_id: Decode::decode(decoder)?,
             // In a live bot, q isn't used.
-            q: Box::new(()),
+            q: QueueState::None,
             maker: Decode::decode(decoder)?,
             order_type: Decode::decode(decoder)?,
             req: Decode::decode(decoder)?,
             status: Decode::decode(decoder)?,
             side: Decode::decode(decoder)?,
             time_in_force: Decode::decode(decoder)?,
+            reduce_only: Decode::decode(decoder)?,
+            position_side: Decode::decode(decoder)?,
         })
     }
 }
 
-impl<'de> BorrowDecode<'de> for Order {
-    fn borrow_decode<D: BorrowDecoder<'de>>(decoder: &mut D) -> Result<Self, DecodeError> {
+impl<'de, Ctx> BorrowDecode<'de, Ctx> for Order {
+    fn borrow_decode<D: BorrowDecoder<'de, Context = Ctx>>(decoder: &mut D) -> Result<Self, DecodeError> {
         Ok(Self {
             qty: Decode::decode(decoder)?,
             leaves_qty: Decode::decode(decoder)?,
@@ -667,13 +1409,15 @@ impl<'de> BorrowDecode<'de> for Order {
             local_timestamp: Decode::decode(decoder)?,
             order_id: Decode::decode(decoder)?,
             // In a live bot, q isn't used.
-            q: Box::new(()),
+            q: QueueState::None,
             maker: Decode::decode(decoder)?,
             order_type: Decode::decode(decoder)?,
             req: Decode::decode(decoder)?,
             status: Decode::decode(decoder)?,
             side: Decode::decode(decoder)?,
             time_in_force: Decode::decode(decoder)?,
+            reduce_only: Decode::decode(decoder)?,
+            position_side: Decode::decode(decoder)?,
         })
     }
 }
@@ -696,10 +1440,29 @@ impl Encode for Order {
         self.status.encode(encoder)?;
         self.side.encode(encoder)?;
         self.time_in_force.encode(encoder)?;
+        self.reduce_only.encode(encoder)?;
+        self.position_side.encode(encoder)?;
         Ok(())
     }
 }
 
+/// The granularity of the market depth that a live bot needs for an instrument, by which the
+/// connector subscribes to the lightest exchange stream that provides it.
+///
+/// The levels are ordered from the coarsest to the finest, so that the finest one requested by the
+/// bots trading the same instrument is subscribed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode)]
+pub enum DepthLevel {
+    /// Only the best bid and the best ask.
+    Bbo,
+    /// The best `n` price levels on each side. The connector may provide more levels if the
+    /// exchange doesn't offer a stream of exactly `n` levels.
+    Top(usize),
+    /// The full market depth.
+    #[default]
+    Full,
+}
+
 /// An asynchronous request to [`Connector`](`crate::connector::Connector`).
 #[derive(Clone, Debug, Encode, Decode)]
 pub enum LiveRequest {
@@ -710,20 +1473,41 @@ pub enum LiveRequest {
         symbol: String,
         tick_size: f64,
         lot_size: f64,
+        depth: DepthLevel,
+    },
+    /// A request to stop trading the instrument, sent when the bot is closed.
+    DeregisterInstrument { symbol: String },
+    /// A request to resend the market depth snapshot of the instrument, which replaces the
+    /// current market depth.
+    Resync { symbol: String },
+    /// A request for the trading rules of the instrument, answered by
+    /// [`LiveEvent::InstrumentInfo`].
+    QueryInstrumentInfo { symbol: String },
+    /// A request to manage the external order, reported by [`LiveEvent::ExternalOrder`], as the
+    /// bot's order with the order ID of `order`.
+    AdoptOrder {
+        symbol: String,
+        exch_order_id: String,
+        order: Order,
+    },
+    /// A request to cancel the external order, reported by [`LiveEvent::ExternalOrder`].
+    CancelExternal {
+        symbol: String,
+        exch_order_id: String,
     },
 }
 
 /// Provides state values.
 ///
-/// **Note:** In a live bot, currently only `position` value is delivered correctly, and other
-/// values are invalid.
+/// **Note:** In a live bot, currently only `position` and `fee` values are delivered correctly,
+/// and other values are invalid.
 #[repr(C)]
 #[derive(PartialEq, Clone, Debug, Default)]
 pub struct StateValues {
     pub position: f64,
     /// Backtest only
     pub balance: f64,
-    /// Backtest only
+    /// In a live bot, it's accumulated from the fills reported by the connector.
     pub fee: f64,
     // todo: currently, they are cumulative values, but they need to be values within the record
     //       interval.
@@ -733,6 +1517,75 @@ pub struct StateValues {
     pub trading_volume: f64,
     /// Backtest only
     pub trading_value: f64,
+    /// The quantity of the long position in hedge mode, which is included in `position`.
+    pub long_position: f64,
+    /// The quantity of the short position in hedge mode as a non-negative value, which is deducted
+    /// from `position`.
+    pub short_position: f64,
+    /// Backtest only. The balance attributed to the long position in hedge mode, from which the
+    /// long position's PnL is derived, as `balance` is for the total.
+    pub long_balance: f64,
+    /// Backtest only. The balance attributed to the short position in hedge mode.
+    pub short_balance: f64,
+}
+
+impl StateValues {
+    /// Returns the gross position quantity, which counts both sides in hedge mode instead of
+    /// netting them.
+    pub fn gross_position(&self) -> f64 {
+        let one_way = self.position - self.long_position + self.short_position;
+        one_way.abs() + self.long_position + self.short_position
+    }
+
+    /// Returns the position quantity of the position side, where the short position is negative.
+    pub fn side_position(&self, position_side: PositionSide) -> f64 {
+        match position_side {
+            PositionSide::Long => self.long_position,
+            PositionSide::Short => -self.short_position,
+            PositionSide::Both | PositionSide::Unsupported => self.position,
+        }
+    }
+}
+
+/// Portfolio-level values aggregated across all assets, each valued at its mid price.
+///
+/// **Note:** In a live bot, the balance and fee are not delivered, and every asset is valued as a
+/// linear asset with a contract size of 1, so only the exposures are valid.
+#[repr(C)]
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Portfolio {
+    /// The sum of each asset's equity, including the unrealized PnL and fees.
+    pub equity: f64,
+    /// The sum of the absolute values of the positions.
+    pub gross_exposure: f64,
+    /// The sum of the values of the positions, where short positions are negative.
+    pub net_exposure: f64,
+    /// The total fees paid.
+    pub fee: f64,
+}
+
+/// The counters of the events processed for an asset since the last query, which can be used to
+/// detect a dead or anomalously quiet feed.
+#[repr(C)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, Encode, Decode)]
+pub struct EventStats {
+    /// The number of market depth updates, including depth clear and snapshot events.
+    pub depth_updates: u64,
+    /// The number of market trades.
+    pub trades: u64,
+    /// The number of order responses received.
+    pub order_updates: u64,
+    /// The number of events received but discarded, such as stale order responses.
+    pub dropped: u64,
+}
+
+impl AddAssign for Portfolio {
+    fn add_assign(&mut self, rhs: Self) {
+        self.equity += rhs.equity;
+        self.gross_exposure += rhs.gross_exposure;
+        self.net_exposure += rhs.net_exposure;
+        self.fee += rhs.fee;
+    }
 }
 
 /// Provides errors that can occur in builders.
@@ -759,18 +1612,25 @@ pub struct OrderRequest {
     pub side: Side,
     pub time_in_force: TimeInForce,
     pub order_type: OrdType,
+    /// See [`Order::reduce_only`].
+    pub reduce_only: bool,
+    /// See [`Order::position_side`].
+    pub position_side: PositionSide,
 }
 
 /// Provides a bot interface for backtesting and live trading.
-pub trait Bot<MD>
+pub trait Bot<MD>: Clock
 where
     MD: MarketDepth,
 {
     type Error;
 
     /// In backtesting, this timestamp reflects the time at which the backtesting is conducted
-    /// within the provided data. In a live bot, it's literally the current local timestamp.
-    fn current_timestamp(&self) -> i64;
+    /// within the provided data. In a live bot, it's literally the current local timestamp. Both
+    /// are provided by the [`Clock`].
+    fn current_timestamp(&self) -> i64 {
+        self.now()
+    }
 
     /// Returns the number of assets.
     fn num_assets(&self) -> usize;
@@ -783,6 +1643,9 @@ where
     /// Returns the state's values such as balance, fee, and so on.
     fn state_values(&self, asset_no: usize) -> &StateValues;
 
+    /// Returns the [`Portfolio`] aggregating the equity, exposures, and fees across all assets.
+    fn portfolio(&self) -> Portfolio;
+
     /// Returns the [`MarketDepth`].
     ///
     /// * `asset_no` - Asset number from which the market depth will be retrieved.
@@ -799,11 +1662,72 @@ where
     ///                trades in any assets will be cleared.
     fn clear_last_trades(&mut self, asset_no: Option<usize>);
 
+    /// Returns the fills of the orders that have occurred since the fills were last cleared. The
+    /// fills are stored only if the asset is set up with a nonzero last fills capacity.
+    ///
+    /// * `asset_no` - Asset number from which the last fills will be retrieved.
+    fn last_fills(&self, asset_no: usize) -> &[Fill];
+
+    /// Clears the last fills from the buffer.
+    ///
+    /// * `asset_no` - Asset number at which this command will be executed. If `None`, the last
+    ///   fills in all assets will be cleared.
+    fn clear_last_fills(&mut self, asset_no: Option<usize>);
+
+    /// Returns the forced liquidations in the market, as opposed to the ones of the bot's position
+    /// returned by [`Bot::liquidations`], that have occurred since they were last cleared. They're
+    /// the [`LIQUIDATION_EVENT`]s in the feed, which are only stored if their capacity is set on
+    /// the asset.
+    ///
+    /// * `asset_no` - Asset number from which the last liquidation prints will be retrieved.
+    fn last_liquidation_prints(&self, asset_no: usize) -> &[Event];
+
+    /// Clears the last liquidation prints from the buffer.
+    ///
+    /// * `asset_no` - Asset number at which this command will be executed. If `None`, the last
+    ///   liquidation prints in all assets will be cleared.
+    fn clear_last_liquidation_prints(&mut self, asset_no: Option<usize>);
+
+    /// Returns the latest open interest of the asset, or `None` if no [`OPEN_INTEREST_EVENT`] has
+    /// been received yet.
+    ///
+    /// * `asset_no` - Asset number from which the open interest will be retrieved.
+    fn open_interest(&self, asset_no: usize) -> Option<f64>;
+
+    /// Returns the last changes applied to the price levels of the market depth, which is only
+    /// stored if its capacity is set on the asset. Clearing the depth isn't included.
+    ///
+    /// * `asset_no` - Asset number from which the last depth updates will be retrieved.
+    fn last_depth_updates(&self, asset_no: usize) -> &[DepthUpdate];
+
+    /// Clears the last depth updates from the buffer.
+    ///
+    /// * `asset_no` - Asset number at which this command will be executed. If `None`, the last
+    ///   depth updates in all assets will be cleared.
+    fn clear_last_depth_updates(&mut self, asset_no: Option<usize>);
+
+    /// Returns the latest bars aggregated from the market trades over the interval, which are only
+    /// built if the interval is set on the asset. The last bar is still being formed.
+    ///
+    /// * `asset_no` - Asset number from which the bars will be retrieved.
+    /// * `interval` - The interval of the bars.
+    fn bars(&self, asset_no: usize, interval: i64) -> &[Bar];
+
+    /// Returns the forced liquidations of the position that have occurred so far. In live trading,
+    /// only the latest ones up to the instrument's liquidations capacity are kept.
+    ///
+    /// * `asset_no` - Asset number from which the liquidations will be retrieved.
+    fn liquidations(&self, asset_no: usize) -> &[Liquidation];
+
     /// Returns a hash map of order IDs and their corresponding [`Order`]s.
     ///
     /// * `asset_no` - Asset number from which orders will be retrieved.
     fn orders(&self, asset_no: usize) -> &HashMap<OrderId, Order>;
 
+    /// Returns a new order ID generated by [`OrderIdGenerator`], which doesn't collide with the
+    /// order IDs of the other bots sharing the connector or of the previous runs of this bot.
+    fn next_order_id(&mut self) -> OrderId;
+
     /// Places a buy order.
     ///
     /// * `asset_no` - Asset number at which this command will be executed.
@@ -888,6 +1812,20 @@ where
         timeout: i64,
     ) -> Result<bool, Self::Error>;
 
+    /// Waits until the order with the given order ID is in the given status, such as
+    /// [`Status::Filled`] or [`Status::Canceled`], or until timeout. Unlike
+    /// [wait_order_response()](Self::wait_order_response()), a response that leaves the order in
+    /// another status, such as an acknowledgement or a partial fill, doesn't end the wait unless
+    /// the order reaches a final status, after which the status can no longer change. It returns
+    /// immediately if the order is already in such a status.
+    fn wait_order_status(
+        &mut self,
+        asset_no: usize,
+        order_id: OrderId,
+        status: Status,
+        timeout: i64,
+    ) -> Result<bool, Self::Error>;
+
     /// Wait until the next feed is received, or until timeout.
     fn wait_next_feed(
         &mut self,
@@ -895,6 +1833,19 @@ where
         timeout: i64,
     ) -> Result<bool, Self::Error>;
 
+    /// Waits until the first of the given conditions fires, so that several conditions can be
+    /// multiplexed without a tight [elapse()](Self::elapse()) loop. If no
+    /// [`WaitCondition::Timeout`] is given, it waits until one of the other conditions fires.
+    ///
+    /// Returns:
+    ///   `Ok(Some(condition))` with the condition that fired first. If several conditions fire
+    ///   at the same time, the one that comes first in `conditions` is returned. If the end of
+    ///   the data is reached, or the live bot is interrupted, it returns `Ok(None)`.
+    fn wait_any(
+        &mut self,
+        conditions: &[WaitCondition],
+    ) -> Result<Option<WaitCondition>, Self::Error>;
+
     /// Elapses the specified duration.
     ///
     /// Args:
@@ -921,6 +1872,16 @@ where
     ///   the data is reached before the specified timestamp, it returns `Ok(false)`.
     fn elapse_bt(&mut self, duration: i64) -> Result<bool, Self::Error>;
 
+    /// Elapses until the given timestamp, such as the one scheduled by
+    /// [`Clock::next_time_of_day`]. If the timestamp has already passed, it only processes the
+    /// pending events as `elapse(0)` does.
+    ///
+    /// Returns the same as [`elapse`](Self::elapse).
+    fn elapse_until(&mut self, timestamp: i64) -> Result<bool, Self::Error> {
+        let duration = timestamp.saturating_sub(self.current_timestamp());
+        self.elapse(duration.max(0))
+    }
+
     /// Closes this backtester or bot.
     fn close(&mut self) -> Result<(), Self::Error>;
 
@@ -930,6 +1891,19 @@ where
     /// Returns the last order's request timestamp, exchange timestamp, and response receipt
     /// timestamp.
     fn order_latency(&self, asset_no: usize) -> Option<(i64, i64, i64)>;
+
+    /// Returns the [`EventStats`] counted since the last call, and resets the counters.
+    ///
+    /// * `asset_no` - Asset number from which the event statistics will be retrieved.
+    fn event_stats(&mut self, asset_no: usize) -> EventStats;
+
+    /// Returns the [`RiskStats`] of the pre-trade risk checks of the asset, which are all zero if
+    /// no [`RiskGuard`](crate::risk::RiskGuard) is set.
+    fn risk_stats(&self, asset_no: usize) -> RiskStats;
+
+    /// Returns `true` if the bot is in the warm-up period, during which orders cannot be
+    /// submitted. A live bot has no warm-up period.
+    fn is_warming_up(&self) -> bool;
 }
 
 /// Provides bot statistics and [`StateValues`] recording features for backtesting result analysis
@@ -950,6 +1924,13 @@ mod tests {
         prelude::LOCAL_EVENT,
         types::{
             Event,
+            FilterViolation,
+            OrdType,
+            Order,
+            OrderFilter,
+            Side,
+            Status,
+            TimeInForce,
             BUY_EVENT,
             LOCAL_BID_DEPTH_CLEAR_EVENT,
             LOCAL_BID_DEPTH_EVENT,
@@ -993,4 +1974,68 @@ mod tests {
         assert!(event.is(LOCAL_EVENT));
         assert!(event.is(BUY_EVENT));
     }
+
+    #[test]
+    fn test_order_filter() {
+        let filter = OrderFilter::new()
+            .min_notional(5.0)
+            .lot_size(0.001)
+            .price_band(0.05)
+            .max_open_orders(2);
+
+        assert_eq!(filter.check(Side::Buy, 100.0, 0.123, 100.0, 1), Ok(()));
+        assert_eq!(
+            filter.check(Side::Buy, 100.0, 0.01, 100.0, 0),
+            Err(FilterViolation::MinNotional {
+                notional: 1.0,
+                min_notional: 5.0
+            })
+        );
+        assert_eq!(
+            filter.check(Side::Buy, 100.0, 0.1234, 100.0, 0),
+            Err(FilterViolation::LotSize {
+                qty: 0.1234,
+                lot_size: 0.001
+            })
+        );
+        assert!(matches!(
+            filter.check(Side::Buy, 106.0, 0.1, 100.0, 0),
+            Err(FilterViolation::PriceBand { .. })
+        ));
+        assert_eq!(filter.check(Side::Sell, 106.0, 0.1, 100.0, 0), Ok(()));
+        // The band isn't checked without the mid price.
+        assert_eq!(filter.check(Side::Buy, 106.0, 0.1, f64::NAN, 0), Ok(()));
+        assert_eq!(
+            filter.check(Side::Sell, 100.0, 0.1, 100.0, 2),
+            Err(FilterViolation::MaxOpenOrders { max_open_orders: 2 })
+        );
+    }
+
+    #[test]
+    fn test_order_state() {
+        let mut order = Order::new(
+            1,
+            100,
+            0.1,
+            1.0,
+            Side::Buy,
+            OrdType::Limit,
+            TimeInForce::GTC,
+        );
+        order.req = Status::New;
+        assert_eq!(order.state(), Status::PendingNew);
+
+        order.req = Status::None;
+        order.status = Status::New;
+        assert_eq!(order.state(), Status::New);
+
+        order.req = Status::Canceled;
+        assert_eq!(order.state(), Status::PendingCancel);
+
+        assert!(Status::New.can_transition_to(Status::PartiallyFilled));
+        assert!(Status::PartiallyFilled.can_transition_to(Status::Filled));
+        assert!(!Status::PartiallyFilled.can_transition_to(Status::New));
+        assert!(!Status::Canceled.can_transition_to(Status::Canceled));
+        assert!(!Status::Filled.can_transition_to(Status::New));
+    }
 }
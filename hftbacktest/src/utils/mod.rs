@@ -1,6 +1,10 @@
 mod aligned;
+#[cfg(feature = "backtest")]
+mod rng;
 
 pub use aligned::{AlignedArray, CACHE_LINE_SIZE};
+#[cfg(feature = "backtest")]
+pub use rng::SplitMix64;
 
 /// Gets price precision.
 ///
@@ -0,0 +1,166 @@
+use std::f64::consts::PI;
+
+use hftbacktest::{
+    backtest::{
+        assettype::LinearAsset,
+        data::{Data, DataSource},
+        models::{
+            CommonFees,
+            ConstantLatency,
+            PowerProbQueueFunc3,
+            ProbQueueModel,
+            TradingValueFeeModel,
+        },
+        recorder::BacktestRecorder,
+        Backtest,
+        ExchangeKind,
+        L2AssetBuilder,
+    },
+    prelude::{
+        Bot,
+        Event,
+        HashMapMarketDepth,
+        MarketDepth,
+        BUY_EVENT,
+        DEPTH_EVENT,
+        EXCH_EVENT,
+        LOCAL_EVENT,
+        SELL_EVENT,
+        TRADE_EVENT,
+    },
+};
+use strategies::{run, AvellanedaStoikov, GridTrading, ImbalanceMarketMaking, Strategy};
+
+#[path = "../examples/strategies/mod.rs"]
+mod strategies;
+
+const TICK_SIZE: f64 = 0.01;
+const ORDER_QTY: f64 = 1.0;
+const MAX_POSITION: f64 = 10.0 * ORDER_QTY;
+
+/// Builds 10 seconds of the feed in which the mid price oscillates by 30 ticks around 100.0 every
+/// 2 seconds, so that the quotes on both sides are crossed repeatedly.
+fn feed() -> DataSource<Event> {
+    let mut events = Vec::new();
+    let mut push = |ev: u64, exch_ts: i64, price_tick: i64, qty: f64| {
+        events.push(Event {
+            ev: EXCH_EVENT | LOCAL_EVENT | ev,
+            exch_ts,
+            local_ts: exch_ts + 100_000,
+            px: price_tick as f64 * TICK_SIZE,
+            qty,
+            order_id: 0,
+            ival: 0,
+            fval: 0.0,
+        });
+    };
+    let mut prev: Option<(i64, i64)> = None;
+    for k in 0..1000 {
+        let exch_ts = 1_000_000_000 + k * 10_000_000;
+        let mid_tick = 10_000 + (30.0 * (2.0 * PI * k as f64 / 200.0).sin()).round() as i64;
+        let (bid_tick, ask_tick) = (mid_tick - 1, mid_tick + 1);
+        if let Some((prev_bid_tick, prev_ask_tick)) = prev {
+            if prev_bid_tick != bid_tick {
+                push(DEPTH_EVENT | BUY_EVENT, exch_ts, prev_bid_tick, 0.0);
+            }
+            if prev_ask_tick != ask_tick {
+                push(DEPTH_EVENT | SELL_EVENT, exch_ts, prev_ask_tick, 0.0);
+            }
+        }
+        let qty = 5.0 + (k % 7) as f64;
+        push(DEPTH_EVENT | BUY_EVENT, exch_ts, bid_tick, qty);
+        push(DEPTH_EVENT | SELL_EVENT, exch_ts, ask_tick, 12.0 - qty);
+        push(TRADE_EVENT | SELL_EVENT, exch_ts, bid_tick, 2.0);
+        push(TRADE_EVENT | BUY_EVENT, exch_ts, ask_tick, 2.0);
+        prev = Some((bid_tick, ask_tick));
+    }
+    DataSource::Data(Data::from_data(&events))
+}
+
+fn backtest() -> Backtest<HashMapMarketDepth> {
+    Backtest::builder()
+        .add_asset(
+            L2AssetBuilder::new()
+                .data(vec![feed()])
+                .latency_model(ConstantLatency::new(1_000_000, 1_000_000))
+                .asset_type(LinearAsset::new(1.0))
+                .fee_model(TradingValueFeeModel::new(CommonFees::new(-0.00005, 0.0007)))
+                .exchange(ExchangeKind::NoPartialFillExchange)
+                .queue_model(ProbQueueModel::new(PowerProbQueueFunc3::new(3.0)))
+                .depth(|| HashMapMarketDepth::new(TICK_SIZE, 1.0))
+                .build()
+                .unwrap(),
+        )
+        .build()
+        .unwrap()
+}
+
+/// Tracks the largest absolute position seen by the strategy.
+struct PositionTracker<'a, S> {
+    strategy: &'a mut S,
+    max_abs_position: f64,
+}
+
+impl<MD, S> Strategy<MD> for PositionTracker<'_, S>
+where
+    MD: MarketDepth,
+    S: Strategy<MD>,
+{
+    fn update<I>(&mut self, hbt: &mut I, asset_no: usize) -> Result<(), I::Error>
+    where
+        I: Bot<MD>,
+    {
+        self.max_abs_position = self.max_abs_position.max(hbt.position(asset_no).abs());
+        self.strategy.update(hbt, asset_no)
+    }
+}
+
+/// Runs the strategy until the end of the data, updating the quotes every 100ms, and checks that
+/// it traded within the position limit, allowing for `max_overshoot` orders filled at once.
+fn run_strategy<S>(strategy: &mut S, max_overshoot: f64)
+where
+    S: Strategy<HashMapMarketDepth>,
+{
+    let mut hbt = backtest();
+    let mut recorder = BacktestRecorder::new(&hbt);
+    let mut tracker = PositionTracker {
+        strategy,
+        max_abs_position: 0.0,
+    };
+    run(&mut hbt, &mut recorder, &mut tracker, 0, 100_000_000, 10).unwrap();
+
+    let state = hbt.state_values(0).clone();
+    assert!(state.num_trades > 0);
+    assert_eq!(state.trading_volume, state.num_trades as f64 * ORDER_QTY);
+    assert!(tracker.max_abs_position.max(state.position.abs()) <= MAX_POSITION + max_overshoot);
+    assert!(state.balance.is_finite() && state.fee.is_finite());
+    hbt.close().unwrap();
+}
+
+#[test]
+fn test_grid_trading() {
+    let grid_num = 10;
+    let mut strategy = GridTrading {
+        relative_half_spread: 0.0005,
+        relative_grid_interval: 0.0005,
+        grid_num,
+        min_grid_step: TICK_SIZE,
+        skew: 0.00005,
+        order_qty: ORDER_QTY,
+        max_position: MAX_POSITION,
+    };
+    // The whole grid can be filled by a single sweep.
+    run_strategy(&mut strategy, (grid_num - 1) as f64 * ORDER_QTY);
+}
+
+#[test]
+fn test_avellaneda_stoikov() {
+    let mut strategy = AvellanedaStoikov::new(0.05, 1.5, 100.0, 0.01, ORDER_QTY, MAX_POSITION);
+    run_strategy(&mut strategy, 0.0);
+}
+
+#[test]
+fn test_imbalance_market_making() {
+    let mut strategy = ImbalanceMarketMaking::new(2.0, 3.0, 0.5, 0.1, ORDER_QTY, MAX_POSITION);
+    run_strategy(&mut strategy, 0.0);
+}
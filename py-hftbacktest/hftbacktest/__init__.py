@@ -1,4 +1,4 @@
-from typing import List, Any
+from typing import List, Any, Callable, Dict, Optional
 
 import numpy as np
 from numpy.typing import NDArray
@@ -29,6 +29,9 @@ from .order import (
     GTX,
     LIMIT,
     MARKET,
+    BOTH,
+    LONG,
+    SHORT,
 )
 from .recorder import Recorder
 from .types import (
@@ -39,6 +42,8 @@ from .types import (
     DEPTH_CLEAR_EVENT,
     DEPTH_SNAPSHOT_EVENT,
     DEPTH_BBO_EVENT,
+    OPEN_INTEREST_EVENT,
+    LIQUIDATION_EVENT,
     ADD_ORDER_EVENT,
     CANCEL_ORDER_EVENT,
     MODIFY_ORDER_EVENT,
@@ -80,6 +85,8 @@ __all__ = (
     'DEPTH_CLEAR_EVENT',
     'DEPTH_SNAPSHOT_EVENT',
     'DEPTH_BBO_EVENT',
+    'OPEN_INTEREST_EVENT',
+    'LIQUIDATION_EVENT',
     'ADD_ORDER_EVENT',
     'CANCEL_ORDER_EVENT',
     'MODIFY_ORDER_EVENT',
@@ -108,6 +115,11 @@ __all__ = (
 
     'LIMIT',
     'MARKET',
+
+    # Position side
+    'BOTH',
+    'LONG',
+    'SHORT',
     
     'Recorder'
 )
@@ -219,17 +231,50 @@ def ROIVectorMarketDepthBacktest(
 
 
 if LIVE_FEATURE:
+    def HashMapMarketDepthLiveBot(
+            assets: List[LiveInstrument],
+            error_handler: Optional[Callable[[str | int, Any], None]] = None,
+            order_recv_hook: Optional[Callable[[Dict[str, Any], Dict[str, Any]], None]] = None
+    ) -> HashMapMarketDepthLiveBot_TypeHint:
+        """
+        Constructs an instance of `HashMapMarketDepthLiveBot`.
+
+        Args:
+            assets: A list of live instruments constructed using :class:`LiveInstrument`.
+            error_handler: A callable invoked with the error kind and the detailed error value when
+                           a connector reports an error. The error kind is the name of the kind, or
+                           the code of a custom error kind. If it raises an exception, the bot
+                           returns an error from the current call.
+            order_recv_hook: A callable invoked with the previous and the new order, each as a
+                             ``dict``, when an order response is received. If it raises an
+                             exception, the bot returns an error from the current call.
+
+        Returns:
+            A jit`ed `HashMapMarketDepthLiveBot` that can be used in an ``njit`` function.
+        """
+        ptr = build_hashmap_livebot(assets, error_handler, order_recv_hook)
+        return HashMapMarketDepthLiveBot_(ptr)
+
     def ROIVectorMarketDepthLiveBot(
-            assets: List[LiveInstrument]
+            assets: List[LiveInstrument],
+            error_handler: Optional[Callable[[str | int, Any], None]] = None,
+            order_recv_hook: Optional[Callable[[Dict[str, Any], Dict[str, Any]], None]] = None
     ) -> ROIVectorMarketDepthLiveBot_TypeHint:
         """
         Constructs an instance of `ROIVectorMarketDepthLiveBot`.
 
         Args:
             assets: A list of live instruments constructed using :class:`LiveInstrument`.
+            error_handler: A callable invoked with the error kind and the detailed error value when
+                           a connector reports an error. The error kind is the name of the kind, or
+                           the code of a custom error kind. If it raises an exception, the bot
+                           returns an error from the current call.
+            order_recv_hook: A callable invoked with the previous and the new order, each as a
+                             ``dict``, when an order response is received. If it raises an
+                             exception, the bot returns an error from the current call.
 
         Returns:
             A jit`ed `ROIVectorMarketDepthLiveBot` that can be used in an ``njit`` function.
         """
-        ptr = build_roivec_livebot(assets)
+        ptr = build_roivec_livebot(assets, error_handler, order_recv_hook)
         return ROIVectorMarketDepthLiveBot_(ptr)
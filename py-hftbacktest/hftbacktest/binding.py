@@ -26,7 +26,15 @@ from . import _hftbacktest
 from .intrinsic import ptr_from_val, address_as_void_pointer, val_from_ptr, is_null_ptr
 from .order import order_dtype, Order, Order_
 from .state import StateValues, StateValues_
-from .types import event_dtype, state_values_dtype, EVENT_ARRAY
+from .types import (
+    event_dtype,
+    depth_update_dtype,
+    state_values_dtype,
+    portfolio_dtype,
+    event_stats_dtype,
+    EVENT_ARRAY,
+    DEPTH_UPDATE_ARRAY
+)
 
 LIVE_FEATURE = 'build_hashmap_livebot' in dir(_hftbacktest)
 
@@ -64,6 +72,14 @@ hashmapdepth_ask_qty_at_tick = lib.hashmapdepth_ask_qty_at_tick
 hashmapdepth_ask_qty_at_tick.restype = c_double
 hashmapdepth_ask_qty_at_tick.argtypes = [c_void_p, c_int64]
 
+hashmapdepth_bid_depth_range = lib.hashmapdepth_bid_depth_range
+hashmapdepth_bid_depth_range.restype = None
+hashmapdepth_bid_depth_range.argtypes = [c_void_p, c_int64, c_int64, c_void_p]
+
+hashmapdepth_ask_depth_range = lib.hashmapdepth_ask_depth_range
+hashmapdepth_ask_depth_range.restype = None
+hashmapdepth_ask_depth_range.argtypes = [c_void_p, c_int64, c_int64, c_void_p]
+
 hashmapdepth_snapshot = lib.hashmapdepth_snapshot
 hashmapdepth_snapshot.restype = c_void_p
 hashmapdepth_snapshot.argtypes = [c_void_p, POINTER(c_uint64)]
@@ -145,6 +161,38 @@ class HashMapMarketDepth:
         """
         return hashmapdepth_ask_qty_at_tick(self.ptr, price_tick)
 
+    def bid_depth_range(self, lb_tick: int64, ub_tick: int64) -> np.ndarray[Any, float64]:
+        """
+        Returns the bid market depth array, which contains the quantities at prices from ``lb_tick`` to ``ub_tick``,
+        inclusive. The index is calculated as `price in ticks - lb_tick`. The quantities are copied into a new array.
+
+        Args:
+            lb_tick: The lower bound price in ticks.
+            ub_tick: The upper bound price in ticks.
+
+        Returns:
+            The bid market depth array.
+        """
+        arr = np.zeros(max(ub_tick + 1 - lb_tick, 0), float64)
+        hashmapdepth_bid_depth_range(self.ptr, lb_tick, ub_tick, arr.ctypes.data)
+        return arr
+
+    def ask_depth_range(self, lb_tick: int64, ub_tick: int64) -> np.ndarray[Any, float64]:
+        """
+        Returns the ask market depth array, which contains the quantities at prices from ``lb_tick`` to ``ub_tick``,
+        inclusive. The index is calculated as `price in ticks - lb_tick`. The quantities are copied into a new array.
+
+        Args:
+            lb_tick: The lower bound price in ticks.
+            ub_tick: The upper bound price in ticks.
+
+        Returns:
+            The ask market depth array.
+        """
+        arr = np.zeros(max(ub_tick + 1 - lb_tick, 0), float64)
+        hashmapdepth_ask_depth_range(self.ptr, lb_tick, ub_tick, arr.ctypes.data)
+        return arr
+
     def snapshot(self) -> EVENT_ARRAY:
         length = uint64(0)
         len_ptr = ptr_from_val(length)
@@ -194,6 +242,14 @@ roivecdepth_ask_qty_at_tick = lib.roivecdepth_ask_qty_at_tick
 roivecdepth_ask_qty_at_tick.restype = c_double
 roivecdepth_ask_qty_at_tick.argtypes = [c_void_p, c_int64]
 
+roivecdepth_roi_lb_tick = lib.roivecdepth_roi_lb_tick
+roivecdepth_roi_lb_tick.restype = c_int64
+roivecdepth_roi_lb_tick.argtypes = [c_void_p]
+
+roivecdepth_roi_ub_tick = lib.roivecdepth_roi_ub_tick
+roivecdepth_roi_ub_tick.restype = c_int64
+roivecdepth_roi_ub_tick.argtypes = [c_void_p]
+
 roivecdepth_bid_depth = lib.roivecdepth_bid_depth
 roivecdepth_bid_depth.restype = c_void_p
 roivecdepth_bid_depth.argtypes = [c_void_p, POINTER(c_uint64)]
@@ -202,6 +258,14 @@ roivecdepth_ask_depth = lib.roivecdepth_ask_depth
 roivecdepth_ask_depth.restype = c_void_p
 roivecdepth_ask_depth.argtypes = [c_void_p, POINTER(c_uint64)]
 
+roivecdepth_bid_depth_range = lib.roivecdepth_bid_depth_range
+roivecdepth_bid_depth_range.restype = None
+roivecdepth_bid_depth_range.argtypes = [c_void_p, c_int64, c_int64, c_void_p]
+
+roivecdepth_ask_depth_range = lib.roivecdepth_ask_depth_range
+roivecdepth_ask_depth_range.restype = None
+roivecdepth_ask_depth_range.argtypes = [c_void_p, c_int64, c_int64, c_void_p]
+
 
 class ROIVectorMarketDepth:
     ptr: voidptr
@@ -275,6 +339,22 @@ class ROIVectorMarketDepth:
         """
         return roivecdepth_ask_qty_at_tick(self.ptr, price_tick)
 
+    @property
+    def roi_lb_tick(self) -> int64:
+        """
+        Returns the lower bound of the range of interest in ticks, which changes as the range is re-centered if
+        ``roi_auto_recenter`` is enabled.
+        """
+        return roivecdepth_roi_lb_tick(self.ptr)
+
+    @property
+    def roi_ub_tick(self) -> int64:
+        """
+        Returns the upper bound of the range of interest in ticks, which changes as the range is re-centered if
+        ``roi_auto_recenter`` is enabled.
+        """
+        return roivecdepth_roi_ub_tick(self.ptr)
+
     @property
     def bid_depth(self) -> np.ndarray[Any, float64]:
         """
@@ -311,6 +391,48 @@ class ROIVectorMarketDepth:
             float64
         )
 
+    def bid_depth_range(self, lb_tick: int64, ub_tick: int64) -> np.ndarray[Any, float64]:
+        """
+        Returns the bid market depth array, which contains the quantities at prices from ``lb_tick`` to ``ub_tick``,
+        inclusive. The index is calculated as `price in ticks - lb_tick`. If the range lies within the range of
+        interest, the array is a view of the market depth without copying, which is only valid until the market depth
+        is next updated. Otherwise, the quantities are copied into a new array.
+
+        Args:
+            lb_tick: The lower bound price in ticks.
+            ub_tick: The upper bound price in ticks.
+
+        Returns:
+            The bid market depth array.
+        """
+        roi_lb_tick = self.roi_lb_tick
+        if roi_lb_tick <= lb_tick and ub_tick <= self.roi_ub_tick:
+            return self.bid_depth[lb_tick - roi_lb_tick:ub_tick + 1 - roi_lb_tick]
+        arr = np.zeros(max(ub_tick + 1 - lb_tick, 0), float64)
+        roivecdepth_bid_depth_range(self.ptr, lb_tick, ub_tick, arr.ctypes.data)
+        return arr
+
+    def ask_depth_range(self, lb_tick: int64, ub_tick: int64) -> np.ndarray[Any, float64]:
+        """
+        Returns the ask market depth array, which contains the quantities at prices from ``lb_tick`` to ``ub_tick``,
+        inclusive. The index is calculated as `price in ticks - lb_tick`. If the range lies within the range of
+        interest, the array is a view of the market depth without copying, which is only valid until the market depth
+        is next updated. Otherwise, the quantities are copied into a new array.
+
+        Args:
+            lb_tick: The lower bound price in ticks.
+            ub_tick: The upper bound price in ticks.
+
+        Returns:
+            The ask market depth array.
+        """
+        roi_lb_tick = self.roi_lb_tick
+        if roi_lb_tick <= lb_tick and ub_tick <= self.roi_ub_tick:
+            return self.ask_depth[lb_tick - roi_lb_tick:ub_tick + 1 - roi_lb_tick]
+        arr = np.zeros(max(ub_tick + 1 - lb_tick, 0), float64)
+        roivecdepth_ask_depth_range(self.ptr, lb_tick, ub_tick, arr.ctypes.data)
+        return arr
+
 
 ROIVectorMarketDepth_ = jitclass(ROIVectorMarketDepth)
 
@@ -548,6 +670,14 @@ hashmapbt_clear_last_trades = lib.hashmapbt_clear_last_trades
 hashmapbt_clear_last_trades.restype = c_void_p
 hashmapbt_clear_last_trades.argtypes = [c_void_p, c_uint64]
 
+hashmapbt_last_depth_updates = lib.hashmapbt_last_depth_updates
+hashmapbt_last_depth_updates.restype = c_void_p
+hashmapbt_last_depth_updates.argtypes = [c_void_p, c_uint64, POINTER(c_uint64)]
+
+hashmapbt_clear_last_depth_updates = lib.hashmapbt_clear_last_depth_updates
+hashmapbt_clear_last_depth_updates.restype = c_void_p
+hashmapbt_clear_last_depth_updates.argtypes = [c_void_p, c_uint64]
+
 hashmapbt_clear_inactive_orders = lib.hashmapbt_clear_inactive_orders
 hashmapbt_clear_inactive_orders.restype = c_void_p
 hashmapbt_clear_inactive_orders.argtypes = [c_void_p, c_uint64]
@@ -560,6 +690,10 @@ hashmapbt_state_values = lib.hashmapbt_state_values
 hashmapbt_state_values.restype = c_void_p
 hashmapbt_state_values.argtypes = [c_void_p, c_uint64]
 
+hashmapbt_portfolio = lib.hashmapbt_portfolio
+hashmapbt_portfolio.restype = c_void_p
+hashmapbt_portfolio.argtypes = [c_void_p, c_void_p]
+
 hashmapbt_feed_latency = lib.hashmapbt_feed_latency
 hashmapbt_feed_latency.restype = c_bool
 hashmapbt_feed_latency.argtypes = [c_void_p, c_uint64, POINTER(c_int64), POINTER(c_int64)]
@@ -568,6 +702,10 @@ hashmapbt_order_latency = lib.hashmapbt_order_latency
 hashmapbt_order_latency.restype = c_bool
 hashmapbt_order_latency.argtypes = [c_void_p, c_uint64, POINTER(c_int64), POINTER(c_int64), POINTER(c_int64)]
 
+hashmapbt_event_stats = lib.hashmapbt_event_stats
+hashmapbt_event_stats.restype = c_void_p
+hashmapbt_event_stats.argtypes = [c_void_p, c_uint64, c_void_p]
+
 hashmapbt_goto_end = lib.hashmapbt_goto_end
 hashmapbt_goto_end.restype = c_int64
 hashmapbt_goto_end.argtypes = [c_void_p]
@@ -629,6 +767,16 @@ class HashMapMarketDepthBacktest:
         )
         return StateValues_(arr)
 
+    def portfolio(self) -> np.ndarray[Any, portfolio_dtype]:
+        """
+        Returns:
+            The portfolio-level values aggregated across all assets, such as `equity`, `gross_exposure`,
+            `net_exposure`, and `fee`.
+        """
+        arr = np.empty(1, portfolio_dtype)
+        hashmapbt_portfolio(self.ptr, arr.ctypes.data)
+        return arr[0]
+
     def last_trades(self, asset_no: uint64) -> EVENT_ARRAY:
         """
         Args:
@@ -657,6 +805,35 @@ class HashMapMarketDepthBacktest:
         """
         hashmapbt_clear_last_trades(self.ptr, asset_no)
 
+    def last_depth_updates(self, asset_no: uint64) -> DEPTH_UPDATE_ARRAY:
+        """
+        Args:
+            asset_no: Asset number from which the depth updates will be retrieved.
+
+        Returns:
+            An array of the changes applied to the price levels of the market depth for the specific
+            asset. It is only stored if ``last_depth_updates_capacity`` is set on the asset.
+        """
+        length = uint64(0)
+        len_ptr = ptr_from_val(length)
+        ptr = hashmapbt_last_depth_updates(self.ptr, asset_no, len_ptr)
+        return numba.carray(
+            address_as_void_pointer(ptr),
+            val_from_ptr(len_ptr),
+            depth_update_dtype
+        )
+
+    def clear_last_depth_updates(self, asset_no: uint64) -> None:
+        """
+        Clears the last depth updates from the buffer for :func:`last_depth_updates`.
+
+        Args:
+            asset_no: Asset number at which this command will be executed.
+                      If :const:`ALL_ASSETS <hftbacktest.types.ALL_ASSETS>`,
+                      all last depth updates in any assets will be cleared.
+        """
+        hashmapbt_clear_last_depth_updates(self.ptr, asset_no)
+
     def orders(self, asset_no: uint64) -> OrderDict:
         """
         Args:
@@ -890,6 +1067,21 @@ class HashMapMarketDepthBacktest:
             return val_from_ptr(req_ts_ptr), val_from_ptr(exch_ts_ptr), val_from_ptr(resp_ts_ptr)
         return None
 
+    def event_stats(self, asset_no: uint64) -> np.ndarray[Any, event_stats_dtype]:
+        """
+        Returns the counters of the events processed since the last call, and resets them. This can be used
+        to detect a dead or anomalously quiet feed.
+
+        Args:
+            asset_no: Asset number from which the event statistics will be retrieved.
+
+        Returns:
+            The event statistics, such as `depth_updates`, `trades`, `order_updates`, and `dropped`.
+        """
+        arr = np.empty(1, event_stats_dtype)
+        hashmapbt_event_stats(self.ptr, asset_no, arr.ctypes.data)
+        return arr[0]
+
     def _goto_end(self) -> int64:
         return hashmapbt_goto_end(self.ptr)
 
@@ -971,6 +1163,14 @@ roivecbt_clear_last_trades = lib.roivecbt_clear_last_trades
 roivecbt_clear_last_trades.restype = c_void_p
 roivecbt_clear_last_trades.argtypes = [c_void_p, c_uint64]
 
+roivecbt_last_depth_updates = lib.roivecbt_last_depth_updates
+roivecbt_last_depth_updates.restype = c_void_p
+roivecbt_last_depth_updates.argtypes = [c_void_p, c_uint64, POINTER(c_uint64)]
+
+roivecbt_clear_last_depth_updates = lib.roivecbt_clear_last_depth_updates
+roivecbt_clear_last_depth_updates.restype = c_void_p
+roivecbt_clear_last_depth_updates.argtypes = [c_void_p, c_uint64]
+
 roivecbt_clear_inactive_orders = lib.roivecbt_clear_inactive_orders
 roivecbt_clear_inactive_orders.restype = c_void_p
 roivecbt_clear_inactive_orders.argtypes = [c_void_p, c_uint64]
@@ -983,6 +1183,10 @@ roivecbt_state_values = lib.roivecbt_state_values
 roivecbt_state_values.restype = c_void_p
 roivecbt_state_values.argtypes = [c_void_p, c_uint64]
 
+roivecbt_portfolio = lib.roivecbt_portfolio
+roivecbt_portfolio.restype = c_void_p
+roivecbt_portfolio.argtypes = [c_void_p, c_void_p]
+
 roivecbt_feed_latency = lib.roivecbt_feed_latency
 roivecbt_feed_latency.restype = c_bool
 roivecbt_feed_latency.argtypes = [c_void_p, c_uint64, POINTER(c_int64), POINTER(c_int64)]
@@ -991,6 +1195,10 @@ roivecbt_order_latency = lib.roivecbt_order_latency
 roivecbt_order_latency.restype = c_bool
 roivecbt_order_latency.argtypes = [c_void_p, c_uint64, POINTER(c_int64), POINTER(c_int64), POINTER(c_int64)]
 
+roivecbt_event_stats = lib.roivecbt_event_stats
+roivecbt_event_stats.restype = c_void_p
+roivecbt_event_stats.argtypes = [c_void_p, c_uint64, c_void_p]
+
 
 class ROIVectorMarketDepthBacktest:
     ptr: voidptr
@@ -1048,6 +1256,16 @@ class ROIVectorMarketDepthBacktest:
         )
         return StateValues_(arr)
 
+    def portfolio(self) -> np.ndarray[Any, portfolio_dtype]:
+        """
+        Returns:
+            The portfolio-level values aggregated across all assets, such as `equity`, `gross_exposure`,
+            `net_exposure`, and `fee`.
+        """
+        arr = np.empty(1, portfolio_dtype)
+        roivecbt_portfolio(self.ptr, arr.ctypes.data)
+        return arr[0]
+
     def last_trades(self, asset_no: uint64) -> EVENT_ARRAY:
         """
         Args:
@@ -1076,6 +1294,35 @@ class ROIVectorMarketDepthBacktest:
         """
         roivecbt_clear_last_trades(self.ptr, asset_no)
 
+    def last_depth_updates(self, asset_no: uint64) -> DEPTH_UPDATE_ARRAY:
+        """
+        Args:
+            asset_no: Asset number from which the depth updates will be retrieved.
+
+        Returns:
+            An array of the changes applied to the price levels of the market depth for the specific
+            asset. It is only stored if ``last_depth_updates_capacity`` is set on the asset.
+        """
+        length = uint64(0)
+        len_ptr = ptr_from_val(length)
+        ptr = roivecbt_last_depth_updates(self.ptr, asset_no, len_ptr)
+        return numba.carray(
+            address_as_void_pointer(ptr),
+            val_from_ptr(len_ptr),
+            depth_update_dtype
+        )
+
+    def clear_last_depth_updates(self, asset_no: uint64) -> None:
+        """
+        Clears the last depth updates from the buffer for :func:`last_depth_updates`.
+
+        Args:
+            asset_no: Asset number at which this command will be executed.
+                      If :const:`ALL_ASSETS <hftbacktest.types.ALL_ASSETS>`,
+                      all last depth updates in any assets will be cleared.
+        """
+        roivecbt_clear_last_depth_updates(self.ptr, asset_no)
+
     def orders(self, asset_no: uint64) -> OrderDict:
         """
         Args:
@@ -1309,6 +1556,21 @@ class ROIVectorMarketDepthBacktest:
             return val_from_ptr(req_ts_ptr), val_from_ptr(exch_ts_ptr), val_from_ptr(resp_ts_ptr)
         return None
 
+    def event_stats(self, asset_no: uint64) -> np.ndarray[Any, event_stats_dtype]:
+        """
+        Returns the counters of the events processed since the last call, and resets them. This can be used
+        to detect a dead or anomalously quiet feed.
+
+        Args:
+            asset_no: Asset number from which the event statistics will be retrieved.
+
+        Returns:
+            The event statistics, such as `depth_updates`, `trades`, `order_updates`, and `dropped`.
+        """
+        arr = np.empty(1, event_stats_dtype)
+        roivecbt_event_stats(self.ptr, asset_no, arr.ctypes.data)
+        return arr[0]
+
 
 ROIVectorMarketDepthBacktest_ = jitclass(ROIVectorMarketDepthBacktest)
 
@@ -1383,10 +1645,22 @@ if LIVE_FEATURE:
     hashmaplive_cancel.restype = c_int64
     hashmaplive_cancel.argtypes = [c_void_p, c_uint64, c_uint64, c_bool]
 
+    hashmaplive_resync = lib.hashmaplive_resync
+    hashmaplive_resync.restype = c_int64
+    hashmaplive_resync.argtypes = [c_void_p, c_uint64]
+
     hashmaplive_clear_last_trades = lib.hashmaplive_clear_last_trades
     hashmaplive_clear_last_trades.restype = c_void_p
     hashmaplive_clear_last_trades.argtypes = [c_void_p, c_uint64]
 
+    hashmaplive_last_depth_updates = lib.hashmaplive_last_depth_updates
+    hashmaplive_last_depth_updates.restype = c_void_p
+    hashmaplive_last_depth_updates.argtypes = [c_void_p, c_uint64, POINTER(c_uint64)]
+
+    hashmaplive_clear_last_depth_updates = lib.hashmaplive_clear_last_depth_updates
+    hashmaplive_clear_last_depth_updates.restype = c_void_p
+    hashmaplive_clear_last_depth_updates.argtypes = [c_void_p, c_uint64]
+
     hashmaplive_clear_inactive_orders = lib.hashmaplive_clear_inactive_orders
     hashmaplive_clear_inactive_orders.restype = c_void_p
     hashmaplive_clear_inactive_orders.argtypes = [c_void_p, c_uint64]
@@ -1399,6 +1673,10 @@ if LIVE_FEATURE:
     hashmaplive_state_values.restype = c_void_p
     hashmaplive_state_values.argtypes = [c_void_p, c_uint64]
 
+    hashmaplive_portfolio = lib.hashmaplive_portfolio
+    hashmaplive_portfolio.restype = c_void_p
+    hashmaplive_portfolio.argtypes = [c_void_p, c_void_p]
+
     hashmaplive_feed_latency = lib.hashmaplive_feed_latency
     hashmaplive_feed_latency.restype = c_bool
     hashmaplive_feed_latency.argtypes = [c_void_p, c_uint64, POINTER(c_int64), POINTER(c_int64)]
@@ -1407,6 +1685,10 @@ if LIVE_FEATURE:
     hashmaplive_order_latency.restype = c_bool
     hashmaplive_order_latency.argtypes = [c_void_p, c_uint64, POINTER(c_int64), POINTER(c_int64), POINTER(c_int64)]
 
+    hashmaplive_event_stats = lib.hashmaplive_event_stats
+    hashmaplive_event_stats.restype = c_void_p
+    hashmaplive_event_stats.argtypes = [c_void_p, c_uint64, c_void_p]
+
 
     class HashMapMarketDepthLiveBot:
         ptr: voidptr
@@ -1464,6 +1746,16 @@ if LIVE_FEATURE:
             )
             return StateValues_(arr)
 
+        def portfolio(self) -> np.ndarray[Any, portfolio_dtype]:
+            """
+            Returns:
+                The portfolio-level values aggregated across all assets, such as `equity`, `gross_exposure`,
+                `net_exposure`, and `fee`.
+            """
+            arr = np.empty(1, portfolio_dtype)
+            hashmaplive_portfolio(self.ptr, arr.ctypes.data)
+            return arr[0]
+
         def last_trades(self, asset_no: uint64) -> EVENT_ARRAY:
             """
             Args:
@@ -1492,6 +1784,35 @@ if LIVE_FEATURE:
             """
             hashmaplive_clear_last_trades(self.ptr, asset_no)
 
+        def last_depth_updates(self, asset_no: uint64) -> DEPTH_UPDATE_ARRAY:
+            """
+            Args:
+                asset_no: Asset number from which the depth updates will be retrieved.
+
+            Returns:
+                An array of the changes applied to the price levels of the market depth for the specific
+                asset. It is only stored if ``last_depth_updates_capacity`` is set on the asset.
+            """
+            length = uint64(0)
+            len_ptr = ptr_from_val(length)
+            ptr = hashmaplive_last_depth_updates(self.ptr, asset_no, len_ptr)
+            return numba.carray(
+                address_as_void_pointer(ptr),
+                val_from_ptr(len_ptr),
+                depth_update_dtype
+            )
+
+        def clear_last_depth_updates(self, asset_no: uint64) -> None:
+            """
+            Clears the last depth updates from the buffer for :func:`last_depth_updates`.
+
+            Args:
+                asset_no: Asset number at which this command will be executed.
+                          If :const:`ALL_ASSETS <hftbacktest.types.ALL_ASSETS>`,
+                          all last depth updates in any assets will be cleared.
+            """
+            hashmaplive_clear_last_depth_updates(self.ptr, asset_no)
+
         def orders(self, asset_no: uint64) -> OrderDict:
             """
             Args:
@@ -1599,6 +1920,21 @@ if LIVE_FEATURE:
             """
             return hashmaplive_cancel(self.ptr, asset_no, order_id, wait)
 
+        def resync(self, asset_no: uint64) -> int64:
+            """
+            Clears the market depth of the asset and requests a fresh market depth snapshot from the
+            connector, to recover from a suspected corruption of the order book without restarting
+            the bot.
+
+            Args:
+                asset_no: Asset number at which this command will be executed.
+
+            Returns:
+                * `0` when it successfully requests the resync.
+                * Otherwise, an error occurred.
+            """
+            return hashmaplive_resync(self.ptr, asset_no)
+
         def clear_inactive_orders(self, asset_no: uint64) -> None:
             """
             Clears inactive orders from the local order dictionary whose status is neither
@@ -1725,6 +2061,21 @@ if LIVE_FEATURE:
                 return val_from_ptr(req_ts_ptr), val_from_ptr(exch_ts_ptr), val_from_ptr(resp_ts_ptr)
             return None
 
+        def event_stats(self, asset_no: uint64) -> np.ndarray[Any, event_stats_dtype]:
+            """
+            Returns the counters of the events processed since the last call, and resets them. This can be used
+            to detect a dead or anomalously quiet feed.
+
+            Args:
+                asset_no: Asset number from which the event statistics will be retrieved.
+
+            Returns:
+                The event statistics, such as `depth_updates`, `trades`, `order_updates`, and `dropped`.
+            """
+            arr = np.empty(1, event_stats_dtype)
+            hashmaplive_event_stats(self.ptr, asset_no, arr.ctypes.data)
+            return arr[0]
+
         def _goto_end(self) -> int64:
             return hashmaplive_goto_end(self.ptr)
 
@@ -1802,10 +2153,22 @@ if LIVE_FEATURE:
     roiveclive_cancel.restype = c_int64
     roiveclive_cancel.argtypes = [c_void_p, c_uint64, c_uint64, c_bool]
 
+    roiveclive_resync = lib.roiveclive_resync
+    roiveclive_resync.restype = c_int64
+    roiveclive_resync.argtypes = [c_void_p, c_uint64]
+
     roiveclive_clear_last_trades = lib.roiveclive_clear_last_trades
     roiveclive_clear_last_trades.restype = c_void_p
     roiveclive_clear_last_trades.argtypes = [c_void_p, c_uint64]
 
+    roiveclive_last_depth_updates = lib.roiveclive_last_depth_updates
+    roiveclive_last_depth_updates.restype = c_void_p
+    roiveclive_last_depth_updates.argtypes = [c_void_p, c_uint64, POINTER(c_uint64)]
+
+    roiveclive_clear_last_depth_updates = lib.roiveclive_clear_last_depth_updates
+    roiveclive_clear_last_depth_updates.restype = c_void_p
+    roiveclive_clear_last_depth_updates.argtypes = [c_void_p, c_uint64]
+
     roiveclive_clear_inactive_orders = lib.roiveclive_clear_inactive_orders
     roiveclive_clear_inactive_orders.restype = c_void_p
     roiveclive_clear_inactive_orders.argtypes = [c_void_p, c_uint64]
@@ -1818,6 +2181,10 @@ if LIVE_FEATURE:
     roiveclive_state_values.restype = c_void_p
     roiveclive_state_values.argtypes = [c_void_p, c_uint64]
 
+    roiveclive_portfolio = lib.roiveclive_portfolio
+    roiveclive_portfolio.restype = c_void_p
+    roiveclive_portfolio.argtypes = [c_void_p, c_void_p]
+
     roiveclive_feed_latency = lib.roiveclive_feed_latency
     roiveclive_feed_latency.restype = c_bool
     roiveclive_feed_latency.argtypes = [c_void_p, c_uint64, POINTER(c_int64), POINTER(c_int64)]
@@ -1826,6 +2193,10 @@ if LIVE_FEATURE:
     roiveclive_order_latency.restype = c_bool
     roiveclive_order_latency.argtypes = [c_void_p, c_uint64, POINTER(c_int64), POINTER(c_int64), POINTER(c_int64)]
 
+    roiveclive_event_stats = lib.roiveclive_event_stats
+    roiveclive_event_stats.restype = c_void_p
+    roiveclive_event_stats.argtypes = [c_void_p, c_uint64, c_void_p]
+
 
     class ROIVectorMarketDepthLiveBot:
         ptr: voidptr
@@ -1883,6 +2254,16 @@ if LIVE_FEATURE:
             )
             return StateValues_(arr)
 
+        def portfolio(self) -> np.ndarray[Any, portfolio_dtype]:
+            """
+            Returns:
+                The portfolio-level values aggregated across all assets, such as `equity`, `gross_exposure`,
+                `net_exposure`, and `fee`.
+            """
+            arr = np.empty(1, portfolio_dtype)
+            roiveclive_portfolio(self.ptr, arr.ctypes.data)
+            return arr[0]
+
         def last_trades(self, asset_no: uint64) -> EVENT_ARRAY:
             """
             Args:
@@ -1911,6 +2292,35 @@ if LIVE_FEATURE:
             """
             roiveclive_clear_last_trades(self.ptr, asset_no)
 
+        def last_depth_updates(self, asset_no: uint64) -> DEPTH_UPDATE_ARRAY:
+            """
+            Args:
+                asset_no: Asset number from which the depth updates will be retrieved.
+
+            Returns:
+                An array of the changes applied to the price levels of the market depth for the specific
+                asset. It is only stored if ``last_depth_updates_capacity`` is set on the asset.
+            """
+            length = uint64(0)
+            len_ptr = ptr_from_val(length)
+            ptr = roiveclive_last_depth_updates(self.ptr, asset_no, len_ptr)
+            return numba.carray(
+                address_as_void_pointer(ptr),
+                val_from_ptr(len_ptr),
+                depth_update_dtype
+            )
+
+        def clear_last_depth_updates(self, asset_no: uint64) -> None:
+            """
+            Clears the last depth updates from the buffer for :func:`last_depth_updates`.
+
+            Args:
+                asset_no: Asset number at which this command will be executed.
+                          If :const:`ALL_ASSETS <hftbacktest.types.ALL_ASSETS>`,
+                          all last depth updates in any assets will be cleared.
+            """
+            roiveclive_clear_last_depth_updates(self.ptr, asset_no)
+
         def orders(self, asset_no: uint64) -> OrderDict:
             """
             Args:
@@ -2018,6 +2428,21 @@ if LIVE_FEATURE:
             """
             return roiveclive_cancel(self.ptr, asset_no, order_id, wait)
 
+        def resync(self, asset_no: uint64) -> int64:
+            """
+            Clears the market depth of the asset and requests a fresh market depth snapshot from the
+            connector, to recover from a suspected corruption of the order book without restarting
+            the bot.
+
+            Args:
+                asset_no: Asset number at which this command will be executed.
+
+            Returns:
+                * `0` when it successfully requests the resync.
+                * Otherwise, an error occurred.
+            """
+            return roiveclive_resync(self.ptr, asset_no)
+
         def clear_inactive_orders(self, asset_no: uint64) -> None:
             """
             Clears inactive orders from the local order dictionary whose status is neither
@@ -2144,5 +2569,20 @@ if LIVE_FEATURE:
                 return val_from_ptr(req_ts_ptr), val_from_ptr(exch_ts_ptr), val_from_ptr(resp_ts_ptr)
             return None
 
+        def event_stats(self, asset_no: uint64) -> np.ndarray[Any, event_stats_dtype]:
+            """
+            Returns the counters of the events processed since the last call, and resets them. This can be used
+            to detect a dead or anomalously quiet feed.
+
+            Args:
+                asset_no: Asset number from which the event statistics will be retrieved.
+
+            Returns:
+                The event statistics, such as `depth_updates`, `trades`, `order_updates`, and `dropped`.
+            """
+            arr = np.empty(1, event_stats_dtype)
+            roiveclive_event_stats(self.ptr, asset_no, arr.ctypes.data)
+            return arr[0]
+
 
     ROIVectorMarketDepthLiveBot_ = jitclass(ROIVectorMarketDepthLiveBot)
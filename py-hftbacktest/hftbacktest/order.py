@@ -41,6 +41,18 @@ PARTIALLY_FILLED = 5
 #: REJECTED
 REJECTED = 6
 
+#: PENDING_NEW
+PENDING_NEW = 7
+
+#: PENDING_CANCEL
+PENDING_CANCEL = 8
+
+#: PENDING_REPLACE
+PENDING_REPLACE = 9
+
+#: RATE_LIMITED
+RATE_LIMITED = 10
+
 #: Good 'till cancel
 GTC = 0
 
@@ -59,6 +71,15 @@ LIMIT = 0
 #: MARKET
 MARKET = 1
 
+#: One-way mode
+BOTH = 0
+
+#: Hedge mode's long position
+LONG = 1
+
+#: Hedge mode's short position
+SHORT = 2
+
 
 class Order:
     arr: from_dtype(order_dtype)[:]
@@ -192,6 +213,21 @@ class Order:
         """
         return self.arr[0].status
 
+    @property
+    def state(self) -> uint8:
+        """
+        Returns the state of the order as seen by the local, which is :const:`PENDING_NEW`, :const:`PENDING_CANCEL` or
+        :const:`PENDING_REPLACE` while the corresponding request is in flight, and :obj:`status` otherwise.
+        """
+        req = self.arr[0].req
+        if req == NEW:
+            return PENDING_NEW
+        elif req == CANCELED:
+            return PENDING_CANCEL
+        elif req == PENDING_REPLACE:
+            return PENDING_REPLACE
+        return self.arr[0].status
+
     @property
     def side(self) -> uint8:
         """
@@ -215,5 +251,23 @@ class Order:
         """
         return self.arr[0].time_in_force
 
+    @property
+    def reduce_only(self) -> bool:
+        """
+        Returns whether the order may only reduce the position.
+        """
+        return self.arr[0].reduce_only
+
+    @property
+    def position_side(self) -> uint8:
+        """
+        Returns the position side of the order in hedge mode.
+
+            * :const:`BOTH`
+            * :const:`LONG`
+            * :const:`SHORT`
+        """
+        return self.arr[0].position_side
+
 
 Order_ = jitclass(Order)
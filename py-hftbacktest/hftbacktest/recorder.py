@@ -1,7 +1,7 @@
 from typing import Any
 
 import numpy as np
-from numba import uint64, from_dtype
+from numba import uint64, float64, from_dtype
 from numba.experimental import jitclass
 
 from .types import record_dtype
@@ -10,12 +10,19 @@ from .types import record_dtype
 @jitclass
 class Recorder_:
     records: from_dtype(record_dtype)[:, :]
+    metrics: float64[:, :, :]
+    metric_values: float64[:, :]
     i: uint64
 
-    def __init__(self, num_assets: uint64, record_size: uint64):
+    def __init__(self, num_assets: uint64, record_size: uint64, num_metrics: uint64):
         self.records = np.empty((record_size, num_assets), record_dtype)
+        self.metrics = np.full((record_size, num_assets, num_metrics), np.nan, np.float64)
+        self.metric_values = np.full((num_assets, num_metrics), np.nan, np.float64)
         self.i = 0
 
+    def set_metric(self, asset_no: uint64, metric_no: uint64, value: float64):
+        self.metric_values[asset_no, metric_no] = value
+
     def record(self, hbt):
         timestamp = hbt.current_timestamp
         for asset_no in range(hbt.num_assets):
@@ -30,6 +37,7 @@ class Recorder_:
             self.records[self.i, asset_no].num_trades = state_values.num_trades
             self.records[self.i, asset_no].trading_volume = state_values.trading_volume
             self.records[self.i, asset_no].trading_value = state_values.trading_value
+            self.metrics[self.i, asset_no, :] = self.metric_values[asset_no, :]
 
         self.i += 1
         if self.i == len(self.records):
@@ -37,8 +45,21 @@ class Recorder_:
 
 
 class Recorder:
-    def __init__(self, num_assets: uint64, record_size: uint64):
-        self._recorder = Recorder_(num_assets, record_size)
+    """
+    Records the state values of the strategy.
+
+    Args:
+        num_assets: The number of assets.
+        record_size: The maximum number of records.
+        metrics: The names of the custom metrics, such as a model's signal value or the target inventory, which are
+                 recorded alongside the state values. The value of a metric is set by
+                 ``recorder.set_metric(asset_no, metric_no, value)``, where ``metric_no`` is the index of the metric in
+                 this list, and is held until it is set again.
+    """
+
+    def __init__(self, num_assets: uint64, record_size: uint64, metrics: list[str] | None = None):
+        self._metric_names = list(metrics) if metrics is not None else []
+        self._recorder = Recorder_(num_assets, record_size, len(self._metric_names))
 
     @property
     def recorder(self):
@@ -47,7 +68,22 @@ class Recorder:
     def to_npz(self, file: str):
         data = self._recorder.records[:self._recorder.i]
         kwargs = {str(asset_no): data[:, asset_no] for asset_no in range(data.shape[1])}
+        if len(self._metric_names) > 0:
+            for asset_no in range(data.shape[1]):
+                kwargs[f'{asset_no}_metrics'] = self.get_metrics(asset_no)
         np.savez_compressed(file, **kwargs)
 
     def get(self, asset_no: int) -> np.ndarray[Any, record_dtype]:
         return self._recorder.records[:self._recorder.i, asset_no]
+
+    def get_metrics(self, asset_no: int) -> np.ndarray:
+        """
+        Returns the recorded custom metrics of the asset as a structured array with the ``timestamp`` field followed by
+        a field for each metric.
+        """
+        dtype = np.dtype([('timestamp', 'i8')] + [(name, 'f8') for name in self._metric_names])
+        metrics = np.empty(self._recorder.i, dtype)
+        metrics['timestamp'] = self._recorder.records[:self._recorder.i, asset_no]['timestamp']
+        for metric_no, name in enumerate(self._metric_names):
+            metrics[name] = self._recorder.metrics[:self._recorder.i, asset_no, metric_no]
+        return metrics
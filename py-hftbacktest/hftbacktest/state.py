@@ -37,5 +37,21 @@ class StateValues:
     def trading_value(self) -> float64:
         return self.arr[0].trading_value
 
+    @property
+    def long_position(self) -> float64:
+        return self.arr[0].long_position
+
+    @property
+    def short_position(self) -> float64:
+        return self.arr[0].short_position
+
+    @property
+    def long_balance(self) -> float64:
+        return self.arr[0].long_balance
+
+    @property
+    def short_balance(self) -> float64:
+        return self.arr[0].short_balance
+
 
 StateValues_ = jitclass(StateValues)
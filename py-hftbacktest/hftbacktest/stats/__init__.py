@@ -1,7 +1,8 @@
 from .stats import (
     Stats,
     InverseAssetRecord,
-    LinearAssetRecord
+    LinearAssetRecord,
+    QuantoAssetRecord
 )
 from .metrics import (
     Metric,
@@ -28,6 +29,7 @@ __all__ = (
     'Stats',
     'InverseAssetRecord',
     'LinearAssetRecord',
+    'QuantoAssetRecord',
 
     'Metric',
     'Ret',
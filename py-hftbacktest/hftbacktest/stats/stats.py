@@ -447,3 +447,32 @@ class InverseAssetRecord(Record):
                 self.df = self.df.with_columns(
                     pl.col('trading_value').diff().fill_null(0).alias('trading_value_')
                 )
+
+
+class QuantoAssetRecord(LinearAssetRecord):
+    """
+    The PnL is linear to the price but converted into the settlement currency, so this is equivalent to
+    :class:`LinearAssetRecord` with the contract size multiplied by the multiplier.
+    """
+
+    def __init__(self, data: NDArray | pl.DataFrame):
+        super().__init__(data)
+        self._multiplier = 1.0
+
+    def multiplier(self, multiplier: float) -> 'Self':
+        """
+        Sets the value of one unit of the quote currency in the settlement currency. The default value is `1.0`.
+
+        Args:
+            multiplier: The quanto multiplier.
+        """
+        self._multiplier = multiplier
+        return self
+
+    def prepare(self):
+        contract_size = self._contract_size
+        self._contract_size = contract_size * self._multiplier
+        try:
+            super().prepare()
+        finally:
+            self._contract_size = contract_size
@@ -20,6 +20,13 @@ DEPTH_SNAPSHOT_EVENT = 4
 #: Indicates that the best bid and best ask update event is received.
 DEPTH_BBO_EVENT = 5
 
+#: Indicates that the open interest of the instrument is updated. ``qty`` is the new open interest.
+OPEN_INTEREST_EVENT = 7
+
+#: Indicates that a position in the market is forcibly liquidated. Combined with :data:`BUY_EVENT` or
+#: :data:`SELL_EVENT`, it means the side of the liquidation order.
+LIQUIDATION_EVENT = 8
+
 #: Indicates that an order has been added to the order book.
 ADD_ORDER_EVENT = 10
 
@@ -66,7 +73,31 @@ state_values_dtype = np.dtype(
         ('fee', 'f8'),
         ('num_trades', 'i8'),
         ('trading_volume', 'f8'),
-        ('trading_value', 'f8')
+        ('trading_value', 'f8'),
+        ('long_position', 'f8'),
+        ('short_position', 'f8'),
+        ('long_balance', 'f8'),
+        ('short_balance', 'f8')
+    ],
+    align=True
+)
+
+portfolio_dtype = np.dtype(
+    [
+        ('equity', 'f8'),
+        ('gross_exposure', 'f8'),
+        ('net_exposure', 'f8'),
+        ('fee', 'f8')
+    ],
+    align=True
+)
+
+event_stats_dtype = np.dtype(
+    [
+        ('depth_updates', 'u8'),
+        ('trades', 'u8'),
+        ('order_updates', 'u8'),
+        ('dropped', 'u8')
     ],
     align=True
 )
@@ -87,6 +118,19 @@ event_dtype = np.dtype(
 
 EVENT_ARRAY = np.ndarray[Any, event_dtype]
 
+depth_update_dtype = np.dtype(
+    [
+        ('price_tick', 'i8'),
+        ('prev_qty', 'f8'),
+        ('qty', 'f8'),
+        ('timestamp', 'i8'),
+        ('side', 'i1')
+    ],
+    align=True
+)
+
+DEPTH_UPDATE_ARRAY = np.ndarray[Any, depth_update_dtype]
+
 order_dtype = np.dtype(
     [
         ('qty', 'f8'),
@@ -105,7 +149,9 @@ order_dtype = np.dtype(
         ('req', 'u1'),
         ('status', 'u1'),
         ('side', 'i1'),
-        ('time_in_force', 'u1')
+        ('time_in_force', 'u1'),
+        ('reduce_only', 'bool'),
+        ('position_side', 'u1')
     ],
     align=True
 )
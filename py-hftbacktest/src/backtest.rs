@@ -5,7 +5,7 @@ use std::{collections::HashMap, mem};
 use hftbacktest::{
     backtest::{Backtest, BacktestError},
     depth::{HashMapMarketDepth, ROIVectorMarketDepth},
-    prelude::{Bot, Event, Order, StateValues},
+    prelude::{Bot, DepthUpdate, Event, EventStats, Order, Portfolio, StateValues},
     types::{OrdType, TimeInForce},
 };
 
@@ -62,6 +62,7 @@ pub extern "C" fn hashmapbt_close(hbt_ptr: *mut HashMapMarketDepthBacktest) -> i
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -78,6 +79,7 @@ pub extern "C" fn hashmapbt_elapse(hbt_ptr: *mut HashMapMarketDepthBacktest, dur
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -97,6 +99,7 @@ pub extern "C" fn hashmapbt_elapse_bt(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -124,6 +127,7 @@ pub extern "C" fn hashmapbt_wait_order_response(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -144,6 +148,7 @@ pub extern "C" fn hashmapbt_wait_next_feed(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -178,6 +183,7 @@ pub extern "C" fn hashmapbt_submit_buy_order(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -211,6 +217,7 @@ pub extern "C" fn hashmapbt_submit_sell_order(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -232,6 +239,7 @@ pub extern "C" fn hashmapbt_cancel(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -249,6 +257,33 @@ pub extern "C" fn hashmapbt_clear_last_trades(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn hashmapbt_last_depth_updates(
+    hbt_ptr: *const HashMapMarketDepthBacktest,
+    asset_no: usize,
+    len_ptr: *mut usize,
+) -> *const DepthUpdate {
+    let hbt = unsafe { &*hbt_ptr };
+    let updates = hbt.last_depth_updates(asset_no);
+    unsafe {
+        *len_ptr = updates.len();
+    }
+    updates.as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn hashmapbt_clear_last_depth_updates(
+    hbt_ptr: *mut HashMapMarketDepthBacktest,
+    asset_no: usize,
+) {
+    let hbt = unsafe { &mut *hbt_ptr };
+    if asset_no == usize::MAX {
+        hbt.clear_last_depth_updates(None);
+    } else {
+        hbt.clear_last_depth_updates(Some(asset_no));
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn hashmapbt_clear_inactive_orders(
     hbt_ptr: *mut HashMapMarketDepthBacktest,
@@ -280,6 +315,17 @@ pub extern "C" fn hashmapbt_state_values(
     hbt.state_values(asset_no) as *const _
 }
 
+#[no_mangle]
+pub extern "C" fn hashmapbt_portfolio(
+    hbt_ptr: *const HashMapMarketDepthBacktest,
+    portfolio: *mut Portfolio,
+) {
+    let hbt = unsafe { &*hbt_ptr };
+    unsafe {
+        *portfolio = hbt.portfolio();
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn hashmapbt_feed_latency(
     hbt_ptr: *const HashMapMarketDepthBacktest,
@@ -322,6 +368,18 @@ pub extern "C" fn hashmapbt_order_latency(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn hashmapbt_event_stats(
+    hbt_ptr: *mut HashMapMarketDepthBacktest,
+    asset_no: usize,
+    event_stats: *mut EventStats,
+) {
+    let hbt = unsafe { &mut *hbt_ptr };
+    unsafe {
+        *event_stats = hbt.event_stats(asset_no);
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn hashmapbt_goto_end(hbt_ptr: *mut HashMapMarketDepthBacktest) -> i64 {
     let hbt = unsafe { &mut *hbt_ptr };
@@ -334,6 +392,7 @@ pub extern "C" fn hashmapbt_goto_end(hbt_ptr: *mut HashMapMarketDepthBacktest) -
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -388,6 +447,7 @@ pub extern "C" fn roivecbt_close(hbt_ptr: *mut ROIVectorMarketDepthBacktest) ->
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -407,6 +467,7 @@ pub extern "C" fn roivecbt_elapse(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -426,6 +487,7 @@ pub extern "C" fn roivecbt_elapse_bt(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -453,6 +515,7 @@ pub extern "C" fn roivecbt_wait_order_response(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -473,6 +536,7 @@ pub extern "C" fn roivecbt_wait_next_feed(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -507,6 +571,7 @@ pub extern "C" fn roivecbt_submit_buy_order(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -540,6 +605,7 @@ pub extern "C" fn roivecbt_submit_sell_order(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -561,6 +627,7 @@ pub extern "C" fn roivecbt_cancel(
         Err(BacktestError::InvalidOrderRequest) => 13,
         Err(BacktestError::InvalidOrderStatus) => 14,
         Err(BacktestError::EndOfData) => 15,
+        Err(BacktestError::RiskViolation(_)) => 16,
         Err(BacktestError::DataError(_)) => 100,
     }
 }
@@ -578,6 +645,33 @@ pub extern "C" fn roivecbt_clear_last_trades(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn roivecbt_last_depth_updates(
+    hbt_ptr: *const ROIVectorMarketDepthBacktest,
+    asset_no: usize,
+    len_ptr: *mut usize,
+) -> *const DepthUpdate {
+    let hbt = unsafe { &*hbt_ptr };
+    let updates = hbt.last_depth_updates(asset_no);
+    unsafe {
+        *len_ptr = updates.len();
+    }
+    updates.as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn roivecbt_clear_last_depth_updates(
+    hbt_ptr: *mut ROIVectorMarketDepthBacktest,
+    asset_no: usize,
+) {
+    let hbt = unsafe { &mut *hbt_ptr };
+    if asset_no == usize::MAX {
+        hbt.clear_last_depth_updates(None);
+    } else {
+        hbt.clear_last_depth_updates(Some(asset_no));
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn roivecbt_clear_inactive_orders(
     hbt_ptr: *mut ROIVectorMarketDepthBacktest,
@@ -609,6 +703,17 @@ pub extern "C" fn roivecbt_state_values(
     hbt.state_values(asset_no) as *const _
 }
 
+#[no_mangle]
+pub extern "C" fn roivecbt_portfolio(
+    hbt_ptr: *const ROIVectorMarketDepthBacktest,
+    portfolio: *mut Portfolio,
+) {
+    let hbt = unsafe { &*hbt_ptr };
+    unsafe {
+        *portfolio = hbt.portfolio();
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn roivecbt_feed_latency(
     hbt_ptr: *const ROIVectorMarketDepthBacktest,
@@ -650,3 +755,15 @@ pub extern "C" fn roivecbt_order_latency(
         },
     }
 }
+
+#[no_mangle]
+pub extern "C" fn roivecbt_event_stats(
+    hbt_ptr: *mut ROIVectorMarketDepthBacktest,
+    asset_no: usize,
+    event_stats: *mut EventStats,
+) {
+    let hbt = unsafe { &mut *hbt_ptr };
+    unsafe {
+        *event_stats = hbt.event_stats(asset_no);
+    }
+}
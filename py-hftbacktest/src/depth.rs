@@ -1,12 +1,21 @@
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
-use std::mem::forget;
+use std::{mem::forget, slice};
 
 use hftbacktest::{
     depth::HashMapMarketDepth,
     prelude::{ApplySnapshot, Event, MarketDepth, ROIVectorMarketDepth},
 };
 
+/// Fills `out` with the quantities at the prices from `lb_tick` to `ub_tick`, inclusive.
+fn fill_depth_range(lb_tick: i64, ub_tick: i64, out: *mut f64, qty_at_tick: impl Fn(i64) -> f64) {
+    let len = (ub_tick + 1 - lb_tick).max(0) as usize;
+    let out = unsafe { slice::from_raw_parts_mut(out, len) };
+    for (i, qty) in out.iter_mut().enumerate() {
+        *qty = qty_at_tick(lb_tick + i as i64);
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn hashmapdepth_best_bid_tick(ptr: *const HashMapMarketDepth) -> i64 {
     let depth = unsafe { &*ptr };
@@ -61,6 +70,32 @@ pub extern "C" fn hashmapdepth_ask_qty_at_tick(
     depth.ask_qty_at_tick(price_tick)
 }
 
+#[no_mangle]
+pub extern "C" fn hashmapdepth_bid_depth_range(
+    ptr: *const HashMapMarketDepth,
+    lb_tick: i64,
+    ub_tick: i64,
+    out: *mut f64,
+) {
+    let depth = unsafe { &*ptr };
+    fill_depth_range(lb_tick, ub_tick, out, |price_tick| {
+        depth.bid_qty_at_tick(price_tick)
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn hashmapdepth_ask_depth_range(
+    ptr: *const HashMapMarketDepth,
+    lb_tick: i64,
+    ub_tick: i64,
+    out: *mut f64,
+) {
+    let depth = unsafe { &*ptr };
+    fill_depth_range(lb_tick, ub_tick, out, |price_tick| {
+        depth.ask_qty_at_tick(price_tick)
+    });
+}
+
 #[no_mangle]
 pub extern "C" fn hashmapdepth_snapshot(
     ptr: *const HashMapMarketDepth,
@@ -136,6 +171,18 @@ pub extern "C" fn roivecdepth_ask_qty_at_tick(
     depth.ask_qty_at_tick(price_tick)
 }
 
+#[no_mangle]
+pub extern "C" fn roivecdepth_roi_lb_tick(ptr: *const ROIVectorMarketDepth) -> i64 {
+    let depth = unsafe { &*ptr };
+    depth.roi_lb
+}
+
+#[no_mangle]
+pub extern "C" fn roivecdepth_roi_ub_tick(ptr: *const ROIVectorMarketDepth) -> i64 {
+    let depth = unsafe { &*ptr };
+    depth.roi_ub
+}
+
 #[no_mangle]
 pub extern "C" fn roivecdepth_bid_depth(
     ptr: *const ROIVectorMarketDepth,
@@ -155,3 +202,29 @@ pub extern "C" fn roivecdepth_ask_depth(
     unsafe { *len = depth.ask_depth().len() }
     depth.ask_depth().as_ptr()
 }
+
+#[no_mangle]
+pub extern "C" fn roivecdepth_bid_depth_range(
+    ptr: *const ROIVectorMarketDepth,
+    lb_tick: i64,
+    ub_tick: i64,
+    out: *mut f64,
+) {
+    let depth = unsafe { &*ptr };
+    fill_depth_range(lb_tick, ub_tick, out, |price_tick| {
+        depth.bid_qty_at_tick(price_tick)
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn roivecdepth_ask_depth_range(
+    ptr: *const ROIVectorMarketDepth,
+    lb_tick: i64,
+    ub_tick: i64,
+    out: *mut f64,
+) {
+    let depth = unsafe { &*ptr };
+    fill_depth_range(lb_tick, ub_tick, out, |price_tick| {
+        depth.ask_qty_at_tick(price_tick)
+    });
+}
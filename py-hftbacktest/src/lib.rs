@@ -6,7 +6,7 @@ pub use depth::*;
 use hftbacktest::live::{Instrument, LiveBotBuilder};
 use hftbacktest::{
     backtest::{
-        assettype::{InverseAsset, LinearAsset},
+        assettype::{InverseAsset, LinearAsset, QuantoAsset},
         data::{read_npz_file, Data, DataPtr, FeedLatencyAdjustment, Reader},
         models::{
             CommonFees,
@@ -33,6 +33,7 @@ use hftbacktest::{
             LocalProcessor,
             NoPartialFillExchange,
             PartialFillExchange,
+            PostOnlyBehavior,
             Processor,
         },
         state::State,
@@ -47,7 +48,12 @@ pub use order::*;
 use pyo3::{exceptions::PyValueError, prelude::*};
 
 #[cfg(feature = "live")]
-use crate::live::{HashMapMarketDepthLiveBot, ROIVectorMarketDepthLiveBot};
+use crate::live::{
+    py_error_handler,
+    py_order_recv_hook,
+    HashMapMarketDepthLiveBot,
+    ROIVectorMarketDepthLiveBot,
+};
 
 mod backtest;
 mod depth;
@@ -59,6 +65,7 @@ mod order;
 pub enum AssetType {
     LinearAsset { contract_size: f64 },
     InverseAsset { contract_size: f64 },
+    QuantoAsset { contract_size: f64, multiplier: f64 },
 }
 
 #[derive(Clone)]
@@ -105,11 +112,14 @@ pub struct BacktestAsset {
     latency_model: LatencyModel,
     queue_model: QueueModel,
     exch_kind: ExchangeKind,
+    post_only: PostOnlyBehavior,
     tick_size: f64,
     lot_size: f64,
     last_trades_cap: usize,
+    last_depth_updates_cap: usize,
     roi_lb: f64,
     roi_ub: f64,
+    roi_auto_recenter: bool,
     initial_snapshot: Option<DataSource<Event>>,
     fee_model: FeeModel,
     latency_offset: i64,
@@ -136,9 +146,12 @@ impl BacktestAsset {
             tick_size: 0.0,
             lot_size: 0.0,
             exch_kind: ExchangeKind::NoPartialFillExchange {},
+            post_only: PostOnlyBehavior::Reject,
             last_trades_cap: 0,
+            last_depth_updates_cap: 0,
             roi_lb: 0.0,
             roi_ub: 0.0,
+            roi_auto_recenter: false,
             initial_snapshot: None,
             fee_model: FeeModel::TradingValueFeeModel {
                 fees: CommonFees::new(0.0, 0.0),
@@ -191,6 +204,17 @@ impl BacktestAsset {
         slf
     }
 
+    /// Sets whether to re-center the range of interest of the `ROIVectorMarketDepth <https://docs.rs/hftbacktest/latest/hftbacktest/depth/struct.ROIVectorMarketDepth.html>`_
+    /// around the mid-price when the price trends out of it.
+    /// Only valid if `ROIVectorMarketDepthBacktest` is built.
+    ///
+    /// Args:
+    ///     roi_auto_recenter: whether to re-center the range of interest. The default value is `False`.
+    pub fn roi_auto_recenter(mut slf: PyRefMut<Self>, roi_auto_recenter: bool) -> PyRefMut<Self> {
+        slf.roi_auto_recenter = roi_auto_recenter;
+        slf
+    }
+
     pub fn add_file(mut slf: PyRefMut<Self>, data: String) -> PyRefMut<Self> {
         slf.data.push(DataSource::File(data));
         slf
@@ -221,6 +245,23 @@ impl BacktestAsset {
         slf
     }
 
+    /// Sets the asset as a `QuantoAsset <https://docs.rs/hftbacktest/latest/hftbacktest/backtest/assettype/struct.QuantoAsset.html>`_.
+    ///
+    /// Args:
+    ///     contract_size: contract size of the asset.
+    ///     multiplier: value of one unit of the quote currency in the settlement currency.
+    pub fn quanto_asset(
+        mut slf: PyRefMut<Self>,
+        contract_size: f64,
+        multiplier: f64,
+    ) -> PyRefMut<Self> {
+        slf.asset_type = AssetType::QuantoAsset {
+            contract_size,
+            multiplier,
+        };
+        slf
+    }
+
     /// Uses `ConstantLatency <https://docs.rs/hftbacktest/latest/hftbacktest/backtest/models/struct.ConstantLatency.html>`_
     /// for the order latency model.
     /// The units of the arguments should match the timestamp units of your data. Nanoseconds are
@@ -406,6 +447,20 @@ impl BacktestAsset {
         slf
     }
 
+    /// Rejects a post-only order that would take liquidity upon arrival at the exchange. This is
+    /// the default behavior.
+    pub fn post_only_reject(mut slf: PyRefMut<Self>) -> PyRefMut<Self> {
+        slf.post_only = PostOnlyBehavior::Reject;
+        slf
+    }
+
+    /// Reprices a post-only order that would take liquidity upon arrival at the exchange to one
+    /// tick inside the opposite best price, so that it rests on the book.
+    pub fn post_only_adjust(mut slf: PyRefMut<Self>) -> PyRefMut<Self> {
+        slf.post_only = PostOnlyBehavior::Adjust;
+        slf
+    }
+
     /// Sets the initial capacity of the vector storing the last market trades.
     /// The default value is `0`, indicating that no last trades are stored.
     pub fn last_trades_capacity(mut slf: PyRefMut<Self>, capacity: usize) -> PyRefMut<Self> {
@@ -413,6 +468,13 @@ impl BacktestAsset {
         slf
     }
 
+    /// Sets the initial capacity of the vector storing the last depth updates.
+    /// The default value is `0`, indicating that no last depth updates are stored.
+    pub fn last_depth_updates_capacity(mut slf: PyRefMut<Self>, capacity: usize) -> PyRefMut<Self> {
+        slf.last_depth_updates_cap = capacity;
+        slf
+    }
+
     /// Uses `TradingValueFeeModel <https://docs.rs/hftbacktest/latest/hftbacktest/backtest/models/struct.TradingValueFeeModel.html>`_.
     /// A negative fee represents rebates.
     pub fn trading_value_fee_model(
@@ -490,7 +552,11 @@ pub fn build_hashmap_backtest(assets: Vec<PyRefMut<BacktestAsset>>) -> PyResult<
             HashMapMarketDepth,
             [
                 LinearAsset { contract_size },
-                InverseAsset { contract_size }
+                InverseAsset { contract_size },
+                QuantoAsset {
+                    contract_size,
+                    multiplier
+                }
             ],
             [
                 ConstantLatency {
@@ -544,7 +610,11 @@ pub fn build_roivec_backtest(assets: Vec<PyRefMut<BacktestAsset>>) -> PyResult<u
             ROIVectorMarketDepth,
             [
                 LinearAsset { contract_size },
-                InverseAsset { contract_size }
+                InverseAsset { contract_size },
+                QuantoAsset {
+                    contract_size,
+                    multiplier
+                }
             ],
             [
                 ConstantLatency {
@@ -588,8 +658,10 @@ pub struct LiveInstrument {
     tick_size: f64,
     lot_size: f64,
     last_trades_cap: usize,
+    last_depth_updates_cap: usize,
     roi_lb: f64,
     roi_ub: f64,
+    roi_auto_recenter: bool,
 }
 
 unsafe impl Send for LiveInstrument {}
@@ -606,8 +678,10 @@ impl LiveInstrument {
             tick_size: 0.0,
             lot_size: 0.0,
             last_trades_cap: 0,
+            last_depth_updates_cap: 0,
             roi_lb: 0.0,
             roi_ub: 0.0,
+            roi_auto_recenter: false,
         }
     }
 
@@ -642,6 +716,13 @@ impl LiveInstrument {
         slf
     }
 
+    /// Sets the initial capacity of the vector storing the last depth updates.
+    /// The default value is `0`, indicating that no last depth updates are stored.
+    pub fn last_depth_updates_capacity(mut slf: PyRefMut<Self>, capacity: usize) -> PyRefMut<Self> {
+        slf.last_depth_updates_cap = capacity;
+        slf
+    }
+
     /// Sets the lower bound price of the `ROIVectorMarketDepth <https://docs.rs/hftbacktest/latest/hftbacktest/depth/struct.ROIVectorMarketDepth.html>`_.
     /// Only valid if `ROIVectorMarketDepthLiveBot` is built.
     ///
@@ -661,55 +742,91 @@ impl LiveInstrument {
         slf.roi_ub = roi_ub;
         slf
     }
+
+    /// Sets whether to re-center the range of interest of the `ROIVectorMarketDepth <https://docs.rs/hftbacktest/latest/hftbacktest/depth/struct.ROIVectorMarketDepth.html>`_
+    /// around the mid-price when the price trends out of it.
+    /// Only valid if `ROIVectorMarketDepthLiveBot` is built.
+    ///
+    /// Args:
+    ///     roi_auto_recenter: whether to re-center the range of interest. The default value is `False`.
+    pub fn roi_auto_recenter(mut slf: PyRefMut<Self>, roi_auto_recenter: bool) -> PyRefMut<Self> {
+        slf.roi_auto_recenter = roi_auto_recenter;
+        slf
+    }
 }
 
 #[cfg(feature = "live")]
 #[pyfunction]
-pub fn build_hashmap_livebot(instruments: Vec<PyRefMut<LiveInstrument>>) -> PyResult<usize> {
+#[pyo3(signature = (instruments, error_handler=None, order_recv_hook=None))]
+pub fn build_hashmap_livebot(
+    instruments: Vec<PyRefMut<LiveInstrument>>,
+    error_handler: Option<PyObject>,
+    order_recv_hook: Option<PyObject>,
+) -> PyResult<usize> {
     let mut builder = LiveBotBuilder::new();
     for instrument in instruments {
-        builder = builder.register(Instrument::new(
-            &instrument.connector_name,
-            &instrument.symbol,
-            instrument.tick_size,
-            instrument.lot_size,
-            HashMapMarketDepth::new(instrument.tick_size, instrument.lot_size),
-            instrument.last_trades_cap,
-        ));
+        builder = builder.register(
+            Instrument::new(
+                &instrument.connector_name,
+                &instrument.symbol,
+                instrument.tick_size,
+                instrument.lot_size,
+                HashMapMarketDepth::new(instrument.tick_size, instrument.lot_size),
+                instrument.last_trades_cap,
+            )
+            .last_depth_updates_capacity(instrument.last_depth_updates_cap),
+        );
+    }
+    if let Some(handler) = error_handler {
+        builder = builder.error_handler(py_error_handler(handler));
+    }
+    if let Some(hook) = order_recv_hook {
+        builder = builder.order_recv_hook(py_order_recv_hook(hook));
     }
     let hbt: HashMapMarketDepthLiveBot = builder
-        .error_handler(|_error| Ok(()))
-        .order_recv_hook(|_prev, _new| Ok(()))
         .build()
-        .unwrap();
+        .map_err(|error| PyValueError::new_err(error.to_string()))?;
 
     Ok(Box::into_raw(Box::new(hbt)) as *mut c_void as usize)
 }
 
 #[cfg(feature = "live")]
 #[pyfunction]
-pub fn build_roivec_livebot(instruments: Vec<PyRefMut<LiveInstrument>>) -> PyResult<usize> {
+#[pyo3(signature = (instruments, error_handler=None, order_recv_hook=None))]
+pub fn build_roivec_livebot(
+    instruments: Vec<PyRefMut<LiveInstrument>>,
+    error_handler: Option<PyObject>,
+    order_recv_hook: Option<PyObject>,
+) -> PyResult<usize> {
     let mut builder = LiveBotBuilder::new();
     for instrument in instruments {
-        builder = builder.register(Instrument::new(
-            &instrument.connector_name,
-            &instrument.symbol,
-            instrument.tick_size,
-            instrument.lot_size,
-            ROIVectorMarketDepth::new(
+        builder = builder.register(
+            Instrument::new(
+                &instrument.connector_name,
+                &instrument.symbol,
                 instrument.tick_size,
                 instrument.lot_size,
-                instrument.roi_lb,
-                instrument.roi_ub,
-            ),
-            instrument.last_trades_cap,
-        ));
+                ROIVectorMarketDepth::new(
+                    instrument.tick_size,
+                    instrument.lot_size,
+                    instrument.roi_lb,
+                    instrument.roi_ub,
+                )
+                .auto_recenter(instrument.roi_auto_recenter),
+                instrument.last_trades_cap,
+            )
+            .last_depth_updates_capacity(instrument.last_depth_updates_cap),
+        );
+    }
+    if let Some(handler) = error_handler {
+        builder = builder.error_handler(py_error_handler(handler));
+    }
+    if let Some(hook) = order_recv_hook {
+        builder = builder.order_recv_hook(py_order_recv_hook(hook));
     }
     let hbt: ROIVectorMarketDepthLiveBot = builder
-        .error_handler(|_error| Ok(()))
-        .order_recv_hook(|_prev, _new| Ok(()))
         .build()
-        .unwrap();
+        .map_err(|error| PyValueError::new_err(error.to_string()))?;
 
     Ok(Box::into_raw(Box::new(hbt)) as *mut c_void as usize)
 }
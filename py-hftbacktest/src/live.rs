@@ -5,13 +5,94 @@ use std::{collections::HashMap, mem};
 use hftbacktest::{
     depth::{HashMapMarketDepth, ROIVectorMarketDepth},
     live::{ipc::iceoryx::IceoryxUnifiedChannel, BotError, LiveBot},
-    prelude::{Bot, Event, Order, StateValues},
-    types::{OrdType, TimeInForce},
+    prelude::{Bot, DepthUpdate, Event, EventStats, Order, Portfolio, StateValues},
+    types::{ErrorKind, LiveError, OrdType, TimeInForce, Value},
 };
+use pyo3::{prelude::*, types::PyDict, IntoPyObjectExt};
 
 pub type HashMapMarketDepthLiveBot = LiveBot<IceoryxUnifiedChannel, HashMapMarketDepth>;
 pub type ROIVectorMarketDepthLiveBot = LiveBot<IceoryxUnifiedChannel, ROIVectorMarketDepth>;
 
+fn value_to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    match value {
+        Value::String(v) => v.into_py_any(py),
+        Value::Int(v) => v.into_py_any(py),
+        Value::Float(v) => v.into_py_any(py),
+        Value::Bool(v) => v.into_py_any(py),
+        Value::List(v) => v
+            .iter()
+            .map(|item| value_to_py(py, item))
+            .collect::<PyResult<Vec<_>>>()?
+            .into_py_any(py),
+        Value::Map(v) => {
+            let dict = PyDict::new(py);
+            for (key, item) in v {
+                dict.set_item(key, value_to_py(py, item)?)?;
+            }
+            dict.into_py_any(py)
+        },
+        Value::Empty => Ok(py.None()),
+    }
+}
+
+fn order_to_py(py: Python<'_>, order: &Order) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("order_id", order.order_id)?;
+    dict.set_item("price", order.price_tick as f64 * order.tick_size)?;
+    dict.set_item("price_tick", order.price_tick)?;
+    dict.set_item("qty", order.qty)?;
+    dict.set_item("leaves_qty", order.leaves_qty)?;
+    dict.set_item("exec_qty", order.exec_qty)?;
+    dict.set_item("exec_price", order.exec_price_tick as f64 * order.tick_size)?;
+    dict.set_item("side", order.side as i8)?;
+    dict.set_item("status", order.status as u8)?;
+    dict.set_item("req", order.req as u8)?;
+    dict.set_item("time_in_force", order.time_in_force as u8)?;
+    dict.set_item("order_type", order.order_type as u8)?;
+    dict.set_item("maker", order.maker)?;
+    dict.set_item("exch_timestamp", order.exch_timestamp)?;
+    dict.set_item("local_timestamp", order.local_timestamp)?;
+    dict.into_py_any(py)
+}
+
+/// Wraps a Python callable as the error handler of the live bot. The callable is invoked with the
+/// error kind and the detailed error value. The error kind is the name of the kind, or the code of
+/// a custom error kind. If the callable raises an exception, the bot returns
+/// [`BotError::Custom`].
+pub fn py_error_handler(handler: PyObject) -> impl Fn(LiveError) -> Result<(), BotError> {
+    move |error| {
+        Python::with_gil(|py| {
+            let kind = match error.kind {
+                ErrorKind::ConnectionInterrupted => "ConnectionInterrupted".into_py_any(py)?,
+                ErrorKind::CriticalConnectionError => "CriticalConnectionError".into_py_any(py)?,
+                ErrorKind::OrderError => "OrderError".into_py_any(py)?,
+                ErrorKind::ConnectorUnresponsive => "ConnectorUnresponsive".into_py_any(py)?,
+                ErrorKind::StaleFeed => "StaleFeed".into_py_any(py)?,
+                ErrorKind::SeqGap => "SeqGap".into_py_any(py)?,
+                ErrorKind::AuthFailure => "AuthFailure".into_py_any(py)?,
+                ErrorKind::Custom(code) => code.into_py_any(py)?,
+            };
+            let value = value_to_py(py, error.value())?;
+            handler.call1(py, (kind, value)).map(|_| ())
+        })
+        .map_err(|error| BotError::Custom(error.to_string()))
+    }
+}
+
+/// Wraps a Python callable as the order receive hook of the live bot. The callable is invoked with
+/// the previous and the new order, each as a `dict`. If the callable raises an exception, the bot
+/// returns [`BotError::Custom`].
+pub fn py_order_recv_hook(hook: PyObject) -> impl Fn(&Order, &Order) -> Result<(), BotError> {
+    move |prev, new| {
+        Python::with_gil(|py| {
+            let prev = order_to_py(py, prev)?;
+            let new = order_to_py(py, new)?;
+            hook.call1(py, (prev, new)).map(|_| ())
+        })
+        .map_err(|error| BotError::Custom(error.to_string()))
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn hashmaplive_current_timestamp(hbt_ptr: *const HashMapMarketDepthLiveBot) -> i64 {
     let hbt = unsafe { &*hbt_ptr };
@@ -63,6 +144,8 @@ pub extern "C" fn hashmaplive_close(hbt_ptr: *mut HashMapMarketDepthLiveBot) ->
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -82,6 +165,8 @@ pub extern "C" fn hashmaplive_elapse(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -101,6 +186,8 @@ pub extern "C" fn hashmaplive_elapse_bt(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -128,6 +215,8 @@ pub extern "C" fn hashmaplive_wait_order_response(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -148,6 +237,8 @@ pub extern "C" fn hashmaplive_wait_next_feed(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -182,6 +273,8 @@ pub extern "C" fn hashmaplive_submit_buy_order(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -215,6 +308,8 @@ pub extern "C" fn hashmaplive_submit_sell_order(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -236,6 +331,28 @@ pub extern "C" fn hashmaplive_cancel(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn hashmaplive_resync(
+    hbt_ptr: *mut HashMapMarketDepthLiveBot,
+    asset_no: usize,
+) -> i64 {
+    let hbt = unsafe { &mut *hbt_ptr };
+    match hbt.resync(asset_no) {
+        Ok(()) => 0,
+        Err(BotError::OrderIdExist) => 10,
+        Err(BotError::OrderNotFound) => 12,
+        Err(BotError::InvalidOrderStatus) => 14,
+        Err(BotError::InstrumentNotFound) => 16,
+        Err(BotError::Timeout) => 17,
+        Err(BotError::Interrupted) => 18,
+        Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -252,6 +369,33 @@ pub extern "C" fn hashmaplive_clear_last_trades(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn hashmaplive_last_depth_updates(
+    hbt_ptr: *const HashMapMarketDepthLiveBot,
+    asset_no: usize,
+    len_ptr: *mut usize,
+) -> *const DepthUpdate {
+    let hbt = unsafe { &*hbt_ptr };
+    let updates = hbt.last_depth_updates(asset_no);
+    unsafe {
+        *len_ptr = updates.len();
+    }
+    updates.as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn hashmaplive_clear_last_depth_updates(
+    hbt_ptr: *mut HashMapMarketDepthLiveBot,
+    asset_no: usize,
+) {
+    let hbt = unsafe { &mut *hbt_ptr };
+    if asset_no == usize::MAX {
+        hbt.clear_last_depth_updates(None);
+    } else {
+        hbt.clear_last_depth_updates(Some(asset_no));
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn hashmaplive_clear_inactive_orders(
     hbt_ptr: *mut HashMapMarketDepthLiveBot,
@@ -283,6 +427,17 @@ pub extern "C" fn hashmaplive_state_values(
     hbt.state_values(asset_no) as *const _
 }
 
+#[no_mangle]
+pub extern "C" fn hashmaplive_portfolio(
+    hbt_ptr: *const HashMapMarketDepthLiveBot,
+    portfolio: *mut Portfolio,
+) {
+    let hbt = unsafe { &*hbt_ptr };
+    unsafe {
+        *portfolio = hbt.portfolio();
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn hashmaplive_feed_latency(
     hbt_ptr: *const HashMapMarketDepthLiveBot,
@@ -325,6 +480,18 @@ pub extern "C" fn hashmaplive_order_latency(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn hashmaplive_event_stats(
+    hbt_ptr: *mut HashMapMarketDepthLiveBot,
+    asset_no: usize,
+    event_stats: *mut EventStats,
+) {
+    let hbt = unsafe { &mut *hbt_ptr };
+    unsafe {
+        *event_stats = hbt.event_stats(asset_no);
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn roiveclive_current_timestamp(hbt_ptr: *const ROIVectorMarketDepthLiveBot) -> i64 {
     let hbt = unsafe { &*hbt_ptr };
@@ -376,6 +543,8 @@ pub extern "C" fn roiveclive_close(hbt_ptr: *mut ROIVectorMarketDepthLiveBot) ->
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -395,6 +564,8 @@ pub extern "C" fn roiveclive_elapse(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -414,6 +585,8 @@ pub extern "C" fn roiveclive_elapse_bt(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -441,6 +614,8 @@ pub extern "C" fn roiveclive_wait_order_response(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -461,6 +636,8 @@ pub extern "C" fn roiveclive_wait_next_feed(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -495,6 +672,8 @@ pub extern "C" fn roiveclive_submit_buy_order(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -528,6 +707,8 @@ pub extern "C" fn roiveclive_submit_sell_order(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -549,6 +730,28 @@ pub extern "C" fn roiveclive_cancel(
         Err(BotError::Timeout) => 17,
         Err(BotError::Interrupted) => 18,
         Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn roiveclive_resync(
+    hbt_ptr: *mut ROIVectorMarketDepthLiveBot,
+    asset_no: usize,
+) -> i64 {
+    let hbt = unsafe { &mut *hbt_ptr };
+    match hbt.resync(asset_no) {
+        Ok(()) => 0,
+        Err(BotError::OrderIdExist) => 10,
+        Err(BotError::OrderNotFound) => 12,
+        Err(BotError::InvalidOrderStatus) => 14,
+        Err(BotError::InstrumentNotFound) => 16,
+        Err(BotError::Timeout) => 17,
+        Err(BotError::Interrupted) => 18,
+        Err(BotError::Custom(_)) => 19,
+        Err(BotError::FilterViolation(_)) => 20,
+        Err(BotError::RiskViolation(_)) => 21,
     }
 }
 
@@ -565,6 +768,33 @@ pub extern "C" fn roiveclive_clear_last_trades(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn roiveclive_last_depth_updates(
+    hbt_ptr: *const ROIVectorMarketDepthLiveBot,
+    asset_no: usize,
+    len_ptr: *mut usize,
+) -> *const DepthUpdate {
+    let hbt = unsafe { &*hbt_ptr };
+    let updates = hbt.last_depth_updates(asset_no);
+    unsafe {
+        *len_ptr = updates.len();
+    }
+    updates.as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn roiveclive_clear_last_depth_updates(
+    hbt_ptr: *mut ROIVectorMarketDepthLiveBot,
+    asset_no: usize,
+) {
+    let hbt = unsafe { &mut *hbt_ptr };
+    if asset_no == usize::MAX {
+        hbt.clear_last_depth_updates(None);
+    } else {
+        hbt.clear_last_depth_updates(Some(asset_no));
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn roiveclive_clear_inactive_orders(
     hbt_ptr: *mut ROIVectorMarketDepthLiveBot,
@@ -596,6 +826,17 @@ pub extern "C" fn roiveclive_state_values(
     hbt.state_values(asset_no) as *const _
 }
 
+#[no_mangle]
+pub extern "C" fn roiveclive_portfolio(
+    hbt_ptr: *const ROIVectorMarketDepthLiveBot,
+    portfolio: *mut Portfolio,
+) {
+    let hbt = unsafe { &*hbt_ptr };
+    unsafe {
+        *portfolio = hbt.portfolio();
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn roiveclive_feed_latency(
     hbt_ptr: *const ROIVectorMarketDepthLiveBot,
@@ -637,3 +878,15 @@ pub extern "C" fn roiveclive_order_latency(
         },
     }
 }
+
+#[no_mangle]
+pub extern "C" fn roiveclive_event_stats(
+    hbt_ptr: *mut ROIVectorMarketDepthLiveBot,
+    asset_no: usize,
+    event_stats: *mut EventStats,
+) {
+    let hbt = unsafe { &mut *hbt_ptr };
+    unsafe {
+        *event_stats = hbt.event_stats(asset_no);
+    }
+}